@@ -0,0 +1,251 @@
+use std::collections::VecDeque;
+use std::env;
+use std::fs::{self, File, OpenOptions};
+use std::io::Write;
+use std::path::PathBuf;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+use rmcp::model::{CallToolResult, JsonObject};
+use rmcp::schemars::{self, JsonSchema};
+use rmcp::ErrorData as McpError;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+
+const AUDIT_LOG_ENV: &str = "INDEX_MCP_AUDIT_LOG";
+const SESSION_HISTORY_CAPACITY: usize = 200;
+const DEFAULT_HISTORY_LIMIT: usize = 20;
+const MAX_RECORDED_STRING_CHARS: usize = 200;
+const REDACTED: &str = "[redacted]";
+const SENSITIVE_KEY_FRAGMENTS: &[&str] = &[
+    "secret",
+    "password",
+    "passwd",
+    "apikey",
+    "api_key",
+    "authorization",
+    "credential",
+    "cookie",
+];
+
+#[derive(Debug, Deserialize, JsonSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct SessionHistoryParams {
+    #[serde(default)]
+    pub limit: Option<u32>,
+    #[serde(default)]
+    pub tool: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize, JsonSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct AuditRecord {
+    pub timestamp_ms: i64,
+    pub tool: String,
+    pub arguments: Option<Value>,
+    pub duration_ms: u64,
+    pub is_error: bool,
+    pub result_bytes: usize,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize, JsonSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct SessionHistoryResponse {
+    pub enabled: bool,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub log_path: Option<String>,
+    pub recorded_calls: u64,
+    /// Most recent first.
+    pub entries: Vec<AuditRecord>,
+}
+
+#[derive(Debug, Default)]
+struct AuditState {
+    entries: VecDeque<AuditRecord>,
+    recorded_calls: u64,
+    file: Option<File>,
+}
+
+/// Opt-in record of tool invocations, enabled by pointing
+/// `INDEX_MCP_AUDIT_LOG` at a JSONL file. The current session's calls are
+/// also kept in memory for `session_history`.
+#[derive(Debug, Clone, Default)]
+pub struct AuditLog {
+    path: Option<PathBuf>,
+    state: Arc<Mutex<AuditState>>,
+}
+
+impl AuditLog {
+    pub fn from_env() -> Self {
+        let Some(path) = env::var(AUDIT_LOG_ENV)
+            .ok()
+            .map(|value| value.trim().to_string())
+            .filter(|value| !value.is_empty())
+            .map(PathBuf::from)
+        else {
+            return Self::default();
+        };
+
+        let file = open_log_file(&path)
+            .map_err(|error| {
+                tracing::warn!(
+                    ?error,
+                    path = %path.display(),
+                    "Unable to open audit log; keeping session history in memory only"
+                );
+            })
+            .ok();
+
+        Self {
+            path: Some(path),
+            state: Arc::new(Mutex::new(AuditState {
+                file,
+                ..AuditState::default()
+            })),
+        }
+    }
+
+    pub fn is_enabled(&self) -> bool {
+        self.path.is_some()
+    }
+
+    pub fn record(
+        &self,
+        tool: &str,
+        arguments: Option<&JsonObject>,
+        duration: Duration,
+        result: &Result<CallToolResult, McpError>,
+    ) {
+        if !self.is_enabled() {
+            return;
+        }
+
+        let (is_error, result_bytes, error) = match result {
+            Ok(result) => (
+                result.is_error.unwrap_or(false),
+                serde_json::to_vec(result)
+                    .map(|bytes| bytes.len())
+                    .unwrap_or(0),
+                None,
+            ),
+            Err(error) => (true, 0, Some(error.message.to_string())),
+        };
+        let record = AuditRecord {
+            timestamp_ms: timestamp_ms(),
+            tool: tool.to_string(),
+            arguments: arguments
+                .map(|arguments| redact_arguments(&Value::Object(arguments.clone()))),
+            duration_ms: duration.as_millis() as u64,
+            is_error,
+            result_bytes,
+            error,
+        };
+
+        let Ok(mut state) = self.state.lock() else {
+            return;
+        };
+        if let Some(file) = state.file.as_mut() {
+            if let Ok(line) = serde_json::to_string(&record) {
+                if let Err(error) = writeln!(file, "{line}") {
+                    tracing::warn!(?error, "Failed to append audit record");
+                }
+            }
+        }
+        state.recorded_calls += 1;
+        if state.entries.len() == SESSION_HISTORY_CAPACITY {
+            state.entries.pop_front();
+        }
+        state.entries.push_back(record);
+    }
+
+    pub fn history(&self, params: SessionHistoryParams) -> SessionHistoryResponse {
+        let limit = params
+            .limit
+            .map(|value| value as usize)
+            .unwrap_or(DEFAULT_HISTORY_LIMIT)
+            .clamp(1, SESSION_HISTORY_CAPACITY);
+        let tool = params
+            .tool
+            .map(|value| value.trim().to_string())
+            .filter(|value| !value.is_empty());
+
+        let (entries, recorded_calls) = match self.state.lock() {
+            Ok(state) => (
+                state
+                    .entries
+                    .iter()
+                    .rev()
+                    .filter(|entry| tool.as_deref().is_none_or(|name| entry.tool == name))
+                    .take(limit)
+                    .cloned()
+                    .collect(),
+                state.recorded_calls,
+            ),
+            Err(_) => (Vec::new(), 0),
+        };
+
+        SessionHistoryResponse {
+            enabled: self.is_enabled(),
+            log_path: self
+                .path
+                .as_ref()
+                .map(|path| path.to_string_lossy().to_string()),
+            recorded_calls,
+            entries,
+        }
+    }
+}
+
+fn open_log_file(path: &PathBuf) -> std::io::Result<File> {
+    if let Some(parent) = path
+        .parent()
+        .filter(|parent| !parent.as_os_str().is_empty())
+    {
+        fs::create_dir_all(parent)?;
+    }
+    OpenOptions::new().create(true).append(true).open(path)
+}
+
+/// Replaces credential-like values and shortens long strings (queries, file
+/// contents) so the log stays small and safe to share.
+pub(crate) fn redact_arguments(value: &Value) -> Value {
+    match value {
+        Value::Object(map) => Value::Object(
+            map.iter()
+                .map(|(key, value)| {
+                    let redacted = if is_sensitive_key(key) {
+                        Value::String(REDACTED.to_string())
+                    } else {
+                        redact_arguments(value)
+                    };
+                    (key.clone(), redacted)
+                })
+                .collect(),
+        ),
+        Value::Array(items) => Value::Array(items.iter().map(redact_arguments).collect()),
+        Value::String(text) if text.chars().count() > MAX_RECORDED_STRING_CHARS => {
+            let truncated: String = text.chars().take(MAX_RECORDED_STRING_CHARS).collect();
+            Value::String(format!("{truncated}…"))
+        }
+        other => other.clone(),
+    }
+}
+
+// `budgetTokens` and friends are counts, not credentials, so only a trailing
+// singular "token" counts as sensitive
+fn is_sensitive_key(key: &str) -> bool {
+    let lower = key.to_ascii_lowercase();
+    lower.ends_with("token")
+        || SENSITIVE_KEY_FRAGMENTS
+            .iter()
+            .any(|fragment| lower.contains(fragment))
+}
+
+fn timestamp_ms() -> i64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|duration| duration.as_millis() as i64)
+        .unwrap_or(0)
+}
@@ -0,0 +1,534 @@
+#[path = "../chunk_labels.rs"]
+mod chunk_labels;
+#[path = "../content_store.rs"]
+mod content_store;
+#[path = "../data_dir.rs"]
+mod data_dir;
+#[path = "../db_lock.rs"]
+mod db_lock;
+#[path = "../embedder.rs"]
+mod embedder;
+#[path = "../embedding_cache.rs"]
+mod embedding_cache;
+#[path = "../embedding_store.rs"]
+mod embedding_store;
+#[path = "../generations.rs"]
+mod generations;
+#[path = "../git_timeline.rs"]
+mod git_timeline;
+#[path = "../graph.rs"]
+mod graph;
+#[path = "../hit_counter.rs"]
+mod hit_counter;
+#[path = "../ignore_rules.rs"]
+mod ignore_rules;
+#[path = "../index_status.rs"]
+mod index_status;
+#[path = "../ingest.rs"]
+mod ingest;
+#[path = "../ingest_queue.rs"]
+mod ingest_queue;
+#[path = "../integrity.rs"]
+mod integrity;
+#[path = "../keywords.rs"]
+mod keywords;
+#[path = "../language_pack.rs"]
+mod language_pack;
+#[path = "../limits.rs"]
+mod limits;
+#[path = "../model_registry.rs"]
+mod model_registry;
+#[path = "../normalization.rs"]
+mod normalization;
+#[path = "../path_boosts.rs"]
+mod path_boosts;
+#[path = "../roots.rs"]
+mod roots;
+#[cfg(feature = "tree-sitter")]
+#[path = "../rust_graph.rs"]
+mod rust_graph;
+#[path = "../sandbox.rs"]
+mod sandbox;
+#[path = "../search.rs"]
+mod search;
+#[cfg(feature = "tree-sitter")]
+#[path = "../syntax_chunks.rs"]
+mod syntax_chunks;
+#[path = "../text_format.rs"]
+mod text_format;
+#[path = "../vector_index.rs"]
+mod vector_index;
+#[path = "../workspace.rs"]
+mod workspace;
+
+use clap::{builder::BoolishValueParser, Parser, ValueHint};
+use ingest::{ingest_codebase, warm_up_embedder, IngestParams, IngestResponse};
+use search::{semantic_search, SemanticSearchParams, SummaryMode};
+use serde::Serialize;
+use std::collections::BTreeMap;
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+use std::process;
+use std::time::Instant;
+use walkdir::WalkDir;
+
+const WORDS: &[&str] = &[
+    "account", "buffer", "cache", "channel", "config", "cursor", "digest", "entry", "event",
+    "filter", "graph", "handle", "index", "ledger", "limit", "merge", "node", "offset", "parser",
+    "payload", "queue", "record", "route", "schema", "session", "shard", "signal", "snapshot",
+    "stream", "token", "vector", "window",
+];
+const VERBS: &[&str] = &[
+    "build", "compact", "decode", "encode", "flush", "load", "merge", "parse", "refresh",
+    "resolve", "score", "split", "store", "sync", "validate", "write",
+];
+// read by ingest when it decides whether to build the vector index
+const VECTOR_INDEX_MIN_CHUNKS_ENV: &str = "INDEX_MCP_VECTOR_INDEX_MIN_CHUNKS";
+const DEFAULT_QUERIES: &[&str] = &[
+    "flush the session cache",
+    "parse stream payload",
+    "validate schema record",
+    "merge ledger snapshot",
+    "resolve route token",
+];
+
+#[derive(Parser, Debug)]
+#[command(
+    author,
+    version,
+    about = "Measures ingest throughput and search latency against a real or synthetic corpus",
+    long_about = None
+)]
+struct Cli {
+    /// Existing workspace to index. A synthetic corpus is generated when
+    /// omitted.
+    #[arg(long, env = "INDEX_MCP_BENCH_CORPUS", value_hint = ValueHint::DirPath)]
+    corpus: Option<PathBuf>,
+
+    #[arg(long, env = "INDEX_MCP_BENCH_FILES", default_value_t = 500)]
+    files: usize,
+
+    #[arg(long, env = "INDEX_MCP_BENCH_FUNCTIONS", default_value_t = 12)]
+    functions_per_file: usize,
+
+    #[arg(long, env = "INDEX_MCP_BENCH_SEED", default_value_t = 42)]
+    seed: u64,
+
+    #[arg(long, env = "INDEX_MCP_BENCH_DATABASE")]
+    database: Option<String>,
+
+    #[arg(long = "query")]
+    queries: Vec<String>,
+
+    /// Passes over the query set per search strategy.
+    #[arg(long, env = "INDEX_MCP_BENCH_ITERATIONS", default_value_t = 10)]
+    iterations: usize,
+
+    /// Shortlist size for the keyword-routed strategy.
+    #[arg(long, env = "INDEX_MCP_BENCH_ROUTER_FILES", default_value_t = 200)]
+    router_files: u32,
+
+    /// Chunk count at which ingest builds the vector index. Defaults to 0
+    /// so corpora below the server's 20,000-chunk threshold get one too.
+    #[arg(long, env = VECTOR_INDEX_MIN_CHUNKS_ENV, default_value_t = 0)]
+    vector_index_min_chunks: u64,
+
+    #[arg(
+        long,
+        env = "INDEX_MCP_BENCH_KEEP_CORPUS",
+        default_value_t = false,
+        value_parser = BoolishValueParser::new()
+    )]
+    keep_corpus: bool,
+
+    #[arg(long, env = "INDEX_MCP_BENCH_JSON_REPORT")]
+    json_report: Option<PathBuf>,
+}
+
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+struct IngestMeasurement {
+    files: usize,
+    chunks: usize,
+    bytes: u64,
+    duration_ms: f64,
+    files_per_second: f64,
+    chunks_per_second: f64,
+    megabytes_per_second: f64,
+}
+
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+struct LatencyMeasurement {
+    strategy: &'static str,
+    samples: usize,
+    /// How many samples reported each `searchStrategy` kind.
+    search_strategies: BTreeMap<String, usize>,
+    mean_evaluated_chunks: f64,
+    min_ms: f64,
+    p50_ms: f64,
+    p90_ms: f64,
+    p99_ms: f64,
+    max_ms: f64,
+}
+
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+struct BenchReport {
+    corpus: String,
+    synthetic: bool,
+    ingest: IngestMeasurement,
+    search: Vec<LatencyMeasurement>,
+}
+
+#[tokio::main]
+async fn main() {
+    let cli = Cli::parse();
+    if let Err(error) = run(cli).await {
+        eprintln!("bench failed: {error}");
+        process::exit(1);
+    }
+}
+
+async fn run(cli: Cli) -> Result<(), String> {
+    std::env::set_var(
+        VECTOR_INDEX_MIN_CHUNKS_ENV,
+        cli.vector_index_min_chunks.to_string(),
+    );
+    let synthetic = cli.corpus.is_none();
+    let corpus = match &cli.corpus {
+        Some(path) => path.clone(),
+        None => {
+            let path = std::env::temp_dir().join(format!("index-mcp-bench-{}", process::id()));
+            generate_corpus(&path, cli.files, cli.functions_per_file, cli.seed)
+                .map_err(|error| format!("failed to generate corpus: {error}"))?;
+            path
+        }
+    };
+
+    // load the model up front so it does not count against ingest throughput
+    tokio::task::spawn_blocking(|| warm_up_embedder(None))
+        .await
+        .map_err(|error| error.to_string())?
+        .map_err(|error| format!("embedder warm-up failed: {error}"))?;
+
+    let result = measure(&cli, &corpus, synthetic).await;
+    if synthetic && !cli.keep_corpus {
+        let _ = fs::remove_dir_all(&corpus);
+    }
+    let report = result?;
+
+    print_report(&report);
+    if let Some(path) = &cli.json_report {
+        let payload = serde_json::to_string_pretty(&report).map_err(|error| error.to_string())?;
+        fs::write(path, payload)
+            .map_err(|error| format!("failed to write {}: {error}", path.display()))?;
+    }
+    Ok(())
+}
+
+async fn measure(cli: &Cli, corpus: &Path, synthetic: bool) -> Result<BenchReport, String> {
+    let bytes = corpus_bytes(corpus);
+    let start = Instant::now();
+    let response = run_ingest(corpus, cli.database.clone())
+        .await
+        .map_err(|error| format!("ingest failed: {error}"))?;
+    let seconds = start.elapsed().as_secs_f64().max(f64::EPSILON);
+    let ingest = IngestMeasurement {
+        files: response.ingested_file_count,
+        chunks: response.embedded_chunk_count,
+        bytes,
+        duration_ms: seconds * 1_000.0,
+        files_per_second: response.ingested_file_count as f64 / seconds,
+        chunks_per_second: response.embedded_chunk_count as f64 / seconds,
+        megabytes_per_second: bytes as f64 / 1_048_576.0 / seconds,
+    };
+
+    let queries: Vec<String> = if cli.queries.is_empty() {
+        DEFAULT_QUERIES
+            .iter()
+            .map(|query| query.to_string())
+            .collect()
+    } else {
+        cli.queries.clone()
+    };
+    // a routerFiles value bypasses the vector index, so only the last run
+    // probes it
+    let strategies = [
+        ("brute-force", Some(0)),
+        ("keyword-routed", Some(cli.router_files)),
+        ("vector-index", None),
+    ];
+    let mut search = Vec::new();
+    for (strategy, router_files) in strategies {
+        let mut latencies = Vec::new();
+        let mut evaluated = 0u64;
+        let mut observed: BTreeMap<String, usize> = BTreeMap::new();
+        for _ in 0..cli.iterations.max(1) {
+            for query in &queries {
+                let params = search_params(corpus, cli.database.clone(), query, router_files);
+                let start = Instant::now();
+                let response = semantic_search(params)
+                    .await
+                    .map_err(|error| format!("search failed: {error}"))?;
+                latencies.push(start.elapsed().as_secs_f64() * 1_000.0);
+                evaluated += response.evaluated_chunks;
+                let kind = response
+                    .search_strategy
+                    .and_then(|used| serde_json::to_value(used.kind).ok())
+                    .and_then(|kind| kind.as_str().map(str::to_string))
+                    .unwrap_or_else(|| "none".to_string());
+                *observed.entry(kind).or_default() += 1;
+            }
+        }
+        search.push(summarize_latencies(
+            strategy, latencies, evaluated, observed,
+        ));
+    }
+    hit_counter::flush_pending_hits();
+
+    Ok(BenchReport {
+        corpus: corpus.to_string_lossy().to_string(),
+        synthetic,
+        ingest,
+        search,
+    })
+}
+
+async fn run_ingest(
+    corpus: &Path,
+    database: Option<String>,
+) -> Result<IngestResponse, ingest::IngestError> {
+    ingest_codebase(IngestParams {
+        root: Some(corpus.to_string_lossy().to_string()),
+        include: None,
+        exclude: None,
+        database_name: database,
+        max_file_size_bytes: None,
+        store_file_content: None,
+        store_content_globs: None,
+        skip_content_globs: None,
+        paths: None,
+        auto_evict: Some(false),
+        max_database_size_bytes: None,
+        protected_classifications: None,
+        embedding: None,
+        allow_model_mix: None,
+        compress_content: None,
+        chunk_content_by_reference: None,
+        embedding_encoding: None,
+        include_ignored_paths: None,
+        include_hidden: None,
+        include_skipped: None,
+    })
+    .await
+}
+
+fn search_params(
+    corpus: &Path,
+    database: Option<String>,
+    query: &str,
+    router_files: Option<u32>,
+) -> SemanticSearchParams {
+    SemanticSearchParams {
+        root: Some(corpus.to_string_lossy().to_string()),
+        roots: None,
+        all_roots: None,
+        worktrees: None,
+        explain: None,
+        query: query.to_string(),
+        database_name: database,
+        limit: Some(10),
+        model: None,
+        models: None,
+        language: None,
+        path_prefix: None,
+        path_contains: None,
+        include: None,
+        exclude: None,
+        classification: None,
+        summary_mode: Some(SummaryMode::Brief),
+        max_context_before: Some(0),
+        max_context_after: Some(0),
+        only_paths: None,
+        recency_weight: None,
+        package: None,
+        include_symbols: None,
+        context_source: None,
+        context_scope: None,
+        router_files,
+        group_by: None,
+        generation: None,
+        at_commit: None,
+    }
+}
+
+fn summarize_latencies(
+    strategy: &'static str,
+    mut latencies: Vec<f64>,
+    evaluated: u64,
+    search_strategies: BTreeMap<String, usize>,
+) -> LatencyMeasurement {
+    latencies.sort_by(f64::total_cmp);
+    let samples = latencies.len();
+    let percentile = |fraction: f64| {
+        let index = ((samples as f64 - 1.0) * fraction).round() as usize;
+        latencies.get(index).copied().unwrap_or(0.0)
+    };
+    LatencyMeasurement {
+        strategy,
+        samples,
+        search_strategies,
+        mean_evaluated_chunks: evaluated as f64 / samples.max(1) as f64,
+        min_ms: percentile(0.0),
+        p50_ms: percentile(0.5),
+        p90_ms: percentile(0.9),
+        p99_ms: percentile(0.99),
+        max_ms: percentile(1.0),
+    }
+}
+
+fn print_report(report: &BenchReport) {
+    let ingest = &report.ingest;
+    println!(
+        "corpus: {}{}",
+        report.corpus,
+        if report.synthetic { " (synthetic)" } else { "" }
+    );
+    println!();
+    println!(
+        "ingest: {} files, {} chunks, {:.1} MiB in {:.0} ms",
+        ingest.files,
+        ingest.chunks,
+        ingest.bytes as f64 / 1_048_576.0,
+        ingest.duration_ms
+    );
+    println!(
+        "        {:.1} files/s, {:.1} chunks/s, {:.2} MiB/s",
+        ingest.files_per_second, ingest.chunks_per_second, ingest.megabytes_per_second
+    );
+    println!();
+    println!(
+        "{:<16} {:>8} {:>10} {:>9} {:>9} {:>9} {:>9} {:>9}  searchStrategy",
+        "strategy", "samples", "chunks", "min ms", "p50 ms", "p90 ms", "p99 ms", "max ms"
+    );
+    for row in &report.search {
+        println!(
+            "{:<16} {:>8} {:>10.0} {:>9.2} {:>9.2} {:>9.2} {:>9.2} {:>9.2}  {}",
+            row.strategy,
+            row.samples,
+            row.mean_evaluated_chunks,
+            row.min_ms,
+            row.p50_ms,
+            row.p90_ms,
+            row.p99_ms,
+            row.max_ms,
+            row.search_strategies
+                .iter()
+                .map(|(kind, count)| format!("{kind} x{count}"))
+                .collect::<Vec<_>>()
+                .join(", ")
+        );
+    }
+}
+
+fn corpus_bytes(corpus: &Path) -> u64 {
+    WalkDir::new(corpus)
+        .into_iter()
+        .filter_entry(|entry| entry.file_name() != ".git")
+        .filter_map(Result::ok)
+        .filter(|entry| entry.file_type().is_file())
+        .filter_map(|entry| entry.metadata().ok())
+        .map(|metadata| metadata.len())
+        .sum()
+}
+
+/// Deterministic mix of TypeScript, Rust, and Python modules built from a
+/// small vocabulary, so queries have keyword overlap with a subset of files.
+fn generate_corpus(
+    root: &Path,
+    files: usize,
+    functions_per_file: usize,
+    seed: u64,
+) -> io::Result<()> {
+    let mut rng = XorShift(seed.max(1));
+    for index in 0..files {
+        let directory = root.join(format!("module_{:03}", index / 50));
+        fs::create_dir_all(&directory)?;
+        let topic = rng.pick(WORDS);
+        let (extension, body) = match index % 3 {
+            0 => ("ts", typescript_module(&mut rng, topic, functions_per_file)),
+            1 => ("rs", rust_module(&mut rng, topic, functions_per_file)),
+            _ => ("py", python_module(&mut rng, topic, functions_per_file)),
+        };
+        fs::write(directory.join(format!("{topic}_{index}.{extension}")), body)?;
+    }
+    Ok(())
+}
+
+fn typescript_module(rng: &mut XorShift, topic: &str, functions: usize) -> String {
+    let mut source = format!("// {topic} helpers\n\n");
+    for _ in 0..functions {
+        let (verb, noun) = (rng.pick(VERBS), rng.pick(WORDS));
+        source.push_str(&format!(
+            "export function {verb}{}({topic}: {}, {noun}: number): boolean {{\n  const next = {topic}.{noun} + {noun};\n  if (next > {}) {{\n    return {verb}Fallback({topic}, next);\n  }}\n  return next % 2 === 0;\n}}\n\n",
+            capitalize(noun),
+            capitalize(topic),
+            rng.below(1_000)
+        ));
+    }
+    source
+}
+
+fn rust_module(rng: &mut XorShift, topic: &str, functions: usize) -> String {
+    let mut source = format!("//! {topic} helpers\n\n");
+    for _ in 0..functions {
+        let (verb, noun) = (rng.pick(VERBS), rng.pick(WORDS));
+        source.push_str(&format!(
+            "pub fn {verb}_{noun}({topic}: &mut {}, {noun}: usize) -> bool {{\n    let next = {topic}.{noun} + {noun};\n    if next > {} {{\n        return {verb}_fallback({topic}, next);\n    }}\n    next % 2 == 0\n}}\n\n",
+            capitalize(topic),
+            rng.below(1_000)
+        ));
+    }
+    source
+}
+
+fn python_module(rng: &mut XorShift, topic: &str, functions: usize) -> String {
+    let mut source = format!("\"\"\"{topic} helpers\"\"\"\n\n");
+    for _ in 0..functions {
+        let (verb, noun) = (rng.pick(VERBS), rng.pick(WORDS));
+        source.push_str(&format!(
+            "def {verb}_{noun}({topic}, {noun}):\n    next_value = {topic}.{noun} + {noun}\n    if next_value > {}:\n        return {verb}_fallback({topic}, next_value)\n    return next_value % 2 == 0\n\n\n",
+            rng.below(1_000)
+        ));
+    }
+    source
+}
+
+fn capitalize(word: &str) -> String {
+    let mut chars = word.chars();
+    match chars.next() {
+        Some(first) => first.to_ascii_uppercase().to_string() + chars.as_str(),
+        None => String::new(),
+    }
+}
+
+struct XorShift(u64);
+
+impl XorShift {
+    fn next_u64(&mut self) -> u64 {
+        self.0 ^= self.0 << 13;
+        self.0 ^= self.0 >> 7;
+        self.0 ^= self.0 << 17;
+        self.0
+    }
+
+    fn below(&mut self, bound: u64) -> u64 {
+        self.next_u64() % bound
+    }
+
+    fn pick<'a>(&mut self, items: &[&'a str]) -> &'a str {
+        items[self.below(items.len() as u64) as usize]
+    }
+}
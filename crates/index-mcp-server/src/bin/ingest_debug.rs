@@ -1,15 +1,67 @@
 #[path = "../bundle.rs"]
 mod bundle;
+#[path = "../chunk_labels.rs"]
+mod chunk_labels;
+#[path = "../content_store.rs"]
+mod content_store;
+#[path = "../data_dir.rs"]
+mod data_dir;
+#[path = "../db_lock.rs"]
+mod db_lock;
+#[path = "../embedder.rs"]
+mod embedder;
+#[path = "../embedding_cache.rs"]
+mod embedding_cache;
+#[path = "../embedding_store.rs"]
+mod embedding_store;
+#[path = "../generations.rs"]
+mod generations;
 #[path = "../git_timeline.rs"]
 mod git_timeline;
 #[path = "../graph.rs"]
 mod graph;
+#[path = "../hit_counter.rs"]
+mod hit_counter;
+#[path = "../ignore_rules.rs"]
+mod ignore_rules;
 #[path = "../index_status.rs"]
 mod index_status;
 #[path = "../ingest.rs"]
 mod ingest;
+#[path = "../ingest_queue.rs"]
+mod ingest_queue;
+#[path = "../integrity.rs"]
+mod integrity;
+#[path = "../keywords.rs"]
+mod keywords;
+#[path = "../language_pack.rs"]
+mod language_pack;
+#[path = "../limits.rs"]
+mod limits;
+#[path = "../model_registry.rs"]
+mod model_registry;
+#[path = "../normalization.rs"]
+mod normalization;
+#[path = "../path_boosts.rs"]
+mod path_boosts;
+#[path = "../roots.rs"]
+mod roots;
+#[cfg(feature = "tree-sitter")]
+#[path = "../rust_graph.rs"]
+mod rust_graph;
+#[path = "../sandbox.rs"]
+mod sandbox;
 #[path = "../search.rs"]
 mod search;
+#[cfg(feature = "tree-sitter")]
+#[path = "../syntax_chunks.rs"]
+mod syntax_chunks;
+#[path = "../text_format.rs"]
+mod text_format;
+#[path = "../vector_index.rs"]
+mod vector_index;
+#[path = "../workspace.rs"]
+mod workspace;
 
 use bundle::{context_bundle, ContextBundleError, ContextBundleParams, ContextBundleResponse};
 use clap::{builder::BoolishValueParser, Parser, ValueEnum, ValueHint};
@@ -18,8 +70,14 @@ use git_timeline::{
     RepositoryTimelineEntryLookupResponse, RepositoryTimelineError, RepositoryTimelineParams,
     RepositoryTimelineResponse,
 };
-use index_status::{get_index_status, IndexStatusError, IndexStatusParams, IndexStatusResponse};
-use ingest::{ingest_codebase, warm_up_embedder, IngestError, IngestParams, IngestResponse};
+use index_status::{
+    get_index_status, list_databases, IndexStatusError, IndexStatusParams, IndexStatusResponse,
+    ListDatabasesParams, ListDatabasesResponse,
+};
+use ingest::{
+    estimate_ingest, ingest_codebase, warm_up_embedder, IngestError, IngestEstimateParams,
+    IngestEstimateResponse, IngestParams, IngestResponse,
+};
 use search::{
     semantic_search, summarize_semantic_search, SemanticSearchError, SemanticSearchParams,
     SemanticSearchResponse, SummaryMode,
@@ -114,36 +172,42 @@ struct Cli {
 
 #[derive(Clone, Copy, Debug, Eq, PartialEq, Hash, ValueEnum)]
 enum Section {
+    EstimateIngest,
     Ingest,
     SemanticSearch,
     CodeLookupSearch,
     ContextBundle,
     CodeLookupBundle,
     IndexStatus,
+    ListDatabases,
     RepositoryTimeline,
 }
 
 impl Section {
     fn all() -> Vec<Self> {
         vec![
+            Self::EstimateIngest,
             Self::Ingest,
             Self::SemanticSearch,
             Self::CodeLookupSearch,
             Self::ContextBundle,
             Self::CodeLookupBundle,
             Self::IndexStatus,
+            Self::ListDatabases,
             Self::RepositoryTimeline,
         ]
     }
 
     fn key(&self) -> &'static str {
         match self {
+            Self::EstimateIngest => "estimate_ingest",
             Self::Ingest => "ingest_codebase",
             Self::SemanticSearch => "semantic_search",
             Self::CodeLookupSearch => "code_lookup_search",
             Self::ContextBundle => "context_bundle",
             Self::CodeLookupBundle => "code_lookup_bundle",
             Self::IndexStatus => "index_status",
+            Self::ListDatabases => "list_databases",
             Self::RepositoryTimeline => "repository_timeline",
         }
     }
@@ -340,6 +404,8 @@ async fn execute(config: RunConfig) -> i32 {
         }
     }
 
+    hit_counter::flush_pending_hits();
+
     if let Some(path) = &config.json_report {
         if let Err(error) = write_json_report(path, &summaries) {
             eprintln!(
@@ -360,18 +426,23 @@ async fn execute_section(
     state: &mut RunState,
 ) -> SectionExecution {
     match section {
+        Section::EstimateIngest => estimate_ingest_section(config).await,
         Section::Ingest => ingest_section(config, state).await,
         Section::SemanticSearch => semantic_search_section(config, state).await,
         Section::CodeLookupSearch => code_lookup_search_section(state),
         Section::ContextBundle => context_bundle_section(config, state).await,
         Section::CodeLookupBundle => code_lookup_bundle_section(state),
         Section::IndexStatus => index_status_section(config).await,
+        Section::ListDatabases => list_databases_section(config).await,
         Section::RepositoryTimeline => repository_timeline_section(config, state).await,
     }
 }
 
 fn section_header(section: Section, config: &RunConfig) -> String {
     match section {
+        Section::EstimateIngest => {
+            format!("estimate_ingest (root={})", config.root.display())
+        }
         Section::Ingest => format!("ingest_codebase (root={})", config.root.display()),
         Section::SemanticSearch => {
             format!("semantic_search query='{}'", config.query)
@@ -383,6 +454,7 @@ fn section_header(section: Section, config: &RunConfig) -> String {
         },
         Section::CodeLookupBundle => "code_lookup (bundle mode approximation)".to_string(),
         Section::IndexStatus => "index_status".to_string(),
+        Section::ListDatabases => format!("list_databases (root={})", config.root.display()),
         Section::RepositoryTimeline => "repository_timeline".to_string(),
     }
 }
@@ -443,6 +515,23 @@ fn write_json_report(path: &Path, summaries: &[SectionSummary]) -> io::Result<()
     fs::write(path, payload)
 }
 
+async fn estimate_ingest_section(config: &RunConfig) -> SectionExecution {
+    match run_estimate_ingest(config).await {
+        Ok(response) => {
+            let message = summarize_estimate_ingest(&response);
+            if config.verbose {
+                dump_json("estimate_ingest response", &response);
+            }
+            SectionExecution::Success {
+                message: Some(message),
+            }
+        }
+        Err(error) => SectionExecution::Failed {
+            error: error.to_string(),
+        },
+    }
+}
+
 async fn ingest_section(config: &RunConfig, state: &mut RunState) -> SectionExecution {
     match run_ingest(config).await {
         Ok(response) => {
@@ -555,6 +644,23 @@ async fn index_status_section(config: &RunConfig) -> SectionExecution {
     }
 }
 
+async fn list_databases_section(config: &RunConfig) -> SectionExecution {
+    match run_list_databases(config).await {
+        Ok(response) => {
+            let message = summarize_list_databases(&response);
+            if config.verbose {
+                dump_json("list_databases response", &response);
+            }
+            SectionExecution::Success {
+                message: Some(message),
+            }
+        }
+        Err(error) => SectionExecution::Failed {
+            error: error.to_string(),
+        },
+    }
+}
+
 async fn repository_timeline_section(config: &RunConfig, state: &mut RunState) -> SectionExecution {
     match run_repository_timeline(config).await {
         Ok(response) => {
@@ -594,6 +700,25 @@ async fn repository_timeline_section(config: &RunConfig, state: &mut RunState) -
     }
 }
 
+async fn run_estimate_ingest(config: &RunConfig) -> Result<IngestEstimateResponse, IngestError> {
+    let params = IngestEstimateParams {
+        root: Some(config.root.to_string_lossy().to_string()),
+        include: None,
+        exclude: None,
+        max_file_size_bytes: None,
+        store_file_content: None,
+        store_content_globs: None,
+        skip_content_globs: None,
+        paths: None,
+        embedding: None,
+        sample_chunks: None,
+        include_hidden: None,
+        embedding_encoding: None,
+    };
+
+    estimate_ingest(params).await
+}
+
 async fn run_ingest(config: &RunConfig) -> Result<IngestResponse, IngestError> {
     let params = IngestParams {
         root: Some(config.root.to_string_lossy().to_string()),
@@ -602,10 +727,20 @@ async fn run_ingest(config: &RunConfig) -> Result<IngestResponse, IngestError> {
         database_name: config.database.clone(),
         max_file_size_bytes: None,
         store_file_content: None,
+        store_content_globs: None,
+        skip_content_globs: None,
         paths: None,
         auto_evict: Some(false),
         max_database_size_bytes: None,
+        protected_classifications: None,
         embedding: None,
+        allow_model_mix: None,
+        compress_content: None,
+        chunk_content_by_reference: None,
+        embedding_encoding: None,
+        include_ignored_paths: None,
+        include_hidden: None,
+        include_skipped: None,
     };
 
     ingest_codebase(params).await
@@ -616,17 +751,34 @@ async fn run_semantic_search(
 ) -> Result<SemanticSearchResponse, SemanticSearchError> {
     let params = SemanticSearchParams {
         root: Some(config.root.to_string_lossy().to_string()),
+        roots: None,
+        all_roots: None,
+        worktrees: None,
+        explain: None,
         query: config.query.clone(),
         database_name: config.database.clone(),
         limit: Some(config.limit),
         model: None,
+        models: None,
         language: None,
         path_prefix: None,
         path_contains: None,
+        include: None,
+        exclude: None,
         classification: None,
         summary_mode: Some(SummaryMode::Brief),
         max_context_before: Some(1),
         max_context_after: Some(1),
+        only_paths: None,
+        recency_weight: None,
+        package: None,
+        include_symbols: None,
+        context_source: None,
+        context_scope: None,
+        router_files: None,
+        group_by: None,
+        generation: None,
+        at_commit: None,
     };
 
     semantic_search(params).await
@@ -644,8 +796,13 @@ async fn run_context_bundle(
         max_snippets: Some(config.max_snippets),
         max_neighbors: Some(config.max_neighbors),
         budget_tokens: Some(config.budget_tokens),
+        max_quick_links: None,
         ranges: None,
         focus_line: None,
+        content_override: None,
+        compare_to_commit: None,
+        generation: None,
+        at_commit: None,
     };
 
     context_bundle(params).await
@@ -656,11 +813,21 @@ async fn run_index_status(config: &RunConfig) -> Result<IndexStatusResponse, Ind
         root: Some(config.root.to_string_lossy().to_string()),
         database_name: config.database.clone(),
         history_limit: Some(5),
+        max_database_size_bytes: None,
     };
 
     get_index_status(params).await
 }
 
+async fn run_list_databases(config: &RunConfig) -> Result<ListDatabasesResponse, IndexStatusError> {
+    let params = ListDatabasesParams {
+        root: Some(config.root.to_string_lossy().to_string()),
+        max_depth: None,
+    };
+
+    list_databases(params).await
+}
+
 async fn run_repository_timeline(
     config: &RunConfig,
 ) -> Result<RepositoryTimelineResponse, RepositoryTimelineError> {
@@ -675,6 +842,8 @@ async fn run_repository_timeline(
         include_diffs: Some(config.include_diffs),
         paths: None,
         diff_pattern: None,
+        max_diff_bytes: None,
+        max_diff_age_days: None,
     };
 
     repository_timeline(params).await
@@ -750,6 +919,18 @@ fn summarize_ingest(response: &IngestResponse) -> String {
     summary
 }
 
+fn summarize_estimate_ingest(response: &IngestEstimateResponse) -> String {
+    format!(
+        "estimate_ingest: {} file(s), ~{} chunk(s), db~{} bytes, embed~{} ms ({:.1} chunk(s)/s, measured={})",
+        response.file_count,
+        response.estimated_chunk_count,
+        response.estimated_database_size_bytes,
+        response.estimated_embedding_ms,
+        response.chunks_per_second,
+        response.throughput_measured
+    )
+}
+
 fn summarize_code_lookup_search(response: &SemanticSearchResponse) -> String {
     let mut lines = vec![format!(
         "code_lookup (search): mirrored {} semantic_search result(s)",
@@ -852,6 +1033,19 @@ fn summarize_index_status(response: &IndexStatusResponse) -> String {
     )
 }
 
+fn summarize_list_databases(response: &ListDatabasesResponse) -> String {
+    let names = response
+        .databases
+        .iter()
+        .map(|database| database.name.as_str())
+        .collect::<Vec<_>>();
+    format!(
+        "list_databases: {} database(s) [{}]",
+        names.len(),
+        names.join(", ")
+    )
+}
+
 fn summarize_repository_timeline(response: &RepositoryTimelineResponse) -> String {
     let mut lines = vec![format!(
         "repository_timeline: {} commit(s), merge_commits={}, total_insertions={}, total_deletions={}",
@@ -2,29 +2,81 @@ use std::collections::{HashMap, HashSet};
 use std::convert::TryFrom;
 use std::fs;
 use std::path::{Path, PathBuf};
+use std::process::Command;
 use std::sync::Mutex;
 
 use once_cell::sync::Lazy;
 use regex::Regex;
 use rmcp::schemars::{self, JsonSchema};
-use rusqlite::{params, Connection, OpenFlags};
+use rusqlite::{params, Connection};
 use serde::{Deserialize, Serialize};
-use serde_json::Value;
+use serde_json::{json, Value};
+use sha2::{Digest, Sha256};
 use thiserror::Error;
 use tokio::task::JoinError;
 
+use crate::content_store::{
+    content_encoding_column, decode_file_content, slice_chunk, CHUNK_REFERENCE_ENCODING,
+};
+use crate::data_dir::{database_path, open_read_only};
+use crate::generations::{resolve_generation, GenerationError};
+use crate::git_timeline::has_timeline_filter_columns;
+use crate::graph::{extract_graph, symbol_node_id, GraphNode};
 use crate::index_status::DEFAULT_DB_FILENAME;
+use crate::limits::{clamp_parameter, describe_adjustments, ParameterAdjustment};
+use crate::roots::resolve_workspace_root;
+use crate::sandbox::{ensure_root_allowed, resolve_within_root, SandboxError};
+use crate::text_format::{
+    decode_text, detect_line_ending, text_format_columns, DecodedText, LineEnding, TextEncoding,
+};
 
 const DEFAULT_SNIPPET_LIMIT: usize = 3;
 const MAX_SNIPPET_LIMIT: usize = 10;
 const DEFAULT_NEIGHBOR_LIMIT: usize = 12;
 const MAX_NEIGHBOR_LIMIT: usize = 50;
 const DEFAULT_TOKEN_BUDGET: usize = 3_000;
+const MIN_TOKEN_BUDGET: usize = 100;
+const MAX_TOKEN_BUDGET: usize = 100_000;
+const DEFAULT_QUICK_LINK_LIMIT: usize = 16;
+const MAX_QUICK_LINK_LIMIT: usize = 64;
 const FOCUS_CONTEXT_RADIUS: u32 = 25;
 const SUMMARY_CHAR_LIMIT: usize = 220;
 const EXCERPT_TOKEN_LIMIT: usize = 320;
 const MIN_SUMMARY_TOKEN_FLOOR: usize = 1;
 const BUNDLE_CACHE_CAPACITY: usize = 32;
+const MAX_CO_CHANGE_LINKS: usize = 3;
+const MIN_CO_CHANGE_COMMITS: u32 = 2;
+// commits touching more files than this are usually sweeping refactors and
+// say little about which files belong together
+const MAX_CO_CHANGE_COMMIT_FILES: usize = 40;
+// window size for snippets cut from a contentOverride buffer
+const BUFFER_SNIPPET_LINES: u32 = 40;
+const MAX_INTERFACE_ENTRIES: usize = 40;
+
+// module specifiers in import statements, one capture group per form
+static IMPORT_SPECIFIER: Lazy<Regex> = Lazy::new(|| {
+    Regex::new(r#"^\s*(?:import\s[^'"]*?from\s+['"]([^'"]+)['"]|import\s+['"]([^'"]+)['"]|export\s[^'"]*?from\s+['"]([^'"]+)['"]|(?:pub(?:\([^)]*\))?\s+)?use\s+([\w:]*\w)|from\s+([\w.]+)\s+import\s|import\s+([\w.]+)\s*(?:$|,))|require\(\s*['"]([^'"]+)['"]\s*\)"#)
+        .expect("valid import pattern")
+});
+// Go import paths, alone or inside an `import ( ... )` block
+static GO_IMPORT_PATH: Lazy<Regex> = Lazy::new(|| {
+    Regex::new(r#"^\s*(?:import\s+)?(?:[\w.]+\s+)?"([^"]+)"\s*$"#).expect("valid Go import pattern")
+});
+// top-level declarations visible outside the file; group 1 is the kind and
+// group 2 the name
+static SCRIPT_EXPORT: Lazy<Regex> = Lazy::new(|| {
+    Regex::new(r"^export\s+(?:default\s+)?(?:declare\s+)?(?:abstract\s+)?(?:async\s+)?(function\*?|class|interface|type|enum|const|let|var)\s+([A-Za-z_$][\w$]*)")
+        .expect("valid export pattern")
+});
+static RUST_PUBLIC_ITEM: Lazy<Regex> = Lazy::new(|| {
+    Regex::new(r"^pub\s+(?:(?:async|const|unsafe)\s+)*(fn|struct|enum|trait|type|const|static|mod|union)\s+([A-Za-z_]\w*)")
+        .expect("valid export pattern")
+});
+static GO_EXPORTED: Lazy<Regex> = Lazy::new(|| {
+    Regex::new(r"^(func|type)\s+(?:\([^)]*\)\s*)?([A-Z]\w*)").expect("valid export pattern")
+});
+static PYTHON_PUBLIC: Lazy<Regex> =
+    Lazy::new(|| Regex::new(r"^(def|class)\s+([A-Za-z]\w*)").expect("valid export pattern"));
 
 static CONTEXT_BUNDLE_CACHE: Lazy<Mutex<BundleCache>> =
     Lazy::new(|| Mutex::new(BundleCache::new(BUNDLE_CACHE_CAPACITY)));
@@ -40,6 +92,7 @@ struct BundleCacheKey {
     max_snippets: usize,
     budget_tokens: usize,
     max_neighbors: usize,
+    max_quick_links: usize,
 }
 
 #[derive(Debug)]
@@ -111,10 +164,28 @@ pub struct ContextBundleParams {
     pub max_neighbors: Option<u32>,
     #[serde(default)]
     pub budget_tokens: Option<u32>,
+    /// Cap on `quickLinks` after ranking (default 16, max 64).
+    #[serde(default)]
+    pub max_quick_links: Option<u32>,
     #[serde(default)]
     pub ranges: Option<Vec<LineRange>>,
     #[serde(default)]
     pub focus_line: Option<u32>,
+    /// Current editor buffer text for `file`. The bundle is built from this
+    /// text instead of the indexed copy, is never cached, and the file does
+    /// not need to be indexed.
+    #[serde(default)]
+    pub content_override: Option<String>,
+    /// Commit (sha or ref) to compare against; each snippet reports which of
+    /// its lines differ in the working tree.
+    #[serde(default)]
+    pub compare_to_commit: Option<String>,
+    /// Build from the retained snapshot of this ingest generation.
+    #[serde(default)]
+    pub generation: Option<i64>,
+    /// Build from the newest retained snapshot indexed at this commit.
+    #[serde(default)]
+    pub at_commit: Option<String>,
 }
 
 #[derive(Debug, Deserialize, JsonSchema, Clone)]
@@ -140,11 +211,64 @@ pub struct ContextBundleResponse {
     pub definitions: Vec<BundleDefinition>,
     pub focus_definition: Option<BundleDefinition>,
     pub related: Vec<BundleEdgeNeighbor>,
+    /// What the file imports and exposes, ahead of snippets so it survives
+    /// tight budgets.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub interface: Option<BundleInterface>,
     pub snippets: Vec<BundleSnippet>,
     pub latest_ingestion: Option<BundleIngestionSummary>,
     pub warnings: Vec<String>,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub range_corrections: Vec<BundleRangeCorrection>,
     pub quick_links: Vec<ContextBundleQuickLink>,
     pub usage: BundleUsageStats,
+    /// Built from `contentOverride`; snippets and definitions reflect the
+    /// unsaved buffer rather than the index.
+    #[serde(skip_serializing_if = "std::ops::Not::not")]
+    pub ephemeral: bool,
+    /// Parameters clamped to their allowed range.
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub adjustments: Vec<ParameterAdjustment>,
+    /// Full sha that `compareToCommit` resolved to.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub compared_to_commit: Option<String>,
+    /// The file was read from the working tree and no longer matches the
+    /// indexed hash (`file.hash`); `diskHash` is what was read.
+    #[serde(skip_serializing_if = "std::ops::Not::not")]
+    pub content_drift: bool,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub disk_hash: Option<String>,
+    /// Chunk snippets moved to where their text now sits in the working tree.
+    #[serde(skip_serializing_if = "is_zero")]
+    pub remapped_snippets: usize,
+    /// Snapshot generation the bundle was built from instead of the live
+    /// index.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub generation: Option<i64>,
+}
+
+#[derive(Debug, Serialize, JsonSchema, Clone, Default)]
+#[serde(rename_all = "camelCase")]
+pub struct BundleInterface {
+    /// Module specifiers in source order, deduplicated.
+    pub imports: Vec<String>,
+    /// Public graph definitions, then exported declarations the graph does
+    /// not cover.
+    pub exports: Vec<InterfaceSymbol>,
+    /// Entries dropped past the per-list cap.
+    #[serde(skip_serializing_if = "is_zero")]
+    pub omitted: usize,
+}
+
+#[derive(Debug, Serialize, JsonSchema, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct InterfaceSymbol {
+    pub name: String,
+    pub kind: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub signature: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub line: Option<u32>,
 }
 
 #[derive(Debug, Serialize, JsonSchema, Clone)]
@@ -157,6 +281,13 @@ pub struct BundleFileMetadata {
     pub last_indexed_at: i64,
     #[serde(skip_serializing_if = "Option::is_none")]
     pub brief: Option<String>,
+    /// How the file's bytes were decoded. Snippet byte ranges count bytes of
+    /// the decoded UTF-8 text, so they only match the file on disk when this
+    /// is `utf-8`.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub text_encoding: Option<TextEncoding>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub line_ending: Option<LineEnding>,
     #[serde(skip_serializing)]
     pub content: Option<String>,
 }
@@ -165,6 +296,7 @@ pub struct BundleFileMetadata {
 #[serde(rename_all = "camelCase")]
 pub struct BundleUsageStats {
     pub definitions_tokens: usize,
+    pub interface_tokens: usize,
     pub snippet_tokens: usize,
     pub used_tokens: usize,
     pub budget_tokens: usize,
@@ -188,6 +320,18 @@ pub struct BundleDefinition {
     pub visibility: Option<String>,
     pub docstring: Option<String>,
     pub todo_count: Option<u32>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub metrics: Option<DefinitionMetrics>,
+}
+
+/// Size and complexity recorded at graph extraction; absent for databases
+/// ingested before metrics were collected.
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct DefinitionMetrics {
+    pub line_count: u32,
+    pub parameter_count: u32,
+    pub branch_count: u32,
 }
 
 #[derive(Debug, Serialize, JsonSchema, Clone)]
@@ -223,6 +367,55 @@ pub struct BundleSnippet {
     pub line_end: Option<i64>,
     #[serde(skip_serializing_if = "Option::is_none")]
     pub served_count: Option<i64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub provenance: Option<SnippetProvenance>,
+    /// Set when the bundle was requested with `compareToCommit`.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub changes: Option<SnippetChanges>,
+}
+
+#[derive(Debug, Serialize, JsonSchema, Clone, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct SnippetChanges {
+    pub changed: bool,
+    /// Snippet lines added or modified since the commit; a deletion marks
+    /// the line it happened in front of.
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub changed_lines: Vec<u32>,
+}
+
+/// Identifies which ingest produced a snippet so clients can compare the
+/// indexed hash against the working tree before trusting the content.
+#[derive(Debug, Serialize, JsonSchema, Clone, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct SnippetProvenance {
+    pub file_hash: String,
+    pub indexed_at: i64,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub ingestion_id: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub generation: Option<i64>,
+    pub from_working_tree: bool,
+}
+
+#[derive(Debug, Serialize, JsonSchema, Clone, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct BundleRangeCorrection {
+    pub requested_start_line: u32,
+    pub requested_end_line: u32,
+    pub applied_start_line: Option<u32>,
+    pub applied_end_line: Option<u32>,
+    pub reason: RangeCorrectionReason,
+}
+
+#[derive(Debug, Serialize, JsonSchema, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub enum RangeCorrectionReason {
+    Swapped,
+    ClampedStart,
+    ClampedEnd,
+    BeyondEndOfFile,
+    Merged,
 }
 
 #[derive(Debug, Serialize, JsonSchema, Clone)]
@@ -238,6 +431,7 @@ pub struct BundleIngestionSummary {
 #[serde(rename_all = "camelCase")]
 pub struct ContextBundleQuickLink {
     pub r#type: QuickLinkType,
+    pub reason: QuickLinkReason,
     pub label: String,
     pub path: Option<String>,
     #[serde(skip_serializing_if = "Option::is_none")]
@@ -246,6 +440,9 @@ pub struct ContextBundleQuickLink {
     pub symbol_id: Option<String>,
     #[serde(skip_serializing_if = "Option::is_none")]
     pub symbol_kind: Option<String>,
+    /// Number of cached timeline commits that touched both files.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub co_change_commits: Option<u32>,
 }
 
 #[derive(Debug, Serialize, JsonSchema, Clone)]
@@ -253,6 +450,26 @@ pub struct ContextBundleQuickLink {
 pub enum QuickLinkType {
     File,
     RelatedSymbol,
+    CoChange,
+}
+
+/// Why a quick link was suggested; links are ordered by this, most useful
+/// first.
+#[derive(Debug, Serialize, JsonSchema, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+#[serde(rename_all = "camelCase")]
+pub enum QuickLinkReason {
+    /// The symbol the request targeted.
+    FocusSymbol,
+    /// Calls or references the target file's symbols.
+    Caller,
+    /// Edited alongside the target in cached timeline commits.
+    CoChange,
+    /// Called or referenced by the target file's symbols.
+    Callee,
+    /// Another definition in the target file.
+    Definition,
+    /// The target file itself.
+    File,
 }
 
 #[allow(dead_code)]
@@ -263,7 +480,7 @@ pub enum SnippetSource {
     Content,
 }
 
-#[derive(Debug, Serialize, JsonSchema, Clone, Copy, PartialEq)]
+#[derive(Debug, Serialize, Deserialize, JsonSchema, Clone, Copy, PartialEq, Eq, Hash)]
 #[serde(rename_all = "camelCase")]
 pub enum NeighborDirection {
     Incoming,
@@ -286,6 +503,10 @@ pub enum ContextBundleError {
         #[source]
         source: std::io::Error,
     },
+    #[error(transparent)]
+    Sandbox(#[from] SandboxError),
+    #[error(transparent)]
+    Generation(#[from] GenerationError),
     #[error("blocking task panicked: {0}")]
     Join(#[from] JoinError),
 }
@@ -305,34 +526,93 @@ fn build_bundle(params: ContextBundleParams) -> Result<ContextBundleResponse, Co
         max_snippets,
         max_neighbors,
         budget_tokens,
+        max_quick_links,
         ranges,
         focus_line,
+        content_override,
+        compare_to_commit,
+        generation,
+        at_commit,
     } = params;
+    let ephemeral = content_override.is_some();
 
     let root_path = resolve_root(root.unwrap_or_else(|| "./".to_string()))?;
-    let db_path = root_path.join(database_name.unwrap_or_else(|| DEFAULT_DB_FILENAME.to_string()));
+    ensure_root_allowed(&root_path)?;
+    let db_path = database_path(
+        &root_path,
+        &database_name.unwrap_or_else(|| DEFAULT_DB_FILENAME.to_string()),
+    );
+    let snapshot = resolve_generation(&root_path, &db_path, generation, at_commit.as_deref())?;
+    let db_path = snapshot
+        .as_ref()
+        .map_or(db_path, |snapshot| snapshot.path.clone());
     let db_path_string = db_path.to_string_lossy().to_string();
 
-    let conn = Connection::open_with_flags(&db_path, OpenFlags::SQLITE_OPEN_READ_ONLY)
-        .map_err(ContextBundleError::Sqlite)?;
+    let conn = open_read_only(&db_path).map_err(ContextBundleError::Sqlite)?;
 
     let target_file = normalize_file(&file);
 
-    let max_snippets = max_snippets
-        .map(|value| value.min(MAX_SNIPPET_LIMIT as u32) as usize)
-        .unwrap_or(DEFAULT_SNIPPET_LIMIT);
-    let max_neighbors = max_neighbors
-        .map(|value| value.min(MAX_NEIGHBOR_LIMIT as u32) as usize)
-        .unwrap_or(DEFAULT_NEIGHBOR_LIMIT);
-    let budget_tokens = budget_tokens
-        .map(|value| value as usize)
-        .unwrap_or(DEFAULT_TOKEN_BUDGET);
-
-    let mut file_record =
-        load_file_metadata(&conn, &target_file)?.ok_or_else(|| ContextBundleError::Io {
+    let mut adjustments = Vec::new();
+    let max_snippets = clamp_parameter(
+        "maxSnippets",
+        max_snippets,
+        DEFAULT_SNIPPET_LIMIT as u32,
+        0,
+        MAX_SNIPPET_LIMIT as u32,
+        &mut adjustments,
+    ) as usize;
+    let max_neighbors = clamp_parameter(
+        "maxNeighbors",
+        max_neighbors,
+        DEFAULT_NEIGHBOR_LIMIT as u32,
+        0,
+        MAX_NEIGHBOR_LIMIT as u32,
+        &mut adjustments,
+    ) as usize;
+    let budget_tokens = clamp_parameter(
+        "budgetTokens",
+        budget_tokens,
+        DEFAULT_TOKEN_BUDGET as u32,
+        MIN_TOKEN_BUDGET as u32,
+        MAX_TOKEN_BUDGET as u32,
+        &mut adjustments,
+    ) as usize;
+    let max_quick_links = clamp_parameter(
+        "maxQuickLinks",
+        max_quick_links,
+        DEFAULT_QUICK_LINK_LIMIT as u32,
+        0,
+        MAX_QUICK_LINK_LIMIT as u32,
+        &mut adjustments,
+    ) as usize;
+
+    let mut file_record = match (load_file_metadata(&conn, &target_file)?, &content_override) {
+        (Some(record), _) => record,
+        // scratch buffers need not be indexed; size and hash are filled below
+        (None, Some(_)) => BundleFileMetadata {
             path: target_file.clone(),
-            source: std::io::Error::new(std::io::ErrorKind::NotFound, "file not indexed"),
-        })?;
+            size: 0,
+            modified: 0,
+            hash: String::new(),
+            last_indexed_at: 0,
+            brief: None,
+            text_encoding: None,
+            line_ending: None,
+            content: None,
+        },
+        (None, None) => {
+            return Err(ContextBundleError::Io {
+                path: target_file.clone(),
+                source: std::io::Error::new(std::io::ErrorKind::NotFound, "file not indexed"),
+            })
+        }
+    };
+    if let Some(buffer) = &content_override {
+        file_record.size = buffer.len() as i64;
+        file_record.hash = hex::encode(Sha256::digest(buffer.as_bytes()));
+        file_record.text_encoding = Some(TextEncoding::Utf8);
+        file_record.line_ending = detect_line_ending(buffer);
+    }
 
     let symbol_fingerprint = symbol
         .as_ref()
@@ -359,20 +639,44 @@ fn build_bundle(params: ContextBundleParams) -> Result<ContextBundleResponse, Co
         max_snippets,
         budget_tokens,
         max_neighbors,
+        max_quick_links,
     };
 
-    if let Ok(mut cache) = CONTEXT_BUNDLE_CACHE.lock() {
-        if let Some(cached) = cache.get(&cache_key) {
-            return Ok(cached);
+    if !ephemeral {
+        if let Ok(mut cache) = CONTEXT_BUNDLE_CACHE.lock() {
+            if let Some(cached) = cache.get(&cache_key) {
+                let cached = with_adjustments(cached, adjustments);
+                return Ok(with_commit_comparison(
+                    cached,
+                    &root_path,
+                    compare_to_commit.as_deref(),
+                ));
+            }
         }
     }
 
-    let file_content = file_record
-        .content
-        .clone()
-        .or_else(|| read_file_from_disk(&root_path, &target_file).ok());
-
-    let definitions = load_definitions(&conn, &target_file, file_content.as_deref());
+    let disk_copy = if ephemeral || file_record.content.is_some() {
+        None
+    } else {
+        read_file_from_disk(&root_path, &target_file).ok()
+    };
+    let content_from_disk = disk_copy.is_some();
+    let disk_hash = disk_copy
+        .as_ref()
+        .map(|(_, hash)| hash.clone())
+        .filter(|hash| hash != &file_record.hash);
+    if let Some((decoded, _)) = &disk_copy {
+        file_record.text_encoding = Some(decoded.encoding);
+        file_record.line_ending = detect_line_ending(&decoded.text);
+    }
+    let file_content = content_override
+        .or_else(|| file_record.content.clone())
+        .or_else(|| disk_copy.map(|(decoded, _)| decoded.text));
+
+    let definitions = match file_content.as_deref().filter(|_| ephemeral) {
+        Some(buffer) => extract_buffer_definitions(&target_file, buffer),
+        None => load_definitions(&conn, &target_file, file_content.as_deref()),
+    };
     let focus_definition =
         symbol.and_then(|selector| find_focus_definition(&definitions, selector));
 
@@ -386,32 +690,109 @@ fn build_bundle(params: ContextBundleParams) -> Result<ContextBundleResponse, Co
     let content_ref = file_content.as_deref();
     let line_offsets = content_ref.map(compute_line_offsets);
 
-    let (snippets, mut snippet_warnings) = collect_snippets(
-        &conn,
-        &target_file,
+    let line_count = line_offsets
+        .as_deref()
+        .map(|offsets| offsets.len().saturating_sub(1) as u32);
+    let (requested_ranges, range_corrections) = validate_ranges(&requested_ranges, line_count);
+
+    let load_base_snippets = |limit: usize| match (ephemeral, content_ref, line_offsets.as_deref())
+    {
+        (true, Some(content), Some(offsets)) => {
+            buffer_snippets(content, offsets, limit, focus_line)
+        }
+        (true, _, _) => Vec::new(),
+        (false, _, _) => load_snippets(&conn, &target_file, limit, content_ref),
+    };
+    let (mut snippets, mut snippet_warnings) = collect_snippets(
+        load_base_snippets,
         max_snippets,
         &requested_ranges,
         focus_line,
         content_ref,
         line_offsets.as_deref(),
     );
-    let (trimmed_snippets, usage_stats, mut trimming_warnings) =
-        trim_snippets_to_budget(snippets, &definitions, budget_tokens);
+    // chunk ranges describe the indexed text; move them onto the disk copy
+    let mut remapped_snippets = 0usize;
+    let mut unmapped_snippets = 0usize;
+    if let (Some(_), Some(content)) = (&disk_hash, content_ref) {
+        for snippet in snippets
+            .iter_mut()
+            .filter(|snippet| matches!(snippet.source, SnippetSource::Chunk))
+        {
+            if remap_snippet(snippet, content) {
+                remapped_snippets += 1;
+            } else {
+                unmapped_snippets += 1;
+            }
+        }
+    }
+    let (ingestion_id, generation) = load_file_generation(&conn, file_record.last_indexed_at);
+    // buffer snippets were never ingested, so there is no provenance to report
+    for snippet in snippets.iter_mut().filter(|_| !ephemeral) {
+        snippet.provenance = Some(SnippetProvenance {
+            file_hash: file_record.hash.clone(),
+            indexed_at: file_record.last_indexed_at,
+            ingestion_id: ingestion_id.clone(),
+            generation,
+            // chunk rows always come from the index; content snippets are cut from
+            // the working tree when the index did not store file content
+            from_working_tree: matches!(snippet.source, SnippetSource::Content)
+                && content_from_disk,
+        });
+    }
+    let interface = match (content_ref, line_offsets.as_deref()) {
+        (Some(content), Some(offsets)) => {
+            build_interface(&target_file, content, offsets, &definitions)
+        }
+        _ => None,
+    };
+    let (trimmed_snippets, usage_stats, mut trimming_warnings) = trim_snippets_to_budget(
+        snippets,
+        &definitions,
+        interface.as_ref().map_or(0, interface_token_cost),
+        budget_tokens,
+    );
 
     let ingestion = load_latest_ingestion(&conn)?;
     let mut warnings = gather_warnings(&definitions, content_ref);
+    if ephemeral {
+        warnings.push(
+            "Built from contentOverride; the bundle reflects the unsaved buffer and was not cached."
+                .to_string(),
+        );
+    }
+    warnings.extend(range_corrections.iter().map(describe_range_correction));
+    if disk_hash.is_some() {
+        let mut warning =
+            "Working tree copy differs from the indexed version; chunk snippets may be stale."
+                .to_string();
+        if unmapped_snippets > 0 {
+            warning.push_str(&format!(
+                " {unmapped_snippets} chunk snippet(s) could not be located on disk and keep their indexed line ranges."
+            ));
+        }
+        warning.push_str(" Re-run ingest_codebase.");
+        warnings.push(warning);
+    }
     warnings.append(&mut snippet_warnings);
     warnings.append(&mut trimming_warnings);
-    if symbol_fingerprint.is_none() && requested_ranges.is_empty() && focus_line.is_none() {
+    if symbol_fingerprint.is_none()
+        && requested_ranges.is_empty()
+        && range_corrections.is_empty()
+        && focus_line.is_none()
+    {
         warnings.push(
             "No symbol, ranges, or focusLine provided; prefer targeting definitions to minimize context.".to_string(),
         );
     }
+    let co_changes = load_co_change_partners(&conn, &root_path, &target_file);
     let quick_links = build_quick_links(
         &target_file,
         &definitions,
         &related,
         focus_definition.as_ref(),
+        &co_changes,
+        max_quick_links,
     );
 
     let brief = file_content.as_deref().and_then(build_file_brief);
@@ -425,46 +806,176 @@ fn build_bundle(params: ContextBundleParams) -> Result<ContextBundleResponse, Co
             hash: file_record.hash,
             last_indexed_at: file_record.last_indexed_at,
             brief,
+            text_encoding: file_record.text_encoding,
+            // databases from before line endings were recorded
+            line_ending: file_record
+                .line_ending
+                .or_else(|| file_content.as_deref().and_then(detect_line_ending)),
             content: file_record.content.take(),
         },
         definitions,
         focus_definition,
         related,
+        interface,
         snippets: trimmed_snippets,
         latest_ingestion: ingestion,
         warnings,
+        range_corrections,
         quick_links,
         usage: usage_stats,
+        ephemeral,
+        adjustments: Vec::new(),
+        compared_to_commit: None,
+        content_drift: disk_hash.is_some(),
+        disk_hash,
+        remapped_snippets,
+        generation: snapshot.map(|snapshot| snapshot.generation),
     };
 
-    if let Ok(mut cache) = CONTEXT_BUNDLE_CACHE.lock() {
-        cache.put(cache_key, response.clone());
+    if !ephemeral {
+        if let Ok(mut cache) = CONTEXT_BUNDLE_CACHE.lock() {
+            cache.put(cache_key, response.clone());
+        }
     }
 
-    Ok(response)
+    let response = with_adjustments(response, adjustments);
+    Ok(with_commit_comparison(
+        response,
+        &root_path,
+        compare_to_commit.as_deref(),
+    ))
 }
 
-fn resolve_root(root: String) -> Result<PathBuf, ContextBundleError> {
-    let candidate = PathBuf::from(root);
-    if candidate.is_absolute() {
-        return Ok(candidate);
+// applied outside the cache because the working tree moves independently of
+// the indexed hash
+fn with_commit_comparison(
+    mut response: ContextBundleResponse,
+    root: &Path,
+    commit: Option<&str>,
+) -> ContextBundleResponse {
+    let Some(commit) = commit.map(str::trim).filter(|value| !value.is_empty()) else {
+        return response;
+    };
+    match changed_lines_since(root, &response.file.path, commit) {
+        Ok((sha, changed)) => {
+            for snippet in &mut response.snippets {
+                let (Some(start), Some(end)) = (snippet.line_start, snippet.line_end) else {
+                    continue;
+                };
+                let changed_lines: Vec<u32> = (start.max(1) as u32..=end as u32)
+                    .filter(|line| changed.contains(line))
+                    .collect();
+                snippet.changes = Some(SnippetChanges {
+                    changed: !changed_lines.is_empty(),
+                    changed_lines,
+                });
+            }
+            if response.ephemeral {
+                response.warnings.push(
+                    "compareToCommit diffs the working tree, not contentOverride; change markers may be offset."
+                        .to_string(),
+                );
+            }
+            response.compared_to_commit = Some(sha);
+        }
+        Err(message) => response.warnings.push(format!(
+            "compareToCommit '{commit}' could not be compared: {message}"
+        )),
     }
-    let cwd = std::env::current_dir().map_err(|source| ContextBundleError::InvalidRoot {
-        path: "./".to_string(),
-        source,
-    })?;
-    Ok(cwd.join(candidate))
+    response
+}
+
+/// Resolves `commit` and returns the working-tree line numbers of `path` that
+/// differ from it.
+fn changed_lines_since(
+    root: &Path,
+    path: &str,
+    commit: &str,
+) -> Result<(String, HashSet<u32>), String> {
+    static HUNK_HEADER: Lazy<Regex> = Lazy::new(|| {
+        Regex::new(r"^@@ -\d+(?:,\d+)? \+(\d+)(?:,(\d+))? @@").expect("valid hunk header")
+    });
+
+    if commit.starts_with('-') {
+        return Err("not a commit".to_string());
+    }
+    let git = |args: &[&str]| -> Result<String, String> {
+        let output = Command::new("git")
+            .args(args)
+            .current_dir(root)
+            .output()
+            .map_err(|error| error.to_string())?;
+        if !output.status.success() {
+            return Err(String::from_utf8_lossy(&output.stderr).trim().to_string());
+        }
+        Ok(String::from_utf8_lossy(&output.stdout).into_owned())
+    };
+    let sha = git(&[
+        "rev-parse",
+        "--verify",
+        "--quiet",
+        &format!("{commit}^{{commit}}"),
+    ])
+    .map_err(|_| "not a commit in this repository".to_string())?
+    .trim()
+    .to_string();
+    let diff = git(&[
+        "diff",
+        "-U0",
+        "--no-color",
+        "--no-ext-diff",
+        &sha,
+        "--",
+        path,
+    ])?;
+
+    let mut changed = HashSet::new();
+    for captures in diff.lines().filter_map(|line| HUNK_HEADER.captures(line)) {
+        let start: u32 = captures[1].parse().unwrap_or(0);
+        let count: u32 = captures
+            .get(2)
+            .and_then(|value| value.as_str().parse().ok())
+            .unwrap_or(1);
+        if count == 0 {
+            // pure deletion after line `start`
+            changed.insert(start + 1);
+        } else {
+            changed.extend(start..start + count);
+        }
+    }
+    Ok((sha, changed))
+}
+
+// attached after caching, since requests clamped to the same key may have
+// asked for different values
+fn with_adjustments(
+    mut response: ContextBundleResponse,
+    adjustments: Vec<ParameterAdjustment>,
+) -> ContextBundleResponse {
+    if let Some(warning) = describe_adjustments(&adjustments) {
+        response.warnings.push(warning);
+    }
+    response.adjustments = adjustments;
+    response
+}
+
+fn resolve_root(root: String) -> Result<PathBuf, ContextBundleError> {
+    resolve_workspace_root(&root)
+        .map_err(|source| ContextBundleError::InvalidRoot { path: root, source })
 }
 
 fn load_file_metadata(
     conn: &Connection,
     path: &str,
 ) -> Result<Option<BundleFileMetadata>, ContextBundleError> {
-    let mut stmt = conn.prepare(
-        "SELECT path, size, modified, hash, last_indexed_at, content FROM files WHERE path = ?1",
-    )?;
+    let mut stmt = conn.prepare(&format!(
+        "SELECT path, size, modified, hash, last_indexed_at, content, {}, {} FROM files WHERE path = ?1",
+        content_encoding_column(conn, "files"),
+        text_format_columns(conn)
+    ))?;
 
     let record = stmt.query_row(params![path], |row| {
+        let encoding: Option<String> = row.get(6)?;
         Ok(BundleFileMetadata {
             path: row.get(0)?,
             size: row.get(1)?,
@@ -472,7 +983,15 @@ fn load_file_metadata(
             hash: row.get(3)?,
             last_indexed_at: row.get(4)?,
             brief: None,
-            content: row.get(5)?,
+            text_encoding: row
+                .get::<_, Option<String>>(7)?
+                .as_deref()
+                .and_then(TextEncoding::parse),
+            line_ending: row
+                .get::<_, Option<String>>(8)?
+                .as_deref()
+                .and_then(LineEnding::parse),
+            content: decode_file_content(row.get(5)?, encoding.as_deref()),
         })
     });
 
@@ -483,8 +1002,40 @@ fn load_file_metadata(
     }
 }
 
-fn read_file_from_disk(root: &Path, relative: &str) -> Result<String, std::io::Error> {
-    fs::read_to_string(root.join(relative))
+/// The working tree copy decoded the way ingest decodes it, with the hash of
+/// its raw bytes for comparison against the indexed hash.
+fn read_file_from_disk(
+    root: &Path,
+    relative: &str,
+) -> Result<(DecodedText, String), std::io::Error> {
+    let path = resolve_within_root(root, relative).map_err(std::io::Error::other)?;
+    let bytes = fs::read(path)?;
+    let hash = hex::encode(Sha256::digest(&bytes));
+    let decoded = decode_text(&bytes).ok_or_else(|| {
+        std::io::Error::new(std::io::ErrorKind::InvalidData, "binary file content")
+    })?;
+    Ok((decoded, hash))
+}
+
+/// Points a chunk snippet at the one place its text occurs in the working
+/// tree copy. Like ingest, offsets and lines are relative to the trimmed
+/// file text. Ambiguous or missing text leaves the snippet untouched.
+fn remap_snippet(snippet: &mut BundleSnippet, disk_content: &str) -> bool {
+    let needle = snippet.content.trim();
+    if needle.is_empty() {
+        return false;
+    }
+    let text = disk_content.trim();
+    let mut found = text.match_indices(needle).map(|(start, _)| start);
+    let (Some(start), None) = (found.next(), found.next()) else {
+        return false;
+    };
+    let line_start = text[..start].matches('\n').count() as i64 + 1;
+    snippet.byte_start = Some(start as i64);
+    snippet.byte_end = Some((start + needle.len()) as i64);
+    snippet.line_start = Some(line_start);
+    snippet.line_end = Some(line_start + needle.matches('\n').count() as i64);
+    true
 }
 
 fn normalize_file(file: &str) -> String {
@@ -520,34 +1071,151 @@ fn load_definitions(conn: &Connection, path: &str, content: Option<&str>) -> Vec
             let metadata_value = metadata_raw
                 .as_deref()
                 .and_then(|payload| serde_json::from_str::<Value>(payload).ok());
-
-            let (visibility, docstring, todo_count) = match content {
-                Some(text) => (
-                    determine_visibility(text, range_start, &kind, metadata_value.as_ref()),
-                    extract_docstring(text, range_start),
-                    count_todos(text, range_start, range_end),
-                ),
-                None => (None, None, None),
-            };
-
-            definitions.push(BundleDefinition {
-                id,
-                name,
-                kind,
-                signature,
-                range_start,
-                range_end,
-                metadata: metadata_value,
-                visibility,
-                docstring,
-                todo_count,
-            });
+            let metrics = metadata_value
+                .as_ref()
+                .and_then(|metadata| metadata.get("metrics"))
+                .and_then(|metrics| serde_json::from_value(metrics.clone()).ok());
+
+            definitions.push(annotate_definition(
+                BundleDefinition {
+                    id,
+                    name,
+                    kind,
+                    signature,
+                    range_start,
+                    range_end,
+                    metadata: metadata_value,
+                    visibility: None,
+                    docstring: None,
+                    todo_count: None,
+                    metrics,
+                },
+                content,
+            ));
         }
     }
 
+    if definitions
+        .iter()
+        .any(|definition| definition.docstring.is_none())
+    {
+        attach_doc_chunks(conn, path, content, &mut definitions);
+    }
     definitions
 }
 
+/// Falls back to doc comment chunks recorded at ingest for definitions whose
+/// source has no JS-style doc block in front of it.
+fn attach_doc_chunks(
+    conn: &Connection,
+    path: &str,
+    content: Option<&str>,
+    definitions: &mut [BundleDefinition],
+) {
+    let Ok(mut stmt) = conn.prepare(&format!(
+        "SELECT c.content, c.{}, c.byte_start, c.byte_end \
+         FROM chunk_doc_symbols d JOIN file_chunks c ON c.id = d.chunk_id \
+         WHERE d.path = ?1 AND d.symbol = ?2 \
+         ORDER BY c.chunk_index DESC \
+         LIMIT 1",
+        content_encoding_column(conn, "file_chunks")
+    )) else {
+        return;
+    };
+    for definition in definitions
+        .iter_mut()
+        .filter(|definition| definition.docstring.is_none())
+    {
+        let raw = stmt
+            .query_row(params![path, &definition.name], |row| {
+                let stored: String = row.get(0)?;
+                let encoding: Option<String> = row.get(1)?;
+                if encoding.as_deref() == Some(CHUNK_REFERENCE_ENCODING) {
+                    return Ok(content.and_then(|text| slice_chunk(text, row.get(2)?, row.get(3)?)));
+                }
+                Ok(Some(stored))
+            })
+            .ok()
+            .flatten();
+        definition.docstring = raw
+            .map(|comment| strip_comment_markers(&comment))
+            .filter(|cleaned| !cleaned.is_empty());
+    }
+}
+
+fn strip_comment_markers(comment: &str) -> String {
+    comment
+        .lines()
+        .map(|line| {
+            line.trim()
+                .trim_start_matches("/**")
+                .trim_end_matches("*/")
+                .trim_start_matches(['/', '!', '*'])
+                .trim_matches(|ch| ch == '"' || ch == '\'')
+                .trim()
+        })
+        .filter(|line| !line.is_empty())
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+/// Parses an unsaved buffer with the same extractor ingest uses, so
+/// definitions line up with what the editor shows.
+fn extract_buffer_definitions(path: &str, content: &str) -> Vec<BundleDefinition> {
+    let Some(extraction) = extract_graph(path, content) else {
+        return Vec::new();
+    };
+    let mut nodes: Vec<GraphNode> = extraction
+        .nodes
+        .into_iter()
+        .filter(|node| node.path.as_deref() == Some(path))
+        .collect();
+    nodes.sort_by_key(|node| node.range_start);
+    nodes
+        .into_iter()
+        .map(|node| {
+            let metrics = node
+                .metadata
+                .as_ref()
+                .and_then(|metadata| metadata.get("metrics"))
+                .and_then(|metrics| serde_json::from_value(metrics.clone()).ok());
+            annotate_definition(
+                BundleDefinition {
+                    id: node.id,
+                    name: node.name,
+                    kind: node.kind,
+                    signature: node.signature,
+                    range_start: node.range_start,
+                    range_end: node.range_end,
+                    metadata: node.metadata,
+                    visibility: None,
+                    docstring: None,
+                    todo_count: None,
+                    metrics,
+                },
+                Some(content),
+            )
+        })
+        .collect()
+}
+
+fn annotate_definition(
+    mut definition: BundleDefinition,
+    content: Option<&str>,
+) -> BundleDefinition {
+    if let Some(text) = content {
+        definition.visibility = determine_visibility(
+            text,
+            definition.range_start,
+            &definition.kind,
+            definition.metadata.as_ref(),
+        );
+        definition.docstring = extract_docstring(text, definition.range_start);
+        definition.todo_count = count_todos(text, definition.range_start, definition.range_end);
+    }
+    definition
+}
+
 fn determine_visibility(
     content: &str,
     range_start: Option<i64>,
@@ -573,7 +1241,7 @@ fn determine_visibility(
     if line.contains("protected") {
         return Some("protected".to_string());
     }
-    if line.contains("public") || line.contains("export") {
+    if line.contains("public") || line.contains("export") || line.starts_with("pub ") {
         return Some("public".to_string());
     }
     if kind == "method" {
@@ -646,76 +1314,133 @@ fn load_related_neighbors(
     limit: usize,
     _focus: Option<&BundleDefinition>,
 ) -> Vec<BundleEdgeNeighbor> {
-    if definitions.is_empty() {
-        return Vec::new();
-    }
-
     let mut neighbors = Vec::new();
-    let mut stmt = match conn.prepare(
-        "SELECT id, type, source_id, target_id, metadata FROM code_graph_edges WHERE source_id = ?1 OR target_id = ?1",
-    ) {
-        Ok(stmt) => stmt,
-        Err(_) => return neighbors,
-    };
-
     for definition in definitions {
         if neighbors.len() >= limit {
             break;
         }
+        let name = (definition.kind != "file").then_some(definition.name.as_str());
+        let Ok(edges) = load_node_edges(conn, &definition.id, name) else {
+            return neighbors;
+        };
+        for edge in edges {
+            if let Some(node) = load_neighbor_node(conn, &edge.other_id) {
+                neighbors.push(BundleEdgeNeighbor {
+                    id: edge.id,
+                    r#type: edge.edge_type,
+                    direction: edge.direction,
+                    metadata: edge.metadata,
+                    neighbor: resolve_symbol_neighbor(conn, node, None),
+                });
+            }
 
-        let rows = stmt
-            .query_map(params![&definition.id], |row| {
-                Ok((
-                    row.get::<_, String>(0)?,
-                    row.get::<_, String>(1)?,
-                    row.get::<_, String>(2)?,
-                    row.get::<_, String>(3)?,
-                    row.get::<_, Option<String>>(4)?,
-                ))
-            })
-            .ok();
+            if neighbors.len() >= limit {
+                break;
+            }
+        }
+    }
 
-        if let Some(rows) = rows {
-            for row in rows.flatten() {
-                let (edge_id, edge_type, source_id, target_id, metadata_raw) = row;
-                let direction = if source_id == definition.id {
-                    NeighborDirection::Outgoing
-                } else {
-                    NeighborDirection::Incoming
-                };
+    neighbors
+}
 
-                let neighbor_id = if direction == NeighborDirection::Outgoing {
-                    &target_id
-                } else {
-                    &source_id
-                };
+/// One edge seen from a node, pointing at the node on its other end.
+pub(crate) struct NodeEdge {
+    pub id: String,
+    pub edge_type: String,
+    pub direction: NeighborDirection,
+    pub other_id: String,
+    pub metadata: Option<Value>,
+}
 
-                if let Some(node) = load_neighbor_node(conn, neighbor_id) {
-                    let metadata = metadata_raw
-                        .as_deref()
-                        .and_then(|payload| serde_json::from_str::<Value>(payload).ok());
-                    neighbors.push(BundleEdgeNeighbor {
-                        id: edge_id,
-                        r#type: edge_type,
-                        direction,
-                        metadata,
-                        neighbor: node,
-                    });
-                }
+/// Edges into and out of a node. Calls from other files point at the
+/// global symbol node for the callee's name rather than at the definition,
+/// so passing the definition's `name` picks those callers up too; they are
+/// marked `resolvedByName` because any same-named callee matches.
+pub(crate) fn load_node_edges(
+    conn: &Connection,
+    node_id: &str,
+    name: Option<&str>,
+) -> rusqlite::Result<Vec<NodeEdge>> {
+    let symbol_id = name.map(symbol_node_id);
+    let mut stmt = conn.prepare_cached(
+        "SELECT id, type, source_id, target_id, metadata FROM code_graph_edges
+         WHERE source_id = ?1 OR target_id = ?1 OR target_id = ?2",
+    )?;
+    let rows = stmt.query_map(params![node_id, symbol_id], |row| {
+        Ok((
+            row.get::<_, String>(0)?,
+            row.get::<_, String>(1)?,
+            row.get::<_, String>(2)?,
+            row.get::<_, String>(3)?,
+            row.get::<_, Option<String>>(4)?,
+        ))
+    })?;
 
-                if neighbors.len() >= limit {
-                    break;
+    let mut edges = Vec::new();
+    for row in rows {
+        let (id, edge_type, source_id, target_id, metadata_raw) = row?;
+        let mut metadata = metadata_raw
+            .as_deref()
+            .and_then(|payload| serde_json::from_str::<Value>(payload).ok());
+        let (direction, other_id) = if source_id == node_id {
+            (NeighborDirection::Outgoing, target_id)
+        } else {
+            if target_id != node_id {
+                match metadata.as_mut().and_then(Value::as_object_mut) {
+                    Some(object) => {
+                        object.insert("resolvedByName".to_string(), Value::Bool(true));
+                    }
+                    None => metadata = Some(json!({ "resolvedByName": true })),
                 }
             }
-        }
+            (NeighborDirection::Incoming, source_id)
+        };
+        edges.push(NodeEdge {
+            id,
+            edge_type,
+            direction,
+            other_id,
+            metadata,
+        });
     }
+    Ok(edges)
+}
 
-    neighbors
+/// Swaps a global symbol node for the definition it names when that is
+/// unambiguous: the only function or method with the name, or the one in
+/// `prefer_path`. Anything else comes back unchanged.
+pub(crate) fn resolve_symbol_neighbor(
+    conn: &Connection,
+    node: NeighborNode,
+    prefer_path: Option<&str>,
+) -> NeighborNode {
+    if node.kind != "symbol" {
+        return node;
+    }
+    let Ok(mut stmt) = conn.prepare_cached(
+        "SELECT id FROM code_graph_nodes
+         WHERE name = ?1 AND kind IN ('function', 'method', 'constructor')
+         ORDER BY path = ?2 DESC
+         LIMIT 2",
+    ) else {
+        return node;
+    };
+    let candidates: Vec<String> = stmt
+        .query_map(params![&node.name, prefer_path], |row| row.get(0))
+        .map(|rows| rows.flatten().collect())
+        .unwrap_or_default();
+    let chosen = match candidates.as_slice() {
+        [only] => load_neighbor_node(conn, only),
+        [first, _] => load_neighbor_node(conn, first)
+            .filter(|candidate| prefer_path.is_some() && candidate.path.as_deref() == prefer_path),
+        _ => None,
+    };
+    chosen.unwrap_or(node)
 }
 
-fn load_neighbor_node(conn: &Connection, node_id: &str) -> Option<NeighborNode> {
+pub(crate) fn load_neighbor_node(conn: &Connection, node_id: &str) -> Option<NeighborNode> {
     let mut stmt = conn
-        .prepare(
+        .prepare_cached(
             "SELECT id, path, kind, name, signature, metadata FROM code_graph_nodes WHERE id = ?1",
         )
         .ok()?;
@@ -735,28 +1460,46 @@ fn load_neighbor_node(conn: &Connection, node_id: &str) -> Option<NeighborNode>
     .ok()
 }
 
-fn load_snippets(conn: &Connection, path: &str, max_snippets: usize) -> Vec<BundleSnippet> {
-    let mut stmt = match conn.prepare(
-        "SELECT chunk_index, content, byte_start, byte_end, line_start, line_end, hits \
+fn load_snippets(
+    conn: &Connection,
+    path: &str,
+    max_snippets: usize,
+    file_content: Option<&str>,
+) -> Vec<BundleSnippet> {
+    let mut stmt = match conn.prepare(&format!(
+        "SELECT chunk_index, content, byte_start, byte_end, line_start, line_end, hits, {} \
          FROM file_chunks \
-         WHERE path = ?1 \
+         WHERE path = ?1 AND chunk_index >= 0 \
          ORDER BY hits ASC, chunk_index ASC \
          LIMIT ?2",
-    ) {
+        content_encoding_column(conn, "file_chunks")
+    )) {
         Ok(stmt) => stmt,
         Err(_) => return Vec::new(),
     };
 
     stmt.query_map(params![path, max_snippets as i64], |row| {
+        let byte_start: Option<i64> = row.get(2)?;
+        let byte_end: Option<i64> = row.get(3)?;
+        let mut content: String = row.get(1)?;
+        if row.get::<_, Option<String>>(7)?.as_deref() == Some(CHUNK_REFERENCE_ENCODING) {
+            if let Some(sliced) =
+                file_content.and_then(|text| slice_chunk(text, byte_start, byte_end))
+            {
+                content = sliced;
+            }
+        }
         Ok(BundleSnippet {
             source: SnippetSource::Chunk,
             chunk_index: Some(row.get(0)?),
-            content: row.get(1)?,
-            byte_start: row.get(2)?,
-            byte_end: row.get(3)?,
+            content,
+            byte_start,
+            byte_end,
             line_start: row.get(4)?,
             line_end: row.get(5)?,
             served_count: Some(row.get::<_, i64>(6)?),
+            provenance: None,
+            changes: None,
         })
     })
     .map(|rows| rows.flatten().collect())
@@ -764,8 +1507,7 @@ fn load_snippets(conn: &Connection, path: &str, max_snippets: usize) -> Vec<Bund
 }
 
 fn collect_snippets(
-    conn: &Connection,
-    path: &str,
+    load_base_snippets: impl Fn(usize) -> Vec<BundleSnippet>,
     max_snippets: usize,
     ranges: &[LineRange],
     focus_line: Option<u32>,
@@ -838,7 +1580,7 @@ fn collect_snippets(
     let fetch_limit = std::cmp::max(max_snippets, 1)
         .saturating_mul(3)
         .min(MAX_SNIPPET_LIMIT);
-    for snippet in load_snippets(conn, path, fetch_limit) {
+    for snippet in load_base_snippets(fetch_limit) {
         let mut score = 30.0 + snippet_semantic_weight(&snippet.content);
         if let Some(line) = focus_line {
             score += proximity_bonus(&snippet, line);
@@ -848,7 +1590,7 @@ fn collect_snippets(
     }
 
     if candidates.is_empty() {
-        let fallback = load_snippets(conn, path, max_snippets.max(1));
+        let fallback = load_base_snippets(max_snippets.max(1));
         if fallback.is_empty() {
             warnings.push("No snippets available for the requested file.".to_string());
         } else if had_range_request || focus_line.is_some() {
@@ -1019,9 +1761,118 @@ fn build_range_snippet(
         line_start: Some(start as i64),
         line_end: Some(end as i64),
         served_count: None,
+        provenance: None,
+        changes: None,
     })
 }
 
+/// Repairs requested line ranges before snippets are assembled: inverted
+/// ranges are swapped, line numbers are clamped to the file, ranges starting
+/// past the end of the file are dropped, and overlapping ranges are merged.
+/// Every adjustment is reported so callers can see what was actually served.
+fn validate_ranges(
+    ranges: &[LineRange],
+    line_count: Option<u32>,
+) -> (Vec<LineRange>, Vec<BundleRangeCorrection>) {
+    let mut corrections = Vec::new();
+    let mut valid: Vec<(LineRange, (u32, u32))> = Vec::new();
+
+    for range in ranges {
+        let requested = (range.start_line, range.end_line);
+        let mut correct = |applied: Option<(u32, u32)>, reason| {
+            corrections.push(BundleRangeCorrection {
+                requested_start_line: requested.0,
+                requested_end_line: requested.1,
+                applied_start_line: applied.map(|(start, _)| start),
+                applied_end_line: applied.map(|(_, end)| end),
+                reason,
+            });
+        };
+
+        let (mut start, mut end) = requested;
+        if start > end {
+            std::mem::swap(&mut start, &mut end);
+            correct(Some((start, end)), RangeCorrectionReason::Swapped);
+        }
+        if start == 0 {
+            start = 1;
+            end = end.max(1);
+            correct(Some((start, end)), RangeCorrectionReason::ClampedStart);
+        }
+        if let Some(line_count) = line_count {
+            if start > line_count {
+                correct(None, RangeCorrectionReason::BeyondEndOfFile);
+                continue;
+            }
+            if end > line_count {
+                end = line_count;
+                correct(Some((start, end)), RangeCorrectionReason::ClampedEnd);
+            }
+        }
+
+        valid.push((
+            LineRange {
+                start_line: start,
+                end_line: end,
+            },
+            requested,
+        ));
+    }
+
+    valid.sort_by(|(a, _), (b, _)| {
+        a.start_line
+            .cmp(&b.start_line)
+            .then(a.end_line.cmp(&b.end_line))
+    });
+
+    let mut merged: Vec<LineRange> = Vec::new();
+    for (range, requested) in valid {
+        match merged.last_mut() {
+            Some(previous) if range.start_line <= previous.end_line => {
+                previous.end_line = previous.end_line.max(range.end_line);
+                corrections.push(BundleRangeCorrection {
+                    requested_start_line: requested.0,
+                    requested_end_line: requested.1,
+                    applied_start_line: Some(previous.start_line),
+                    applied_end_line: Some(previous.end_line),
+                    reason: RangeCorrectionReason::Merged,
+                });
+            }
+            _ => merged.push(range),
+        }
+    }
+
+    (merged, corrections)
+}
+
+fn describe_range_correction(correction: &BundleRangeCorrection) -> String {
+    let requested = format!(
+        "{}-{}",
+        correction.requested_start_line, correction.requested_end_line
+    );
+    let applied = match (correction.applied_start_line, correction.applied_end_line) {
+        (Some(start), Some(end)) => format!("{start}-{end}"),
+        _ => String::new(),
+    };
+    match correction.reason {
+        RangeCorrectionReason::Swapped => {
+            format!("Range {requested} was inverted; using {applied}.")
+        }
+        RangeCorrectionReason::ClampedStart => {
+            format!("Range {requested} starts before line 1; using {applied}.")
+        }
+        RangeCorrectionReason::ClampedEnd => {
+            format!("Range {requested} extends past the end of the file; clamped to {applied}.")
+        }
+        RangeCorrectionReason::BeyondEndOfFile => {
+            format!("Range {requested} starts past the end of the file and was dropped.")
+        }
+        RangeCorrectionReason::Merged => {
+            format!("Range {requested} overlaps another requested range; merged into {applied}.")
+        }
+    }
+}
+
 fn build_focus_snippet(content: &str, offsets: &[usize], focus_line: u32) -> Option<BundleSnippet> {
     if offsets.len() < 2 {
         return None;
@@ -1042,6 +1893,35 @@ fn build_focus_snippet(content: &str, offsets: &[usize], focus_line: u32) -> Opt
     build_range_snippet(content, offsets, start_line, end_line)
 }
 
+/// Fixed line windows over a buffer, nearest to `focus_line` first, standing
+/// in for stored chunks when the bundle is built from a contentOverride.
+fn buffer_snippets(
+    content: &str,
+    offsets: &[usize],
+    limit: usize,
+    focus_line: Option<u32>,
+) -> Vec<BundleSnippet> {
+    let line_count = offsets.len().saturating_sub(1) as u32;
+    let mut windows: Vec<(u32, u32)> = (1..=line_count)
+        .step_by(BUFFER_SNIPPET_LINES as usize)
+        .map(|start| (start, (start + BUFFER_SNIPPET_LINES - 1).min(line_count)))
+        .collect();
+    if let Some(line) = focus_line {
+        windows.sort_by_key(|(start, end)| {
+            if line < *start {
+                start - line
+            } else {
+                line.saturating_sub(*end)
+            }
+        });
+    }
+    windows
+        .into_iter()
+        .take(limit)
+        .filter_map(|(start, end)| build_range_snippet(content, offsets, start, end))
+        .collect()
+}
+
 fn snippet_covers_line(snippet: &BundleSnippet, line: u32) -> bool {
     match (snippet.line_start, snippet.line_end) {
         (Some(start), Some(end)) => {
@@ -1071,6 +1951,7 @@ fn compute_line_offsets(content: &str) -> Vec<usize> {
 fn trim_snippets_to_budget(
     snippets: Vec<BundleSnippet>,
     definitions: &[BundleDefinition],
+    interface_cost: usize,
     budget_tokens: usize,
 ) -> (Vec<BundleSnippet>, BundleUsageStats, Vec<String>) {
     #[derive(Copy, Clone, Eq, PartialEq)]
@@ -1089,6 +1970,8 @@ fn trim_snippets_to_budget(
         line_start: Option<i64>,
         line_end: Option<i64>,
         served_count: Option<i64>,
+        provenance: Option<SnippetProvenance>,
+        changes: Option<SnippetChanges>,
         summary_content: String,
         summary_tokens: usize,
         excerpt_content: Option<String>,
@@ -1116,6 +1999,8 @@ fn trim_snippets_to_budget(
                 line_start: snippet.line_start,
                 line_end: snippet.line_end,
                 served_count: snippet.served_count,
+                provenance: snippet.provenance,
+                changes: snippet.changes,
                 summary_content,
                 summary_tokens,
                 excerpt_content,
@@ -1154,6 +2039,8 @@ fn trim_snippets_to_budget(
                 line_start,
                 line_end,
                 served_count,
+                provenance,
+                changes,
                 summary_content,
                 excerpt_content,
                 full_content,
@@ -1177,15 +2064,18 @@ fn trim_snippets_to_budget(
                 line_start,
                 line_end,
                 served_count,
+                provenance,
+                changes,
             })
         }
     }
 
     let definitions_cost = definition_token_cost(definitions);
-    let mut used_tokens = definitions_cost;
+    let mut used_tokens = definitions_cost + interface_cost;
     let mut warnings = Vec::new();
     let mut usage = BundleUsageStats {
         definitions_tokens: definitions_cost,
+        interface_tokens: interface_cost,
         budget_tokens,
         ..BundleUsageStats::default()
     };
@@ -1282,13 +2172,19 @@ fn trim_snippets_to_budget(
     }
 
     if budget_tokens > 0 {
-        let snippet_tokens_used = used_tokens.saturating_sub(definitions_cost);
+        let snippet_tokens_used = used_tokens.saturating_sub(definitions_cost + interface_cost);
         usage.snippet_tokens = snippet_tokens_used;
         usage.used_tokens = used_tokens;
         usage.remaining_tokens = budget_tokens.saturating_sub(used_tokens);
+        let interface_part = if interface_cost > 0 {
+            format!(" + interface {interface_cost}")
+        } else {
+            String::new()
+        };
         warnings.push(format!(
-            "Token usage: definitions {} + snippets {} = {} of {} ({} unused).",
+            "Token usage: definitions {}{} + snippets {} = {} of {} ({} unused).",
             definitions_cost,
+            interface_part,
             snippet_tokens_used,
             used_tokens,
             budget_tokens,
@@ -1315,6 +2211,125 @@ fn definition_token_cost(definitions: &[BundleDefinition]) -> usize {
     total
 }
 
+/// Imports and the public surface of a file, or `None` when it has neither.
+fn build_interface(
+    path: &str,
+    content: &str,
+    line_offsets: &[usize],
+    definitions: &[BundleDefinition],
+) -> Option<BundleInterface> {
+    let mut interface = BundleInterface::default();
+    let mut seen_imports = HashSet::new();
+    let mut in_go_import_block = false;
+    for line in content.lines() {
+        let trimmed = line.trim();
+        let specifier = if in_go_import_block {
+            if trimmed.starts_with(')') {
+                in_go_import_block = false;
+            }
+            GO_IMPORT_PATH
+                .captures(line)
+                .and_then(|captures| captures.get(1))
+                .map(|matched| matched.as_str().to_string())
+        } else if trimmed == "import (" {
+            in_go_import_block = true;
+            None
+        } else {
+            IMPORT_SPECIFIER.captures(line).and_then(|captures| {
+                captures
+                    .iter()
+                    .skip(1)
+                    .flatten()
+                    .next()
+                    .map(|matched| matched.as_str().to_string())
+            })
+        };
+        if let Some(specifier) = specifier {
+            if seen_imports.insert(specifier.clone()) {
+                interface.imports.push(specifier);
+            }
+        }
+    }
+
+    let line_of = |offset: usize| line_offsets.partition_point(|start| *start <= offset) as u32;
+    let mut exported_names = HashSet::new();
+    for definition in definitions.iter().filter(|definition| {
+        definition.visibility.as_deref() == Some("public") && definition.kind != "lambda"
+    }) {
+        exported_names.insert(definition.name.clone());
+        interface.exports.push(InterfaceSymbol {
+            name: definition.name.clone(),
+            kind: definition.kind.clone(),
+            signature: definition.signature.clone(),
+            line: definition
+                .range_start
+                .and_then(|start| usize::try_from(start).ok())
+                .map(line_of),
+        });
+    }
+    let extension = Path::new(path)
+        .extension()
+        .and_then(|extension| extension.to_str())
+        .unwrap_or("")
+        .to_ascii_lowercase();
+    let declaration: Option<&Regex> = match extension.as_str() {
+        "ts" | "tsx" | "js" | "jsx" | "mjs" | "cjs" => Some(&SCRIPT_EXPORT),
+        "rs" => Some(&RUST_PUBLIC_ITEM),
+        "go" => Some(&GO_EXPORTED),
+        "py" => Some(&PYTHON_PUBLIC),
+        _ => None,
+    };
+    for (index, line) in content
+        .lines()
+        .enumerate()
+        .filter(|_| declaration.is_some())
+    {
+        let Some(captures) = declaration.and_then(|pattern| pattern.captures(line)) else {
+            continue;
+        };
+        let name = &captures[2];
+        if !exported_names.insert(name.to_string()) {
+            continue;
+        }
+        interface.exports.push(InterfaceSymbol {
+            name: name.to_string(),
+            kind: captures[1].to_string(),
+            signature: Some(line.trim().trim_end_matches('{').trim_end().to_string()),
+            line: Some(index as u32 + 1),
+        });
+    }
+
+    for list_len in [interface.imports.len(), interface.exports.len()] {
+        interface.omitted += list_len.saturating_sub(MAX_INTERFACE_ENTRIES);
+    }
+    interface.imports.truncate(MAX_INTERFACE_ENTRIES);
+    interface.exports.truncate(MAX_INTERFACE_ENTRIES);
+    if interface.imports.is_empty() && interface.exports.is_empty() {
+        return None;
+    }
+    Some(interface)
+}
+
+fn interface_token_cost(interface: &BundleInterface) -> usize {
+    let imports: usize = interface
+        .imports
+        .iter()
+        .map(|specifier| estimate_tokens(specifier))
+        .sum();
+    let exports: usize = interface
+        .exports
+        .iter()
+        .map(|symbol| {
+            estimate_tokens(&symbol.name) + symbol.signature.as_deref().map_or(0, estimate_tokens)
+        })
+        .sum();
+    imports + exports
+}
+
+fn is_zero(value: &usize) -> bool {
+    *value == 0
+}
+
 fn build_file_brief(content: &str) -> Option<String> {
     let snippet = content
         .lines()
@@ -1404,6 +2419,27 @@ fn load_latest_ingestion(
     }
 }
 
+/// Resolves the ingest that last wrote a file. Files are stamped with the
+/// ingest's start time, so the matching ingestion row shares `started_at`.
+fn load_file_generation(conn: &Connection, indexed_at: i64) -> (Option<String>, Option<i64>) {
+    let ingestion_id = conn
+        .query_row(
+            "SELECT id FROM ingestions WHERE started_at = ?1 ORDER BY finished_at DESC LIMIT 1",
+            params![indexed_at],
+            |row| row.get::<_, String>(0),
+        )
+        .ok();
+    let generation = conn
+        .query_row(
+            "SELECT COUNT(*) FROM ingestions WHERE started_at <= ?1",
+            params![indexed_at],
+            |row| row.get::<_, i64>(0),
+        )
+        .ok()
+        .filter(|count| *count > 0);
+    (ingestion_id, generation)
+}
+
 fn gather_warnings(definitions: &[BundleDefinition], content: Option<&str>) -> Vec<String> {
     let mut warnings = Vec::new();
     if definitions.is_empty() {
@@ -1416,59 +2452,140 @@ fn gather_warnings(definitions: &[BundleDefinition], content: Option<&str>) -> V
     warnings
 }
 
+/// Files that most often appear in the same cached timeline commits as
+/// `path`, with their shared commit counts. Empty when no timeline has been
+/// captured.
+fn load_co_change_partners(conn: &Connection, root: &Path, path: &str) -> Vec<(String, u32)> {
+    // filtered captures hold partial file lists and would double count
+    let query = if has_timeline_filter_columns(conn) {
+        "SELECT payload FROM repository_timeline_entries WHERE filter_fingerprint = ''"
+    } else {
+        "SELECT payload FROM repository_timeline_entries"
+    };
+    let Ok(mut stmt) = conn.prepare(query) else {
+        return Vec::new();
+    };
+    let Ok(rows) = stmt.query_map([], |row| row.get::<_, String>(0)) else {
+        return Vec::new();
+    };
+
+    let mut counts: HashMap<String, u32> = HashMap::new();
+    for payload in rows.flatten() {
+        let Ok(entry) = serde_json::from_str::<Value>(&payload) else {
+            continue;
+        };
+        let Some(changes) = entry.get("fileChanges").and_then(Value::as_array) else {
+            continue;
+        };
+        if changes.len() > MAX_CO_CHANGE_COMMIT_FILES {
+            continue;
+        }
+        let paths: Vec<&str> = changes
+            .iter()
+            .filter_map(|change| change.get("path").and_then(Value::as_str))
+            .collect();
+        if !paths.contains(&path) {
+            continue;
+        }
+        for partner in paths.into_iter().filter(|partner| *partner != path) {
+            *counts.entry(partner.to_string()).or_default() += 1;
+        }
+    }
+
+    let mut partners: Vec<(String, u32)> = counts
+        .into_iter()
+        .filter(|(_, commits)| *commits >= MIN_CO_CHANGE_COMMITS)
+        .filter(|(partner, _)| {
+            resolve_within_root(root, partner).is_ok_and(|resolved| resolved.is_file())
+        })
+        .collect();
+    partners.sort_by(|a, b| b.1.cmp(&a.1).then_with(|| a.0.cmp(&b.0)));
+    partners.truncate(MAX_CO_CHANGE_LINKS);
+    partners
+}
+
+/// Ranks links by `QuickLinkReason`; within a reason, co-changes keep their
+/// commit order and symbols their graph order. The target file comes last
+/// since the caller already has it open.
 fn build_quick_links(
     path: &str,
     definitions: &[BundleDefinition],
     neighbors: &[BundleEdgeNeighbor],
     focus: Option<&BundleDefinition>,
+    co_changes: &[(String, u32)],
+    limit: usize,
 ) -> Vec<ContextBundleQuickLink> {
-    let mut links = Vec::new();
-    links.push(ContextBundleQuickLink {
-        r#type: QuickLinkType::File,
-        label: path.to_string(),
+    let definition_link = |definition: &BundleDefinition, reason| ContextBundleQuickLink {
+        r#type: QuickLinkType::RelatedSymbol,
+        reason,
+        label: definition.name.clone(),
         path: Some(path.to_string()),
         direction: None,
-        symbol_id: None,
-        symbol_kind: None,
-    });
+        symbol_id: Some(definition.id.clone()),
+        symbol_kind: Some(definition.kind.clone()),
+        co_change_commits: None,
+    };
 
+    let mut links = Vec::new();
     if let Some(definition) = focus {
-        links.push(ContextBundleQuickLink {
-            r#type: QuickLinkType::RelatedSymbol,
-            label: definition.name.clone(),
-            path: Some(path.to_string()),
-            direction: None,
-            symbol_id: Some(definition.id.clone()),
-            symbol_kind: Some(definition.kind.clone()),
-        });
+        links.push(definition_link(definition, QuickLinkReason::FocusSymbol));
     }
 
-    for definition in definitions {
-        if focus.map(|f| f.id.as_str()) == Some(definition.id.as_str()) {
+    let mut seen_neighbors = HashSet::new();
+    for neighbor in neighbors {
+        if !seen_neighbors.insert((neighbor.neighbor.id.as_str(), neighbor.direction)) {
             continue;
         }
+        let reason = match neighbor.direction {
+            NeighborDirection::Incoming => QuickLinkReason::Caller,
+            NeighborDirection::Outgoing => QuickLinkReason::Callee,
+        };
         links.push(ContextBundleQuickLink {
             r#type: QuickLinkType::RelatedSymbol,
-            label: definition.name.clone(),
-            path: Some(path.to_string()),
-            direction: None,
-            symbol_id: Some(definition.id.clone()),
-            symbol_kind: Some(definition.kind.clone()),
-        });
-    }
-
-    for neighbor in neighbors {
-        links.push(ContextBundleQuickLink {
-            r#type: QuickLinkType::RelatedSymbol,
+            reason,
             label: neighbor.neighbor.name.clone(),
             path: neighbor.neighbor.path.clone(),
             direction: Some(neighbor.direction),
             symbol_id: Some(neighbor.neighbor.id.clone()),
             symbol_kind: Some(neighbor.neighbor.kind.clone()),
+            co_change_commits: None,
+        });
+    }
+
+    for (partner, commits) in co_changes {
+        links.push(ContextBundleQuickLink {
+            r#type: QuickLinkType::CoChange,
+            reason: QuickLinkReason::CoChange,
+            label: format!("co-change: {partner}"),
+            path: Some(partner.clone()),
+            direction: None,
+            symbol_id: None,
+            symbol_kind: None,
+            co_change_commits: Some(*commits),
         });
     }
 
-    links.truncate(16);
+    for definition in definitions {
+        if focus.map(|f| f.id.as_str()) == Some(definition.id.as_str()) {
+            continue;
+        }
+        links.push(definition_link(definition, QuickLinkReason::Definition));
+    }
+
+    links.push(ContextBundleQuickLink {
+        r#type: QuickLinkType::File,
+        reason: QuickLinkReason::File,
+        label: path.to_string(),
+        path: Some(path.to_string()),
+        direction: None,
+        symbol_id: None,
+        symbol_kind: None,
+        co_change_commits: None,
+    });
+
+    // stable, so insertion order breaks ties within a reason
+    links.sort_by_key(|link| link.reason);
+    links.truncate(limit);
     links
 }
 
@@ -1504,6 +2621,8 @@ mod tests {
             line_start: Some(1),
             line_end: Some(content.lines().count() as i64),
             served_count: None,
+            provenance: None,
+            changes: None,
         }
     }
 
@@ -1513,7 +2632,7 @@ mod tests {
         let snippets = vec![build_snippet(&long_content)];
 
         let (result, usage, warnings) =
-            trim_snippets_to_budget(snippets, &[], /* budget_tokens */ 60);
+            trim_snippets_to_budget(snippets, &[], 0, /* budget_tokens */ 60);
 
         assert_eq!(result.len(), 1);
         let content = &result[0].content;
@@ -1526,6 +2645,49 @@ mod tests {
         assert!(usage.snippet_tokens > 0);
     }
 
+    fn line_range(start_line: u32, end_line: u32) -> LineRange {
+        LineRange {
+            start_line,
+            end_line,
+        }
+    }
+
+    #[test]
+    fn validates_and_merges_requested_ranges() {
+        let ranges = vec![
+            line_range(12, 8),
+            line_range(10, 14),
+            line_range(30, 60),
+            line_range(80, 90),
+        ];
+
+        let (valid, corrections) = validate_ranges(&ranges, Some(50));
+
+        let spans: Vec<(u32, u32)> = valid
+            .iter()
+            .map(|range| (range.start_line, range.end_line))
+            .collect();
+        assert_eq!(spans, vec![(8, 14), (30, 50)]);
+
+        let reasons: Vec<RangeCorrectionReason> = corrections
+            .iter()
+            .map(|correction| correction.reason)
+            .collect();
+        assert_eq!(
+            reasons,
+            vec![
+                RangeCorrectionReason::Swapped,
+                RangeCorrectionReason::ClampedEnd,
+                RangeCorrectionReason::BeyondEndOfFile,
+                RangeCorrectionReason::Merged,
+            ]
+        );
+        let merged = corrections.last().unwrap();
+        assert_eq!(merged.requested_start_line, 10);
+        assert_eq!(merged.applied_start_line, Some(8));
+        assert_eq!(merged.applied_end_line, Some(14));
+    }
+
     #[test]
     fn upgrades_to_excerpt_when_budget_allows() {
         let long_content = (0..500)
@@ -1535,7 +2697,7 @@ mod tests {
         let snippets = vec![build_snippet(&long_content)];
 
         let (result, usage, warnings) =
-            trim_snippets_to_budget(snippets, &[], /* budget_tokens */ 360);
+            trim_snippets_to_budget(snippets, &[], 0, /* budget_tokens */ 360);
 
         assert_eq!(result.len(), 1);
         let content = &result[0].content;
@@ -1549,4 +2711,87 @@ mod tests {
         assert_eq!(usage.excerpt_snippets, 1);
         assert!(usage.snippet_tokens > 0);
     }
+
+    #[test]
+    fn remaps_drifted_chunk_to_its_new_lines() {
+        let mut snippet = build_snippet("fn moved() {\n    work();\n}");
+        let disk = "// header added\n\nfn moved() {\n    work();\n}\n";
+
+        assert!(remap_snippet(&mut snippet, disk));
+        assert_eq!(snippet.line_start, Some(3));
+        assert_eq!(snippet.line_end, Some(5));
+
+        let mut ambiguous = build_snippet("work();");
+        assert!(!remap_snippet(&mut ambiguous, "work();\nwork();\n"));
+        assert_eq!(ambiguous.line_start, Some(1));
+    }
+
+    #[test]
+    fn ranks_quick_links_by_reason() {
+        let definition = |id: &str| BundleDefinition {
+            id: id.to_string(),
+            name: id.to_string(),
+            kind: "function".to_string(),
+            signature: None,
+            range_start: None,
+            range_end: None,
+            metadata: None,
+            visibility: None,
+            docstring: None,
+            todo_count: None,
+            metrics: None,
+        };
+        let neighbor = |id: &str, direction| BundleEdgeNeighbor {
+            id: format!("edge-{id}"),
+            r#type: "call".to_string(),
+            direction,
+            metadata: None,
+            neighbor: NeighborNode {
+                id: id.to_string(),
+                path: Some("src/other.rs".to_string()),
+                kind: "function".to_string(),
+                name: id.to_string(),
+                signature: None,
+                metadata: None,
+            },
+        };
+        let definitions = vec![definition("helper"), definition("focus")];
+        let neighbors = vec![
+            neighbor("callee", NeighborDirection::Outgoing),
+            neighbor("caller", NeighborDirection::Incoming),
+        ];
+        let co_changes = vec![("src/partner.rs".to_string(), 3)];
+
+        let links = build_quick_links(
+            "src/lib.rs",
+            &definitions,
+            &neighbors,
+            Some(&definitions[1]),
+            &co_changes,
+            16,
+        );
+        let reasons: Vec<QuickLinkReason> = links.iter().map(|link| link.reason).collect();
+        assert_eq!(
+            reasons,
+            vec![
+                QuickLinkReason::FocusSymbol,
+                QuickLinkReason::Caller,
+                QuickLinkReason::CoChange,
+                QuickLinkReason::Callee,
+                QuickLinkReason::Definition,
+                QuickLinkReason::File,
+            ]
+        );
+
+        let capped = build_quick_links(
+            "src/lib.rs",
+            &definitions,
+            &neighbors,
+            Some(&definitions[1]),
+            &co_changes,
+            2,
+        );
+        assert_eq!(capped.len(), 2);
+        assert_eq!(capped[1].label, "caller");
+    }
 }
@@ -0,0 +1,38 @@
+use rusqlite::Connection;
+
+/// Adds the `language` and `classification` columns ingest fills per chunk,
+/// so search can filter on them in SQL.
+pub(crate) fn ensure_chunk_label_schema(conn: &Connection) -> Result<(), rusqlite::Error> {
+    if !has_chunk_labels(conn)? {
+        conn.execute_batch(
+            "ALTER TABLE file_chunks ADD COLUMN language TEXT;
+             ALTER TABLE file_chunks ADD COLUMN classification TEXT;
+             CREATE INDEX IF NOT EXISTS file_chunks_classification_idx
+                 ON file_chunks(embedding_model, classification);",
+        )?;
+    }
+    Ok(())
+}
+
+/// Whether `file_chunks` has the label columns. Rows ingested before them
+/// hold NULL labels until the next ingest backfills them.
+pub(crate) fn has_chunk_labels(conn: &Connection) -> Result<bool, rusqlite::Error> {
+    let mut stmt = conn.prepare("PRAGMA table_info(file_chunks)")?;
+    let names = stmt.query_map([], |row| row.get::<_, String>(1))?;
+    for name in names {
+        if name? == "classification" {
+            return Ok(true);
+        }
+    }
+    Ok(false)
+}
+
+/// Column expressions for `language, classification`, given whether the
+/// database has them.
+pub(crate) fn chunk_label_columns(labelled: bool) -> &'static str {
+    if labelled {
+        "language, classification"
+    } else {
+        "NULL, NULL"
+    }
+}
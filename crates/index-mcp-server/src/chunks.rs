@@ -0,0 +1,200 @@
+use std::path::PathBuf;
+
+use rmcp::schemars::{self, JsonSchema};
+use rusqlite::params;
+use serde::{Deserialize, Serialize};
+use thiserror::Error;
+
+use crate::content_store::{content_encoding_column, StoredContentCache};
+use crate::data_dir::{database_path, open_read_only};
+use crate::index_status::DEFAULT_DB_FILENAME;
+use crate::roots::resolve_workspace_root;
+use crate::sandbox::{ensure_root_allowed, SandboxError};
+
+const DEFAULT_NEIGHBOR_CHUNKS: u32 = 1;
+const MAX_NEIGHBOR_CHUNKS: u32 = 10;
+
+#[derive(Debug, Deserialize, JsonSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct ChunkNeighborsParams {
+    #[serde(default)]
+    pub root: Option<String>,
+    #[serde(default)]
+    pub database_name: Option<String>,
+    pub path: String,
+    pub chunk_index: u32,
+    /// Chunks to return before the anchor (default 1, max 10).
+    #[serde(default)]
+    pub before: Option<u32>,
+    /// Chunks to return after the anchor (default 1, max 10).
+    #[serde(default)]
+    pub after: Option<u32>,
+}
+
+#[derive(Debug, Clone, Serialize, JsonSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct NeighborChunk {
+    pub chunk_index: u32,
+    pub line_start: Option<u32>,
+    pub line_end: Option<u32>,
+    pub content: String,
+    pub is_anchor: bool,
+}
+
+#[derive(Debug, Clone, Serialize, JsonSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct ChunkNeighborsResponse {
+    pub database_path: String,
+    pub path: String,
+    pub anchor_index: u32,
+    pub total_chunks: u32,
+    pub chunks: Vec<NeighborChunk>,
+    /// Pass as `chunkIndex` to keep scrolling; `None` at the start or end of
+    /// the file.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub previous_index: Option<u32>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub next_index: Option<u32>,
+}
+
+#[derive(Debug, Error)]
+pub enum ChunkNeighborsError {
+    #[error("failed to resolve workspace root '{path}': {source}")]
+    InvalidRoot {
+        path: String,
+        #[source]
+        source: std::io::Error,
+    },
+    #[error("SQLite error: {0}")]
+    Sqlite(#[from] rusqlite::Error),
+    #[error("chunk {chunk_index} of '{path}' is not indexed ({total_chunks} chunk(s) stored)")]
+    ChunkNotFound {
+        path: String,
+        chunk_index: u32,
+        total_chunks: u32,
+    },
+    #[error(transparent)]
+    Sandbox(#[from] SandboxError),
+    #[error("blocking task panicked: {0}")]
+    Join(#[from] tokio::task::JoinError),
+}
+
+pub async fn chunk_neighbors(
+    params: ChunkNeighborsParams,
+) -> Result<ChunkNeighborsResponse, ChunkNeighborsError> {
+    tokio::task::spawn_blocking(move || load_chunk_neighbors(params)).await?
+}
+
+fn load_chunk_neighbors(
+    params: ChunkNeighborsParams,
+) -> Result<ChunkNeighborsResponse, ChunkNeighborsError> {
+    let root = params.root.unwrap_or_else(|| "./".to_string());
+    let root_path = resolve_root(&root)?;
+    ensure_root_allowed(&root_path)?;
+    let database_path = database_path(
+        &root_path,
+        &params
+            .database_name
+            .unwrap_or_else(|| DEFAULT_DB_FILENAME.to_string()),
+    );
+    let path = params.path.replace('\\', "/");
+    let anchor = params.chunk_index;
+    let before = params
+        .before
+        .unwrap_or(DEFAULT_NEIGHBOR_CHUNKS)
+        .min(MAX_NEIGHBOR_CHUNKS);
+    let after = params
+        .after
+        .unwrap_or(DEFAULT_NEIGHBOR_CHUNKS)
+        .min(MAX_NEIGHBOR_CHUNKS);
+
+    let conn = open_read_only(&database_path)?;
+    let total_chunks: u32 = conn.query_row(
+        "SELECT COUNT(*) FROM file_chunks WHERE path = ?1 AND chunk_index >= 0",
+        params![path],
+        |row| row.get(0),
+    )?;
+
+    let encoding_column = content_encoding_column(&conn, "file_chunks");
+    let mut stmt = conn.prepare(&format!(
+        "SELECT chunk_index, line_start, line_end, content, {encoding_column}, byte_start, byte_end
+         FROM file_chunks
+         WHERE path = ?1 AND chunk_index BETWEEN ?2 AND ?3
+         ORDER BY chunk_index ASC"
+    ))?;
+    let mut content_cache = StoredContentCache::new(&conn)?;
+    let rows = stmt
+        .query_map(
+            params![
+                path,
+                anchor.saturating_sub(before),
+                anchor.saturating_add(after)
+            ],
+            |row| {
+                Ok((
+                    row.get::<_, u32>(0)?,
+                    row.get::<_, Option<u32>>(1)?,
+                    row.get::<_, Option<u32>>(2)?,
+                    row.get::<_, String>(3)?,
+                    row.get::<_, Option<String>>(4)?,
+                    row.get::<_, Option<i64>>(5)?,
+                    row.get::<_, Option<i64>>(6)?,
+                ))
+            },
+        )?
+        .collect::<Result<Vec<_>, _>>()?;
+
+    let chunks: Vec<NeighborChunk> = rows
+        .into_iter()
+        .map(
+            |(chunk_index, line_start, line_end, content, encoding, byte_start, byte_end)| {
+                NeighborChunk {
+                    chunk_index,
+                    line_start,
+                    line_end,
+                    content: content_cache.resolve_chunk(
+                        &path,
+                        content,
+                        encoding.as_deref(),
+                        byte_start,
+                        byte_end,
+                    ),
+                    is_anchor: chunk_index == anchor,
+                }
+            },
+        )
+        .collect();
+
+    if !chunks.iter().any(|chunk| chunk.is_anchor) {
+        return Err(ChunkNeighborsError::ChunkNotFound {
+            path,
+            chunk_index: anchor,
+            total_chunks,
+        });
+    }
+
+    let first = chunks
+        .first()
+        .map(|chunk| chunk.chunk_index)
+        .unwrap_or(anchor);
+    let last = chunks
+        .last()
+        .map(|chunk| chunk.chunk_index)
+        .unwrap_or(anchor);
+    Ok(ChunkNeighborsResponse {
+        database_path: database_path.to_string_lossy().to_string(),
+        path,
+        anchor_index: anchor,
+        total_chunks,
+        previous_index: first.checked_sub(1),
+        next_index: Some(last + 1).filter(|next| *next < total_chunks),
+        chunks,
+    })
+}
+
+fn resolve_root(root: &str) -> Result<PathBuf, ChunkNeighborsError> {
+    resolve_workspace_root(root).map_err(|source| ChunkNeighborsError::InvalidRoot {
+        path: root.to_string(),
+        source,
+    })
+}
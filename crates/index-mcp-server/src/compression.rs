@@ -0,0 +1,102 @@
+use std::io::Write;
+
+use base64::engine::general_purpose::STANDARD as BASE64;
+use base64::Engine;
+use flate2::write::GzEncoder;
+use flate2::Compression;
+use rmcp::model::{CallToolResult, Meta};
+use serde_json::{json, Value};
+
+// payloads this small gain less than the base64 wrapper costs
+const MIN_COMPRESS_BYTES: usize = 4 * 1024;
+const ZSTD_LEVEL: i32 = 3;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum ContentEncoding {
+    Zstd,
+    Gzip,
+}
+
+impl ContentEncoding {
+    fn parse(value: &str) -> Option<Self> {
+        match value.trim().to_ascii_lowercase().as_str() {
+            "zstd" => Some(Self::Zstd),
+            "gzip" => Some(Self::Gzip),
+            _ => None,
+        }
+    }
+
+    fn as_str(self) -> &'static str {
+        match self {
+            Self::Zstd => "zstd",
+            Self::Gzip => "gzip",
+        }
+    }
+
+    fn compress(self, bytes: &[u8]) -> std::io::Result<Vec<u8>> {
+        match self {
+            Self::Zstd => zstd::bulk::compress(bytes, ZSTD_LEVEL),
+            Self::Gzip => {
+                let mut encoder = GzEncoder::new(Vec::new(), Compression::default());
+                encoder.write_all(bytes)?;
+                encoder.finish()
+            }
+        }
+    }
+}
+
+/// Picks the first encoding the server supports from the request's
+/// `acceptEncoding` meta, given either as a list in preference order or as a
+/// comma-separated string.
+pub(crate) fn negotiate_encoding(meta: &Meta) -> Option<ContentEncoding> {
+    match meta.get("acceptEncoding")? {
+        Value::String(list) => list.split(',').find_map(ContentEncoding::parse),
+        Value::Array(items) => items
+            .iter()
+            .filter_map(Value::as_str)
+            .find_map(ContentEncoding::parse),
+        _ => None,
+    }
+}
+
+/// Replaces large structured content with `{encoding, data}` where `data` is
+/// the base64 of the compressed JSON, and records both sizes under the
+/// `contentEncoding` meta key. The text summary is left as is so clients
+/// that ignore structured content are unaffected.
+pub(crate) fn compress_structured_content(result: &mut CallToolResult, encoding: ContentEncoding) {
+    let Some(content) = &result.structured_content else {
+        return;
+    };
+    let Ok(raw) = serde_json::to_vec(content) else {
+        return;
+    };
+    if raw.len() < MIN_COMPRESS_BYTES {
+        return;
+    }
+    let compressed = match encoding.compress(&raw) {
+        Ok(compressed) => compressed,
+        Err(error) => {
+            tracing::debug!(?error, encoding = encoding.as_str(), "Compression failed");
+            return;
+        }
+    };
+    let wrapped = json!({
+        "encoding": encoding.as_str(),
+        "data": BASE64.encode(&compressed),
+    });
+    // base64 adds a third; only a payload smaller once encoded is worth it
+    let wrapped_bytes = serde_json::to_vec(&wrapped).map_or(usize::MAX, |bytes| bytes.len());
+    if wrapped_bytes >= raw.len() {
+        return;
+    }
+
+    result.structured_content = Some(wrapped);
+    result.meta.get_or_insert_with(Meta::new).insert(
+        "contentEncoding".to_string(),
+        json!({
+            "encoding": encoding.as_str(),
+            "originalBytes": raw.len(),
+            "compressedBytes": compressed.len(),
+        }),
+    );
+}
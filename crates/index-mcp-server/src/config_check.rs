@@ -0,0 +1,360 @@
+use std::fmt;
+
+use tracing_subscriber::EnvFilter;
+
+const ENV_PREFIX: &str = "INDEX_MCP_";
+// read by the ingest_debug and bench binaries, not the server
+const TOOL_ENV_PREFIXES: &[&str] = &["INDEX_MCP_DEBUG_", "INDEX_MCP_BENCH_"];
+const MIN_WATCH_DEBOUNCE_MS: u64 = 50;
+const DEFAULT_LOG_LEVEL: &str = "info";
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum ValueKind {
+    Bool,
+    Count,
+    Json,
+    LogFilter,
+    Text,
+}
+
+struct KnownVar {
+    name: &'static str,
+    kind: ValueKind,
+}
+
+const KNOWN_VARS: &[KnownVar] = &[
+    KnownVar {
+        name: "INDEX_MCP_ALLOWED_ROOTS",
+        kind: ValueKind::Text,
+    },
+    KnownVar {
+        name: "INDEX_MCP_AUDIT_LOG",
+        kind: ValueKind::Text,
+    },
+    KnownVar {
+        name: "INDEX_MCP_BUDGET_TOKENS",
+        kind: ValueKind::Count,
+    },
+    KnownVar {
+        name: "INDEX_MCP_DATA_DIR",
+        kind: ValueKind::Text,
+    },
+    KnownVar {
+        name: "INDEX_MCP_DATA_DIR_DATABASES",
+        kind: ValueKind::Bool,
+    },
+    KnownVar {
+        name: "INDEX_MCP_FILTER_PRESETS",
+        kind: ValueKind::Json,
+    },
+    KnownVar {
+        name: "INDEX_MCP_KEEP_GENERATIONS",
+        kind: ValueKind::Count,
+    },
+    KnownVar {
+        name: "INDEX_MCP_LOG_CONSOLE",
+        kind: ValueKind::Bool,
+    },
+    KnownVar {
+        name: "INDEX_MCP_LOG_DIR",
+        kind: ValueKind::Text,
+    },
+    KnownVar {
+        name: "INDEX_MCP_LOG_LEVEL",
+        kind: ValueKind::LogFilter,
+    },
+    KnownVar {
+        name: "INDEX_MCP_MAX_INGESTS_PER_HOUR",
+        kind: ValueKind::Count,
+    },
+    KnownVar {
+        name: "INDEX_MCP_MAX_SEARCHES_PER_MINUTE",
+        kind: ValueKind::Count,
+    },
+    KnownVar {
+        name: "INDEX_MCP_OLLAMA_URL",
+        kind: ValueKind::Text,
+    },
+    KnownVar {
+        name: "INDEX_MCP_OPENAI_API_KEY",
+        kind: ValueKind::Text,
+    },
+    KnownVar {
+        name: "INDEX_MCP_OPENAI_BASE_URL",
+        kind: ValueKind::Text,
+    },
+    KnownVar {
+        name: "INDEX_MCP_PATH_BOOSTS",
+        kind: ValueKind::Json,
+    },
+    KnownVar {
+        name: "INDEX_MCP_REMOTE_SERVERS",
+        kind: ValueKind::Json,
+    },
+    KnownVar {
+        name: "INDEX_MCP_TIMELINE_MAX_DIFF_AGE_DAYS",
+        kind: ValueKind::Count,
+    },
+    KnownVar {
+        name: "INDEX_MCP_TIMELINE_MAX_DIFF_BYTES",
+        kind: ValueKind::Count,
+    },
+    KnownVar {
+        name: "INDEX_MCP_VECTOR_INDEX_MIN_CHUNKS",
+        kind: ValueKind::Count,
+    },
+    // consumed by start.sh before the server starts
+    KnownVar {
+        name: "INDEX_MCP_ARGS",
+        kind: ValueKind::Text,
+    },
+    KnownVar {
+        name: "INDEX_MCP_CARGO_PROFILE",
+        kind: ValueKind::Text,
+    },
+    KnownVar {
+        name: "INDEX_MCP_MODE",
+        kind: ValueKind::Text,
+    },
+    KnownVar {
+        name: "INDEX_MCP_RUNTIME",
+        kind: ValueKind::Text,
+    },
+];
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum Severity {
+    Error,
+    Warning,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum ValueSource {
+    Environment,
+    Flag,
+}
+
+#[derive(Debug, Clone)]
+pub(crate) struct ConfigDiagnostic {
+    pub(crate) severity: Severity,
+    pub(crate) key: String,
+    pub(crate) source: ValueSource,
+    pub(crate) message: String,
+    pub(crate) suggestion: Option<String>,
+}
+
+impl fmt::Display for ConfigDiagnostic {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let severity = match self.severity {
+            Severity::Error => "error",
+            Severity::Warning => "warning",
+        };
+        let source = match self.source {
+            ValueSource::Environment => "environment",
+            ValueSource::Flag => "command line",
+        };
+        write!(f, "{severity}: {} ({source}): {}", self.key, self.message)?;
+        if let Some(suggestion) = &self.suggestion {
+            write!(f, "; {suggestion}")?;
+        }
+        Ok(())
+    }
+}
+
+/// Flag values that interact with each other or with the environment.
+pub(crate) struct FlagSettings<'a> {
+    pub(crate) log_level: Option<&'a str>,
+    pub(crate) watch: bool,
+    /// Watcher flags given on the command line, by name.
+    pub(crate) watch_flags: Vec<&'static str>,
+    pub(crate) watch_debounce: Option<u64>,
+}
+
+/// Checks `INDEX_MCP_*` variables and flag combinations that the server
+/// would otherwise ignore or silently replace with defaults.
+pub(crate) fn validate_config(
+    vars: impl IntoIterator<Item = (String, String)>,
+    flags: &FlagSettings<'_>,
+) -> Vec<ConfigDiagnostic> {
+    let mut diagnostics = Vec::new();
+    let mut log_level_env = None;
+    let mut rust_log_env = false;
+
+    for (key, value) in vars {
+        if key == "RUST_LOG" {
+            rust_log_env = true;
+            continue;
+        }
+        if !key.starts_with(ENV_PREFIX)
+            || TOOL_ENV_PREFIXES
+                .iter()
+                .any(|prefix| key.starts_with(prefix))
+        {
+            continue;
+        }
+        let Some(known) = KNOWN_VARS.iter().find(|known| known.name == key) else {
+            let suggestion = closest_known(&key).map(|name| format!("did you mean {name}?"));
+            diagnostics.push(ConfigDiagnostic {
+                severity: Severity::Error,
+                message: "unknown setting".to_string(),
+                suggestion,
+                key,
+                source: ValueSource::Environment,
+            });
+            continue;
+        };
+        if known.name == "INDEX_MCP_LOG_LEVEL" {
+            log_level_env = Some(value.clone());
+        }
+        if let Some((message, suggestion)) = check_value(known.kind, &value) {
+            diagnostics.push(ConfigDiagnostic {
+                severity: Severity::Error,
+                key,
+                source: ValueSource::Environment,
+                message,
+                suggestion: Some(suggestion.to_string()),
+            });
+        }
+    }
+
+    if let Some(level) = flags.log_level {
+        if let Some((message, suggestion)) = check_value(ValueKind::LogFilter, level) {
+            diagnostics.push(flag_error("--log-level", message, suggestion));
+        }
+        let overridden_by = if log_level_env.is_some() {
+            Some("INDEX_MCP_LOG_LEVEL")
+        } else if rust_log_env {
+            Some("RUST_LOG")
+        } else {
+            None
+        };
+        if let Some(variable) = overridden_by {
+            diagnostics.push(ConfigDiagnostic {
+                severity: Severity::Warning,
+                key: "--log-level".to_string(),
+                source: ValueSource::Flag,
+                message: format!("ignored because {variable} is set"),
+                suggestion: Some(format!("unset {variable} to use the flag")),
+            });
+        }
+    } else if log_level_env.is_some() && rust_log_env {
+        diagnostics.push(ConfigDiagnostic {
+            severity: Severity::Warning,
+            key: "RUST_LOG".to_string(),
+            source: ValueSource::Environment,
+            message: "ignored because INDEX_MCP_LOG_LEVEL is set".to_string(),
+            suggestion: None,
+        });
+    }
+
+    if !flags.watch {
+        for flag in &flags.watch_flags {
+            diagnostics.push(flag_error(
+                flag,
+                "has no effect without --watch".to_string(),
+                "add --watch or drop the flag",
+            ));
+        }
+    }
+    if let Some(debounce) = flags.watch_debounce {
+        if debounce < MIN_WATCH_DEBOUNCE_MS {
+            diagnostics.push(ConfigDiagnostic {
+                severity: Severity::Warning,
+                key: "--watch-debounce".to_string(),
+                source: ValueSource::Flag,
+                message: format!("{debounce} ms is below the minimum and is raised to {MIN_WATCH_DEBOUNCE_MS} ms"),
+                suggestion: None,
+            });
+        }
+    }
+
+    diagnostics
+}
+
+/// The effective log filter and where it came from, for the startup log.
+pub(crate) fn effective_log_level(cli_level: Option<&str>) -> (String, &'static str) {
+    if let Ok(value) = std::env::var("INDEX_MCP_LOG_LEVEL") {
+        (value, "INDEX_MCP_LOG_LEVEL")
+    } else if let Ok(value) = std::env::var("RUST_LOG") {
+        (value, "RUST_LOG")
+    } else if let Some(level) = cli_level {
+        (level.to_string(), "--log-level")
+    } else {
+        (DEFAULT_LOG_LEVEL.to_string(), "default")
+    }
+}
+
+fn flag_error(flag: &str, message: String, suggestion: &str) -> ConfigDiagnostic {
+    ConfigDiagnostic {
+        severity: Severity::Error,
+        key: flag.to_string(),
+        source: ValueSource::Flag,
+        message,
+        suggestion: Some(suggestion.to_string()),
+    }
+}
+
+fn check_value(kind: ValueKind, value: &str) -> Option<(String, &'static str)> {
+    let trimmed = value.trim();
+    match kind {
+        ValueKind::Bool => (!matches!(
+            trimmed.to_ascii_lowercase().as_str(),
+            "1" | "true" | "yes" | "on" | "0" | "false" | "no" | "off"
+        ))
+        .then(|| {
+            (
+                format!("'{value}' is not a boolean"),
+                "use one of 1/0, true/false, yes/no, on/off",
+            )
+        }),
+        ValueKind::Count => trimmed.parse::<u32>().is_err().then(|| {
+            (
+                format!("'{value}' is not a non-negative integer"),
+                "use a whole number such as 60; 0 disables the limit",
+            )
+        }),
+        ValueKind::Json => {
+            if trimmed.is_empty() {
+                return None;
+            }
+            serde_json::from_str::<serde_json::Value>(trimmed)
+                .err()
+                .map(|error| {
+                    (
+                        format!("invalid JSON: {error}"),
+                        "expected a JSON array of remote server objects",
+                    )
+                })
+        }
+        ValueKind::LogFilter => EnvFilter::builder().parse(trimmed).err().map(|error| {
+            (
+                format!("invalid log filter '{value}': {error}"),
+                "use a level such as info or directives such as index_mcp_server=debug",
+            )
+        }),
+        ValueKind::Text => None,
+    }
+}
+
+fn closest_known(key: &str) -> Option<&'static str> {
+    KNOWN_VARS
+        .iter()
+        .map(|known| (known.name, edit_distance(key, known.name)))
+        .filter(|(_, distance)| *distance <= 3)
+        .min_by_key(|(_, distance)| *distance)
+        .map(|(name, _)| name)
+}
+
+fn edit_distance(left: &str, right: &str) -> usize {
+    let right: Vec<char> = right.chars().collect();
+    let mut previous: Vec<usize> = (0..=right.len()).collect();
+    for (i, left_char) in left.chars().enumerate() {
+        let mut current = vec![i + 1; right.len() + 1];
+        for (j, right_char) in right.iter().enumerate() {
+            let substitution = previous[j] + usize::from(left_char != *right_char);
+            current[j + 1] = substitution.min(previous[j + 1] + 1).min(current[j] + 1);
+        }
+        previous = current;
+    }
+    previous[right.len()]
+}
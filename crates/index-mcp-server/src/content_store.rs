@@ -0,0 +1,182 @@
+use std::collections::HashMap;
+
+use rusqlite::types::Value as SqlValue;
+use rusqlite::{params, Connection, Statement};
+
+pub(crate) const ZSTD_ENCODING: &str = "zstd";
+/// Chunk rows with this encoding store no text; their content is the
+/// `byte_start..byte_end` slice of the owning file's stored content.
+pub(crate) const CHUNK_REFERENCE_ENCODING: &str = "ref";
+
+const ZSTD_LEVEL: i32 = 3;
+
+pub(crate) fn ensure_content_schema(conn: &Connection) -> Result<(), rusqlite::Error> {
+    for table in ["files", "file_chunks"] {
+        if !has_content_encoding(conn, table)? {
+            conn.execute_batch(&format!(
+                "ALTER TABLE {table} ADD COLUMN content_encoding TEXT"
+            ))?;
+        }
+    }
+    Ok(())
+}
+
+/// Column expression for readers that may open databases written before
+/// content encodings existed.
+pub(crate) fn content_encoding_column(conn: &Connection, table: &str) -> &'static str {
+    match has_content_encoding(conn, table) {
+        Ok(true) => "content_encoding",
+        _ => "NULL",
+    }
+}
+
+fn has_content_encoding(conn: &Connection, table: &str) -> Result<bool, rusqlite::Error> {
+    let mut stmt = conn.prepare(&format!("PRAGMA table_info({table})"))?;
+    let names = stmt.query_map([], |row| row.get::<_, String>(1))?;
+    for name in names {
+        if name? == "content_encoding" {
+            return Ok(true);
+        }
+    }
+    Ok(false)
+}
+
+pub(crate) fn encode_file_content(
+    content: Option<String>,
+    compress: bool,
+) -> (Option<SqlValue>, Option<&'static str>) {
+    match content {
+        None => (None, None),
+        Some(text) if compress => match zstd::bulk::compress(text.as_bytes(), ZSTD_LEVEL) {
+            Ok(bytes) => (Some(SqlValue::Blob(bytes)), Some(ZSTD_ENCODING)),
+            Err(_) => (Some(SqlValue::Text(text)), None),
+        },
+        Some(text) => (Some(SqlValue::Text(text)), None),
+    }
+}
+
+pub(crate) fn decode_file_content(raw: Option<SqlValue>, encoding: Option<&str>) -> Option<String> {
+    match (raw?, encoding) {
+        (SqlValue::Blob(bytes), Some(ZSTD_ENCODING)) => zstd::stream::decode_all(bytes.as_slice())
+            .ok()
+            .map(|decoded| String::from_utf8_lossy(&decoded).into_owned()),
+        (SqlValue::Text(text), _) => Some(text),
+        (SqlValue::Blob(bytes), _) => Some(String::from_utf8_lossy(&bytes).into_owned()),
+        _ => None,
+    }
+}
+
+/// Rebuilds a chunk stored by reference. Ingest chunks the trimmed file text,
+/// so offsets are relative to it rather than to the raw content.
+pub(crate) fn slice_chunk(
+    file_content: &str,
+    byte_start: Option<i64>,
+    byte_end: Option<i64>,
+) -> Option<String> {
+    let start = usize::try_from(byte_start?).ok()?;
+    let end = usize::try_from(byte_end?).ok()?;
+    file_content.trim().get(start..end).map(str::to_string)
+}
+
+/// Decoded `files.content` keyed by path, loaded lazily.
+pub(crate) struct StoredContentCache<'conn> {
+    stmt: Statement<'conn>,
+    entries: HashMap<String, Option<String>>,
+}
+
+impl<'conn> StoredContentCache<'conn> {
+    pub fn new(conn: &'conn Connection) -> Result<Self, rusqlite::Error> {
+        let encoding_column = content_encoding_column(conn, "files");
+        let stmt = conn.prepare(&format!(
+            "SELECT content, {encoding_column} FROM files WHERE path = ?1"
+        ))?;
+        Ok(Self {
+            stmt,
+            entries: HashMap::new(),
+        })
+    }
+
+    pub fn get(&mut self, path: &str) -> Option<&str> {
+        if !self.entries.contains_key(path) {
+            let content = self
+                .stmt
+                .query_row(params![path], |row| {
+                    Ok((
+                        row.get::<_, Option<SqlValue>>(0)?,
+                        row.get::<_, Option<String>>(1)?,
+                    ))
+                })
+                .ok()
+                .and_then(|(raw, encoding)| decode_file_content(raw, encoding.as_deref()));
+            self.entries.insert(path.to_string(), content);
+        }
+        self.entries
+            .get(path)
+            .and_then(|content| content.as_deref())
+    }
+
+    /// Returns the chunk text, reading through to the file content for chunks
+    /// stored by reference.
+    pub fn resolve_chunk(
+        &mut self,
+        path: &str,
+        content: String,
+        encoding: Option<&str>,
+        byte_start: Option<i64>,
+        byte_end: Option<i64>,
+    ) -> String {
+        if encoding != Some(CHUNK_REFERENCE_ENCODING) {
+            return content;
+        }
+        self.get(path)
+            .and_then(|file_content| slice_chunk(file_content, byte_start, byte_end))
+            .unwrap_or(content)
+    }
+}
+
+pub(crate) fn load_reference_chunk_paths(
+    conn: &Connection,
+) -> Result<std::collections::HashSet<String>, rusqlite::Error> {
+    let mut stmt =
+        conn.prepare("SELECT DISTINCT path FROM file_chunks WHERE content_encoding = ?1")?;
+    let rows = stmt.query_map(params![CHUNK_REFERENCE_ENCODING], |row| row.get(0))?;
+    rows.collect()
+}
+
+/// Switches an unchanged file's chunks between inline and by-reference storage
+/// without re-embedding them.
+pub(crate) fn convert_chunk_storage(
+    conn: &Connection,
+    path: &str,
+    file_content: &str,
+    by_reference: bool,
+) -> Result<(), rusqlite::Error> {
+    if by_reference {
+        conn.execute(
+            "UPDATE file_chunks SET content = '', content_encoding = ?2 WHERE path = ?1",
+            params![path, CHUNK_REFERENCE_ENCODING],
+        )?;
+        return Ok(());
+    }
+
+    let mut select = conn.prepare(
+        "SELECT id, byte_start, byte_end FROM file_chunks WHERE path = ?1 AND content_encoding = ?2",
+    )?;
+    let chunks = select
+        .query_map(params![path, CHUNK_REFERENCE_ENCODING], |row| {
+            Ok((
+                row.get::<_, String>(0)?,
+                row.get::<_, Option<i64>>(1)?,
+                row.get::<_, Option<i64>>(2)?,
+            ))
+        })?
+        .collect::<Result<Vec<_>, _>>()?;
+
+    let mut update =
+        conn.prepare("UPDATE file_chunks SET content = ?2, content_encoding = NULL WHERE id = ?1")?;
+    for (id, byte_start, byte_end) in chunks {
+        let content = slice_chunk(file_content, byte_start, byte_end).unwrap_or_default();
+        update.execute(params![id, content])?;
+    }
+    Ok(())
+}
@@ -0,0 +1,255 @@
+use std::collections::{BTreeMap, HashSet};
+use std::path::{Path, PathBuf};
+
+use rmcp::schemars::{self, JsonSchema};
+use rusqlite::Connection;
+use serde::{Deserialize, Serialize};
+use thiserror::Error;
+
+use crate::data_dir::{database_path, open_read_only};
+use crate::ignore_rules::has_hidden_component;
+use crate::index_status::{git_tracked_files, DEFAULT_DB_FILENAME};
+use crate::ingest::{compile_globs, IngestError, DEFAULT_EXCLUDE_GLOBS, DEFAULT_INCLUDE_GLOBS};
+use crate::roots::resolve_workspace_root;
+use crate::sandbox::{ensure_root_allowed, SandboxError};
+use crate::skipped::recorded_skip;
+
+const DEFAULT_PATH_LIMIT: usize = 50;
+const MAX_PATH_LIMIT: usize = 500;
+const MAX_DIRECTORY_ROWS: usize = 25;
+
+#[derive(Debug, Deserialize, JsonSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct CoverageReportParams {
+    #[serde(default)]
+    pub root: Option<String>,
+    #[serde(default)]
+    pub database_name: Option<String>,
+    /// Same semantics as ingest_codebase; tracked files outside these globs
+    /// are not reported as missing.
+    #[serde(default)]
+    pub include: Option<Vec<String>>,
+    #[serde(default)]
+    pub exclude: Option<Vec<String>>,
+    /// Named filter preset from INDEX_MCP_FILTER_PRESETS; its `include`
+    /// (or `pathPrefix`) and `exclude` apply here.
+    #[serde(default)]
+    pub preset: Option<String>,
+    /// Count tracked dotfiles as expected in the index; match the ingest
+    /// setting.
+    #[serde(default)]
+    pub include_hidden: Option<bool>,
+    /// Maximum paths listed per category.
+    #[serde(default)]
+    pub limit: Option<u32>,
+}
+
+#[derive(Debug, Clone, Serialize, JsonSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct CoverageDirectory {
+    pub directory: String,
+    pub tracked: u64,
+    pub indexed: u64,
+    pub missing: u64,
+    pub untracked: u64,
+}
+
+#[derive(Debug, Clone, Serialize, JsonSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct CoverageReportResponse {
+    pub root: String,
+    pub database_path: String,
+    pub database_exists: bool,
+    pub tracked_file_count: u64,
+    pub indexed_file_count: u64,
+    pub covered_file_count: u64,
+    /// Tracked by git and matched by include/exclude, but not in the index.
+    pub missing_from_index: Vec<String>,
+    /// Skip reason recorded by a recent ingestion for listed missing paths.
+    #[serde(skip_serializing_if = "BTreeMap::is_empty")]
+    pub missing_reasons: BTreeMap<String, String>,
+    pub missing_count: u64,
+    /// In the index but not tracked by git (deleted, renamed, or never added).
+    pub untracked_in_index: Vec<String>,
+    pub untracked_count: u64,
+    /// Directories with gaps, largest first.
+    pub directories: Vec<CoverageDirectory>,
+}
+
+#[derive(Debug, Error)]
+pub enum CoverageError {
+    #[error("failed to resolve workspace root '{path}': {source}")]
+    InvalidRoot {
+        path: String,
+        #[source]
+        source: std::io::Error,
+    },
+    #[error("failed to list git-tracked files: {0}")]
+    Git(#[source] std::io::Error),
+    #[error(transparent)]
+    Sqlite(#[from] rusqlite::Error),
+    #[error(transparent)]
+    Glob(#[from] IngestError),
+    #[error("blocking task panicked: {0}")]
+    Join(#[from] tokio::task::JoinError),
+    #[error(transparent)]
+    Sandbox(#[from] SandboxError),
+}
+
+pub async fn coverage_report(
+    params: CoverageReportParams,
+) -> Result<CoverageReportResponse, CoverageError> {
+    tokio::task::spawn_blocking(move || compute_coverage_report(params)).await?
+}
+
+fn compute_coverage_report(
+    params: CoverageReportParams,
+) -> Result<CoverageReportResponse, CoverageError> {
+    let root = params.root.unwrap_or_else(|| "./".to_string());
+    let database_name = params
+        .database_name
+        .unwrap_or_else(|| DEFAULT_DB_FILENAME.to_string());
+    let limit = params
+        .limit
+        .map(|value| value as usize)
+        .unwrap_or(DEFAULT_PATH_LIMIT)
+        .clamp(1, MAX_PATH_LIMIT);
+
+    let absolute_root = resolve_root(&root)?;
+    ensure_root_allowed(&absolute_root)?;
+    let database_path = database_path(&absolute_root, &database_name);
+
+    let include_patterns = params.include.unwrap_or_else(|| {
+        DEFAULT_INCLUDE_GLOBS
+            .iter()
+            .map(|s| s.to_string())
+            .collect()
+    });
+    let exclude_patterns = params.exclude.unwrap_or_else(|| {
+        DEFAULT_EXCLUDE_GLOBS
+            .iter()
+            .map(|s| s.to_string())
+            .collect()
+    });
+    let include_globs = compile_globs(&include_patterns)?;
+    let exclude_globs = compile_globs(&exclude_patterns)?;
+    let include_hidden = params.include_hidden.unwrap_or(false);
+
+    let tracked: HashSet<String> = git_tracked_files(&absolute_root)
+        .map_err(CoverageError::Git)?
+        .into_iter()
+        .filter(|path| *path != database_name)
+        .filter(|path| {
+            let path = Path::new(path);
+            (include_hidden || !has_hidden_component(path))
+                && include_globs.as_ref().is_none_or(|set| set.is_match(path))
+                && !exclude_globs.as_ref().is_some_and(|set| set.is_match(path))
+        })
+        .collect();
+
+    let database_exists = database_path.is_file();
+    let conn = if database_exists {
+        Some(open_read_only(&database_path)?)
+    } else {
+        None
+    };
+    let indexed = match &conn {
+        Some(conn) => load_indexed_paths(conn)?,
+        None => HashSet::new(),
+    };
+
+    let mut missing: Vec<String> = tracked.difference(&indexed).cloned().collect();
+    let mut untracked: Vec<String> = indexed.difference(&tracked).cloned().collect();
+    missing.sort();
+    untracked.sort();
+
+    let mut directories: BTreeMap<String, CoverageDirectory> = BTreeMap::new();
+    for path in &tracked {
+        let entry = directory_row(&mut directories, path);
+        entry.tracked += 1;
+        if indexed.contains(path) {
+            entry.indexed += 1;
+        } else {
+            entry.missing += 1;
+        }
+    }
+    for path in &untracked {
+        let entry = directory_row(&mut directories, path);
+        entry.indexed += 1;
+        entry.untracked += 1;
+    }
+
+    let mut directories: Vec<CoverageDirectory> = directories
+        .into_values()
+        .filter(|entry| entry.missing > 0 || entry.untracked > 0)
+        .collect();
+    directories.sort_by(|left, right| {
+        (right.missing + right.untracked)
+            .cmp(&(left.missing + left.untracked))
+            .then_with(|| left.directory.cmp(&right.directory))
+    });
+    directories.truncate(MAX_DIRECTORY_ROWS);
+
+    let missing_count = missing.len() as u64;
+    let untracked_count = untracked.len() as u64;
+    missing.truncate(limit);
+    untracked.truncate(limit);
+    let missing_reasons = match &conn {
+        Some(conn) => missing
+            .iter()
+            .filter_map(|path| Some((path.clone(), recorded_skip(conn, path)?.reason)))
+            .collect(),
+        None => BTreeMap::new(),
+    };
+
+    Ok(CoverageReportResponse {
+        root: absolute_root.to_string_lossy().to_string(),
+        database_path: database_path.to_string_lossy().to_string(),
+        database_exists,
+        tracked_file_count: tracked.len() as u64,
+        indexed_file_count: indexed.len() as u64,
+        covered_file_count: tracked.intersection(&indexed).count() as u64,
+        missing_from_index: missing,
+        missing_reasons,
+        missing_count,
+        untracked_in_index: untracked,
+        untracked_count,
+        directories,
+    })
+}
+
+fn directory_row<'a>(
+    directories: &'a mut BTreeMap<String, CoverageDirectory>,
+    path: &str,
+) -> &'a mut CoverageDirectory {
+    let directory = parent_directory(path);
+    directories
+        .entry(directory.clone())
+        .or_insert_with(|| CoverageDirectory {
+            directory,
+            tracked: 0,
+            indexed: 0,
+            missing: 0,
+            untracked: 0,
+        })
+}
+
+fn parent_directory(path: &str) -> String {
+    match path.rfind('/') {
+        Some(index) => path[..index].to_string(),
+        None => ".".to_string(),
+    }
+}
+
+fn load_indexed_paths(conn: &Connection) -> Result<HashSet<String>, rusqlite::Error> {
+    let mut stmt = conn.prepare("SELECT path FROM files")?;
+    let rows = stmt.query_map([], |row| row.get::<_, String>(0))?;
+    rows.collect()
+}
+
+fn resolve_root(root: &str) -> Result<PathBuf, CoverageError> {
+    resolve_workspace_root(root).map_err(|source| CoverageError::InvalidRoot {
+        path: root.to_string(),
+        source,
+    })
+}
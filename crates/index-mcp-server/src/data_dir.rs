@@ -0,0 +1,105 @@
+use std::env;
+use std::fs::OpenOptions;
+use std::io::ErrorKind;
+use std::path::{Path, PathBuf};
+
+use once_cell::sync::Lazy;
+use rmcp::schemars::{self, JsonSchema};
+use rusqlite::{Connection, OpenFlags};
+use serde::Serialize;
+use sha2::{Digest, Sha256};
+
+const DATA_DIR_ENV: &str = "INDEX_MCP_DATA_DIR";
+const DATA_DIR_DATABASES_ENV: &str = "INDEX_MCP_DATA_DIR_DATABASES";
+
+static LAYOUT: Lazy<Option<DataDirLayout>> = Lazy::new(|| {
+    let value = env::var(DATA_DIR_ENV).ok()?;
+    let trimmed = value.trim();
+    if trimmed.is_empty() {
+        return None;
+    }
+    let root = PathBuf::from(trimmed);
+    let root = if root.is_absolute() {
+        root
+    } else {
+        env::current_dir().ok()?.join(root)
+    };
+    let relocate_databases = env::var(DATA_DIR_DATABASES_ENV)
+        .ok()
+        .is_some_and(|value| matches!(value.trim(), "1" | "true" | "yes" | "on"));
+
+    Some(DataDirLayout {
+        model_cache_dir: root.join("models"),
+        log_dir: root.join("logs"),
+        database_dir: relocate_databases.then(|| root.join("databases")),
+        root,
+    })
+});
+
+/// Where state goes when `INDEX_MCP_DATA_DIR` points at a single mountable
+/// directory. Databases stay next to each workspace unless
+/// `INDEX_MCP_DATA_DIR_DATABASES` is also set.
+#[derive(Debug, Clone, Serialize, JsonSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct DataDirLayout {
+    pub root: PathBuf,
+    pub model_cache_dir: PathBuf,
+    pub log_dir: PathBuf,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub database_dir: Option<PathBuf>,
+}
+
+pub(crate) fn layout() -> Option<&'static DataDirLayout> {
+    LAYOUT.as_ref()
+}
+
+/// Directory holding the index databases for `root`: the root itself, or a
+/// per-workspace folder under the data directory named after the root's
+/// basename plus a hash of its full path.
+pub(crate) fn database_dir(root: &Path) -> PathBuf {
+    let Some(databases) = layout().and_then(|layout| layout.database_dir.as_ref()) else {
+        return root.to_path_buf();
+    };
+    let digest = hex::encode(Sha256::digest(root.to_string_lossy().as_bytes()));
+    let name = root
+        .file_name()
+        .map(|name| name.to_string_lossy().to_string())
+        .unwrap_or_else(|| "root".to_string());
+    databases.join(format!("{name}-{}", &digest[..12]))
+}
+
+pub(crate) fn database_path(root: &Path, database_name: &str) -> PathBuf {
+    database_dir(root).join(database_name)
+}
+
+/// Whether the database sits on media the server cannot write: a read-only
+/// mount or a file without write permission. Searches still work; hit
+/// counters and eviction are skipped.
+pub(crate) fn is_read_only_media(database_path: &Path) -> bool {
+    // opening for write without truncating leaves the file untouched
+    match OpenOptions::new().write(true).open(database_path) {
+        Ok(_) => false,
+        Err(error) => matches!(
+            error.kind(),
+            ErrorKind::ReadOnlyFilesystem | ErrorKind::PermissionDenied
+        ),
+    }
+}
+
+/// Opens an index for reading. On read-only media SQLite cannot create the
+/// `-shm` file a WAL database needs, so the file is opened immutable there;
+/// a WAL left uncheckpointed on such media is not visible.
+pub(crate) fn open_read_only(database_path: &Path) -> Result<Connection, rusqlite::Error> {
+    if !is_read_only_media(database_path) {
+        return Connection::open_with_flags(database_path, OpenFlags::SQLITE_OPEN_READ_ONLY);
+    }
+    let path = database_path
+        .to_string_lossy()
+        .replace('%', "%25")
+        .replace('?', "%3f")
+        .replace('#', "%23");
+    Connection::open_with_flags(
+        format!("file:{path}?immutable=1"),
+        OpenFlags::SQLITE_OPEN_READ_ONLY | OpenFlags::SQLITE_OPEN_URI,
+    )
+}
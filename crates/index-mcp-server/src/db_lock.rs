@@ -0,0 +1,314 @@
+use std::fs::{self, OpenOptions};
+use std::io::{ErrorKind, Write};
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+use std::thread::{self, JoinHandle};
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+use serde::{Deserialize, Serialize};
+use thiserror::Error;
+
+const LOCK_SUFFIX: &str = ".lock";
+const HEARTBEAT_INTERVAL: Duration = Duration::from_secs(5);
+// a holder that missed this many heartbeats is presumed dead and its lock
+// may be stolen
+const STALE_AFTER_MS: i64 = 30_000;
+// how long a second writer waits for the holder before giving up
+const ACQUIRE_TIMEOUT: Duration = Duration::from_secs(10);
+const ACQUIRE_POLL: Duration = Duration::from_millis(200);
+
+/// Contents of `<database>.lock`, rewritten by the holder on every heartbeat.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct LockHolder {
+    pub pid: u32,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub host: Option<String>,
+    pub operation: String,
+    /// Unix milliseconds.
+    pub acquired_at: i64,
+    pub heartbeat_at: i64,
+}
+
+#[derive(Debug, Error)]
+pub enum DatabaseLockError {
+    #[error(
+        "database '{database}' is locked by pid {} on {} for {} since {}",
+        holder.pid,
+        holder.host.as_deref().unwrap_or("an unknown host"),
+        holder.operation,
+        holder.acquired_at
+    )]
+    Held {
+        database: String,
+        holder: LockHolder,
+    },
+    #[error("failed to write lock file '{path}': {source}")]
+    Io {
+        path: String,
+        #[source]
+        source: std::io::Error,
+    },
+}
+
+/// Advisory lock held for the duration of a destructive database operation.
+/// Other server instances pointed at the same database wait for it, and
+/// steal it once the heartbeat goes stale. Released on drop.
+pub(crate) struct DatabaseLock {
+    path: PathBuf,
+    holder: LockHolder,
+    stop: Arc<AtomicBool>,
+    heartbeat: Option<JoinHandle<()>>,
+}
+
+impl DatabaseLock {
+    pub(crate) fn acquire(
+        database_path: &Path,
+        operation: &str,
+    ) -> Result<Self, DatabaseLockError> {
+        let mut lock_path = database_path.as_os_str().to_owned();
+        lock_path.push(LOCK_SUFFIX);
+        let path = PathBuf::from(lock_path);
+        let deadline = SystemTime::now() + ACQUIRE_TIMEOUT;
+
+        loop {
+            let now = timestamp_ms();
+            let holder = LockHolder {
+                pid: std::process::id(),
+                host: hostname(),
+                operation: operation.to_string(),
+                acquired_at: now,
+                heartbeat_at: now,
+            };
+            match OpenOptions::new().write(true).create_new(true).open(&path) {
+                Ok(mut file) => {
+                    write_holder(&mut file, &holder).map_err(|source| io_error(&path, source))?;
+                    return Ok(Self::start(path, holder));
+                }
+                Err(error) if error.kind() == ErrorKind::AlreadyExists => {}
+                Err(source) => return Err(io_error(&path, source)),
+            }
+
+            let current = match read_holder(&path) {
+                Some(holder) if now - holder.heartbeat_at <= STALE_AFTER_MS => holder,
+                Some(stale) => {
+                    tracing::warn!(path = %path.display(), "Stealing stale database lock");
+                    // a concurrent stealer may win the create_new race instead
+                    remove_stale_lock(&path, &stale)?;
+                    continue;
+                }
+                // released since create_new failed
+                None => continue,
+            };
+            if SystemTime::now() >= deadline {
+                return Err(DatabaseLockError::Held {
+                    database: database_path.to_string_lossy().to_string(),
+                    holder: current,
+                });
+            }
+            thread::sleep(ACQUIRE_POLL);
+        }
+    }
+
+    fn start(path: PathBuf, holder: LockHolder) -> Self {
+        let stop = Arc::new(AtomicBool::new(false));
+        let heartbeat = {
+            let path = path.clone();
+            let stop = Arc::clone(&stop);
+            let mut holder = holder.clone();
+            thread::Builder::new()
+                .name("db-lock-heartbeat".to_string())
+                .spawn(move || {
+                    while !stop.load(Ordering::Relaxed) {
+                        thread::park_timeout(HEARTBEAT_INTERVAL);
+                        if stop.load(Ordering::Relaxed) || !heartbeat(&path, &mut holder) {
+                            break;
+                        }
+                    }
+                })
+                .ok()
+        };
+        Self {
+            path,
+            holder,
+            stop,
+            heartbeat,
+        }
+    }
+}
+
+impl Drop for DatabaseLock {
+    fn drop(&mut self) {
+        self.stop.store(true, Ordering::Relaxed);
+        if let Some(handle) = self.heartbeat.take() {
+            handle.thread().unpark();
+            let _ = handle.join();
+        }
+        // leave the file alone if another instance stole the lock meanwhile
+        if held_by(&self.path, &self.holder) {
+            let _ = fs::remove_file(&self.path);
+        }
+    }
+}
+
+/// Refreshes the holder's heartbeat. Returns false, leaving the file alone,
+/// once another instance has stolen the lock.
+fn heartbeat(path: &Path, holder: &mut LockHolder) -> bool {
+    if !held_by(path, holder) {
+        tracing::warn!(path = %path.display(), "Database lock was stolen; stopping heartbeat");
+        return false;
+    }
+    holder.heartbeat_at = timestamp_ms();
+    if let Err(error) = replace_holder(path, holder) {
+        tracing::warn!(?error, path = %path.display(), "Database lock heartbeat failed");
+    }
+    true
+}
+
+fn held_by(path: &Path, holder: &LockHolder) -> bool {
+    read_holder(path).is_some_and(|current| {
+        current.pid == holder.pid && current.acquired_at == holder.acquired_at
+    })
+}
+
+/// Rewrites the lock through a temp file and a rename, so readers never see
+/// it half-written.
+fn replace_holder(path: &Path, holder: &LockHolder) -> std::io::Result<()> {
+    let mut staged = path.as_os_str().to_owned();
+    staged.push(format!(".{}.tmp", uuid::Uuid::new_v4()));
+    let staged = PathBuf::from(staged);
+    let result = fs::File::create(&staged)
+        .and_then(|mut file| write_holder(&mut file, holder))
+        .and_then(|()| fs::rename(&staged, path));
+    if result.is_err() {
+        let _ = fs::remove_file(&staged);
+    }
+    result
+}
+
+/// Moves the lock aside and deletes it only if it is still the holder judged
+/// stale. Another waiter may have stolen it first and created a fresh lock
+/// in its place; that one is moved back.
+fn remove_stale_lock(path: &Path, stale: &LockHolder) -> Result<(), DatabaseLockError> {
+    let mut tombstone = path.as_os_str().to_owned();
+    tombstone.push(format!(".{}.stale", uuid::Uuid::new_v4()));
+    let tombstone = PathBuf::from(tombstone);
+    match fs::rename(path, &tombstone) {
+        Ok(()) => {}
+        Err(error) if error.kind() == ErrorKind::NotFound => return Ok(()),
+        Err(source) => return Err(io_error(path, source)),
+    }
+    if read_holder(&tombstone).as_ref() == Some(stale) {
+        let _ = fs::remove_file(&tombstone);
+        return Ok(());
+    }
+    fs::rename(&tombstone, path).map_err(|source| io_error(path, source))
+}
+
+fn read_holder(path: &Path) -> Option<LockHolder> {
+    let contents = fs::read_to_string(path).ok()?;
+    serde_json::from_str(&contents).ok().or_else(|| {
+        // a lock created but not yet written is empty; its mtime is the
+        // heartbeat
+        let modified = fs::metadata(path).and_then(|meta| meta.modified()).ok()?;
+        let modified_ms = modified.duration_since(UNIX_EPOCH).ok()?.as_millis() as i64;
+        Some(LockHolder {
+            pid: 0,
+            host: None,
+            operation: "unknown".to_string(),
+            acquired_at: modified_ms,
+            heartbeat_at: modified_ms,
+        })
+    })
+}
+
+fn write_holder(file: &mut fs::File, holder: &LockHolder) -> std::io::Result<()> {
+    let payload = serde_json::to_vec(holder).map_err(std::io::Error::other)?;
+    file.write_all(&payload)?;
+    file.sync_data()
+}
+
+fn hostname() -> Option<String> {
+    std::env::var("HOSTNAME")
+        .or_else(|_| std::env::var("COMPUTERNAME"))
+        .ok()
+        .filter(|value| !value.trim().is_empty())
+}
+
+fn io_error(path: &Path, source: std::io::Error) -> DatabaseLockError {
+    DatabaseLockError::Io {
+        path: path.to_string_lossy().to_string(),
+        source,
+    }
+}
+
+fn timestamp_ms() -> i64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|duration| duration.as_millis() as i64)
+        .unwrap_or(0)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn holder(pid: u32, heartbeat_at: i64) -> LockHolder {
+        LockHolder {
+            pid,
+            host: None,
+            operation: "ingest_codebase".to_string(),
+            acquired_at: heartbeat_at,
+            heartbeat_at,
+        }
+    }
+
+    fn write_lock(path: &Path, holder: &LockHolder) {
+        let mut file = fs::File::create(path).expect("create lock file");
+        write_holder(&mut file, holder).expect("write lock file");
+    }
+
+    #[test]
+    fn stealing_keeps_a_lock_that_replaced_the_stale_one() {
+        let dir = std::env::temp_dir().join(format!("index-mcp-lock-{}", uuid::Uuid::new_v4()));
+        fs::create_dir_all(&dir).expect("create temp dir");
+        let path = dir.join("index.db.lock");
+        let stale = holder(1, 0);
+
+        // another waiter already stole the stale lock and holds a fresh one
+        let fresh = holder(2, timestamp_ms());
+        write_lock(&path, &fresh);
+        remove_stale_lock(&path, &stale).expect("steal attempt");
+        assert_eq!(read_holder(&path), Some(fresh));
+
+        write_lock(&path, &stale);
+        remove_stale_lock(&path, &stale).expect("steal");
+        assert!(!path.exists());
+        assert_eq!(fs::read_dir(&dir).expect("list temp dir").count(), 0);
+
+        let _ = fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn heartbeat_stops_once_the_lock_is_stolen() {
+        let dir = std::env::temp_dir().join(format!("index-mcp-lock-{}", uuid::Uuid::new_v4()));
+        fs::create_dir_all(&dir).expect("create temp dir");
+        let path = dir.join("index.db.lock");
+        let mut ours = holder(std::process::id(), 0);
+
+        write_lock(&path, &ours);
+        assert!(heartbeat(&path, &mut ours));
+        let current = read_holder(&path).expect("refreshed lock");
+        assert!(current.heartbeat_at > 0);
+        assert_eq!(current, ours);
+        assert_eq!(fs::read_dir(&dir).expect("list temp dir").count(), 1);
+
+        let thief = holder(ours.pid + 1, timestamp_ms());
+        write_lock(&path, &thief);
+        assert!(!heartbeat(&path, &mut ours));
+        assert_eq!(read_holder(&path), Some(thief));
+
+        let _ = fs::remove_dir_all(&dir);
+    }
+}
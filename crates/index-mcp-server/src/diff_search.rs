@@ -0,0 +1,403 @@
+use std::path::PathBuf;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use rmcp::schemars::{self, JsonSchema};
+use rusqlite::{params, Connection, OpenFlags};
+use serde::{Deserialize, Serialize};
+use thiserror::Error;
+
+use crate::data_dir::{database_path, is_read_only_media, open_read_only};
+use crate::index_status::DEFAULT_DB_FILENAME;
+use crate::ingest::{embed_texts, embedding_to_bytes, IngestError};
+use crate::roots::resolve_workspace_root;
+use crate::sandbox::{ensure_root_allowed, SandboxError};
+use crate::search::{blob_to_vec, dot_product};
+
+const DEFAULT_LIMIT: u32 = 10;
+const MAX_LIMIT: u32 = 50;
+// commits embedded per call; the rest are picked up by the next search
+const MAX_COMMITS_PER_PASS: usize = 200;
+const MAX_HUNKS_PER_COMMIT: usize = 100;
+const MAX_HUNK_CHARS: usize = 2_000;
+const PREVIEW_CHARS: usize = 400;
+const EMBED_BATCH_HUNKS: usize = 64;
+
+const DIFF_HUNKS_SQL: &str = "CREATE TABLE IF NOT EXISTS commit_diff_hunks (
+    commit_sha TEXT NOT NULL,
+    embedding_model TEXT NOT NULL,
+    hunk_index INTEGER NOT NULL,
+    path TEXT NOT NULL,
+    header TEXT NOT NULL,
+    content TEXT NOT NULL,
+    embedding BLOB NOT NULL,
+    PRIMARY KEY (commit_sha, embedding_model, hunk_index)
+);
+CREATE TABLE IF NOT EXISTS commit_diff_index (
+    commit_sha TEXT NOT NULL,
+    embedding_model TEXT NOT NULL,
+    subject TEXT NOT NULL,
+    author_date TEXT NOT NULL,
+    hunk_count INTEGER NOT NULL,
+    indexed_at INTEGER NOT NULL,
+    PRIMARY KEY (commit_sha, embedding_model)
+)";
+
+#[derive(Debug, Deserialize, JsonSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct SearchDiffsParams {
+    #[serde(default)]
+    pub root: Option<String>,
+    #[serde(default)]
+    pub database_name: Option<String>,
+    pub query: String,
+    /// Hunks to return (default 10, max 50).
+    #[serde(default)]
+    pub limit: Option<u32>,
+    /// Embedding model name or alias; defaults to the ingest default.
+    #[serde(default)]
+    pub model: Option<String>,
+    /// Only hunks in files under this path.
+    #[serde(default)]
+    pub path_prefix: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize, JsonSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct DiffHunkHit {
+    pub commit_sha: String,
+    pub subject: String,
+    pub author_date: String,
+    pub path: String,
+    /// The `@@ -a,b +c,d @@` line that opens the hunk.
+    pub hunk_header: String,
+    pub score: f32,
+    pub preview: String,
+}
+
+#[derive(Debug, Clone, Serialize, JsonSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct SearchDiffsResponse {
+    pub database_path: String,
+    pub embedding_model: String,
+    /// Commits whose cached diffs were embedded during this call.
+    pub newly_indexed_commits: usize,
+    /// Cached diffs still waiting to be embedded; later calls continue.
+    pub pending_commits: usize,
+    pub searched_hunks: usize,
+    pub hits: Vec<DiffHunkHit>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub recommendation: Option<String>,
+}
+
+#[derive(Debug, Error)]
+pub enum SearchDiffsError {
+    #[error("failed to resolve workspace root '{path}': {source}")]
+    InvalidRoot {
+        path: String,
+        #[source]
+        source: std::io::Error,
+    },
+    #[error("query must not be empty")]
+    EmptyQuery,
+    #[error("SQLite error: {0}")]
+    Sqlite(#[from] rusqlite::Error),
+    #[error("embedding error: {0}")]
+    Embedding(String),
+    #[error(transparent)]
+    Sandbox(#[from] SandboxError),
+    #[error("blocking task panicked: {0}")]
+    Join(#[from] tokio::task::JoinError),
+}
+
+impl From<IngestError> for SearchDiffsError {
+    fn from(error: IngestError) -> Self {
+        match error {
+            IngestError::Embedding(message) => SearchDiffsError::Embedding(message),
+            other => SearchDiffsError::Embedding(other.to_string()),
+        }
+    }
+}
+
+struct DiffHunk {
+    path: String,
+    header: String,
+    content: String,
+}
+
+struct StoredHunk {
+    commit_sha: String,
+    subject: String,
+    author_date: String,
+    path: String,
+    header: String,
+    content: String,
+    embedding: Vec<u8>,
+}
+
+pub async fn search_diffs(
+    params: SearchDiffsParams,
+) -> Result<SearchDiffsResponse, SearchDiffsError> {
+    tokio::task::spawn_blocking(move || perform_search_diffs(params)).await?
+}
+
+fn perform_search_diffs(
+    params: SearchDiffsParams,
+) -> Result<SearchDiffsResponse, SearchDiffsError> {
+    let query = params.query.trim().to_string();
+    if query.is_empty() {
+        return Err(SearchDiffsError::EmptyQuery);
+    }
+    let root = params.root.unwrap_or_else(|| "./".to_string());
+    let root_path = resolve_root(&root)?;
+    ensure_root_allowed(&root_path)?;
+    let database_path = database_path(
+        &root_path,
+        &params
+            .database_name
+            .unwrap_or_else(|| DEFAULT_DB_FILENAME.to_string()),
+    );
+    let limit = params.limit.unwrap_or(DEFAULT_LIMIT).clamp(1, MAX_LIMIT) as usize;
+
+    let (embedding_model, mut query_vectors) = embed_texts(params.model, vec![query])?;
+    let query_vector = query_vectors.pop().unwrap_or_default();
+
+    let mut response = SearchDiffsResponse {
+        database_path: database_path.to_string_lossy().to_string(),
+        embedding_model: embedding_model.clone(),
+        newly_indexed_commits: 0,
+        pending_commits: 0,
+        searched_hunks: 0,
+        hits: Vec::new(),
+        recommendation: None,
+    };
+    if !database_path.is_file() {
+        response.recommendation = Some(NO_DIFFS_RECOMMENDATION.to_string());
+        return Ok(response);
+    }
+
+    if !is_read_only_media(&database_path) {
+        let mut conn =
+            Connection::open_with_flags(&database_path, OpenFlags::SQLITE_OPEN_READ_WRITE)?;
+        if has_timeline_table(&conn)? {
+            conn.execute_batch(DIFF_HUNKS_SQL)?;
+            let (indexed, pending) = index_pending_diffs(&mut conn, &embedding_model)?;
+            response.newly_indexed_commits = indexed;
+            response.pending_commits = pending;
+        }
+    }
+
+    let conn = open_read_only(&database_path)?;
+    let hunks = load_hunks(&conn, &embedding_model, params.path_prefix.as_deref())?;
+    response.searched_hunks = hunks.len();
+    let mut scored: Vec<(f32, StoredHunk)> = hunks
+        .into_iter()
+        .map(|hunk| {
+            let score = dot_product(&query_vector, &blob_to_vec(&hunk.embedding));
+            (score, hunk)
+        })
+        .filter(|(score, _)| score.is_finite())
+        .collect();
+    scored.sort_by(|left, right| right.0.total_cmp(&left.0));
+    response.hits = scored
+        .into_iter()
+        .take(limit)
+        .map(|(score, hunk)| DiffHunkHit {
+            commit_sha: hunk.commit_sha,
+            subject: hunk.subject,
+            author_date: hunk.author_date,
+            path: hunk.path,
+            hunk_header: hunk.header,
+            score,
+            preview: truncate_chars(&hunk.content, PREVIEW_CHARS),
+        })
+        .collect();
+
+    if response.searched_hunks == 0 {
+        response.recommendation = Some(NO_DIFFS_RECOMMENDATION.to_string());
+    } else if response.pending_commits > 0 {
+        response.recommendation = Some(format!(
+            "{} cached commit diff(s) are not embedded yet; repeat the search to cover them.",
+            response.pending_commits
+        ));
+    }
+    Ok(response)
+}
+
+const NO_DIFFS_RECOMMENDATION: &str =
+    "No cached diffs to search. Run repository_timeline with includeDiffs: true to cache recent commit diffs first.";
+
+fn has_timeline_table(conn: &Connection) -> Result<bool, rusqlite::Error> {
+    conn.query_row(
+        "SELECT EXISTS(SELECT 1 FROM sqlite_master WHERE type = 'table' AND name = 'repository_timeline_entries')",
+        [],
+        |row| row.get(0),
+    )
+}
+
+/// Embeds cached diffs not yet indexed for `model`, oldest capture first,
+/// up to `MAX_COMMITS_PER_PASS` commits. Returns (indexed, still pending).
+fn index_pending_diffs(
+    conn: &mut Connection,
+    model: &str,
+) -> Result<(usize, usize), SearchDiffsError> {
+    let pending: Vec<(String, String, String)> = {
+        let mut stmt = conn.prepare(
+            "SELECT commit_sha, MAX(payload), MAX(diff) FROM repository_timeline_entries
+             WHERE diff IS NOT NULL
+               AND commit_sha NOT IN (SELECT commit_sha FROM commit_diff_index WHERE embedding_model = ?1)
+             GROUP BY commit_sha
+             ORDER BY MIN(captured_at)",
+        )?;
+        let rows = stmt.query_map(params![model], |row| {
+            Ok((row.get(0)?, row.get(1)?, row.get(2)?))
+        })?;
+        rows.collect::<Result<_, _>>()?
+    };
+    let remaining = pending.len().saturating_sub(MAX_COMMITS_PER_PASS);
+
+    let transaction = conn.transaction()?;
+    let now = timestamp_ms();
+    let mut indexed = 0usize;
+    for (commit_sha, payload, diff) in pending.into_iter().take(MAX_COMMITS_PER_PASS) {
+        let entry: serde_json::Value = serde_json::from_str(&payload).unwrap_or_default();
+        let subject = entry["subject"].as_str().unwrap_or_default().to_string();
+        let author_date = entry["author_date"]
+            .as_str()
+            .unwrap_or_default()
+            .to_string();
+
+        let hunks = split_hunks(&diff);
+        for (batch_index, batch) in hunks.chunks(EMBED_BATCH_HUNKS).enumerate() {
+            let texts = batch
+                .iter()
+                .map(|hunk| format!("{}\n{}\n{}", hunk.path, hunk.header, hunk.content))
+                .collect();
+            let (_, vectors) = embed_texts(Some(model.to_string()), texts)?;
+            for (offset, (hunk, vector)) in batch.iter().zip(vectors).enumerate() {
+                transaction.execute(
+                    "INSERT OR REPLACE INTO commit_diff_hunks
+                         (commit_sha, embedding_model, hunk_index, path, header, content, embedding)
+                     VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7)",
+                    params![
+                        commit_sha,
+                        model,
+                        (batch_index * EMBED_BATCH_HUNKS + offset) as i64,
+                        hunk.path,
+                        hunk.header,
+                        hunk.content,
+                        embedding_to_bytes(&vector)
+                    ],
+                )?;
+            }
+        }
+        transaction.execute(
+            "INSERT OR REPLACE INTO commit_diff_index
+                 (commit_sha, embedding_model, subject, author_date, hunk_count, indexed_at)
+             VALUES (?1, ?2, ?3, ?4, ?5, ?6)",
+            params![
+                commit_sha,
+                model,
+                subject,
+                author_date,
+                hunks.len() as i64,
+                now
+            ],
+        )?;
+        indexed += 1;
+    }
+    transaction.commit()?;
+    Ok((indexed, remaining))
+}
+
+fn load_hunks(
+    conn: &Connection,
+    model: &str,
+    path_prefix: Option<&str>,
+) -> Result<Vec<StoredHunk>, SearchDiffsError> {
+    let table_exists: bool = conn.query_row(
+        "SELECT EXISTS(SELECT 1 FROM sqlite_master WHERE type = 'table' AND name = 'commit_diff_hunks')",
+        [],
+        |row| row.get(0),
+    )?;
+    if !table_exists {
+        return Ok(Vec::new());
+    }
+    let prefix = path_prefix
+        .map(|prefix| prefix.trim_start_matches("./").to_string())
+        .unwrap_or_default();
+    let mut stmt = conn.prepare(
+        "SELECT h.commit_sha, i.subject, i.author_date, h.path, h.header, h.content, h.embedding
+         FROM commit_diff_hunks h
+         JOIN commit_diff_index i
+           ON i.commit_sha = h.commit_sha AND i.embedding_model = h.embedding_model
+         WHERE h.embedding_model = ?1 AND substr(h.path, 1, length(?2)) = ?2",
+    )?;
+    let rows = stmt.query_map(params![model, prefix], |row| {
+        Ok(StoredHunk {
+            commit_sha: row.get(0)?,
+            subject: row.get(1)?,
+            author_date: row.get(2)?,
+            path: row.get(3)?,
+            header: row.get(4)?,
+            content: row.get(5)?,
+            embedding: row.get(6)?,
+        })
+    })?;
+    Ok(rows.collect::<Result<_, _>>()?)
+}
+
+/// Splits a `git log --patch` diff into hunks, keeping only changed lines so
+/// the embedding reflects what the commit did rather than its context.
+fn split_hunks(diff: &str) -> Vec<DiffHunk> {
+    let mut hunks: Vec<DiffHunk> = Vec::new();
+    let mut path = String::new();
+    let mut current: Option<DiffHunk> = None;
+    for line in diff.lines() {
+        if let Some(rest) = line.strip_prefix("diff --git ") {
+            hunks.extend(current.take());
+            path = rest
+                .rsplit_once(" b/")
+                .map(|(_, target)| target.to_string())
+                .unwrap_or_else(|| rest.to_string());
+        } else if line.starts_with("@@") {
+            hunks.extend(current.take());
+            current = Some(DiffHunk {
+                path: path.clone(),
+                header: line.to_string(),
+                content: String::new(),
+            });
+        } else if let Some(hunk) = current.as_mut() {
+            let changed = (line.starts_with('+') && !line.starts_with("+++"))
+                || (line.starts_with('-') && !line.starts_with("---"));
+            if changed && hunk.content.len() < MAX_HUNK_CHARS {
+                hunk.content.push_str(line);
+                hunk.content.push('\n');
+            }
+        }
+    }
+    hunks.extend(current);
+    hunks.retain(|hunk| !hunk.content.trim().is_empty());
+    hunks.truncate(MAX_HUNKS_PER_COMMIT);
+    hunks
+}
+
+fn truncate_chars(text: &str, max_chars: usize) -> String {
+    match text.char_indices().nth(max_chars) {
+        Some((index, _)) => format!("{}…", &text[..index]),
+        None => text.to_string(),
+    }
+}
+
+fn resolve_root(root: &str) -> Result<PathBuf, SearchDiffsError> {
+    resolve_workspace_root(root).map_err(|source| SearchDiffsError::InvalidRoot {
+        path: root.to_string(),
+        source,
+    })
+}
+
+fn timestamp_ms() -> i64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|duration| duration.as_millis() as i64)
+        .unwrap_or(0)
+}
@@ -0,0 +1,309 @@
+use std::str::FromStr;
+use std::time::Duration;
+
+use fastembed::{EmbeddingModel, TextEmbedding, TextInitOptions};
+use rusqlite::{params, Connection};
+use serde_json::{json, Value};
+
+use crate::data_dir::layout;
+
+/// Model name recorded for vectors from the hash-based mock backend.
+pub(crate) const MOCK_EMBEDDING_MODEL: &str = "index-mcp/mock-hash";
+pub(crate) const MOCK_EMBEDDING_DIMENSIONS: usize = 384;
+
+/// Recorded model names of HTTP backends carry the provider as a prefix, so
+/// search reaches the same service the chunks were embedded with.
+pub(crate) const OPENAI_MODEL_PREFIX: &str = "openai:";
+pub(crate) const OLLAMA_MODEL_PREFIX: &str = "ollama:";
+/// Meta key holding the endpoint an HTTP backend was last used with.
+pub(crate) const META_EMBEDDING_ENDPOINT: &str = "embedding_endpoint";
+
+const DEFAULT_OPENAI_BASE_URL: &str = "https://api.openai.com/v1";
+const DEFAULT_OLLAMA_URL: &str = "http://localhost:11434";
+const HTTP_EMBED_TIMEOUT: Duration = Duration::from_secs(120);
+
+/// A loaded embedding backend. `Mock` needs no model download or ONNX
+/// runtime, so tests can run ingest and search offline. `Http` sends texts to
+/// an OpenAI-compatible or Ollama server instead of running ONNX locally.
+pub(crate) enum Embedder {
+    Model(Box<TextEmbedding>),
+    Mock,
+    Http(HttpEmbedder),
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum HttpApi {
+    OpenAi,
+    Ollama,
+}
+
+impl HttpApi {
+    fn default_endpoint(self) -> String {
+        let (variable, fallback) = match self {
+            Self::OpenAi => ("INDEX_MCP_OPENAI_BASE_URL", DEFAULT_OPENAI_BASE_URL),
+            Self::Ollama => ("INDEX_MCP_OLLAMA_URL", DEFAULT_OLLAMA_URL),
+        };
+        std::env::var(variable)
+            .ok()
+            .map(|value| value.trim().to_string())
+            .filter(|value| !value.is_empty())
+            .unwrap_or_else(|| fallback.to_string())
+    }
+}
+
+pub(crate) struct HttpEmbedder {
+    client: reqwest::blocking::Client,
+    api: HttpApi,
+    url: String,
+    model: String,
+    api_key: Option<String>,
+}
+
+impl HttpEmbedder {
+    fn embed(&self, texts: &[String]) -> Result<Vec<Vec<f32>>, String> {
+        let mut request = self
+            .client
+            .post(&self.url)
+            .json(&json!({ "model": self.model, "input": texts }));
+        if let Some(key) = &self.api_key {
+            request = request.bearer_auth(key);
+        }
+        let response = request
+            .send()
+            .map_err(|error| format!("Embedding request to {} failed: {error}", self.url))?;
+        let status = response.status();
+        let body: Value = response
+            .json()
+            .map_err(|error| format!("Unreadable embedding response from {}: {error}", self.url))?;
+        if !status.is_success() {
+            let message = body
+                .pointer("/error/message")
+                .or_else(|| body.get("error"))
+                .and_then(Value::as_str)
+                .unwrap_or("no error message");
+            return Err(format!(
+                "Embedding endpoint {} returned {status}: {message}",
+                self.url
+            ));
+        }
+
+        let vectors: Option<Vec<Vec<f32>>> = match self.api {
+            // entries carry their input position; do not rely on order
+            HttpApi::OpenAi => body.get("data").and_then(Value::as_array).map(|data| {
+                let mut entries: Vec<(u64, Vec<f32>)> = data
+                    .iter()
+                    .map(|entry| {
+                        (
+                            entry.get("index").and_then(Value::as_u64).unwrap_or(0),
+                            json_vector(entry.get("embedding")),
+                        )
+                    })
+                    .collect();
+                entries.sort_by_key(|(index, _)| *index);
+                entries.into_iter().map(|(_, vector)| vector).collect()
+            }),
+            HttpApi::Ollama => body
+                .get("embeddings")
+                .and_then(Value::as_array)
+                .map(|rows| rows.iter().map(|row| json_vector(Some(row))).collect()),
+        };
+        let mut vectors = vectors
+            .filter(|vectors| {
+                vectors.len() == texts.len() && vectors.iter().all(|vector| !vector.is_empty())
+            })
+            .ok_or_else(|| {
+                format!(
+                    "Embedding endpoint {} did not return one vector per input",
+                    self.url
+                )
+            })?;
+        // search scores by dot product, which assumes unit vectors
+        for vector in &mut vectors {
+            let norm = vector.iter().map(|value| value * value).sum::<f32>().sqrt();
+            if norm > 0.0 {
+                for value in vector.iter_mut() {
+                    *value /= norm;
+                }
+            }
+        }
+        Ok(vectors)
+    }
+}
+
+impl Embedder {
+    pub(crate) fn embed(
+        &mut self,
+        texts: Vec<String>,
+        batch_size: Option<usize>,
+    ) -> Result<Vec<Vec<f32>>, String> {
+        match self {
+            Self::Model(model) => model
+                .embed(texts, batch_size)
+                .map_err(|error| error.to_string()),
+            Self::Mock => Ok(texts.iter().map(|text| mock_embedding(text)).collect()),
+            Self::Http(embedder) => {
+                let batch_size = batch_size.unwrap_or(texts.len()).max(1);
+                let mut vectors = Vec::with_capacity(texts.len());
+                for batch in texts.chunks(batch_size) {
+                    vectors.extend(embedder.embed(batch)?);
+                }
+                Ok(vectors)
+            }
+        }
+    }
+}
+
+pub(crate) fn is_mock_model(model: &str) -> bool {
+    model.trim().eq_ignore_ascii_case(MOCK_EMBEDDING_MODEL)
+}
+
+/// The HTTP backend a recorded model name belongs to, and the name the
+/// service knows the model by.
+pub(crate) fn http_backend(model: &str) -> Option<(HttpApi, &str)> {
+    let name = model.trim();
+    if let Some(rest) = name.strip_prefix(OPENAI_MODEL_PREFIX) {
+        return Some((HttpApi::OpenAi, rest));
+    }
+    name.strip_prefix(OLLAMA_MODEL_PREFIX)
+        .map(|rest| (HttpApi::Ollama, rest))
+}
+
+/// The endpoint recorded by the last ingest through an HTTP backend.
+pub(crate) fn load_embedding_endpoint(conn: &Connection) -> Option<String> {
+    conn.query_row(
+        "SELECT value FROM meta WHERE key = ?1",
+        params![META_EMBEDDING_ENDPOINT],
+        |row| row.get::<_, String>(0),
+    )
+    .ok()
+}
+
+/// `endpoint` is the base URL of an HTTP backend; without one the
+/// provider's environment variable or public default is used.
+pub(crate) fn load_embedder(model: &str, endpoint: Option<&str>) -> Result<Embedder, String> {
+    let name = model.trim();
+    if is_mock_model(name) {
+        return Ok(Embedder::Mock);
+    }
+    if let Some((api, remote_model)) = http_backend(name) {
+        return load_http_embedder(api, remote_model, endpoint);
+    }
+    let parsed = EmbeddingModel::from_str(name)
+        .map_err(|error| format!("Unknown embedding model '{name}': {error}"))?;
+    let mut options = TextInitOptions::new(parsed).with_show_download_progress(false);
+    if let Some(layout) = layout() {
+        options = options.with_cache_dir(layout.model_cache_dir.clone());
+    }
+    TextEmbedding::try_new(options)
+        .map(|model| Embedder::Model(Box::new(model)))
+        .map_err(|error| error.to_string())
+}
+
+fn load_http_embedder(
+    api: HttpApi,
+    model: &str,
+    endpoint: Option<&str>,
+) -> Result<Embedder, String> {
+    if model.is_empty() {
+        return Err("HTTP embedding backends need a model name".to_string());
+    }
+    let base = endpoint
+        .map(str::to_string)
+        .unwrap_or_else(|| api.default_endpoint());
+    let base = base.trim_end_matches('/');
+    let (url, api_key) = match api {
+        HttpApi::OpenAi => (
+            format!("{base}/embeddings"),
+            ["INDEX_MCP_OPENAI_API_KEY", "OPENAI_API_KEY"]
+                .iter()
+                .find_map(|variable| std::env::var(variable).ok())
+                .filter(|key| !key.trim().is_empty()),
+        ),
+        HttpApi::Ollama => (format!("{base}/api/embed"), None),
+    };
+    let client = reqwest::blocking::Client::builder()
+        .timeout(HTTP_EMBED_TIMEOUT)
+        .build()
+        .map_err(|error| format!("Failed to build HTTP client: {error}"))?;
+    Ok(Embedder::Http(HttpEmbedder {
+        client,
+        api,
+        url,
+        model: model.to_string(),
+        api_key,
+    }))
+}
+
+fn json_vector(value: Option<&Value>) -> Vec<f32> {
+    value
+        .and_then(Value::as_array)
+        .map(|values| {
+            values
+                .iter()
+                .filter_map(Value::as_f64)
+                .map(|value| value as f32)
+                .collect()
+        })
+        .unwrap_or_default()
+}
+
+/// Feature-hashes the text's lowercased identifier parts (`parseConfig` and
+/// `parse_config` both give `parse`, `config`) into a unit vector, so texts
+/// sharing words score higher against each other. Deterministic across runs
+/// and platforms; it measures word overlap, not meaning.
+pub(crate) fn mock_embedding(text: &str) -> Vec<f32> {
+    let mut vector = vec![0.0f32; MOCK_EMBEDDING_DIMENSIONS];
+    let mut any = false;
+    for word in identifier_parts(text) {
+        let hash = fnv1a(word.as_bytes());
+        let sign = if hash >> 63 == 1 { -1.0 } else { 1.0 };
+        vector[(hash % MOCK_EMBEDDING_DIMENSIONS as u64) as usize] += sign;
+        any = true;
+    }
+    if !any {
+        // punctuation-only text still gets a stable, non-zero vector
+        vector[(fnv1a(text.as_bytes()) % MOCK_EMBEDDING_DIMENSIONS as u64) as usize] = 1.0;
+    }
+    let norm = vector.iter().map(|value| value * value).sum::<f32>().sqrt();
+    for value in &mut vector {
+        *value /= norm;
+    }
+    vector
+}
+
+fn identifier_parts(text: &str) -> Vec<String> {
+    let mut parts = Vec::new();
+    let mut current = String::new();
+    let mut previous_lower = false;
+    for ch in text.chars() {
+        if !ch.is_alphanumeric() {
+            push_part(&mut parts, &mut current);
+            previous_lower = false;
+            continue;
+        }
+        if ch.is_uppercase() && previous_lower {
+            push_part(&mut parts, &mut current);
+        }
+        previous_lower = ch.is_lowercase() || ch.is_numeric();
+        current.extend(ch.to_lowercase());
+    }
+    push_part(&mut parts, &mut current);
+    parts
+}
+
+fn push_part(parts: &mut Vec<String>, current: &mut String) {
+    if current.chars().count() > 1 {
+        parts.push(std::mem::take(current));
+    } else {
+        current.clear();
+    }
+}
+
+fn fnv1a(bytes: &[u8]) -> u64 {
+    let mut hash: u64 = 0xcbf2_9ce4_8422_2325;
+    for byte in bytes {
+        hash ^= u64::from(*byte);
+        hash = hash.wrapping_mul(0x0000_0100_0000_01b3);
+    }
+    hash
+}
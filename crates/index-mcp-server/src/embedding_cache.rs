@@ -0,0 +1,127 @@
+use std::collections::HashMap;
+
+use rusqlite::{params, Connection};
+use sha2::{Digest, Sha256};
+
+use crate::embedding_store::{decode_embedding, encode_embedding, EmbeddingEncoding};
+
+// the live chunks of a model already hold their vectors, so the cache keeps
+// one row per this many of them (plus a floor for small indexes); older
+// entries beyond that are dropped least recently used first
+const LIVE_CHUNKS_PER_CACHE_ROW: i64 = 4;
+const MIN_CACHE_ROWS: i64 = 256;
+
+/// Vectors by model and embedded text, so a chunk whose text was embedded
+/// before (in any file, by any earlier ingest) skips the model.
+pub(crate) fn ensure_embedding_cache_schema(conn: &Connection) -> Result<(), rusqlite::Error> {
+    conn.execute_batch(
+        r#"
+        CREATE TABLE IF NOT EXISTS embedding_cache (
+            embedding_model TEXT NOT NULL,
+            content_hash TEXT NOT NULL,
+            embedding BLOB NOT NULL,
+            embedding_encoding TEXT,
+            last_used_at INTEGER NOT NULL,
+            PRIMARY KEY (embedding_model, content_hash)
+        );
+        CREATE INDEX IF NOT EXISTS idx_embedding_cache_last_used
+            ON embedding_cache(embedding_model, last_used_at);
+        "#,
+    )
+}
+
+/// Key for the text actually handed to the model, after normalization.
+pub(crate) fn embedding_cache_key(text: &str) -> String {
+    hex::encode(Sha256::digest(text.as_bytes()))
+}
+
+/// Cached vectors for `keys`, marking each hit as used at `now_ms`.
+pub(crate) fn load_cached_embeddings(
+    conn: &Connection,
+    model: &str,
+    keys: &[&str],
+    now_ms: i64,
+) -> Result<HashMap<String, Vec<f32>>, rusqlite::Error> {
+    let mut select = conn.prepare_cached(
+        "SELECT embedding, embedding_encoding FROM embedding_cache
+         WHERE embedding_model = ?1 AND content_hash = ?2",
+    )?;
+    let mut touch = conn.prepare_cached(
+        "UPDATE embedding_cache SET last_used_at = ?3
+         WHERE embedding_model = ?1 AND content_hash = ?2",
+    )?;
+    let mut found = HashMap::new();
+    for key in keys {
+        if found.contains_key(*key) {
+            continue;
+        }
+        let mut rows = select.query(params![model, key])?;
+        let Some(row) = rows.next()? else {
+            continue;
+        };
+        let blob: Vec<u8> = row.get(0)?;
+        let encoding: Option<String> = row.get(1)?;
+        let vector = decode_embedding(&blob, encoding.as_deref());
+        // an undecodable entry is a miss; storing the fresh vector replaces it
+        if vector.is_empty() {
+            continue;
+        }
+        touch.execute(params![model, key, now_ms])?;
+        found.insert(key.to_string(), vector);
+    }
+    Ok(found)
+}
+
+pub(crate) fn store_cached_embeddings(
+    conn: &Connection,
+    model: &str,
+    entries: &[(&str, &[f32])],
+    encoding: EmbeddingEncoding,
+    now_ms: i64,
+) -> Result<(), rusqlite::Error> {
+    let mut insert = conn.prepare_cached(
+        "INSERT OR REPLACE INTO embedding_cache
+            (embedding_model, content_hash, embedding, embedding_encoding, last_used_at)
+         VALUES (?1, ?2, ?3, ?4, ?5)",
+    )?;
+    for (key, vector) in entries {
+        let (blob, stored_encoding) = encode_embedding(vector, encoding);
+        insert.execute(params![model, key, blob, stored_encoding, now_ms])?;
+    }
+    Ok(())
+}
+
+/// Bounds the cache to a fraction of the model's live chunk count and drops
+/// entries for models no chunk uses any more.
+pub(crate) fn prune_embedding_cache(conn: &Connection, model: &str) -> Result<(), rusqlite::Error> {
+    conn.execute(
+        "DELETE FROM embedding_cache
+         WHERE embedding_model NOT IN (SELECT DISTINCT embedding_model FROM file_chunks)",
+        [],
+    )?;
+    let live_chunks: i64 = conn.query_row(
+        "SELECT COUNT(*) FROM file_chunks WHERE embedding_model = ?1",
+        params![model],
+        |row| row.get(0),
+    )?;
+    conn.execute(
+        "DELETE FROM embedding_cache
+         WHERE embedding_model = ?1 AND content_hash NOT IN (
+             SELECT content_hash FROM embedding_cache
+             WHERE embedding_model = ?1
+             ORDER BY last_used_at DESC
+             LIMIT ?2
+         )",
+        params![
+            model,
+            (live_chunks / LIVE_CHUNKS_PER_CACHE_ROW).max(MIN_CACHE_ROWS)
+        ],
+    )?;
+    Ok(())
+}
+
+/// Empties the cache, returning the rows removed. Eviction runs this before
+/// touching chunks: cached vectors only save model calls.
+pub(crate) fn clear_embedding_cache(conn: &Connection) -> Result<usize, rusqlite::Error> {
+    conn.execute("DELETE FROM embedding_cache", [])
+}
@@ -0,0 +1,407 @@
+use std::fs;
+use std::path::PathBuf;
+
+use rmcp::schemars::{self, JsonSchema};
+use rusqlite::{params, Connection, OpenFlags};
+use serde::{Deserialize, Serialize};
+use thiserror::Error;
+
+use crate::content_store::{content_encoding_column, StoredContentCache};
+use crate::data_dir::{database_path, is_read_only_media};
+use crate::db_lock::{DatabaseLock, DatabaseLockError};
+use crate::index_status::DEFAULT_DB_FILENAME;
+use crate::ingest::embedding_to_bytes;
+use crate::integrity::{chunk_checksum, ensure_checksum_schema};
+use crate::roots::resolve_workspace_root;
+use crate::sandbox::{ensure_root_allowed, SandboxError};
+
+const F16_ENCODING: &str = "f16";
+const ZSTD_ENCODING: &str = "zstd";
+const ZSTD_LEVEL: i32 = 3;
+// rows rewritten per batch, so a large index is never held in memory at once
+const RECODE_BATCH: i64 = 512;
+
+/// How `file_chunks.embedding` stores a vector. Rows record their own
+/// encoding, so a database may mix them and readers decode per row.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize, JsonSchema)]
+#[serde(rename_all = "lowercase")]
+pub enum EmbeddingEncoding {
+    /// Little-endian f32, four bytes per dimension.
+    #[default]
+    F32,
+    /// Half precision, two bytes per dimension. Lossy, but the rounding
+    /// (about three significant digits) rarely reorders search results.
+    F16,
+    /// Lossless: the f32 bytes are shuffled into byte planes (all sign and
+    /// exponent bytes together) and zstd-compressed.
+    Zstd,
+}
+
+impl EmbeddingEncoding {
+    fn stored(self) -> Option<&'static str> {
+        match self {
+            EmbeddingEncoding::F32 => None,
+            EmbeddingEncoding::F16 => Some(F16_ENCODING),
+            EmbeddingEncoding::Zstd => Some(ZSTD_ENCODING),
+        }
+    }
+
+    /// Expected blob size for an estimate; zstd's ratio is typical for
+    /// normalized embeddings, not guaranteed.
+    pub(crate) fn estimated_bytes(self, dimensions: usize) -> u64 {
+        let dimensions = dimensions as u64;
+        match self {
+            EmbeddingEncoding::F32 => dimensions * 4,
+            EmbeddingEncoding::F16 => dimensions * 2,
+            EmbeddingEncoding::Zstd => dimensions * 7 / 2,
+        }
+    }
+}
+
+pub(crate) fn ensure_embedding_schema(conn: &Connection) -> Result<(), rusqlite::Error> {
+    if !has_embedding_encoding(conn)? {
+        conn.execute_batch("ALTER TABLE file_chunks ADD COLUMN embedding_encoding TEXT")?;
+    }
+    Ok(())
+}
+
+/// Column expression for readers that may open databases written before
+/// embedding encodings existed.
+pub(crate) fn embedding_encoding_column(conn: &Connection) -> &'static str {
+    match has_embedding_encoding(conn) {
+        Ok(true) => "embedding_encoding",
+        _ => "NULL",
+    }
+}
+
+fn has_embedding_encoding(conn: &Connection) -> Result<bool, rusqlite::Error> {
+    let mut stmt = conn.prepare("PRAGMA table_info(file_chunks)")?;
+    let names = stmt.query_map([], |row| row.get::<_, String>(1))?;
+    for name in names {
+        if name? == "embedding_encoding" {
+            return Ok(true);
+        }
+    }
+    Ok(false)
+}
+
+/// The blob and the `embedding_encoding` value to store with it. A vector
+/// that fails to compress is stored as plain f32.
+pub(crate) fn encode_embedding(
+    vector: &[f32],
+    encoding: EmbeddingEncoding,
+) -> (Vec<u8>, Option<&'static str>) {
+    match encoding {
+        EmbeddingEncoding::F32 => (embedding_to_bytes(vector), None),
+        EmbeddingEncoding::F16 => {
+            let mut bytes = Vec::with_capacity(vector.len() * 2);
+            for value in vector {
+                bytes.extend_from_slice(&f32_to_f16(*value).to_le_bytes());
+            }
+            (bytes, encoding.stored())
+        }
+        EmbeddingEncoding::Zstd => {
+            let plain = embedding_to_bytes(vector);
+            let mut shuffled = Vec::with_capacity(plain.len());
+            for plane in 0..4 {
+                shuffled.extend(plain.iter().skip(plane).step_by(4));
+            }
+            match zstd::bulk::compress(&shuffled, ZSTD_LEVEL) {
+                Ok(bytes) => (bytes, encoding.stored()),
+                Err(_) => (plain, None),
+            }
+        }
+    }
+}
+
+/// Empty when the blob cannot be decoded, which callers treat like a
+/// truncated f32 blob.
+pub(crate) fn decode_embedding(blob: &[u8], encoding: Option<&str>) -> Vec<f32> {
+    match encoding {
+        None => f32_values(blob),
+        Some(F16_ENCODING) if blob.len().is_multiple_of(2) => blob
+            .chunks_exact(2)
+            .map(|half| f16_to_f32(u16::from_le_bytes([half[0], half[1]])))
+            .collect(),
+        Some(ZSTD_ENCODING) => {
+            let Ok(shuffled) = zstd::stream::decode_all(blob) else {
+                return Vec::new();
+            };
+            if !shuffled.len().is_multiple_of(4) {
+                return Vec::new();
+            }
+            let count = shuffled.len() / 4;
+            let mut plain = vec![0u8; shuffled.len()];
+            for (offset, byte) in shuffled.iter().enumerate() {
+                plain[(offset % count) * 4 + offset / count] = *byte;
+            }
+            f32_values(&plain)
+        }
+        _ => Vec::new(),
+    }
+}
+
+fn f32_values(blob: &[u8]) -> Vec<f32> {
+    if !blob.len().is_multiple_of(4) {
+        return Vec::new();
+    }
+    blob.chunks_exact(4)
+        .map(|value| f32::from_le_bytes([value[0], value[1], value[2], value[3]]))
+        .collect()
+}
+
+/// Round-to-nearest-even; out-of-range values saturate to infinity.
+fn f32_to_f16(value: f32) -> u16 {
+    let bits = value.to_bits();
+    let sign = ((bits >> 16) & 0x8000) as u16;
+    let exponent = ((bits >> 23) & 0xff) as i32;
+    let mantissa = bits & 0x007f_ffff;
+    if exponent == 0xff {
+        let nan = if mantissa == 0 { 0 } else { 0x0200 };
+        return sign | 0x7c00 | nan;
+    }
+    let half_exponent = exponent - 127 + 15;
+    if half_exponent >= 0x1f {
+        return sign | 0x7c00;
+    }
+    if half_exponent <= 0 {
+        if half_exponent < -10 {
+            return sign;
+        }
+        let mantissa = mantissa | 0x0080_0000;
+        let shift = (14 - half_exponent) as u32;
+        return sign | round_shifted(mantissa, shift) as u16;
+    }
+    // a mantissa that rounds up carries into the exponent, as it should
+    let unrounded = ((half_exponent as u32) << 23) | mantissa;
+    sign | round_shifted(unrounded, 13) as u16
+}
+
+fn round_shifted(value: u32, shift: u32) -> u32 {
+    let halfway = 1 << (shift - 1);
+    let remainder = value & ((1 << shift) - 1);
+    let shifted = value >> shift;
+    if remainder > halfway || (remainder == halfway && shifted & 1 == 1) {
+        shifted + 1
+    } else {
+        shifted
+    }
+}
+
+fn f16_to_f32(half: u16) -> f32 {
+    let negative = half & 0x8000 != 0;
+    let exponent = u32::from((half >> 10) & 0x1f);
+    let mantissa = u32::from(half & 0x03ff);
+    let sign = if negative { 0x8000_0000 } else { 0 };
+    match exponent {
+        0 => {
+            let magnitude = mantissa as f32 * 2f32.powi(-24);
+            if negative {
+                -magnitude
+            } else {
+                magnitude
+            }
+        }
+        0x1f => f32::from_bits(sign | 0x7f80_0000 | (mantissa << 13)),
+        _ => f32::from_bits(sign | ((exponent + 127 - 15) << 23) | (mantissa << 13)),
+    }
+}
+
+#[derive(Debug, Deserialize, JsonSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct RecodeEmbeddingsParams {
+    #[serde(default)]
+    pub root: Option<String>,
+    #[serde(default)]
+    pub database_name: Option<String>,
+    /// Target encoding for every stored chunk embedding.
+    pub encoding: EmbeddingEncoding,
+}
+
+#[derive(Debug, Clone, Serialize, JsonSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct RecodeEmbeddingsResponse {
+    pub database_path: String,
+    pub encoding: EmbeddingEncoding,
+    pub recoded_chunks: usize,
+    /// Chunks already in the target encoding.
+    pub unchanged_chunks: usize,
+    /// Chunks left as they were because their blob does not decode or no
+    /// longer matches its checksum; `verify_index` reports them.
+    pub skipped_chunks: usize,
+    /// Embedding bytes of the recoded chunks before and after.
+    pub bytes_before: u64,
+    pub bytes_after: u64,
+    /// Database file size after the rewrite and VACUUM.
+    pub database_size_bytes: u64,
+}
+
+#[derive(Debug, Error)]
+pub enum RecodeEmbeddingsError {
+    #[error("failed to resolve workspace root '{path}': {source}")]
+    InvalidRoot {
+        path: String,
+        #[source]
+        source: std::io::Error,
+    },
+    #[error("no index at '{path}'; run ingest_codebase first")]
+    MissingDatabase { path: String },
+    #[error("database '{path}' is on read-only media")]
+    ReadOnlyDatabase { path: String },
+    #[error(transparent)]
+    DatabaseLocked(#[from] DatabaseLockError),
+    #[error("SQLite error: {0}")]
+    Sqlite(#[from] rusqlite::Error),
+    #[error(transparent)]
+    Sandbox(#[from] SandboxError),
+    #[error("blocking task panicked: {0}")]
+    Join(#[from] tokio::task::JoinError),
+}
+
+pub async fn recode_embeddings(
+    params: RecodeEmbeddingsParams,
+) -> Result<RecodeEmbeddingsResponse, RecodeEmbeddingsError> {
+    tokio::task::spawn_blocking(move || perform_recode_embeddings(params)).await?
+}
+
+/// Rewrites chunk embeddings into `params.encoding` without re-embedding,
+/// recomputing each checksum over the new blob.
+fn perform_recode_embeddings(
+    params: RecodeEmbeddingsParams,
+) -> Result<RecodeEmbeddingsResponse, RecodeEmbeddingsError> {
+    let root = params.root.unwrap_or_else(|| "./".to_string());
+    let root_path = resolve_root(&root)?;
+    ensure_root_allowed(&root_path)?;
+    let database_path = database_path(
+        &root_path,
+        &params
+            .database_name
+            .unwrap_or_else(|| DEFAULT_DB_FILENAME.to_string()),
+    );
+    let database_path_string = database_path.to_string_lossy().to_string();
+    if !database_path.is_file() {
+        return Err(RecodeEmbeddingsError::MissingDatabase {
+            path: database_path_string,
+        });
+    }
+    if is_read_only_media(&database_path) {
+        return Err(RecodeEmbeddingsError::ReadOnlyDatabase {
+            path: database_path_string,
+        });
+    }
+
+    let _lock = DatabaseLock::acquire(&database_path, "recode_embeddings")?;
+    let mut conn = Connection::open_with_flags(&database_path, OpenFlags::SQLITE_OPEN_READ_WRITE)?;
+    ensure_checksum_schema(&conn)?;
+    ensure_embedding_schema(&conn)?;
+    let target = params.encoding.stored();
+    let mut response = RecodeEmbeddingsResponse {
+        database_path: database_path_string,
+        encoding: params.encoding,
+        recoded_chunks: 0,
+        unchanged_chunks: conn.query_row(
+            "SELECT COUNT(*) FROM file_chunks WHERE embedding_encoding IS ?1",
+            params![target],
+            |row| row.get::<_, i64>(0),
+        )? as usize,
+        skipped_chunks: 0,
+        bytes_before: 0,
+        bytes_after: 0,
+        database_size_bytes: 0,
+    };
+
+    let transaction = conn.transaction()?;
+    {
+        let mut select = transaction.prepare(&format!(
+            "SELECT id, path, content, {}, byte_start, byte_end, embedding, embedding_encoding, checksum
+             FROM file_chunks WHERE embedding_encoding IS NOT ?1 AND id > ?2 ORDER BY id LIMIT ?3",
+            content_encoding_column(&transaction, "file_chunks")
+        ))?;
+        let mut update = transaction.prepare(
+            "UPDATE file_chunks SET embedding = ?2, embedding_encoding = ?3, checksum = ?4 WHERE id = ?1",
+        )?;
+        let mut stored_content = StoredContentCache::new(&transaction)?;
+        let mut last_id = String::new();
+        loop {
+            let batch = select
+                .query_map(params![target, last_id, RECODE_BATCH], |row| {
+                    Ok(StoredChunk {
+                        id: row.get(0)?,
+                        path: row.get(1)?,
+                        content: row.get(2)?,
+                        content_encoding: row.get(3)?,
+                        byte_start: row.get(4)?,
+                        byte_end: row.get(5)?,
+                        embedding: row.get(6)?,
+                        embedding_encoding: row.get(7)?,
+                        checksum: row.get(8)?,
+                    })
+                })?
+                .collect::<Result<Vec<_>, _>>()?;
+            let Some(last) = batch.last() else {
+                break;
+            };
+            last_id = last.id.clone();
+
+            for chunk in batch {
+                let vector =
+                    decode_embedding(&chunk.embedding, chunk.embedding_encoding.as_deref());
+                if vector.is_empty() {
+                    response.skipped_chunks += 1;
+                    continue;
+                }
+                let (blob, encoding) = encode_embedding(&vector, params.encoding);
+                let checksum = match chunk.checksum {
+                    Some(expected) => {
+                        let content = stored_content.resolve_chunk(
+                            &chunk.path,
+                            chunk.content,
+                            chunk.content_encoding.as_deref(),
+                            chunk.byte_start,
+                            chunk.byte_end,
+                        );
+                        // recoding a corrupt chunk would give it a valid checksum
+                        if chunk_checksum(&chunk.embedding, &content) != expected {
+                            response.skipped_chunks += 1;
+                            continue;
+                        }
+                        Some(chunk_checksum(&blob, &content))
+                    }
+                    None => None,
+                };
+                update.execute(params![chunk.id, &blob, encoding, checksum])?;
+                response.recoded_chunks += 1;
+                response.bytes_before += chunk.embedding.len() as u64;
+                response.bytes_after += blob.len() as u64;
+            }
+        }
+    }
+    transaction.commit()?;
+    if response.recoded_chunks > 0 {
+        // freed pages only shrink the file once vacuumed
+        conn.execute_batch("VACUUM")?;
+    }
+    response.database_size_bytes = fs::metadata(&database_path)
+        .map(|metadata| metadata.len())
+        .unwrap_or(0);
+    Ok(response)
+}
+
+struct StoredChunk {
+    id: String,
+    path: String,
+    content: String,
+    content_encoding: Option<String>,
+    byte_start: Option<i64>,
+    byte_end: Option<i64>,
+    embedding: Vec<u8>,
+    embedding_encoding: Option<String>,
+    checksum: Option<String>,
+}
+
+fn resolve_root(root: &str) -> Result<PathBuf, RecodeEmbeddingsError> {
+    resolve_workspace_root(root).map_err(|source| RecodeEmbeddingsError::InvalidRoot {
+        path: root.to_string(),
+        source,
+    })
+}
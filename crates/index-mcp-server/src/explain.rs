@@ -0,0 +1,191 @@
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use globset::GlobSet;
+use rmcp::schemars::{self, JsonSchema};
+use serde::{Deserialize, Serialize};
+use thiserror::Error;
+
+use crate::data_dir::{database_path, open_read_only};
+use crate::ignore_rules::{gitignore_match, has_hidden_component, GitignoreMatch};
+use crate::index_status::DEFAULT_DB_FILENAME;
+use crate::ingest::{compile_globs, IngestError, DEFAULT_EXCLUDE_GLOBS, DEFAULT_INCLUDE_GLOBS};
+use crate::roots::resolve_workspace_root;
+use crate::sandbox::{ensure_root_allowed, resolve_within_root, SandboxError};
+use crate::skipped::{recorded_skip, RecordedSkip};
+use crate::text_format::is_binary;
+
+#[derive(Debug, Deserialize, JsonSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct ExplainPathParams {
+    #[serde(default)]
+    pub root: Option<String>,
+    #[serde(default)]
+    pub database_name: Option<String>,
+    /// Path relative to the root.
+    pub path: String,
+    /// Same semantics as ingest_codebase.
+    #[serde(default)]
+    pub include: Option<Vec<String>>,
+    #[serde(default)]
+    pub exclude: Option<Vec<String>>,
+    #[serde(default)]
+    pub max_file_size_bytes: Option<f64>,
+    #[serde(default)]
+    pub include_hidden: Option<bool>,
+}
+
+#[derive(Debug, Clone, Serialize, JsonSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct ExplainPathResponse {
+    pub root: String,
+    pub path: String,
+    pub exists: bool,
+    pub is_directory: bool,
+    /// Whether ingest_codebase would index the file with these settings.
+    pub included: bool,
+    /// Reason code for the first filter that drops the path, matching the
+    /// `reason` values ingest reports for skipped files where they overlap.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub reason: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub gitignore: Option<GitignoreMatch>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub include_pattern: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub exclude_pattern: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub size: Option<u64>,
+    /// Reason a recent ingestion actually skipped the path, which can differ
+    /// from `reason` when the file or the ingest settings changed since.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub recorded_skip: Option<RecordedSkip>,
+}
+
+#[derive(Debug, Error)]
+pub enum ExplainPathError {
+    #[error("failed to resolve workspace root '{path}': {source}")]
+    InvalidRoot {
+        path: String,
+        #[source]
+        source: std::io::Error,
+    },
+    #[error(transparent)]
+    Glob(#[from] IngestError),
+    #[error(transparent)]
+    Sandbox(#[from] SandboxError),
+    #[error("blocking task panicked: {0}")]
+    Join(#[from] tokio::task::JoinError),
+}
+
+pub async fn explain_path(
+    params: ExplainPathParams,
+) -> Result<ExplainPathResponse, ExplainPathError> {
+    tokio::task::spawn_blocking(move || compute_explain_path(params)).await?
+}
+
+fn compute_explain_path(
+    params: ExplainPathParams,
+) -> Result<ExplainPathResponse, ExplainPathError> {
+    let root = params.root.unwrap_or_else(|| "./".to_string());
+    let root_path = resolve_root(&root)?;
+    ensure_root_allowed(&root_path)?;
+    resolve_within_root(&root_path, &params.path)?;
+
+    let relative = params
+        .path
+        .replace('\\', "/")
+        .trim_start_matches("./")
+        .trim_end_matches('/')
+        .to_string();
+    let absolute = root_path.join(&relative);
+    let metadata = fs::metadata(&absolute).ok();
+    let is_directory = metadata.as_ref().is_some_and(|meta| meta.is_dir());
+
+    let include_patterns = params.include.unwrap_or_else(|| {
+        DEFAULT_INCLUDE_GLOBS
+            .iter()
+            .map(|s| s.to_string())
+            .collect()
+    });
+    let exclude_patterns = params.exclude.unwrap_or_else(|| {
+        DEFAULT_EXCLUDE_GLOBS
+            .iter()
+            .map(|s| s.to_string())
+            .collect()
+    });
+    let include_globs = compile_globs(&include_patterns)?;
+    let exclude_globs = compile_globs(&exclude_patterns)?;
+    let max_file_size_bytes = params
+        .max_file_size_bytes
+        .map(|value| value.max(0.0).round() as u64);
+
+    let relative_path = Path::new(&relative);
+    let gitignore = gitignore_match(&root_path, &absolute, is_directory);
+    let include_pattern = first_match(include_globs.as_ref(), &include_patterns, relative_path);
+    let exclude_pattern = first_match(exclude_globs.as_ref(), &exclude_patterns, relative_path);
+
+    let reason = if metadata.is_none() {
+        Some("not_found")
+    } else if !params.include_hidden.unwrap_or(false) && has_hidden_component(relative_path) {
+        Some("hidden")
+    } else if gitignore.as_ref().is_some_and(|rule| !rule.whitelisted) {
+        Some("gitignore")
+    } else if is_directory {
+        Some("directory")
+    } else if include_globs.is_some() && include_pattern.is_none() {
+        Some("include_glob")
+    } else if exclude_pattern.is_some() {
+        Some("exclude_glob")
+    } else if max_file_size_bytes
+        .zip(metadata.as_ref())
+        .is_some_and(|(limit, meta)| meta.len() > limit)
+    {
+        Some("max_file_size")
+    } else if fs::read(&absolute).is_ok_and(|bytes| is_binary(&bytes)) {
+        // binary files are indexed for metadata but never chunked
+        Some("binary")
+    } else {
+        None
+    };
+
+    let database_path = database_path(
+        &root_path,
+        &params
+            .database_name
+            .unwrap_or_else(|| DEFAULT_DB_FILENAME.to_string()),
+    );
+    let recorded_skip = open_read_only(&database_path)
+        .ok()
+        .and_then(|conn| recorded_skip(&conn, &relative));
+
+    Ok(ExplainPathResponse {
+        root: root_path.to_string_lossy().to_string(),
+        path: relative,
+        exists: metadata.is_some(),
+        is_directory,
+        included: matches!(reason, None | Some("binary")),
+        reason: reason.map(str::to_string),
+        gitignore,
+        include_pattern,
+        exclude_pattern,
+        size: metadata
+            .filter(|meta| meta.is_file())
+            .map(|meta| meta.len()),
+        recorded_skip,
+    })
+}
+
+fn first_match(set: Option<&GlobSet>, patterns: &[String], path: &Path) -> Option<String> {
+    set?.matches(path)
+        .first()
+        .and_then(|index| patterns.get(*index))
+        .cloned()
+}
+
+fn resolve_root(root: &str) -> Result<PathBuf, ExplainPathError> {
+    resolve_workspace_root(root).map_err(|source| ExplainPathError::InvalidRoot {
+        path: root.to_string(),
+        source,
+    })
+}
@@ -0,0 +1,102 @@
+use std::collections::BTreeMap;
+use std::env;
+
+use rmcp::schemars::{self, JsonSchema};
+use serde::{Deserialize, Serialize};
+use thiserror::Error;
+
+use crate::search::Classification;
+
+pub(crate) const FILTER_PRESETS_ENV: &str = "INDEX_MCP_FILTER_PRESETS";
+
+/// Named path filters from `INDEX_MCP_FILTER_PRESETS`, e.g.
+/// `{"backend": {"pathPrefix": "src/server", "exclude": ["**/tests/**"]}}`.
+/// Tools take the fields they support and ignore the rest.
+#[derive(Debug, Clone, Default, Serialize, Deserialize, JsonSchema)]
+#[serde(rename_all = "camelCase", deny_unknown_fields)]
+pub struct FilterPreset {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub path_prefix: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub path_contains: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub language: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub classification: Option<Classification>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub package: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub include: Option<Vec<String>>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub exclude: Option<Vec<String>>,
+}
+
+#[derive(Debug, Error)]
+pub enum FilterPresetError {
+    #[error("{FILTER_PRESETS_ENV} is not a JSON object of presets: {0}")]
+    Invalid(String),
+    #[error("unknown filter preset '{name}'")]
+    Unknown {
+        name: String,
+        available: Vec<String>,
+    },
+}
+
+pub(crate) fn load_filter_presets() -> Result<BTreeMap<String, FilterPreset>, FilterPresetError> {
+    match env::var(FILTER_PRESETS_ENV) {
+        Ok(raw) if !raw.trim().is_empty() => serde_json::from_str(&raw)
+            .map_err(|error| FilterPresetError::Invalid(error.to_string())),
+        _ => Ok(BTreeMap::new()),
+    }
+}
+
+/// `None` when no preset was requested.
+pub(crate) fn resolve_filter_preset(
+    name: Option<&str>,
+) -> Result<Option<FilterPreset>, FilterPresetError> {
+    let Some(name) = name.map(str::trim).filter(|name| !name.is_empty()) else {
+        return Ok(None);
+    };
+    let mut presets = load_filter_presets()?;
+    match presets.remove(name) {
+        Some(preset) => Ok(Some(preset)),
+        None => Err(FilterPresetError::Unknown {
+            name: name.to_string(),
+            available: presets.into_keys().collect(),
+        }),
+    }
+}
+
+impl FilterPreset {
+    /// `include` for tools that filter by globs only: the preset's own
+    /// globs, or everything under its `pathPrefix`.
+    pub(crate) fn include_globs(&self) -> Option<Vec<String>> {
+        self.include.clone().or_else(|| {
+            self.path_prefix
+                .as_deref()
+                .map(|prefix| prefix.trim().trim_start_matches("./").trim_end_matches('/'))
+                .filter(|prefix| !prefix.is_empty())
+                .map(|prefix| vec![format!("{prefix}/**")])
+        })
+    }
+}
+
+/// An explicit argument wins over the preset's value.
+pub(crate) fn fill<T>(slot: &mut Option<T>, preset: Option<T>) {
+    if slot.is_none() {
+        *slot = preset;
+    }
+}
+
+/// Glob lists are merged, so a call can exclude more than its preset does.
+pub(crate) fn merge_globs(slot: &mut Option<Vec<String>>, preset: Option<Vec<String>>) {
+    let Some(preset) = preset else {
+        return;
+    };
+    let globs = slot.get_or_insert_with(Vec::new);
+    for glob in preset {
+        if !globs.contains(&glob) {
+            globs.push(glob);
+        }
+    }
+}
@@ -0,0 +1,509 @@
+use std::fs;
+use std::io;
+use std::path::PathBuf;
+
+/// One file of the synthetic repository, with the definitions it is known
+/// to contain.
+pub(crate) struct FixtureFile {
+    pub path: &'static str,
+    pub symbols: &'static [&'static str],
+    pub content: &'static str,
+}
+
+/// A call the graph extractor is expected to record.
+pub(crate) struct FixtureCall {
+    pub caller_path: &'static str,
+    pub caller: &'static str,
+    pub callee_path: &'static str,
+    pub callee: &'static str,
+}
+
+pub(crate) const FIXTURE_FILES: &[FixtureFile] = &[
+    FixtureFile {
+        path: "inventory/src/ledger.rs",
+        symbols: &["new", "restock", "slot", "total_units", "reconcile_ledger"],
+        content: r#"/// Stock counts per SKU for one warehouse.
+pub struct Inventory {
+    items: Vec<(String, u32)>,
+}
+
+impl Inventory {
+    pub fn new() -> Self {
+        Self { items: Vec::new() }
+    }
+
+    pub fn restock(&mut self, sku: &str, quantity: u32) {
+        let slot = self.slot(sku);
+        *slot += quantity;
+    }
+
+    fn slot(&mut self, sku: &str) -> &mut u32 {
+        if let Some(index) = self.items.iter().position(|(name, _)| name == sku) {
+            return &mut self.items[index].1;
+        }
+        self.items.push((sku.to_string(), 0));
+        &mut self.items.last_mut().expect("just pushed").1
+    }
+
+    pub fn total_units(&self) -> u32 {
+        self.items.iter().map(|(_, quantity)| quantity).sum()
+    }
+}
+
+/// Applies ledger entries to the inventory and returns the units on hand.
+pub fn reconcile_ledger(inventory: &mut Inventory, entries: &[(String, u32)]) -> u32 {
+    for (sku, quantity) in entries {
+        inventory.restock(sku, *quantity);
+    }
+    inventory.total_units()
+}
+"#,
+    },
+    FixtureFile {
+        path: "web/src/invoice.ts",
+        symbols: &["computeInvoiceTotal", "sumLineItems", "applyDiscount"],
+        content: r#"export interface LineItem {
+  description: string;
+  unitPrice: number;
+  quantity: number;
+}
+
+export function computeInvoiceTotal(items: LineItem[], discountRate: number): number {
+  const subtotal = sumLineItems(items);
+  return applyDiscount(subtotal, discountRate);
+}
+
+function sumLineItems(items: LineItem[]): number {
+  return items.reduce((total, item) => total + item.unitPrice * item.quantity, 0);
+}
+
+function applyDiscount(amount: number, rate: number): number {
+  if (rate <= 0) {
+    return amount;
+  }
+  return amount * (1 - rate);
+}
+"#,
+    },
+    FixtureFile {
+        path: "web/src/checkout.ts",
+        symbols: &["checkoutCart"],
+        content: r#"import { computeInvoiceTotal, LineItem } from "./invoice";
+
+export function checkoutCart(items: LineItem[], couponRate: number): string {
+  const total = computeInvoiceTotal(items, couponRate);
+  return `Charged ${total.toFixed(2)} for ${items.length} items`;
+}
+"#,
+    },
+    FixtureFile {
+        path: "scripts/latency_report.py",
+        symbols: &["percentile", "summarize_latency"],
+        content: r#"def percentile(samples, fraction):
+    """Nearest-rank percentile of a list of latency samples in milliseconds."""
+    ordered = sorted(samples)
+    index = max(0, int(round(fraction * len(ordered))) - 1)
+    return ordered[index]
+
+
+def summarize_latency(samples):
+    """Median and tail latency for a dashboard row."""
+    return {
+        "p50": percentile(samples, 0.5),
+        "p99": percentile(samples, 0.99),
+    }
+"#,
+    },
+    FixtureFile {
+        path: "services/probe/healthcheck.go",
+        symbols: &["ProbeEndpoint", "classifyStatus"],
+        content: r#"package probe
+
+import "net/http"
+
+// ProbeEndpoint issues a GET and reports whether the service looks healthy.
+func ProbeEndpoint(client *http.Client, url string) (string, error) {
+	response, err := client.Get(url)
+	if err != nil {
+		return "unreachable", err
+	}
+	defer response.Body.Close()
+	return classifyStatus(response.StatusCode), nil
+}
+
+func classifyStatus(code int) string {
+	if code >= 500 {
+		return "failing"
+	}
+	return "healthy"
+}
+"#,
+    },
+];
+
+pub(crate) const FIXTURE_CALLS: &[FixtureCall] = &[
+    FixtureCall {
+        caller_path: "web/src/invoice.ts",
+        caller: "computeInvoiceTotal",
+        callee_path: "web/src/invoice.ts",
+        callee: "sumLineItems",
+    },
+    FixtureCall {
+        caller_path: "web/src/invoice.ts",
+        caller: "computeInvoiceTotal",
+        callee_path: "web/src/invoice.ts",
+        callee: "applyDiscount",
+    },
+    FixtureCall {
+        caller_path: "web/src/checkout.ts",
+        caller: "checkoutCart",
+        callee_path: "web/src/invoice.ts",
+        callee: "computeInvoiceTotal",
+    },
+    FixtureCall {
+        caller_path: "inventory/src/ledger.rs",
+        caller: "reconcile_ledger",
+        callee_path: "inventory/src/ledger.rs",
+        callee: "restock",
+    },
+    FixtureCall {
+        caller_path: "inventory/src/ledger.rs",
+        caller: "restock",
+        callee_path: "inventory/src/ledger.rs",
+        callee: "slot",
+    },
+];
+
+/// The fixture written to a fresh temporary directory, removed on drop.
+pub(crate) struct FixtureRepo {
+    root: PathBuf,
+}
+
+impl FixtureRepo {
+    pub(crate) fn create() -> io::Result<Self> {
+        let root = std::env::temp_dir().join(format!("index-mcp-fixture-{}", uuid::Uuid::new_v4()));
+        for file in FIXTURE_FILES {
+            let path = root.join(file.path);
+            if let Some(parent) = path.parent() {
+                fs::create_dir_all(parent)?;
+            }
+            fs::write(&path, file.content)?;
+        }
+        Ok(Self { root })
+    }
+
+    pub(crate) fn root_string(&self) -> String {
+        self.root.to_string_lossy().to_string()
+    }
+}
+
+impl Drop for FixtureRepo {
+    fn drop(&mut self) {
+        let _ = fs::remove_dir_all(&self.root);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use super::*;
+    use crate::bundle::{context_bundle, ContextBundleParams, NeighborDirection};
+    use crate::embedder::MOCK_EMBEDDING_MODEL;
+    use crate::graph_neighbors::{graph_neighbors, GraphNeighborsParams};
+    use crate::ingest::{ingest_codebase, IngestError, IngestParams};
+    use crate::search::{semantic_search, Classification, SemanticSearchParams};
+    use crate::suggest_excludes::{suggest_excludes, SuggestExcludesParams};
+
+    async fn ingest_fixture(repo: &FixtureRepo) {
+        let params: IngestParams = serde_json::from_value(json!({
+            "root": repo.root_string(),
+            "embedding": { "provider": "mock", "chunking": "structural" },
+        }))
+        .expect("valid ingest params");
+        let response = ingest_codebase(params).await.expect("fixture ingest");
+
+        assert_eq!(response.ingested_file_count, FIXTURE_FILES.len());
+        assert_eq!(
+            response.embedding_model.as_deref(),
+            Some(MOCK_EMBEDDING_MODEL)
+        );
+        assert!(response.mock_embeddings);
+        assert!(response.embedded_chunk_count >= FIXTURE_FILES.len());
+        assert!(response.graph_edge_count > 0);
+    }
+
+    #[tokio::test]
+    async fn search_finds_fixture_files_by_their_words() {
+        let repo = FixtureRepo::create().expect("write fixture");
+        ingest_fixture(&repo).await;
+
+        let queries = [
+            (
+                "reconcile ledger restock inventory units",
+                "inventory/src/ledger.rs",
+            ),
+            (
+                "summarize latency percentile samples",
+                "scripts/latency_report.py",
+            ),
+            (
+                "probe endpoint healthy status",
+                "services/probe/healthcheck.go",
+            ),
+        ];
+        for (query, expected) in queries {
+            let params: SemanticSearchParams = serde_json::from_value(json!({
+                "root": repo.root_string(),
+                "query": query,
+                "limit": 3,
+            }))
+            .expect("valid search params");
+            let response = semantic_search(params).await.expect("fixture search");
+
+            assert!(response.fallback.is_none(), "{query}: fell back to lexical");
+            assert_eq!(
+                response.results.first().map(|hit| hit.path.as_str()),
+                Some(expected),
+                "{query}"
+            );
+        }
+    }
+
+    #[tokio::test]
+    async fn search_filters_on_ingested_labels() {
+        let repo = FixtureRepo::create().expect("write fixture");
+        ingest_fixture(&repo).await;
+
+        let params: SemanticSearchParams = serde_json::from_value(json!({
+            "root": repo.root_string(),
+            "query": "reconcile ledger restock inventory units",
+            "language": "python",
+            "classification": "function",
+        }))
+        .expect("valid search params");
+        let response = semantic_search(params).await.expect("fixture search");
+
+        assert!(!response.results.is_empty());
+        for hit in &response.results {
+            assert_eq!(hit.path, "scripts/latency_report.py");
+            assert_eq!(hit.language.as_deref(), Some("Python"));
+            assert_eq!(hit.classification, Classification::Function);
+        }
+    }
+
+    #[tokio::test]
+    async fn search_flags_hits_in_files_edited_since_ingest() {
+        let repo = FixtureRepo::create().expect("write fixture");
+        ingest_fixture(&repo).await;
+        let edited = "scripts/latency_report.py";
+        fs::write(
+            repo.root.join(edited),
+            "def percentile(samples):\n    return max(samples)\n",
+        )
+        .expect("edit fixture file");
+
+        let params: SemanticSearchParams = serde_json::from_value(json!({
+            "root": repo.root_string(),
+            "query": "summarize latency percentile samples",
+            "limit": 5,
+        }))
+        .expect("valid search params");
+        let response = semantic_search(params).await.expect("fixture search");
+
+        assert!(response.results.iter().any(|hit| hit.path == edited));
+        for hit in &response.results {
+            assert_eq!(hit.stale, hit.path == edited, "{}", hit.path);
+        }
+    }
+
+    #[tokio::test]
+    async fn chunking_changes_rechunk_unchanged_files() {
+        let repo = FixtureRepo::create().expect("write fixture");
+        ingest_fixture(&repo).await;
+        let ingest = |embedding: serde_json::Value| {
+            let params: IngestParams = serde_json::from_value(json!({
+                "root": repo.root_string(),
+                "embedding": embedding,
+            }))
+            .expect("valid ingest params");
+            ingest_codebase(params)
+        };
+
+        // settings left out keep the recorded ones
+        let response = ingest(json!({ "provider": "mock" }))
+            .await
+            .expect("repeat ingest");
+        assert_eq!(response.reused_file_count, Some(FIXTURE_FILES.len()));
+
+        let response = ingest(json!({ "provider": "mock", "chunking": "size" }))
+            .await
+            .expect("size-chunked ingest");
+        assert_eq!(response.reused_file_count, None);
+
+        // structural chunks split the fixture files by definition
+        let response = ingest(json!({
+            "provider": "mock",
+            "chunking": "structural",
+            "maxChunksPerFile": 1,
+        }))
+        .await
+        .expect("capped ingest");
+        assert_eq!(response.reused_file_count, None);
+        assert!(!response.truncated_files.is_empty());
+
+        let params: IngestParams = serde_json::from_value(json!({
+            "root": repo.root_string(),
+            "paths": ["web/src/invoice.ts"],
+            "embedding": { "provider": "mock", "docChunks": true },
+        }))
+        .expect("valid ingest params");
+        let error = ingest_codebase(params)
+            .await
+            .expect_err("scoped ingest cannot change chunking");
+        assert!(matches!(error, IngestError::ChunkingChanged { .. }));
+    }
+
+    #[tokio::test]
+    async fn suggest_excludes_reads_compressed_content() {
+        let repo = FixtureRepo::create().expect("write fixture");
+        for name in ["api.rs", "types.rs"] {
+            let path = repo.root.join("proto").join(name);
+            fs::create_dir_all(path.parent().expect("has parent")).expect("create proto dir");
+            fs::write(
+                &path,
+                "// @generated by protoc. DO NOT EDIT.\npub struct Envelope;\n",
+            )
+            .expect("write generated file");
+        }
+        let params: IngestParams = serde_json::from_value(json!({
+            "root": repo.root_string(),
+            "compressContent": true,
+            "embedding": { "provider": "mock" },
+        }))
+        .expect("valid ingest params");
+        ingest_codebase(params).await.expect("compressed ingest");
+
+        let params: SuggestExcludesParams = serde_json::from_value(json!({
+            "root": repo.root_string(),
+        }))
+        .expect("valid suggest params");
+        let response = suggest_excludes(params)
+            .await
+            .expect("suggest over compressed content");
+
+        assert_eq!(response.indexed_files as usize, FIXTURE_FILES.len() + 2);
+        assert!(response.suggestions.iter().any(|suggestion| {
+            suggestion.glob == "proto/**" && suggestion.reason == "generated_code"
+        }));
+    }
+
+    #[tokio::test]
+    async fn bundle_lists_callers_and_callees() {
+        let repo = FixtureRepo::create().expect("write fixture");
+        ingest_fixture(&repo).await;
+
+        let params: ContextBundleParams = serde_json::from_value(json!({
+            "root": repo.root_string(),
+            "file": "web/src/invoice.ts",
+            "symbol": { "name": "computeInvoiceTotal" },
+        }))
+        .expect("valid bundle params");
+        let response = context_bundle(params).await.expect("fixture bundle");
+
+        let invoice = &FIXTURE_FILES[1];
+        for symbol in invoice.symbols {
+            assert!(
+                response
+                    .definitions
+                    .iter()
+                    .any(|definition| definition.name == *symbol),
+                "missing definition {symbol}"
+            );
+        }
+        for call in FIXTURE_CALLS
+            .iter()
+            .filter(|call| call.caller_path == invoice.path || call.callee_path == invoice.path)
+        {
+            let (direction, name, path) = if call.caller_path == invoice.path {
+                (NeighborDirection::Outgoing, call.callee, call.callee_path)
+            } else {
+                (NeighborDirection::Incoming, call.caller, call.caller_path)
+            };
+            assert!(
+                response.related.iter().any(|related| {
+                    related.direction == direction
+                        && related.neighbor.name == name
+                        && related.neighbor.path.as_deref() == Some(path)
+                }),
+                "missing {direction:?} neighbor {name} in {path}"
+            );
+        }
+    }
+
+    #[tokio::test]
+    async fn graph_neighbors_walks_two_hops() {
+        let repo = FixtureRepo::create().expect("write fixture");
+        ingest_fixture(&repo).await;
+
+        let response = graph_neighbors(GraphNeighborsParams {
+            root: Some(repo.root_string()),
+            database_name: None,
+            path: Some("web/src/checkout.ts".to_string()),
+            symbol: Some("checkoutCart".to_string()),
+            node_id: None,
+            depth: Some(2),
+            direction: Some(NeighborDirection::Outgoing),
+            edge_type: Some("calls".to_string()),
+            limit: None,
+        })
+        .await
+        .expect("fixture graph walk");
+
+        let hop = |name: &str| {
+            response
+                .neighbors
+                .iter()
+                .find(|neighbor| neighbor.node.name == name)
+                .map(|neighbor| neighbor.hop)
+        };
+        assert_eq!(hop("computeInvoiceTotal"), Some(1));
+        assert_eq!(hop("sumLineItems"), Some(2));
+        assert_eq!(hop("applyDiscount"), Some(2));
+    }
+
+    #[cfg(feature = "tree-sitter")]
+    #[tokio::test]
+    async fn rust_calls_resolve_to_methods() {
+        let repo = FixtureRepo::create().expect("write fixture");
+        ingest_fixture(&repo).await;
+
+        let response = graph_neighbors(GraphNeighborsParams {
+            root: Some(repo.root_string()),
+            database_name: None,
+            path: Some("inventory/src/ledger.rs".to_string()),
+            symbol: Some("reconcile_ledger".to_string()),
+            node_id: None,
+            depth: Some(2),
+            direction: Some(NeighborDirection::Outgoing),
+            edge_type: None,
+            limit: None,
+        })
+        .await
+        .expect("fixture graph walk");
+
+        for call in FIXTURE_CALLS
+            .iter()
+            .filter(|call| call.callee_path == "inventory/src/ledger.rs")
+        {
+            assert!(
+                response.neighbors.iter().any(|neighbor| {
+                    neighbor.node.name == call.callee
+                        && neighbor.node.path.as_deref() == Some(call.callee_path)
+                }),
+                "missing callee {}",
+                call.callee
+            );
+        }
+    }
+}
@@ -0,0 +1,215 @@
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::process::Command;
+
+use rusqlite::{params, Connection, OptionalExtension};
+use thiserror::Error;
+
+use crate::data_dir::open_read_only;
+
+/// Directory beside the database holding one snapshot per retained ingest.
+pub(crate) const GENERATIONS_DIR: &str = ".mcp-index-generations";
+const KEEP_GENERATIONS_ENV: &str = "INDEX_MCP_KEEP_GENERATIONS";
+// a sha prefix shorter than this matches too many commits to be useful
+const MIN_COMMIT_PREFIX_LEN: usize = 7;
+
+#[derive(Debug, Error)]
+pub enum GenerationError {
+    #[error("pass either generation or atCommit, not both")]
+    Conflicting,
+    #[error("generation {0} is not retained; set INDEX_MCP_KEEP_GENERATIONS to keep snapshots of past ingests")]
+    NotRetained(i64),
+    #[error("no retained generation was indexed at commit '{0}'")]
+    UnknownCommit(String),
+    #[error("snapshot for generation {generation} is missing at '{path}'")]
+    MissingSnapshot { generation: i64, path: String },
+    #[error("failed to write snapshot '{path}': {source}")]
+    Snapshot {
+        path: String,
+        #[source]
+        source: std::io::Error,
+    },
+    #[error("SQLite error: {0}")]
+    Sqlite(#[from] rusqlite::Error),
+}
+
+/// A retained snapshot picked by `generation` or `atCommit`.
+#[derive(Debug, Clone)]
+pub(crate) struct GenerationSnapshot {
+    pub generation: i64,
+    pub path: PathBuf,
+}
+
+/// Snapshots to keep, from `INDEX_MCP_KEEP_GENERATIONS`. Each is a full copy
+/// of the database, so none are kept unless asked for.
+pub(crate) fn keep_generations() -> usize {
+    std::env::var(KEEP_GENERATIONS_ENV)
+        .ok()
+        .and_then(|value| value.trim().parse().ok())
+        .unwrap_or(0)
+}
+
+pub(crate) fn ensure_generation_schema(conn: &Connection) -> Result<(), rusqlite::Error> {
+    conn.execute_batch(
+        "CREATE TABLE IF NOT EXISTS index_generations (
+            generation INTEGER PRIMARY KEY,
+            ingestion_id TEXT NOT NULL,
+            commit_sha TEXT,
+            created_at INTEGER NOT NULL,
+            snapshot_path TEXT NOT NULL
+        );
+        CREATE INDEX IF NOT EXISTS index_generations_commit_idx ON index_generations(commit_sha);",
+    )
+}
+
+/// Copies the just-committed database into the generations directory and
+/// drops snapshots past `keep`. Generations count ingestions, matching the
+/// `generation` bundle provenance reports. Must run outside a transaction.
+pub(crate) fn snapshot_generation(
+    conn: &Connection,
+    database_path: &Path,
+    ingestion_id: &str,
+    commit_sha: Option<&str>,
+    created_at: i64,
+    keep: usize,
+) -> Result<Option<i64>, GenerationError> {
+    if keep == 0 {
+        return Ok(None);
+    }
+    let generation: i64 =
+        conn.query_row("SELECT COUNT(*) FROM ingestions", [], |row| row.get(0))?;
+    let directory = database_path
+        .parent()
+        .unwrap_or_else(|| Path::new("."))
+        .join(GENERATIONS_DIR);
+    fs::create_dir_all(&directory).map_err(|source| GenerationError::Snapshot {
+        path: directory.to_string_lossy().to_string(),
+        source,
+    })?;
+    let file_name = database_path
+        .file_name()
+        .map(|name| name.to_string_lossy().to_string())
+        .unwrap_or_default();
+    let snapshot = directory.join(format!("{file_name}.{generation}"));
+    // VACUUM INTO refuses to overwrite; a leftover from a rolled-back run
+    // is stale anyway
+    let _ = fs::remove_file(&snapshot);
+    conn.execute(
+        "VACUUM INTO ?1",
+        params![snapshot.to_string_lossy().to_string()],
+    )?;
+    conn.execute(
+        "INSERT OR REPLACE INTO index_generations (generation, ingestion_id, commit_sha, created_at, snapshot_path)
+         VALUES (?1, ?2, ?3, ?4, ?5)",
+        params![
+            generation,
+            ingestion_id,
+            commit_sha,
+            created_at,
+            snapshot.to_string_lossy().to_string()
+        ],
+    )?;
+
+    let expired: Vec<(i64, String)> = {
+        let mut stmt = conn.prepare(
+            "SELECT generation, snapshot_path FROM index_generations
+             ORDER BY generation DESC LIMIT -1 OFFSET ?1",
+        )?;
+        let rows = stmt.query_map(params![keep as i64], |row| {
+            Ok((row.get::<_, i64>(0)?, row.get::<_, String>(1)?))
+        })?;
+        rows.collect::<Result<_, _>>()?
+    };
+    for (expired_generation, path) in expired {
+        let _ = fs::remove_file(&path);
+        conn.execute(
+            "DELETE FROM index_generations WHERE generation = ?1",
+            params![expired_generation],
+        )?;
+    }
+    Ok(Some(generation))
+}
+
+/// Resolves `generation` or `atCommit` to a retained snapshot; `None` when
+/// neither was passed and the live index should be used. `atCommit` accepts
+/// any ref git can resolve in `root`, or a sha prefix, and picks the newest
+/// generation indexed at that commit.
+pub(crate) fn resolve_generation(
+    root: &Path,
+    database_path: &Path,
+    generation: Option<i64>,
+    at_commit: Option<&str>,
+) -> Result<Option<GenerationSnapshot>, GenerationError> {
+    let at_commit = at_commit.map(str::trim).filter(|value| !value.is_empty());
+    match (generation, at_commit) {
+        (None, None) => return Ok(None),
+        (Some(_), Some(_)) => return Err(GenerationError::Conflicting),
+        _ => {}
+    }
+
+    let conn = open_read_only(database_path)?;
+    let retained: bool = conn.query_row(
+        "SELECT EXISTS(SELECT 1 FROM sqlite_master WHERE type = 'table' AND name = 'index_generations')",
+        [],
+        |row| row.get(0),
+    )?;
+
+    let (generation, path) = match generation {
+        Some(generation) => {
+            let path = if retained {
+                conn.query_row(
+                    "SELECT snapshot_path FROM index_generations WHERE generation = ?1",
+                    params![generation],
+                    |row| row.get::<_, String>(0),
+                )
+                .optional()?
+            } else {
+                None
+            };
+            path.map(|path| (generation, path))
+                .ok_or(GenerationError::NotRetained(generation))?
+        }
+        None => {
+            let commit = at_commit.unwrap_or_default();
+            let sha = resolve_commit(root, commit).unwrap_or_else(|| commit.to_lowercase());
+            let found = if retained && sha.len() >= MIN_COMMIT_PREFIX_LEN {
+                conn.query_row(
+                    "SELECT generation, snapshot_path FROM index_generations
+                     WHERE commit_sha = ?1 OR commit_sha LIKE ?1 || '%'
+                     ORDER BY generation DESC LIMIT 1",
+                    params![sha],
+                    |row| Ok((row.get::<_, i64>(0)?, row.get::<_, String>(1)?)),
+                )
+                .optional()?
+            } else {
+                None
+            };
+            found.ok_or_else(|| GenerationError::UnknownCommit(commit.to_string()))?
+        }
+    };
+
+    let path = PathBuf::from(path);
+    if !path.is_file() {
+        return Err(GenerationError::MissingSnapshot {
+            generation,
+            path: path.to_string_lossy().to_string(),
+        });
+    }
+    Ok(Some(GenerationSnapshot { generation, path }))
+}
+
+fn resolve_commit(root: &Path, reference: &str) -> Option<String> {
+    let output = Command::new("git")
+        .arg("rev-parse")
+        .arg("--verify")
+        .arg("--quiet")
+        .arg(format!("{reference}^{{commit}}"))
+        .current_dir(root)
+        .output()
+        .ok()?;
+    if !output.status.success() {
+        return None;
+    }
+    let sha = String::from_utf8_lossy(&output.stdout).trim().to_string();
+    (!sha.is_empty()).then_some(sha)
+}
@@ -8,15 +8,25 @@ use regex::Regex;
 use rmcp::schemars::{self, JsonSchema};
 use rusqlite::{params, Connection, OpenFlags};
 use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
 use thiserror::Error;
 
+use crate::data_dir::{database_path, is_read_only_media, open_read_only};
 use crate::index_status::DEFAULT_DB_FILENAME;
+use crate::limits::{clamp_parameter, ParameterAdjustment};
+use crate::roots::resolve_workspace_root;
 
 const GIT_LOG_FIELD_SEPARATOR: &str = "\u{001f}";
 const GIT_LOG_RECORD_SEPARATOR: &str = "\u{001e}";
 const DIFF_PREVIEW_MAX_LINES: usize = 200;
 const DIFF_PREVIEW_MAX_CHARS: usize = 4_000;
+const DEFAULT_REPOSITORY_TIMELINE_LIMIT: u32 = 20;
 const MAX_REPOSITORY_TIMELINE_LIMIT: u32 = 200;
+const MAX_DIFF_BYTES_ENV: &str = "INDEX_MCP_TIMELINE_MAX_DIFF_BYTES";
+const MAX_DIFF_AGE_DAYS_ENV: &str = "INDEX_MCP_TIMELINE_MAX_DIFF_AGE_DAYS";
+const DEFAULT_MAX_DIFF_BYTES: u64 = 64 * 1024 * 1024;
+const DEFAULT_MAX_DIFF_AGE_DAYS: u64 = 90;
+const DAY_MS: i64 = 24 * 60 * 60 * 1000;
 
 static RELATIVE_SINCE_PATTERN: Lazy<Regex> =
     Lazy::new(|| Regex::new(r"^(\d+)\s*(d|w|m|y)$").expect("valid regex"));
@@ -51,6 +61,42 @@ pub struct RepositoryTimelineParams {
     pub paths: Option<Vec<String>>,
     #[serde(default)]
     pub diff_pattern: Option<String>,
+    /// Total bytes of cached diffs to keep, newest captures first (default
+    /// `INDEX_MCP_TIMELINE_MAX_DIFF_BYTES` or 64 MiB; 0 for no cap).
+    #[serde(default)]
+    pub max_diff_bytes: Option<u64>,
+    /// Drop cached diffs captured longer ago than this (default
+    /// `INDEX_MCP_TIMELINE_MAX_DIFF_AGE_DAYS` or 90; 0 keeps them forever).
+    #[serde(default)]
+    pub max_diff_age_days: Option<u64>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, JsonSchema)]
+#[serde(rename_all = "camelCase")]
+pub enum DiffEvictionReason {
+    MaxAge,
+    MaxTotalBytes,
+}
+
+/// Left in place of a cached diff removed by retention, so lookups can say
+/// why the diff is gone.
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct DiffTombstone {
+    pub reason: DiffEvictionReason,
+    pub evicted_at: i64,
+    pub original_bytes: u64,
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize, JsonSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct DiffRetentionReport {
+    pub max_diff_bytes: u64,
+    pub max_diff_age_days: u64,
+    pub evicted_by_age: usize,
+    pub evicted_by_size: usize,
+    pub freed_bytes: u64,
+    pub retained_bytes: u64,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
@@ -123,6 +169,16 @@ pub struct RepositoryTimelineEntry {
     pub captured_at: Option<i64>,
 }
 
+/// Options that narrowed a cached entry's file stats. Entries captured
+/// without path filters and with file stats carry none and are preferred on
+/// lookup.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize, JsonSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct TimelineFilters {
+    pub paths: Vec<String>,
+    pub include_file_stats: bool,
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
 #[serde(rename_all = "camelCase")]
 pub struct TimelineIdentity {
@@ -154,6 +210,11 @@ pub struct RepositoryTimelineResponse {
     pub remote_url: Option<String>,
     #[serde(skip_serializing_if = "Option::is_none")]
     pub database_path: Option<String>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub adjustments: Vec<ParameterAdjustment>,
+    /// Set when this run evicted cached diffs to stay within retention.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub diff_retention: Option<DiffRetentionReport>,
 }
 
 #[derive(Debug, Deserialize, JsonSchema)]
@@ -173,6 +234,13 @@ pub struct RepositoryTimelineEntryLookupResponse {
     pub entry: RepositoryTimelineEntry,
     #[serde(skip_serializing_if = "Option::is_none")]
     pub diff: Option<String>,
+    /// Filters the returned entry was captured with; absent for unfiltered
+    /// entries.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub filters: Option<TimelineFilters>,
+    /// Why the cached diff is gone, when retention removed it.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub diff_evicted: Option<DiffTombstone>,
 }
 
 #[derive(Debug, Error)]
@@ -227,6 +295,8 @@ fn perform_repository_timeline(
         include_diffs,
         paths,
         diff_pattern,
+        max_diff_bytes,
+        max_diff_age_days,
     } = params;
 
     let root_param = root.unwrap_or_else(|| "./".to_string());
@@ -237,8 +307,15 @@ fn perform_repository_timeline(
 
     let branch_name = branch.unwrap_or_else(|| "HEAD".to_string());
 
-    let requested_limit = limit.unwrap_or(20);
-    let limit_value = requested_limit.clamp(1, MAX_REPOSITORY_TIMELINE_LIMIT);
+    let mut adjustments = Vec::new();
+    let limit_value = clamp_parameter(
+        "limit",
+        limit,
+        DEFAULT_REPOSITORY_TIMELINE_LIMIT,
+        1,
+        MAX_REPOSITORY_TIMELINE_LIMIT,
+        &mut adjustments,
+    );
 
     let log_output = run_git_log(
         &repo_root,
@@ -279,13 +356,27 @@ fn perform_repository_timeline(
     }
 
     let storage_entries = entries.clone();
+    let filters = timeline_filters(paths.as_deref(), include_file_stats.unwrap_or(true));
     let database_path = persist_timeline_entries(
         &absolute_root,
         database_name.as_deref(),
         &branch_name,
         captured_at,
+        filters.as_ref(),
         &storage_entries,
     )?;
+    let diff_retention = enforce_diff_retention(
+        &resolve_database_path(&absolute_root, database_name.as_deref()),
+        max_diff_bytes.unwrap_or_else(|| env_limit(MAX_DIFF_BYTES_ENV, DEFAULT_MAX_DIFF_BYTES)),
+        max_diff_age_days
+            .unwrap_or_else(|| env_limit(MAX_DIFF_AGE_DAYS_ENV, DEFAULT_MAX_DIFF_AGE_DAYS)),
+        captured_at,
+    )
+    .map_err(|error| RepositoryTimelineError::Database {
+        path: database_path.clone().unwrap_or_default(),
+        source: error,
+    })?
+    .filter(|report| report.evicted_by_age + report.evicted_by_size > 0);
 
     let response_entries = transform_entries_for_response(entries);
 
@@ -320,6 +411,8 @@ fn perform_repository_timeline(
         entries: response_entries,
         remote_url,
         database_path,
+        adjustments,
+        diff_retention,
     })
 }
 
@@ -337,17 +430,31 @@ fn fetch_repository_timeline_entry(
     let db_path = resolve_database_path(&absolute_root, database_name.as_deref());
     let db_path_string = db_path.to_string_lossy().to_string();
 
-    let conn = Connection::open_with_flags(&db_path, OpenFlags::SQLITE_OPEN_READ_ONLY).map_err(
-        |error| RepositoryTimelineError::Database {
-            path: db_path_string.clone(),
-            source: error,
-        },
-    )?;
+    let conn = open_read_only(&db_path).map_err(|error| RepositoryTimelineError::Database {
+        path: db_path_string.clone(),
+        source: error,
+    })?;
 
-    let mut stmt = conn
-        .prepare(
-            "SELECT branch, captured_at, payload, diff FROM repository_timeline_entries WHERE commit_sha = ?1",
+    let tombstone_column = if has_timeline_column(&conn, "diff_tombstone") {
+        "diff_tombstone"
+    } else {
+        "NULL"
+    };
+    // databases written before filter provenance existed have one row per sha
+    let query = if has_timeline_filter_columns(&conn) {
+        format!(
+            "SELECT branch, captured_at, payload, diff, filters, {tombstone_column} FROM repository_timeline_entries
+             WHERE commit_sha = ?1
+             ORDER BY filter_fingerprint = '' DESC, captured_at DESC
+             LIMIT 1"
         )
+    } else {
+        format!(
+            "SELECT branch, captured_at, payload, diff, NULL, {tombstone_column} FROM repository_timeline_entries WHERE commit_sha = ?1"
+        )
+    };
+    let mut stmt = conn
+        .prepare(&query)
         .map_err(|error| RepositoryTimelineError::Database {
             path: db_path_string.clone(),
             source: error,
@@ -358,10 +465,12 @@ fn fetch_repository_timeline_entry(
         let captured_at: i64 = row.get(1)?;
         let payload: String = row.get(2)?;
         let diff: Option<String> = row.get(3)?;
-        Ok((branch, captured_at, payload, diff))
+        let filters: Option<String> = row.get(4)?;
+        let tombstone: Option<String> = row.get(5)?;
+        Ok((branch, captured_at, payload, diff, filters, tombstone))
     });
 
-    let (_branch, captured_at, payload, diff) = match result {
+    let (_branch, captured_at, payload, diff, filters, tombstone) = match result {
         Ok(values) => values,
         Err(rusqlite::Error::QueryReturnedNoRows) => {
             return Err(RepositoryTimelineError::EntryNotFound {
@@ -392,20 +501,16 @@ fn fetch_repository_timeline_entry(
         database_path: db_path_string,
         entry,
         diff,
+        filters: filters.and_then(|value| serde_json::from_str(&value).ok()),
+        diff_evicted: tombstone.and_then(|value| serde_json::from_str(&value).ok()),
     })
 }
 
 fn resolve_root(root: &str) -> Result<PathBuf, RepositoryTimelineError> {
-    let candidate = PathBuf::from(root);
-    if candidate.is_absolute() {
-        return Ok(candidate);
-    }
-
-    let cwd = std::env::current_dir().map_err(|source| RepositoryTimelineError::InvalidRoot {
+    resolve_workspace_root(root).map_err(|source| RepositoryTimelineError::InvalidRoot {
         path: root.to_string(),
         source,
-    })?;
-    Ok(cwd.join(candidate))
+    })
 }
 
 fn verify_git_repository(root: &PathBuf) -> Result<String, RepositoryTimelineError> {
@@ -897,6 +1002,7 @@ fn persist_timeline_entries(
     database_name: Option<&str>,
     branch: &str,
     captured_at: i64,
+    filters: Option<&TimelineFilters>,
     entries: &[RepositoryTimelineEntry],
 ) -> Result<Option<String>, RepositoryTimelineError> {
     let db_path = resolve_database_path(root, database_name);
@@ -915,17 +1021,7 @@ fn persist_timeline_entries(
         source: error,
     })?;
 
-    conn.execute(
-        "CREATE TABLE IF NOT EXISTS repository_timeline_entries (
-            commit_sha TEXT PRIMARY KEY,
-            branch TEXT NOT NULL,
-            captured_at INTEGER NOT NULL,
-            payload TEXT NOT NULL,
-            diff TEXT
-        )",
-        [],
-    )
-    .map_err(|error| RepositoryTimelineError::Database {
+    ensure_timeline_schema(&conn).map_err(|error| RepositoryTimelineError::Database {
         path: db_path_string.clone(),
         source: error,
     })?;
@@ -939,19 +1035,25 @@ fn persist_timeline_entries(
 
     let mut stmt = tx
         .prepare(
-            "INSERT INTO repository_timeline_entries (commit_sha, branch, captured_at, payload, diff)
-             VALUES (?1, ?2, ?3, ?4, ?5)
-             ON CONFLICT(commit_sha) DO UPDATE SET
+            "INSERT INTO repository_timeline_entries
+                 (commit_sha, filter_fingerprint, filters, branch, captured_at, payload, diff)
+             VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7)
+             ON CONFLICT(commit_sha, filter_fingerprint) DO UPDATE SET
+                 filters = excluded.filters,
                  branch = excluded.branch,
                  captured_at = excluded.captured_at,
                  payload = excluded.payload,
-                 diff = excluded.diff",
+                 diff = COALESCE(excluded.diff, repository_timeline_entries.diff),
+                 diff_tombstone = CASE WHEN excluded.diff IS NULL
+                     THEN repository_timeline_entries.diff_tombstone END",
         )
         .map_err(|error| RepositoryTimelineError::Database {
             path: db_path_string.clone(),
             source: error,
         })?;
 
+    let fingerprint = filter_fingerprint(filters);
+    let filters_json = filters.map(serde_json::to_string).transpose()?;
     for entry in entries {
         let mut payload_entry = entry.clone();
         payload_entry.diff = None;
@@ -964,6 +1066,8 @@ fn persist_timeline_entries(
 
         stmt.execute(params![
             entry.sha,
+            fingerprint,
+            filters_json,
             branch,
             captured_at,
             payload_json,
@@ -986,9 +1090,176 @@ fn persist_timeline_entries(
     Ok(Some(db_path_string))
 }
 
+const TIMELINE_TABLE_SQL: &str = "CREATE TABLE IF NOT EXISTS repository_timeline_entries (
+    commit_sha TEXT NOT NULL,
+    filter_fingerprint TEXT NOT NULL DEFAULT '',
+    filters TEXT,
+    branch TEXT NOT NULL,
+    captured_at INTEGER NOT NULL,
+    payload TEXT NOT NULL,
+    diff TEXT,
+    PRIMARY KEY (commit_sha, filter_fingerprint)
+)";
+
+/// Creates the timeline table, rebuilding tables keyed by sha alone. Legacy
+/// rows are kept as unfiltered entries since their filters were not recorded.
+fn ensure_timeline_schema(conn: &Connection) -> Result<(), rusqlite::Error> {
+    let table_exists: bool = conn.query_row(
+        "SELECT EXISTS(SELECT 1 FROM sqlite_master WHERE type = 'table' AND name = 'repository_timeline_entries')",
+        [],
+        |row| row.get(0),
+    )?;
+    if table_exists && !has_timeline_filter_columns(conn) {
+        let tx = conn.unchecked_transaction()?;
+        tx.execute_batch(&format!(
+            "ALTER TABLE repository_timeline_entries RENAME TO repository_timeline_entries_legacy;
+             {TIMELINE_TABLE_SQL};
+             INSERT INTO repository_timeline_entries (commit_sha, branch, captured_at, payload, diff)
+                 SELECT commit_sha, branch, captured_at, payload, diff
+                 FROM repository_timeline_entries_legacy;
+             DROP TABLE repository_timeline_entries_legacy;"
+        ))?;
+        tx.commit()?;
+    }
+    conn.execute_batch(TIMELINE_TABLE_SQL)?;
+    if !has_timeline_column(conn, "diff_tombstone") {
+        conn.execute_batch(
+            "ALTER TABLE repository_timeline_entries ADD COLUMN diff_tombstone TEXT",
+        )?;
+    }
+    Ok(())
+}
+
+pub(crate) fn has_timeline_filter_columns(conn: &Connection) -> bool {
+    has_timeline_column(conn, "filter_fingerprint")
+}
+
+fn has_timeline_column(conn: &Connection, column: &str) -> bool {
+    let Ok(mut stmt) = conn.prepare("PRAGMA table_info(repository_timeline_entries)") else {
+        return false;
+    };
+    let Ok(names) = stmt.query_map([], |row| row.get::<_, String>(1)) else {
+        return false;
+    };
+    names.flatten().any(|name| name == column)
+}
+
+fn env_limit(key: &str, default: u64) -> u64 {
+    std::env::var(key)
+        .ok()
+        .and_then(|value| value.trim().parse().ok())
+        .unwrap_or(default)
+}
+
+/// Replaces cached diffs past the age limit, then the oldest captures beyond
+/// the byte budget, with tombstones. Returns `None` when there is no
+/// timeline table to compact.
+fn enforce_diff_retention(
+    db_path: &Path,
+    max_bytes: u64,
+    max_age_days: u64,
+    now: i64,
+) -> Result<Option<DiffRetentionReport>, rusqlite::Error> {
+    if !db_path.is_file() || is_read_only_media(db_path) {
+        return Ok(None);
+    }
+    let conn = Connection::open(db_path)?;
+    if !has_timeline_column(&conn, "diff_tombstone") {
+        return Ok(None);
+    }
+
+    let mut report = DiffRetentionReport {
+        max_diff_bytes: max_bytes,
+        max_diff_age_days: max_age_days,
+        ..DiffRetentionReport::default()
+    };
+    let mut stmt = conn.prepare(
+        "SELECT commit_sha, filter_fingerprint, captured_at, length(CAST(diff AS BLOB))
+         FROM repository_timeline_entries
+         WHERE diff IS NOT NULL
+         ORDER BY captured_at DESC, commit_sha",
+    )?;
+    let rows = stmt
+        .query_map([], |row| {
+            Ok((
+                row.get::<_, String>(0)?,
+                row.get::<_, String>(1)?,
+                row.get::<_, i64>(2)?,
+                row.get::<_, i64>(3)?.max(0) as u64,
+            ))
+        })?
+        .collect::<Result<Vec<_>, _>>()?;
+    drop(stmt);
+
+    let age_cutoff = (max_age_days > 0).then(|| now - max_age_days as i64 * DAY_MS);
+    let mut evictions = Vec::new();
+    for (sha, fingerprint, captured_at, bytes) in rows {
+        let reason = if age_cutoff.is_some_and(|cutoff| captured_at < cutoff) {
+            report.evicted_by_age += 1;
+            DiffEvictionReason::MaxAge
+        } else if max_bytes > 0 && report.retained_bytes + bytes > max_bytes {
+            report.evicted_by_size += 1;
+            DiffEvictionReason::MaxTotalBytes
+        } else {
+            report.retained_bytes += bytes;
+            continue;
+        };
+        report.freed_bytes += bytes;
+        let tombstone = DiffTombstone {
+            reason,
+            evicted_at: now,
+            original_bytes: bytes,
+        };
+        evictions.push((sha, fingerprint, tombstone));
+    }
+    if evictions.is_empty() {
+        return Ok(Some(report));
+    }
+
+    let tx = conn.unchecked_transaction()?;
+    {
+        let mut update = tx.prepare(
+            "UPDATE repository_timeline_entries SET diff = NULL, diff_tombstone = ?3
+             WHERE commit_sha = ?1 AND filter_fingerprint = ?2",
+        )?;
+        for (sha, fingerprint, tombstone) in &evictions {
+            let tombstone = serde_json::to_string(tombstone).unwrap_or_default();
+            update.execute(params![sha, fingerprint, tombstone])?;
+        }
+    }
+    tx.commit()?;
+    Ok(Some(report))
+}
+
+fn timeline_filters(paths: Option<&[String]>, include_file_stats: bool) -> Option<TimelineFilters> {
+    let mut paths: Vec<String> = paths
+        .unwrap_or_default()
+        .iter()
+        .map(|value| value.trim().to_string())
+        .filter(|value| !value.is_empty())
+        .collect();
+    paths.sort();
+    paths.dedup();
+    if paths.is_empty() && include_file_stats {
+        return None;
+    }
+    Some(TimelineFilters {
+        paths,
+        include_file_stats,
+    })
+}
+
+// empty for unfiltered runs so they share one row per commit
+fn filter_fingerprint(filters: Option<&TimelineFilters>) -> String {
+    let Some(filters) = filters else {
+        return String::new();
+    };
+    let canonical = serde_json::to_string(filters).unwrap_or_default();
+    hex::encode(Sha256::digest(canonical.as_bytes()))[..16].to_string()
+}
+
 fn resolve_database_path(root: &Path, database_name: Option<&str>) -> PathBuf {
-    let filename = database_name.unwrap_or(DEFAULT_DB_FILENAME);
-    root.join(filename)
+    database_path(root, database_name.unwrap_or(DEFAULT_DB_FILENAME))
 }
 
 fn current_time_millis() -> i64 {
@@ -998,7 +1269,7 @@ fn current_time_millis() -> i64 {
         .unwrap_or(0)
 }
 
-fn normalize_remote_url(raw: Option<String>) -> Option<String> {
+pub(crate) fn normalize_remote_url(raw: Option<String>) -> Option<String> {
     let value = raw?.trim().to_string();
     if value.is_empty() {
         return None;
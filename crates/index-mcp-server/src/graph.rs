@@ -1,3 +1,5 @@
+use std::collections::HashMap;
+
 use serde::Serialize;
 use serde_json::Value;
 use sha2::{Digest, Sha256};
@@ -6,6 +8,8 @@ use swc_ecma_ast::*;
 use swc_ecma_parser::{lexer::Lexer, Parser, StringInput, Syntax, TsSyntax};
 use swc_ecma_visit::{noop_visit_type, Visit, VisitWith};
 
+use crate::language_pack::pack_for_path;
+
 #[derive(Debug, Serialize, Clone)]
 pub struct GraphNode {
     pub id: String,
@@ -35,7 +39,51 @@ pub struct GraphExtraction {
     pub edges: Vec<GraphEdge>,
 }
 
+/// Graph for one file from its language pack. Files no pack claims a graph
+/// for still go through the TypeScript parser, which accepts plain
+/// JavaScript and rejects most everything else.
 pub fn extract_graph(relative_path: &str, source: &str) -> Option<GraphExtraction> {
+    let mut extraction = match pack_for_path(relative_path).filter(|pack| pack.provides_graph()) {
+        Some(pack) => pack.extract_graph(relative_path, source),
+        None => extract_script_graph(relative_path, source),
+    }?;
+    merge_duplicate_definitions(&mut extraction);
+    Some(extraction)
+}
+
+/// Nodes are unique per path, kind, and name, so a file with two same-named
+/// definitions (`fmt` in two impls, two class constructors) keeps the first
+/// and moves the others' edges onto it.
+fn merge_duplicate_definitions(extraction: &mut GraphExtraction) {
+    let mut kept: HashMap<(Option<String>, String, String), String> = HashMap::new();
+    let mut merged: HashMap<String, String> = HashMap::new();
+    extraction.nodes.retain(|node| {
+        let key = (node.path.clone(), node.kind.clone(), node.name.clone());
+        match kept.get(&key) {
+            Some(first) if node.path.is_some() && *first != node.id => {
+                merged.insert(node.id.clone(), first.clone());
+                false
+            }
+            _ => {
+                kept.entry(key).or_insert_with(|| node.id.clone());
+                true
+            }
+        }
+    });
+    if merged.is_empty() {
+        return;
+    }
+    for edge in &mut extraction.edges {
+        if let Some(first) = merged.get(&edge.source_id) {
+            edge.source_id = first.clone();
+        }
+        if let Some(first) = merged.get(&edge.target_id) {
+            edge.target_id = first.clone();
+        }
+    }
+}
+
+pub(crate) fn extract_script_graph(relative_path: &str, source: &str) -> Option<GraphExtraction> {
     let cm: Lrc<SourceMap> = Default::default();
     let fm = cm.new_source_file(
         FileName::Custom(relative_path.to_string()).into(),
@@ -61,24 +109,70 @@ pub fn extract_graph(relative_path: &str, source: &str) -> Option<GraphExtractio
         Err(_) => return None,
     };
 
-    let mut extractor = GraphExtractor::new(relative_path.to_string());
+    let mut extractor = GraphExtractor::new(relative_path.to_string(), source, fm.start_pos.0);
     module.visit_with(&mut extractor);
 
-    let (nodes, edges) = extractor.into_parts();
+    let (mut nodes, mut edges) = extractor.into_parts();
+    link_local_calls(&mut nodes, &mut edges);
 
     Some(GraphExtraction { nodes, edges })
 }
 
-struct GraphExtractor {
+/// Points calls at a same-file definition the extractor had not reached yet
+/// when it saw the call, and drops symbol nodes nothing calls any more.
+pub(crate) fn link_local_calls(nodes: &mut Vec<GraphNode>, edges: &mut [GraphEdge]) {
+    let mut definitions: HashMap<&str, (&str, Option<&str>)> = HashMap::new();
+    let mut symbols: HashMap<&str, &str> = HashMap::new();
+    for node in nodes.iter() {
+        match node.kind.as_str() {
+            "symbol" => {
+                symbols.insert(node.id.as_str(), node.name.as_str());
+            }
+            "function" | "method" | "constructor" => {
+                definitions
+                    .entry(node.name.as_str())
+                    .or_insert((node.id.as_str(), node.path.as_deref()));
+            }
+            _ => {}
+        }
+    }
+    let mut retargeted = Vec::new();
+    for (index, edge) in edges.iter().enumerate() {
+        if let Some((id, path)) = symbols
+            .get(edge.target_id.as_str())
+            .and_then(|name| definitions.get(name))
+        {
+            retargeted.push((index, id.to_string(), path.map(str::to_string)));
+        }
+    }
+    for (index, target_id, target_path) in retargeted {
+        edges[index].target_id = target_id;
+        edges[index].target_path = target_path;
+    }
+    nodes
+        .retain(|node| node.kind != "symbol" || edges.iter().any(|edge| edge.target_id == node.id));
+}
+
+/// Id of the global node a call to `name` points at when the callee is not
+/// defined in the caller's file.
+pub(crate) fn symbol_node_id(name: &str) -> String {
+    stable_id(&["symbol", name])
+}
+
+struct GraphExtractor<'src> {
     file_path: String,
+    source: &'src str,
+    source_start: u32,
     nodes: Vec<GraphNode>,
     edges: Vec<GraphEdge>,
     scope_stack: Vec<String>,
-    symbol_index: std::collections::HashMap<String, String>,
+    // decision points seen in each open function scope, innermost last
+    branch_counts: Vec<usize>,
+    symbol_index: HashMap<String, String>,
 }
 
-impl GraphExtractor {
-    fn new(file_path: String) -> Self {
+impl<'src> GraphExtractor<'src> {
+    fn new(file_path: String, source: &'src str, source_start: u32) -> Self {
         let file_id = stable_id(&["file", &file_path]);
         let file_node = GraphNode {
             id: file_id.clone(),
@@ -92,10 +186,13 @@ impl GraphExtractor {
         };
         Self {
             file_path,
+            source,
+            source_start,
             nodes: vec![file_node],
             edges: Vec::new(),
             scope_stack: vec![file_id],
-            symbol_index: std::collections::HashMap::new(),
+            branch_counts: Vec::new(),
+            symbol_index: HashMap::new(),
         }
     }
 
@@ -109,10 +206,38 @@ impl GraphExtractor {
 
     fn push_scope(&mut self, id: String) {
         self.scope_stack.push(id);
+        self.branch_counts.push(0);
     }
 
     fn pop_scope(&mut self) {
-        self.scope_stack.pop();
+        let (Some(id), Some(branches)) = (self.scope_stack.pop(), self.branch_counts.pop()) else {
+            return;
+        };
+        // nested definitions sit after the function node, so search backwards
+        if let Some(node) = self.nodes.iter_mut().rev().find(|node| node.id == id) {
+            if let Some(metrics) = node
+                .metadata
+                .as_mut()
+                .and_then(|metadata| metadata.get_mut("metrics"))
+            {
+                metrics["branchCount"] = serde_json::json!(branches);
+            }
+        }
+    }
+
+    fn record_branch(&mut self) {
+        if let Some(count) = self.branch_counts.last_mut() {
+            *count += 1;
+        }
+    }
+
+    fn line_count(&self, span: Span) -> usize {
+        let start = span.lo.0.saturating_sub(self.source_start) as usize;
+        let end = span.hi.0.saturating_sub(self.source_start) as usize;
+        self.source
+            .get(start..end)
+            .map(|text| text.lines().count().max(1))
+            .unwrap_or(0)
     }
 
     fn span_offsets(&self, span: Span) -> (Option<i64>, Option<i64>) {
@@ -133,6 +258,11 @@ impl GraphExtractor {
         let metadata = serde_json::json!({
             "async": is_async,
             "generator": is_generator,
+            "metrics": {
+                "lineCount": self.line_count(span),
+                "parameterCount": param_count,
+                "branchCount": 0,
+            },
         });
         let id = stable_id(&[kind, &self.file_path, name, &format!("{:?}", start)]);
         self.nodes.push(GraphNode {
@@ -155,7 +285,7 @@ impl GraphExtractor {
         if let Some(id) = self.symbol_index.get(name) {
             return id.clone();
         }
-        let id = stable_id(&["symbol", name]);
+        let id = symbol_node_id(name);
         self.nodes.push(GraphNode {
             id: id.clone(),
             path: None,
@@ -202,7 +332,7 @@ impl GraphExtractor {
     }
 }
 
-impl Visit for GraphExtractor {
+impl Visit for GraphExtractor<'_> {
     noop_visit_type!();
 
     fn visit_fn_decl(&mut self, node: &FnDecl) {
@@ -285,9 +415,66 @@ impl Visit for GraphExtractor {
     fn visit_module_item(&mut self, node: &ModuleItem) {
         node.visit_children_with(self);
     }
+
+    fn visit_if_stmt(&mut self, node: &IfStmt) {
+        self.record_branch();
+        node.visit_children_with(self);
+    }
+
+    fn visit_cond_expr(&mut self, node: &CondExpr) {
+        self.record_branch();
+        node.visit_children_with(self);
+    }
+
+    fn visit_for_stmt(&mut self, node: &ForStmt) {
+        self.record_branch();
+        node.visit_children_with(self);
+    }
+
+    fn visit_for_in_stmt(&mut self, node: &ForInStmt) {
+        self.record_branch();
+        node.visit_children_with(self);
+    }
+
+    fn visit_for_of_stmt(&mut self, node: &ForOfStmt) {
+        self.record_branch();
+        node.visit_children_with(self);
+    }
+
+    fn visit_while_stmt(&mut self, node: &WhileStmt) {
+        self.record_branch();
+        node.visit_children_with(self);
+    }
+
+    fn visit_do_while_stmt(&mut self, node: &DoWhileStmt) {
+        self.record_branch();
+        node.visit_children_with(self);
+    }
+
+    fn visit_switch_case(&mut self, node: &SwitchCase) {
+        if node.test.is_some() {
+            self.record_branch();
+        }
+        node.visit_children_with(self);
+    }
+
+    fn visit_catch_clause(&mut self, node: &CatchClause) {
+        self.record_branch();
+        node.visit_children_with(self);
+    }
+
+    fn visit_bin_expr(&mut self, node: &BinExpr) {
+        if matches!(
+            node.op,
+            BinaryOp::LogicalAnd | BinaryOp::LogicalOr | BinaryOp::NullishCoalescing
+        ) {
+            self.record_branch();
+        }
+        node.visit_children_with(self);
+    }
 }
 
-fn stable_id(inputs: &[&str]) -> String {
+pub(crate) fn stable_id(inputs: &[&str]) -> String {
     let mut hasher = Sha256::new();
     for input in inputs {
         hasher.update(input.as_bytes());
@@ -0,0 +1,254 @@
+use std::collections::HashSet;
+use std::path::PathBuf;
+
+use rmcp::schemars::{self, JsonSchema};
+use rusqlite::{params, Connection};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use thiserror::Error;
+
+use crate::bundle::{
+    load_neighbor_node, load_node_edges, resolve_symbol_neighbor, NeighborDirection, NeighborNode,
+};
+use crate::data_dir::{database_path, open_read_only};
+use crate::index_status::DEFAULT_DB_FILENAME;
+use crate::roots::resolve_workspace_root;
+use crate::sandbox::{ensure_root_allowed, SandboxError};
+
+const DEFAULT_DEPTH: u32 = 1;
+const MAX_DEPTH: u32 = 2;
+const DEFAULT_LIMIT: usize = 50;
+const MAX_LIMIT: usize = 200;
+// a path or a common name can match many definitions; traverse from this many
+const MAX_START_NODES: usize = 50;
+
+#[derive(Debug, Deserialize, JsonSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct GraphNeighborsParams {
+    #[serde(default)]
+    pub root: Option<String>,
+    #[serde(default)]
+    pub database_name: Option<String>,
+    /// File whose definitions to start from; with `symbol`, only the ones
+    /// with that name.
+    #[serde(default)]
+    pub path: Option<String>,
+    /// Function or method name to start from.
+    #[serde(default)]
+    pub symbol: Option<String>,
+    /// Graph node id, as returned by context_bundle or a previous call.
+    #[serde(default)]
+    pub node_id: Option<String>,
+    /// Hops to traverse (default 1, max 2). The second hop keeps the first
+    /// hop's direction: callers of callers, callees of callees.
+    #[serde(default)]
+    pub depth: Option<u32>,
+    /// Only follow edges this way; both when omitted.
+    #[serde(default)]
+    pub direction: Option<NeighborDirection>,
+    /// Only follow edges of this type, e.g. `calls`.
+    #[serde(default)]
+    pub edge_type: Option<String>,
+    /// Maximum neighbors returned (default 50, max 200).
+    #[serde(default)]
+    pub limit: Option<u32>,
+}
+
+#[derive(Debug, Clone, Serialize, JsonSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct GraphNeighbor {
+    /// 1 for direct neighbors, 2 for neighbors of those.
+    pub hop: u32,
+    pub direction: NeighborDirection,
+    pub edge_type: String,
+    /// Node this neighbor was reached from.
+    pub via: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub edge_metadata: Option<Value>,
+    pub node: NeighborNode,
+}
+
+#[derive(Debug, Clone, Serialize, JsonSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct GraphNeighborsResponse {
+    pub database_path: String,
+    pub depth: u32,
+    pub start: Vec<NeighborNode>,
+    pub neighbors: Vec<GraphNeighbor>,
+    /// `limit` stopped the traversal before every neighbor was visited.
+    pub truncated: bool,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub note: Option<String>,
+}
+
+#[derive(Debug, Error)]
+pub enum GraphNeighborsError {
+    #[error("failed to resolve workspace root '{path}': {source}")]
+    InvalidRoot {
+        path: String,
+        #[source]
+        source: std::io::Error,
+    },
+    #[error("graph_neighbors needs a path, symbol, or nodeId to start from")]
+    MissingTarget,
+    #[error("SQLite error: {0}")]
+    Sqlite(#[from] rusqlite::Error),
+    #[error(transparent)]
+    Sandbox(#[from] SandboxError),
+    #[error("blocking task panicked: {0}")]
+    Join(#[from] tokio::task::JoinError),
+}
+
+pub async fn graph_neighbors(
+    params: GraphNeighborsParams,
+) -> Result<GraphNeighborsResponse, GraphNeighborsError> {
+    tokio::task::spawn_blocking(move || perform_graph_neighbors(params)).await?
+}
+
+fn perform_graph_neighbors(
+    params: GraphNeighborsParams,
+) -> Result<GraphNeighborsResponse, GraphNeighborsError> {
+    let path = params
+        .path
+        .as_deref()
+        .map(|path| path.trim().trim_start_matches("./"))
+        .filter(|path| !path.is_empty());
+    let symbol = params
+        .symbol
+        .as_deref()
+        .map(str::trim)
+        .filter(|symbol| !symbol.is_empty());
+    let node_id = params
+        .node_id
+        .as_deref()
+        .map(str::trim)
+        .filter(|id| !id.is_empty());
+    if path.is_none() && symbol.is_none() && node_id.is_none() {
+        return Err(GraphNeighborsError::MissingTarget);
+    }
+
+    let root = params.root.clone().unwrap_or_else(|| "./".to_string());
+    let root_path = resolve_root(&root)?;
+    ensure_root_allowed(&root_path)?;
+    let database_path = database_path(
+        &root_path,
+        params
+            .database_name
+            .as_deref()
+            .unwrap_or(DEFAULT_DB_FILENAME),
+    );
+    let depth = params.depth.unwrap_or(DEFAULT_DEPTH).clamp(1, MAX_DEPTH);
+    let limit = params
+        .limit
+        .map_or(DEFAULT_LIMIT, |value| value as usize)
+        .clamp(1, MAX_LIMIT);
+
+    let mut response = GraphNeighborsResponse {
+        database_path: database_path.to_string_lossy().to_string(),
+        depth,
+        start: Vec::new(),
+        neighbors: Vec::new(),
+        truncated: false,
+        note: None,
+    };
+    if !database_path.is_file() {
+        response.note = Some("No index yet; run ingest_codebase first.".to_string());
+        return Ok(response);
+    }
+
+    let conn = open_read_only(&database_path)?;
+    response.start = match node_id {
+        Some(id) => load_neighbor_node(&conn, id).into_iter().collect(),
+        None => load_start_nodes(&conn, path, symbol)?,
+    };
+    if response.start.is_empty() {
+        response.note = Some(
+            "No graph node matched; the file may not be indexed or its language has no graph extractor."
+                .to_string(),
+        );
+        return Ok(response);
+    }
+
+    let mut visited: HashSet<String> = response.start.iter().map(|node| node.id.clone()).collect();
+    // (node, direction it was reached in; None for start nodes)
+    let mut frontier: Vec<(NeighborNode, Option<NeighborDirection>)> = response
+        .start
+        .iter()
+        .map(|node| (node.clone(), None))
+        .collect();
+    'hops: for hop in 1..=depth {
+        let mut next = Vec::new();
+        for (node, reached) in &frontier {
+            let name = is_definition(&node.kind).then_some(node.name.as_str());
+            for edge in load_node_edges(&conn, &node.id, name)? {
+                let direction_allowed = reached
+                    .or(params.direction)
+                    .is_none_or(|wanted| wanted == edge.direction);
+                let type_allowed = params
+                    .edge_type
+                    .as_deref()
+                    .is_none_or(|wanted| wanted == edge.edge_type);
+                if !direction_allowed || !type_allowed {
+                    continue;
+                }
+                let Some(neighbor) = load_neighbor_node(&conn, &edge.other_id) else {
+                    continue;
+                };
+                let neighbor = resolve_symbol_neighbor(&conn, neighbor, node.path.as_deref());
+                if !visited.insert(neighbor.id.clone()) {
+                    continue;
+                }
+                if response.neighbors.len() >= limit {
+                    response.truncated = true;
+                    break 'hops;
+                }
+                response.neighbors.push(GraphNeighbor {
+                    hop,
+                    direction: edge.direction,
+                    edge_type: edge.edge_type,
+                    via: node.id.clone(),
+                    edge_metadata: edge.metadata,
+                    node: neighbor.clone(),
+                });
+                if neighbor.kind != "symbol" {
+                    next.push((neighbor, Some(edge.direction)));
+                }
+            }
+        }
+        frontier = next;
+    }
+    Ok(response)
+}
+
+fn load_start_nodes(
+    conn: &Connection,
+    path: Option<&str>,
+    symbol: Option<&str>,
+) -> Result<Vec<NeighborNode>, GraphNeighborsError> {
+    let mut stmt = conn.prepare(
+        "SELECT id FROM code_graph_nodes
+         WHERE (?1 IS NULL OR path = ?1) AND (?2 IS NULL OR name = ?2) AND path IS NOT NULL
+         ORDER BY path, range_start
+         LIMIT ?3",
+    )?;
+    let ids = stmt
+        .query_map(params![path, symbol, MAX_START_NODES as i64], |row| {
+            row.get::<_, String>(0)
+        })?
+        .collect::<Result<Vec<_>, _>>()?;
+    Ok(ids
+        .iter()
+        .filter_map(|id| load_neighbor_node(conn, id))
+        .collect())
+}
+
+fn is_definition(kind: &str) -> bool {
+    matches!(kind, "function" | "method" | "constructor")
+}
+
+fn resolve_root(root: &str) -> Result<PathBuf, GraphNeighborsError> {
+    resolve_workspace_root(root).map_err(|source| GraphNeighborsError::InvalidRoot {
+        path: root.to_string(),
+        source,
+    })
+}
@@ -0,0 +1,200 @@
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+
+use once_cell::sync::Lazy;
+use rmcp::schemars::{self, JsonSchema};
+use serde::{Deserialize, Serialize};
+use thiserror::Error;
+
+use crate::data_dir::{database_path, open_read_only};
+use crate::index_status::DEFAULT_DB_FILENAME;
+use crate::ingest_queue::{queue_snapshot, IngestJobStatus};
+use crate::roots::resolve_workspace_root;
+use crate::sandbox::{ensure_root_allowed, SandboxError};
+
+static EMBEDDER_STATE: Lazy<Mutex<ComponentHealth>> =
+    Lazy::new(|| Mutex::new(ComponentHealth::new(ComponentState::Pending)));
+static WATCHER_STATE: Lazy<Mutex<ComponentHealth>> =
+    Lazy::new(|| Mutex::new(ComponentHealth::new(ComponentState::Disabled)));
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, JsonSchema)]
+#[serde(rename_all = "camelCase")]
+pub enum ComponentState {
+    Pending,
+    Ready,
+    Running,
+    Stopped,
+    Disabled,
+    Failed,
+}
+
+#[derive(Debug, Clone, Serialize, JsonSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct ComponentHealth {
+    pub state: ComponentState,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub detail: Option<String>,
+}
+
+impl ComponentHealth {
+    fn new(state: ComponentState) -> Self {
+        Self {
+            state,
+            detail: None,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, JsonSchema)]
+#[serde(rename_all = "camelCase")]
+pub enum DatabaseState {
+    Accessible,
+    /// Nothing ingested yet; the server can still accept ingest calls.
+    Missing,
+    Unreadable,
+}
+
+#[derive(Debug, Clone, Serialize, JsonSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct DatabaseHealth {
+    pub path: String,
+    pub state: DatabaseState,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+}
+
+#[derive(Debug, Deserialize, JsonSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct HealthParams {
+    #[serde(default)]
+    pub root: Option<String>,
+    #[serde(default)]
+    pub database_name: Option<String>,
+}
+
+/// Liveness is implied by answering at all; `ready` is what an HTTP
+/// `/readyz` probe would report.
+#[derive(Debug, Clone, Serialize, JsonSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct HealthResponse {
+    pub ready: bool,
+    pub database: DatabaseHealth,
+    pub embedder: ComponentHealth,
+    pub watcher: ComponentHealth,
+}
+
+#[derive(Debug, Clone, Serialize, JsonSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct WatcherStatusResponse {
+    pub watcher: ComponentHealth,
+    /// Running ingests first; targeted ingests wait ahead of full passes.
+    pub ingest_queue: Vec<IngestJobStatus>,
+}
+
+#[derive(Debug, Error)]
+pub enum HealthError {
+    #[error("failed to resolve workspace root '{path}': {source}")]
+    InvalidRoot {
+        path: String,
+        #[source]
+        source: std::io::Error,
+    },
+    #[error(transparent)]
+    Sandbox(#[from] SandboxError),
+    #[error("blocking task panicked: {0}")]
+    Join(#[from] tokio::task::JoinError),
+}
+
+pub(crate) fn record_embedder_state(state: ComponentState, detail: Option<String>) {
+    if let Ok(mut guard) = EMBEDDER_STATE.lock() {
+        *guard = ComponentHealth { state, detail };
+    }
+}
+
+pub(crate) fn record_watcher_state(state: ComponentState, detail: Option<String>) {
+    if let Ok(mut guard) = WATCHER_STATE.lock() {
+        *guard = ComponentHealth { state, detail };
+    }
+}
+
+pub(crate) fn watcher_status() -> WatcherStatusResponse {
+    WatcherStatusResponse {
+        watcher: snapshot(&WATCHER_STATE),
+        ingest_queue: queue_snapshot(),
+    }
+}
+
+pub async fn check_health(params: HealthParams) -> Result<HealthResponse, HealthError> {
+    tokio::task::spawn_blocking(move || compute_health(params)).await?
+}
+
+fn compute_health(params: HealthParams) -> Result<HealthResponse, HealthError> {
+    let root = params.root.unwrap_or_else(|| "./".to_string());
+    let root_path = resolve_root(&root)?;
+    ensure_root_allowed(&root_path)?;
+    let database_path = database_path(
+        &root_path,
+        &params
+            .database_name
+            .unwrap_or_else(|| DEFAULT_DB_FILENAME.to_string()),
+    );
+
+    let database = probe_database(&database_path);
+    let embedder = snapshot(&EMBEDDER_STATE);
+    let watcher = snapshot(&WATCHER_STATE);
+    let ready = database.state != DatabaseState::Unreadable
+        && embedder.state == ComponentState::Ready
+        && watcher.state != ComponentState::Failed;
+
+    Ok(HealthResponse {
+        ready,
+        database,
+        embedder,
+        watcher,
+    })
+}
+
+fn probe_database(path: &Path) -> DatabaseHealth {
+    let path_string = path.to_string_lossy().to_string();
+    if !path.is_file() {
+        return DatabaseHealth {
+            path: path_string,
+            state: DatabaseState::Missing,
+            error: None,
+        };
+    }
+    let result = open_read_only(path).and_then(|conn| {
+        conn.query_row("SELECT COUNT(*) FROM sqlite_master", [], |row| {
+            row.get::<_, i64>(0)
+        })
+    });
+    match result {
+        Ok(_) => DatabaseHealth {
+            path: path_string,
+            state: DatabaseState::Accessible,
+            error: None,
+        },
+        Err(error) => DatabaseHealth {
+            path: path_string,
+            state: DatabaseState::Unreadable,
+            error: Some(error.to_string()),
+        },
+    }
+}
+
+fn snapshot(state: &Mutex<ComponentHealth>) -> ComponentHealth {
+    state
+        .lock()
+        .map(|guard| guard.clone())
+        .unwrap_or_else(|_| ComponentHealth {
+            state: ComponentState::Failed,
+            detail: Some("health state lock poisoned".to_string()),
+        })
+}
+
+fn resolve_root(root: &str) -> Result<PathBuf, HealthError> {
+    resolve_workspace_root(root).map_err(|source| HealthError::InvalidRoot {
+        path: root.to_string(),
+        source,
+    })
+}
@@ -0,0 +1,93 @@
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+use std::time::Duration;
+
+use once_cell::sync::Lazy;
+use rusqlite::{params, Connection, OpenFlags};
+
+use crate::data_dir::is_read_only_media;
+
+const FLUSH_BUSY_TIMEOUT: Duration = Duration::from_secs(2);
+
+/// Chunk hit increments waiting to be written, keyed by database then chunk
+/// id. Counts for the same chunk coalesce, so the queue is bounded by the
+/// number of distinct chunks served between flushes.
+static PENDING_HITS: Lazy<Mutex<HashMap<PathBuf, HashMap<String, i64>>>> =
+    Lazy::new(|| Mutex::new(HashMap::new()));
+
+/// Queues hit increments instead of updating rows on the search path.
+/// Databases on read-only media keep no counters.
+pub(crate) fn record_chunk_hits(database_path: &Path, chunk_ids: Vec<String>) {
+    if chunk_ids.is_empty() || is_read_only_media(database_path) {
+        return;
+    }
+    let Ok(mut pending) = PENDING_HITS.lock() else {
+        return;
+    };
+    let counts = pending.entry(database_path.to_path_buf()).or_default();
+    for id in chunk_ids {
+        *counts.entry(id).or_insert(0) += 1;
+    }
+}
+
+/// Writes every queued increment. Called on an interval and at shutdown.
+pub(crate) fn flush_pending_hits() {
+    let drained = match PENDING_HITS.lock() {
+        Ok(mut pending) => std::mem::take(&mut *pending),
+        Err(_) => return,
+    };
+    for (database_path, counts) in drained {
+        flush_database(database_path, counts);
+    }
+}
+
+/// Flushes one database ahead of work that reads `hits`, such as eviction.
+pub(crate) fn flush_pending_hits_for(database_path: &Path) {
+    let counts = match PENDING_HITS.lock() {
+        Ok(mut pending) => pending.remove(database_path),
+        Err(_) => None,
+    };
+    if let Some(counts) = counts {
+        flush_database(database_path.to_path_buf(), counts);
+    }
+}
+
+fn flush_database(database_path: PathBuf, counts: HashMap<String, i64>) {
+    if counts.is_empty() {
+        return;
+    }
+    let Err(error) = write_hits(&database_path, &counts) else {
+        return;
+    };
+    tracing::warn!(
+        ?error,
+        path = %database_path.display(),
+        pending = counts.len(),
+        "Failed to flush chunk hit counters"
+    );
+    // databases removed since the search have nowhere to put their hits
+    if !database_path.exists() {
+        return;
+    }
+    if let Ok(mut pending) = PENDING_HITS.lock() {
+        let queued = pending.entry(database_path).or_default();
+        for (id, count) in counts {
+            *queued.entry(id).or_insert(0) += count;
+        }
+    }
+}
+
+fn write_hits(database_path: &Path, counts: &HashMap<String, i64>) -> Result<(), rusqlite::Error> {
+    let mut conn = Connection::open_with_flags(database_path, OpenFlags::SQLITE_OPEN_READ_WRITE)?;
+    conn.busy_timeout(FLUSH_BUSY_TIMEOUT)?;
+    let transaction = conn.transaction()?;
+    {
+        let mut stmt = transaction
+            .prepare("UPDATE file_chunks SET hits = COALESCE(hits, 0) + ?2 WHERE id = ?1")?;
+        for (id, count) in counts {
+            stmt.execute(params![id, count])?;
+        }
+    }
+    transaction.commit()
+}
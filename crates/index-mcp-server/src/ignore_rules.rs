@@ -0,0 +1,277 @@
+use std::fs;
+use std::path::{Component, Path, PathBuf};
+
+use ignore::gitignore::{Gitignore, GitignoreBuilder};
+use ignore::{Match, WalkBuilder};
+use rmcp::schemars::{self, JsonSchema};
+use serde::Serialize;
+
+#[derive(Debug, Clone, Serialize, JsonSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct GitignoreMatch {
+    /// Ignore file that holds the deciding rule, relative to the root when
+    /// possible.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub source: Option<String>,
+    pub pattern: String,
+    /// The rule is a `!` negation that re-includes the path.
+    pub whitelisted: bool,
+}
+
+/// Walks `path` (inside `root`) with the ignore sources `gitignore_match`
+/// consults. Hidden entries below `path` are skipped unless `include_hidden`.
+pub(crate) fn build_ignore_walk(
+    root: &Path,
+    path: &Path,
+    is_dir: bool,
+    respect_gitignore: bool,
+    include_hidden: bool,
+) -> ignore::Walk {
+    let mut builder = WalkBuilder::new(path);
+    builder.follow_links(false);
+    builder.hidden(!include_hidden);
+    builder.git_ignore(respect_gitignore);
+    builder.git_exclude(respect_gitignore);
+    let repo_excludes = if respect_gitignore {
+        repo_excludes_matcher(root)
+    } else {
+        None
+    };
+    // a repository's core.excludesFile replaces the global one, as in git
+    builder.git_global(respect_gitignore && repo_excludes.is_none());
+    if let Some(matcher) = repo_excludes {
+        builder.filter_entry(move |entry| {
+            let is_dir = entry.file_type().is_some_and(|kind| kind.is_dir());
+            !matcher
+                .matched_path_or_any_parents(entry.path(), is_dir)
+                .is_ignore()
+        });
+    }
+    if !is_dir {
+        builder.max_depth(Some(1));
+    }
+    builder.build()
+}
+
+/// Finds the rule that decides whether git ignores `path`: per-directory
+/// `.gitignore` files (deepest wins), then `.git/info/exclude`, then the
+/// repository's `core.excludesFile` or, without one, the global excludes
+/// file. Rules on a parent directory count, since git never descends into
+/// an ignored directory.
+pub(crate) fn gitignore_match(root: &Path, path: &Path, is_dir: bool) -> Option<GitignoreMatch> {
+    let git_dir = find_git_dir(root)?;
+
+    let mut matchers: Vec<Gitignore> = Vec::new();
+    let directories: Vec<&Path> = path
+        .parent()?
+        .ancestors()
+        .take_while(|dir| dir.starts_with(root))
+        .collect();
+    for dir in directories {
+        let file = dir.join(".gitignore");
+        if file.is_file() {
+            matchers.push(build_matcher(dir, &file));
+        }
+    }
+    if let Some(repo_root) = git_dir.parent() {
+        let exclude = git_dir.join("info").join("exclude");
+        if exclude.is_file() {
+            matchers.push(build_matcher(repo_root, &exclude));
+        }
+    }
+    matchers.push(
+        repo_excludes_matcher(root).unwrap_or_else(|| GitignoreBuilder::new(root).build_global().0),
+    );
+
+    matchers.iter().find_map(|matcher| {
+        let glob = match matcher.matched_path_or_any_parents(path, is_dir) {
+            Match::Ignore(glob) | Match::Whitelist(glob) => glob,
+            Match::None => return None,
+        };
+        Some(GitignoreMatch {
+            source: glob.from().map(|source| {
+                source
+                    .strip_prefix(root)
+                    .unwrap_or(source)
+                    .to_string_lossy()
+                    .replace('\\', "/")
+            }),
+            pattern: glob.original().to_string(),
+            whitelisted: glob.is_whitelist(),
+        })
+    })
+}
+
+/// Whether any component of a root-relative path is a dotfile or
+/// dot-directory.
+pub(crate) fn has_hidden_component(relative: &Path) -> bool {
+    relative.components().any(|component| match component {
+        Component::Normal(name) => name.to_string_lossy().starts_with('.'),
+        _ => false,
+    })
+}
+
+fn find_git_dir(root: &Path) -> Option<PathBuf> {
+    root.ancestors()
+        .map(|dir| dir.join(".git"))
+        .find(|candidate| candidate.exists())
+}
+
+fn repo_excludes_matcher(root: &Path) -> Option<Gitignore> {
+    let git_dir = find_git_dir(root)?;
+    let repo_root = git_dir.parent()?;
+    let file = repo_excludes_file(&git_dir, repo_root)?;
+    file.is_file().then(|| build_matcher(repo_root, &file))
+}
+
+/// `core.excludesFile` from the repository's own config. The ignore crate
+/// only reads it from the global config.
+fn repo_excludes_file(git_dir: &Path, repo_root: &Path) -> Option<PathBuf> {
+    let config = fs::read_to_string(git_dir.join("config")).ok()?;
+    let mut in_core = false;
+    let mut value = None;
+    for line in config.lines().map(str::trim) {
+        if let Some(section) = line.strip_prefix('[') {
+            in_core = section
+                .trim_end_matches(']')
+                .trim()
+                .eq_ignore_ascii_case("core");
+            continue;
+        }
+        if !in_core {
+            continue;
+        }
+        if let Some((key, raw)) = line.split_once('=') {
+            if key.trim().eq_ignore_ascii_case("excludesfile") {
+                // the last assignment wins
+                value = Some(raw.trim().trim_matches('"').to_string());
+            }
+        }
+    }
+    let value = value.filter(|value| !value.is_empty())?;
+    let path = match value.strip_prefix("~/") {
+        Some(rest) => PathBuf::from(std::env::var_os("HOME")?).join(rest),
+        None => PathBuf::from(value),
+    };
+    Some(if path.is_absolute() {
+        path
+    } else {
+        repo_root.join(path)
+    })
+}
+
+fn build_matcher(dir: &Path, file: &Path) -> Gitignore {
+    let mut builder = GitignoreBuilder::new(dir);
+    if let Some(error) = builder.add(file) {
+        tracing::debug!(?error, path = %file.display(), "Skipping unreadable ignore rules");
+    }
+    builder.build().unwrap_or_else(|_| Gitignore::empty())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    struct Fixture {
+        root: PathBuf,
+    }
+
+    impl Fixture {
+        fn new() -> Self {
+            let root =
+                std::env::temp_dir().join(format!("index-mcp-ignore-{}", uuid::Uuid::new_v4()));
+            fs::create_dir_all(root.join(".git").join("info")).expect("create fixture");
+            Self { root }
+        }
+
+        fn write(&self, relative: &str, contents: &str) {
+            let path = self.root.join(relative);
+            fs::create_dir_all(path.parent().expect("parent")).expect("create parent");
+            fs::write(path, contents).expect("write fixture file");
+        }
+
+        fn walked(&self, include_hidden: bool) -> Vec<String> {
+            let mut paths: Vec<String> =
+                build_ignore_walk(&self.root, &self.root, true, true, include_hidden)
+                    .flatten()
+                    .filter(|entry| entry.file_type().is_some_and(|kind| kind.is_file()))
+                    .filter_map(|entry| {
+                        entry
+                            .path()
+                            .strip_prefix(&self.root)
+                            .ok()
+                            .map(|path| path.to_string_lossy().replace('\\', "/"))
+                    })
+                    .collect();
+            paths.sort();
+            paths
+        }
+
+        fn ignored(&self, relative: &str) -> bool {
+            gitignore_match(&self.root, &self.root.join(relative), false)
+                .is_some_and(|rule| !rule.whitelisted)
+        }
+    }
+
+    impl Drop for Fixture {
+        fn drop(&mut self) {
+            let _ = fs::remove_dir_all(&self.root);
+        }
+    }
+
+    fn fixture_tree() -> Fixture {
+        let fixture = Fixture::new();
+        fixture.write(".gitignore", "*.log\nbuild/\n");
+        fixture.write("src/.gitignore", "generated.rs\n!keep.log\n");
+        fixture.write(".git/info/exclude", "scratch.txt\n");
+        fixture.write(
+            ".git/config",
+            "[core]\n\tbare = false\n\texcludesFile = .repo-excludes\n",
+        );
+        fixture.write(".repo-excludes", "*.bak\n");
+        fixture.write("src/lib.rs", "");
+        fixture.write("src/generated.rs", "");
+        fixture.write("src/keep.log", "");
+        fixture.write("debug.log", "");
+        fixture.write("build/out.rs", "");
+        fixture.write("scratch.txt", "");
+        fixture.write("notes.bak", "");
+        fixture.write(".github/workflows/ci.yml", "");
+        fixture
+    }
+
+    #[test]
+    fn walker_and_explain_agree_on_ignored_paths() {
+        let fixture = fixture_tree();
+        let walked = fixture.walked(false);
+        assert_eq!(walked, vec!["src/keep.log", "src/lib.rs"]);
+
+        for path in [
+            "src/generated.rs",
+            "debug.log",
+            "build/out.rs",
+            "scratch.txt",
+            "notes.bak",
+        ] {
+            assert!(fixture.ignored(path), "{path} should be ignored");
+        }
+        for path in ["src/lib.rs", "src/keep.log"] {
+            assert!(!fixture.ignored(path), "{path} should be indexed");
+        }
+    }
+
+    #[test]
+    fn hidden_files_need_an_explicit_opt_in() {
+        let fixture = fixture_tree();
+        assert!(!fixture
+            .walked(false)
+            .contains(&".github/workflows/ci.yml".to_string()));
+        let walked = fixture.walked(true);
+        assert!(walked.contains(&".github/workflows/ci.yml".to_string()));
+        // ignore rules still apply with hidden files included
+        assert!(!walked.contains(&"notes.bak".to_string()));
+        assert!(!walked.contains(&"build/out.rs".to_string()));
+        assert!(has_hidden_component(Path::new(".github/workflows/ci.yml")));
+        assert!(!has_hidden_component(Path::new("src/lib.rs")));
+    }
+}
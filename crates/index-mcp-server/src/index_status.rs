@@ -1,17 +1,44 @@
 use std::{
+    collections::HashSet,
     fs,
     path::{Path, PathBuf},
     process::Command,
+    time::{SystemTime, UNIX_EPOCH},
 };
 
 use rmcp::schemars::{self, JsonSchema};
-use rusqlite::{params, Connection, OpenFlags};
+use rusqlite::{params, Connection};
 use serde::Serialize;
 use thiserror::Error;
+use walkdir::WalkDir;
+
+use crate::data_dir::{database_dir, database_path, is_read_only_media, open_read_only};
+use crate::ingest::DEFAULT_MAX_DATABASE_SIZE_BYTES;
+use crate::roots::resolve_workspace_root;
+use crate::sandbox::{ensure_root_allowed, SandboxError};
 
 /// Default SQLite filename used by the legacy Node implementation.
 pub const DEFAULT_DB_FILENAME: &str = ".mcp-index.sqlite";
+/// Written to `PRAGMA user_version` by ingest; databases from before schema
+/// versioning report 0. Bump it with every migration in `ensure_schema`:
+///
+/// 1. content encodings, checksums, and `ingestion_skips`
+/// 2. `index_generations`
+/// 3. `chunk_centroids` and `chunk_lists`
+/// 4. `file_chunks.embedding_encoding`
+/// 5. `embedding_cache`
+/// 6. text encoding and line-ending columns on `files`
+/// 7. `file_chunks.language` and `classification`
+pub const SCHEMA_VERSION: i64 = 7;
 const DEFAULT_HISTORY_LIMIT: u32 = 5;
+const DEFAULT_SCAN_DEPTH: u32 = 3;
+// past this a scoped re-ingest is no cheaper than a full one
+const MAX_CHANGED_PATHS: usize = 200;
+const MAX_SCAN_DEPTH: u32 = 8;
+const SKIPPED_SCAN_DIRS: &[&str] = &[".git", "node_modules", "target"];
+const DAY_MS: i64 = 24 * 60 * 60 * 1000;
+const HEALTH_GREEN_THRESHOLD: u8 = 80;
+const HEALTH_YELLOW_THRESHOLD: u8 = 50;
 
 #[derive(Debug, Clone, serde::Deserialize, JsonSchema)]
 #[serde(rename_all = "camelCase")]
@@ -22,6 +49,9 @@ pub struct IndexStatusParams {
     pub database_name: Option<String>,
     #[serde(default)]
     pub history_limit: Option<u32>,
+    /// Cap used to grade database size; defaults to ingest's auto-evict cap.
+    #[serde(default)]
+    pub max_database_size_bytes: Option<f64>,
 }
 
 #[derive(Debug, Clone, Serialize, JsonSchema)]
@@ -37,6 +67,15 @@ pub struct IndexStatusIngestion {
     pub deleted_count: i64,
 }
 
+#[derive(Debug, Clone, Serialize, JsonSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct IndexStatusPackage {
+    pub ecosystem: String,
+    pub name: String,
+    pub root: String,
+    pub file_count: u64,
+}
+
 #[derive(Debug, Clone, Serialize, JsonSchema)]
 #[serde(rename_all = "camelCase")]
 pub struct IndexStatusResponse {
@@ -54,6 +93,119 @@ pub struct IndexStatusResponse {
     pub indexed_at: Option<i64>,
     pub current_commit_sha: Option<String>,
     pub is_stale: bool,
+    /// Files that differ between the indexed commit and HEAD, for a scoped
+    /// re-ingest; absent when fresh or when too many changed to list.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub changed_paths: Option<Vec<String>>,
+    /// The database is on read-only media: searches work, but ingest,
+    /// eviction, and hit counting are off.
+    pub read_only: bool,
+    pub packages: Vec<IndexStatusPackage>,
+    pub health: IndexHealth,
+}
+
+/// Ordered from best to worst.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize, JsonSchema)]
+#[serde(rename_all = "lowercase")]
+pub enum HealthStatus {
+    Green,
+    Yellow,
+    Red,
+}
+
+impl HealthStatus {
+    fn from_score(score: u8) -> Self {
+        if score >= HEALTH_GREEN_THRESHOLD {
+            HealthStatus::Green
+        } else if score >= HEALTH_YELLOW_THRESHOLD {
+            HealthStatus::Yellow
+        } else {
+            HealthStatus::Red
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, JsonSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct HealthDimension {
+    pub name: String,
+    pub score: u8,
+    pub status: HealthStatus,
+    pub detail: String,
+}
+
+/// Composite 0-100 score; the overall status is the worst of the overall
+/// score and any single dimension so one red signal is never averaged away.
+#[derive(Debug, Clone, Serialize, JsonSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct IndexHealth {
+    pub score: u8,
+    pub status: HealthStatus,
+    pub dimensions: Vec<HealthDimension>,
+}
+
+impl IndexHealth {
+    fn from_dimensions(dimensions: Vec<HealthDimension>) -> Self {
+        let score = if dimensions.is_empty() {
+            0
+        } else {
+            (dimensions
+                .iter()
+                .map(|dimension| dimension.score as u32)
+                .sum::<u32>()
+                / dimensions.len() as u32) as u8
+        };
+        let status = dimensions
+            .iter()
+            .map(|dimension| dimension.status)
+            .chain([HealthStatus::from_score(score)])
+            .max()
+            .unwrap_or(HealthStatus::Red);
+        Self {
+            score,
+            status,
+            dimensions,
+        }
+    }
+
+    fn missing_database() -> Self {
+        Self::from_dimensions(vec![health_dimension(
+            "database",
+            0,
+            "Index database does not exist; run ingest_codebase.".to_string(),
+        )])
+    }
+}
+
+#[derive(Debug, Clone, serde::Deserialize, JsonSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct ListDatabasesParams {
+    #[serde(default)]
+    pub root: Option<String>,
+    #[serde(default)]
+    pub max_depth: Option<u32>,
+}
+
+#[derive(Debug, Clone, Serialize, JsonSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct DatabaseSummary {
+    /// Root-relative path, accepted as `databaseName` by the other tools.
+    pub name: String,
+    pub database_path: String,
+    pub size_bytes: u64,
+    pub schema_version: i64,
+    pub embedding_models: Vec<String>,
+    pub total_files: u64,
+    pub total_chunks: u64,
+    pub latest_ingestion: Option<IndexStatusIngestion>,
+    pub is_default: bool,
+}
+
+#[derive(Debug, Clone, Serialize, JsonSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct ListDatabasesResponse {
+    pub root: String,
+    pub databases: Vec<DatabaseSummary>,
 }
 
 #[derive(Debug, Error)]
@@ -76,6 +228,8 @@ pub enum IndexStatusError {
     Git(#[from] std::io::Error),
     #[error("blocking task panicked: {0}")]
     Join(#[from] tokio::task::JoinError),
+    #[error(transparent)]
+    Sandbox(#[from] SandboxError),
 }
 
 pub async fn get_index_status(
@@ -94,7 +248,7 @@ fn compute_index_status(
         .unwrap_or_else(|| DEFAULT_DB_FILENAME.to_string());
 
     let absolute_root = resolve_root(&root)?;
-    let database_path = absolute_root.join(&database_name);
+    let database_path = database_path(&absolute_root, &database_name);
     let database_path_string = database_path.to_string_lossy().to_string();
 
     let metadata = match fs::metadata(&database_path) {
@@ -125,13 +279,17 @@ fn compute_index_status(
             indexed_at: None,
             current_commit_sha,
             is_stale: true,
+            changed_paths: None,
+            read_only: false,
+            packages: Vec::new(),
+            health: IndexHealth::missing_database(),
         });
     }
 
     let database_size_bytes = metadata.map(|m| m.len());
     let current_commit_sha = get_current_commit_sha(&absolute_root).ok();
 
-    let conn = Connection::open_with_flags(&database_path, OpenFlags::SQLITE_OPEN_READ_ONLY)?;
+    let conn = open_read_only(&database_path)?;
 
     let total_files = query_count(&conn, "SELECT COUNT(*) FROM files")?;
     let total_chunks = query_count(&conn, "SELECT COUNT(*) FROM file_chunks")?;
@@ -144,8 +302,28 @@ fn compute_index_status(
         query_meta_value(&conn, "indexed_at").and_then(|value| value.parse::<i64>().ok());
     let ingestions = query_ingestions(&conn, history_limit)?;
     let latest_ingestion = ingestions.first().cloned();
+    let packages = query_packages(&conn);
 
     let is_stale = matches!((&current_commit_sha, &commit_sha), (Some(current), Some(stored)) if current != stored);
+    let changed_paths = match (&current_commit_sha, &commit_sha) {
+        (Some(current), Some(stored)) if is_stale => {
+            changed_paths_between(&absolute_root, stored, current)
+        }
+        _ => None,
+    };
+
+    let max_database_size_bytes = params
+        .max_database_size_bytes
+        .filter(|value| value.is_finite() && *value >= 0.0)
+        .map(|value| value as u64)
+        .unwrap_or(DEFAULT_MAX_DATABASE_SIZE_BYTES);
+    let health = IndexHealth::from_dimensions(vec![
+        freshness_dimension(is_stale, indexed_at),
+        coverage_dimension(&conn, &absolute_root),
+        embedding_dimension(&embedding_models),
+        orphan_dimension(&conn, total_chunks),
+        size_dimension(database_size_bytes.unwrap_or(0), max_database_size_bytes),
+    ]);
 
     Ok(IndexStatusResponse {
         database_path: database_path_string,
@@ -162,21 +340,292 @@ fn compute_index_status(
         indexed_at,
         current_commit_sha,
         is_stale,
+        changed_paths,
+        read_only: is_read_only_media(&database_path),
+        packages,
+        health,
     })
 }
 
-fn resolve_root(root: &str) -> Result<PathBuf, IndexStatusError> {
-    let candidate = PathBuf::from(root);
-    if candidate.is_absolute() {
-        Ok(candidate)
+fn health_dimension(name: &str, score: u8, detail: String) -> HealthDimension {
+    HealthDimension {
+        name: name.to_string(),
+        score,
+        status: HealthStatus::from_score(score),
+        detail,
+    }
+}
+
+fn freshness_dimension(is_stale: bool, indexed_at: Option<i64>) -> HealthDimension {
+    let age_days = indexed_at.map(|indexed_at| (now_ms() - indexed_at).max(0) / DAY_MS);
+    let age = match age_days {
+        Some(0) => "today".to_string(),
+        Some(days) => format!("{days} day(s) ago"),
+        None => "at an unknown time".to_string(),
+    };
+    let (score, detail) = if is_stale {
+        (
+            30,
+            format!("Indexed commit differs from HEAD; last indexed {age}."),
+        )
+    } else {
+        let score = match age_days {
+            Some(0) => 100,
+            Some(1..=7) => 85,
+            Some(_) => 60,
+            None => 50,
+        };
+        (score, format!("Matches HEAD; last indexed {age}."))
+    };
+    health_dimension("freshness", score, detail)
+}
+
+fn coverage_dimension(conn: &Connection, root: &Path) -> HealthDimension {
+    let Ok(tracked) = git_tracked_files(root) else {
+        return health_dimension(
+            "coverage",
+            100,
+            "Not a git repository; coverage not measured.".to_string(),
+        );
+    };
+    if tracked.is_empty() {
+        return health_dimension("coverage", 100, "No files tracked by git.".to_string());
+    }
+
+    let indexed = query_indexed_paths(conn);
+    let covered = tracked
+        .iter()
+        .filter(|path| indexed.contains(path.as_str()))
+        .count();
+    let ratio = covered as f64 / tracked.len() as f64;
+    health_dimension(
+        "coverage",
+        (ratio * 100.0).round() as u8,
+        format!(
+            "{covered} of {} git-tracked file(s) are indexed ({:.0}%).",
+            tracked.len(),
+            ratio * 100.0
+        ),
+    )
+}
+
+fn embedding_dimension(embedding_models: &[String]) -> HealthDimension {
+    match embedding_models {
+        [] => health_dimension("embeddingModels", 50, "No embedded chunks.".to_string()),
+        [model] => health_dimension("embeddingModels", 100, format!("All chunks use {model}.")),
+        models => health_dimension(
+            "embeddingModels",
+            40,
+            format!(
+                "{} models mixed ({}); searches only compare chunks from one model.",
+                models.len(),
+                models.join(", ")
+            ),
+        ),
+    }
+}
+
+fn orphan_dimension(conn: &Connection, total_chunks: u64) -> HealthDimension {
+    let orphan_chunks = query_count(
+        conn,
+        "SELECT COUNT(*) FROM file_chunks WHERE path NOT IN (SELECT path FROM files)",
+    )
+    .unwrap_or(0);
+    let orphan_nodes = query_count(
+        conn,
+        "SELECT COUNT(*) FROM code_graph_nodes WHERE path IS NOT NULL AND path NOT IN (SELECT path FROM files)",
+    )
+    .unwrap_or(0);
+    if orphan_chunks == 0 && orphan_nodes == 0 {
+        return health_dimension("orphans", 100, "No orphaned rows.".to_string());
+    }
+
+    let ratio = orphan_chunks as f64 / total_chunks.max(1) as f64;
+    let score = (100.0 - ratio * 200.0).clamp(0.0, 90.0).round() as u8;
+    health_dimension(
+        "orphans",
+        score,
+        format!(
+            "{orphan_chunks} chunk(s) and {orphan_nodes} graph node(s) reference files no longer in the index; re-run ingest_codebase."
+        ),
+    )
+}
+
+fn size_dimension(size_bytes: u64, max_size_bytes: u64) -> HealthDimension {
+    if max_size_bytes == 0 {
+        return health_dimension("size", 100, "No size cap configured.".to_string());
+    }
+
+    let ratio = size_bytes as f64 / max_size_bytes as f64;
+    let score = if ratio <= 0.7 {
+        100
+    } else if ratio <= 1.0 {
+        (100.0 - (ratio - 0.7) * 150.0).round() as u8
     } else {
-        let current_dir =
-            std::env::current_dir().map_err(|source| IndexStatusError::InvalidRoot {
-                path: root.to_string(),
-                source,
-            })?;
-        Ok(current_dir.join(candidate))
+        20
+    };
+    health_dimension(
+        "size",
+        score,
+        format!(
+            "{:.0}% of the {} MiB cap; ingest evicts old chunks past the cap.",
+            ratio * 100.0,
+            max_size_bytes / (1024 * 1024)
+        ),
+    )
+}
+
+fn query_indexed_paths(conn: &Connection) -> HashSet<String> {
+    let Ok(mut stmt) = conn.prepare("SELECT path FROM files") else {
+        return HashSet::new();
+    };
+    match stmt.query_map([], |row| row.get::<_, String>(0)) {
+        Ok(rows) => rows.flatten().collect(),
+        Err(_) => HashSet::new(),
+    }
+}
+
+/// Paths tracked by git, relative to `root` with `/` separators to match
+/// `files.path`.
+pub(crate) fn git_tracked_files(root: &Path) -> Result<Vec<String>, std::io::Error> {
+    let output = Command::new("git")
+        .arg("ls-files")
+        .arg("-z")
+        .current_dir(root)
+        .output()?;
+
+    if !output.status.success() {
+        return Err(std::io::Error::other(
+            "git ls-files returned non-zero status",
+        ));
     }
+
+    Ok(output
+        .stdout
+        .split(|byte| *byte == 0)
+        .filter(|path| !path.is_empty())
+        .map(|path| String::from_utf8_lossy(path).to_string())
+        .collect())
+}
+
+fn now_ms() -> i64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|duration| duration.as_millis() as i64)
+        .unwrap_or(0)
+}
+
+pub async fn list_databases(
+    params: ListDatabasesParams,
+) -> Result<ListDatabasesResponse, IndexStatusError> {
+    tokio::task::spawn_blocking(move || scan_databases(params)).await?
+}
+
+fn scan_databases(params: ListDatabasesParams) -> Result<ListDatabasesResponse, IndexStatusError> {
+    let root = params.root.unwrap_or_else(|| "./".to_string());
+    let max_depth = params
+        .max_depth
+        .unwrap_or(DEFAULT_SCAN_DEPTH)
+        .clamp(1, MAX_SCAN_DEPTH) as usize;
+
+    let absolute_root = resolve_root(&root)?;
+    ensure_root_allowed(&absolute_root)?;
+
+    let walker = WalkDir::new(&absolute_root)
+        .max_depth(max_depth)
+        .into_iter()
+        .filter_entry(|entry| {
+            !(entry.file_type().is_dir()
+                && entry.depth() > 0
+                && SKIPPED_SCAN_DIRS
+                    .iter()
+                    .any(|name| entry.file_name() == *name))
+        });
+
+    let mut databases = Vec::new();
+    for entry in walker.flatten() {
+        let path = entry.path();
+        if !entry.file_type().is_file()
+            || path.extension().and_then(|ext| ext.to_str()) != Some("sqlite")
+        {
+            continue;
+        }
+        let Ok(relative) = path.strip_prefix(&absolute_root) else {
+            continue;
+        };
+        let name = relative.to_string_lossy().replace('\\', "/");
+        if let Some(summary) = summarize_database(path, name) {
+            databases.push(summary);
+        }
+    }
+
+    // databases relocated under INDEX_MCP_DATA_DIR live outside the root
+    let relocated = database_dir(&absolute_root);
+    if relocated != absolute_root {
+        for entry in fs::read_dir(&relocated).into_iter().flatten().flatten() {
+            let path = entry.path();
+            if path.extension().and_then(|ext| ext.to_str()) != Some("sqlite") {
+                continue;
+            }
+            let name = entry.file_name().to_string_lossy().to_string();
+            if let Some(summary) = summarize_database(&path, name) {
+                databases.push(summary);
+            }
+        }
+    }
+
+    databases.sort_by(|a, b| {
+        let a_finished = a.latest_ingestion.as_ref().map(|ingest| ingest.finished_at);
+        let b_finished = b.latest_ingestion.as_ref().map(|ingest| ingest.finished_at);
+        b_finished
+            .cmp(&a_finished)
+            .then_with(|| a.name.cmp(&b.name))
+    });
+
+    Ok(ListDatabasesResponse {
+        root: absolute_root.to_string_lossy().to_string(),
+        databases,
+    })
+}
+
+// returns None for SQLite files that are not index databases (or cannot be read)
+fn summarize_database(path: &Path, name: String) -> Option<DatabaseSummary> {
+    let conn = open_read_only(path).ok()?;
+    let index_tables = query_count(
+        &conn,
+        "SELECT COUNT(*) FROM sqlite_master WHERE type = 'table' AND name IN ('files', 'file_chunks')",
+    )
+    .ok()?;
+    if index_tables < 2 {
+        return None;
+    }
+
+    let schema_version = conn
+        .query_row("PRAGMA user_version", [], |row| row.get::<_, i64>(0))
+        .unwrap_or(0);
+
+    Some(DatabaseSummary {
+        is_default: path
+            .file_name()
+            .is_some_and(|file| file == DEFAULT_DB_FILENAME),
+        database_path: path.to_string_lossy().to_string(),
+        size_bytes: fs::metadata(path).map(|meta| meta.len()).unwrap_or(0),
+        schema_version,
+        embedding_models: query_embedding_models(&conn).unwrap_or_default(),
+        total_files: query_count(&conn, "SELECT COUNT(*) FROM files").unwrap_or(0),
+        total_chunks: query_count(&conn, "SELECT COUNT(*) FROM file_chunks").unwrap_or(0),
+        latest_ingestion: query_ingestions(&conn, 1)
+            .ok()
+            .and_then(|mut ingestions| ingestions.pop()),
+        name,
+    })
+}
+
+fn resolve_root(root: &str) -> Result<PathBuf, IndexStatusError> {
+    resolve_workspace_root(root).map_err(|source| IndexStatusError::InvalidRoot {
+        path: root.to_string(),
+        source,
+    })
 }
 
 fn query_count(conn: &Connection, sql: &str) -> Result<u64, rusqlite::Error> {
@@ -200,6 +649,31 @@ fn query_meta_value(conn: &Connection, key: &str) -> Option<String> {
         .ok()
 }
 
+fn query_packages(conn: &Connection) -> Vec<IndexStatusPackage> {
+    // databases written before package tracking have no file_packages table
+    let Ok(mut stmt) = conn.prepare(
+        "SELECT ecosystem, name, root, COUNT(*) FROM file_packages
+         GROUP BY ecosystem, name, root
+         ORDER BY COUNT(*) DESC, name ASC",
+    ) else {
+        return Vec::new();
+    };
+
+    let rows = stmt.query_map([], |row| {
+        Ok(IndexStatusPackage {
+            ecosystem: row.get(0)?,
+            name: row.get(1)?,
+            root: row.get(2)?,
+            file_count: row.get::<_, i64>(3)?.max(0) as u64,
+        })
+    });
+
+    match rows {
+        Ok(rows) => rows.flatten().collect(),
+        Err(_) => Vec::new(),
+    }
+}
+
 fn query_ingestions(
     conn: &Connection,
     limit: usize,
@@ -244,6 +718,29 @@ fn query_ingestions(
     Ok(result)
 }
 
+/// Paths relative to `root` changed between two commits, including deletions;
+/// `None` if git fails or the list exceeds `MAX_CHANGED_PATHS`.
+fn changed_paths_between(root: &Path, from: &str, to: &str) -> Option<Vec<String>> {
+    let output = Command::new("git")
+        .arg("diff")
+        .arg("--name-only")
+        .arg("--relative")
+        .arg(from)
+        .arg(to)
+        .current_dir(root)
+        .output()
+        .ok()?;
+    if !output.status.success() {
+        return None;
+    }
+    let paths: Vec<String> = String::from_utf8_lossy(&output.stdout)
+        .lines()
+        .filter(|line| !line.is_empty())
+        .map(str::to_string)
+        .collect();
+    (paths.len() <= MAX_CHANGED_PATHS).then_some(paths)
+}
+
 fn get_current_commit_sha(root: &Path) -> Result<String, std::io::Error> {
     let output = Command::new("git")
         .arg("rev-parse")
@@ -1,14 +1,15 @@
-use std::collections::{HashMap, HashSet};
+use std::collections::{BTreeMap, HashMap, HashSet};
 use std::fs;
 use std::path::{Path, PathBuf};
 use std::str::FromStr;
+use std::sync::atomic::{AtomicBool, Ordering};
 use std::sync::{Arc, Mutex};
 use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
 
-use fastembed::{EmbeddingModel, TextEmbedding, TextInitOptions};
+use fastembed::EmbeddingModel;
 use globset::{Glob, GlobSet, GlobSetBuilder};
-use ignore::WalkBuilder;
 use once_cell::sync::{Lazy, OnceCell};
+use regex::Regex;
 use rmcp::schemars::{self, JsonSchema};
 use rusqlite::{params, Connection, OpenFlags, Transaction};
 use serde::{Deserialize, Serialize};
@@ -17,16 +18,77 @@ use thiserror::Error;
 use uuid::Uuid;
 
 use crate::{
+    chunk_labels::ensure_chunk_label_schema,
+    content_store::{
+        content_encoding_column, convert_chunk_storage, encode_file_content, ensure_content_schema,
+        load_reference_chunk_paths, StoredContentCache, CHUNK_REFERENCE_ENCODING,
+    },
+    data_dir::{database_path, is_read_only_media, open_read_only},
+    db_lock::{DatabaseLock, DatabaseLockError},
+    embedder::{
+        http_backend, is_mock_model, load_embedder, load_embedding_endpoint, Embedder,
+        META_EMBEDDING_ENDPOINT, MOCK_EMBEDDING_MODEL, OLLAMA_MODEL_PREFIX, OPENAI_MODEL_PREFIX,
+    },
+    embedding_cache::{
+        clear_embedding_cache, embedding_cache_key, ensure_embedding_cache_schema,
+        load_cached_embeddings, prune_embedding_cache, store_cached_embeddings,
+    },
+    embedding_store::{encode_embedding, ensure_embedding_schema, EmbeddingEncoding},
+    generations::{ensure_generation_schema, keep_generations, snapshot_generation},
+    git_timeline::normalize_remote_url,
     graph::{extract_graph, GraphExtraction},
-    index_status::DEFAULT_DB_FILENAME,
+    hit_counter::flush_pending_hits_for,
+    ignore_rules::{build_ignore_walk, gitignore_match, has_hidden_component},
+    index_status::{DEFAULT_DB_FILENAME, SCHEMA_VERSION},
+    ingest_queue::{IngestPriority, IngestTicket},
+    integrity::{chunk_checksum, ensure_checksum_schema},
+    keywords::{load_keyword_paths, replace_file_keywords, top_keywords},
+    language_pack::{pack_for_path, LanguagePack},
+    model_registry::{
+        check_dimensions, find_preset, model_limits, preset_names, resolve_model_name,
+    },
+    normalization::{ChunkNormalization, META_CHUNK_NORMALIZATION},
+    roots::{relative_to_root, resolve_workspace_root},
+    sandbox::{ensure_root_allowed, resolve_within_root, SandboxError},
+    search::{classify_chunk, detect_language, Classification},
+    text_format::{
+        decode_text, detect_line_ending, ensure_text_format_schema, text_format_columns,
+        LineEnding, TextEncoding,
+    },
+    vector_index::{ensure_vector_index_schema, refresh_vector_index},
+    workspace::{ensure_package_schema, replace_file_packages, PackageResolver},
 };
 
+const GENERATED_FILE_SUFFIXES: &[&str] = &[
+    ".min.js",
+    ".min.css",
+    ".map",
+    ".lock",
+    ".snap",
+    ".pb.go",
+    "_pb2.py",
+    ".g.dart",
+    ".generated.ts",
+    ".designer.cs",
+];
+const GENERATED_MARKERS: &[&str] = &[
+    "@generated",
+    "DO NOT EDIT",
+    "Code generated",
+    "auto-generated",
+    "autogenerated",
+];
+// chunk classifications auto-eviction keeps while their file still exists
+const DEFAULT_PROTECTED_CLASSIFICATIONS: &[Classification] = &[Classification::Function];
+
 pub(crate) const DEFAULT_INCLUDE_GLOBS: &[&str] = &["**/*"];
 pub(crate) const DEFAULT_EXCLUDE_GLOBS: &[&str] = &[
     "**/.git/**",
     "**/.svn/**",
     "**/.hg/**",
     "**/.mcp-index.sqlite",
+    "**/.mcp-index.sqlite.lock",
+    "**/.mcp-index-generations/**",
     "**/node_modules/**",
     "**/vendor/**",
     "**/dist/**",
@@ -38,15 +100,50 @@ pub(crate) const DEFAULT_EMBEDDING_MODEL: &str = "Xenova/all-MiniLM-L6-v2";
 const DEFAULT_CHUNK_SIZE_TOKENS: usize = 256;
 const DEFAULT_CHUNK_OVERLAP_TOKENS: usize = 32;
 const DEFAULT_EMBEDDING_BATCH_SIZE: usize = 32;
-const DEFAULT_MAX_DATABASE_SIZE_BYTES: u64 = 150 * 1024 * 1024; // 150 MB
-
-type EmbedderHandle = Arc<Mutex<TextEmbedding>>;
+const DEFAULT_OPENAI_EMBEDDING_MODEL: &str = "text-embedding-3-small";
+const DEFAULT_OLLAMA_EMBEDDING_MODEL: &str = "nomic-embed-text";
+// enough for a ~500 KB source file at the default chunk size
+const DEFAULT_MAX_CHUNKS_PER_FILE: usize = 500;
+pub(crate) const DEFAULT_MAX_DATABASE_SIZE_BYTES: u64 = 150 * 1024 * 1024; // 150 MB
+const DEFAULT_EVICTION_EXAMPLES: usize = 5;
+const MAX_EVICTION_EXAMPLES: usize = 50;
+const META_EMBEDDING_MODEL: &str = "embedding_model";
+const META_EMBEDDING_DIMENSIONS: &str = "embedding_dimensions";
+/// Meta key holding the [`ChunkingConfig`] of the last ingest as JSON.
+const META_CHUNKING_CONFIG: &str = "chunking_config";
+pub(crate) const META_REMOTE_URL: &str = "remote_url";
+/// Path of the ingest root inside its git repository, with a trailing slash.
+pub(crate) const META_REPOSITORY_PREFIX: &str = "repository_prefix";
+const DEFAULT_ESTIMATE_SAMPLE_CHUNKS: usize = 16;
+const FALLBACK_EMBEDDING_DIMENSIONS: usize = 384;
+const FALLBACK_CHUNKS_PER_SECOND: f64 = 40.0;
+const FILE_ROW_OVERHEAD_BYTES: u64 = 128;
+const CHUNK_ROW_OVERHEAD_BYTES: u64 = 160;
+// ingestions whose skip lists are kept for list_skipped and coverage diagnostics
+const RETAINED_SKIP_INGESTIONS: i64 = 5;
+const CHUNK_RECORD_MEMORY_OVERHEAD_BYTES: u64 = 96;
+// shorter doc comments carry too little text to be worth a chunk
+const MIN_DOC_COMMENT_CHARS: usize = 24;
+
+type EmbedderHandle = Arc<Mutex<Embedder>>;
 type EmbedderEntry = Arc<OnceCell<EmbedderHandle>>;
 
 static EMBEDDER_CACHE: Lazy<Mutex<HashMap<String, EmbedderEntry>>> =
     Lazy::new(|| Mutex::new(HashMap::new()));
 
-#[derive(Debug, Deserialize, JsonSchema)]
+// name declared on the line a doc comment documents
+static DOC_SYMBOL_NAME: Lazy<Regex> = Lazy::new(|| {
+    Regex::new(r"\b(?:fn|struct|enum|trait|mod|type|union|class|interface|def|fun|func|function\*?|object|record|module|const|let|var|static)\s+(?:\([^)]*\)\s*)?([A-Za-z_$][\w$]*)")
+        .expect("valid doc symbol pattern")
+});
+static PYTHON_DOCUMENTED_DEFINITION: Lazy<Regex> = Lazy::new(|| {
+    Regex::new(r"^\s*(?:async\s+def|def|class)\s+([A-Za-z_]\w*)")
+        .expect("valid Python definition pattern")
+});
+static GO_DOCUMENTED_DEFINITION: Lazy<Regex> =
+    Lazy::new(|| Regex::new(r"^(func|type|var|const)\s").expect("valid Go definition pattern"));
+
+#[derive(Debug, Clone, Deserialize, JsonSchema)]
 #[serde(rename_all = "camelCase")]
 pub struct IngestParams {
     #[serde(default)]
@@ -61,38 +158,230 @@ pub struct IngestParams {
     pub max_file_size_bytes: Option<f64>,
     #[serde(default)]
     pub store_file_content: Option<bool>,
+    /// Store content for paths matching these globs even when
+    /// `storeFileContent` is false.
+    #[serde(default)]
+    pub store_content_globs: Option<Vec<String>>,
+    /// Never store content for paths matching these globs; wins over
+    /// `storeContentGlobs`.
+    #[serde(default)]
+    pub skip_content_globs: Option<Vec<String>>,
     #[serde(default)]
     pub paths: Option<Vec<String>>,
     #[serde(default)]
     pub auto_evict: Option<bool>,
     #[serde(default)]
     pub max_database_size_bytes: Option<f64>,
+    /// Chunk classifications auto-eviction never removes while their file is
+    /// still on disk (default `["function"]`; `[]` protects nothing).
+    #[serde(default)]
+    pub protected_classifications: Option<Vec<Classification>>,
     #[serde(default)]
     pub embedding: Option<EmbeddingParams>,
+    #[serde(default)]
+    pub allow_model_mix: Option<bool>,
+    #[serde(default)]
+    pub compress_content: Option<bool>,
+    #[serde(default)]
+    pub chunk_content_by_reference: Option<bool>,
+    /// Storage for newly embedded chunks (default `f32`). Chunks reused from
+    /// an earlier ingest keep theirs; `recode_embeddings` converts them.
+    #[serde(default)]
+    pub embedding_encoding: Option<EmbeddingEncoding>,
+    /// Ingest explicitly requested `paths` even when .gitignore excludes them.
+    #[serde(default)]
+    pub include_ignored_paths: Option<bool>,
+    /// Index dotfiles and dot-directories such as `.github/`. Off by default;
+    /// the default excludes still apply when on.
+    #[serde(default)]
+    pub include_hidden: Option<bool>,
+    /// Return every skipped file inline. By default only counts by reason are
+    /// returned and list_skipped pages through the details.
+    #[serde(default)]
+    pub include_skipped: Option<bool>,
 }
 
-#[derive(Debug, Deserialize, JsonSchema, Default)]
+#[derive(Debug, Clone, Deserialize, JsonSchema, Default)]
 #[serde(rename_all = "camelCase")]
 pub struct EmbeddingParams {
     #[serde(default)]
     pub enabled: Option<bool>,
     #[serde(default)]
+    pub provider: Option<EmbeddingProvider>,
+    /// Model name or alias (`default`, `small`, `base`, `code`,
+    /// `multilingual`); the concrete name is what gets recorded.
+    #[serde(default)]
     pub model: Option<String>,
+    /// `general`, `code` or `multilingual`: a model with chunk sizes suited
+    /// to it. `model`, `chunkSizeTokens` and `chunkOverlapTokens` override it.
+    #[serde(default)]
+    pub preset: Option<String>,
     #[serde(default)]
     pub chunk_size_tokens: Option<u32>,
     #[serde(default)]
     pub chunk_overlap_tokens: Option<u32>,
     #[serde(default)]
     pub batch_size: Option<u32>,
+    #[serde(default)]
+    pub chunking: Option<ChunkingMode>,
+    /// Also embed doc comments (`///`, `/** */`, Go declaration comments,
+    /// Python docstrings) as separate chunks linked to the symbol they
+    /// document.
+    #[serde(default)]
+    pub doc_chunks: Option<bool>,
+    /// Cap on chunks embedded per file (default 500, 0 for no cap). Larger
+    /// files keep their head, tail, and definition-bearing chunks.
+    #[serde(default)]
+    pub max_chunks_per_file: Option<u32>,
+    /// Text rewrites applied before embedding. Recorded in the database, so
+    /// search normalizes queries the same way and later ingests that omit
+    /// it keep it; changing it re-embeds every file.
+    #[serde(default)]
+    pub normalize: Option<ChunkNormalization>,
+    /// Base URL of the `openai` or `ollama` service. Recorded in the
+    /// database, so later ingests and search reuse it.
+    #[serde(default)]
+    pub endpoint: Option<String>,
+}
+
+/// `fastembed` runs the ONNX model named by `model`. `mock` needs no model
+/// download or ONNX runtime: it hashes each chunk's words into a
+/// deterministic vector, so ingest and search can be exercised offline.
+/// Mock scores only reflect shared words; responses built from them carry
+/// `mockEmbeddings: true`. `openai` posts chunks to an OpenAI-compatible
+/// `/embeddings` endpoint (key from INDEX_MCP_OPENAI_API_KEY or
+/// OPENAI_API_KEY) and `ollama` to an Ollama server's `/api/embed`; their
+/// models are recorded as `openai:<model>` and `ollama:<model>`.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize, JsonSchema)]
+#[serde(rename_all = "lowercase")]
+pub enum EmbeddingProvider {
+    #[default]
+    Fastembed,
+    Mock,
+    OpenAi,
+    Ollama,
+}
+
+/// `structural` keeps size limits but breaks chunks before function, class,
+/// and type definitions when one falls inside the window. Languages are
+/// inferred from the file extension; others fall back to size-based chunking.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize, JsonSchema)]
+#[serde(rename_all = "lowercase")]
+pub enum ChunkingMode {
+    #[default]
+    Size,
+    Structural,
+}
+
+#[derive(Debug, Deserialize, JsonSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct IngestEstimateParams {
+    #[serde(default)]
+    pub root: Option<String>,
+    #[serde(default)]
+    pub include: Option<Vec<String>>,
+    #[serde(default)]
+    pub exclude: Option<Vec<String>>,
+    #[serde(default)]
+    pub max_file_size_bytes: Option<f64>,
+    #[serde(default)]
+    pub store_file_content: Option<bool>,
+    #[serde(default)]
+    pub store_content_globs: Option<Vec<String>>,
+    #[serde(default)]
+    pub skip_content_globs: Option<Vec<String>>,
+    #[serde(default)]
+    pub paths: Option<Vec<String>>,
+    #[serde(default)]
+    pub embedding: Option<EmbeddingParams>,
+    #[serde(default)]
+    pub sample_chunks: Option<u32>,
+    #[serde(default)]
+    pub include_hidden: Option<bool>,
+    #[serde(default)]
+    pub embedding_encoding: Option<EmbeddingEncoding>,
 }
 
 struct EmbeddingConfig {
     enabled: bool,
     model: String,
-    model_variant: EmbeddingModel,
+    /// `None` for the mock backend.
+    model_variant: Option<EmbeddingModel>,
     chunk_size_tokens: usize,
     chunk_overlap_tokens: usize,
     batch_size: Option<usize>,
+    chunking: ChunkingMode,
+    doc_chunks: bool,
+    max_chunks_per_file: Option<usize>,
+    /// Chunking settings the caller passed; the rest keep what the database
+    /// recorded.
+    requested_chunking: RequestedChunking,
+    /// `None` keeps whatever the database recorded.
+    normalization: Option<ChunkNormalization>,
+    /// HTTP backends only; `None` falls back to the recorded endpoint, then
+    /// the provider's default.
+    endpoint: Option<String>,
+}
+
+#[derive(Debug, Clone, Copy, Default)]
+struct RequestedChunking {
+    sizes: bool,
+    mode: bool,
+    doc_chunks: bool,
+    max_chunks_per_file: bool,
+}
+
+impl RequestedChunking {
+    fn any(&self) -> bool {
+        self.sizes || self.mode || self.doc_chunks || self.max_chunks_per_file
+    }
+}
+
+/// Everything that decides how a file is cut into chunks. Recorded per
+/// database; files whose content did not change are re-chunked when it does.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ChunkingConfig {
+    chunk_size_tokens: usize,
+    chunk_overlap_tokens: usize,
+    mode: ChunkingMode,
+    /// Structural boundaries come from tree-sitter rather than line patterns.
+    syntax_boundaries: bool,
+    doc_chunks: bool,
+    max_chunks_per_file: Option<usize>,
+}
+
+impl EmbeddingConfig {
+    fn chunking_config(&self) -> ChunkingConfig {
+        ChunkingConfig {
+            chunk_size_tokens: self.chunk_size_tokens,
+            chunk_overlap_tokens: self.chunk_overlap_tokens,
+            mode: self.chunking,
+            syntax_boundaries: self.chunking == ChunkingMode::Structural
+                && cfg!(feature = "tree-sitter"),
+            doc_chunks: self.doc_chunks,
+            max_chunks_per_file: self.max_chunks_per_file,
+        }
+    }
+
+    /// Takes the recorded value of every chunking setting the caller left
+    /// unset.
+    fn inherit_chunking(&mut self, stored: &ChunkingConfig) {
+        let requested = self.requested_chunking;
+        if !requested.sizes {
+            self.chunk_size_tokens = stored.chunk_size_tokens;
+            self.chunk_overlap_tokens = stored.chunk_overlap_tokens;
+        }
+        if !requested.mode {
+            self.chunking = stored.mode;
+        }
+        if !requested.doc_chunks {
+            self.doc_chunks = stored.doc_chunks;
+        }
+        if !requested.max_chunks_per_file {
+            self.max_chunks_per_file = stored.max_chunks_per_file;
+        }
+    }
 }
 
 #[derive(Debug, Clone, Serialize, JsonSchema)]
@@ -113,17 +402,72 @@ pub struct IngestResponse {
     pub database_path: String,
     pub database_size_bytes: u64,
     pub ingested_file_count: usize,
+    pub skipped_count: usize,
+    pub skipped_by_reason: BTreeMap<String, usize>,
+    /// Only populated with `includeSkipped`.
+    #[serde(skip_serializing_if = "Vec::is_empty")]
     pub skipped: Vec<SkippedFile>,
     pub deleted_paths: Vec<String>,
     pub duration_ms: u128,
     pub embedded_chunk_count: usize,
+    /// Embedded chunks whose vector came from the embedding cache, or from
+    /// an identical chunk earlier in the run, rather than the model.
+    pub embedding_cache_hits: usize,
     pub embedding_model: Option<String>,
+    /// Rewrites applied to chunk text before embedding; absent when none are.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub chunk_normalization: Option<ChunkNormalization>,
+    /// Chunks were embedded by the mock provider; search over them measures
+    /// shared words, not meaning.
+    #[serde(skip_serializing_if = "std::ops::Not::not")]
+    pub mock_embeddings: bool,
     pub graph_node_count: usize,
     pub graph_edge_count: usize,
     #[serde(skip_serializing_if = "Option::is_none")]
     pub evicted: Option<EvictionReport>,
+    /// Files whose stored hash matched, so chunks and embeddings were kept.
     #[serde(skip_serializing_if = "Option::is_none")]
     pub reused_file_count: Option<usize>,
+    /// Files ingested only because `includeIgnoredPaths` bypassed .gitignore.
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub gitignore_overrides: Vec<String>,
+    /// Files chunked in this run that exceeded `maxChunksPerFile`.
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub truncated_files: Vec<TruncatedFile>,
+    /// Generation snapshotted for `generation`/`atCommit` queries; absent
+    /// unless `INDEX_MCP_KEEP_GENERATIONS` is set.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub generation: Option<i64>,
+}
+
+#[derive(Debug, Clone, Serialize, JsonSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct TruncatedFile {
+    pub path: String,
+    pub total_chunks: usize,
+    pub kept_chunks: usize,
+}
+
+#[derive(Debug, Clone, Serialize, JsonSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct IngestEstimateResponse {
+    pub root: String,
+    pub file_count: usize,
+    pub text_file_count: usize,
+    pub skipped_count: usize,
+    pub total_bytes: u64,
+    pub estimated_chunk_count: usize,
+    pub embedding_enabled: bool,
+    pub embedding_model: String,
+    pub chunk_size_tokens: usize,
+    pub chunk_overlap_tokens: usize,
+    pub embedding_dimensions: usize,
+    pub chunks_per_second: f64,
+    pub throughput_measured: bool,
+    pub estimated_database_size_bytes: u64,
+    pub estimated_embedding_ms: u64,
+    pub estimated_peak_memory_bytes: u64,
+    pub scan_duration_ms: u128,
 }
 
 #[derive(Debug, Clone, Serialize, JsonSchema)]
@@ -134,6 +478,58 @@ pub struct EvictionReport {
     pub size_after: u64,
     pub evicted_chunks: usize,
     pub evicted_nodes: usize,
+    /// Embedding cache rows dropped before any chunk was considered.
+    pub evicted_cache_entries: usize,
+    /// Chunks kept back because their classification is protected.
+    pub protected_chunks: usize,
+}
+
+#[derive(Debug, Deserialize, JsonSchema, Default)]
+#[serde(rename_all = "camelCase")]
+pub struct EvictionPreviewParams {
+    #[serde(default)]
+    pub root: Option<String>,
+    #[serde(default)]
+    pub database_name: Option<String>,
+    /// Cap to simulate; defaults to ingest's `maxDatabaseSizeBytes` default.
+    #[serde(default)]
+    pub max_database_size_bytes: Option<f64>,
+    #[serde(default)]
+    pub protected_classifications: Option<Vec<Classification>>,
+    /// Example paths listed per category (default 5, max 50).
+    #[serde(default)]
+    pub example_limit: Option<u32>,
+}
+
+#[derive(Debug, Clone, Serialize, JsonSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct EvictionPreviewCategory {
+    pub tier: EvictionTier,
+    pub chunks: usize,
+    /// Distinct paths, in the order eviction would reach them.
+    pub example_paths: Vec<String>,
+}
+
+#[derive(Debug, Clone, Serialize, JsonSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct EvictionPreviewResponse {
+    pub database_path: String,
+    pub database_size_bytes: u64,
+    pub max_database_size_bytes: u64,
+    /// False when the database is under the cap; the counts below are then 0.
+    pub would_evict: bool,
+    pub target_size_bytes: u64,
+    pub chunks_to_evict: usize,
+    /// Upper bound: ingest trims nodes only if dropping chunks did not free
+    /// enough, and by less as the file shrinks.
+    pub max_nodes_to_evict: usize,
+    pub protected_chunks: usize,
+    /// Evicted chunks by tier, in eviction order.
+    pub categories: Vec<EvictionPreviewCategory>,
+    /// Least-hit graph node paths, the first to go.
+    pub example_node_paths: Vec<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub note: Option<String>,
 }
 
 #[derive(Debug)]
@@ -144,12 +540,15 @@ struct ScannedFile {
     hash: String,
     stored_content: Option<String>,
     text_content: Option<String>,
+    text_encoding: Option<TextEncoding>,
+    line_ending: Option<LineEnding>,
 }
 
 #[derive(Debug)]
 struct ScanOutcome {
     files: Vec<ScannedFile>,
     skipped: Vec<SkippedFile>,
+    gitignore_overrides: Vec<String>,
 }
 
 #[derive(Debug)]
@@ -185,8 +584,7 @@ struct TargetEntry {
 #[derive(Debug, Clone)]
 struct ExistingFileMetadata {
     hash: String,
-    modified: i64,
-    size: i64,
+    text_encoding: Option<TextEncoding>,
 }
 
 #[derive(Debug, Error)]
@@ -205,19 +603,89 @@ pub enum IngestError {
     },
     #[error("failed to compile glob set: {0}")]
     GlobSet(globset::Error),
+    #[error("failed to create database directory '{path}': {source}")]
+    DatabaseDirectory {
+        path: String,
+        #[source]
+        source: std::io::Error,
+    },
     #[error("SQLite error: {0}")]
     Sqlite(#[from] rusqlite::Error),
+    #[error("database '{path}' is on read-only media")]
+    ReadOnlyDatabase { path: String },
     #[error("embedding error: {0}")]
     Embedding(String),
+    #[error("database is pinned to embedding model '{pinned}' but ingest requested '{requested}'")]
+    EmbeddingModelMismatch {
+        pinned: String,
+        pinned_dimensions: Option<usize>,
+        requested: String,
+    },
+    #[error("database embeds chunks with normalization {stored:?} but ingest requested {requested:?}; changing it needs a full ingest")]
+    NormalizationChanged {
+        stored: ChunkNormalization,
+        requested: ChunkNormalization,
+    },
+    #[error("database was chunked with {stored:?} but ingest requested {requested:?}; changing chunking needs a full ingest")]
+    ChunkingChanged {
+        /// `None` for databases from before chunking settings were recorded.
+        stored: Option<ChunkingConfig>,
+        requested: ChunkingConfig,
+    },
+    #[error(transparent)]
+    DatabaseLocked(#[from] DatabaseLockError),
+    #[error(transparent)]
+    Sandbox(#[from] SandboxError),
     #[error("blocking task panicked: {0}")]
     Join(#[from] tokio::task::JoinError),
+    /// A full pass rolled back so a targeted ingest could run first; the
+    /// queue restarts it.
+    #[error("ingest yielded to a targeted ingest")]
+    Preempted,
 }
 
+/// Runs through the ingest queue: a path-scoped ingest waits for at most the
+/// current embedding batch of a full pass on the same database, which then
+/// rolls back and restarts after it.
 pub async fn ingest_codebase(params: IngestParams) -> Result<IngestResponse, IngestError> {
-    tokio::task::spawn_blocking(move || perform_ingest(params)).await?
+    let root = resolve_root(params.root.as_deref().unwrap_or("./"))?;
+    let database = database_path(
+        &root,
+        params
+            .database_name
+            .as_deref()
+            .unwrap_or(DEFAULT_DB_FILENAME),
+    );
+    let target_paths = params.paths.as_ref().map_or(0, Vec::len);
+    let priority = if target_paths > 0 {
+        IngestPriority::Interactive
+    } else {
+        IngestPriority::Background
+    };
+    let ticket = IngestTicket::enqueue(
+        database.to_string_lossy().to_string(),
+        priority,
+        target_paths,
+    );
+
+    loop {
+        ticket.wait_turn().await;
+        let preempt = ticket.preempt_flag();
+        let attempt = params.clone();
+        match tokio::task::spawn_blocking(move || perform_ingest(attempt, &preempt)).await? {
+            Err(IngestError::Preempted) => {
+                tracing::info!(database = %database.display(), "Full ingest yielded to a targeted ingest and will restart");
+                ticket.requeue_after_preemption();
+            }
+            result => return result,
+        }
+    }
 }
 
-fn perform_ingest(params: IngestParams) -> Result<IngestResponse, IngestError> {
+fn perform_ingest(
+    params: IngestParams,
+    preempt: &AtomicBool,
+) -> Result<IngestResponse, IngestError> {
     let start = Instant::now();
 
     let IngestParams {
@@ -227,10 +695,20 @@ fn perform_ingest(params: IngestParams) -> Result<IngestResponse, IngestError> {
         database_name,
         max_file_size_bytes,
         store_file_content,
+        store_content_globs,
+        skip_content_globs,
         paths,
         auto_evict,
         max_database_size_bytes,
+        protected_classifications,
         embedding,
+        allow_model_mix,
+        compress_content,
+        chunk_content_by_reference,
+        embedding_encoding,
+        include_ignored_paths,
+        include_hidden,
+        include_skipped,
     } = params;
 
     let root_param = root.unwrap_or_else(|| "./".to_string());
@@ -250,26 +728,32 @@ fn perform_ingest(params: IngestParams) -> Result<IngestResponse, IngestError> {
     });
     let database_name = database_name.unwrap_or_else(|| DEFAULT_DB_FILENAME.to_string());
 
-    let database_path = absolute_root.join(&database_name);
+    let database_path = database_path(&absolute_root, &database_name);
     let database_path_string = database_path.to_string_lossy().to_string();
 
     let max_file_size_bytes = max_file_size_bytes.map(|value| value.max(0.0).round() as u64);
-    let store_file_content = store_file_content.unwrap_or(true);
-    let embedding_config = resolve_embedding_config(embedding)?;
+    let content_policy = ContentPolicy::new(
+        store_file_content.unwrap_or(true),
+        store_content_globs.as_deref(),
+        skip_content_globs.as_deref(),
+    )?;
+    let compress_content = compress_content.unwrap_or(false);
+    let reference_chunks = chunk_content_by_reference.unwrap_or(false);
+    let embedding_encoding = embedding_encoding.unwrap_or_default();
+    let mut embedding_config = resolve_embedding_config(embedding)?;
     let auto_evict = auto_evict.unwrap_or(false);
+    let protected_classifications =
+        protected_classifications.unwrap_or_else(|| DEFAULT_PROTECTED_CLASSIFICATIONS.to_vec());
+    let allow_model_mix = allow_model_mix.unwrap_or(false);
     let max_database_size_bytes = max_database_size_bytes
         .map(|value| value.max(0.0).round() as u64)
         .unwrap_or(DEFAULT_MAX_DATABASE_SIZE_BYTES);
 
-    let root_metadata =
-        fs::metadata(&absolute_root).map_err(|source| IngestError::InvalidRoot {
-            path: absolute_root.to_string_lossy().to_string(),
-            source,
-        })?;
-    if !root_metadata.is_dir() {
-        return Err(IngestError::InvalidRoot {
-            path: absolute_root.to_string_lossy().to_string(),
-            source: std::io::Error::other("path is not a directory"),
+    ensure_root_directory(&absolute_root)?;
+    // fail before scanning; searches keep working against the existing index
+    if is_read_only_media(&database_path) {
+        return Err(IngestError::ReadOnlyDatabase {
+            path: database_path_string,
         });
     }
 
@@ -284,18 +768,24 @@ fn perform_ingest(params: IngestParams) -> Result<IngestResponse, IngestError> {
         &absolute_root,
         &include_globs,
         &exclude_globs,
-        store_file_content,
+        &content_policy,
         max_file_size_bytes,
         if using_target_paths {
             Some(&target_entries)
         } else {
             None
         },
+        include_ignored_paths.unwrap_or(false),
+        include_hidden.unwrap_or(false),
     )?;
+    if preempt.load(Ordering::Relaxed) {
+        return Err(IngestError::Preempted);
+    }
 
     let ScanOutcome {
         files: scanned_files,
         mut skipped,
+        gitignore_overrides,
     } = scan_outcome;
     for entry in &target_entries {
         if !entry.exists {
@@ -310,6 +800,14 @@ fn perform_ingest(params: IngestParams) -> Result<IngestResponse, IngestError> {
 
     let now_ms = timestamp_ms();
 
+    if let Some(parent) = database_path.parent() {
+        fs::create_dir_all(parent).map_err(|source| IngestError::DatabaseDirectory {
+            path: parent.to_string_lossy().to_string(),
+            source,
+        })?;
+    }
+    // held through the write transaction and any eviction that follows
+    let _lock = DatabaseLock::acquire(&database_path, "ingest_codebase")?;
     let mut conn = Connection::open_with_flags(
         &database_path,
         OpenFlags::SQLITE_OPEN_READ_WRITE | OpenFlags::SQLITE_OPEN_CREATE,
@@ -321,58 +819,150 @@ fn perform_ingest(params: IngestParams) -> Result<IngestResponse, IngestError> {
 
     let existing_files = load_existing_files(&transaction)?;
     let existing_models = load_existing_embedding_models(&transaction)?;
+
+    if embedding_config.enabled && !allow_model_mix {
+        let pinned_model = load_meta_value(&transaction, META_EMBEDDING_MODEL)?.or_else(|| {
+            let mut models: Vec<&String> = existing_models.values().collect();
+            models.sort();
+            models.dedup();
+            (models.len() == 1).then(|| models[0].clone())
+        });
+        if let Some(pinned) = pinned_model {
+            if pinned != embedding_config.model {
+                let pinned_dimensions = load_meta_value(&transaction, META_EMBEDDING_DIMENSIONS)?
+                    .and_then(|value| value.parse::<usize>().ok());
+                return Err(IngestError::EmbeddingModelMismatch {
+                    pinned,
+                    pinned_dimensions,
+                    requested: embedding_config.model.clone(),
+                });
+            }
+        }
+    }
+    let stored_normalization: ChunkNormalization =
+        load_meta_value(&transaction, META_CHUNK_NORMALIZATION)?
+            .and_then(|value| serde_json::from_str(&value).ok())
+            .unwrap_or_default();
+    let normalization = embedding_config
+        .normalization
+        .unwrap_or(stored_normalization);
+    // embeddings of unchanged files were made from differently normalized text
+    let normalization_changed = normalization != stored_normalization;
+    if embedding_config.enabled
+        && normalization_changed
+        && using_target_paths
+        && !existing_models.is_empty()
+    {
+        return Err(IngestError::NormalizationChanged {
+            stored: stored_normalization,
+            requested: normalization,
+        });
+    }
+    let stored_chunking: Option<ChunkingConfig> =
+        load_meta_value(&transaction, META_CHUNKING_CONFIG)?
+            .and_then(|value| serde_json::from_str(&value).ok());
+    if let Some(stored) = &stored_chunking {
+        embedding_config.inherit_chunking(stored);
+    }
+    let chunking = embedding_config.chunking_config();
+    // databases from before the config was recorded re-chunk only when the
+    // caller asks for specific settings
+    let chunking_changed = match &stored_chunking {
+        Some(stored) => *stored != chunking,
+        None => embedding_config.requested_chunking.any() && !existing_models.is_empty(),
+    };
+    if embedding_config.enabled
+        && chunking_changed
+        && using_target_paths
+        && !existing_models.is_empty()
+    {
+        return Err(IngestError::ChunkingChanged {
+            stored: stored_chunking,
+            requested: chunking,
+        });
+    }
+    if embedding_config.endpoint.is_none() && http_backend(&embedding_config.model).is_some() {
+        embedding_config.endpoint = load_embedding_endpoint(&transaction);
+    }
     let existing_paths: HashSet<String> = existing_files.keys().cloned().collect();
     let relevant_existing_paths: HashSet<String> = if using_target_paths {
         existing_paths
-            .iter()
-            .filter(|path| target_path_set.contains(*path))
-            .cloned()
+            .into_iter()
+            .filter(|path| is_within_targets(path, &target_path_set))
             .collect()
     } else {
-        existing_paths.clone()
+        existing_paths
     };
     let mut retained_paths: HashSet<String> = HashSet::new();
     let mut paths_to_clear: HashSet<String> = HashSet::new();
 
     let mut chunk_records_by_path: HashMap<String, Vec<ChunkRecord>> = HashMap::new();
+    let mut truncated_files: Vec<TruncatedFile> = Vec::new();
     let mut graph_records: HashMap<String, GraphExtraction> = HashMap::new();
     let mut chunk_locations: Vec<(String, usize)> = Vec::new();
+    let mut doc_symbols: Vec<(String, String, String)> = Vec::new();
 
     let mut ingested_count = 0usize;
     let mut reused_count = 0usize;
+    let mut package_resolver = PackageResolver::new(&absolute_root);
+    let reference_chunk_paths = load_reference_chunk_paths(&transaction)?;
+    let keyword_paths = load_keyword_paths(&transaction)?;
+    let mut file_keywords: Vec<(String, Vec<(String, f64)>)> = Vec::new();
+    // references need the file text in the database to slice from
+    let referenced_paths: HashSet<String> = if reference_chunks {
+        scanned_files
+            .iter()
+            .filter(|file| file.stored_content.is_some())
+            .map(|file| file.path.clone())
+            .collect()
+    } else {
+        HashSet::new()
+    };
 
     for file in &scanned_files {
         let path = file.path.clone();
-        let size_bytes = file.size as i64;
-        let modified = file.modified_ms;
-        let db_content = file.stored_content.clone();
         let existing = existing_files.get(&path);
+        // content hash only: checkouts and `touch` move mtimes without
+        // changing what would be chunked and embedded. Text that decodes
+        // differently than when it was chunked (BOM or non-UTF-8 files from
+        // before encodings were recorded) needs fresh byte ranges.
         let is_unchanged = existing
             .map(|metadata| {
                 metadata.hash == file.hash
-                    && metadata.modified == modified
-                    && metadata.size == size_bytes
+                    && (file.text_encoding == Some(TextEncoding::Utf8)
+                        || metadata.text_encoding == file.text_encoding)
             })
             .unwrap_or(false);
         let model_matches = existing_models
             .get(&path)
             .map(|model| model == &embedding_config.model)
-            .unwrap_or(false);
+            .unwrap_or(false)
+            && !normalization_changed
+            && !chunking_changed;
 
-        upsert_file(
-            &transaction,
-            &path,
-            size_bytes,
-            modified,
-            file.hash.clone(),
-            now_ms,
-            db_content,
-        )?;
+        upsert_file(&transaction, file, now_ms, compress_content)?;
+        replace_file_packages(&transaction, &path, &package_resolver.resolve(&path))?;
 
         retained_paths.insert(path.clone());
         ingested_count += 1;
 
+        // databases from before keyword routing backfill unchanged files once
+        if !is_unchanged || !keyword_paths.contains(&path) {
+            let keywords = file
+                .text_content
+                .as_deref()
+                .map(top_keywords)
+                .unwrap_or_default();
+            file_keywords.push((path.clone(), keywords));
+        }
+
         if is_unchanged && model_matches {
+            let reference_file = referenced_paths.contains(&path);
+            if reference_chunk_paths.contains(&path) != reference_file {
+                if let Some(text) = &file.text_content {
+                    convert_chunk_storage(&transaction, &path, text, reference_file)?;
+                }
+            }
             reused_count += 1;
             continue;
         }
@@ -381,11 +971,23 @@ fn perform_ingest(params: IngestParams) -> Result<IngestResponse, IngestError> {
 
         if let Some(text) = &file.text_content {
             if embedding_config.enabled {
-                let fragments = chunk_content(
+                let mut fragments = chunk_content(
                     text,
                     embedding_config.chunk_size_tokens,
                     embedding_config.chunk_overlap_tokens,
+                    chunk_boundaries(embedding_config.chunking, &path),
                 );
+                if let Some(cap) = embedding_config.max_chunks_per_file {
+                    let total_chunks = fragments.len();
+                    if total_chunks > cap {
+                        fragments = select_capped_fragments(fragments, cap, &path);
+                        truncated_files.push(TruncatedFile {
+                            path: path.clone(),
+                            total_chunks,
+                            kept_chunks: fragments.len(),
+                        });
+                    }
+                }
                 if !fragments.is_empty() {
                     let entry = chunk_records_by_path.entry(path.clone()).or_default();
                     for (index, fragment) in fragments.into_iter().enumerate() {
@@ -403,6 +1005,36 @@ fn perform_ingest(params: IngestParams) -> Result<IngestResponse, IngestError> {
                         chunk_locations.push((path.clone(), entry.len() - 1));
                     }
                 }
+
+                if embedding_config.doc_chunks {
+                    // same trimmed coordinates as chunk_content, so reference
+                    // storage can slice doc chunks back out
+                    let docs = extract_doc_comments(text.trim(), &path);
+                    if !docs.is_empty() {
+                        let entry = chunk_records_by_path.entry(path.clone()).or_default();
+                        for (index, doc) in docs.into_iter().enumerate() {
+                            let id = format!("{}:doc:{}", path, index);
+                            if let Some(symbol) = doc.symbol {
+                                doc_symbols.push((id.clone(), path.clone(), symbol));
+                            }
+                            let fragment = doc.fragment;
+                            // negative indexes keep doc chunks out of the
+                            // positional chunk sequence
+                            entry.push(ChunkRecord {
+                                id,
+                                path: path.clone(),
+                                chunk_index: -(index as i32) - 1,
+                                content: fragment.content,
+                                byte_start: Some(fragment.byte_start as i64),
+                                byte_end: Some(fragment.byte_end as i64),
+                                line_start: Some(fragment.line_start as i64),
+                                line_end: Some(fragment.line_end as i64),
+                                embedding: None,
+                            });
+                            chunk_locations.push((path.clone(), entry.len() - 1));
+                        }
+                    }
+                }
             }
 
             if let Some(extraction) = extract_graph(&path, text) {
@@ -411,17 +1043,7 @@ fn perform_ingest(params: IngestParams) -> Result<IngestResponse, IngestError> {
         }
     }
 
-    let deleted = if using_target_paths {
-        target_path_set
-            .iter()
-            .filter(|path| {
-                relevant_existing_paths.contains(*path) && !retained_paths.contains(*path)
-            })
-            .cloned()
-            .collect::<Vec<String>>()
-    } else {
-        compute_deleted(&existing_paths, &retained_paths)
-    };
+    let deleted = compute_deleted(&relevant_existing_paths, &retained_paths);
     let deleted_count = deleted.len();
     remove_deleted(&transaction, &deleted)?;
 
@@ -438,9 +1060,22 @@ fn perform_ingest(params: IngestParams) -> Result<IngestResponse, IngestError> {
         skipped.len(),
         deleted_count,
     )?;
+    record_skipped_files(&transaction, &ingestion_id, &skipped)?;
 
-    if let Ok(commit) = get_current_commit_sha(&absolute_root) {
-        upsert_meta(&transaction, "commit_sha", &commit, finished_ms)?;
+    let commit_sha = get_current_commit_sha(&absolute_root).ok();
+    if let Some(commit) = &commit_sha {
+        upsert_meta(&transaction, "commit_sha", commit, finished_ms)?;
+    }
+    // remote and repository prefix let search compose permalinks to the
+    // indexed commit
+    let remote_url = git_stdout(&absolute_root, &["config", "--get", "remote.origin.url"])
+        .ok()
+        .and_then(|value| normalize_remote_url(Some(value)));
+    if let Some(remote_url) = remote_url {
+        upsert_meta(&transaction, META_REMOTE_URL, &remote_url, finished_ms)?;
+    }
+    if let Ok(prefix) = git_stdout(&absolute_root, &["rev-parse", "--show-prefix"]) {
+        upsert_meta(&transaction, META_REPOSITORY_PREFIX, &prefix, finished_ms)?;
     }
     upsert_meta(
         &transaction,
@@ -460,13 +1095,25 @@ fn perform_ingest(params: IngestParams) -> Result<IngestResponse, IngestError> {
         }
     }
 
+    replace_file_keywords(&transaction, &file_keywords)?;
+
     let mut graph_node_count = 0usize;
     let mut graph_edge_count = 0usize;
 
     if !graph_records.is_empty() {
+        // an upsert rather than REPLACE: replacing a shared symbol node would
+        // cascade away the call edges other files point at it
         let mut insert_node_stmt = transaction.prepare(
-            "INSERT OR REPLACE INTO code_graph_nodes (id, path, kind, name, signature, range_start, range_end, metadata)
-             VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8)",
+            "INSERT INTO code_graph_nodes (id, path, kind, name, signature, range_start, range_end, metadata)
+             VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8)
+             ON CONFLICT(id) DO UPDATE SET
+                 path = excluded.path,
+                 kind = excluded.kind,
+                 name = excluded.name,
+                 signature = excluded.signature,
+                 range_start = excluded.range_start,
+                 range_end = excluded.range_end,
+                 metadata = excluded.metadata",
         )?;
         let mut insert_edge_stmt = transaction.prepare(
             "INSERT OR REPLACE INTO code_graph_edges (id, source_id, target_id, type, source_path, target_path, metadata)
@@ -516,78 +1163,177 @@ fn perform_ingest(params: IngestParams) -> Result<IngestResponse, IngestError> {
     }
 
     let mut embedded_chunk_count = 0usize;
+    let mut embedding_cache_hits = 0usize;
     let mut embedding_model_output: Option<String> = None;
 
     if embedding_config.enabled && !chunk_locations.is_empty() {
-        let embedder = get_or_create_embedder(&embedding_config)?;
-        let mut guard = embedder.lock().map_err(|error| {
-            IngestError::Embedding(format!("failed to acquire embedder: {error}"))
-        })?;
-
-        let stream_batch_size = embedding_config
-            .batch_size
-            .unwrap_or(DEFAULT_EMBEDDING_BATCH_SIZE)
-            .max(1);
-
-        let mut batch_start = 0usize;
-        while batch_start < chunk_locations.len() {
-            let batch_end = (batch_start + stream_batch_size).min(chunk_locations.len());
-            let mut batch_texts = Vec::with_capacity(batch_end - batch_start);
-
-            for (path, index) in &chunk_locations[batch_start..batch_end] {
-                let content = chunk_records_by_path
-                    .get(path)
-                    .and_then(|records| records.get(*index))
-                    .map(|record| record.content.clone())
-                    .unwrap_or_default();
-                batch_texts.push(content);
+        let chunk_text = |(path, index): &(String, usize)| {
+            chunk_records_by_path
+                .get(path)
+                .and_then(|records| records.get(*index))
+                .map(|record| normalization.apply(&record.content).into_owned())
+                .unwrap_or_default()
+        };
+        let chunk_keys: Vec<String> = chunk_locations
+            .iter()
+            .map(|location| embedding_cache_key(&chunk_text(location)))
+            .collect();
+        let mut vectors = load_cached_embeddings(
+            &transaction,
+            &embedding_config.model,
+            &chunk_keys.iter().map(String::as_str).collect::<Vec<_>>(),
+            now_ms,
+        )?;
+        // one position per distinct uncached text; repeats reuse its vector
+        let mut pending: Vec<usize> = Vec::new();
+        let mut pending_keys: HashSet<&str> = HashSet::new();
+        for (position, key) in chunk_keys.iter().enumerate() {
+            if !vectors.contains_key(key) && pending_keys.insert(key.as_str()) {
+                pending.push(position);
             }
+        }
+        embedding_cache_hits = chunk_locations.len() - pending.len();
+
+        if !pending.is_empty() {
+            let embedder = get_or_create_embedder(&embedding_config)?;
+            let mut guard = embedder.lock().map_err(|error| {
+                IngestError::Embedding(format!("failed to acquire embedder: {error}"))
+            })?;
+
+            let stream_batch_size = embedding_config
+                .batch_size
+                .unwrap_or(DEFAULT_EMBEDDING_BATCH_SIZE)
+                .max(1);
+
+            let mut embedded = 0usize;
+            for batch in pending.chunks(stream_batch_size) {
+                // dropping the transaction rolls back everything written so far
+                if preempt.load(Ordering::Relaxed) {
+                    return Err(IngestError::Preempted);
+                }
+                let batch_texts = batch
+                    .iter()
+                    .map(|position| chunk_text(&chunk_locations[*position]))
+                    .collect();
+
+                let embeddings = guard
+                    .embed(batch_texts, embedding_config.batch_size)
+                    .map_err(IngestError::Embedding)?;
+                if let Some(vector) = embeddings.first() {
+                    check_dimensions(&embedding_config.model, vector.len())
+                        .map_err(IngestError::Embedding)?;
+                }
 
-            let embeddings = guard
-                .embed(batch_texts, embedding_config.batch_size)
-                .map_err(|error| IngestError::Embedding(error.to_string()))?;
-
-            for (offset, embedding_vec) in embeddings.into_iter().enumerate() {
-                let (path, record_index) = &chunk_locations[batch_start + offset];
-                if let Some(records) = chunk_records_by_path.get_mut(path) {
-                    if let Some(record) = records.get_mut(*record_index) {
-                        record.embedding = Some(embedding_vec);
-                    }
+                let entries: Vec<(&str, &[f32])> = batch
+                    .iter()
+                    .zip(&embeddings)
+                    .map(|(position, vector)| (chunk_keys[*position].as_str(), vector.as_slice()))
+                    .collect();
+                store_cached_embeddings(
+                    &transaction,
+                    &embedding_config.model,
+                    &entries,
+                    embedding_encoding,
+                    now_ms,
+                )?;
+                for (position, vector) in batch.iter().zip(embeddings) {
+                    vectors.insert(chunk_keys[*position].clone(), vector);
                 }
+
+                embedded += batch.len();
+                tracing::debug!(embedded, total = pending.len(), "Embedded chunk batch");
             }
+        }
 
-            batch_start = batch_end;
+        for (location, key) in chunk_locations.iter().zip(&chunk_keys) {
+            let (path, record_index) = location;
+            if let Some(record) = chunk_records_by_path
+                .get_mut(path)
+                .and_then(|records| records.get_mut(*record_index))
+            {
+                record.embedding = vectors.get(key).cloned();
+            }
         }
 
         let mut insert_stmt = transaction.prepare(
-            "INSERT INTO file_chunks (id, path, chunk_index, content, embedding, embedding_model, byte_start, byte_end, line_start, line_end)
-             VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10)"
+            "INSERT INTO file_chunks (id, path, chunk_index, content, embedding, embedding_model, byte_start, byte_end, line_start, line_end, content_encoding, checksum, embedding_encoding, language, classification)
+             VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11, ?12, ?13, ?14, ?15)"
         )?;
-
         for records in chunk_records_by_path.values() {
             for record in records {
+                let reference_file = referenced_paths.contains(&record.path);
+                let chunk_encoding = reference_file.then_some(CHUNK_REFERENCE_ENCODING);
                 if let Some(embedding_vec) = &record.embedding {
-                    let blob = embedding_to_bytes(embedding_vec);
+                    let (blob, stored_encoding) =
+                        encode_embedding(embedding_vec, embedding_encoding);
+                    let checksum = chunk_checksum(&blob, &record.content);
+                    let classification =
+                        classify_chunk(&record.path, record.chunk_index, &record.content);
                     insert_stmt.execute(params![
                         &record.id,
                         &record.path,
                         record.chunk_index,
-                        &record.content,
+                        if reference_file {
+                            ""
+                        } else {
+                            record.content.as_str()
+                        },
                         blob,
                         &embedding_config.model,
                         record.byte_start,
                         record.byte_end,
                         record.line_start,
-                        record.line_end
+                        record.line_end,
+                        chunk_encoding,
+                        checksum,
+                        stored_encoding,
+                        detect_language(&record.path),
+                        classification.as_str()
                     ])?;
                     embedded_chunk_count += 1;
                 }
             }
         }
 
+        if !doc_symbols.is_empty() {
+            let mut link_stmt = transaction.prepare(
+                "INSERT OR REPLACE INTO chunk_doc_symbols (chunk_id, path, symbol)
+                 SELECT ?1, ?2, ?3 WHERE EXISTS (SELECT 1 FROM file_chunks WHERE id = ?1)",
+            )?;
+            for (chunk_id, path, symbol) in &doc_symbols {
+                link_stmt.execute(params![chunk_id, path, symbol])?;
+            }
+        }
+
         if embedded_chunk_count > 0 {
             embedding_model_output = Some(embedding_config.model.clone());
         }
+
+        let embedding_dimensions = chunk_records_by_path
+            .values()
+            .flatten()
+            .find_map(|record| record.embedding.as_ref().map(|vector| vector.len()));
+        pin_embedding_model(&transaction, embedding_dimensions, finished_ms)?;
+    }
+    if embedding_config.enabled {
+        backfill_chunk_labels(&transaction)?;
+        refresh_vector_index(&transaction, &embedding_config.model)?;
+        prune_embedding_cache(&transaction, &embedding_config.model)?;
+        upsert_meta(
+            &transaction,
+            META_CHUNK_NORMALIZATION,
+            &serde_json::to_string(&normalization).unwrap_or_default(),
+            finished_ms,
+        )?;
+        upsert_meta(
+            &transaction,
+            META_CHUNKING_CONFIG,
+            &serde_json::to_string(&chunking).unwrap_or_default(),
+            finished_ms,
+        )?;
+        if let Some(endpoint) = &embedding_config.endpoint {
+            upsert_meta(&transaction, META_EMBEDDING_ENDPOINT, endpoint, finished_ms)?;
+        }
     }
 
     transaction.commit()?;
@@ -597,7 +1343,13 @@ fn perform_ingest(params: IngestParams) -> Result<IngestResponse, IngestError> {
         .unwrap_or_default();
 
     let eviction_report = if auto_evict {
-        maybe_auto_evict(&database_path, database_size_bytes, max_database_size_bytes)?
+        maybe_auto_evict(
+            &absolute_root,
+            &database_path,
+            database_size_bytes,
+            max_database_size_bytes,
+            &protected_classifications,
+        )?
     } else {
         None
     };
@@ -606,6 +1358,20 @@ fn perform_ingest(params: IngestParams) -> Result<IngestResponse, IngestError> {
         database_size_bytes = report.size_after;
     }
 
+    // a failed snapshot costs time travel, not the ingest that just committed
+    let generation = snapshot_generation(
+        &conn,
+        &database_path,
+        &ingestion_id,
+        commit_sha.as_deref(),
+        finished_ms,
+        keep_generations(),
+    )
+    .unwrap_or_else(|error| {
+        tracing::warn!(?error, "Failed to snapshot index generation");
+        None
+    });
+
     let mut deleted_sorted = deleted;
     deleted_sorted.sort();
 
@@ -616,11 +1382,21 @@ fn perform_ingest(params: IngestParams) -> Result<IngestResponse, IngestError> {
         database_path: database_path_string,
         database_size_bytes,
         ingested_file_count: ingested_count,
-        skipped,
+        skipped_count: skipped.len(),
+        skipped_by_reason: count_skips_by_reason(&skipped),
+        skipped: if include_skipped.unwrap_or(false) {
+            skipped
+        } else {
+            Vec::new()
+        },
         deleted_paths: deleted_sorted,
         duration_ms,
         embedded_chunk_count,
+        embedding_cache_hits,
         embedding_model: embedding_model_output,
+        chunk_normalization: (embedding_config.enabled && !normalization.is_noop())
+            .then_some(normalization),
+        mock_embeddings: embedding_config.enabled && is_mock_model(&embedding_config.model),
         graph_node_count,
         graph_edge_count,
         evicted: eviction_report,
@@ -629,38 +1405,345 @@ fn perform_ingest(params: IngestParams) -> Result<IngestResponse, IngestError> {
         } else {
             None
         },
+        gitignore_overrides,
+        truncated_files,
+        generation,
     })
 }
 
+pub async fn estimate_ingest(
+    params: IngestEstimateParams,
+) -> Result<IngestEstimateResponse, IngestError> {
+    tokio::task::spawn_blocking(move || perform_estimate_ingest(params)).await?
+}
+
+fn perform_estimate_ingest(
+    params: IngestEstimateParams,
+) -> Result<IngestEstimateResponse, IngestError> {
+    let start = Instant::now();
+
+    let IngestEstimateParams {
+        root,
+        include,
+        exclude,
+        max_file_size_bytes,
+        store_file_content,
+        store_content_globs,
+        skip_content_globs,
+        paths,
+        embedding,
+        sample_chunks,
+        include_hidden,
+        embedding_encoding,
+    } = params;
+
+    let root_param = root.unwrap_or_else(|| "./".to_string());
+    let absolute_root = resolve_root(&root_param)?;
+    ensure_root_directory(&absolute_root)?;
+
+    let include_globs = include.unwrap_or_else(|| {
+        DEFAULT_INCLUDE_GLOBS
+            .iter()
+            .map(|s| s.to_string())
+            .collect()
+    });
+    let exclude_globs = exclude.unwrap_or_else(|| {
+        DEFAULT_EXCLUDE_GLOBS
+            .iter()
+            .map(|s| s.to_string())
+            .collect()
+    });
+    let max_file_size_bytes = max_file_size_bytes.map(|value| value.max(0.0).round() as u64);
+    let content_policy = ContentPolicy::new(
+        store_file_content.unwrap_or(true),
+        store_content_globs.as_deref(),
+        skip_content_globs.as_deref(),
+    )?;
+    let embedding_config = resolve_embedding_config(embedding)?;
+    let sample_limit = sample_chunks
+        .map(|value| value as usize)
+        .unwrap_or(DEFAULT_ESTIMATE_SAMPLE_CHUNKS);
+
+    let target_entries = resolve_target_entries(&absolute_root, paths);
+    let ScanOutcome { files, skipped, .. } = scan_workspace(
+        &absolute_root,
+        &include_globs,
+        &exclude_globs,
+        &content_policy,
+        max_file_size_bytes,
+        if target_entries.is_empty() {
+            None
+        } else {
+            Some(&target_entries)
+        },
+        false,
+        include_hidden.unwrap_or(false),
+    )?;
+    let scan_duration_ms = start.elapsed().as_millis();
+
+    let mut total_bytes = 0u64;
+    let mut text_bytes = 0u64;
+    let mut text_file_count = 0usize;
+    let mut file_table_bytes = 0u64;
+    let mut chunk_count = 0usize;
+    let mut chunk_content_bytes = 0u64;
+    let mut chunk_key_bytes = 0u64;
+    let mut samples: Vec<String> = Vec::new();
+
+    for file in &files {
+        total_bytes += file.size;
+        file_table_bytes += FILE_ROW_OVERHEAD_BYTES
+            + file.path.len() as u64
+            + file.hash.len() as u64
+            + file
+                .stored_content
+                .as_ref()
+                .map(|content| content.len() as u64)
+                .unwrap_or(0);
+
+        let Some(text) = &file.text_content else {
+            continue;
+        };
+        text_file_count += 1;
+        text_bytes += text.len() as u64;
+
+        if !embedding_config.enabled {
+            continue;
+        }
+
+        let mut fragments = chunk_content(
+            text,
+            embedding_config.chunk_size_tokens,
+            embedding_config.chunk_overlap_tokens,
+            chunk_boundaries(embedding_config.chunking, &file.path),
+        );
+        if let Some(cap) = embedding_config.max_chunks_per_file {
+            if fragments.len() > cap {
+                fragments = select_capped_fragments(fragments, cap, &file.path);
+            }
+        }
+        for (index, fragment) in fragments.into_iter().enumerate() {
+            chunk_count += 1;
+            chunk_content_bytes += fragment.content.len() as u64;
+            // id (`path:index`) plus the path column
+            chunk_key_bytes += (file.path.len() * 2 + index.to_string().len() + 1) as u64;
+            if samples.len() < sample_limit {
+                samples.push(fragment.content);
+            }
+        }
+    }
+
+    let mut embedding_dimensions = model_limits(&embedding_config.model)
+        .map_or(FALLBACK_EMBEDDING_DIMENSIONS, |limits| limits.dimensions);
+    let mut chunks_per_second = FALLBACK_CHUNKS_PER_SECOND;
+    let mut throughput_measured = false;
+
+    if embedding_config.enabled && !samples.is_empty() {
+        match measure_embedding_throughput(&embedding_config, samples) {
+            Ok((dimensions, throughput)) => {
+                embedding_dimensions = dimensions;
+                chunks_per_second = throughput;
+                throughput_measured = true;
+            }
+            Err(error) => {
+                tracing::warn!(?error, "Embedding throughput sample failed; using fallback");
+            }
+        }
+    }
+
+    let embedding_bytes = embedding_encoding
+        .unwrap_or_default()
+        .estimated_bytes(embedding_dimensions);
+    let chunk_table_bytes = if embedding_config.enabled {
+        chunk_count as u64 * (CHUNK_ROW_OVERHEAD_BYTES + embedding_bytes)
+            + chunk_content_bytes
+            + chunk_key_bytes
+    } else {
+        0
+    };
+
+    let estimated_embedding_ms = if embedding_config.enabled && chunk_count > 0 {
+        ((chunk_count as f64 / chunks_per_second.max(f64::EPSILON)) * 1000.0).ceil() as u64
+    } else {
+        0
+    };
+
+    // scanned text is held twice for files whose content is stored, and every
+    // chunk record keeps its text and embedding until the transaction commits
+    let held_text_bytes = text_bytes
+        + files
+            .iter()
+            .filter_map(|file| file.stored_content.as_ref())
+            .map(|content| content.len() as u64)
+            .sum::<u64>();
+    let estimated_peak_memory_bytes = held_text_bytes
+        + chunk_content_bytes
+        + chunk_key_bytes
+        + chunk_count as u64 * (embedding_bytes + CHUNK_RECORD_MEMORY_OVERHEAD_BYTES);
+
+    Ok(IngestEstimateResponse {
+        root: absolute_root.to_string_lossy().to_string(),
+        file_count: files.len(),
+        text_file_count,
+        skipped_count: skipped.len(),
+        total_bytes,
+        estimated_chunk_count: chunk_count,
+        embedding_enabled: embedding_config.enabled,
+        embedding_model: embedding_config.model.clone(),
+        chunk_size_tokens: embedding_config.chunk_size_tokens,
+        chunk_overlap_tokens: embedding_config.chunk_overlap_tokens,
+        embedding_dimensions,
+        chunks_per_second,
+        throughput_measured,
+        estimated_database_size_bytes: file_table_bytes + chunk_table_bytes,
+        estimated_embedding_ms,
+        estimated_peak_memory_bytes,
+        scan_duration_ms,
+    })
+}
+
+fn measure_embedding_throughput(
+    config: &EmbeddingConfig,
+    samples: Vec<String>,
+) -> Result<(usize, f64), IngestError> {
+    let embedder = get_or_create_embedder(config)?;
+    let mut guard = embedder
+        .lock()
+        .map_err(|error| IngestError::Embedding(format!("failed to acquire embedder: {error}")))?;
+
+    let sample_count = samples.len();
+    let started = Instant::now();
+    let embeddings = guard
+        .embed(samples, config.batch_size)
+        .map_err(IngestError::Embedding)?;
+    let elapsed = started.elapsed().as_secs_f64().max(0.001);
+
+    let dimensions = embeddings
+        .first()
+        .map(|vector| vector.len())
+        .unwrap_or(FALLBACK_EMBEDDING_DIMENSIONS);
+
+    Ok((dimensions, sample_count as f64 / elapsed))
+}
+
+fn ensure_root_directory(root: &Path) -> Result<(), IngestError> {
+    let metadata = fs::metadata(root).map_err(|source| IngestError::InvalidRoot {
+        path: root.to_string_lossy().to_string(),
+        source,
+    })?;
+    if !metadata.is_dir() {
+        return Err(IngestError::InvalidRoot {
+            path: root.to_string_lossy().to_string(),
+            source: std::io::Error::other("path is not a directory"),
+        });
+    }
+    ensure_root_allowed(root)?;
+    Ok(())
+}
+
 fn resolve_embedding_config(
     params: Option<EmbeddingParams>,
 ) -> Result<EmbeddingConfig, IngestError> {
     let params = params.unwrap_or_default();
     let enabled = params.enabled.unwrap_or(true);
 
-    let model = params
-        .model
-        .unwrap_or_else(|| DEFAULT_EMBEDDING_MODEL.to_string());
+    let preset = match params.preset.as_deref() {
+        Some(name) => Some(find_preset(name).ok_or_else(|| {
+            IngestError::Embedding(format!(
+                "Unknown embedding preset '{name}'; expected one of {}",
+                preset_names().join(", ")
+            ))
+        })?),
+        None => None,
+    };
 
-    let model_variant = EmbeddingModel::from_str(&model).map_err(|error| {
-        IngestError::Embedding(format!("Unknown embedding model '{model}': {error}"))
-    })?;
+    let provider = params.provider.unwrap_or_default();
+    let model = match provider {
+        EmbeddingProvider::Fastembed => params
+            .model
+            .map(|name| resolve_model_name(&name))
+            .or_else(|| preset.map(|preset| preset.model.to_string()))
+            .unwrap_or_else(|| DEFAULT_EMBEDDING_MODEL.to_string()),
+        EmbeddingProvider::Mock => match params.model {
+            Some(name) if !is_mock_model(&name) => {
+                return Err(IngestError::Embedding(format!(
+                    "Embedding model '{name}' needs the fastembed provider; the mock provider always uses '{MOCK_EMBEDDING_MODEL}'"
+                )));
+            }
+            _ => MOCK_EMBEDDING_MODEL.to_string(),
+        },
+        EmbeddingProvider::OpenAi | EmbeddingProvider::Ollama => {
+            let (prefix, default_model) = if provider == EmbeddingProvider::OpenAi {
+                (OPENAI_MODEL_PREFIX, DEFAULT_OPENAI_EMBEDDING_MODEL)
+            } else {
+                (OLLAMA_MODEL_PREFIX, DEFAULT_OLLAMA_EMBEDDING_MODEL)
+            };
+            let name = params
+                .model
+                .as_deref()
+                .map(|name| name.trim().trim_start_matches(prefix))
+                .filter(|name| !name.is_empty())
+                .unwrap_or(default_model);
+            format!("{prefix}{name}")
+        }
+    };
+    let endpoint = params
+        .endpoint
+        .map(|endpoint| endpoint.trim().to_string())
+        .filter(|endpoint| !endpoint.is_empty());
+    if endpoint.is_some() && http_backend(&model).is_none() {
+        return Err(IngestError::Embedding(
+            "embedding.endpoint applies to the openai and ollama providers".to_string(),
+        ));
+    }
 
-    let chunk_size_tokens = params
+    let model_variant = if is_mock_model(&model) || http_backend(&model).is_some() {
+        None
+    } else {
+        Some(EmbeddingModel::from_str(&model).map_err(|error| {
+            IngestError::Embedding(format!("Unknown embedding model '{model}': {error}"))
+        })?)
+    };
+    let limits = model_limits(&model);
+    if let Some(limits) = limits {
+        check_dimensions(&model, limits.dimensions).map_err(IngestError::Embedding)?;
+    }
+
+    let requested_chunking = RequestedChunking {
+        sizes: params.chunk_size_tokens.is_some()
+            || params.chunk_overlap_tokens.is_some()
+            || preset.is_some(),
+        mode: params.chunking.is_some(),
+        doc_chunks: params.doc_chunks.is_some(),
+        max_chunks_per_file: params.max_chunks_per_file.is_some(),
+    };
+    let mut chunk_size_tokens = params
         .chunk_size_tokens
         .map(|value| value.max(1) as usize)
+        .or_else(|| preset.map(|preset| preset.chunk_size_tokens))
         .unwrap_or(DEFAULT_CHUNK_SIZE_TOKENS);
+    if let Some(limits) = limits.filter(|limits| chunk_size_tokens > limits.max_input_tokens) {
+        // the model would truncate anything longer without saying so
+        tracing::warn!(
+            model = %model,
+            requested = chunk_size_tokens,
+            window = limits.max_input_tokens,
+            "Chunk size exceeds the model's input window; clamping"
+        );
+        chunk_size_tokens = limits.max_input_tokens;
+    }
 
     let chunk_overlap_tokens = params
         .chunk_overlap_tokens
         .map(|value| value as usize)
+        .or_else(|| preset.map(|preset| preset.chunk_overlap_tokens))
         .unwrap_or(DEFAULT_CHUNK_OVERLAP_TOKENS)
         .min(chunk_size_tokens);
 
     let batch_size = match params.batch_size {
         Some(value) => Some(value.max(1) as usize),
         None => {
-            if is_quantized_model(&model_variant) {
+            if model_variant.as_ref().is_some_and(is_quantized_model) {
                 None
             } else {
                 Some(DEFAULT_EMBEDDING_BATCH_SIZE)
@@ -675,6 +1758,16 @@ fn resolve_embedding_config(
         chunk_size_tokens,
         chunk_overlap_tokens,
         batch_size,
+        chunking: params.chunking.unwrap_or_default(),
+        doc_chunks: params.doc_chunks.unwrap_or(false),
+        max_chunks_per_file: match params.max_chunks_per_file {
+            Some(0) => None,
+            Some(value) => Some(value as usize),
+            None => Some(DEFAULT_MAX_CHUNKS_PER_FILE),
+        },
+        requested_chunking,
+        normalization: params.normalize,
+        endpoint,
     })
 }
 
@@ -715,13 +1808,8 @@ fn resolve_target_entries(root: &Path, paths: Option<Vec<String>>) -> Vec<Target
             root.join(&candidate)
         };
 
-        if !absolute.starts_with(root) {
+        let Some(relative_pathbuf) = relative_to_root(root, &absolute) else {
             continue;
-        }
-
-        let relative_pathbuf = match absolute.strip_prefix(root) {
-            Ok(relative) => relative.to_path_buf(),
-            Err(_) => continue,
         };
 
         if relative_pathbuf.as_os_str().is_empty() {
@@ -733,6 +1821,15 @@ fn resolve_target_entries(root: &Path, paths: Option<Vec<String>>) -> Vec<Target
             continue;
         }
 
+        // `..` segments and symlinks can point outside the root even when the
+        // joined path looks nested
+        if resolve_within_root(root, &relative).is_err() {
+            continue;
+        }
+
+        // rebuilt from the root so a differently cased or `\\?\`-prefixed
+        // spelling of it still lines up with the walk below
+        let absolute = root.join(&relative);
         let metadata = fs::metadata(&absolute).ok();
         let (exists, is_dir) = match metadata {
             Some(meta) => (true, meta.is_dir()),
@@ -750,10 +1847,142 @@ fn resolve_target_entries(root: &Path, paths: Option<Vec<String>>) -> Vec<Target
     entries
 }
 
+pub(crate) fn is_generated_file(path: &str, head: &str) -> bool {
+    GENERATED_FILE_SUFFIXES
+        .iter()
+        .any(|suffix| path.ends_with(suffix))
+        || GENERATED_MARKERS.iter().any(|marker| head.contains(marker))
+}
+
+/// Eviction order, cheapest loss first: chunks of files gone from disk, then
+/// comments and doc chunks, then generated code, then everything else.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize, JsonSchema)]
+#[serde(rename_all = "camelCase")]
+pub enum EvictionTier {
+    MissingFile,
+    Comment,
+    Generated,
+    Code,
+}
+
+struct EvictionCandidate {
+    id: String,
+    path: String,
+    tier: EvictionTier,
+    hits: i64,
+    chunk_index: i32,
+}
+
+/// Labels chunks kept from ingests before `language` and `classification`
+/// were recorded, so search can filter every row in SQL.
+fn backfill_chunk_labels(conn: &Connection) -> Result<(), rusqlite::Error> {
+    let mut stmt = conn.prepare(&format!(
+        "SELECT id, path, chunk_index, content, {}, byte_start, byte_end
+         FROM file_chunks WHERE classification IS NULL",
+        content_encoding_column(conn, "file_chunks")
+    ))?;
+    let mut stored_content = StoredContentCache::new(conn)?;
+    let mut labels = Vec::new();
+    let mut rows = stmt.query([])?;
+    while let Some(row) = rows.next()? {
+        let id: String = row.get(0)?;
+        let path: String = row.get(1)?;
+        let chunk_index: i32 = row.get(2)?;
+        let encoding: Option<String> = row.get(4)?;
+        let content = stored_content.resolve_chunk(
+            &path,
+            row.get(3)?,
+            encoding.as_deref(),
+            row.get(5)?,
+            row.get(6)?,
+        );
+        let classification = classify_chunk(&path, chunk_index, &content);
+        labels.push((id, detect_language(&path), classification));
+    }
+    let mut update =
+        conn.prepare("UPDATE file_chunks SET language = ?2, classification = ?3 WHERE id = ?1")?;
+    for (id, language, classification) in &labels {
+        update.execute(params![id, language, classification.as_str()])?;
+    }
+    Ok(())
+}
+
+/// Ranks every chunk for eviction and drops the protected ones.
+fn eviction_candidates(
+    conn: &Connection,
+    root: &Path,
+    protected: &[Classification],
+) -> Result<(Vec<EvictionCandidate>, usize), IngestError> {
+    let encoding_column = content_encoding_column(conn, "file_chunks");
+    let mut stmt = conn.prepare(&format!(
+        "SELECT id, path, chunk_index, content, {encoding_column}, byte_start, byte_end, COALESCE(hits, 0)
+         FROM file_chunks"
+    ))?;
+    let mut stored_content = StoredContentCache::new(conn)?;
+    let mut present: HashMap<String, bool> = HashMap::new();
+    let mut generated: HashMap<String, bool> = HashMap::new();
+    let mut candidates = Vec::new();
+    let mut protected_chunks = 0usize;
+
+    let mut rows = stmt.query([])?;
+    while let Some(row) = rows.next()? {
+        let id: String = row.get(0)?;
+        let path: String = row.get(1)?;
+        let chunk_index: i32 = row.get(2)?;
+        let encoding: Option<String> = row.get(4)?;
+        let content = stored_content.resolve_chunk(
+            &path,
+            row.get(3)?,
+            encoding.as_deref(),
+            row.get(5)?,
+            row.get(6)?,
+        );
+        let hits: i64 = row.get(7)?;
+
+        let exists = *present
+            .entry(path.clone())
+            .or_insert_with(|| root.join(&path).is_file());
+        let classification = classify_chunk(&path, chunk_index, &content);
+        if exists && protected.contains(&classification) {
+            protected_chunks += 1;
+            continue;
+        }
+        let tier = if !exists {
+            EvictionTier::MissingFile
+        } else if classification == Classification::Comment {
+            EvictionTier::Comment
+        } else if *generated.entry(path.clone()).or_insert_with(|| {
+            let head = stored_content.get(&path).unwrap_or(&content);
+            is_generated_file(&path, head)
+        }) {
+            EvictionTier::Generated
+        } else {
+            EvictionTier::Code
+        };
+        candidates.push(EvictionCandidate {
+            id,
+            path,
+            tier,
+            hits,
+            chunk_index,
+        });
+    }
+
+    candidates.sort_by(|left, right| {
+        left.tier
+            .cmp(&right.tier)
+            .then_with(|| left.hits.cmp(&right.hits))
+            .then_with(|| left.chunk_index.cmp(&right.chunk_index))
+    });
+    Ok((candidates, protected_chunks))
+}
+
 fn maybe_auto_evict(
+    root: &Path,
     database_path: &Path,
     database_size_bytes: u64,
     max_database_size_bytes: u64,
+    protected: &[Classification],
 ) -> Result<Option<EvictionReport>, IngestError> {
     if max_database_size_bytes == 0 {
         return Ok(None);
@@ -762,38 +1991,51 @@ fn maybe_auto_evict(
         return Ok(None);
     }
 
-    let target_size = ((max_database_size_bytes as f64) * 0.8).round() as u64;
-    let bytes_to_free = database_size_bytes.saturating_sub(target_size);
-    if bytes_to_free == 0 {
+    let target_size = eviction_target_size(max_database_size_bytes);
+    if database_size_bytes <= target_size {
         return Ok(None);
     }
 
+    // eviction ranks chunks by hits, so queued search hits must land first
+    flush_pending_hits_for(database_path);
     let conn = Connection::open(database_path)?;
     conn.execute("PRAGMA foreign_keys = ON", [])?;
 
+    // cached vectors go before any live data
+    let evicted_cache_entries = clear_embedding_cache(&conn)?;
+    let size_before = database_size_bytes;
+    let database_size_bytes = if evicted_cache_entries > 0 {
+        conn.execute_batch("VACUUM")?;
+        fs::metadata(database_path)
+            .map(|meta| meta.len())
+            .unwrap_or(database_size_bytes)
+    } else {
+        database_size_bytes
+    };
+    let bytes_to_free = database_size_bytes.saturating_sub(target_size);
+
     let total_chunks = query_table_count(&conn, "file_chunks")?;
     let total_nodes = query_table_count(&conn, "code_graph_nodes")?;
 
     let mut evicted_chunks = 0usize;
     let mut evicted_nodes = 0usize;
+    let mut protected_chunks = 0usize;
 
     if total_chunks > 0 && database_size_bytes > 0 {
-        let chunk_count_to_evict = ((bytes_to_free as f64 / database_size_bytes as f64)
-            * 0.5
-            * total_chunks as f64)
-            .ceil() as i64;
+        let chunk_count_to_evict =
+            chunk_eviction_count(bytes_to_free, database_size_bytes, total_chunks);
 
         if chunk_count_to_evict > 0 {
-            let result = conn.execute(
-                "DELETE FROM file_chunks
-                 WHERE id IN (
-                     SELECT id FROM file_chunks
-                     ORDER BY COALESCE(hits, 0) ASC, chunk_index ASC
-                     LIMIT ?1
-                 )",
-                params![chunk_count_to_evict],
-            )?;
-            evicted_chunks = result as usize;
+            let (candidates, protected_count) = eviction_candidates(&conn, root, protected)?;
+            protected_chunks = protected_count;
+            let tx = conn.unchecked_transaction()?;
+            {
+                let mut delete = tx.prepare("DELETE FROM file_chunks WHERE id = ?1")?;
+                for candidate in candidates.iter().take(chunk_count_to_evict) {
+                    evicted_chunks += delete.execute(params![candidate.id])?;
+                }
+            }
+            tx.commit()?;
         }
     }
 
@@ -803,11 +2045,11 @@ fn maybe_auto_evict(
     };
 
     if total_nodes > 0 && size_after_chunks > target_size && database_size_bytes > 0 {
-        let node_count_to_evict = ((size_after_chunks.saturating_sub(target_size) as f64
-            / database_size_bytes as f64)
-            * 0.3
-            * total_nodes as f64)
-            .ceil() as i64;
+        let node_count_to_evict = node_eviction_count(
+            size_after_chunks.saturating_sub(target_size),
+            database_size_bytes,
+            total_nodes,
+        ) as i64;
 
         if node_count_to_evict > 0 {
             let result = conn.execute(
@@ -823,7 +2065,9 @@ fn maybe_auto_evict(
         }
     }
 
-    conn.execute_batch("VACUUM")?;
+    if evicted_chunks > 0 || evicted_nodes > 0 {
+        conn.execute_batch("VACUUM")?;
+    }
 
     let size_after = match fs::metadata(database_path) {
         Ok(meta) => meta.len(),
@@ -835,26 +2079,204 @@ fn maybe_auto_evict(
 
     Ok(Some(EvictionReport {
         database_path: database_path.to_string_lossy().to_string(),
-        size_before: database_size_bytes,
+        size_before,
         size_after,
         evicted_chunks,
         evicted_nodes,
+        evicted_cache_entries,
+        protected_chunks,
     }))
 }
 
+/// Eviction target: 80% of the cap, so one ingest past it does not evict
+/// again on the next.
+fn eviction_target_size(max_database_size_bytes: u64) -> u64 {
+    ((max_database_size_bytes as f64) * 0.8).round() as u64
+}
+
+fn chunk_eviction_count(
+    bytes_to_free: u64,
+    database_size_bytes: u64,
+    total_chunks: usize,
+) -> usize {
+    ((bytes_to_free as f64 / database_size_bytes as f64) * 0.5 * total_chunks as f64).ceil()
+        as usize
+}
+
+fn node_eviction_count(bytes_to_free: u64, database_size_bytes: u64, total_nodes: usize) -> usize {
+    ((bytes_to_free as f64 / database_size_bytes as f64) * 0.3 * total_nodes as f64).ceil() as usize
+}
+
+pub async fn preview_eviction(
+    params: EvictionPreviewParams,
+) -> Result<EvictionPreviewResponse, IngestError> {
+    tokio::task::spawn_blocking(move || perform_preview_eviction(params)).await?
+}
+
+/// Runs auto-eviction's ranking against the current database without
+/// deleting anything.
+fn perform_preview_eviction(
+    params: EvictionPreviewParams,
+) -> Result<EvictionPreviewResponse, IngestError> {
+    let root_param = params.root.unwrap_or_else(|| "./".to_string());
+    let absolute_root = resolve_root(&root_param)?;
+    ensure_root_directory(&absolute_root)?;
+    let database_path = database_path(
+        &absolute_root,
+        params
+            .database_name
+            .as_deref()
+            .unwrap_or(DEFAULT_DB_FILENAME),
+    );
+    let max_database_size_bytes = params
+        .max_database_size_bytes
+        .map(|value| value.max(0.0).round() as u64)
+        .unwrap_or(DEFAULT_MAX_DATABASE_SIZE_BYTES);
+    let protected = params
+        .protected_classifications
+        .unwrap_or_else(|| DEFAULT_PROTECTED_CLASSIFICATIONS.to_vec());
+    let example_limit = params
+        .example_limit
+        .map_or(DEFAULT_EVICTION_EXAMPLES, |value| value as usize)
+        .clamp(1, MAX_EVICTION_EXAMPLES);
+
+    let database_size_bytes = fs::metadata(&database_path)
+        .map(|meta| meta.len())
+        .unwrap_or_default();
+    let target_size = eviction_target_size(max_database_size_bytes);
+    let mut response = EvictionPreviewResponse {
+        database_path: database_path.to_string_lossy().to_string(),
+        database_size_bytes,
+        max_database_size_bytes,
+        would_evict: false,
+        target_size_bytes: target_size,
+        chunks_to_evict: 0,
+        max_nodes_to_evict: 0,
+        protected_chunks: 0,
+        categories: Vec::new(),
+        example_node_paths: Vec::new(),
+        note: None,
+    };
+    if !database_path.is_file() {
+        response.note = Some("No index yet; run ingest_codebase first.".to_string());
+        return Ok(response);
+    }
+    if max_database_size_bytes == 0 {
+        response.note = Some("A cap of 0 disables eviction.".to_string());
+        return Ok(response);
+    }
+    if database_size_bytes <= max_database_size_bytes {
+        response.note = Some("The database is under the cap; autoEvict would not run.".to_string());
+        return Ok(response);
+    }
+
+    // same ordering ingest would see, so queued hits land first
+    flush_pending_hits_for(&database_path);
+    let conn = open_read_only(&database_path)?;
+    let bytes_to_free = database_size_bytes.saturating_sub(target_size);
+    let total_chunks = query_table_count(&conn, "file_chunks")?;
+    let total_nodes = query_table_count(&conn, "code_graph_nodes")?;
+    response.would_evict = true;
+
+    let (candidates, protected_chunks) = eviction_candidates(&conn, &absolute_root, &protected)?;
+    response.protected_chunks = protected_chunks;
+    let chunk_count = chunk_eviction_count(bytes_to_free, database_size_bytes, total_chunks)
+        .min(candidates.len());
+    response.chunks_to_evict = chunk_count;
+    for candidate in candidates.iter().take(chunk_count) {
+        if response.categories.last().map(|category| category.tier) != Some(candidate.tier) {
+            response.categories.push(EvictionPreviewCategory {
+                tier: candidate.tier,
+                chunks: 0,
+                example_paths: Vec::new(),
+            });
+        }
+        let Some(category) = response.categories.last_mut() else {
+            continue;
+        };
+        category.chunks += 1;
+        if category.example_paths.len() < example_limit
+            && !category.example_paths.contains(&candidate.path)
+        {
+            category.example_paths.push(candidate.path.clone());
+        }
+    }
+
+    let node_count =
+        node_eviction_count(bytes_to_free, database_size_bytes, total_nodes).min(total_nodes);
+    response.max_nodes_to_evict = node_count;
+    if node_count > 0 {
+        let mut stmt = conn.prepare(
+            "SELECT DISTINCT path FROM (
+                 SELECT path FROM code_graph_nodes
+                 ORDER BY COALESCE(hits, 0) ASC
+                 LIMIT ?1
+             ) WHERE path IS NOT NULL LIMIT ?2",
+        )?;
+        let rows = stmt.query_map(params![node_count as i64, example_limit as i64], |row| {
+            row.get::<_, String>(0)
+        })?;
+        for row in rows {
+            response.example_node_paths.push(row?);
+        }
+    }
+    Ok(response)
+}
+
+/// Which files keep their text in the database: `skip` globs win, then
+/// `store` globs, then the `storeFileContent` default.
+struct ContentPolicy {
+    default: bool,
+    store: Option<GlobSet>,
+    skip: Option<GlobSet>,
+}
+
+impl ContentPolicy {
+    fn new(
+        default: bool,
+        store: Option<&[String]>,
+        skip: Option<&[String]>,
+    ) -> Result<Self, IngestError> {
+        Ok(Self {
+            default,
+            store: compile_globs(store.unwrap_or_default())?,
+            skip: compile_globs(skip.unwrap_or_default())?,
+        })
+    }
+
+    fn stores(&self, relative_path: &Path) -> bool {
+        if self
+            .skip
+            .as_ref()
+            .is_some_and(|set| set.is_match(relative_path))
+        {
+            return false;
+        }
+        self.default
+            || self
+                .store
+                .as_ref()
+                .is_some_and(|set| set.is_match(relative_path))
+    }
+}
+
+#[allow(clippy::too_many_arguments)]
 fn scan_workspace(
     root: &Path,
     include_patterns: &[String],
     exclude_patterns: &[String],
-    store_file_content: bool,
+    content_policy: &ContentPolicy,
     max_file_size_bytes: Option<u64>,
     target_entries: Option<&[TargetEntry]>,
+    bypass_gitignore: bool,
+    include_hidden: bool,
 ) -> Result<ScanOutcome, IngestError> {
     let include_globs = compile_globs(include_patterns)?;
     let exclude_globs = compile_globs(exclude_patterns)?;
 
     let mut files = Vec::new();
     let mut skipped = Vec::new();
+    let mut gitignore_overrides = Vec::new();
 
     if let Some(entries) = target_entries {
         let mut seen_abs = HashSet::new();
@@ -871,46 +2293,92 @@ fn scan_workspace(
                 continue;
             }
 
-            let walker = build_ignore_walk(&entry.absolute, entry.is_dir);
+            let relative_target = entry
+                .absolute
+                .strip_prefix(root)
+                .unwrap_or(entry.absolute.as_path());
+            if !include_hidden && has_hidden_component(relative_target) {
+                skipped.push(SkippedFile {
+                    path: entry.relative.clone(),
+                    reason: "hidden".to_string(),
+                    size: None,
+                    message: None,
+                });
+                continue;
+            }
+            // the walker always yields its starting path and does not apply a
+            // parent directory's rules below it, so check the target itself
+            let target_ignored = gitignore_match(root, &entry.absolute, entry.is_dir)
+                .is_some_and(|rule| !rule.whitelisted);
+            if target_ignored && !bypass_gitignore {
+                skipped.push(SkippedFile {
+                    path: entry.relative.clone(),
+                    reason: "gitignore".to_string(),
+                    size: None,
+                    message: None,
+                });
+                continue;
+            }
+
+            let first_new_file = files.len();
+            let walker = build_ignore_walk(
+                root,
+                &entry.absolute,
+                entry.is_dir,
+                !bypass_gitignore,
+                include_hidden,
+            );
             collect_files_from_walk(
                 root,
                 walker,
                 include_globs.as_ref(),
                 exclude_globs.as_ref(),
-                store_file_content,
+                content_policy,
                 max_file_size_bytes,
                 &mut files,
                 &mut skipped,
             );
+
+            if target_ignored {
+                gitignore_overrides
+                    .extend(files[first_new_file..].iter().map(|file| file.path.clone()));
+            } else if bypass_gitignore {
+                // rewalk with ignore rules to learn which files the bypass added
+                let respected: HashSet<PathBuf> =
+                    build_ignore_walk(root, &entry.absolute, entry.is_dir, true, include_hidden)
+                        .flatten()
+                        .map(|walked| walked.into_path())
+                        .collect();
+                gitignore_overrides.extend(
+                    files[first_new_file..]
+                        .iter()
+                        .filter(|file| !respected.contains(&root.join(&file.path)))
+                        .map(|file| file.path.clone()),
+                );
+            }
         }
     } else {
-        let walker = build_ignore_walk(root, true);
+        let walker = build_ignore_walk(root, root, true, true, include_hidden);
         collect_files_from_walk(
             root,
             walker,
             include_globs.as_ref(),
             exclude_globs.as_ref(),
-            store_file_content,
+            content_policy,
             max_file_size_bytes,
             &mut files,
             &mut skipped,
         );
     }
 
-    Ok(ScanOutcome { files, skipped })
-}
+    gitignore_overrides.sort();
+    gitignore_overrides.dedup();
 
-fn build_ignore_walk(path: &Path, is_dir: bool) -> ignore::Walk {
-    let mut builder = WalkBuilder::new(path);
-    builder.follow_links(false);
-    builder.hidden(false);
-    builder.git_ignore(true);
-    builder.git_global(true);
-    builder.git_exclude(true);
-    if !is_dir {
-        builder.max_depth(Some(1));
-    }
-    builder.build()
+    Ok(ScanOutcome {
+        files,
+        skipped,
+        gitignore_overrides,
+    })
 }
 
 #[allow(clippy::too_many_arguments)]
@@ -919,7 +2387,7 @@ fn collect_files_from_walk(
     walker: ignore::Walk,
     include_globs: Option<&GlobSet>,
     exclude_globs: Option<&GlobSet>,
-    store_file_content: bool,
+    content_policy: &ContentPolicy,
     max_file_size_bytes: Option<u64>,
     files: &mut Vec<ScannedFile>,
     skipped: &mut Vec<SkippedFile>,
@@ -1008,13 +2476,14 @@ fn collect_files_from_walk(
 
         let hash = hex::encode(Sha256::digest(&bytes));
 
-        let text_content = if is_binary(&bytes) {
-            None
-        } else {
-            Some(String::from_utf8_lossy(&bytes).into_owned())
-        };
+        let decoded = decode_text(&bytes);
+        let text_encoding = decoded.as_ref().map(|decoded| decoded.encoding);
+        let line_ending = decoded
+            .as_ref()
+            .and_then(|decoded| detect_line_ending(&decoded.text));
+        let text_content = decoded.map(|decoded| decoded.text);
 
-        let stored_content = if store_file_content {
+        let stored_content = if content_policy.stores(&relative_path_buf) {
             text_content.clone()
         } else {
             None
@@ -1027,11 +2496,13 @@ fn collect_files_from_walk(
             hash,
             stored_content,
             text_content,
+            text_encoding,
+            line_ending,
         });
     }
 }
 
-fn compile_globs(patterns: &[String]) -> Result<Option<GlobSet>, IngestError> {
+pub(crate) fn compile_globs(patterns: &[String]) -> Result<Option<GlobSet>, IngestError> {
     if patterns.is_empty() {
         return Ok(None);
     }
@@ -1049,16 +2520,10 @@ fn compile_globs(patterns: &[String]) -> Result<Option<GlobSet>, IngestError> {
 }
 
 fn resolve_root(root: &str) -> Result<PathBuf, IngestError> {
-    let candidate = PathBuf::from(root);
-    if candidate.is_absolute() {
-        return Ok(candidate);
-    }
-
-    let cwd = std::env::current_dir().map_err(|source| IngestError::InvalidRoot {
+    resolve_workspace_root(root).map_err(|source| IngestError::InvalidRoot {
         path: root.to_string(),
         source,
-    })?;
-    Ok(cwd.join(candidate))
+    })
 }
 
 fn timestamp_ms() -> i64 {
@@ -1093,6 +2558,21 @@ fn ensure_schema(conn: &Connection) -> Result<(), rusqlite::Error> {
             hits INTEGER DEFAULT 0,
             FOREIGN KEY (path) REFERENCES files(path) ON DELETE CASCADE
         );
+        CREATE TABLE IF NOT EXISTS file_keywords (
+            path TEXT NOT NULL,
+            keyword TEXT NOT NULL,
+            weight REAL NOT NULL,
+            PRIMARY KEY (path, keyword),
+            FOREIGN KEY (path) REFERENCES files(path) ON DELETE CASCADE
+        );
+        CREATE INDEX IF NOT EXISTS file_keywords_keyword_idx ON file_keywords(keyword);
+        CREATE TABLE IF NOT EXISTS chunk_doc_symbols (
+            chunk_id TEXT PRIMARY KEY,
+            path TEXT NOT NULL,
+            symbol TEXT NOT NULL,
+            FOREIGN KEY (chunk_id) REFERENCES file_chunks(id) ON DELETE CASCADE
+        );
+        CREATE INDEX IF NOT EXISTS chunk_doc_symbols_symbol_idx ON chunk_doc_symbols(path, symbol);
         CREATE TABLE IF NOT EXISTS ingestions (
             id TEXT PRIMARY KEY,
             root TEXT NOT NULL,
@@ -1102,6 +2582,15 @@ fn ensure_schema(conn: &Connection) -> Result<(), rusqlite::Error> {
             skipped_count INTEGER NOT NULL,
             deleted_count INTEGER NOT NULL
         );
+        CREATE TABLE IF NOT EXISTS ingestion_skips (
+            ingestion_id TEXT NOT NULL,
+            path TEXT NOT NULL,
+            reason TEXT NOT NULL,
+            size REAL,
+            message TEXT
+        );
+        CREATE INDEX IF NOT EXISTS ingestion_skips_ingestion_idx ON ingestion_skips(ingestion_id);
+        CREATE INDEX IF NOT EXISTS ingestion_skips_path_idx ON ingestion_skips(path);
         CREATE TABLE IF NOT EXISTS meta (
             key TEXT PRIMARY KEY,
             value TEXT NOT NULL,
@@ -1133,23 +2622,39 @@ fn ensure_schema(conn: &Connection) -> Result<(), rusqlite::Error> {
             FOREIGN KEY (target_id) REFERENCES code_graph_nodes(id) ON DELETE CASCADE
         );
         CREATE INDEX IF NOT EXISTS code_graph_nodes_path_idx ON code_graph_nodes(path);
+        CREATE INDEX IF NOT EXISTS code_graph_nodes_name_idx ON code_graph_nodes(name);
         CREATE INDEX IF NOT EXISTS code_graph_edges_source_idx ON code_graph_edges(source_id);
         CREATE INDEX IF NOT EXISTS code_graph_edges_target_idx ON code_graph_edges(target_id);
         "#,
-    )
+    )?;
+    ensure_package_schema(conn)?;
+    ensure_content_schema(conn)?;
+    ensure_text_format_schema(conn)?;
+    ensure_checksum_schema(conn)?;
+    ensure_chunk_label_schema(conn)?;
+    ensure_embedding_schema(conn)?;
+    ensure_embedding_cache_schema(conn)?;
+    ensure_generation_schema(conn)?;
+    ensure_vector_index_schema(conn)?;
+    conn.pragma_update(None, "user_version", SCHEMA_VERSION)
 }
 
 fn load_existing_files(
     conn: &Transaction<'_>,
 ) -> Result<HashMap<String, ExistingFileMetadata>, rusqlite::Error> {
-    let mut stmt = conn.prepare("SELECT path, hash, modified, size FROM files")?;
+    let mut stmt = conn.prepare(&format!(
+        "SELECT path, hash, {} FROM files",
+        text_format_columns(conn)
+    ))?;
     let rows = stmt.query_map([], |row| {
         Ok((
             row.get::<_, String>(0)?,
             ExistingFileMetadata {
                 hash: row.get::<_, String>(1)?,
-                modified: row.get::<_, i64>(2)?,
-                size: row.get::<_, i64>(3)?,
+                text_encoding: row
+                    .get::<_, Option<String>>(2)?
+                    .as_deref()
+                    .and_then(TextEncoding::parse),
             },
         ))
     })?;
@@ -1187,27 +2692,47 @@ fn query_table_count(conn: &Connection, table: &str) -> Result<usize, rusqlite::
 
 fn upsert_file(
     conn: &Transaction<'_>,
-    path: &str,
-    size: i64,
-    modified: i64,
-    hash: String,
+    file: &ScannedFile,
     indexed_at: i64,
-    content: Option<String>,
+    compress: bool,
 ) -> Result<(), rusqlite::Error> {
+    let (content, encoding) = encode_file_content(file.stored_content.clone(), compress);
     conn.execute(
-        "INSERT INTO files (path, size, modified, hash, last_indexed_at, content)
-         VALUES (?1, ?2, ?3, ?4, ?5, ?6)
+        "INSERT INTO files (path, size, modified, hash, last_indexed_at, content, content_encoding, text_encoding, line_ending)
+         VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9)
          ON CONFLICT(path) DO UPDATE SET
             size = excluded.size,
             modified = excluded.modified,
             hash = excluded.hash,
             last_indexed_at = excluded.last_indexed_at,
-            content = excluded.content",
-        params![path, size, modified, hash, indexed_at, content],
+            content = excluded.content,
+            content_encoding = excluded.content_encoding,
+            text_encoding = excluded.text_encoding,
+            line_ending = excluded.line_ending",
+        params![
+            file.path,
+            file.size as i64,
+            file.modified_ms,
+            file.hash,
+            indexed_at,
+            content,
+            encoding,
+            file.text_encoding.map(TextEncoding::as_str),
+            file.line_ending.map(LineEnding::as_str),
+        ],
     )?;
     Ok(())
 }
 
+/// A target names a file or, when a directory was renamed or removed,
+/// everything indexed under it.
+fn is_within_targets(path: &str, targets: &HashSet<String>) -> bool {
+    targets.contains(path)
+        || path
+            .match_indices('/')
+            .any(|(index, _)| targets.contains(&path[..index]))
+}
+
 fn compute_deleted(existing: &HashSet<String>, retained: &HashSet<String>) -> Vec<String> {
     existing
         .iter()
@@ -1250,6 +2775,43 @@ fn insert_ingestion_record(
     Ok(())
 }
 
+/// Stores the skip list under the ingestion id, keeping the most recent
+/// ingestions so diagnostics can say why a path is missing without a rescan.
+fn record_skipped_files(
+    conn: &Transaction<'_>,
+    ingestion_id: &str,
+    skipped: &[SkippedFile],
+) -> Result<(), rusqlite::Error> {
+    conn.execute(
+        "DELETE FROM ingestion_skips WHERE ingestion_id NOT IN (
+             SELECT id FROM ingestions ORDER BY finished_at DESC LIMIT ?1
+         )",
+        params![RETAINED_SKIP_INGESTIONS],
+    )?;
+    let mut stmt = conn.prepare(
+        "INSERT INTO ingestion_skips (ingestion_id, path, reason, size, message)
+         VALUES (?1, ?2, ?3, ?4, ?5)",
+    )?;
+    for file in skipped {
+        stmt.execute(params![
+            ingestion_id,
+            &file.path,
+            &file.reason,
+            file.size,
+            &file.message
+        ])?;
+    }
+    Ok(())
+}
+
+fn count_skips_by_reason(skipped: &[SkippedFile]) -> BTreeMap<String, usize> {
+    let mut counts = BTreeMap::new();
+    for file in skipped {
+        *counts.entry(file.reason.clone()).or_insert(0) += 1;
+    }
+    counts
+}
+
 fn upsert_meta(
     conn: &Transaction<'_>,
     key: &str,
@@ -1267,35 +2829,70 @@ fn upsert_meta(
     Ok(())
 }
 
+fn load_meta_value(conn: &Transaction<'_>, key: &str) -> Result<Option<String>, rusqlite::Error> {
+    let mut stmt = conn.prepare("SELECT value FROM meta WHERE key = ?1")?;
+    let mut rows = stmt.query(params![key])?;
+    match rows.next()? {
+        Some(row) => row.get::<_, String>(0).map(Some),
+        None => Ok(None),
+    }
+}
+
+fn pin_embedding_model(
+    conn: &Transaction<'_>,
+    dimensions: Option<usize>,
+    updated_at: i64,
+) -> Result<(), rusqlite::Error> {
+    let mut stmt = conn.prepare("SELECT DISTINCT embedding_model FROM file_chunks")?;
+    let models: Vec<String> = stmt
+        .query_map([], |row| row.get::<_, String>(0))?
+        .flatten()
+        .collect();
+
+    // only pin when the database holds a single model; mixed databases keep
+    // their original pin so later ingests still get flagged
+    if let [model] = models.as_slice() {
+        upsert_meta(conn, META_EMBEDDING_MODEL, model, updated_at)?;
+        if let Some(dimensions) = dimensions {
+            upsert_meta(
+                conn,
+                META_EMBEDDING_DIMENSIONS,
+                &dimensions.to_string(),
+                updated_at,
+            )?;
+        }
+    }
+    Ok(())
+}
+
 fn get_or_create_embedder(config: &EmbeddingConfig) -> Result<EmbedderHandle, IngestError> {
     let model_name = config.model.trim().to_string();
+    let endpoint = config.endpoint.clone();
+    let cache_key = match &endpoint {
+        Some(endpoint) => format!("{model_name}@{endpoint}"),
+        None => model_name.clone(),
+    };
 
     let entry = {
         let mut cache = EMBEDDER_CACHE.lock().map_err(|error| {
             IngestError::Embedding(format!("failed to access embedder cache: {error}"))
         })?;
         cache
-            .entry(model_name.clone())
+            .entry(cache_key)
             .or_insert_with(|| Arc::new(OnceCell::new()))
             .clone()
     };
 
-    let model_variant = config.model_variant.clone();
     let handle = entry.get_or_try_init(move || {
-        initialize_embedder(model_variant.clone())
+        load_embedder(&model_name, endpoint.as_deref())
             .map(|embedder| Arc::new(Mutex::new(embedder)) as EmbedderHandle)
+            .map_err(IngestError::Embedding)
     })?;
 
     Ok(handle.clone())
 }
 
-fn initialize_embedder(model: EmbeddingModel) -> Result<TextEmbedding, IngestError> {
-    let options = TextInitOptions::new(model).with_show_download_progress(false);
-
-    TextEmbedding::try_new(options).map_err(|error| IngestError::Embedding(error.to_string()))
-}
-
-fn embedding_to_bytes(vector: &[f32]) -> Vec<u8> {
+pub(crate) fn embedding_to_bytes(vector: &[f32]) -> Vec<u8> {
     let mut bytes = Vec::with_capacity(vector.len() * 4);
     for value in vector {
         bytes.extend_from_slice(&value.to_le_bytes());
@@ -1304,24 +2901,28 @@ fn embedding_to_bytes(vector: &[f32]) -> Vec<u8> {
 }
 
 fn get_current_commit_sha(root: &Path) -> Result<String, std::io::Error> {
+    let stdout = git_stdout(root, &["rev-parse", "HEAD"])?;
+    if stdout.is_empty() {
+        Err(std::io::Error::other("git rev-parse returned empty output"))
+    } else {
+        Ok(stdout)
+    }
+}
+
+pub(crate) fn git_stdout(root: &Path, args: &[&str]) -> Result<String, std::io::Error> {
     let output = std::process::Command::new("git")
-        .arg("rev-parse")
-        .arg("HEAD")
+        .args(args)
         .current_dir(root)
         .output()?;
 
     if !output.status.success() {
-        return Err(std::io::Error::other(
-            "git rev-parse returned non-zero status",
-        ));
+        return Err(std::io::Error::other(format!(
+            "git {} returned non-zero status",
+            args.first().copied().unwrap_or_default()
+        )));
     }
 
-    let stdout = String::from_utf8_lossy(&output.stdout).trim().to_string();
-    if stdout.is_empty() {
-        Err(std::io::Error::other("git rev-parse returned empty output"))
-    } else {
-        Ok(stdout)
-    }
+    Ok(String::from_utf8_lossy(&output.stdout).trim().to_string())
 }
 
 fn normalize_path(path: &str) -> String {
@@ -1338,7 +2939,27 @@ pub fn warm_up_embedder(model: Option<String>) -> Result<(), IngestError> {
     get_or_create_embedder(&config).map(|_| ())
 }
 
-fn file_modified_to_ms(metadata: &fs::Metadata) -> i64 {
+/// Embeds `texts` with the cached embedder for `model` (or its alias),
+/// returning the concrete model name alongside the vectors.
+pub(crate) fn embed_texts(
+    model: Option<String>,
+    texts: Vec<String>,
+) -> Result<(String, Vec<Vec<f32>>), IngestError> {
+    let config = resolve_embedding_config(Some(EmbeddingParams {
+        model,
+        ..Default::default()
+    }))?;
+    let embedder = get_or_create_embedder(&config)?;
+    let mut guard = embedder
+        .lock()
+        .map_err(|error| IngestError::Embedding(format!("failed to acquire embedder: {error}")))?;
+    let vectors = guard
+        .embed(texts, config.batch_size)
+        .map_err(IngestError::Embedding)?;
+    Ok((config.model, vectors))
+}
+
+pub(crate) fn file_modified_to_ms(metadata: &fs::Metadata) -> i64 {
     metadata
         .modified()
         .ok()
@@ -1347,14 +2968,228 @@ fn file_modified_to_ms(metadata: &fs::Metadata) -> i64 {
         .unwrap_or(0)
 }
 
-fn is_binary(bytes: &[u8]) -> bool {
-    bytes.contains(&0)
+/// Keeps `cap` of a file's chunks in order: the first quarter and last
+/// eighth of the budget go to the head and tail, the rest to chunks that open
+/// a definition, then to chunks spread evenly over what remains.
+fn select_capped_fragments(
+    fragments: Vec<ChunkFragment>,
+    cap: usize,
+    path: &str,
+) -> Vec<ChunkFragment> {
+    let total = fragments.len();
+    if total <= cap {
+        return fragments;
+    }
+    let head = cap.div_ceil(4);
+    let tail = (cap / 8).min(cap - head);
+    let mut keep = vec![false; total];
+    keep[..head].fill(true);
+    keep[total - tail..].fill(true);
+    let mut remaining = cap - head - tail;
+
+    if let Some(pattern) = pack_for_path(path).and_then(|pack| pack.definition_pattern()) {
+        for (index, fragment) in fragments.iter().enumerate() {
+            if remaining == 0 {
+                break;
+            }
+            if !keep[index] && fragment.content.lines().any(|line| pattern.is_match(line)) {
+                keep[index] = true;
+                remaining -= 1;
+            }
+        }
+    }
+
+    let middle: Vec<usize> = (0..total).filter(|index| !keep[*index]).collect();
+    if remaining > 0 && !middle.is_empty() {
+        let step = middle.len() as f64 / remaining as f64;
+        for slot in 0..remaining {
+            keep[middle[(slot as f64 * step) as usize]] = true;
+        }
+    }
+
+    fragments
+        .into_iter()
+        .zip(keep)
+        .filter_map(|(fragment, kept)| kept.then_some(fragment))
+        .collect()
+}
+
+/// Where structural chunking takes its boundaries from for one file.
+#[derive(Clone, Copy)]
+struct ChunkBoundaries<'a> {
+    pack: &'static dyn LanguagePack,
+    path: &'a str,
+}
+
+fn chunk_boundaries(mode: ChunkingMode, path: &str) -> Option<ChunkBoundaries<'_>> {
+    if mode != ChunkingMode::Structural {
+        return None;
+    }
+    let pack = pack_for_path(path)?;
+    Some(ChunkBoundaries { pack, path })
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum DocStyle {
+    /// `///` and `//!` runs.
+    RustLine,
+    /// `/** ... */` blocks.
+    Block,
+    /// `//` runs directly above a top-level declaration.
+    GoLine,
+    PythonDocstring,
+}
+
+#[derive(Debug)]
+struct DocComment {
+    fragment: ChunkFragment,
+    symbol: Option<String>,
+}
+
+fn doc_style(path: &str) -> Option<DocStyle> {
+    let extension = Path::new(path)
+        .extension()
+        .and_then(|extension| extension.to_str())
+        .unwrap_or("")
+        .to_ascii_lowercase();
+    match extension.as_str() {
+        "rs" => Some(DocStyle::RustLine),
+        "ts" | "tsx" | "js" | "jsx" | "mjs" | "cjs" | "java" | "kt" | "kts" | "cs" | "swift"
+        | "scala" | "php" | "c" | "h" | "cc" | "cpp" | "hpp" => Some(DocStyle::Block),
+        "go" => Some(DocStyle::GoLine),
+        "py" => Some(DocStyle::PythonDocstring),
+        _ => None,
+    }
+}
+
+/// Doc comments as verbatim slices of `text`, each linked to the symbol
+/// declared on the line it documents when one can be recognised.
+fn extract_doc_comments(text: &str, path: &str) -> Vec<DocComment> {
+    let Some(style) = doc_style(path) else {
+        return Vec::new();
+    };
+    let mut lines: Vec<(usize, &str)> = Vec::new();
+    let mut offset = 0usize;
+    for line in text.split_inclusive('\n') {
+        lines.push((offset, line.trim_end_matches(['\n', '\r'])));
+        offset += line.len();
+    }
+    let trimmed = |index: usize| lines.get(index).map_or("", |(_, line)| line.trim_start());
+    // first line after `from` that is not an attribute, decorator, or blank
+    let declaration_after = |from: usize| {
+        (from..lines.len())
+            .map(trimmed)
+            .find(|line| !line.is_empty() && !line.starts_with("#[") && !line.starts_with('@'))
+    };
+    let symbol_on = |line: &str| {
+        DOC_SYMBOL_NAME
+            .captures(line)
+            .map(|captures| captures[1].to_string())
+    };
+
+    let mut docs = Vec::new();
+    let mut index = 0usize;
+    while index < lines.len() {
+        let line = trimmed(index);
+        let span = match style {
+            DocStyle::RustLine
+                if (line.starts_with("///") && !line.starts_with("////"))
+                    || line.starts_with("//!") =>
+            {
+                let prefix = &line[..3];
+                let mut last = index;
+                while trimmed(last + 1).starts_with(prefix) {
+                    last += 1;
+                }
+                let symbol = if prefix == "//!" {
+                    None
+                } else {
+                    declaration_after(last + 1).and_then(symbol_on)
+                };
+                Some((index, last, symbol))
+            }
+            DocStyle::Block if line.starts_with("/**") && !line.starts_with("/**/") => {
+                let mut last = index;
+                while last + 1 < lines.len() && !lines[last].1.contains("*/") {
+                    last += 1;
+                }
+                Some((index, last, declaration_after(last + 1).and_then(symbol_on)))
+            }
+            DocStyle::GoLine if line.starts_with("//") && lines[index].1.starts_with("//") => {
+                let mut last = index;
+                while lines
+                    .get(last + 1)
+                    .is_some_and(|(_, next)| next.starts_with("//"))
+                {
+                    last += 1;
+                }
+                lines
+                    .get(last + 1)
+                    .filter(|(_, next)| GO_DOCUMENTED_DEFINITION.is_match(next))
+                    .map(|(_, next)| (index, last, symbol_on(next)))
+            }
+            DocStyle::PythonDocstring => PYTHON_DOCUMENTED_DEFINITION
+                .captures(lines[index].1)
+                .and_then(|captures| {
+                    let name = captures[1].to_string();
+                    // the body starts after the line that closes the signature
+                    let signature_end = (index..lines.len().min(index + 20))
+                        .find(|candidate| lines[*candidate].1.trim_end().ends_with(':'))?;
+                    let first = (signature_end + 1..lines.len())
+                        .find(|candidate| !trimmed(*candidate).is_empty())?;
+                    let opening = trimmed(first);
+                    let quote = ["\"\"\"", "'''"]
+                        .into_iter()
+                        .find(|quote| opening.starts_with(quote))?;
+                    let mut last = first;
+                    if !opening[3..].contains(quote) {
+                        last += 1;
+                        while last < lines.len() && !lines[last].1.contains(quote) {
+                            last += 1;
+                        }
+                        last = last.min(lines.len() - 1);
+                    }
+                    Some((first, last, Some(name)))
+                }),
+            _ => None,
+        };
+
+        let Some((first, last, symbol)) = span else {
+            index += 1;
+            continue;
+        };
+        let byte_start = lines[first].0 + (lines[first].1.len() - trimmed(first).len());
+        let byte_end = lines[last].0 + lines[last].1.len();
+        let content = &text[byte_start..byte_end];
+        if content.trim().len() >= MIN_DOC_COMMENT_CHARS {
+            docs.push(DocComment {
+                fragment: ChunkFragment {
+                    content: content.to_string(),
+                    byte_start: byte_start as u32,
+                    byte_end: byte_end as u32,
+                    line_start: (first + 1) as u32,
+                    line_end: (last + 1) as u32,
+                },
+                symbol,
+            });
+        }
+        index = last.max(index) + 1;
+    }
+    docs
+}
+
+fn is_definition_prelude(line: &str) -> bool {
+    let line = line.trim_start();
+    ["#[", "@", "///", "//!", "/**", "*"]
+        .iter()
+        .any(|prefix| line.starts_with(prefix))
 }
 
 fn chunk_content(
     content: &str,
     chunk_size_tokens: usize,
     chunk_overlap_tokens: usize,
+    boundaries: Option<ChunkBoundaries<'_>>,
 ) -> Vec<ChunkFragment> {
     let trimmed = content.trim();
     if trimmed.is_empty() {
@@ -1380,6 +3215,34 @@ fn chunk_content(
     let total_chars = current_char_index;
     let total_bytes = trimmed.len();
 
+    // newlines that precede a definition, counting its leading attributes,
+    // decorators, and doc comments as part of it; a parse wins over line
+    // patterns when the pack has one
+    let parsed_lines = boundaries
+        .and_then(|boundaries| boundaries.pack.definition_lines(boundaries.path, trimmed));
+    let definition_breaks: Vec<usize> = match (parsed_lines, boundaries) {
+        (Some(lines), _) => lines
+            .into_iter()
+            .filter_map(|line| line.checked_sub(1))
+            .filter_map(|line| newline_char_indices.get(line).copied())
+            .collect(),
+        (None, Some(boundaries)) => match boundaries.pack.definition_pattern() {
+            Some(pattern) => {
+                let lines: Vec<&str> = trimmed.split('\n').collect();
+                lines
+                    .windows(2)
+                    .zip(newline_char_indices.iter())
+                    .filter(|(pair, _)| {
+                        pattern.is_match(pair[1]) && !is_definition_prelude(pair[0])
+                    })
+                    .map(|(_, newline)| *newline)
+                    .collect()
+            }
+            None => Vec::new(),
+        },
+        (None, None) => Vec::new(),
+    };
+
     let mut fragments: Vec<ChunkFragment> = Vec::new();
     let mut start = 0usize;
 
@@ -1388,7 +3251,9 @@ fn chunk_content(
 
         if end < total_chars {
             let min_break = start + 200;
-            if let Some(break_index) = find_break_index(&newline_char_indices, end, min_break) {
+            if let Some(break_index) = find_break_index(&definition_breaks, end, min_break)
+                .or_else(|| find_break_index(&newline_char_indices, end, min_break))
+            {
                 end = break_index + 1;
             }
         }
@@ -0,0 +1,208 @@
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use once_cell::sync::Lazy;
+use rmcp::schemars::{self, JsonSchema};
+use serde::Serialize;
+use tokio::sync::Notify;
+
+// a full pass yields to targeted ingests at most this many times, so a
+// steady stream of edits cannot starve it
+const MAX_PREEMPTIONS: u32 = 3;
+
+static QUEUE: Lazy<Mutex<QueueState>> = Lazy::new(|| Mutex::new(QueueState::default()));
+static QUEUE_CHANGED: Lazy<Notify> = Lazy::new(Notify::new);
+
+/// Path-scoped ingests run ahead of full passes on the same database.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize, JsonSchema)]
+#[serde(rename_all = "camelCase")]
+pub enum IngestPriority {
+    Background,
+    Interactive,
+}
+
+#[derive(Debug, Clone, Serialize, JsonSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct IngestJobStatus {
+    pub id: u64,
+    pub database_path: String,
+    pub priority: IngestPriority,
+    /// Number of requested paths; 0 for a full pass.
+    pub target_paths: usize,
+    pub running: bool,
+    /// Times a full pass was rolled back to let a targeted ingest through.
+    pub preemptions: u32,
+    /// Unix milliseconds.
+    pub enqueued_at: i64,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub started_at: Option<i64>,
+}
+
+struct QueuedIngest {
+    status: IngestJobStatus,
+    preempt: Arc<AtomicBool>,
+}
+
+#[derive(Default)]
+struct QueueState {
+    next_id: u64,
+    jobs: Vec<QueuedIngest>,
+}
+
+impl QueueState {
+    fn job_mut(&mut self, id: u64) -> Option<&mut QueuedIngest> {
+        self.jobs.iter_mut().find(|job| job.status.id == id)
+    }
+
+    /// Whether `id` is the next job to run against its database: nothing
+    /// else is running there and no waiting job outranks it.
+    fn is_next(&self, id: u64) -> bool {
+        let Some(job) = self.jobs.iter().find(|job| job.status.id == id) else {
+            return false;
+        };
+        let database = &job.status.database_path;
+        let same_database = || {
+            self.jobs
+                .iter()
+                .filter(move |other| &other.status.database_path == database)
+        };
+        if same_database().any(|other| other.status.running) {
+            return false;
+        }
+        same_database()
+            .max_by(|left, right| {
+                left.status
+                    .priority
+                    .cmp(&right.status.priority)
+                    .then_with(|| right.status.id.cmp(&left.status.id))
+            })
+            .is_some_and(|best| best.status.id == id)
+    }
+}
+
+/// A place in the ingest queue; leaving it (on drop) lets the next job for
+/// the same database start.
+pub(crate) struct IngestTicket {
+    id: u64,
+    preempt: Arc<AtomicBool>,
+}
+
+impl IngestTicket {
+    pub(crate) fn enqueue(
+        database_path: String,
+        priority: IngestPriority,
+        target_paths: usize,
+    ) -> Self {
+        let preempt = Arc::new(AtomicBool::new(false));
+        let id = {
+            let mut queue = QUEUE
+                .lock()
+                .unwrap_or_else(|poisoned| poisoned.into_inner());
+            queue.next_id += 1;
+            let id = queue.next_id;
+            if priority == IngestPriority::Interactive {
+                for job in queue.jobs.iter_mut().filter(|job| {
+                    job.status.running
+                        && job.status.database_path == database_path
+                        && job.status.priority == IngestPriority::Background
+                        && job.status.preemptions < MAX_PREEMPTIONS
+                }) {
+                    job.preempt.store(true, Ordering::Relaxed);
+                }
+            }
+            queue.jobs.push(QueuedIngest {
+                status: IngestJobStatus {
+                    id,
+                    database_path,
+                    priority,
+                    target_paths,
+                    running: false,
+                    preemptions: 0,
+                    enqueued_at: timestamp_ms(),
+                    started_at: None,
+                },
+                preempt: preempt.clone(),
+            });
+            id
+        };
+        Self { id, preempt }
+    }
+
+    /// Waits until this job may run and marks it running.
+    pub(crate) async fn wait_turn(&self) {
+        loop {
+            let notified = QUEUE_CHANGED.notified();
+            tokio::pin!(notified);
+            notified.as_mut().enable();
+            {
+                let mut queue = QUEUE
+                    .lock()
+                    .unwrap_or_else(|poisoned| poisoned.into_inner());
+                if queue.is_next(self.id) {
+                    if let Some(job) = queue.job_mut(self.id) {
+                        job.status.running = true;
+                        job.status.started_at = Some(timestamp_ms());
+                    }
+                    return;
+                }
+            }
+            notified.await;
+        }
+    }
+
+    /// Set while a targeted ingest waits on this running full pass.
+    pub(crate) fn preempt_flag(&self) -> Arc<AtomicBool> {
+        self.preempt.clone()
+    }
+
+    /// Returns the job to the queue after it rolled back for a targeted
+    /// ingest; it keeps its place ahead of later full passes.
+    pub(crate) fn requeue_after_preemption(&self) {
+        let mut queue = QUEUE
+            .lock()
+            .unwrap_or_else(|poisoned| poisoned.into_inner());
+        if let Some(job) = queue.job_mut(self.id) {
+            job.status.running = false;
+            job.status.started_at = None;
+            job.status.preemptions += 1;
+            job.preempt.store(false, Ordering::Relaxed);
+        }
+        drop(queue);
+        QUEUE_CHANGED.notify_waiters();
+    }
+}
+
+impl Drop for IngestTicket {
+    fn drop(&mut self) {
+        let mut queue = QUEUE
+            .lock()
+            .unwrap_or_else(|poisoned| poisoned.into_inner());
+        queue.jobs.retain(|job| job.status.id != self.id);
+        drop(queue);
+        QUEUE_CHANGED.notify_waiters();
+    }
+}
+
+/// Running and waiting ingests, running first, then in queue order.
+pub(crate) fn queue_snapshot() -> Vec<IngestJobStatus> {
+    let queue = QUEUE
+        .lock()
+        .unwrap_or_else(|poisoned| poisoned.into_inner());
+    let mut jobs: Vec<IngestJobStatus> = queue.jobs.iter().map(|job| job.status.clone()).collect();
+    jobs.sort_by(|left, right| {
+        right
+            .running
+            .cmp(&left.running)
+            .then_with(|| right.priority.cmp(&left.priority))
+            .then_with(|| left.id.cmp(&right.id))
+    });
+    jobs
+}
+
+fn timestamp_ms() -> i64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|duration| duration.as_millis() as i64)
+        .unwrap_or(0)
+}
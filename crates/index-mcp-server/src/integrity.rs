@@ -0,0 +1,231 @@
+use std::path::PathBuf;
+
+use rmcp::schemars::{self, JsonSchema};
+use rusqlite::{params, Connection};
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use thiserror::Error;
+
+use crate::content_store::{content_encoding_column, StoredContentCache, CHUNK_REFERENCE_ENCODING};
+use crate::data_dir::{database_path, open_read_only};
+use crate::embedding_store::{decode_embedding, embedding_encoding_column};
+use crate::index_status::DEFAULT_DB_FILENAME;
+use crate::roots::resolve_workspace_root;
+use crate::sandbox::{ensure_root_allowed, SandboxError};
+
+const DEFAULT_REPORT_LIMIT: u32 = 50;
+const MAX_REPORT_LIMIT: u32 = 500;
+
+pub(crate) fn ensure_checksum_schema(conn: &Connection) -> Result<(), rusqlite::Error> {
+    if !has_checksum(conn)? {
+        conn.execute_batch("ALTER TABLE file_chunks ADD COLUMN checksum TEXT")?;
+    }
+    Ok(())
+}
+
+/// Column expression for readers that may open databases written before
+/// chunk checksums existed.
+pub(crate) fn checksum_column(conn: &Connection) -> &'static str {
+    match has_checksum(conn) {
+        Ok(true) => "checksum",
+        _ => "NULL",
+    }
+}
+
+fn has_checksum(conn: &Connection) -> Result<bool, rusqlite::Error> {
+    let mut stmt = conn.prepare("PRAGMA table_info(file_chunks)")?;
+    let names = stmt.query_map([], |row| row.get::<_, String>(1))?;
+    for name in names {
+        if name? == "checksum" {
+            return Ok(true);
+        }
+    }
+    Ok(false)
+}
+
+/// Checksum of a chunk's embedding blob and text. It covers the text as
+/// served rather than as stored, so storage conversions keep it valid.
+pub(crate) fn chunk_checksum(embedding: &[u8], content: &str) -> String {
+    let mut hasher = Sha256::new();
+    hasher.update((embedding.len() as u64).to_le_bytes());
+    hasher.update(embedding);
+    hasher.update(content.as_bytes());
+    hex::encode(hasher.finalize())[..16].to_string()
+}
+
+/// Why an embedding blob cannot be scored, if it cannot.
+pub(crate) fn embedding_defect(blob: &[u8], encoding: Option<&str>) -> Option<&'static str> {
+    if blob.is_empty() {
+        return Some("empty_embedding");
+    }
+    let vector = decode_embedding(blob, encoding);
+    if vector.is_empty() {
+        Some("truncated_embedding")
+    } else if vector.iter().any(|value| !value.is_finite()) {
+        Some("non_finite_embedding")
+    } else {
+        None
+    }
+}
+
+#[derive(Debug, Deserialize, JsonSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct VerifyIndexParams {
+    #[serde(default)]
+    pub root: Option<String>,
+    #[serde(default)]
+    pub database_name: Option<String>,
+    /// Corrupt chunks to list (default 50, max 500); all are counted.
+    #[serde(default)]
+    pub limit: Option<u32>,
+}
+
+#[derive(Debug, Clone, Serialize, JsonSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct VerifyIndexResponse {
+    pub database_path: String,
+    pub checked_chunks: u64,
+    /// Chunks written before checksums were recorded; only their
+    /// embeddings are checked.
+    pub unverified_chunks: u64,
+    pub corrupt_chunks: u64,
+    pub corrupt: Vec<CorruptChunk>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub recommendation: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize, JsonSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct CorruptChunk {
+    pub id: String,
+    pub path: String,
+    pub chunk_index: i32,
+    /// `truncated_embedding`, `non_finite_embedding`, `empty_embedding`,
+    /// `missing_content` or `checksum_mismatch`.
+    pub reason: String,
+}
+
+#[derive(Debug, Error)]
+pub enum VerifyIndexError {
+    #[error("failed to resolve workspace root '{path}': {source}")]
+    InvalidRoot {
+        path: String,
+        #[source]
+        source: std::io::Error,
+    },
+    #[error("SQLite error: {0}")]
+    Sqlite(#[from] rusqlite::Error),
+    #[error(transparent)]
+    Sandbox(#[from] SandboxError),
+    #[error("blocking task panicked: {0}")]
+    Join(#[from] tokio::task::JoinError),
+}
+
+pub async fn verify_index(
+    params: VerifyIndexParams,
+) -> Result<VerifyIndexResponse, VerifyIndexError> {
+    tokio::task::spawn_blocking(move || verify(params)).await?
+}
+
+fn verify(params: VerifyIndexParams) -> Result<VerifyIndexResponse, VerifyIndexError> {
+    let root = params.root.unwrap_or_else(|| "./".to_string());
+    let root_path = resolve_root(&root)?;
+    ensure_root_allowed(&root_path)?;
+    let database_path = database_path(
+        &root_path,
+        &params
+            .database_name
+            .unwrap_or_else(|| DEFAULT_DB_FILENAME.to_string()),
+    );
+    let limit = params
+        .limit
+        .unwrap_or(DEFAULT_REPORT_LIMIT)
+        .clamp(1, MAX_REPORT_LIMIT) as usize;
+
+    let mut response = VerifyIndexResponse {
+        database_path: database_path.to_string_lossy().to_string(),
+        checked_chunks: 0,
+        unverified_chunks: 0,
+        corrupt_chunks: 0,
+        corrupt: Vec::new(),
+        recommendation: None,
+    };
+    if !database_path.is_file() {
+        return Ok(response);
+    }
+
+    let conn = open_read_only(&database_path)?;
+    let mut stmt = conn.prepare(&format!(
+        "SELECT id, path, chunk_index, content, embedding, byte_start, byte_end, {}, {}, {} FROM file_chunks ORDER BY path, chunk_index",
+        content_encoding_column(&conn, "file_chunks"),
+        checksum_column(&conn),
+        embedding_encoding_column(&conn)
+    ))?;
+    let mut stored_content = StoredContentCache::new(&conn)?;
+    let mut rows = stmt.query(params![])?;
+    while let Some(row) = rows.next()? {
+        response.checked_chunks += 1;
+        let id: String = row.get(0)?;
+        let path: String = row.get(1)?;
+        let chunk_index: i32 = row.get(2)?;
+        let content: String = row.get(3)?;
+        let embedding: Vec<u8> = row.get(4)?;
+        let byte_start: Option<i64> = row.get(5)?;
+        let byte_end: Option<i64> = row.get(6)?;
+        let encoding: Option<String> = row.get(7)?;
+        let checksum: Option<String> = row.get(8)?;
+        let embedding_encoding: Option<String> = row.get(9)?;
+
+        let reason = match embedding_defect(&embedding, embedding_encoding.as_deref()) {
+            Some(defect) => Some(defect),
+            None => match checksum {
+                None => {
+                    response.unverified_chunks += 1;
+                    None
+                }
+                Some(expected) => {
+                    let by_reference = encoding.as_deref() == Some(CHUNK_REFERENCE_ENCODING);
+                    let content = stored_content.resolve_chunk(
+                        &path,
+                        content,
+                        encoding.as_deref(),
+                        byte_start,
+                        byte_end,
+                    );
+                    if by_reference && content.is_empty() {
+                        Some("missing_content")
+                    } else if chunk_checksum(&embedding, &content) != expected {
+                        Some("checksum_mismatch")
+                    } else {
+                        None
+                    }
+                }
+            },
+        };
+        if let Some(reason) = reason {
+            response.corrupt_chunks += 1;
+            if response.corrupt.len() < limit {
+                response.corrupt.push(CorruptChunk {
+                    id,
+                    path,
+                    chunk_index,
+                    reason: reason.to_string(),
+                });
+            }
+        }
+    }
+
+    if response.corrupt_chunks > 0 {
+        response.recommendation = Some(
+            "Re-ingest the affected paths with ingest_codebase (paths: [...]) to rewrite their chunks; if corruption is widespread, delete the database and run a full ingest.".to_string(),
+        );
+    }
+    Ok(response)
+}
+
+fn resolve_root(root: &str) -> Result<PathBuf, VerifyIndexError> {
+    resolve_workspace_root(root).map_err(|source| VerifyIndexError::InvalidRoot {
+        path: root.to_string(),
+        source,
+    })
+}
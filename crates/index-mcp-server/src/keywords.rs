@@ -0,0 +1,183 @@
+use std::collections::{HashMap, HashSet};
+
+use once_cell::sync::Lazy;
+use regex::Regex;
+use rusqlite::{params, params_from_iter, Connection};
+
+// terms kept per file; enough to route on without storing full term vectors
+const KEYWORDS_PER_FILE: usize = 48;
+const MIN_TERM_CHARS: usize = 3;
+
+static IDENTIFIER: Lazy<Regex> =
+    Lazy::new(|| Regex::new(r"[A-Za-z_][A-Za-z0-9_]*").expect("valid identifier pattern"));
+
+// language keywords and filler that appear in nearly every file
+const STOPWORDS: &[&str] = &[
+    "and", "are", "as", "async", "await", "bool", "break", "case", "catch", "class", "const",
+    "continue", "def", "default", "else", "enum", "export", "false", "fn", "for", "from", "func",
+    "function", "if", "impl", "import", "in", "int", "let", "mut", "new", "none", "not", "null",
+    "pub", "return", "self", "some", "static", "str", "string", "struct", "the", "this", "true",
+    "try", "type", "use", "var", "void", "while", "with", "ok", "err", "into", "that",
+];
+
+/// Lowercased search terms in `text`: each identifier plus its snake_case and
+/// camelCase parts.
+pub(crate) fn terms(text: &str) -> Vec<String> {
+    let mut terms = Vec::new();
+    for identifier in IDENTIFIER.find_iter(text) {
+        let identifier = identifier.as_str();
+        let parts = split_identifier(identifier);
+        if parts.len() > 1 {
+            push_term(&mut terms, identifier.to_lowercase());
+        }
+        for part in parts {
+            push_term(&mut terms, part);
+        }
+    }
+    terms
+}
+
+fn push_term(terms: &mut Vec<String>, term: String) {
+    if term.len() >= MIN_TERM_CHARS
+        && !term.chars().all(|ch| ch.is_ascii_digit() || ch == '_')
+        && !STOPWORDS.contains(&term.as_str())
+    {
+        terms.push(term);
+    }
+}
+
+fn split_identifier(identifier: &str) -> Vec<String> {
+    let mut parts = Vec::new();
+    for word in identifier.split('_').filter(|word| !word.is_empty()) {
+        let chars: Vec<char> = word.chars().collect();
+        let mut start = 0;
+        for index in 1..chars.len() {
+            let boundary = chars[index].is_uppercase()
+                && (chars[index - 1].is_lowercase()
+                    || chars.get(index + 1).is_some_and(|next| next.is_lowercase()));
+            if boundary {
+                parts.push(
+                    chars[start..index]
+                        .iter()
+                        .collect::<String>()
+                        .to_lowercase(),
+                );
+                start = index;
+            }
+        }
+        parts.push(chars[start..].iter().collect::<String>().to_lowercase());
+    }
+    parts
+}
+
+/// The file's most frequent terms with their share of its term count. IDF is
+/// applied at query time across every file in the table, so targeted ingests
+/// never have to rescore untouched files.
+pub(crate) fn top_keywords(text: &str) -> Vec<(String, f64)> {
+    let terms = terms(text);
+    if terms.is_empty() {
+        return Vec::new();
+    }
+    let total = terms.len() as f64;
+    let mut counts: HashMap<String, u32> = HashMap::new();
+    for term in terms {
+        *counts.entry(term).or_insert(0) += 1;
+    }
+    let mut ranked: Vec<(String, u32)> = counts.into_iter().collect();
+    ranked.sort_by(|left, right| right.1.cmp(&left.1).then_with(|| left.0.cmp(&right.0)));
+    ranked.truncate(KEYWORDS_PER_FILE);
+    ranked
+        .into_iter()
+        .map(|(term, count)| (term, f64::from(count) / total))
+        .collect()
+}
+
+pub(crate) fn load_keyword_paths(conn: &Connection) -> rusqlite::Result<HashSet<String>> {
+    let mut stmt = conn.prepare("SELECT DISTINCT path FROM file_keywords")?;
+    let paths = stmt
+        .query_map([], |row| row.get(0))?
+        .collect::<Result<HashSet<String>, _>>()?;
+    Ok(paths)
+}
+
+pub(crate) fn replace_file_keywords(
+    conn: &Connection,
+    entries: &[(String, Vec<(String, f64)>)],
+) -> rusqlite::Result<()> {
+    let mut delete_stmt = conn.prepare("DELETE FROM file_keywords WHERE path = ?1")?;
+    let mut insert_stmt =
+        conn.prepare("INSERT INTO file_keywords (path, keyword, weight) VALUES (?1, ?2, ?3)")?;
+    for (path, keywords) in entries {
+        delete_stmt.execute(params![path])?;
+        for (keyword, weight) in keywords {
+            insert_stmt.execute(params![path, keyword, weight])?;
+        }
+    }
+    Ok(())
+}
+
+/// Up to `limit` paths ranked by the TF-IDF of the query's terms. `None` when
+/// the database has no keywords or nothing matches, so callers fall back to
+/// scoring every chunk.
+pub(crate) fn shortlist_paths(conn: &Connection, query: &str, limit: usize) -> Option<Vec<String>> {
+    let mut query_terms = terms(query);
+    query_terms.sort();
+    query_terms.dedup();
+    if query_terms.is_empty() || limit == 0 {
+        return None;
+    }
+
+    let corpus: f64 = conn
+        .query_row(
+            "SELECT COUNT(DISTINCT path) FROM file_keywords",
+            [],
+            |row| row.get::<_, i64>(0),
+        )
+        .ok()? as f64;
+    if corpus == 0.0 {
+        return None;
+    }
+
+    let placeholders = (1..=query_terms.len())
+        .map(|index| format!("?{index}"))
+        .collect::<Vec<_>>()
+        .join(", ");
+    let mut stmt = conn
+        .prepare(&format!(
+            "SELECT path, keyword, weight FROM file_keywords WHERE keyword IN ({placeholders})"
+        ))
+        .ok()?;
+    let rows: Vec<(String, String, f64)> = stmt
+        .query_map(params_from_iter(query_terms.iter()), |row| {
+            Ok((row.get(0)?, row.get(1)?, row.get(2)?))
+        })
+        .ok()?
+        .flatten()
+        .collect();
+
+    let mut document_frequency: HashMap<&str, f64> = HashMap::new();
+    for (_, keyword, _) in &rows {
+        *document_frequency.entry(keyword.as_str()).or_insert(0.0) += 1.0;
+    }
+    let mut scores: HashMap<&str, f64> = HashMap::new();
+    for (path, keyword, weight) in &rows {
+        let idf = ((corpus + 1.0) / (document_frequency[keyword.as_str()] + 0.5)).ln();
+        *scores.entry(path.as_str()).or_insert(0.0) += weight * idf.max(0.0);
+    }
+
+    let mut ranked: Vec<(&str, f64)> = scores
+        .into_iter()
+        .filter(|(_, score)| *score > 0.0)
+        .collect();
+    if ranked.is_empty() {
+        return None;
+    }
+    ranked.sort_by(|left, right| right.1.total_cmp(&left.1).then_with(|| left.0.cmp(right.0)));
+    ranked.truncate(limit);
+    Some(
+        ranked
+            .into_iter()
+            .map(|(path, _)| path.to_string())
+            .collect(),
+    )
+}
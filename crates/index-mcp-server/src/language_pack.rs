@@ -0,0 +1,240 @@
+use std::path::Path;
+
+use once_cell::sync::Lazy;
+use regex::Regex;
+use rmcp::schemars::{self, JsonSchema};
+use serde::Serialize;
+
+use crate::graph::{extract_script_graph, GraphExtraction};
+use crate::search::Classification;
+
+// lines that open a top-level definition; structural chunking prefers to
+// break right before them
+static RUST_DEFINITION: Lazy<Regex> = Lazy::new(|| {
+    Regex::new(r"^\s{0,4}(pub(\([^)]*\))?\s+)?((async|const|unsafe|extern)\s+)*(fn|struct|enum|trait|impl|mod)\b|^\s{0,4}(macro_rules!|#\[|///)")
+        .expect("valid Rust definition pattern")
+});
+static PYTHON_DEFINITION: Lazy<Regex> = Lazy::new(|| {
+    Regex::new(r"^\s{0,4}(async\s+def|def|class)\s|^\s{0,4}@")
+        .expect("valid Python definition pattern")
+});
+static SCRIPT_DEFINITION: Lazy<Regex> = Lazy::new(|| {
+    Regex::new(r"^\s{0,2}(export\s+)?(default\s+)?(declare\s+)?(abstract\s+)?(async\s+)?(function\*?|class|interface|enum|type\s+\w+\s*=|(const|let)\s+\w+\s*=\s*(async\s*)?(\(|function))|^\s{0,2}(/\*\*|@)")
+        .expect("valid JavaScript definition pattern")
+});
+static GO_DEFINITION: Lazy<Regex> =
+    Lazy::new(|| Regex::new(r"^(func|type)\s").expect("valid Go definition pattern"));
+static BRACE_LANGUAGE_DEFINITION: Lazy<Regex> = Lazy::new(|| {
+    Regex::new(r"^\s{0,4}((public|private|protected|internal|static|final|abstract|sealed|override|open|data)\s+)*(class|interface|enum|struct|record|fun|func|object)\s")
+        .expect("valid definition pattern")
+});
+static RUBY_DEFINITION: Lazy<Regex> = Lazy::new(|| {
+    Regex::new(r"^\s{0,2}(def|class|module)\s").expect("valid Ruby definition pattern")
+});
+
+/// Per-language extraction hooks. Every hook is optional; a pack that
+/// declines one leaves ingest and search on their generic behavior.
+///
+/// Packs are compiled in: add an implementation and list it in
+/// `REGISTRY`, gated behind a cargo feature if it pulls in a parser crate.
+pub(crate) trait LanguagePack: Send + Sync {
+    fn name(&self) -> &'static str;
+
+    /// Lowercase file extensions without the dot.
+    fn extensions(&self) -> &'static [&'static str];
+
+    /// Lines that open a top-level definition, used as structural chunk
+    /// boundaries.
+    fn definition_pattern(&self) -> Option<&'static Regex> {
+        None
+    }
+
+    /// Zero-based lines that open a definition, from a real parse. Takes
+    /// precedence over `definition_pattern`; `None` falls back to it.
+    fn definition_lines(&self, _relative_path: &str, _source: &str) -> Option<Vec<usize>> {
+        None
+    }
+
+    fn provides_syntax(&self) -> bool {
+        false
+    }
+
+    fn provides_graph(&self) -> bool {
+        false
+    }
+
+    /// Graph nodes and edges for one file. Only called when
+    /// `provides_graph` is true.
+    fn extract_graph(&self, _relative_path: &str, _source: &str) -> Option<GraphExtraction> {
+        None
+    }
+
+    /// Overrides the generic snippet classifier; `None` defers to it.
+    fn classify(&self, _snippet: &str) -> Option<Classification> {
+        None
+    }
+}
+
+#[derive(Debug, Clone, Serialize, JsonSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct LanguagePackInfo {
+    pub name: String,
+    pub extensions: Vec<String>,
+    pub chunk_boundaries: bool,
+    /// Chunk boundaries come from a tree-sitter parse rather than line
+    /// patterns.
+    pub syntax_chunking: bool,
+    pub graph: bool,
+}
+
+struct BuiltinPack {
+    name: &'static str,
+    extensions: &'static [&'static str],
+    definition: &'static Lazy<Regex>,
+    syntax: Option<fn(&str, &str) -> Option<Vec<usize>>>,
+    graph: Option<fn(&str, &str) -> Option<GraphExtraction>>,
+}
+
+impl LanguagePack for BuiltinPack {
+    fn name(&self) -> &'static str {
+        self.name
+    }
+
+    fn extensions(&self) -> &'static [&'static str] {
+        self.extensions
+    }
+
+    fn definition_pattern(&self) -> Option<&'static Regex> {
+        Some(Lazy::force(self.definition))
+    }
+
+    fn definition_lines(&self, relative_path: &str, source: &str) -> Option<Vec<usize>> {
+        self.syntax.and_then(|parse| parse(relative_path, source))
+    }
+
+    fn provides_syntax(&self) -> bool {
+        self.syntax.is_some()
+    }
+
+    fn provides_graph(&self) -> bool {
+        self.graph.is_some()
+    }
+
+    fn extract_graph(&self, relative_path: &str, source: &str) -> Option<GraphExtraction> {
+        self.graph
+            .and_then(|extract| extract(relative_path, source))
+    }
+}
+
+#[derive(Clone, Copy)]
+enum SyntaxLanguage {
+    Rust,
+    Script,
+    Python,
+    Go,
+}
+
+/// Tree-sitter boundaries for the language, when built with the
+/// `tree-sitter` feature.
+#[cfg(feature = "tree-sitter")]
+fn syntax_parser(language: SyntaxLanguage) -> Option<fn(&str, &str) -> Option<Vec<usize>>> {
+    use crate::syntax_chunks;
+    Some(match language {
+        SyntaxLanguage::Rust => syntax_chunks::rust_definition_lines,
+        SyntaxLanguage::Script => syntax_chunks::script_definition_lines,
+        SyntaxLanguage::Python => syntax_chunks::python_definition_lines,
+        SyntaxLanguage::Go => syntax_chunks::go_definition_lines,
+    })
+}
+
+#[cfg(not(feature = "tree-sitter"))]
+fn syntax_parser(_language: SyntaxLanguage) -> Option<fn(&str, &str) -> Option<Vec<usize>>> {
+    None
+}
+
+/// The Rust call graph is read off the same tree-sitter parse.
+#[cfg(feature = "tree-sitter")]
+fn rust_graph() -> Option<fn(&str, &str) -> Option<GraphExtraction>> {
+    Some(crate::rust_graph::extract_rust_graph)
+}
+
+#[cfg(not(feature = "tree-sitter"))]
+fn rust_graph() -> Option<fn(&str, &str) -> Option<GraphExtraction>> {
+    None
+}
+
+static REGISTRY: Lazy<Vec<Box<dyn LanguagePack>>> = Lazy::new(|| {
+    vec![
+        Box::new(BuiltinPack {
+            name: "typescript",
+            extensions: &["ts", "tsx", "js", "jsx", "mjs", "cjs"],
+            definition: &SCRIPT_DEFINITION,
+            syntax: syntax_parser(SyntaxLanguage::Script),
+            graph: Some(extract_script_graph),
+        }),
+        Box::new(BuiltinPack {
+            name: "rust",
+            extensions: &["rs"],
+            definition: &RUST_DEFINITION,
+            syntax: syntax_parser(SyntaxLanguage::Rust),
+            graph: rust_graph(),
+        }),
+        Box::new(BuiltinPack {
+            name: "python",
+            extensions: &["py"],
+            definition: &PYTHON_DEFINITION,
+            syntax: syntax_parser(SyntaxLanguage::Python),
+            graph: None,
+        }),
+        Box::new(BuiltinPack {
+            name: "go",
+            extensions: &["go"],
+            definition: &GO_DEFINITION,
+            syntax: syntax_parser(SyntaxLanguage::Go),
+            graph: None,
+        }),
+        Box::new(BuiltinPack {
+            name: "jvm-dotnet-swift",
+            extensions: &["java", "kt", "kts", "cs", "swift", "scala"],
+            definition: &BRACE_LANGUAGE_DEFINITION,
+            syntax: None,
+            graph: None,
+        }),
+        Box::new(BuiltinPack {
+            name: "ruby",
+            extensions: &["rb"],
+            definition: &RUBY_DEFINITION,
+            syntax: None,
+            graph: None,
+        }),
+    ]
+});
+
+/// The first registered pack claiming the path's extension.
+pub(crate) fn pack_for_path(path: &str) -> Option<&'static dyn LanguagePack> {
+    let extension = Path::new(path)
+        .extension()
+        .and_then(|extension| extension.to_str())?
+        .to_ascii_lowercase();
+    REGISTRY
+        .iter()
+        .find(|pack| pack.extensions().contains(&extension.as_str()))
+        .map(|pack| pack.as_ref())
+}
+
+pub(crate) fn registered_packs() -> Vec<LanguagePackInfo> {
+    REGISTRY
+        .iter()
+        .map(|pack| LanguagePackInfo {
+            name: pack.name().to_string(),
+            extensions: pack
+                .extensions()
+                .iter()
+                .map(|extension| extension.to_string())
+                .collect(),
+            chunk_boundaries: pack.definition_pattern().is_some(),
+            syntax_chunking: pack.provides_syntax(),
+            graph: pack.provides_graph(),
+        })
+        .collect()
+}
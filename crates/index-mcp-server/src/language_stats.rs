@@ -0,0 +1,303 @@
+use std::collections::{BTreeMap, HashMap};
+use std::path::{Path, PathBuf};
+
+use rmcp::schemars::{self, JsonSchema};
+use rusqlite::Connection;
+use serde::{Deserialize, Serialize};
+use thiserror::Error;
+
+use crate::data_dir::{database_path, open_read_only};
+use crate::index_status::DEFAULT_DB_FILENAME;
+use crate::roots::resolve_workspace_root;
+use crate::sandbox::{ensure_root_allowed, SandboxError};
+use crate::search::detect_language;
+
+const DEFAULT_DIRECTORY_DEPTH: usize = 1;
+const MAX_DIRECTORY_DEPTH: usize = 4;
+const DEFAULT_DIRECTORY_LIMIT: usize = 20;
+const MAX_DIRECTORY_LIMIT: usize = 200;
+// languages listed per directory; the rest only count toward its totals
+const MAX_DIRECTORY_LANGUAGES: usize = 5;
+const OTHER_LANGUAGE: &str = "Other";
+
+#[derive(Debug, Deserialize, JsonSchema, Default)]
+#[serde(rename_all = "camelCase")]
+pub struct LanguageStatsParams {
+    #[serde(default)]
+    pub root: Option<String>,
+    #[serde(default)]
+    pub database_name: Option<String>,
+    /// Path segments that make up a directory row (default 1, max 4).
+    #[serde(default)]
+    pub directory_depth: Option<u32>,
+    /// Maximum directory rows, largest first (default 20, max 200).
+    #[serde(default)]
+    pub directory_limit: Option<u32>,
+}
+
+#[derive(Debug, Clone, Default, Serialize, JsonSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct LanguageStat {
+    pub language: String,
+    pub files: u64,
+    /// Lines covered by indexed chunks; files without chunks count as 0.
+    pub lines: u64,
+    pub chunks: u64,
+    pub bytes: u64,
+    /// Share of indexed bytes, in percent.
+    pub percentage: f64,
+}
+
+#[derive(Debug, Clone, Serialize, JsonSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct DirectoryLanguageStats {
+    pub directory: String,
+    pub files: u64,
+    pub lines: u64,
+    pub chunks: u64,
+    pub bytes: u64,
+    /// Largest languages by bytes; percentages are of this directory.
+    pub languages: Vec<LanguageStat>,
+}
+
+#[derive(Debug, Clone, Serialize, JsonSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct LanguageStatsResponse {
+    pub database_path: String,
+    pub total_files: u64,
+    pub total_lines: u64,
+    pub total_chunks: u64,
+    pub total_bytes: u64,
+    /// Sorted by bytes, largest first.
+    pub languages: Vec<LanguageStat>,
+    pub directories: Vec<DirectoryLanguageStats>,
+    /// Directory rows dropped past `directoryLimit`.
+    pub omitted_directories: usize,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub note: Option<String>,
+}
+
+#[derive(Debug, Error)]
+pub enum LanguageStatsError {
+    #[error("failed to resolve workspace root '{path}': {source}")]
+    InvalidRoot {
+        path: String,
+        #[source]
+        source: std::io::Error,
+    },
+    #[error("SQLite error: {0}")]
+    Sqlite(#[from] rusqlite::Error),
+    #[error(transparent)]
+    Sandbox(#[from] SandboxError),
+    #[error("blocking task panicked: {0}")]
+    Join(#[from] tokio::task::JoinError),
+}
+
+struct FileStats {
+    path: String,
+    language: String,
+    bytes: u64,
+    lines: u64,
+    chunks: u64,
+}
+
+#[derive(Default)]
+struct Totals {
+    files: u64,
+    lines: u64,
+    chunks: u64,
+    bytes: u64,
+    languages: HashMap<String, LanguageStat>,
+}
+
+impl Totals {
+    fn add(&mut self, file: &FileStats) {
+        self.files += 1;
+        self.lines += file.lines;
+        self.chunks += file.chunks;
+        self.bytes += file.bytes;
+        let entry = self
+            .languages
+            .entry(file.language.clone())
+            .or_insert_with(|| LanguageStat {
+                language: file.language.clone(),
+                ..LanguageStat::default()
+            });
+        entry.files += 1;
+        entry.lines += file.lines;
+        entry.chunks += file.chunks;
+        entry.bytes += file.bytes;
+    }
+
+    fn ranked_languages(&self) -> Vec<LanguageStat> {
+        let mut languages: Vec<LanguageStat> = self.languages.values().cloned().collect();
+        for language in &mut languages {
+            language.percentage = percentage(language.bytes, self.bytes);
+        }
+        languages.sort_by(|left, right| {
+            right
+                .bytes
+                .cmp(&left.bytes)
+                .then_with(|| left.language.cmp(&right.language))
+        });
+        languages
+    }
+}
+
+pub async fn language_stats(
+    params: LanguageStatsParams,
+) -> Result<LanguageStatsResponse, LanguageStatsError> {
+    tokio::task::spawn_blocking(move || compute_language_stats(params)).await?
+}
+
+fn compute_language_stats(
+    params: LanguageStatsParams,
+) -> Result<LanguageStatsResponse, LanguageStatsError> {
+    let root = params.root.unwrap_or_else(|| "./".to_string());
+    let root_path = resolve_root(&root)?;
+    ensure_root_allowed(&root_path)?;
+    let database_path = database_path(
+        &root_path,
+        &params
+            .database_name
+            .unwrap_or_else(|| DEFAULT_DB_FILENAME.to_string()),
+    );
+    let depth = params
+        .directory_depth
+        .map_or(DEFAULT_DIRECTORY_DEPTH, |value| value as usize)
+        .clamp(1, MAX_DIRECTORY_DEPTH);
+    let limit = params
+        .directory_limit
+        .map_or(DEFAULT_DIRECTORY_LIMIT, |value| value as usize)
+        .clamp(1, MAX_DIRECTORY_LIMIT);
+
+    let mut response = LanguageStatsResponse {
+        database_path: database_path.to_string_lossy().to_string(),
+        total_files: 0,
+        total_lines: 0,
+        total_chunks: 0,
+        total_bytes: 0,
+        languages: Vec::new(),
+        directories: Vec::new(),
+        omitted_directories: 0,
+        note: None,
+    };
+    if !database_path.is_file() {
+        response.note = Some("No index yet; run ingest_codebase first.".to_string());
+        return Ok(response);
+    }
+
+    let conn = open_read_only(&database_path)?;
+    let files = load_file_stats(&conn)?;
+
+    let mut totals = Totals::default();
+    let mut directories: BTreeMap<String, Totals> = BTreeMap::new();
+    for file in &files {
+        totals.add(file);
+        directories
+            .entry(directory_of(&file.path, depth))
+            .or_default()
+            .add(file);
+    }
+
+    response.total_files = totals.files;
+    response.total_lines = totals.lines;
+    response.total_chunks = totals.chunks;
+    response.total_bytes = totals.bytes;
+    response.languages = totals.ranked_languages();
+
+    let mut rows: Vec<DirectoryLanguageStats> = directories
+        .into_iter()
+        .map(|(directory, totals)| {
+            let mut languages = totals.ranked_languages();
+            languages.truncate(MAX_DIRECTORY_LANGUAGES);
+            DirectoryLanguageStats {
+                directory,
+                files: totals.files,
+                lines: totals.lines,
+                chunks: totals.chunks,
+                bytes: totals.bytes,
+                languages,
+            }
+        })
+        .collect();
+    rows.sort_by(|left, right| {
+        right
+            .bytes
+            .cmp(&left.bytes)
+            .then_with(|| left.directory.cmp(&right.directory))
+    });
+    response.omitted_directories = rows.len().saturating_sub(limit);
+    rows.truncate(limit);
+    response.directories = rows;
+    Ok(response)
+}
+
+/// Per-file counts from stored metadata only; lines come from the last
+/// chunk's `line_end` rather than re-reading content.
+fn load_file_stats(conn: &Connection) -> Result<Vec<FileStats>, LanguageStatsError> {
+    let mut stmt = conn.prepare(
+        "SELECT f.path, f.size, COALESCE(MAX(c.line_end), 0), COUNT(c.id)
+         FROM files f
+         LEFT JOIN file_chunks c ON c.path = f.path AND c.chunk_index >= 0
+         GROUP BY f.path",
+    )?;
+    let rows = stmt.query_map([], |row| {
+        Ok((
+            row.get::<_, String>(0)?,
+            row.get::<_, i64>(1)?,
+            row.get::<_, i64>(2)?,
+            row.get::<_, i64>(3)?,
+        ))
+    })?;
+    let mut files = Vec::new();
+    for row in rows {
+        let (path, size, lines, chunks) = row?;
+        files.push(FileStats {
+            language: language_label(&path),
+            bytes: size.max(0) as u64,
+            lines: lines.max(0) as u64,
+            chunks: chunks.max(0) as u64,
+            path,
+        });
+    }
+    Ok(files)
+}
+
+fn language_label(path: &str) -> String {
+    detect_language(path).unwrap_or_else(|| {
+        Path::new(path)
+            .extension()
+            .and_then(|ext| ext.to_str())
+            .map_or_else(
+                || OTHER_LANGUAGE.to_string(),
+                |ext| format!(".{}", ext.to_lowercase()),
+            )
+    })
+}
+
+/// The first `depth` segments of the file's directory; root-level files
+/// group under `.`.
+fn directory_of(path: &str, depth: usize) -> String {
+    let segments: Vec<&str> = path.split('/').collect();
+    let directory_segments = segments.len().saturating_sub(1).min(depth);
+    if directory_segments == 0 {
+        ".".to_string()
+    } else {
+        segments[..directory_segments].join("/")
+    }
+}
+
+fn percentage(part: u64, total: u64) -> f64 {
+    if total == 0 {
+        return 0.0;
+    }
+    (part as f64 * 1000.0 / total as f64).round() / 10.0
+}
+
+fn resolve_root(root: &str) -> Result<PathBuf, LanguageStatsError> {
+    resolve_workspace_root(root).map_err(|source| LanguageStatsError::InvalidRoot {
+        path: root.to_string(),
+        source,
+    })
+}
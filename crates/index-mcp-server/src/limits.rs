@@ -0,0 +1,72 @@
+use rmcp::schemars::{self, JsonSchema};
+use serde::{Deserialize, Serialize};
+
+/// A numeric parameter that was clamped instead of being honoured as given.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, JsonSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct ParameterAdjustment {
+    pub parameter: String,
+    pub requested: f64,
+    pub effective: f64,
+    pub reason: AdjustmentReason,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, JsonSchema)]
+#[serde(rename_all = "camelCase")]
+pub enum AdjustmentReason {
+    AboveMaximum,
+    BelowMinimum,
+}
+
+/// Clamps `requested` into `min..=max`, recording an adjustment when the
+/// value changes. Absent values take `default` silently.
+pub(crate) fn clamp_parameter(
+    parameter: &str,
+    requested: Option<u32>,
+    default: u32,
+    min: u32,
+    max: u32,
+    adjustments: &mut Vec<ParameterAdjustment>,
+) -> u32 {
+    let Some(value) = requested else {
+        return default;
+    };
+    let effective = value.clamp(min, max);
+    if effective != value {
+        adjustments.push(ParameterAdjustment {
+            parameter: parameter.to_string(),
+            requested: f64::from(value),
+            effective: f64::from(effective),
+            reason: if value > max {
+                AdjustmentReason::AboveMaximum
+            } else {
+                AdjustmentReason::BelowMinimum
+            },
+        });
+    }
+    effective
+}
+
+/// One-line summary such as `limit 100000 -> 50 (max)`, for tool summaries
+/// and warnings.
+pub(crate) fn describe_adjustments(adjustments: &[ParameterAdjustment]) -> Option<String> {
+    if adjustments.is_empty() {
+        return None;
+    }
+    let parts: Vec<String> = adjustments
+        .iter()
+        .map(|adjustment| {
+            format!(
+                "{} {} -> {} ({})",
+                adjustment.parameter,
+                adjustment.requested,
+                adjustment.effective,
+                match adjustment.reason {
+                    AdjustmentReason::AboveMaximum => "max",
+                    AdjustmentReason::BelowMinimum => "min",
+                }
+            )
+        })
+        .collect();
+    Some(format!("Parameters adjusted: {}.", parts.join(", ")))
+}
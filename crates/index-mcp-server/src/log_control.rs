@@ -0,0 +1,155 @@
+use std::sync::Mutex;
+
+use once_cell::sync::OnceCell;
+use rmcp::schemars::{self, JsonSchema};
+use serde::{Deserialize, Serialize};
+use thiserror::Error;
+use tracing_subscriber::{filter::LevelFilter, reload, EnvFilter, Registry};
+
+/// Directives appended to the startup filter unless it already mentions the
+/// target, keeping rmcp and model downloads quiet by default.
+const DEFAULT_DIRECTIVES: [(&str, &str); 3] = [
+    ("rmcp::service", "rmcp::service=info"),
+    ("rmcp::handler::server", "rmcp::handler::server=info"),
+    ("hf_hub", "hf_hub=warn"),
+];
+
+static FILTER_CONTROL: OnceCell<FilterControl> = OnceCell::new();
+
+struct FilterControl {
+    handle: reload::Handle<EnvFilter, Registry>,
+    initial: String,
+    current: Mutex<String>,
+}
+
+#[derive(Debug, Deserialize, JsonSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct SetLogLevelParams {
+    /// Comma-separated EnvFilter directives such as `rmcp::service=debug`.
+    /// Directives for a target already in the filter replace it.
+    #[serde(default)]
+    pub directives: Option<String>,
+    /// Restore the filter the server started with before applying directives.
+    #[serde(default)]
+    pub reset: Option<bool>,
+}
+
+#[derive(Debug, Clone, Serialize, JsonSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct SetLogLevelResponse {
+    pub previous_filter: String,
+    pub active_filter: String,
+    pub startup_filter: String,
+    pub changed: bool,
+}
+
+#[derive(Debug, Error)]
+pub enum LogControlError {
+    #[error("runtime log level control is not available in this process")]
+    Unavailable,
+    #[error("invalid log directive '{directives}': {message}")]
+    InvalidDirective { directives: String, message: String },
+    #[error("failed to reload log filter: {0}")]
+    Reload(#[from] reload::Error),
+}
+
+pub(crate) fn with_default_directives(filter: &str) -> String {
+    let lower = filter.to_ascii_lowercase();
+    let mut directives: Vec<&str> = split_directives(filter).collect();
+    for (needle, directive) in DEFAULT_DIRECTIVES {
+        if !lower.contains(needle) {
+            directives.push(directive);
+        }
+    }
+    directives.join(",")
+}
+
+pub(crate) fn parse_filter_lossy(filter: &str) -> EnvFilter {
+    EnvFilter::builder()
+        .with_default_directive(LevelFilter::INFO.into())
+        .parse_lossy(filter)
+}
+
+/// Registers the reload handle created alongside the global subscriber.
+pub(crate) fn install(handle: reload::Handle<EnvFilter, Registry>, filter: String) {
+    let _ = FILTER_CONTROL.set(FilterControl {
+        handle,
+        initial: filter.clone(),
+        current: Mutex::new(filter),
+    });
+}
+
+pub fn set_log_level(params: SetLogLevelParams) -> Result<SetLogLevelResponse, LogControlError> {
+    let control = FILTER_CONTROL.get().ok_or(LogControlError::Unavailable)?;
+    let mut current = control
+        .current
+        .lock()
+        .unwrap_or_else(|poisoned| poisoned.into_inner());
+    let previous = current.clone();
+
+    let base = if params.reset.unwrap_or(false) {
+        control.initial.clone()
+    } else {
+        previous.clone()
+    };
+    let next = match params
+        .directives
+        .as_deref()
+        .map(str::trim)
+        .filter(|value| !value.is_empty())
+    {
+        Some(updates) => merge_directives(&base, updates),
+        None => base,
+    };
+
+    if next != previous {
+        let filter = EnvFilter::builder()
+            .with_default_directive(LevelFilter::INFO.into())
+            .parse(&next)
+            .map_err(|error| LogControlError::InvalidDirective {
+                directives: next.clone(),
+                message: error.to_string(),
+            })?;
+        control.handle.reload(filter)?;
+        tracing::info!(previous = %previous, active = %next, "Log filter updated");
+        *current = next.clone();
+    }
+
+    Ok(SetLogLevelResponse {
+        changed: next != previous,
+        previous_filter: previous,
+        active_filter: next,
+        startup_filter: control.initial.clone(),
+    })
+}
+
+/// Appends `updates` to `current`, dropping earlier directives for the same
+/// target so the new level wins regardless of EnvFilter ordering.
+pub(crate) fn merge_directives(current: &str, updates: &str) -> String {
+    let updates: Vec<&str> = split_directives(updates).collect();
+    let replaced: Vec<&str> = updates
+        .iter()
+        .map(|value| directive_target(value))
+        .collect();
+    split_directives(current)
+        .filter(|directive| !replaced.contains(&directive_target(directive)))
+        .chain(updates.iter().copied())
+        .collect::<Vec<_>>()
+        .join(",")
+}
+
+fn split_directives(filter: &str) -> impl Iterator<Item = &str> {
+    filter
+        .split(',')
+        .map(str::trim)
+        .filter(|directive| !directive.is_empty())
+}
+
+// a bare level such as `debug` sets the default and has an empty target
+fn directive_target(directive: &str) -> &str {
+    match directive.rsplit_once('=') {
+        Some((target, _)) => target.trim(),
+        None if directive.parse::<LevelFilter>().is_ok() => "",
+        None => directive,
+    }
+}
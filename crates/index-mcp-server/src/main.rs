@@ -1,12 +1,60 @@
+mod audit;
 mod bundle;
+mod chunk_labels;
+mod chunks;
+mod compression;
+mod config_check;
+mod content_store;
+mod coverage;
+mod data_dir;
+mod db_lock;
+mod diff_search;
+mod embedder;
+mod embedding_cache;
+mod embedding_store;
+mod explain;
+mod filter_presets;
+#[cfg(test)]
+mod fixtures;
+mod generations;
 mod git_timeline;
 mod graph;
+mod graph_neighbors;
+mod health;
+mod hit_counter;
+mod ignore_rules;
 mod index_status;
 mod ingest;
+mod ingest_queue;
+mod integrity;
+mod keywords;
+mod language_pack;
+mod language_stats;
+mod limits;
+mod log_control;
+mod model_registry;
+mod normalization;
+mod path_boosts;
+mod quota;
+mod recommendations;
+mod regex_search;
 mod remote_proxy;
+mod roots;
+#[cfg(feature = "tree-sitter")]
+mod rust_graph;
+mod sandbox;
 mod search;
+mod self_check;
 mod service;
+mod skipped;
+mod suggest_excludes;
+#[cfg(feature = "tree-sitter")]
+mod syntax_chunks;
+mod text_format;
+mod timeline_query;
+mod vector_index;
 mod watcher;
+mod workspace;
 
 use anyhow::Result;
 use clap::Parser;
@@ -19,12 +67,13 @@ use std::{
     time::{Duration, Instant},
 };
 use tracing_appender::non_blocking::{NonBlocking, WorkerGuard};
-use tracing_subscriber::{
-    filter::{Directive, LevelFilter},
-    fmt, EnvFilter,
-};
+use tracing_subscriber::{fmt, layer::SubscriberExt, reload, util::SubscriberInitExt};
 
+use crate::config_check::{effective_log_level, validate_config, FlagSettings, Severity};
+use crate::health::{record_watcher_state, ComponentState};
+use crate::hit_counter::flush_pending_hits;
 use crate::index_status::DEFAULT_DB_FILENAME;
+use crate::self_check::{run_self_check, CheckStatus, SelfCheckParams};
 use crate::watcher::{start_ingest_watcher, WatcherOptions};
 
 /// Command-line arguments for the Rust MCP server.
@@ -39,9 +88,9 @@ struct Cli {
     #[arg(long)]
     cwd: Option<String>,
 
-    /// Log level filter (e.g. info, debug, trace).
-    #[arg(long, default_value = "info")]
-    log_level: String,
+    /// Log level filter (e.g. info, debug, trace). Defaults to info.
+    #[arg(long)]
+    log_level: Option<String>,
 
     /// Enable file watcher mode.
     #[arg(long)]
@@ -66,6 +115,31 @@ struct Cli {
     /// Database name to use for watcher ingests.
     #[arg(long = "watch-database")]
     watch_database: Option<String>,
+
+    /// Start even when configuration checks report errors.
+    #[arg(long = "lenient-config")]
+    lenient_config: bool,
+}
+
+impl Cli {
+    fn flag_settings(&self) -> FlagSettings<'_> {
+        let watch_flags = [
+            ("--watch-root", self.watch_root.is_some()),
+            ("--watch-debounce", self.watch_debounce.is_some()),
+            ("--watch-no-initial", self.watch_no_initial),
+            ("--watch-quiet", self.watch_quiet),
+            ("--watch-database", self.watch_database.is_some()),
+        ]
+        .into_iter()
+        .filter_map(|(flag, given)| given.then_some(flag))
+        .collect();
+        FlagSettings {
+            log_level: self.log_level.as_deref(),
+            watch: self.watch,
+            watch_flags,
+            watch_debounce: self.watch_debounce,
+        }
+    }
 }
 
 fn parse_bool(value: &str) -> Option<bool> {
@@ -99,6 +173,8 @@ fn create_file_writer(path: &str) -> Result<(NonBlocking, WorkerGuard)> {
     Ok((writer, guard))
 }
 
+const HIT_FLUSH_INTERVAL: Duration = Duration::from_secs(5);
+
 struct LogWriters {
     console_enabled: bool,
     file_writer: Option<NonBlocking>,
@@ -159,51 +235,83 @@ impl Write for CombinedWriter {
     }
 }
 
+/// Logs the startup self-check; failures also go to stderr so they show up
+/// in client logs that drop tracing output.
+async fn report_self_check() {
+    let report =
+        match tokio::task::spawn_blocking(|| run_self_check(SelfCheckParams::default())).await {
+            Ok(Ok(report)) => report,
+            Ok(Err(error)) => {
+                tracing::debug!(?error, "Startup self-check skipped");
+                return;
+            }
+            Err(error) => {
+                tracing::warn!(?error, "Startup self-check task failed");
+                return;
+            }
+        };
+    for check in report.checks {
+        let remediation = check.remediation.as_deref().unwrap_or("");
+        match check.status {
+            CheckStatus::Fail => {
+                tracing::error!(check = %check.name, detail = %check.detail, remediation, "Self-check failed");
+                eprintln!(
+                    "[index-mcp] self-check {}: {}. {remediation}",
+                    check.name, check.detail
+                );
+            }
+            CheckStatus::Warn => {
+                tracing::warn!(check = %check.name, detail = %check.detail, remediation, "Self-check warning");
+            }
+            CheckStatus::Pass | CheckStatus::Skipped => {
+                tracing::debug!(check = %check.name, detail = %check.detail, "Self-check passed");
+            }
+        }
+    }
+}
+
 #[tokio::main]
 async fn main() -> Result<()> {
     let start_time = Instant::now();
     let cli = Cli::parse();
 
-    let log_filter = if let Ok(value) = env::var("INDEX_MCP_LOG_LEVEL") {
-        value
-    } else if let Ok(value) = env::var("RUST_LOG") {
-        value
-    } else {
-        cli.log_level.clone()
-    };
-
-    let mut env_filter = EnvFilter::builder()
-        .with_default_directive(LevelFilter::INFO.into())
-        .parse_lossy(log_filter.as_str());
-
-    let lower_filter = log_filter.to_ascii_lowercase();
-    let default_directives = [
-        ("rmcp::service", "rmcp::service=info"),
-        ("rmcp::handler::server", "rmcp::handler::server=info"),
-        ("hf_hub", "hf_hub=warn"),
-    ];
-
-    for (needle, directive_str) in default_directives {
-        if !lower_filter.contains(needle) {
-            if let Ok(directive) = directive_str.parse::<Directive>() {
-                env_filter = env_filter.add_directive(directive);
-            }
-        }
+    // logging is not up yet, and a rejected config must reach the operator
+    let diagnostics = validate_config(env::vars(), &cli.flag_settings());
+    for diagnostic in &diagnostics {
+        eprintln!("[index-mcp] {diagnostic}");
+    }
+    let errors = diagnostics
+        .iter()
+        .filter(|diagnostic| diagnostic.severity == Severity::Error)
+        .count();
+    if errors > 0 && !cli.lenient_config {
+        anyhow::bail!(
+            "{errors} configuration error(s); fix them or pass --lenient-config to start anyway"
+        );
     }
 
+    let (log_filter, log_filter_source) = effective_log_level(cli.log_level.as_deref());
+
+    let log_filter = log_control::with_default_directives(&log_filter);
+    let (filter_layer, filter_handle) =
+        reload::Layer::new(log_control::parse_filter_lossy(&log_filter));
+
     let log_console = env::var("INDEX_MCP_LOG_CONSOLE")
         .ok()
         .and_then(|value| parse_bool(value.as_str()))
         .unwrap_or(true);
 
-    let log_dir = env::var("INDEX_MCP_LOG_DIR").ok().and_then(|value| {
-        let trimmed = value.trim();
-        if trimmed.is_empty() {
-            None
-        } else {
-            Some(trimmed.to_string())
-        }
-    });
+    let log_dir = env::var("INDEX_MCP_LOG_DIR")
+        .ok()
+        .and_then(|value| {
+            let trimmed = value.trim();
+            if trimmed.is_empty() {
+                None
+            } else {
+                Some(trimmed.to_string())
+            }
+        })
+        .or_else(|| data_dir::layout().map(|layout| layout.log_dir.to_string_lossy().to_string()));
 
     let mut log_guards: Vec<WorkerGuard> = Vec::new();
 
@@ -225,18 +333,23 @@ async fn main() -> Result<()> {
         None
     };
 
-    fmt()
-        .with_env_filter(env_filter)
-        .with_writer(LogWriters::new(log_console, file_writer))
-        .with_ansi(false)
+    tracing_subscriber::registry()
+        .with(filter_layer)
+        .with(
+            fmt::layer()
+                .with_writer(LogWriters::new(log_console, file_writer))
+                .with_ansi(false),
+        )
         .init();
+    log_control::install(filter_handle, log_filter);
     let _log_guards = log_guards;
 
     if let Some(path) = cli.cwd.as_ref() {
         std::env::set_current_dir(path)?;
     }
 
-    tracing::info!("Starting Rust MCP server");
+    tracing::info!(log_filter_source, "Starting Rust MCP server");
+    report_self_check().await;
 
     let mut watcher_handle = None;
     if cli.watch {
@@ -259,14 +372,28 @@ async fn main() -> Result<()> {
 
         match start_ingest_watcher(options).await {
             Ok(handle) => {
+                record_watcher_state(ComponentState::Running, None);
                 watcher_handle = Some(handle);
             }
             Err(error) => {
                 tracing::error!(?error, "Failed to start ingest watcher");
+                record_watcher_state(ComponentState::Failed, Some(error.to_string()));
             }
         }
     }
 
+    // search queues chunk hits in memory; write them behind on a timer
+    let hit_flusher = tokio::spawn(async {
+        let mut interval = tokio::time::interval(HIT_FLUSH_INTERVAL);
+        interval.tick().await;
+        loop {
+            interval.tick().await;
+            if let Err(error) = tokio::task::spawn_blocking(flush_pending_hits).await {
+                tracing::warn!(?error, "Hit counter flush task failed");
+            }
+        }
+    });
+
     let service = service::IndexMcpService::new().await?;
     tracing::info!(
         elapsed_ms = start_time.elapsed().as_millis() as u64,
@@ -279,6 +406,12 @@ async fn main() -> Result<()> {
 
     if let Some(handle) = watcher_handle {
         handle.stop().await;
+        record_watcher_state(ComponentState::Stopped, None);
+    }
+
+    hit_flusher.abort();
+    if let Err(error) = tokio::task::spawn_blocking(flush_pending_hits).await {
+        tracing::warn!(?error, "Final hit counter flush failed");
     }
 
     Ok(())
@@ -0,0 +1,168 @@
+use rmcp::schemars::{self, JsonSchema};
+use serde::Serialize;
+
+use crate::embedder::{MOCK_EMBEDDING_DIMENSIONS, MOCK_EMBEDDING_MODEL};
+use crate::ingest::DEFAULT_EMBEDDING_MODEL;
+
+/// Widest embedding the server stores; larger vectors cost more than they
+/// recover on code search at this index size.
+const MAX_EMBEDDING_DIMENSIONS: usize = 1024;
+
+/// Output size and input window of models whose limits are known ahead of
+/// loading them.
+#[derive(Debug, Clone, Copy)]
+pub(crate) struct ModelLimits {
+    pub(crate) dimensions: usize,
+    pub(crate) max_input_tokens: usize,
+}
+
+const KNOWN_MODELS: &[(&str, ModelLimits)] = &[
+    (DEFAULT_EMBEDDING_MODEL, limits(384, 256)),
+    ("Xenova/bge-small-en-v1.5", limits(384, 512)),
+    ("Xenova/bge-base-en-v1.5", limits(768, 512)),
+    ("Xenova/bge-large-en-v1.5", limits(1024, 512)),
+    ("jinaai/jina-embeddings-v2-base-code", limits(768, 8192)),
+    ("intfloat/multilingual-e5-small", limits(384, 512)),
+    ("intfloat/multilingual-e5-base", limits(768, 512)),
+    ("nomic-ai/nomic-embed-text-v1.5", limits(768, 8192)),
+    // hashes words, so it has no input window to speak of
+    (
+        MOCK_EMBEDDING_MODEL,
+        limits(MOCK_EMBEDDING_DIMENSIONS, 8192),
+    ),
+];
+
+const fn limits(dimensions: usize, max_input_tokens: usize) -> ModelLimits {
+    ModelLimits {
+        dimensions,
+        max_input_tokens,
+    }
+}
+
+/// Model plus chunking tuned for one kind of corpus. Explicit `model` and
+/// chunk sizes override the preset's.
+pub(crate) struct ModelPreset {
+    pub(crate) name: &'static str,
+    pub(crate) model: &'static str,
+    pub(crate) chunk_size_tokens: usize,
+    pub(crate) chunk_overlap_tokens: usize,
+}
+
+const PRESETS: &[ModelPreset] = &[
+    ModelPreset {
+        name: "general",
+        model: DEFAULT_EMBEDDING_MODEL,
+        chunk_size_tokens: 256,
+        chunk_overlap_tokens: 32,
+    },
+    // code models read whole functions; larger chunks keep them intact
+    ModelPreset {
+        name: "code",
+        model: "jinaai/jina-embeddings-v2-base-code",
+        chunk_size_tokens: 512,
+        chunk_overlap_tokens: 64,
+    },
+    ModelPreset {
+        name: "multilingual",
+        model: "intfloat/multilingual-e5-small",
+        chunk_size_tokens: 256,
+        chunk_overlap_tokens: 32,
+    },
+];
+
+struct Alias {
+    name: &'static str,
+    model: &'static str,
+    summary: &'static str,
+}
+
+const ALIASES: &[Alias] = &[
+    Alias {
+        name: "default",
+        model: DEFAULT_EMBEDDING_MODEL,
+        summary: "the model used when none is given",
+    },
+    Alias {
+        name: "small",
+        model: "Xenova/bge-small-en-v1.5",
+        summary: "384-dimension English model, fast to ingest",
+    },
+    Alias {
+        name: "base",
+        model: "Xenova/bge-base-en-v1.5",
+        summary: "768-dimension English model, better recall on prose",
+    },
+    Alias {
+        name: "code",
+        model: "jinaai/jina-embeddings-v2-base-code",
+        summary: "768-dimension model trained on source code",
+    },
+    Alias {
+        name: "multilingual",
+        model: "intfloat/multilingual-e5-small",
+        summary: "384-dimension model for non-English text",
+    },
+];
+
+#[derive(Debug, Clone, Serialize, JsonSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct ModelAlias {
+    pub alias: String,
+    pub model: String,
+    pub summary: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub dimensions: Option<usize>,
+}
+
+/// Maps an alias (case-insensitive) to its concrete model name; any other
+/// name passes through trimmed. Only concrete names reach the database.
+pub(crate) fn resolve_model_name(name: &str) -> String {
+    let name = name.trim();
+    ALIASES
+        .iter()
+        .find(|alias| alias.name.eq_ignore_ascii_case(name))
+        .map_or(name, |alias| alias.model)
+        .to_string()
+}
+
+pub(crate) fn model_aliases() -> Vec<ModelAlias> {
+    ALIASES
+        .iter()
+        .map(|alias| ModelAlias {
+            alias: alias.name.to_string(),
+            model: alias.model.to_string(),
+            summary: alias.summary.to_string(),
+            dimensions: model_limits(alias.model).map(|limits| limits.dimensions),
+        })
+        .collect()
+}
+
+pub(crate) fn model_limits(model: &str) -> Option<ModelLimits> {
+    KNOWN_MODELS
+        .iter()
+        .find(|(name, _)| name.eq_ignore_ascii_case(model))
+        .map(|(_, limits)| *limits)
+}
+
+pub(crate) fn find_preset(name: &str) -> Option<&'static ModelPreset> {
+    let name = name.trim();
+    PRESETS
+        .iter()
+        .find(|preset| preset.name.eq_ignore_ascii_case(name))
+}
+
+pub(crate) fn preset_names() -> Vec<&'static str> {
+    PRESETS.iter().map(|preset| preset.name).collect()
+}
+
+/// Rejects models whose vectors are wider than the server stores, before
+/// any of them are downloaded or written.
+pub(crate) fn check_dimensions(model: &str, dimensions: usize) -> Result<(), String> {
+    if dimensions > MAX_EMBEDDING_DIMENSIONS {
+        return Err(format!(
+            "embedding model '{model}' produces {dimensions} dimensions; the limit is {MAX_EMBEDDING_DIMENSIONS}. Pick a smaller model or a preset ({})",
+            preset_names().join(", ")
+        ));
+    }
+    Ok(())
+}
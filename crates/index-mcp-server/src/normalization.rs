@@ -0,0 +1,149 @@
+use std::borrow::Cow;
+
+use once_cell::sync::Lazy;
+use regex::Regex;
+use rmcp::schemars::{self, JsonSchema};
+use rusqlite::{params, Connection};
+use serde::{Deserialize, Serialize};
+
+/// Meta key holding the database's normalization as JSON.
+pub(crate) const META_CHUNK_NORMALIZATION: &str = "chunk_normalization";
+
+static IDENTIFIER: Lazy<Regex> =
+    Lazy::new(|| Regex::new(r"[A-Za-z_][A-Za-z0-9_]*").expect("valid identifier pattern"));
+
+/// Rewrites applied to chunk text before it is embedded, and to queries
+/// before they are. Stored chunk content is left as it was.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize, JsonSchema)]
+#[serde(rename_all = "camelCase", default)]
+pub struct ChunkNormalization {
+    /// Replace each run of whitespace with a single space.
+    pub collapse_whitespace: bool,
+    pub lowercase: bool,
+    /// Empty string literals (`"..."`, `'...'`, backticks) and drop numeric
+    /// literals, so minified bundles and generated tables embed by their code.
+    pub strip_literals: bool,
+    /// Break snake_case and camelCase identifiers into separate words.
+    pub split_identifiers: bool,
+}
+
+impl ChunkNormalization {
+    pub(crate) fn is_noop(&self) -> bool {
+        *self == Self::default()
+    }
+
+    pub(crate) fn apply<'a>(&self, text: &'a str) -> Cow<'a, str> {
+        if self.is_noop() {
+            return Cow::Borrowed(text);
+        }
+        let mut text = if self.strip_literals {
+            strip_literals(text)
+        } else {
+            text.to_string()
+        };
+        if self.split_identifiers {
+            text = IDENTIFIER
+                .replace_all(&text, |captures: &regex::Captures| {
+                    split_identifier(&captures[0])
+                })
+                .into_owned();
+        }
+        if self.lowercase {
+            text = text.to_lowercase();
+        }
+        if self.collapse_whitespace {
+            text = text.split_whitespace().collect::<Vec<_>>().join(" ");
+        }
+        Cow::Owned(text)
+    }
+}
+
+/// The normalization recorded by the last ingest; none for databases that
+/// predate the setting.
+pub(crate) fn load_chunk_normalization(conn: &Connection) -> ChunkNormalization {
+    conn.query_row(
+        "SELECT value FROM meta WHERE key = ?1",
+        params![META_CHUNK_NORMALIZATION],
+        |row| row.get::<_, String>(0),
+    )
+    .ok()
+    .and_then(|value| serde_json::from_str(&value).ok())
+    .unwrap_or_default()
+}
+
+fn strip_literals(text: &str) -> String {
+    let chars: Vec<char> = text.chars().collect();
+    let mut output = String::with_capacity(text.len());
+    let mut index = 0;
+    while index < chars.len() {
+        let ch = chars[index];
+        let previous = index.checked_sub(1).map(|previous| chars[previous]);
+        let after_word =
+            previous.is_some_and(|previous| previous.is_alphanumeric() || previous == '_');
+        if matches!(ch, '"' | '\'' | '`') && !after_word {
+            if let Some(end) = literal_end(&chars, index) {
+                output.push(ch);
+                output.push(ch);
+                index = end + 1;
+                continue;
+            }
+        }
+        if ch.is_ascii_digit() && !after_word {
+            while index < chars.len()
+                && (chars[index].is_alphanumeric() || matches!(chars[index], '_' | '.'))
+            {
+                index += 1;
+            }
+            continue;
+        }
+        output.push(ch);
+        index += 1;
+    }
+    output
+}
+
+/// Index of the quote closing the literal opened at `start`. Only backticks
+/// span lines; a single quote followed by a letter is a lifetime or an
+/// apostrophe rather than a closing quote.
+fn literal_end(chars: &[char], start: usize) -> Option<usize> {
+    let quote = chars[start];
+    let mut index = start + 1;
+    while index < chars.len() {
+        match chars[index] {
+            '\\' => index += 1,
+            '\n' if quote != '`' => return None,
+            ch if ch == quote => {
+                let followed_by_word = chars
+                    .get(index + 1)
+                    .is_some_and(|next| next.is_alphanumeric() || *next == '_');
+                return (quote != '\'' || !followed_by_word).then_some(index);
+            }
+            _ => {}
+        }
+        index += 1;
+    }
+    None
+}
+
+fn split_identifier(identifier: &str) -> String {
+    let mut words = Vec::new();
+    for word in identifier.split('_').filter(|word| !word.is_empty()) {
+        let chars: Vec<char> = word.chars().collect();
+        let mut start = 0;
+        for index in 1..chars.len() {
+            let boundary = chars[index].is_uppercase()
+                && (chars[index - 1].is_lowercase()
+                    || chars.get(index + 1).is_some_and(|next| next.is_lowercase()));
+            if boundary {
+                words.push(chars[start..index].iter().collect::<String>());
+                start = index;
+            }
+        }
+        words.push(chars[start..].iter().collect::<String>());
+    }
+    if words.is_empty() {
+        identifier.to_string()
+    } else {
+        words.join(" ")
+    }
+}
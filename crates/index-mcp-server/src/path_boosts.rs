@@ -0,0 +1,105 @@
+use std::collections::BTreeMap;
+use std::env;
+
+use globset::{Glob, GlobSet, GlobSetBuilder};
+use rmcp::schemars::{self, JsonSchema};
+use serde::Serialize;
+use thiserror::Error;
+
+pub(crate) const PATH_BOOSTS_ENV: &str = "INDEX_MCP_PATH_BOOSTS";
+
+#[derive(Debug, Error)]
+pub enum PathBoostError {
+    #[error("{PATH_BOOSTS_ENV} is not a JSON object of glob to multiplier: {0}")]
+    Invalid(String),
+    #[error("{PATH_BOOSTS_ENV} multiplier for '{glob}' must be a positive number, got {value}")]
+    Multiplier { glob: String, value: f64 },
+}
+
+/// Score multipliers per path glob from `INDEX_MCP_PATH_BOOSTS`, e.g.
+/// `{"src/**": 1.2, "**/*_generated.go": 0.5}`. A path matching several
+/// globs gets the product of their multipliers.
+#[derive(Debug, Default)]
+pub(crate) struct PathBoosts {
+    rules: Vec<(String, f32)>,
+    set: Option<GlobSet>,
+}
+
+/// The multiplier applied to a hit and the globs it came from.
+#[derive(Debug, Clone, Serialize, JsonSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct AppliedPathBoost {
+    pub multiplier: f32,
+    pub globs: Vec<String>,
+}
+
+pub(crate) fn configured_path_boosts() -> Result<BTreeMap<String, f64>, PathBoostError> {
+    let raw = match env::var(PATH_BOOSTS_ENV) {
+        Ok(raw) if !raw.trim().is_empty() => raw,
+        _ => return Ok(BTreeMap::new()),
+    };
+    let boosts: BTreeMap<String, f64> =
+        serde_json::from_str(&raw).map_err(|error| PathBoostError::Invalid(error.to_string()))?;
+    for (glob, value) in &boosts {
+        if !value.is_finite() || *value <= 0.0 {
+            return Err(PathBoostError::Multiplier {
+                glob: glob.clone(),
+                value: *value,
+            });
+        }
+    }
+    Ok(boosts)
+}
+
+impl PathBoosts {
+    pub(crate) fn from_env() -> Result<Self, PathBoostError> {
+        let boosts = configured_path_boosts()?;
+        if boosts.is_empty() {
+            return Ok(Self::default());
+        }
+        let mut builder = GlobSetBuilder::new();
+        let mut rules = Vec::with_capacity(boosts.len());
+        for (glob, multiplier) in boosts {
+            let compiled = Glob::new(&glob)
+                .map_err(|error| PathBoostError::Invalid(format!("glob '{glob}': {error}")))?;
+            builder.add(compiled);
+            rules.push((glob, multiplier as f32));
+        }
+        let set = builder
+            .build()
+            .map_err(|error| PathBoostError::Invalid(error.to_string()))?;
+        Ok(Self {
+            rules,
+            set: Some(set),
+        })
+    }
+
+    /// `None` when no glob matches `path`.
+    pub(crate) fn boost_for(&self, path: &str) -> Option<AppliedPathBoost> {
+        let matches = self.set.as_ref()?.matches(path);
+        if matches.is_empty() {
+            return None;
+        }
+        let mut boost = AppliedPathBoost {
+            multiplier: 1.0,
+            globs: Vec::with_capacity(matches.len()),
+        };
+        for index in matches {
+            let (glob, multiplier) = &self.rules[index];
+            boost.multiplier *= multiplier;
+            boost.globs.push(glob.clone());
+        }
+        Some(boost)
+    }
+
+    pub(crate) fn multiplier_for(&self, path: &str) -> f32 {
+        match &self.set {
+            Some(set) => set
+                .matches(path)
+                .into_iter()
+                .map(|index| self.rules[index].1)
+                .product(),
+            None => 1.0,
+        }
+    }
+}
@@ -0,0 +1,164 @@
+use std::collections::VecDeque;
+use std::env;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
+
+use rmcp::schemars::{self, JsonSchema};
+use serde::Serialize;
+use thiserror::Error;
+
+const SEARCHES_PER_MINUTE_ENV: &str = "INDEX_MCP_MAX_SEARCHES_PER_MINUTE";
+const INGESTS_PER_HOUR_ENV: &str = "INDEX_MCP_MAX_INGESTS_PER_HOUR";
+const MINUTE: Duration = Duration::from_secs(60);
+const HOUR: Duration = Duration::from_secs(60 * 60);
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, JsonSchema)]
+#[serde(rename_all = "camelCase")]
+pub enum QuotaKind {
+    EmbeddedQueries,
+    Ingests,
+}
+
+impl QuotaKind {
+    pub fn label(self) -> &'static str {
+        match self {
+            QuotaKind::EmbeddedQueries => "embedded queries",
+            QuotaKind::Ingests => "ingests",
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, JsonSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct QuotaStatus {
+    pub kind: QuotaKind,
+    pub limit: u32,
+    pub window_seconds: u64,
+    pub used: u32,
+    pub remaining: u32,
+    /// Milliseconds until the oldest counted call leaves the window.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub resets_in_ms: Option<u64>,
+}
+
+#[derive(Debug, Error)]
+#[error("{} quota exceeded ({limit} per {window_seconds}s); retry in {retry_after_ms} ms", .kind.label())]
+pub struct QuotaExceeded {
+    pub kind: QuotaKind,
+    pub limit: u32,
+    pub window_seconds: u64,
+    pub retry_after_ms: u64,
+    /// Unix milliseconds at which another call will be accepted.
+    pub reset_at_ms: i64,
+}
+
+#[derive(Debug)]
+struct SlidingWindow {
+    kind: QuotaKind,
+    limit: u32,
+    window: Duration,
+    calls: VecDeque<Instant>,
+}
+
+impl SlidingWindow {
+    fn evict_expired(&mut self, now: Instant) {
+        while self
+            .calls
+            .front()
+            .is_some_and(|call| now.duration_since(*call) >= self.window)
+        {
+            self.calls.pop_front();
+        }
+    }
+
+    fn resets_in(&self, now: Instant) -> Option<Duration> {
+        self.calls
+            .front()
+            .map(|oldest| self.window.saturating_sub(now.duration_since(*oldest)))
+    }
+
+    fn status(&mut self, now: Instant) -> QuotaStatus {
+        self.evict_expired(now);
+        let used = self.calls.len() as u32;
+        QuotaStatus {
+            kind: self.kind,
+            limit: self.limit,
+            window_seconds: self.window.as_secs(),
+            used,
+            remaining: self.limit.saturating_sub(used),
+            resets_in_ms: self.resets_in(now).map(|wait| wait.as_millis() as u64),
+        }
+    }
+}
+
+/// Per-session rate limits for expensive tools, configured through
+/// `INDEX_MCP_MAX_SEARCHES_PER_MINUTE` and `INDEX_MCP_MAX_INGESTS_PER_HOUR`.
+/// Unset or zero leaves a kind unlimited.
+#[derive(Debug, Clone, Default)]
+pub struct UsageMeter {
+    windows: Arc<Mutex<Vec<SlidingWindow>>>,
+}
+
+impl UsageMeter {
+    pub fn from_env() -> Self {
+        let windows = [
+            (QuotaKind::EmbeddedQueries, SEARCHES_PER_MINUTE_ENV, MINUTE),
+            (QuotaKind::Ingests, INGESTS_PER_HOUR_ENV, HOUR),
+        ]
+        .into_iter()
+        .filter_map(|(kind, key, window)| {
+            let limit = env::var(key).ok()?.trim().parse::<u32>().ok()?;
+            (limit > 0).then(|| SlidingWindow {
+                kind,
+                limit,
+                window,
+                calls: VecDeque::new(),
+            })
+        })
+        .collect();
+
+        Self {
+            windows: Arc::new(Mutex::new(windows)),
+        }
+    }
+
+    /// Counts a call against `kind`, or reports when the next one is allowed.
+    pub fn check(&self, kind: QuotaKind) -> Result<(), QuotaExceeded> {
+        let Ok(mut windows) = self.windows.lock() else {
+            return Ok(());
+        };
+        let Some(window) = windows.iter_mut().find(|window| window.kind == kind) else {
+            return Ok(());
+        };
+
+        let now = Instant::now();
+        window.evict_expired(now);
+        if (window.calls.len() as u32) < window.limit {
+            window.calls.push_back(now);
+            return Ok(());
+        }
+
+        let retry_after = window.resets_in(now).unwrap_or(window.window);
+        Err(QuotaExceeded {
+            kind,
+            limit: window.limit,
+            window_seconds: window.window.as_secs(),
+            retry_after_ms: retry_after.as_millis() as u64,
+            reset_at_ms: (SystemTime::now() + retry_after)
+                .duration_since(UNIX_EPOCH)
+                .map(|duration| duration.as_millis() as i64)
+                .unwrap_or(0),
+        })
+    }
+
+    pub fn status(&self) -> Vec<QuotaStatus> {
+        let now = Instant::now();
+        match self.windows.lock() {
+            Ok(mut windows) => windows
+                .iter_mut()
+                .map(|window| window.status(now))
+                .collect(),
+            Err(_) => Vec::new(),
+        }
+    }
+}
@@ -0,0 +1,384 @@
+use rmcp::schemars::{self, JsonSchema};
+use serde::Serialize;
+use serde_json::{json, Map, Value};
+
+const MAX_RECOMMENDATIONS: usize = 5;
+// search arguments that narrow the corpus; an empty result retries without them
+const SEARCH_FILTERS: &[&str] = &[
+    "language",
+    "pathPrefix",
+    "pathContains",
+    "classification",
+    "package",
+    "onlyPaths",
+    "include",
+    "exclude",
+    "preset",
+    "generation",
+    "atCommit",
+];
+
+/// A follow-up call, ready to send as-is.
+#[derive(Debug, Clone, Serialize, JsonSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct Recommendation {
+    pub tool: String,
+    pub reason: String,
+    pub parameters: Value,
+}
+
+/// Derives next steps from a tool's structured result and the arguments it
+/// was called with, so every tool answers in the same `recommendations`
+/// shape. `root` is the root the call resolved to.
+pub(crate) fn recommend(
+    tool: &str,
+    arguments: Option<&Map<String, Value>>,
+    root: Option<&str>,
+    result: &Value,
+) -> Vec<Recommendation> {
+    let context = CallContext { arguments, root };
+    let mut recommendations = match tool {
+        "ingest_codebase" => after_ingest(&context, result),
+        "estimate_ingest" => after_estimate(&context),
+        "preview_eviction" => after_eviction_preview(&context, result),
+        "index_status" => after_index_status(&context, result),
+        "semantic_search" => after_search(&context, result),
+        "code_lookup" => match result.get("searchResult") {
+            Some(search) => after_search(&context, search),
+            None => result
+                .get("bundleResult")
+                .map(|bundle| after_bundle(&context, bundle))
+                .unwrap_or_default(),
+        },
+        "context_bundle" => after_bundle(&context, result),
+        "verify_index" => after_verify(&context, result),
+        "regex_search" => after_regex_search(&context, result),
+        _ => Vec::new(),
+    };
+    recommendations.truncate(MAX_RECOMMENDATIONS);
+    recommendations
+}
+
+struct CallContext<'a> {
+    arguments: Option<&'a Map<String, Value>>,
+    root: Option<&'a str>,
+}
+
+impl CallContext<'_> {
+    fn argument(&self, name: &str) -> Option<&Value> {
+        self.arguments
+            .and_then(|arguments| arguments.get(name))
+            .filter(|value| !value.is_null())
+    }
+
+    /// `root` and `databaseName`, which every follow-up needs to hit the
+    /// same index.
+    fn base_parameters(&self) -> Map<String, Value> {
+        let mut parameters = Map::new();
+        if let Some(root) = self.root {
+            parameters.insert("root".to_string(), json!(root));
+        }
+        if let Some(database_name) = self.argument("databaseName") {
+            parameters.insert("databaseName".to_string(), database_name.clone());
+        }
+        parameters
+    }
+
+    fn recommendation(
+        &self,
+        tool: &str,
+        reason: String,
+        extra: impl IntoIterator<Item = (&'static str, Value)>,
+    ) -> Recommendation {
+        let mut parameters = self.base_parameters();
+        for (key, value) in extra {
+            parameters.insert(key.to_string(), value);
+        }
+        Recommendation {
+            tool: tool.to_string(),
+            reason,
+            parameters: Value::Object(parameters),
+        }
+    }
+}
+
+fn after_ingest(context: &CallContext, result: &Value) -> Vec<Recommendation> {
+    let mut recommendations = Vec::new();
+    let skipped = count(result, "skippedCount");
+    if skipped > 0 {
+        recommendations.push(context.recommendation(
+            "list_skipped",
+            format!("{skipped} file(s) were skipped; list them by reason."),
+            [],
+        ));
+    }
+    recommendations.push(context.recommendation(
+        "index_status",
+        "Confirm the refreshed index is fresh and healthy.".to_string(),
+        [],
+    ));
+    recommendations.push(Recommendation {
+        tool: "watcher_status".to_string(),
+        reason: "Check whether watch mode keeps the index fresh; start the server with --watch to re-ingest on change.".to_string(),
+        parameters: json!({}),
+    });
+    recommendations
+}
+
+fn after_estimate(context: &CallContext) -> Vec<Recommendation> {
+    // the estimate takes the ingest's scan and embedding options verbatim
+    let mut parameters = context.arguments.cloned().unwrap_or_default();
+    parameters.remove("sampleChunks");
+    parameters.extend(context.base_parameters());
+    vec![Recommendation {
+        tool: "ingest_codebase".to_string(),
+        reason: "Run the ingest this estimate describes.".to_string(),
+        parameters: Value::Object(parameters),
+    }]
+}
+
+fn after_eviction_preview(context: &CallContext, result: &Value) -> Vec<Recommendation> {
+    if !flag(result, "wouldEvict") {
+        return Vec::new();
+    }
+    let mut extra = vec![
+        ("autoEvict", json!(true)),
+        (
+            "maxDatabaseSizeBytes",
+            result
+                .get("maxDatabaseSizeBytes")
+                .cloned()
+                .unwrap_or(Value::Null),
+        ),
+    ];
+    if let Some(protected) = context.argument("protectedClassifications") {
+        extra.push(("protectedClassifications", protected.clone()));
+    }
+    vec![context.recommendation(
+        "ingest_codebase",
+        format!(
+            "Apply this eviction: {} chunk(s) would go.",
+            count(result, "chunksToEvict")
+        ),
+        extra,
+    )]
+}
+
+fn after_index_status(context: &CallContext, result: &Value) -> Vec<Recommendation> {
+    let mut recommendations = Vec::new();
+    if !flag(result, "databaseExists") {
+        recommendations.push(context.recommendation(
+            "ingest_codebase",
+            "No index exists yet; build it.".to_string(),
+            [],
+        ));
+        return recommendations;
+    }
+    if flag(result, "isStale") {
+        let changed = strings(result, "changedPaths");
+        if changed.is_empty() {
+            recommendations.push(context.recommendation(
+                "ingest_codebase",
+                "HEAD moved since the last ingest; refresh the index.".to_string(),
+                [],
+            ));
+        } else {
+            recommendations.push(context.recommendation(
+                "ingest_codebase",
+                format!(
+                    "{} file(s) changed since the indexed commit; re-ingest just those.",
+                    changed.len()
+                ),
+                [("paths", json!(changed))],
+            ));
+        }
+    }
+    let size_flagged = result
+        .pointer("/health/dimensions")
+        .and_then(Value::as_array)
+        .into_iter()
+        .flatten()
+        .any(|dimension| {
+            dimension.get("name").and_then(Value::as_str) == Some("size")
+                && dimension.get("status").and_then(Value::as_str) != Some("green")
+        });
+    if size_flagged {
+        recommendations.push(context.recommendation(
+            "preview_eviction",
+            "The database is near its size cap; see what autoEvict would remove.".to_string(),
+            [],
+        ));
+    }
+    recommendations
+}
+
+fn after_search(context: &CallContext, result: &Value) -> Vec<Recommendation> {
+    let mut recommendations = Vec::new();
+    let stale = result
+        .pointer("/freshness/stalePaths")
+        .map(strings_of)
+        .unwrap_or_default();
+    if !stale.is_empty() {
+        recommendations.push(context.recommendation(
+            "ingest_codebase",
+            format!(
+                "{} matched file(s) changed on disk since indexing; re-ingest them.",
+                stale.len()
+            ),
+            [("paths", json!(stale))],
+        ));
+    }
+
+    let has_results = result
+        .get("results")
+        .and_then(Value::as_array)
+        .is_some_and(|results| !results.is_empty());
+    if has_results {
+        for suggestion in result
+            .get("suggestedTools")
+            .and_then(Value::as_array)
+            .into_iter()
+            .flatten()
+        {
+            let (Some(tool), Some(parameters)) = (
+                suggestion.get("tool").and_then(Value::as_str),
+                suggestion.get("parameters"),
+            ) else {
+                continue;
+            };
+            let target = suggestion
+                .get("description")
+                .and_then(Value::as_str)
+                .unwrap_or("the hit");
+            recommendations.push(Recommendation {
+                tool: tool.to_string(),
+                reason: format!("Open {target} with its definitions and neighbors."),
+                parameters: parameters.clone(),
+            });
+        }
+        return recommendations;
+    }
+
+    let query = context.argument("query").cloned();
+    if count(result, "totalChunks") == 0 {
+        recommendations.push(context.recommendation(
+            "ingest_codebase",
+            "The index has no chunks; ingest before searching.".to_string(),
+            [],
+        ));
+        return recommendations;
+    }
+    let filters: Vec<&str> = SEARCH_FILTERS
+        .iter()
+        .copied()
+        .filter(|name| context.argument(name).is_some())
+        .collect();
+    if let (false, Some(query)) = (filters.is_empty(), query.clone()) {
+        recommendations.push(context.recommendation(
+            "semantic_search",
+            format!(
+                "Nothing matched with {}; retry without those filters.",
+                filters.join(", ")
+            ),
+            [("query", query)],
+        ));
+    }
+    if let Some(text) = query.as_ref().and_then(Value::as_str) {
+        recommendations.push(context.recommendation(
+            "regex_search",
+            "Look for the query as literal text in stored file content.".to_string(),
+            [
+                ("pattern", json!(regex::escape(text.trim()))),
+                ("caseInsensitive", json!(true)),
+            ],
+        ));
+    }
+    if let Some(query) = query {
+        recommendations.push(context.recommendation(
+            "search_diffs",
+            "Nothing in the indexed tree matched; the code may have been removed, so search commit diffs."
+                .to_string(),
+            [("query", query)],
+        ));
+    }
+    recommendations
+}
+
+fn after_regex_search(context: &CallContext, result: &Value) -> Vec<Recommendation> {
+    let has_matches = result
+        .get("matches")
+        .and_then(Value::as_array)
+        .is_some_and(|matches| !matches.is_empty());
+    if has_matches || count(result, "filesWithoutContent") == 0 {
+        return Vec::new();
+    }
+    vec![context.recommendation(
+        "ingest_codebase",
+        format!(
+            "{} file(s) were indexed without content; re-ingest storing it so they can be searched.",
+            count(result, "filesWithoutContent")
+        ),
+        [("storeFileContent", json!(true))],
+    )]
+}
+
+fn after_bundle(context: &CallContext, result: &Value) -> Vec<Recommendation> {
+    let mut recommendations = Vec::new();
+    let path = result.pointer("/file/path").and_then(Value::as_str);
+    if let (true, Some(path)) = (flag(result, "contentDrift"), path) {
+        recommendations.push(context.recommendation(
+            "ingest_codebase",
+            format!("{path} changed since indexing; re-ingest it."),
+            [("paths", json!([path]))],
+        ));
+    }
+    recommendations
+}
+
+fn after_verify(context: &CallContext, result: &Value) -> Vec<Recommendation> {
+    let mut paths: Vec<String> = Vec::new();
+    for chunk in result
+        .get("corrupt")
+        .and_then(Value::as_array)
+        .into_iter()
+        .flatten()
+    {
+        if let Some(path) = chunk.get("path").and_then(Value::as_str) {
+            if !paths.iter().any(|existing| existing == path) {
+                paths.push(path.to_string());
+            }
+        }
+    }
+    if paths.is_empty() {
+        return Vec::new();
+    }
+    vec![context.recommendation(
+        "ingest_codebase",
+        format!(
+            "{} file(s) have corrupt chunks; re-ingest them.",
+            paths.len()
+        ),
+        [("paths", json!(paths))],
+    )]
+}
+
+fn flag(value: &Value, key: &str) -> bool {
+    value.get(key).and_then(Value::as_bool).unwrap_or(false)
+}
+
+fn count(value: &Value, key: &str) -> u64 {
+    value.get(key).and_then(Value::as_u64).unwrap_or(0)
+}
+
+fn strings(value: &Value, key: &str) -> Vec<String> {
+    value.get(key).map(strings_of).unwrap_or_default()
+}
+
+fn strings_of(value: &Value) -> Vec<String> {
+    value
+        .as_array()
+        .into_iter()
+        .flatten()
+        .filter_map(|item| item.as_str().map(str::to_string))
+        .collect()
+}
@@ -0,0 +1,269 @@
+use std::path::PathBuf;
+
+use regex::{Regex, RegexBuilder};
+use rmcp::schemars::{self, JsonSchema};
+use serde::{Deserialize, Serialize};
+use thiserror::Error;
+
+use crate::content_store::{content_encoding_column, decode_file_content};
+use crate::data_dir::{database_path, open_read_only};
+use crate::index_status::DEFAULT_DB_FILENAME;
+use crate::ingest::{compile_globs, IngestError};
+use crate::roots::resolve_workspace_root;
+use crate::sandbox::{ensure_root_allowed, SandboxError};
+
+const DEFAULT_MATCH_LIMIT: usize = 50;
+const MAX_MATCH_LIMIT: usize = 500;
+const DEFAULT_MATCHES_PER_FILE: usize = 20;
+const MAX_CONTEXT_LINES: usize = 5;
+// keeps minified lines from flooding the response
+const MAX_LINE_CHARS: usize = 400;
+// compiled program size; patterns past it are rejected rather than run
+const REGEX_SIZE_LIMIT: usize = 1 << 20;
+
+#[derive(Debug, Deserialize, JsonSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct RegexSearchParams {
+    #[serde(default)]
+    pub root: Option<String>,
+    #[serde(default)]
+    pub database_name: Option<String>,
+    /// Rust `regex` syntax, matched per line.
+    pub pattern: String,
+    #[serde(default)]
+    pub case_insensitive: Option<bool>,
+    /// Only search paths matching these globs.
+    #[serde(default)]
+    pub include: Option<Vec<String>>,
+    /// Skip paths matching these globs; wins over `include`.
+    #[serde(default)]
+    pub exclude: Option<Vec<String>>,
+    #[serde(default)]
+    pub path_prefix: Option<String>,
+    /// Named filter preset from INDEX_MCP_FILTER_PRESETS; explicit filters
+    /// win and `exclude` globs are merged.
+    #[serde(default)]
+    pub preset: Option<String>,
+    /// Lines of context before and after each match (default 0, max 5).
+    #[serde(default)]
+    pub context_lines: Option<u32>,
+    /// Maximum matches returned (default 50, max 500).
+    #[serde(default)]
+    pub limit: Option<u32>,
+    /// Maximum matches per file (default 20).
+    #[serde(default)]
+    pub max_matches_per_file: Option<u32>,
+}
+
+#[derive(Debug, Clone, Serialize, JsonSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct RegexMatch {
+    pub path: String,
+    /// 1-based.
+    pub line: usize,
+    /// 1-based character column of the first match on the line.
+    pub column: usize,
+    pub text: String,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub before: Vec<String>,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub after: Vec<String>,
+}
+
+#[derive(Debug, Clone, Serialize, JsonSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct RegexSearchResponse {
+    pub database_path: String,
+    pub pattern: String,
+    pub matches: Vec<RegexMatch>,
+    pub files_searched: usize,
+    pub files_matched: usize,
+    /// Indexed files with no stored content (`storeFileContent: false`),
+    /// which cannot be searched.
+    pub files_without_content: usize,
+    /// `limit` stopped the search before every file was read.
+    pub truncated: bool,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub note: Option<String>,
+}
+
+#[derive(Debug, Error)]
+pub enum RegexSearchError {
+    #[error("failed to resolve workspace root '{path}': {source}")]
+    InvalidRoot {
+        path: String,
+        #[source]
+        source: std::io::Error,
+    },
+    #[error("invalid pattern '{pattern}': {source}")]
+    InvalidPattern {
+        pattern: String,
+        #[source]
+        source: regex::Error,
+    },
+    #[error(transparent)]
+    Glob(#[from] IngestError),
+    #[error("SQLite error: {0}")]
+    Sqlite(#[from] rusqlite::Error),
+    #[error(transparent)]
+    Sandbox(#[from] SandboxError),
+    #[error("blocking task panicked: {0}")]
+    Join(#[from] tokio::task::JoinError),
+}
+
+pub async fn regex_search(
+    params: RegexSearchParams,
+) -> Result<RegexSearchResponse, RegexSearchError> {
+    tokio::task::spawn_blocking(move || perform_regex_search(params)).await?
+}
+
+fn perform_regex_search(
+    params: RegexSearchParams,
+) -> Result<RegexSearchResponse, RegexSearchError> {
+    let root = params.root.unwrap_or_else(|| "./".to_string());
+    let root_path = resolve_root(&root)?;
+    ensure_root_allowed(&root_path)?;
+    let database_path = database_path(
+        &root_path,
+        &params
+            .database_name
+            .unwrap_or_else(|| DEFAULT_DB_FILENAME.to_string()),
+    );
+    let regex = RegexBuilder::new(&params.pattern)
+        .case_insensitive(params.case_insensitive.unwrap_or(false))
+        .size_limit(REGEX_SIZE_LIMIT)
+        .build()
+        .map_err(|source| RegexSearchError::InvalidPattern {
+            pattern: params.pattern.clone(),
+            source,
+        })?;
+    let include = compile_globs(params.include.as_deref().unwrap_or_default())?;
+    let exclude = compile_globs(params.exclude.as_deref().unwrap_or_default())?;
+    let path_prefix = params
+        .path_prefix
+        .as_deref()
+        .map(|prefix| prefix.trim().trim_start_matches("./"))
+        .filter(|prefix| !prefix.is_empty());
+    let limit = params
+        .limit
+        .map_or(DEFAULT_MATCH_LIMIT, |value| value as usize)
+        .clamp(1, MAX_MATCH_LIMIT);
+    let per_file = params
+        .max_matches_per_file
+        .map_or(DEFAULT_MATCHES_PER_FILE, |value| value as usize)
+        .max(1);
+    let context_lines = params
+        .context_lines
+        .map_or(0, |value| value as usize)
+        .min(MAX_CONTEXT_LINES);
+
+    let mut response = RegexSearchResponse {
+        database_path: database_path.to_string_lossy().to_string(),
+        pattern: params.pattern,
+        matches: Vec::new(),
+        files_searched: 0,
+        files_matched: 0,
+        files_without_content: 0,
+        truncated: false,
+        note: None,
+    };
+    if !database_path.is_file() {
+        response.note = Some("No index yet; run ingest_codebase first.".to_string());
+        return Ok(response);
+    }
+
+    let conn = open_read_only(&database_path)?;
+    let encoding_column = content_encoding_column(&conn, "files");
+    let mut stmt = conn.prepare(&format!(
+        "SELECT path, content, {encoding_column} FROM files ORDER BY path"
+    ))?;
+    let mut rows = stmt.query([])?;
+    while let Some(row) = rows.next()? {
+        let path: String = row.get(0)?;
+        if path_prefix.is_some_and(|prefix| !path.starts_with(prefix))
+            || include.as_ref().is_some_and(|globs| !globs.is_match(&path))
+            || exclude.as_ref().is_some_and(|globs| globs.is_match(&path))
+        {
+            continue;
+        }
+        if response.matches.len() >= limit {
+            response.truncated = true;
+            break;
+        }
+        let encoding: Option<String> = row.get(2)?;
+        let Some(content) = decode_file_content(row.get(1)?, encoding.as_deref()) else {
+            response.files_without_content += 1;
+            continue;
+        };
+        response.files_searched += 1;
+        let found = search_file(
+            &regex,
+            &path,
+            &content,
+            context_lines,
+            per_file.min(limit - response.matches.len()),
+        );
+        if !found.is_empty() {
+            response.files_matched += 1;
+            response.matches.extend(found);
+        }
+    }
+    if response.matches.is_empty() && response.files_without_content > 0 {
+        response.note = Some(format!(
+            "{} file(s) have no stored content; re-ingest with storeFileContent to search them.",
+            response.files_without_content
+        ));
+    }
+    Ok(response)
+}
+
+fn search_file(
+    regex: &Regex,
+    path: &str,
+    content: &str,
+    context_lines: usize,
+    limit: usize,
+) -> Vec<RegexMatch> {
+    let lines: Vec<&str> = content
+        .lines()
+        .map(|line| line.trim_end_matches('\r'))
+        .collect();
+    let mut matches = Vec::new();
+    for (index, line) in lines.iter().enumerate() {
+        let Some(found) = regex.find(line) else {
+            continue;
+        };
+        matches.push(RegexMatch {
+            path: path.to_string(),
+            line: index + 1,
+            column: line[..found.start()].chars().count() + 1,
+            text: clip_line(line),
+            before: lines[index.saturating_sub(context_lines)..index]
+                .iter()
+                .map(|line| clip_line(line))
+                .collect(),
+            after: lines[index + 1..(index + 1 + context_lines).min(lines.len())]
+                .iter()
+                .map(|line| clip_line(line))
+                .collect(),
+        });
+        if matches.len() >= limit {
+            break;
+        }
+    }
+    matches
+}
+
+fn clip_line(line: &str) -> String {
+    match line.char_indices().nth(MAX_LINE_CHARS) {
+        Some((cut, _)) => format!("{}…", &line[..cut]),
+        None => line.to_string(),
+    }
+}
+
+fn resolve_root(root: &str) -> Result<PathBuf, RegexSearchError> {
+    resolve_workspace_root(root).map_err(|source| RegexSearchError::InvalidRoot {
+        path: root.to_string(),
+        source,
+    })
+}
@@ -0,0 +1,121 @@
+use std::io::{Error, ErrorKind};
+use std::path::{Path, PathBuf};
+
+/// Resolves a tool's `root` argument the same way for every tool: a leading
+/// `~` expands to the home directory, relative paths join the current
+/// directory, and the result is canonicalized so symlinks, `..` segments and
+/// trailing slashes all map to one database. The root must be an existing
+/// directory.
+pub(crate) fn resolve_workspace_root(root: &str) -> std::io::Result<PathBuf> {
+    let expanded = expand_home(root.trim())?;
+    let absolute = if expanded.is_absolute() {
+        expanded
+    } else {
+        std::env::current_dir()?.join(expanded)
+    };
+    let canonical = simplify_verbatim(absolute.canonicalize()?);
+    if !canonical.is_dir() {
+        return Err(Error::new(
+            ErrorKind::InvalidInput,
+            format!("{} is not a directory", canonical.display()),
+        ));
+    }
+    Ok(canonical)
+}
+
+// `~user` forms are left alone; only the caller's own home is expanded
+fn expand_home(root: &str) -> std::io::Result<PathBuf> {
+    let Some(rest) = root.strip_prefix('~') else {
+        return Ok(PathBuf::from(root));
+    };
+    if !(rest.is_empty() || rest.starts_with('/') || rest.starts_with('\\')) {
+        return Ok(PathBuf::from(root));
+    }
+    let home = std::env::var_os("HOME")
+        .or_else(|| std::env::var_os("USERPROFILE"))
+        .ok_or_else(|| Error::new(ErrorKind::NotFound, "cannot expand '~': HOME is not set"))?;
+    Ok(Path::new(&home).join(rest.trim_start_matches(['/', '\\'])))
+}
+
+/// `candidate` relative to `root`, for absolute paths handed in by callers
+/// and file events. On Windows the comparison ignores case, as NTFS does,
+/// and tolerates a `\\?\` prefix on either side.
+pub(crate) fn relative_to_root(root: &Path, candidate: &Path) -> Option<PathBuf> {
+    let candidate = simplify_verbatim(candidate.to_path_buf());
+    if let Ok(relative) = candidate.strip_prefix(root) {
+        return Some(relative.to_path_buf());
+    }
+    if !cfg!(windows) {
+        return None;
+    }
+    let mut remaining = candidate.components();
+    for expected in root.components() {
+        let actual = remaining.next()?;
+        if !expected
+            .as_os_str()
+            .eq_ignore_ascii_case(actual.as_os_str())
+        {
+            return None;
+        }
+    }
+    Some(remaining.as_path().to_path_buf())
+}
+
+// Windows canonicalization returns verbatim paths (`\\?\C:\repo`), which
+// never prefix-match the `C:\repo` form users and file events use. std adds
+// the prefix back by itself when a path outgrows the legacy length limit.
+pub(crate) fn simplify_verbatim(path: PathBuf) -> PathBuf {
+    if !cfg!(windows) {
+        return path;
+    }
+    match path.to_str().and_then(strip_verbatim_prefix) {
+        Some(simplified) => PathBuf::from(simplified),
+        None => path,
+    }
+}
+
+/// `\\?\C:\repo` becomes `C:\repo` and `\\?\UNC\host\share` becomes
+/// `\\host\share`; other verbatim forms (device paths, volume GUIDs) have no
+/// plain spelling and are kept.
+fn strip_verbatim_prefix(path: &str) -> Option<String> {
+    if let Some(share) = path.strip_prefix(r"\\?\UNC\") {
+        return Some(format!(r"\\{share}"));
+    }
+    let rest = path.strip_prefix(r"\\?\")?;
+    let drive = rest.as_bytes();
+    (drive.len() >= 2 && drive[0].is_ascii_alphabetic() && drive[1] == b':')
+        .then(|| rest.to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn verbatim_prefixes_map_to_plain_paths() {
+        assert_eq!(
+            strip_verbatim_prefix(r"\\?\C:\work\repo").as_deref(),
+            Some(r"C:\work\repo")
+        );
+        assert_eq!(
+            strip_verbatim_prefix(r"\\?\UNC\build01\src\repo").as_deref(),
+            Some(r"\\build01\src\repo")
+        );
+        assert_eq!(strip_verbatim_prefix(r"\\?\Volume{1234}\repo"), None);
+        assert_eq!(strip_verbatim_prefix(r"C:\work\repo"), None);
+        assert_eq!(strip_verbatim_prefix("/home/dev/repo"), None);
+    }
+
+    #[test]
+    fn relative_to_root_strips_the_root() {
+        let root = Path::new("/work/repo");
+        assert_eq!(
+            relative_to_root(root, Path::new("/work/repo/src/lib.rs")),
+            Some(PathBuf::from("src/lib.rs"))
+        );
+        assert_eq!(
+            relative_to_root(root, Path::new("/work/other/lib.rs")),
+            None
+        );
+    }
+}
@@ -0,0 +1,313 @@
+use std::collections::HashMap;
+
+use serde_json::json;
+use tree_sitter::{Node, Parser};
+
+use crate::graph::{stable_id, symbol_node_id, GraphEdge, GraphExtraction, GraphNode};
+
+const BRANCH_KINDS: &[&str] = &[
+    "if_expression",
+    "match_arm",
+    "while_expression",
+    "for_expression",
+];
+
+/// Functions, methods, and the calls between them for one Rust file.
+/// Calls resolve to a definition in the same file when the name (and the
+/// `Type::` or `self.` receiver, if any) matches one; anything else points
+/// at the global symbol node for the callee's name.
+pub(crate) fn extract_rust_graph(relative_path: &str, source: &str) -> Option<GraphExtraction> {
+    let mut parser = Parser::new();
+    parser
+        .set_language(&tree_sitter_rust::LANGUAGE.into())
+        .ok()?;
+    let tree = parser.parse(source, None)?;
+
+    let file_id = stable_id(&["file", relative_path]);
+    let mut extractor = RustGraph {
+        path: relative_path,
+        source,
+        nodes: vec![GraphNode {
+            id: file_id.clone(),
+            path: Some(relative_path.to_string()),
+            kind: "file".to_string(),
+            name: relative_path.to_string(),
+            signature: None,
+            range_start: None,
+            range_end: None,
+            metadata: None,
+        }],
+        definitions: Vec::new(),
+        calls: Vec::new(),
+    };
+    let mut file_branches = 0;
+    extractor.visit(tree.root_node(), &file_id, None, &mut file_branches);
+    Some(extractor.finish())
+}
+
+struct Definition {
+    id: String,
+    name: String,
+    owner: Option<String>,
+}
+
+enum Receiver {
+    /// `name(..)`
+    Free,
+    /// `value.name(..)` on something other than `self`.
+    Method,
+    /// `Type::name(..)`, or `self.name(..)` / `Self::name(..)` inside an impl.
+    Owner(String),
+}
+
+struct Call {
+    scope: String,
+    name: String,
+    receiver: Receiver,
+    offset: usize,
+}
+
+struct RustGraph<'src> {
+    path: &'src str,
+    source: &'src str,
+    nodes: Vec<GraphNode>,
+    definitions: Vec<Definition>,
+    calls: Vec<Call>,
+}
+
+impl<'src> RustGraph<'src> {
+    fn text(&self, node: Node) -> &'src str {
+        &self.source[node.byte_range()]
+    }
+
+    fn visit(&mut self, node: Node, scope: &str, owner: Option<&str>, branches: &mut usize) {
+        let mut cursor = node.walk();
+        for child in node.named_children(&mut cursor) {
+            match child.kind() {
+                "function_item" => {
+                    // only items directly in an impl or trait body are methods
+                    let owner = owner.filter(|_| node.kind() == "declaration_list");
+                    self.visit_function(child, owner)
+                }
+                "impl_item" => {
+                    let owner = child
+                        .child_by_field_name("type")
+                        .map(|ty| type_name(self.text(ty)));
+                    if let Some(body) = child.child_by_field_name("body") {
+                        self.visit(body, scope, owner, branches);
+                    }
+                }
+                "trait_item" => {
+                    let owner = child
+                        .child_by_field_name("name")
+                        .map(|name| self.text(name));
+                    if let Some(body) = child.child_by_field_name("body") {
+                        self.visit(body, scope, owner, branches);
+                    }
+                }
+                "mod_item" => self.visit(child, scope, None, branches),
+                kind => {
+                    if kind == "call_expression" {
+                        self.record_call(child, scope, owner);
+                    } else if BRANCH_KINDS.contains(&kind) || is_short_circuit(child, self.source) {
+                        *branches += 1;
+                    }
+                    self.visit(child, scope, owner, branches);
+                }
+            }
+        }
+    }
+
+    fn visit_function(&mut self, node: Node, owner: Option<&str>) {
+        let Some(name) = node.child_by_field_name("name").map(|name| self.text(name)) else {
+            return;
+        };
+        let parameter_count = node
+            .child_by_field_name("parameters")
+            .map(|parameters| {
+                let mut cursor = parameters.walk();
+                parameters
+                    .named_children(&mut cursor)
+                    .filter(|parameter| parameter.kind().ends_with("parameter"))
+                    .count()
+            })
+            .unwrap_or(0);
+        let is_async = {
+            let mut cursor = node.walk();
+            let modifiers = node
+                .children(&mut cursor)
+                .find(|child| child.kind() == "function_modifiers");
+            modifiers.is_some_and(|modifiers| self.text(modifiers).contains("async"))
+        };
+        let kind = if owner.is_some() {
+            "method"
+        } else {
+            "function"
+        };
+        let qualified = match owner {
+            Some(owner) => format!("{owner}::{name}"),
+            None => name.to_string(),
+        };
+        let start = node.start_byte();
+        let id = stable_id(&[kind, self.path, &qualified, &start.to_string()]);
+        let line_count = node.end_position().row - node.start_position().row + 1;
+        let index = self.nodes.len();
+        self.nodes.push(GraphNode {
+            id: id.clone(),
+            path: Some(self.path.to_string()),
+            kind: kind.to_string(),
+            name: name.to_string(),
+            signature: Some(format!("{name}({parameter_count} params)")),
+            range_start: Some(start as i64),
+            range_end: Some(node.end_byte() as i64),
+            metadata: Some(json!({
+                "async": is_async,
+                "owner": owner,
+                "metrics": {
+                    "lineCount": line_count,
+                    "parameterCount": parameter_count,
+                    "branchCount": 0,
+                },
+            })),
+        });
+        self.definitions.push(Definition {
+            id: id.clone(),
+            name: name.to_string(),
+            owner: owner.map(str::to_string),
+        });
+
+        let mut branches = 0;
+        if let Some(body) = node.child_by_field_name("body") {
+            self.visit(body, &id, owner, &mut branches);
+        }
+        if let Some(metadata) = self.nodes[index].metadata.as_mut() {
+            metadata["metrics"]["branchCount"] = json!(branches);
+        }
+    }
+
+    fn record_call(&mut self, node: Node, scope: &str, owner: Option<&str>) {
+        let Some(function) = node.child_by_field_name("function") else {
+            return;
+        };
+        let Some((name, receiver)) = self.callee(function, owner) else {
+            return;
+        };
+        self.calls.push(Call {
+            scope: scope.to_string(),
+            name: name.to_string(),
+            receiver,
+            offset: node.start_byte(),
+        });
+    }
+
+    fn callee(&self, function: Node, owner: Option<&str>) -> Option<(&'src str, Receiver)> {
+        match function.kind() {
+            "identifier" => Some((self.text(function), Receiver::Free)),
+            "scoped_identifier" => {
+                let name = self.text(function.child_by_field_name("name")?);
+                let receiver = match function
+                    .child_by_field_name("path")
+                    .map(|path| self.text(path))
+                {
+                    Some("Self") => owner.map(str::to_string),
+                    Some("self" | "super" | "crate") | None => None,
+                    Some(path) => {
+                        Some(type_name(path.rsplit("::").next().unwrap_or(path)).to_string())
+                    }
+                };
+                Some((name, receiver.map_or(Receiver::Free, Receiver::Owner)))
+            }
+            "field_expression" => {
+                let name = self.text(function.child_by_field_name("field")?);
+                let on_self = function
+                    .child_by_field_name("value")
+                    .is_some_and(|value| value.kind() == "self");
+                let receiver = match (on_self, owner) {
+                    (true, Some(owner)) => Receiver::Owner(owner.to_string()),
+                    _ => Receiver::Method,
+                };
+                Some((name, receiver))
+            }
+            "generic_function" => self.callee(function.child_by_field_name("function")?, owner),
+            _ => None,
+        }
+    }
+
+    fn resolve(&self, call: &Call) -> Option<&Definition> {
+        let mut candidates = self
+            .definitions
+            .iter()
+            .filter(|definition| definition.name == call.name);
+        match &call.receiver {
+            Receiver::Free => candidates.find(|definition| definition.owner.is_none()),
+            Receiver::Method => candidates.find(|definition| definition.owner.is_some()),
+            Receiver::Owner(owner) => {
+                candidates.find(|definition| definition.owner.as_deref() == Some(owner.as_str()))
+            }
+        }
+    }
+
+    fn finish(mut self) -> GraphExtraction {
+        let mut symbols: HashMap<String, String> = HashMap::new();
+        let mut edges = Vec::with_capacity(self.calls.len());
+        let mut symbol_nodes = Vec::new();
+        for call in &self.calls {
+            let (target_id, target_path) = match self.resolve(call) {
+                Some(definition) => (definition.id.clone(), Some(self.path.to_string())),
+                None => {
+                    let id = symbols.entry(call.name.clone()).or_insert_with(|| {
+                        let id = symbol_node_id(&call.name);
+                        symbol_nodes.push(GraphNode {
+                            id: id.clone(),
+                            path: None,
+                            kind: "symbol".to_string(),
+                            name: call.name.clone(),
+                            signature: None,
+                            range_start: None,
+                            range_end: None,
+                            metadata: None,
+                        });
+                        id
+                    });
+                    (id.clone(), None)
+                }
+            };
+            edges.push(GraphEdge {
+                id: stable_id(&[
+                    "edge",
+                    "calls",
+                    &call.scope,
+                    &target_id,
+                    &call.offset.to_string(),
+                ]),
+                source_id: call.scope.clone(),
+                target_id,
+                edge_type: "calls".to_string(),
+                source_path: Some(self.path.to_string()),
+                target_path,
+                metadata: None,
+            });
+        }
+        self.nodes.extend(symbol_nodes);
+        GraphExtraction {
+            nodes: self.nodes,
+            edges,
+        }
+    }
+}
+
+/// `Foo<T>` and `&mut Foo` both name `Foo`.
+fn type_name(text: &str) -> &str {
+    let text = text
+        .trim_start_matches('&')
+        .trim_start_matches("mut ")
+        .trim();
+    text.split('<').next().unwrap_or(text).trim()
+}
+
+fn is_short_circuit(node: Node, source: &str) -> bool {
+    node.kind() == "binary_expression"
+        && node
+            .child_by_field_name("operator")
+            .is_some_and(|operator| matches!(&source[operator.byte_range()], "&&" | "||"))
+}
@@ -0,0 +1,105 @@
+use std::env;
+use std::path::{Component, Path, PathBuf};
+
+use once_cell::sync::Lazy;
+use thiserror::Error;
+
+use crate::roots::simplify_verbatim;
+
+const ALLOWED_ROOTS_ENV: &str = "INDEX_MCP_ALLOWED_ROOTS";
+
+/// Canonical roots parsed from `INDEX_MCP_ALLOWED_ROOTS` (platform path-list
+/// syntax). `None` leaves every root permitted.
+static ALLOWED_ROOTS: Lazy<Option<Vec<PathBuf>>> = Lazy::new(|| {
+    let raw = env::var_os(ALLOWED_ROOTS_ENV)?;
+    let roots: Vec<PathBuf> = env::split_paths(&raw)
+        .filter(|path| !path.as_os_str().is_empty())
+        .map(|path| canonicalize_lossy(&path))
+        .collect();
+    if roots.is_empty() {
+        None
+    } else {
+        Some(roots)
+    }
+});
+
+#[derive(Debug, Error)]
+pub enum SandboxError {
+    #[error("root '{root}' is outside the allowed roots ({})", .allowed.join(", "))]
+    RootNotAllowed { root: String, allowed: Vec<String> },
+    #[error("path '{path}' escapes workspace root '{root}'")]
+    PathEscape { path: String, root: String },
+}
+
+/// Roots listed in `INDEX_MCP_ALLOWED_ROOTS`. They double as the workspace
+/// registry that multi-root search fans out over.
+pub(crate) fn allowed_roots() -> Option<&'static [PathBuf]> {
+    ALLOWED_ROOTS.as_deref()
+}
+
+pub(crate) fn ensure_root_allowed(root: &Path) -> Result<(), SandboxError> {
+    let Some(allowed) = ALLOWED_ROOTS.as_ref() else {
+        return Ok(());
+    };
+
+    let canonical = canonicalize_lossy(root);
+    if allowed
+        .iter()
+        .any(|candidate| canonical.starts_with(candidate))
+    {
+        return Ok(());
+    }
+
+    Err(SandboxError::RootNotAllowed {
+        root: canonical.to_string_lossy().to_string(),
+        allowed: allowed
+            .iter()
+            .map(|path| path.to_string_lossy().to_string())
+            .collect(),
+    })
+}
+
+/// Resolves `relative` under `root`, following symlinks, and refuses results
+/// that land outside the root.
+pub(crate) fn resolve_within_root(root: &Path, relative: &str) -> Result<PathBuf, SandboxError> {
+    let resolved = canonicalize_lossy(&root.join(relative));
+    if resolved.starts_with(canonicalize_lossy(root)) {
+        Ok(resolved)
+    } else {
+        Err(SandboxError::PathEscape {
+            path: relative.to_string(),
+            root: root.to_string_lossy().to_string(),
+        })
+    }
+}
+
+// canonicalizes the longest existing ancestor so paths that do not exist yet
+// (deleted files, new databases) still resolve symlinks above them
+fn canonicalize_lossy(path: &Path) -> PathBuf {
+    let normalized = normalize_lexically(path);
+    if let Ok(canonical) = normalized.canonicalize() {
+        return simplify_verbatim(canonical);
+    }
+    match (normalized.parent(), normalized.file_name()) {
+        (Some(parent), Some(name)) if !parent.as_os_str().is_empty() => {
+            canonicalize_lossy(parent).join(name)
+        }
+        _ => normalized,
+    }
+}
+
+fn normalize_lexically(path: &Path) -> PathBuf {
+    let mut normalized = PathBuf::new();
+    for component in path.components() {
+        match component {
+            Component::CurDir => {}
+            Component::ParentDir => {
+                if !normalized.pop() {
+                    normalized.push(component);
+                }
+            }
+            other => normalized.push(other),
+        }
+    }
+    normalized
+}
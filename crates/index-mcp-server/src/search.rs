@@ -1,18 +1,43 @@
-use std::collections::HashMap;
+use std::collections::{BTreeSet, HashMap, HashSet};
 use std::fs;
 use std::path::{Path, PathBuf};
-use std::str::FromStr;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Mutex;
 
-use fastembed::{EmbeddingModel, TextEmbedding, TextInitOptions};
+use once_cell::sync::Lazy;
+use regex::{Regex, RegexBuilder};
 use rmcp::schemars::{self, JsonSchema};
-use rusqlite::{params, Connection, OpenFlags};
+use rusqlite::{params, params_from_iter, Connection};
 use serde::{Deserialize, Serialize};
 use serde_json::Value;
+use sha2::{Digest, Sha256};
 use thiserror::Error;
 use tokio::task::JoinError;
 
+use crate::chunk_labels::{chunk_label_columns, has_chunk_labels};
+use crate::content_store::{content_encoding_column, StoredContentCache};
+use crate::data_dir::{database_path, open_read_only};
+use crate::embedder::{is_mock_model, load_embedder, load_embedding_endpoint, Embedder};
+use crate::embedding_store::{decode_embedding, embedding_encoding_column};
+use crate::generations::{resolve_generation, GenerationError};
+use crate::hit_counter::record_chunk_hits;
 use crate::index_status::DEFAULT_DB_FILENAME;
-use crate::ingest::DEFAULT_EMBEDDING_MODEL;
+use crate::ingest::{
+    compile_globs, file_modified_to_ms, git_stdout, IngestError, DEFAULT_EMBEDDING_MODEL,
+    META_REMOTE_URL, META_REPOSITORY_PREFIX,
+};
+use crate::integrity::{checksum_column, chunk_checksum};
+use crate::keywords::shortlist_paths;
+use crate::language_pack::pack_for_path;
+use crate::limits::{clamp_parameter, describe_adjustments, AdjustmentReason, ParameterAdjustment};
+use crate::model_registry::resolve_model_name;
+use crate::normalization::load_chunk_normalization;
+use crate::path_boosts::{AppliedPathBoost, PathBoostError, PathBoosts};
+use crate::roots::resolve_workspace_root;
+use crate::sandbox::{allowed_roots, ensure_root_allowed, resolve_within_root, SandboxError};
+use crate::text_format::decode_text;
+use crate::vector_index::probe_lists;
+use crate::workspace::{load_package_paths, CARGO_ECOSYSTEM};
 
 const DEFAULT_RESULT_LIMIT: usize = 6;
 const DEFAULT_IDENTIFIER_LIMIT: usize = 3;
@@ -20,27 +45,83 @@ const MAX_RESULT_LIMIT: usize = 50;
 const DEFAULT_CONTEXT_BEFORE: usize = 1;
 const DEFAULT_CONTEXT_AFTER: usize = 1;
 const MAX_CONTEXT_LINES: usize = 6;
+// per side; keeps scoped context near the cost of a few padded lines
+const MAX_SCOPE_CONTEXT_LINES: usize = 20;
 const MAX_BRIEF_CONTENT_CHARS: usize = 240;
 const MAX_BRIEF_CONTEXT_CHARS: usize = 160;
-
-#[derive(Debug, Deserialize, JsonSchema)]
+const LEXICAL_TERM_WEIGHT: f32 = 0.8;
+const RECENCY_HALF_LIFE_MS: f64 = 7.0 * 24.0 * 60.0 * 60.0 * 1000.0;
+const STALE_RECOMMENDATION_RATIO: f64 = 0.1;
+// graph lookups are per file, so only the hits a follow-up is likely to
+// target pay for them
+const GRAPH_SIGNAL_CANDIDATES: usize = 8;
+// below this many chunks a full scan is cheap enough that routing only risks
+// dropping files whose keywords miss the query
+const ROUTER_CHUNK_THRESHOLD: u64 = 20_000;
+const DEFAULT_ROUTER_FILES: usize = 200;
+const MAX_ROUTER_FILES: usize = 2_000;
+// candidates scored per requested result when hits fold into symbols, so
+// heavily matched functions do not crowd out the rest of the page
+const SYMBOL_GROUP_OVERFETCH: usize = 4;
+// node covering most of a chunk, then the tightest one; lambdas carry
+// synthetic names and add no context
+const ENCLOSING_SYMBOL_SQL: &str = "SELECT name, kind, signature, id FROM code_graph_nodes
+     WHERE path = ?1 AND range_start IS NOT NULL AND range_end IS NOT NULL
+       AND range_start < ?3 AND range_end > ?2
+       AND kind NOT IN ('file', 'symbol', 'lambda')
+     ORDER BY MIN(range_end, ?3) - MAX(range_start, ?2) DESC,
+              range_end - range_start ASC
+     LIMIT 1";
+
+// process-wide counters of served files whose working-tree hash no longer
+// matches the index
+static SERVED_FILE_CHECKS: AtomicU64 = AtomicU64::new(0);
+static STALE_FILE_HITS: AtomicU64 = AtomicU64::new(0);
+// working-tree hashes of served files by absolute path, reused until the
+// file's size or mtime moves
+static DISK_HASHES: Lazy<Mutex<HashMap<PathBuf, DiskHash>>> =
+    Lazy::new(|| Mutex::new(HashMap::new()));
+const MAX_CACHED_DISK_HASHES: usize = 20_000;
+
+#[derive(Debug, Clone, Deserialize, JsonSchema)]
 #[serde(rename_all = "camelCase")]
 pub struct SemanticSearchParams {
     #[serde(default)]
     pub root: Option<String>,
+    /// Search these roots concurrently instead of `root` and merge the hits.
+    #[serde(default)]
+    pub roots: Option<Vec<String>>,
+    /// Search every root registered in `INDEX_MCP_ALLOWED_ROOTS`.
+    #[serde(default)]
+    pub all_roots: Option<bool>,
+    /// Search every git worktree of `root`'s repository, each with its own
+    /// index, as a multi-root search.
+    #[serde(default)]
+    pub worktrees: Option<bool>,
     pub query: String,
     #[serde(default)]
     pub database_name: Option<String>,
     #[serde(default)]
     pub limit: Option<u32>,
+    /// Model name or alias; see `info` for the alias table.
     #[serde(default)]
     pub model: Option<String>,
+    /// Search each of these models and fuse the rankings, for databases
+    /// mid-migration between two models. Overrides `model`.
+    #[serde(default)]
+    pub models: Option<Vec<String>>,
     #[serde(default)]
     pub language: Option<String>,
     #[serde(default)]
     pub path_prefix: Option<String>,
     #[serde(default)]
     pub path_contains: Option<String>,
+    /// Only search paths matching these globs.
+    #[serde(default)]
+    pub include: Option<Vec<String>>,
+    /// Skip paths matching these globs; wins over `include`.
+    #[serde(default)]
+    pub exclude: Option<Vec<String>>,
     #[serde(default)]
     pub classification: Option<Classification>,
     #[serde(default)]
@@ -49,6 +130,47 @@ pub struct SemanticSearchParams {
     pub max_context_before: Option<u32>,
     #[serde(default)]
     pub max_context_after: Option<u32>,
+    #[serde(default)]
+    pub only_paths: Option<Vec<String>>,
+    #[serde(default)]
+    pub recency_weight: Option<f32>,
+    #[serde(default)]
+    pub package: Option<String>,
+    #[serde(default)]
+    pub include_symbols: Option<bool>,
+    #[serde(default)]
+    pub context_source: Option<ContextSource>,
+    #[serde(default)]
+    pub context_scope: Option<ContextScope>,
+    /// Shortlist this many files by their ingest-time TF-IDF keywords before
+    /// scoring chunks; 0 disables. Defaults to `DEFAULT_ROUTER_FILES` once the
+    /// index holds more than `ROUTER_CHUNK_THRESHOLD` chunks.
+    #[serde(default)]
+    pub router_files: Option<u32>,
+    #[serde(default)]
+    pub group_by: Option<GroupBy>,
+    /// Search a retained snapshot of this ingest generation instead of the
+    /// live index.
+    #[serde(default)]
+    pub generation: Option<i64>,
+    /// Search the newest retained snapshot indexed at this commit (sha,
+    /// prefix, or ref).
+    #[serde(default)]
+    pub at_commit: Option<String>,
+    /// Report how each hit was scored; currently the `INDEX_MCP_PATH_BOOSTS`
+    /// multiplier applied to it.
+    #[serde(default)]
+    pub explain: Option<bool>,
+}
+
+/// `symbol` folds hits inside the same definition into its best-ranked
+/// chunk; hits outside any graph definition are listed as usual.
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, JsonSchema, PartialEq, Eq, Default)]
+#[serde(rename_all = "camelCase")]
+pub enum GroupBy {
+    #[default]
+    Chunk,
+    Symbol,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize, JsonSchema, PartialEq, Eq)]
@@ -59,6 +181,47 @@ pub enum Classification {
     Code,
 }
 
+impl Classification {
+    pub(crate) fn as_str(&self) -> &'static str {
+        match self {
+            Self::Function => "function",
+            Self::Comment => "comment",
+            Self::Code => "code",
+        }
+    }
+
+    pub(crate) fn parse(value: &str) -> Option<Self> {
+        [Self::Function, Self::Comment, Self::Code]
+            .into_iter()
+            .find(|classification| classification.as_str() == value)
+    }
+}
+
+/// Where `contextBefore`/`contextAfter` lines come from. `auto` uses stored
+/// file content, then neighboring chunks, then the working tree; `file` skips
+/// chunk stitching and `chunks` never reads file content.
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, JsonSchema, PartialEq, Eq, Default)]
+#[serde(rename_all = "camelCase")]
+pub enum ContextSource {
+    #[default]
+    Auto,
+    File,
+    Chunks,
+}
+
+/// How far `contextBefore`/`contextAfter` reach. `lines` pads by
+/// `maxContextBefore`/`maxContextAfter`; `statement` and `function` widen to
+/// the enclosing statement or graph function instead, capped per side, and
+/// fall back to `lines` when the scope cannot be resolved.
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, JsonSchema, PartialEq, Eq, Default)]
+#[serde(rename_all = "camelCase")]
+pub enum ContextScope {
+    #[default]
+    Lines,
+    Statement,
+    Function,
+}
+
 #[derive(Debug, Clone, Copy, Serialize, Deserialize, JsonSchema, PartialEq, Eq, Default)]
 #[serde(rename_all = "camelCase")]
 pub enum SummaryMode {
@@ -70,10 +233,18 @@ pub enum SummaryMode {
 #[derive(Debug, Clone, Serialize, JsonSchema)]
 #[serde(rename_all = "camelCase")]
 pub struct SemanticSearchMatch {
+    /// Root the hit came from; set only for multi-root searches.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub root: Option<String>,
     pub path: String,
     pub chunk_index: i32,
     pub score: f32,
+    /// Similarity rescaled to 0..1 (rescaled against the best hit per root
+    /// or model in merged searches).
     pub normalized_score: f32,
+    /// What hits are ordered by: `normalizedScore` blended with
+    /// `recencyScore` and multiplied by any path boost.
+    pub rank_score: f32,
     pub language: Option<String>,
     pub classification: Classification,
     pub content: String,
@@ -84,6 +255,64 @@ pub struct SemanticSearchMatch {
     pub line_end: Option<i64>,
     pub context_before: Option<String>,
     pub context_after: Option<String>,
+    /// Scope the context was widened to; absent for plain line padding.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub context_scope: Option<ContextScope>,
+    /// Link to the hit's lines at the commit that was indexed, when the
+    /// database recorded a recognised git remote.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub permalink: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub recency_score: Option<f32>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub symbol: Option<SearchMatchSymbol>,
+    /// Symbol a doc comment chunk documents.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub documents: Option<String>,
+    /// Present for the leading hits when the index has a code graph.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub graph_signal: Option<GraphSignal>,
+    /// Lower-ranked hits in the same symbol folded into this one by
+    /// `groupBy: "symbol"`.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub merged_hits: Option<u32>,
+    /// Models that ranked this chunk in a `models` search.
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub matched_models: Vec<String>,
+    /// Other roots of a multi-root search holding the same chunk text,
+    /// folded into this hit.
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub also_in_roots: Vec<String>,
+    /// Multiplier from `INDEX_MCP_PATH_BOOSTS` folded into
+    /// `normalizedScore`; reported with `explain`.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub path_boost: Option<AppliedPathBoost>,
+    /// The file changed on disk (or was deleted) since it was indexed, so
+    /// line numbers and content may no longer match.
+    #[serde(skip_serializing_if = "std::ops::Not::not")]
+    pub stale: bool,
+}
+
+/// Code graph evidence for a hit, used to steer follow-up suggestions toward
+/// definition sites rather than call sites.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Serialize, JsonSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct GraphSignal {
+    /// A definition starts inside the hit.
+    pub defines_symbol: bool,
+    /// Definitions recorded for the hit's file.
+    pub definition_count: u32,
+    /// Call edges from other files into the file's definitions.
+    pub incoming_edges: u32,
+}
+
+#[derive(Debug, Clone, Serialize, JsonSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct SearchMatchSymbol {
+    pub name: String,
+    pub kind: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub signature: Option<String>,
 }
 
 #[derive(Debug, Clone, Serialize, JsonSchema)]
@@ -108,10 +337,119 @@ pub struct SemanticSearchResponse {
     pub embedding_model: Option<String>,
     pub total_chunks: u64,
     pub evaluated_chunks: u64,
+    /// Files the keyword router shortlisted; absent when every file was
+    /// scored.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub routed_files: Option<usize>,
     pub results: Vec<SemanticSearchMatch>,
     pub summary_mode: SummaryMode,
     #[serde(default)]
     pub suggested_tools: Vec<SuggestedTool>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub fallback: Option<SearchFallback>,
+    /// Scored against mock embeddings (`embedding.provider: "mock"`), which
+    /// reward shared words and say nothing about semantic quality.
+    #[serde(skip_serializing_if = "std::ops::Not::not")]
+    pub mock_embeddings: bool,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub freshness: Option<SearchFreshness>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub dimension_mismatch: Option<DimensionMismatch>,
+    /// Chunks skipped because their stored embedding is truncated or holds
+    /// non-finite values; verify_index lists them.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub corrupt_chunks: Option<u64>,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub adjustments: Vec<ParameterAdjustment>,
+    /// Per-root outcome of a multi-root search.
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub roots: Vec<RootSearchSummary>,
+    /// Per-model outcome of a `models` search.
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub models: Vec<ModelSearchSummary>,
+    /// Snapshot generation searched instead of the live index.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub generation: Option<i64>,
+    /// How chunks were selected for scoring; absent when nothing was scanned.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub search_strategy: Option<SearchStrategy>,
+}
+
+#[derive(Debug, Clone, Serialize, JsonSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct SearchStrategy {
+    pub kind: SearchStrategyKind,
+    /// Inverted-file lists scanned, nearest first, out of `totalLists`.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub probed_lists: Option<usize>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub total_lists: Option<usize>,
+}
+
+/// `vectorIndex` scans only the chunks filed under the centroids nearest the
+/// query; `vectorIndexFallback` means those held too few matches after
+/// filters and the remaining chunks were scanned as well.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, JsonSchema)]
+#[serde(rename_all = "camelCase")]
+pub enum SearchStrategyKind {
+    Exhaustive,
+    KeywordRouted,
+    VectorIndex,
+    VectorIndexFallback,
+}
+
+#[derive(Debug, Clone, Serialize, JsonSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct ModelSearchSummary {
+    pub model: String,
+    pub evaluated_chunks: u64,
+    pub matches: usize,
+    /// Why the model could not be searched; other models still contribute.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize, JsonSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct RootSearchSummary {
+    pub root: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub database_path: Option<String>,
+    pub evaluated_chunks: u64,
+    pub matches: usize,
+    /// Why the root could not be searched; other roots still contribute.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize, JsonSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct SearchFreshness {
+    pub checked_files: usize,
+    pub stale_paths: Vec<String>,
+    pub session_checked_files: u64,
+    pub session_stale_files: u64,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub recommendation: Option<String>,
+}
+
+/// Chunks skipped because their stored vector length differs from the query
+/// model's dimension, usually a database written by another fastembed build.
+#[derive(Debug, Clone, Serialize, JsonSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct DimensionMismatch {
+    pub expected: usize,
+    /// Distinct stored dimensions that did not match.
+    pub found: Vec<usize>,
+    pub skipped_chunks: u64,
+    pub recommendation: String,
+}
+
+#[derive(Debug, Clone, Serialize, JsonSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct SearchFallback {
+    pub mode: String,
+    pub reason: String,
 }
 
 #[derive(Debug, Error)]
@@ -126,26 +464,401 @@ pub enum SemanticSearchError {
     Sqlite(#[from] rusqlite::Error),
     #[error("embedding error: {0}")]
     Embedding(String),
+    #[error(transparent)]
+    Sandbox(#[from] SandboxError),
     #[error("blocking task panicked: {0}")]
     Join(#[from] JoinError),
-    #[error("multiple embedding models found ({available}). specify the desired model.")]
+    #[error("multiple embedding models found ({available}). specify the desired model or pass `models` to search them all.")]
     MultipleModels { available: String },
     #[error("embedding model '{requested}' not found. available models: {available}")]
     ModelNotFound {
         requested: String,
         available: String,
     },
+    #[error("allRoots requires INDEX_MCP_ALLOWED_ROOTS to list the workspace roots")]
+    NoRegisteredRoots,
+    #[error("worktrees requires '{root}' to be inside a git repository")]
+    NoWorktrees { root: String },
+    #[error(transparent)]
+    Generation(#[from] GenerationError),
+    #[error(transparent)]
+    Glob(#[from] IngestError),
+    #[error(transparent)]
+    PathBoosts(#[from] PathBoostError),
 }
 
 pub async fn semantic_search(
     params: SemanticSearchParams,
 ) -> Result<SemanticSearchResponse, SemanticSearchError> {
-    tokio::task::spawn_blocking(move || perform_semantic_search(params)).await?
+    match requested_roots(&params)? {
+        Some(roots) => search_across_roots(params, roots).await,
+        None => tokio::task::spawn_blocking(move || search_models(params)).await?,
+    }
+}
+
+fn requested_roots(
+    params: &SemanticSearchParams,
+) -> Result<Option<Vec<String>>, SemanticSearchError> {
+    let mut roots: Vec<String> = Vec::new();
+    if params.all_roots.unwrap_or(false) {
+        let registered = allowed_roots().ok_or(SemanticSearchError::NoRegisteredRoots)?;
+        roots.extend(
+            registered
+                .iter()
+                .map(|root| root.to_string_lossy().to_string()),
+        );
+    }
+    if params.worktrees.unwrap_or(false) {
+        roots.extend(list_worktrees(params.root.as_deref().unwrap_or("./"))?);
+    }
+    for root in params.roots.iter().flatten() {
+        let root = root.trim();
+        if !root.is_empty() && !roots.iter().any(|existing| existing == root) {
+            roots.push(root.to_string());
+        }
+    }
+    Ok((!roots.is_empty()).then_some(roots))
+}
+
+/// Checkouts listed by `git worktree list`, the main one first. Bare and
+/// pruned entries have no files to search and are left out.
+fn list_worktrees(root: &str) -> Result<Vec<String>, SemanticSearchError> {
+    let root_path = resolve_root(root)?;
+    let listing = git_stdout(&root_path, &["worktree", "list", "--porcelain"]).map_err(|_| {
+        SemanticSearchError::NoWorktrees {
+            root: root.to_string(),
+        }
+    })?;
+    let mut worktrees = Vec::new();
+    for entry in listing.split("\n\n") {
+        let mut lines = entry.lines();
+        let Some(path) = lines.next().and_then(|line| line.strip_prefix("worktree ")) else {
+            continue;
+        };
+        if lines.any(|line| line == "bare" || line.starts_with("prunable")) {
+            continue;
+        }
+        worktrees.push(path.to_string());
+    }
+    Ok(worktrees)
+}
+
+/// Runs the search against each root concurrently and merges the hits. Each
+/// root's scores are rescaled against its best hit first, since a root on the
+/// lexical fallback or with a different corpus size would otherwise crowd
+/// out the rest.
+async fn search_across_roots(
+    params: SemanticSearchParams,
+    roots: Vec<String>,
+) -> Result<SemanticSearchResponse, SemanticSearchError> {
+    let limit = params.limit.map_or(DEFAULT_RESULT_LIMIT, |value| {
+        (value as usize).min(MAX_RESULT_LIMIT)
+    });
+    let handles: Vec<_> = roots
+        .iter()
+        .map(|root| {
+            let mut root_params = params.clone();
+            root_params.root = Some(root.clone());
+            root_params.roots = None;
+            root_params.all_roots = None;
+            root_params.worktrees = None;
+            tokio::task::spawn_blocking(move || search_models(root_params))
+        })
+        .collect();
+
+    let mut merged: Option<SemanticSearchResponse> = None;
+    let mut summaries = Vec::with_capacity(roots.len());
+    let mut first_error = None;
+    for (root, handle) in roots.into_iter().zip(handles) {
+        let mut response = match handle.await? {
+            Ok(response) => response,
+            Err(error) => {
+                summaries.push(RootSearchSummary {
+                    root,
+                    database_path: None,
+                    evaluated_chunks: 0,
+                    matches: 0,
+                    error: Some(error.to_string()),
+                });
+                first_error.get_or_insert(error);
+                continue;
+            }
+        };
+
+        rescale_to_best_hit(&mut response.results);
+        for result in &mut response.results {
+            result.root = Some(root.clone());
+        }
+        summaries.push(RootSearchSummary {
+            root,
+            database_path: Some(response.database_path.clone()),
+            evaluated_chunks: response.evaluated_chunks,
+            matches: response.results.len(),
+            error: None,
+        });
+        merged = Some(match merged.take() {
+            Some(accumulated) => merge_root_responses(accumulated, response),
+            None => response,
+        });
+    }
+
+    let Some(mut merged) = merged else {
+        return Err(first_error.unwrap_or(SemanticSearchError::NoRegisteredRoots));
+    };
+    merged
+        .results
+        .sort_by(|left, right| right.rank_score.total_cmp(&left.rank_score));
+    merged.results = fold_cross_root_duplicates(merged.results);
+    merged.results.truncate(limit);
+    merged.roots = summaries;
+    Ok(merged)
+}
+
+/// Sibling worktrees mostly hold the same files, so the same chunk text
+/// from another root is folded into the better-ranked hit. `results` is
+/// ordered best first; hits within one root are never folded together.
+fn fold_cross_root_duplicates(results: Vec<SemanticSearchMatch>) -> Vec<SemanticSearchMatch> {
+    let mut kept: Vec<SemanticSearchMatch> = Vec::with_capacity(results.len());
+    let mut by_content: HashMap<String, Vec<usize>> = HashMap::new();
+    for result in results {
+        let key = hex::encode(Sha256::digest(result.content.as_bytes()));
+        let positions = by_content.entry(key).or_default();
+        let target = positions.iter().copied().find(|position| {
+            let hit = &kept[*position];
+            hit.root != result.root
+                && !hit
+                    .also_in_roots
+                    .iter()
+                    .any(|root| Some(root) == result.root.as_ref())
+        });
+        match (target, result.root) {
+            (Some(position), Some(root)) => kept[position].also_in_roots.push(root),
+            (_, root) => {
+                positions.push(kept.len());
+                kept.push(SemanticSearchMatch { root, ..result });
+            }
+        }
+    }
+    kept
+}
+
+fn merge_root_responses(
+    mut accumulated: SemanticSearchResponse,
+    next: SemanticSearchResponse,
+) -> SemanticSearchResponse {
+    accumulated.total_chunks += next.total_chunks;
+    accumulated.evaluated_chunks += next.evaluated_chunks;
+    accumulated.routed_files = match (accumulated.routed_files, next.routed_files) {
+        (None, None) => None,
+        (left, right) => Some(left.unwrap_or(0) + right.unwrap_or(0)),
+    };
+    accumulated.results.extend(next.results);
+    accumulated.fallback = accumulated.fallback.or(next.fallback);
+    accumulated.mock_embeddings |= next.mock_embeddings;
+    accumulated.dimension_mismatch = accumulated.dimension_mismatch.or(next.dimension_mismatch);
+    accumulated.corrupt_chunks = match (accumulated.corrupt_chunks, next.corrupt_chunks) {
+        (None, None) => None,
+        (left, right) => Some(left.unwrap_or(0) + right.unwrap_or(0)),
+    };
+    accumulated.freshness = match (accumulated.freshness, next.freshness) {
+        (Some(mut left), Some(right)) => {
+            left.checked_files += right.checked_files;
+            left.stale_paths.extend(right.stale_paths);
+            // session counters are process-wide, so the later read is current
+            left.session_checked_files = right.session_checked_files;
+            left.session_stale_files = right.session_stale_files;
+            left.recommendation = right.recommendation;
+            Some(left)
+        }
+        (left, right) => left.or(right),
+    };
+    accumulated
+}
+
+/// Searches each requested model in turn and fuses the hits: every model's
+/// scores are rescaled against its best hit, then summed per chunk, so a
+/// chunk both models rank well beats one only a single model found. Chunks
+/// are matched across models by path and line range, since each model's
+/// ingest may have chunked the file differently.
+fn search_models(
+    params: SemanticSearchParams,
+) -> Result<SemanticSearchResponse, SemanticSearchError> {
+    let mut models: Vec<String> = Vec::new();
+    for model in params.models.iter().flatten() {
+        let model = resolve_model_name(model);
+        if !model.is_empty() && !models.contains(&model) {
+            models.push(model);
+        }
+    }
+    if models.is_empty() {
+        return perform_semantic_search(params);
+    }
+
+    let limit = params.limit.map_or(DEFAULT_RESULT_LIMIT, |value| {
+        (value as usize).min(MAX_RESULT_LIMIT)
+    });
+    let mut merged: Option<SemanticSearchResponse> = None;
+    let mut fused: Vec<SemanticSearchMatch> = Vec::new();
+    let mut summaries = Vec::with_capacity(models.len());
+    let mut first_error = None;
+    for model in models {
+        let mut model_params = params.clone();
+        model_params.model = Some(model.clone());
+        model_params.models = None;
+        let mut response = match perform_semantic_search(model_params) {
+            Ok(response) => response,
+            Err(error) => {
+                summaries.push(ModelSearchSummary {
+                    model,
+                    evaluated_chunks: 0,
+                    matches: 0,
+                    error: Some(error.to_string()),
+                });
+                first_error.get_or_insert(error);
+                continue;
+            }
+        };
+
+        rescale_to_best_hit(&mut response.results);
+        let matches = response.results.len();
+        for mut result in std::mem::take(&mut response.results) {
+            let existing = fused.iter_mut().find(|hit| {
+                hit.path == result.path
+                    && hit.line_start == result.line_start
+                    && hit.line_end == result.line_end
+            });
+            match existing {
+                Some(hit) => {
+                    hit.normalized_score += result.normalized_score;
+                    hit.rank_score += result.rank_score;
+                    hit.matched_models.push(model.clone());
+                }
+                None => {
+                    result.matched_models = vec![model.clone()];
+                    fused.push(result);
+                }
+            }
+        }
+        summaries.push(ModelSearchSummary {
+            model,
+            evaluated_chunks: response.evaluated_chunks,
+            matches,
+            error: None,
+        });
+        merged = Some(match merged.take() {
+            Some(accumulated) => merge_model_responses(accumulated, response),
+            None => response,
+        });
+    }
+
+    let Some(mut merged) = merged else {
+        return Err(first_error.unwrap_or_else(|| {
+            SemanticSearchError::Embedding("no embedding model to search".to_string())
+        }));
+    };
+    fused.sort_by(|left, right| right.rank_score.total_cmp(&left.rank_score));
+    fused.truncate(limit);
+    rescale_to_best_hit(&mut fused);
+    merged.embedding_model = Some(
+        summaries
+            .iter()
+            .filter(|summary| summary.error.is_none())
+            .map(|summary| summary.model.as_str())
+            .collect::<Vec<_>>()
+            .join(", "),
+    );
+    merged.results = fused;
+    merged.models = summaries;
+    Ok(merged)
+}
+
+/// Both responses scanned the same database, so the chunk totals are not
+/// summed; only counters that differ per model are.
+fn merge_model_responses(
+    mut accumulated: SemanticSearchResponse,
+    next: SemanticSearchResponse,
+) -> SemanticSearchResponse {
+    accumulated.evaluated_chunks += next.evaluated_chunks;
+    accumulated.fallback = accumulated.fallback.or(next.fallback);
+    accumulated.mock_embeddings |= next.mock_embeddings;
+    accumulated.dimension_mismatch = accumulated.dimension_mismatch.or(next.dimension_mismatch);
+    accumulated.corrupt_chunks = match (accumulated.corrupt_chunks, next.corrupt_chunks) {
+        (None, None) => None,
+        (left, right) => Some(left.unwrap_or(0) + right.unwrap_or(0)),
+    };
+    accumulated.freshness = accumulated.freshness.or(next.freshness);
+    accumulated
+}
+
+enum QueryScorer {
+    Semantic(Vec<f32>),
+    Lexical(LexicalMatcher),
+}
+
+struct LexicalMatcher {
+    phrase: String,
+    terms: Vec<Regex>,
+}
+
+impl LexicalMatcher {
+    fn new(query: &str) -> Self {
+        // `/pattern/` queries are treated as a single case-insensitive regex
+        if let Some(pattern) = query
+            .strip_prefix('/')
+            .and_then(|rest| rest.strip_suffix('/'))
+            .filter(|pattern| !pattern.is_empty())
+        {
+            if let Ok(regex) = RegexBuilder::new(pattern).case_insensitive(true).build() {
+                return Self {
+                    phrase: String::new(),
+                    terms: vec![regex],
+                };
+            }
+        }
+
+        let terms = query
+            .split_whitespace()
+            .filter_map(|term| {
+                RegexBuilder::new(&regex::escape(term))
+                    .case_insensitive(true)
+                    .build()
+                    .ok()
+            })
+            .collect();
+
+        Self {
+            phrase: query.to_lowercase(),
+            terms,
+        }
+    }
+
+    fn score(&self, content: &str) -> f32 {
+        if self.terms.is_empty() {
+            return 0.0;
+        }
+
+        let matched = self
+            .terms
+            .iter()
+            .filter(|term| term.is_match(content))
+            .count();
+        if matched == 0 {
+            return 0.0;
+        }
+
+        let mut score = LEXICAL_TERM_WEIGHT * matched as f32 / self.terms.len() as f32;
+        if self.phrase.is_empty() || content.to_lowercase().contains(&self.phrase) {
+            score += 1.0 - LEXICAL_TERM_WEIGHT;
+        }
+        score
+    }
 }
 
 #[derive(Default)]
 struct FileEntry {
     lines: Option<Vec<String>>,
+    /// Byte offset at which each line starts, for mapping graph ranges.
+    line_starts: Vec<usize>,
 }
 
 struct PendingMatch {
@@ -159,8 +872,13 @@ struct PendingMatch {
     line_end: Option<i64>,
     embedding_model: String,
     score: f32,
+    normalized_score: f32,
+    rank_score: f32,
+    recency_score: Option<f32>,
     classification: Classification,
     language: Option<String>,
+    merged_hits: u32,
+    group_symbol: Option<SearchMatchSymbol>,
 }
 
 fn perform_semantic_search(
@@ -168,6 +886,10 @@ fn perform_semantic_search(
 ) -> Result<SemanticSearchResponse, SemanticSearchError> {
     let SemanticSearchParams {
         root,
+        roots: _,
+        all_roots: _,
+        worktrees: _,
+        models: _,
         query,
         database_name,
         limit,
@@ -175,19 +897,41 @@ fn perform_semantic_search(
         language,
         path_prefix,
         path_contains,
+        include,
+        exclude,
         classification,
         summary_mode,
         max_context_before,
         max_context_after,
+        only_paths,
+        recency_weight,
+        package,
+        include_symbols,
+        context_source,
+        context_scope,
+        router_files,
+        group_by,
+        generation,
+        at_commit,
+        explain,
     } = params;
 
-    let trimmed_query = query.trim();
+    let (scoped_query, crate_filter) = extract_crate_filter(query.trim());
+    let trimmed_query = scoped_query.as_str();
     if trimmed_query.is_empty() {
         return Ok(empty_response("", None, None));
     }
 
     let summary_mode = summary_mode.unwrap_or_default();
-    let normalized_limit = normalize_limit(limit);
+    let mut adjustments = Vec::new();
+    let normalized_limit = clamp_parameter(
+        "limit",
+        limit,
+        DEFAULT_RESULT_LIMIT as u32,
+        0,
+        MAX_RESULT_LIMIT as u32,
+        &mut adjustments,
+    ) as usize;
     let adaptive_limit = if limit.is_none() {
         let base = if is_identifier_query(trimmed_query) {
             DEFAULT_IDENTIFIER_LIMIT
@@ -200,21 +944,61 @@ fn perform_semantic_search(
     };
 
     let language_filter = language.map(|value| value.to_lowercase());
-    let context_before_lines = max_context_before
-        .map(|value| value.min(MAX_CONTEXT_LINES as u32) as usize)
-        .unwrap_or(DEFAULT_CONTEXT_BEFORE);
-    let context_after_lines = max_context_after
-        .map(|value| value.min(MAX_CONTEXT_LINES as u32) as usize)
-        .unwrap_or(DEFAULT_CONTEXT_AFTER);
+    let include = compile_globs(include.as_deref().unwrap_or_default())?;
+    let exclude = compile_globs(exclude.as_deref().unwrap_or_default())?;
+    let path_boosts = PathBoosts::from_env()?;
+    let explain = explain.unwrap_or(false);
+    let recency_weight = recency_weight
+        .filter(|value| value.is_finite())
+        .map(|value| {
+            let clamped = value.clamp(0.0, 1.0);
+            if clamped != value {
+                adjustments.push(ParameterAdjustment {
+                    parameter: "recencyWeight".to_string(),
+                    requested: f64::from(value),
+                    effective: f64::from(clamped),
+                    reason: if value > 1.0 {
+                        AdjustmentReason::AboveMaximum
+                    } else {
+                        AdjustmentReason::BelowMinimum
+                    },
+                });
+            }
+            clamped
+        });
+    let recency_weight = recency_weight.filter(|value| *value > 0.0);
+    let context_before_lines = clamp_parameter(
+        "maxContextBefore",
+        max_context_before,
+        DEFAULT_CONTEXT_BEFORE as u32,
+        0,
+        MAX_CONTEXT_LINES as u32,
+        &mut adjustments,
+    ) as usize;
+    let context_after_lines = clamp_parameter(
+        "maxContextAfter",
+        max_context_after,
+        DEFAULT_CONTEXT_AFTER as u32,
+        0,
+        MAX_CONTEXT_LINES as u32,
+        &mut adjustments,
+    ) as usize;
+    let context_source = context_source.unwrap_or_default();
+    let context_scope = context_scope.unwrap_or_default();
 
     let root_param = root.unwrap_or_else(|| "./".to_string());
     let absolute_root = resolve_root(&root_param)?;
+    ensure_root_allowed(&absolute_root)?;
     let database_name_value = database_name.unwrap_or_else(|| DEFAULT_DB_FILENAME.to_string());
-    let db_path = absolute_root.join(&database_name_value);
+    let db_path = database_path(&absolute_root, &database_name_value);
+    let snapshot = resolve_generation(&absolute_root, &db_path, generation, at_commit.as_deref())?;
+    let db_path = snapshot
+        .as_ref()
+        .map_or(db_path, |snapshot| snapshot.path.clone());
     let db_path_string = db_path.to_string_lossy().to_string();
+    let only_paths = normalize_only_paths(&absolute_root, only_paths);
 
-    let conn = Connection::open_with_flags(&db_path, OpenFlags::SQLITE_OPEN_READ_WRITE)
-        .map_err(SemanticSearchError::Sqlite)?;
+    let conn = open_read_only(&db_path).map_err(SemanticSearchError::Sqlite)?;
 
     let total_chunks: u64 = conn
         .query_row("SELECT COUNT(*) FROM file_chunks", [], |row| row.get(0))
@@ -228,107 +1012,357 @@ fn perform_semantic_search(
         ));
     }
 
+    if only_paths.as_ref().is_some_and(|paths| paths.is_empty()) {
+        return Ok(empty_response(
+            &db_path_string,
+            Some(database_name_value),
+            model,
+        ));
+    }
+
     let available_models = available_embedding_models(&conn)?;
     let requested_model = resolve_requested_model(model, &available_models)?;
 
+    let crate_paths = crate_filter
+        .as_deref()
+        .map(|name| load_package_path_set(&conn, Some(CARGO_ECOSYSTEM), name));
+    let package_paths = package
+        .as_deref()
+        .map(str::trim)
+        .filter(|name| !name.is_empty())
+        .map(|name| load_package_path_set(&conn, None, name));
+
+    let permalink_base = PermalinkBase::load(&conn);
+
+    let recency_index = match recency_weight {
+        Some(_) => Some(load_recency_index(&conn)?),
+        None => None,
+    };
+
+    let group_by_symbol = group_by.unwrap_or_default() == GroupBy::Symbol;
+    let candidate_limit = if group_by_symbol {
+        adaptive_limit.saturating_mul(SYMBOL_GROUP_OVERFETCH)
+    } else {
+        adaptive_limit
+    };
     let mut top_matches: Vec<PendingMatch> = Vec::new();
     let mut evaluated_chunks: u64 = 0;
+    let mut mismatched_chunks: u64 = 0;
+    let mut corrupt_chunks: u64 = 0;
+    let mut mismatched_dimensions: BTreeSet<usize> = BTreeSet::new();
+
+    let mut fallback_reason: Option<String> = None;
+    // queries go through the same rewrites the chunks did at ingest
+    let normalized_query = load_chunk_normalization(&conn).apply(trimmed_query);
+    let endpoint = load_embedding_endpoint(&conn);
+    let scorer = match create_embedder(&requested_model, endpoint.as_deref())
+        .and_then(|mut embedder| embed_query(&mut embedder, &normalized_query))
+    {
+        Ok(vector) => QueryScorer::Semantic(vector),
+        Err(SemanticSearchError::Embedding(message)) => {
+            fallback_reason = Some(message);
+            QueryScorer::Lexical(LexicalMatcher::new(trimmed_query))
+        }
+        Err(error) => return Err(error),
+    };
 
-    let mut stmt = conn.prepare(
-        "SELECT id, path, chunk_index, content, embedding, embedding_model, byte_start, byte_end, line_start, line_end FROM file_chunks WHERE embedding_model = ?1",
-    )?;
-
-    let mut rows = stmt.query(params![&requested_model])?;
-
-    let mut embedder = create_embedder(&requested_model)?;
-    let mut cached_query: Option<(String, Vec<f32>)> = None;
-
-    while let Some(row) = rows.next()? {
-        evaluated_chunks += 1;
-        let id: String = row.get(0)?;
-        let path: String = row.get(1)?;
-        let chunk_index: i32 = row.get(2)?;
-        let content: String = row.get(3)?;
-        let embedding_blob: Vec<u8> = row.get(4)?;
-        let embedding_model: String = row.get(5)?;
-        let byte_start: Option<i64> = row.get(6)?;
-        let byte_end: Option<i64> = row.get(7)?;
-        let line_start: Option<i64> = row.get(8)?;
-        let line_end: Option<i64> = row.get(9)?;
-
-        let classification_value = classify_snippet(&content);
+    // explicit path scopes and router sizes opt out of the vector index
+    let list_probe = match &scorer {
+        QueryScorer::Semantic(query_embedding)
+            if only_paths.is_none() && router_files.is_none() =>
+        {
+            probe_lists(&conn, &requested_model, query_embedding)
+        }
+        _ => None,
+    };
+
+    let labelled = has_chunk_labels(&conn).unwrap_or(false);
+    let mut sql = format!(
+        "SELECT id, path, chunk_index, content, embedding, embedding_model, byte_start, byte_end, line_start, line_end, {}, {}, {}, {} FROM file_chunks WHERE embedding_model = ?1",
+        content_encoding_column(&conn, "file_chunks"),
+        checksum_column(&conn),
+        embedding_encoding_column(&conn),
+        chunk_label_columns(labelled)
+    );
+    // explicit path scopes already narrow the scan
+    let routed_paths = match (&only_paths, &list_probe) {
+        (Some(_), _) | (None, Some(_)) => None,
+        (None, None) => {
+            let router_limit = router_files.map(|value| value as usize).unwrap_or(
+                if total_chunks > ROUTER_CHUNK_THRESHOLD {
+                    DEFAULT_ROUTER_FILES
+                } else {
+                    0
+                },
+            );
+            shortlist_paths(&conn, trimmed_query, router_limit.min(MAX_ROUTER_FILES))
+        }
+    };
+    let routed_files = routed_paths.as_ref().map(Vec::len);
+    let only_paths = only_paths.or(routed_paths);
+    let mut bindings = vec![requested_model.clone()];
+    if let Some(paths) = &only_paths {
+        let placeholders = (0..paths.len())
+            .map(|offset| format!("?{}", offset + 2))
+            .collect::<Vec<_>>()
+            .join(", ");
+        sql.push_str(&format!(" AND path IN ({placeholders})"));
+        bindings.extend(paths.iter().cloned());
+    }
+    // unlabelled rows predate the columns and are classified below instead
+    if labelled {
         if let Some(required) = &classification {
-            if &classification_value != required {
-                continue;
-            }
+            bindings.push(required.as_str().to_string());
+            sql.push_str(&format!(
+                " AND (classification IS NULL OR classification = ?{})",
+                bindings.len()
+            ));
         }
+        if let Some(required) = &language_filter {
+            bindings.push(required.clone());
+            sql.push_str(&format!(
+                " AND (classification IS NULL OR lower(language) = ?{})",
+                bindings.len()
+            ));
+        }
+    }
 
-        if let Some(prefix) = &path_prefix {
-            if !path.starts_with(prefix) {
-                continue;
-            }
+    let mut search_strategy = SearchStrategy {
+        kind: if routed_files.is_some() {
+            SearchStrategyKind::KeywordRouted
+        } else {
+            SearchStrategyKind::Exhaustive
+        },
+        probed_lists: None,
+        total_lists: None,
+    };
+    // the nearest lists first; the rest only when they held too few hits
+    let passes = match &list_probe {
+        Some(probe) => {
+            search_strategy.kind = SearchStrategyKind::VectorIndex;
+            search_strategy.probed_lists = Some(probe.lists.len());
+            search_strategy.total_lists = Some(probe.total_lists);
+            let lists = probe
+                .lists
+                .iter()
+                .map(i64::to_string)
+                .collect::<Vec<_>>()
+                .join(", ");
+            let member = format!(
+                "id IN (SELECT chunk_id FROM chunk_lists WHERE embedding_model = ?1 AND list IN ({lists}))"
+            );
+            vec![format!(" AND {member}"), format!(" AND NOT {member}")]
         }
+        None => vec![String::new()],
+    };
 
-        if let Some(fragment) = &path_contains {
-            if !path.contains(fragment) {
-                continue;
+    let mut stored_content = StoredContentCache::new(&conn)?;
+
+    for (pass, filter) in passes.iter().enumerate() {
+        if pass > 0 {
+            if top_matches.len() >= candidate_limit {
+                break;
             }
+            search_strategy.kind = SearchStrategyKind::VectorIndexFallback;
         }
+        let mut stmt = conn.prepare(&format!("{sql}{filter}"))?;
+        let mut rows = stmt.query(params_from_iter(bindings.iter()))?;
+
+        while let Some(row) = rows.next()? {
+            evaluated_chunks += 1;
+            let id: String = row.get(0)?;
+            let path: String = row.get(1)?;
+            let chunk_index: i32 = row.get(2)?;
+            let content: String = row.get(3)?;
+            let embedding_blob: Vec<u8> = row.get(4)?;
+            let embedding_model: String = row.get(5)?;
+            let byte_start: Option<i64> = row.get(6)?;
+            let byte_end: Option<i64> = row.get(7)?;
+            let line_start: Option<i64> = row.get(8)?;
+            let line_end: Option<i64> = row.get(9)?;
+            let encoding: Option<String> = row.get(10)?;
+            let checksum: Option<String> = row.get(11)?;
+            let embedding_encoding: Option<String> = row.get(12)?;
+            let stored_language: Option<String> = row.get(13)?;
+            let stored_classification: Option<String> = row.get(14)?;
+            let content = stored_content.resolve_chunk(
+                &path,
+                content,
+                encoding.as_deref(),
+                byte_start,
+                byte_end,
+            );
 
-        let detected_language = detect_language(&path);
-        if let Some(required_lang) = &language_filter {
-            match detected_language.as_ref().map(|value| value.to_lowercase()) {
-                Some(ref lang) if lang == required_lang => {}
-                Some(_) => continue,
-                None => continue,
+            let (classification_value, detected_language) = match stored_classification
+                .as_deref()
+                .and_then(Classification::parse)
+            {
+                Some(stored) => (stored, stored_language),
+                None => (
+                    classify_chunk(&path, chunk_index, &content),
+                    detect_language(&path),
+                ),
+            };
+            if let Some(required) = &classification {
+                if &classification_value != required {
+                    continue;
+                }
             }
-        }
 
-        let chunk_embedding = blob_to_vec(&embedding_blob);
-        if chunk_embedding.is_empty() {
-            continue;
-        }
+            if let Some(prefix) = &path_prefix {
+                if !path.starts_with(prefix) {
+                    continue;
+                }
+            }
 
-        let query_embedding = if let Some((cached_text, cached_vector)) = &cached_query {
-            if cached_text == trimmed_query {
-                cached_vector.clone()
-            } else {
-                let vector = embed_query(&mut embedder, trimmed_query)?;
-                cached_query = Some((trimmed_query.to_string(), vector.clone()));
-                vector
+            if let Some(fragment) = &path_contains {
+                if !path.contains(fragment) {
+                    continue;
+                }
             }
-        } else {
-            let vector = embed_query(&mut embedder, trimmed_query)?;
-            cached_query = Some((trimmed_query.to_string(), vector.clone()));
-            vector
-        };
 
-        let score = dot_product(&query_embedding, &chunk_embedding);
+            if include.as_ref().is_some_and(|globs| !globs.is_match(&path))
+                || exclude.as_ref().is_some_and(|globs| globs.is_match(&path))
+            {
+                continue;
+            }
 
-        insert_into_top_matches(
-            &mut top_matches,
-            PendingMatch {
-                id,
-                path,
-                chunk_index,
-                content,
-                byte_start,
-                byte_end,
-                line_start,
-                line_end,
-                embedding_model,
-                score,
-                classification: classification_value,
-                language: detected_language,
-            },
-            adaptive_limit,
-        );
+            if let Some(paths) = &crate_paths {
+                if !paths.contains(&path) {
+                    continue;
+                }
+            }
+
+            if let Some(paths) = &package_paths {
+                if !paths.contains(&path) {
+                    continue;
+                }
+            }
+
+            if let Some(required_lang) = &language_filter {
+                match detected_language.as_ref().map(|value| value.to_lowercase()) {
+                    Some(ref lang) if lang == required_lang => {}
+                    Some(_) => continue,
+                    None => continue,
+                }
+            }
+
+            let (score, normalized_score) = match &scorer {
+                QueryScorer::Semantic(query_embedding) => {
+                    // decoded only for chunks that survived the filters
+                    let chunk_embedding =
+                        decode_embedding(&embedding_blob, embedding_encoding.as_deref());
+                    if chunk_embedding.is_empty() {
+                        // a blob that does not decode was cut short mid-write
+                        if !embedding_blob.is_empty() {
+                            corrupt_chunks += 1;
+                        }
+                        continue;
+                    }
+                    if chunk_embedding.len() != query_embedding.len() {
+                        // a blob truncated on a value boundary also looks like
+                        // another model's dimension; the checksum tells them apart
+                        let truncated = checksum.as_deref().is_some_and(|expected| {
+                            chunk_checksum(&embedding_blob, &content) != expected
+                        });
+                        if truncated {
+                            corrupt_chunks += 1;
+                        } else {
+                            mismatched_chunks += 1;
+                            mismatched_dimensions.insert(chunk_embedding.len());
+                        }
+                        continue;
+                    }
+                    let score = dot_product(query_embedding, &chunk_embedding);
+                    if !score.is_finite() {
+                        corrupt_chunks += 1;
+                        continue;
+                    }
+                    (score, normalize_score(score))
+                }
+                QueryScorer::Lexical(matcher) => {
+                    let score = matcher.score(&content);
+                    if score <= 0.0 {
+                        continue;
+                    }
+                    (score, score)
+                }
+            };
+
+            let recency_score = recency_index.as_ref().map(|index| index.score_for(&path));
+            let rank_score = match (recency_weight, recency_score) {
+                (Some(weight), Some(recency)) => {
+                    (1.0 - weight) * normalized_score + weight * recency
+                }
+                _ => normalized_score,
+            } * path_boosts.multiplier_for(&path);
+
+            insert_into_top_matches(
+                &mut top_matches,
+                PendingMatch {
+                    id,
+                    path,
+                    chunk_index,
+                    content,
+                    byte_start,
+                    byte_end,
+                    line_start,
+                    line_end,
+                    embedding_model,
+                    score,
+                    normalized_score,
+                    rank_score,
+                    recency_score,
+                    classification: classification_value,
+                    language: detected_language,
+                    merged_hits: 0,
+                    group_symbol: None,
+                },
+                candidate_limit,
+            );
+        }
+    }
+    if group_by_symbol {
+        top_matches = group_matches_by_symbol(&conn, top_matches, adaptive_limit);
     }
 
     let mut file_cache: HashMap<String, FileEntry> = HashMap::new();
-    let mut file_stmt = conn.prepare("SELECT content FROM files WHERE path = ?1")?;
-    let mut update_stmt =
-        conn.prepare("UPDATE file_chunks SET hits = COALESCE(hits, 0) + 1 WHERE id = ?1")?;
+    let mut hash_stmt = conn.prepare("SELECT hash, size, modified FROM files WHERE path = ?1")?;
+    let mut neighbor_stmt = conn.prepare(&format!(
+        "SELECT content, {}, byte_start, byte_end, line_start FROM file_chunks
+         WHERE path = ?1 AND embedding_model = ?2 AND chunk_index IN (?3, ?4)
+           AND chunk_index >= 0
+         ORDER BY chunk_index",
+        content_encoding_column(&conn, "file_chunks")
+    ))?;
+    let mut stale_by_path: HashMap<String, bool> = HashMap::new();
+    let mut served_ids = Vec::new();
+    let mut symbol_stmt = if include_symbols.unwrap_or(false) {
+        conn.prepare(ENCLOSING_SYMBOL_SQL).ok()
+    } else {
+        None
+    };
+
+    // tightest function node enclosing the chunk's byte range
+    let mut function_stmt = if context_scope == ContextScope::Function {
+        conn.prepare(
+            "SELECT range_start, range_end FROM code_graph_nodes
+             WHERE path = ?1 AND kind IN ('function', 'method', 'constructor')
+               AND range_start <= ?2 AND range_end >= ?3
+             ORDER BY range_end - range_start ASC
+             LIMIT 1",
+        )
+        .ok()
+    } else {
+        None
+    };
+
+    let mut graph_stmts = prepare_graph_signal_statements(&conn);
+    // absent in databases ingested before doc chunks existed
+    let mut documents_stmt = conn
+        .prepare("SELECT symbol FROM chunk_doc_symbols WHERE chunk_id = ?1")
+        .ok();
+    let mut file_graph_stats: HashMap<String, (u32, u32)> = HashMap::new();
 
     let mut results = Vec::new();
     for pending in top_matches.into_iter().rev() {
@@ -343,20 +1377,120 @@ fn perform_semantic_search(
             line_end,
             embedding_model,
             score,
+            normalized_score,
+            rank_score,
+            recency_score,
             classification,
             language,
+            merged_hits,
+            group_symbol,
         } = pending;
 
-        let file_entry = load_file_entry(&mut file_cache, &absolute_root, &mut file_stmt, &path)?;
-        let (context_before, context_after) = extract_context(
-            file_entry.lines.as_ref(),
-            line_start,
-            line_end,
-            context_before_lines,
-            context_after_lines,
-        );
+        let stale = *stale_by_path
+            .entry(path.clone())
+            .or_insert_with(|| is_file_stale(&mut hash_stmt, &absolute_root, &path));
+
+        let scoped = match context_scope {
+            ContextScope::Lines => None,
+            // scopes are resolved against file lines, which chunks mode never reads
+            _ if context_source == ContextSource::Chunks => None,
+            scope => {
+                let file_entry =
+                    load_file_entry(&mut file_cache, &absolute_root, &mut stored_content, &path)?;
+                scoped_context(
+                    file_entry,
+                    scope,
+                    function_stmt.as_mut(),
+                    &path,
+                    (byte_start, byte_end),
+                    (line_start, line_end),
+                )
+            }
+        };
+        let applied_scope = scoped.is_some().then_some(context_scope);
+
+        let stitch_neighbors = scoped.is_none()
+            && chunk_index >= 0
+            && match context_source {
+                ContextSource::Auto => stored_content.get(&path).is_none(),
+                ContextSource::File => false,
+                ContextSource::Chunks => true,
+            };
+        let mut context = scoped.unwrap_or((None, None));
+        if stitch_neighbors {
+            context = stitch_neighbor_context(
+                &mut neighbor_stmt,
+                &mut stored_content,
+                &path,
+                &embedding_model,
+                chunk_index,
+                line_start,
+                line_end,
+                context_before_lines,
+                context_after_lines,
+            );
+        }
+        let read_file = applied_scope.is_none()
+            && match context_source {
+                ContextSource::Auto => context == (None, None),
+                ContextSource::File => true,
+                ContextSource::Chunks => false,
+            };
+        if read_file {
+            let file_entry =
+                load_file_entry(&mut file_cache, &absolute_root, &mut stored_content, &path)?;
+            context = extract_context(
+                file_entry.lines.as_ref(),
+                line_start,
+                line_end,
+                context_before_lines,
+                context_after_lines,
+            );
+        }
+        let (context_before, context_after) = context;
 
-        update_stmt.execute(params![&id])?;
+        let documents = match documents_stmt.as_mut() {
+            Some(stmt) if chunk_index < 0 => stmt.query_row(params![&id], |row| row.get(0)).ok(),
+            _ => None,
+        };
+        served_ids.push(id);
+
+        let symbol = match (symbol_stmt.as_mut(), byte_start, byte_end) {
+            (Some(stmt), Some(start), Some(end)) => stmt
+                .query_row(params![&path, start, end], |row| {
+                    Ok(SearchMatchSymbol {
+                        name: row.get(0)?,
+                        kind: row.get(1)?,
+                        signature: row.get(2)?,
+                    })
+                })
+                .ok(),
+            _ => None,
+        };
+
+        let graph_signal = match graph_stmts.as_mut() {
+            Some((file_stmt, defines_stmt)) if results.len() < GRAPH_SIGNAL_CANDIDATES => {
+                if !file_graph_stats.contains_key(&path) {
+                    let stats = file_stmt
+                        .query_row(params![&path], |row| Ok((row.get(0)?, row.get(1)?)))
+                        .unwrap_or((0, 0));
+                    file_graph_stats.insert(path.clone(), stats);
+                }
+                let (definition_count, incoming_edges) = file_graph_stats[&path];
+                let defines_symbol = match (byte_start, byte_end) {
+                    (Some(start), Some(end)) => defines_stmt
+                        .query_row(params![&path, start, end], |row| row.get(0))
+                        .unwrap_or(false),
+                    _ => false,
+                };
+                Some(GraphSignal {
+                    defines_symbol,
+                    definition_count,
+                    incoming_edges,
+                })
+            }
+            _ => None,
+        };
 
         let final_content = match summary_mode {
             SummaryMode::Brief => trim_with_ellipsis(&content, MAX_BRIEF_CONTENT_CHARS),
@@ -373,10 +1507,12 @@ fn perform_semantic_search(
         }
 
         results.push(SemanticSearchMatch {
+            root: None,
             path: path.clone(),
             chunk_index,
             score,
-            normalized_score: normalize_score(score),
+            normalized_score,
+            rank_score,
             language,
             classification,
             content: final_content,
@@ -387,18 +1523,252 @@ fn perform_semantic_search(
             line_end,
             context_before: before_context,
             context_after: after_context,
+            context_scope: applied_scope,
+            permalink: permalink_base
+                .as_ref()
+                .map(|base| base.link(&path, line_start, line_end)),
+            recency_score,
+            symbol: symbol.or(group_symbol),
+            documents,
+            graph_signal,
+            merged_hits: (merged_hits > 0).then_some(merged_hits),
+            matched_models: Vec::new(),
+            also_in_roots: Vec::new(),
+            path_boost: explain.then(|| path_boosts.boost_for(&path)).flatten(),
+            stale,
         });
     }
+    // snapshots are frozen; hits belong to the live index
+    if snapshot.is_none() {
+        record_chunk_hits(&db_path, served_ids);
+    }
+
+    let dimension_mismatch = match &scorer {
+        QueryScorer::Semantic(query_embedding) if mismatched_chunks > 0 => {
+            Some(DimensionMismatch {
+                expected: query_embedding.len(),
+                found: mismatched_dimensions.into_iter().collect(),
+                skipped_chunks: mismatched_chunks,
+                recommendation: format!(
+                    "Rebuild the index: delete {db_path_string} (or pass a new databaseName) and run ingest_codebase. Re-ingesting in place keeps the old vectors for unchanged files."
+                ),
+            })
+        }
+        _ => None,
+    };
 
     Ok(SemanticSearchResponse {
         database_path: db_path_string,
         database_name: Some(database_name_value),
+        mock_embeddings: is_mock_model(&requested_model),
         embedding_model: Some(requested_model),
         total_chunks,
         evaluated_chunks,
+        routed_files,
         results,
         summary_mode,
         suggested_tools: Vec::new(),
+        fallback: fallback_reason.map(|reason| SearchFallback {
+            mode: "lexical".to_string(),
+            reason,
+        }),
+        freshness: record_freshness(stale_by_path),
+        dimension_mismatch,
+        corrupt_chunks: (corrupt_chunks > 0).then_some(corrupt_chunks),
+        adjustments,
+        roots: Vec::new(),
+        models: Vec::new(),
+        generation: snapshot.map(|snapshot| snapshot.generation),
+        search_strategy: Some(search_strategy),
+    })
+}
+
+/// Statements for per-file definition and incoming edge counts and for
+/// whether a definition starts inside a byte range. `None` when the database
+/// has no code graph.
+fn prepare_graph_signal_statements(
+    conn: &Connection,
+) -> Option<(rusqlite::Statement<'_>, rusqlite::Statement<'_>)> {
+    // calls into other files target name-keyed symbol nodes, so incoming
+    // edges are matched by the names this file defines
+    let file_stmt = conn
+        .prepare(
+            "SELECT
+                 (SELECT COUNT(*) FROM code_graph_nodes
+                  WHERE path = ?1 AND kind NOT IN ('file', 'symbol', 'lambda')),
+                 (SELECT COUNT(*) FROM code_graph_edges e
+                  JOIN code_graph_nodes t ON t.id = e.target_id
+                  WHERE COALESCE(e.source_path, '') != ?1
+                    AND (t.path = ?1 OR (t.path IS NULL AND t.name IN (
+                        SELECT name FROM code_graph_nodes
+                        WHERE path = ?1 AND kind NOT IN ('file', 'symbol', 'lambda')))))",
+        )
+        .ok()?;
+    let defines_stmt = conn
+        .prepare(
+            "SELECT EXISTS(
+                 SELECT 1 FROM code_graph_nodes
+                 WHERE path = ?1 AND range_start >= ?2 AND range_start < ?3
+                   AND kind NOT IN ('file', 'symbol', 'lambda'))",
+        )
+        .ok()?;
+    Some((file_stmt, defines_stmt))
+}
+
+#[derive(Debug)]
+struct DiskHash {
+    size: u64,
+    modified_ms: i64,
+    hash: String,
+}
+
+/// A stat matching the indexed size and mtime counts as fresh; otherwise the
+/// file is hashed, once per size and mtime it is seen with.
+fn is_file_stale(stmt: &mut rusqlite::Statement<'_>, root: &Path, path: &str) -> bool {
+    let Ok((stored_hash, stored_size, stored_modified)) = stmt.query_row(params![path], |row| {
+        Ok((
+            row.get::<_, String>(0)?,
+            row.get::<_, i64>(1)?,
+            row.get::<_, i64>(2)?,
+        ))
+    }) else {
+        return false;
+    };
+    let Some((full_path, metadata)) = resolve_within_root(root, path)
+        .ok()
+        .and_then(|full_path| fs::metadata(&full_path).ok().map(|meta| (full_path, meta)))
+    else {
+        // deleted since the last ingest
+        return true;
+    };
+    let size = metadata.len();
+    let modified_ms = file_modified_to_ms(&metadata);
+    if size as i64 == stored_size && modified_ms == stored_modified {
+        return false;
+    }
+    match disk_hash(&full_path, size, modified_ms) {
+        Some(hash) => hash != stored_hash,
+        None => true,
+    }
+}
+
+fn disk_hash(full_path: &Path, size: u64, modified_ms: i64) -> Option<String> {
+    if let Ok(cache) = DISK_HASHES.lock() {
+        if let Some(cached) = cache.get(full_path) {
+            if cached.size == size && cached.modified_ms == modified_ms {
+                return Some(cached.hash.clone());
+            }
+        }
+    }
+    let hash = hex::encode(Sha256::digest(fs::read(full_path).ok()?));
+    if let Ok(mut cache) = DISK_HASHES.lock() {
+        if cache.len() >= MAX_CACHED_DISK_HASHES {
+            cache.clear();
+        }
+        cache.insert(
+            full_path.to_path_buf(),
+            DiskHash {
+                size,
+                modified_ms,
+                hash: hash.clone(),
+            },
+        );
+    }
+    Some(hash)
+}
+
+/// Remote, commit, and repository prefix recorded by the last ingest.
+struct PermalinkBase {
+    remote_url: String,
+    commit_sha: String,
+    prefix: String,
+}
+
+impl PermalinkBase {
+    fn load(conn: &Connection) -> Option<Self> {
+        let meta = |key: &str| -> Option<String> {
+            conn.query_row(
+                "SELECT value FROM meta WHERE key = ?1",
+                params![key],
+                |row| row.get(0),
+            )
+            .ok()
+        };
+        Some(Self {
+            remote_url: meta(META_REMOTE_URL)?.trim_end_matches('/').to_string(),
+            commit_sha: meta("commit_sha")?,
+            prefix: meta(META_REPOSITORY_PREFIX).unwrap_or_default(),
+        })
+    }
+
+    // GitHub-style blob URLs unless the host is known to differ
+    fn link(&self, path: &str, line_start: Option<i64>, line_end: Option<i64>) -> String {
+        let Self {
+            remote_url,
+            commit_sha,
+            prefix,
+        } = self;
+        let start = line_start.filter(|line| *line > 0);
+        let end = line_end.filter(|line| Some(*line) > start);
+        if remote_url.contains("gitlab") {
+            let anchor = match (start, end) {
+                (Some(start), Some(end)) => format!("#L{start}-{end}"),
+                (Some(start), None) => format!("#L{start}"),
+                _ => String::new(),
+            };
+            format!("{remote_url}/-/blob/{commit_sha}/{prefix}{path}{anchor}")
+        } else if remote_url.contains("bitbucket") {
+            let anchor = match (start, end) {
+                (Some(start), Some(end)) => format!("#lines-{start}:{end}"),
+                (Some(start), None) => format!("#lines-{start}"),
+                _ => String::new(),
+            };
+            format!("{remote_url}/src/{commit_sha}/{prefix}{path}{anchor}")
+        } else {
+            let anchor = match (start, end) {
+                (Some(start), Some(end)) => format!("#L{start}-L{end}"),
+                (Some(start), None) => format!("#L{start}"),
+                _ => String::new(),
+            };
+            format!("{remote_url}/blob/{commit_sha}/{prefix}{path}{anchor}")
+        }
+    }
+}
+
+fn record_freshness(stale_by_path: HashMap<String, bool>) -> Option<SearchFreshness> {
+    if stale_by_path.is_empty() {
+        return None;
+    }
+
+    let checked_files = stale_by_path.len();
+    let mut stale_paths: Vec<String> = stale_by_path
+        .into_iter()
+        .filter_map(|(path, stale)| stale.then_some(path))
+        .collect();
+    stale_paths.sort();
+
+    let session_checked_files = SERVED_FILE_CHECKS
+        .fetch_add(checked_files as u64, Ordering::Relaxed)
+        + checked_files as u64;
+    let session_stale_files = STALE_FILE_HITS
+        .fetch_add(stale_paths.len() as u64, Ordering::Relaxed)
+        + stale_paths.len() as u64;
+
+    let stale_ratio = session_stale_files as f64 / session_checked_files as f64;
+    let recommendation = (session_stale_files > 0 && stale_ratio >= STALE_RECOMMENDATION_RATIO)
+        .then(|| {
+            format!(
+                "{:.0}% of served files stale — enable watch mode or re-ingest.",
+                stale_ratio * 100.0
+            )
+        });
+
+    Some(SearchFreshness {
+        checked_files,
+        stale_paths,
+        session_checked_files,
+        session_stale_files,
+        recommendation,
     })
 }
 
@@ -410,26 +1780,87 @@ fn empty_response(
     SemanticSearchResponse {
         database_path: db_path.to_string(),
         database_name,
+        mock_embeddings: model.as_deref().is_some_and(is_mock_model),
         embedding_model: model,
         total_chunks: 0,
         evaluated_chunks: 0,
+        routed_files: None,
         results: Vec::new(),
         summary_mode: SummaryMode::Brief,
         suggested_tools: Vec::new(),
+        fallback: None,
+        freshness: None,
+        dimension_mismatch: None,
+        corrupt_chunks: None,
+        adjustments: Vec::new(),
+        roots: Vec::new(),
+        models: Vec::new(),
+        generation: None,
+        search_strategy: None,
     }
 }
 
 fn resolve_root(root: &str) -> Result<PathBuf, SemanticSearchError> {
-    let candidate = PathBuf::from(root);
-    if candidate.is_absolute() {
-        return Ok(candidate);
-    }
-
-    let cwd = std::env::current_dir().map_err(|source| SemanticSearchError::InvalidRoot {
+    resolve_workspace_root(root).map_err(|source| SemanticSearchError::InvalidRoot {
         path: root.to_string(),
         source,
-    })?;
-    Ok(cwd.join(candidate))
+    })
+}
+
+/// Splits `crate:<name>` tokens out of the query so they act as a filter
+/// rather than being embedded.
+fn extract_crate_filter(query: &str) -> (String, Option<String>) {
+    let mut crate_filter = None;
+    let mut terms = Vec::new();
+    for token in query.split_whitespace() {
+        match token.strip_prefix("crate:") {
+            Some(name) if !name.is_empty() => crate_filter = Some(name.to_string()),
+            _ => terms.push(token),
+        }
+    }
+    (terms.join(" "), crate_filter)
+}
+
+fn load_package_path_set(
+    conn: &Connection,
+    ecosystem: Option<&str>,
+    name: &str,
+) -> HashSet<String> {
+    load_package_paths(conn, ecosystem, name)
+        .unwrap_or_default()
+        .into_iter()
+        .collect()
+}
+
+fn normalize_only_paths(root: &Path, paths: Option<Vec<String>>) -> Option<Vec<String>> {
+    let paths = paths?;
+    if paths.iter().all(|path| path.trim().is_empty()) {
+        return None;
+    }
+
+    let mut normalized: Vec<String> = Vec::new();
+    for raw in paths {
+        let trimmed = raw.trim();
+        if trimmed.is_empty() {
+            continue;
+        }
+        let candidate = PathBuf::from(trimmed);
+        let relative = if candidate.is_absolute() {
+            match candidate.strip_prefix(root) {
+                Ok(relative) => relative.to_string_lossy().to_string(),
+                Err(_) => continue,
+            }
+        } else {
+            trimmed.to_string()
+        };
+        let relative = relative.replace('\\', "/");
+        let relative = relative.trim_start_matches("./").to_string();
+        if !relative.is_empty() && !normalized.contains(&relative) {
+            normalized.push(relative);
+        }
+    }
+
+    Some(normalized)
 }
 
 fn available_embedding_models(conn: &Connection) -> Result<Vec<String>, SemanticSearchError> {
@@ -442,7 +1873,7 @@ fn resolve_requested_model(
     requested: Option<String>,
     available: &[String],
 ) -> Result<String, SemanticSearchError> {
-    if let Some(requested) = requested {
+    if let Some(requested) = requested.map(|name| resolve_model_name(&name)) {
         if available.iter().any(|model| model == &requested) {
             Ok(requested)
         } else {
@@ -460,15 +1891,7 @@ fn resolve_requested_model(
     }
 }
 
-fn normalize_limit(limit: Option<u32>) -> usize {
-    match limit {
-        Some(0) => 0,
-        Some(value) => value.min(MAX_RESULT_LIMIT as u32) as usize,
-        None => DEFAULT_RESULT_LIMIT,
-    }
-}
-
-fn blob_to_vec(blob: &[u8]) -> Vec<f32> {
+pub(crate) fn blob_to_vec(blob: &[u8]) -> Vec<f32> {
     if !blob.len().is_multiple_of(4) {
         return Vec::new();
     }
@@ -480,31 +1903,71 @@ fn blob_to_vec(blob: &[u8]) -> Vec<f32> {
     values
 }
 
-fn create_embedder(model_name: &str) -> Result<TextEmbedding, SemanticSearchError> {
-    let name = model_name.trim();
-    let parsed = EmbeddingModel::from_str(name).map_err(|error| {
-        SemanticSearchError::Embedding(format!("Unknown embedding model '{name}': {error}"))
-    })?;
-    let options = TextInitOptions::new(parsed).with_show_download_progress(false);
-
-    TextEmbedding::try_new(options)
-        .map_err(|error| SemanticSearchError::Embedding(error.to_string()))
+fn create_embedder(
+    model_name: &str,
+    endpoint: Option<&str>,
+) -> Result<Embedder, SemanticSearchError> {
+    load_embedder(model_name, endpoint).map_err(SemanticSearchError::Embedding)
 }
 
-fn embed_query(embedder: &mut TextEmbedding, text: &str) -> Result<Vec<f32>, SemanticSearchError> {
+fn embed_query(embedder: &mut Embedder, text: &str) -> Result<Vec<f32>, SemanticSearchError> {
     embedder
         .embed(vec![text.to_string()], None)
-        .map_err(|error| SemanticSearchError::Embedding(error.to_string()))
+        .map_err(SemanticSearchError::Embedding)
         .map(|mut vectors| vectors.pop().unwrap_or_default())
 }
 
-fn dot_product(query: &[f32], chunk: &[f32]) -> f32 {
+pub(crate) fn dot_product(query: &[f32], chunk: &[f32]) -> f32 {
     if query.len() != chunk.len() {
         return 0.0;
     }
     query.iter().zip(chunk.iter()).map(|(a, b)| a * b).sum()
 }
 
+/// Folds hits inside the same definition into the best-ranked one and keeps
+/// the best `limit` groups. `matches` is ordered worst first, as
+/// `insert_into_top_matches` leaves it, and so is the result.
+fn group_matches_by_symbol(
+    conn: &Connection,
+    matches: Vec<PendingMatch>,
+    limit: usize,
+) -> Vec<PendingMatch> {
+    let mut stmt = conn.prepare(ENCLOSING_SYMBOL_SQL).ok();
+    let mut grouped: Vec<PendingMatch> = Vec::new();
+    let mut group_positions: HashMap<String, usize> = HashMap::new();
+    for mut candidate in matches.into_iter().rev() {
+        let enclosing = match (stmt.as_mut(), candidate.byte_start, candidate.byte_end) {
+            (Some(stmt), Some(start), Some(end)) if candidate.chunk_index >= 0 => stmt
+                .query_row(params![&candidate.path, start, end], |row| {
+                    Ok((
+                        row.get::<_, String>(3)?,
+                        SearchMatchSymbol {
+                            name: row.get(0)?,
+                            kind: row.get(1)?,
+                            signature: row.get(2)?,
+                        },
+                    ))
+                })
+                .ok(),
+            _ => None,
+        };
+        match enclosing {
+            Some((node_id, _)) if group_positions.contains_key(&node_id) => {
+                grouped[group_positions[&node_id]].merged_hits += 1;
+            }
+            _ if grouped.len() >= limit => {}
+            Some((node_id, symbol)) => {
+                group_positions.insert(node_id, grouped.len());
+                candidate.group_symbol = Some(symbol);
+                grouped.push(candidate);
+            }
+            None => grouped.push(candidate),
+        }
+    }
+    grouped.reverse();
+    grouped
+}
+
 fn insert_into_top_matches(matches: &mut Vec<PendingMatch>, candidate: PendingMatch, limit: usize) {
     if limit == 0 {
         return;
@@ -512,7 +1975,7 @@ fn insert_into_top_matches(matches: &mut Vec<PendingMatch>, candidate: PendingMa
 
     let idx = matches
         .iter()
-        .position(|existing| existing.score > candidate.score)
+        .position(|existing| existing.rank_score > candidate.rank_score)
         .unwrap_or(matches.len());
     matches.insert(idx, candidate);
     if matches.len() > limit {
@@ -520,35 +1983,205 @@ fn insert_into_top_matches(matches: &mut Vec<PendingMatch>, candidate: PendingMa
     }
 }
 
+struct RecencyIndex {
+    modified: HashMap<String, i64>,
+    newest: i64,
+}
+
+impl RecencyIndex {
+    fn score_for(&self, path: &str) -> f32 {
+        let Some(modified) = self.modified.get(path) else {
+            return 0.0;
+        };
+        let age_ms = (self.newest - modified).max(0) as f64;
+        0.5f64.powf(age_ms / RECENCY_HALF_LIFE_MS) as f32
+    }
+}
+
+fn load_recency_index(conn: &Connection) -> Result<RecencyIndex, SemanticSearchError> {
+    let mut stmt = conn.prepare("SELECT path, modified FROM files")?;
+    let rows = stmt.query_map([], |row| {
+        Ok((row.get::<_, String>(0)?, row.get::<_, i64>(1)?))
+    })?;
+
+    let mut modified = HashMap::new();
+    let mut newest = 0i64;
+    for (path, value) in rows.flatten() {
+        newest = newest.max(value);
+        modified.insert(path, value);
+    }
+
+    Ok(RecencyIndex { modified, newest })
+}
+
 fn load_file_entry<'cache>(
     cache: &'cache mut HashMap<String, FileEntry>,
     root: &Path,
-    stmt: &mut rusqlite::Statement<'_>,
+    stored_content: &mut StoredContentCache<'_>,
     path: &str,
 ) -> Result<&'cache FileEntry, SemanticSearchError> {
     if !cache.contains_key(path) {
-        let content: Option<String> = stmt
-            .query_row(params![path], |row| row.get(0))
-            .unwrap_or(None);
-
-        let resolved_content = match content {
-            Some(text) => Some(text),
-            None => {
-                let full_path = root.join(path);
-                fs::read_to_string(&full_path).ok()
-            }
+        let resolved_content = match stored_content.get(path) {
+            Some(text) => Some(text.to_string()),
+            None => resolve_within_root(root, path)
+                .ok()
+                .and_then(|full_path| fs::read(full_path).ok())
+                .and_then(|bytes| decode_text(&bytes))
+                .map(|decoded| decoded.text),
         };
 
         let lines = resolved_content
             .as_ref()
             .map(|text| text.lines().map(|line| line.to_string()).collect());
+        let line_starts = resolved_content
+            .as_deref()
+            .map(|text| {
+                std::iter::once(0)
+                    .chain(text.match_indices('\n').map(|(offset, _)| offset + 1))
+                    .collect()
+            })
+            .unwrap_or_default();
 
-        cache.insert(path.to_string(), FileEntry { lines });
+        cache.insert(path.to_string(), FileEntry { lines, line_starts });
     }
 
     Ok(cache.get(path).unwrap())
 }
 
+/// Builds context lines from the chunks on either side of a match, so
+/// databases ingested without file content still get context without touching
+/// the working tree. Only lines contiguous with the match are returned.
+#[allow(clippy::too_many_arguments)]
+fn stitch_neighbor_context(
+    stmt: &mut rusqlite::Statement<'_>,
+    stored_content: &mut StoredContentCache<'_>,
+    path: &str,
+    embedding_model: &str,
+    chunk_index: i32,
+    line_start: Option<i64>,
+    line_end: Option<i64>,
+    before_padding: usize,
+    after_padding: usize,
+) -> (Option<String>, Option<String>) {
+    let (Some(line_start), Some(line_end)) = (line_start, line_end) else {
+        return (None, None);
+    };
+    if before_padding == 0 && after_padding == 0 {
+        return (None, None);
+    }
+
+    let neighbors: Vec<(Option<i64>, String)> = stmt
+        .query_map(
+            params![path, embedding_model, chunk_index - 1, chunk_index + 1],
+            |row| {
+                let encoding: Option<String> = row.get(1)?;
+                let content = stored_content.resolve_chunk(
+                    path,
+                    row.get(0)?,
+                    encoding.as_deref(),
+                    row.get(2)?,
+                    row.get(3)?,
+                );
+                Ok((row.get(4)?, content))
+            },
+        )
+        .map(|rows| rows.flatten().collect())
+        .unwrap_or_default();
+
+    let mut neighbor_lines: HashMap<i64, String> = HashMap::new();
+    for (first_line, content) in neighbors {
+        let Some(first_line) = first_line else {
+            continue;
+        };
+        for (offset, line) in content.lines().enumerate() {
+            neighbor_lines
+                .entry(first_line + offset as i64)
+                .or_insert_with(|| line.to_string());
+        }
+    }
+
+    let mut before: Vec<&str> = (1..=before_padding as i64)
+        .map(|distance| line_start - distance)
+        .map_while(|line| neighbor_lines.get(&line).map(String::as_str))
+        .collect();
+    before.reverse();
+    let after: Vec<&str> = (1..=after_padding as i64)
+        .map(|distance| line_end + distance)
+        .map_while(|line| neighbor_lines.get(&line).map(String::as_str))
+        .collect();
+
+    let join = |lines: Vec<&str>| (!lines.is_empty()).then(|| lines.join("\n"));
+    (join(before), join(after))
+}
+
+/// Context widened to the statement or function around the match. `None`
+/// means the scope could not be resolved and line padding applies instead.
+fn scoped_context(
+    entry: &FileEntry,
+    scope: ContextScope,
+    function_stmt: Option<&mut rusqlite::Statement<'_>>,
+    path: &str,
+    (byte_start, byte_end): (Option<i64>, Option<i64>),
+    (line_start, line_end): (Option<i64>, Option<i64>),
+) -> Option<(Option<String>, Option<String>)> {
+    let lines = entry.lines.as_ref()?;
+    let start = usize::try_from(line_start?).ok()?.max(1);
+    let end = usize::try_from(line_end?).ok()?.max(start);
+    if end > lines.len() {
+        return None;
+    }
+
+    let (scope_start, scope_end) = match scope {
+        ContextScope::Lines => return None,
+        ContextScope::Statement => statement_bounds(lines, start, end),
+        ContextScope::Function => {
+            let (range_start, range_end): (i64, i64) = function_stmt?
+                .query_row(params![path, byte_start?, byte_end?], |row| {
+                    Ok((row.get(0)?, row.get(1)?))
+                })
+                .ok()?;
+            let line_of = |offset: i64| {
+                let offset = usize::try_from(offset).unwrap_or(0);
+                entry
+                    .line_starts
+                    .partition_point(|line_start| *line_start <= offset)
+            };
+            (line_of(range_start), line_of(range_end - 1))
+        }
+    };
+
+    let before_begin = scope_start
+        .min(start)
+        .max(start.saturating_sub(MAX_SCOPE_CONTEXT_LINES))
+        .max(1);
+    let after_end = scope_end
+        .max(end)
+        .min(end + MAX_SCOPE_CONTEXT_LINES)
+        .min(lines.len());
+    // 1-based inclusive line span
+    let join =
+        |first: usize, last: usize| (first <= last).then(|| lines[first - 1..last].join("\n"));
+    Some((join(before_begin, start - 1), join(end + 1, after_end)))
+}
+
+// a blank line or one ending in `;`, `{` or `}` closes a statement, so
+// continuation lines on either side of the match are pulled in
+fn statement_bounds(lines: &[String], start: usize, end: usize) -> (usize, usize) {
+    let closes = |line: &str| {
+        let trimmed = line.trim_end();
+        trimmed.is_empty() || trimmed.ends_with([';', '{', '}'])
+    };
+    let mut first = start;
+    while first > 1 && !closes(&lines[first - 2]) {
+        first -= 1;
+    }
+    let mut last = end;
+    while last < lines.len() && !closes(&lines[last - 1]) {
+        last += 1;
+    }
+    (first, last)
+}
+
 fn extract_context(
     lines: Option<&Vec<String>>,
     line_start: Option<i64>,
@@ -595,6 +2228,27 @@ fn extract_context(
     (before, after)
 }
 
+/// Divides both scores by the best hit's, so results of separately searched
+/// roots or models compare on one scale.
+fn rescale_to_best_hit(results: &mut [SemanticSearchMatch]) {
+    let top_similarity = results
+        .iter()
+        .map(|result| result.normalized_score)
+        .fold(0.0_f32, f32::max);
+    let top_rank = results
+        .iter()
+        .map(|result| result.rank_score)
+        .fold(0.0_f32, f32::max);
+    for result in results {
+        if top_similarity > 0.0 {
+            result.normalized_score /= top_similarity;
+        }
+        if top_rank > 0.0 {
+            result.rank_score /= top_rank;
+        }
+    }
+}
+
 fn normalize_score(score: f32) -> f32 {
     ((score + 1.0) / 2.0).clamp(0.0, 1.0)
 }
@@ -626,7 +2280,7 @@ fn is_identifier_query(query: &str) -> bool {
         .all(|c| c.is_alphanumeric() || matches!(c, '_' | ':' | '.' | '#'))
 }
 
-fn detect_language(path: &str) -> Option<String> {
+pub(crate) fn detect_language(path: &str) -> Option<String> {
     let ext = Path::new(path)
         .extension()
         .and_then(|ext| ext.to_str())
@@ -655,6 +2309,16 @@ fn detect_language(path: &str) -> Option<String> {
     }
 }
 
+pub(crate) fn classify_chunk(path: &str, chunk_index: i32, content: &str) -> Classification {
+    // negative indexes mark doc comment chunks
+    if chunk_index < 0 {
+        return Classification::Comment;
+    }
+    pack_for_path(path)
+        .and_then(|pack| pack.classify(content))
+        .unwrap_or_else(|| classify_snippet(content))
+}
+
 fn classify_snippet(snippet: &str) -> Classification {
     let trimmed = snippet.trim();
     if trimmed.is_empty() {
@@ -702,17 +2366,134 @@ pub fn summarize_semantic_search(payload: &SemanticSearchResponse) -> String {
         model
     );
 
+    if let Some(routed) = payload.routed_files {
+        summary.push_str(&format!(
+            " Keyword routing shortlisted {} of the indexed file(s).",
+            routed
+        ));
+    }
+
+    if let Some(SearchStrategy {
+        kind,
+        probed_lists: Some(probed),
+        total_lists: Some(total),
+    }) = &payload.search_strategy
+    {
+        summary.push_str(&format!(
+            " Vector index probed {} of {} list(s){}.",
+            probed,
+            total,
+            if *kind == SearchStrategyKind::VectorIndexFallback {
+                ", then scanned the rest for too few matches"
+            } else {
+                ""
+            }
+        ));
+    }
+
+    if let Some(fallback) = &payload.fallback {
+        summary.push_str(&format!(
+            " Embeddings unavailable; results use a {} fallback ({}).",
+            fallback.mode, fallback.reason
+        ));
+    }
+
+    if payload.mock_embeddings && payload.fallback.is_none() {
+        summary.push_str(
+            " Scores come from mock embeddings and reflect shared words, not semantic similarity.",
+        );
+    }
+
+    if let Some(mismatch) = &payload.dimension_mismatch {
+        summary.push_str(&format!(
+            " Skipped {} chunk(s) whose stored embeddings do not match the model's {} dimensions; rebuild the index.",
+            mismatch.skipped_chunks, mismatch.expected
+        ));
+    }
+
+    if let Some(corrupt) = payload.corrupt_chunks {
+        summary.push_str(&format!(
+            " Skipped {} chunk(s) with corrupt embeddings; run verify_index.",
+            corrupt
+        ));
+    }
+
+    if !payload.roots.is_empty() {
+        let failed = payload
+            .roots
+            .iter()
+            .filter(|root| root.error.is_some())
+            .count();
+        summary.push_str(&format!(" Searched {} root(s)", payload.roots.len()));
+        if failed > 0 {
+            summary.push_str(&format!(", {} failed", failed));
+        }
+        let folded: usize = payload
+            .results
+            .iter()
+            .map(|result| result.also_in_roots.len())
+            .sum();
+        if folded > 0 {
+            summary.push_str(&format!(
+                "; {} identical hit(s) from other roots folded into alsoInRoots",
+                folded
+            ));
+        }
+        summary.push('.');
+    }
+
+    if !payload.models.is_empty() {
+        let searched = payload
+            .models
+            .iter()
+            .filter(|model| model.error.is_none())
+            .count();
+        summary.push_str(&format!(
+            " Fused rankings from {} of {} model(s).",
+            searched,
+            payload.models.len()
+        ));
+    }
+
+    if let Some(generation) = payload.generation {
+        summary.push_str(&format!(
+            " Searched the generation {} snapshot, not the live index.",
+            generation
+        ));
+    }
+
     if let Some(top) = payload.results.first() {
-        let location = match top.line_start {
+        let mut location = match top.line_start {
             Some(line) if line > 0 => format!("{}#L{}", top.path, line),
             _ => top.path.clone(),
         };
+        if let Some(root) = &top.root {
+            location = format!("{} in {}", location, root);
+        }
         summary.push_str(&format!(
             " Top hit: {} (score {:.2}).",
             location, top.normalized_score
         ));
     }
 
+    if let Some(adjusted) = describe_adjustments(&payload.adjustments) {
+        summary.push(' ');
+        summary.push_str(&adjusted);
+    }
+
+    if let Some(freshness) = &payload.freshness {
+        if !freshness.stale_paths.is_empty() {
+            summary.push_str(&format!(
+                " {} of {} served file(s) changed since indexing.",
+                freshness.stale_paths.len(),
+                freshness.checked_files
+            ));
+        }
+        if let Some(recommendation) = &freshness.recommendation {
+            summary.push_str(&format!(" {}", recommendation));
+        }
+    }
+
     if let Some(suggestion) = payload.suggested_tools.first() {
         summary.push_str(&format!(
             " Suggested follow-up: run {} with focus on {} (score {:.2}).",
@@ -0,0 +1,279 @@
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::process::Command;
+
+use rmcp::schemars::{self, JsonSchema};
+use serde::{Deserialize, Serialize};
+use thiserror::Error;
+
+use crate::data_dir::{database_path, is_read_only_media, layout, open_read_only};
+use crate::index_status::{DEFAULT_DB_FILENAME, SCHEMA_VERSION};
+use crate::roots::resolve_workspace_root;
+use crate::sandbox::{ensure_root_allowed, SandboxError};
+
+// fastembed's cache directory when INDEX_MCP_DATA_DIR does not relocate it
+const DEFAULT_MODEL_CACHE_DIR: &str = ".fastembed_cache";
+const LOW_DISK_BYTES: u64 = 1024 * 1024 * 1024;
+const CRITICAL_DISK_BYTES: u64 = 200 * 1024 * 1024;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize, JsonSchema)]
+#[serde(rename_all = "camelCase")]
+pub enum CheckStatus {
+    Pass,
+    Skipped,
+    Warn,
+    Fail,
+}
+
+#[derive(Debug, Clone, Serialize, JsonSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct SelfCheckItem {
+    pub name: String,
+    pub status: CheckStatus,
+    pub detail: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub remediation: Option<String>,
+}
+
+#[derive(Debug, Deserialize, JsonSchema, Default)]
+#[serde(rename_all = "camelCase")]
+pub struct SelfCheckParams {
+    #[serde(default)]
+    pub root: Option<String>,
+    #[serde(default)]
+    pub database_name: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize, JsonSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct SelfCheckResponse {
+    /// The worst status of any check.
+    pub status: CheckStatus,
+    pub checks: Vec<SelfCheckItem>,
+}
+
+#[derive(Debug, Error)]
+pub enum SelfCheckError {
+    #[error("failed to resolve workspace root '{path}': {source}")]
+    InvalidRoot {
+        path: String,
+        #[source]
+        source: std::io::Error,
+    },
+    #[error(transparent)]
+    Sandbox(#[from] SandboxError),
+    #[error("blocking task panicked: {0}")]
+    Join(#[from] tokio::task::JoinError),
+}
+
+pub async fn self_check(params: SelfCheckParams) -> Result<SelfCheckResponse, SelfCheckError> {
+    tokio::task::spawn_blocking(move || run_self_check(params)).await?
+}
+
+/// Probes the environment the tools depend on. Run at startup so a broken
+/// setup is reported before the first tool call trips over it.
+pub(crate) fn run_self_check(params: SelfCheckParams) -> Result<SelfCheckResponse, SelfCheckError> {
+    let root = params.root.unwrap_or_else(|| "./".to_string());
+    let root_path = resolve_root(&root)?;
+    ensure_root_allowed(&root_path)?;
+    let database_path = database_path(
+        &root_path,
+        &params
+            .database_name
+            .unwrap_or_else(|| DEFAULT_DB_FILENAME.to_string()),
+    );
+
+    let checks = vec![
+        check_model_cache(),
+        check_git(&root_path),
+        check_database(&database_path),
+        check_native_extensions(),
+        check_disk_space(database_path.parent().unwrap_or(&root_path)),
+    ];
+    let status = checks
+        .iter()
+        .map(|check| check.status)
+        .max()
+        .unwrap_or(CheckStatus::Pass);
+    Ok(SelfCheckResponse { status, checks })
+}
+
+fn item(
+    name: &str,
+    status: CheckStatus,
+    detail: String,
+    remediation: Option<&str>,
+) -> SelfCheckItem {
+    SelfCheckItem {
+        name: name.to_string(),
+        status,
+        detail,
+        remediation: remediation.map(str::to_string),
+    }
+}
+
+fn check_model_cache() -> SelfCheckItem {
+    let cache = layout()
+        .map(|layout| layout.model_cache_dir.clone())
+        .unwrap_or_else(|| PathBuf::from(DEFAULT_MODEL_CACHE_DIR));
+    let populated = fs::read_dir(&cache).is_ok_and(|mut entries| entries.next().is_some());
+    if populated {
+        item(
+            "model_cache",
+            CheckStatus::Pass,
+            format!("Embedding models cached in {}", cache.display()),
+            None,
+        )
+    } else {
+        item(
+            "model_cache",
+            CheckStatus::Warn,
+            format!("No embedding models cached in {}", cache.display()),
+            Some("The first ingest or search downloads the model and needs network access; on offline hosts copy a populated cache there or set INDEX_MCP_DATA_DIR."),
+        )
+    }
+}
+
+fn check_git(root: &Path) -> SelfCheckItem {
+    match Command::new("git").arg("--version").current_dir(root).output() {
+        Ok(output) if output.status.success() => item(
+            "git",
+            CheckStatus::Pass,
+            String::from_utf8_lossy(&output.stdout).trim().to_string(),
+            None,
+        ),
+        Ok(output) => item(
+            "git",
+            CheckStatus::Warn,
+            format!(
+                "git --version failed: {}",
+                String::from_utf8_lossy(&output.stderr).trim()
+            ),
+            Some("Repair the git installation; timelines and staleness checks need it."),
+        ),
+        Err(error) => item(
+            "git",
+            CheckStatus::Warn,
+            format!("git is not available: {error}"),
+            Some("Install git and put it on PATH; repository_timeline and index staleness checks depend on it."),
+        ),
+    }
+}
+
+fn check_database(path: &Path) -> SelfCheckItem {
+    if !path.is_file() {
+        return item(
+            "database",
+            CheckStatus::Pass,
+            format!("{} not created yet", path.display()),
+            None,
+        );
+    }
+    let version = open_read_only(path)
+        .and_then(|conn| conn.query_row("PRAGMA user_version", [], |row| row.get::<_, i64>(0)));
+    match version {
+        Err(error) => item(
+            "database",
+            CheckStatus::Fail,
+            format!("{} cannot be opened: {error}", path.display()),
+            Some("Check file permissions; if the file is corrupt, delete it and run ingest_codebase again."),
+        ),
+        Ok(version) if version > SCHEMA_VERSION => item(
+            "database",
+            CheckStatus::Fail,
+            format!(
+                "{} has schema version {version}, newer than this server's {SCHEMA_VERSION}",
+                path.display()
+            ),
+            Some("Upgrade the server, or point it at a database written by this version."),
+        ),
+        Ok(version) if version < SCHEMA_VERSION => item(
+            "database",
+            CheckStatus::Warn,
+            format!(
+                "{} has schema version {version}; this server writes {SCHEMA_VERSION}",
+                path.display()
+            ),
+            Some("Run ingest_codebase to migrate it."),
+        ),
+        Ok(_) if is_read_only_media(path) => item(
+            "database",
+            CheckStatus::Warn,
+            format!("{} is on read-only media", path.display()),
+            Some("Searches work; ingest needs a writable copy."),
+        ),
+        Ok(_) => item(
+            "database",
+            CheckStatus::Pass,
+            format!("{} opens at schema version {SCHEMA_VERSION}", path.display()),
+            None,
+        ),
+    }
+}
+
+fn check_native_extensions() -> SelfCheckItem {
+    item(
+        "native_extensions",
+        CheckStatus::Skipped,
+        "This server links its extensions statically; nothing loads at runtime.".to_string(),
+        None,
+    )
+}
+
+fn check_disk_space(dir: &Path) -> SelfCheckItem {
+    let Some(available) = available_bytes(dir) else {
+        return item(
+            "disk_space",
+            CheckStatus::Skipped,
+            format!("Free space on {} could not be determined", dir.display()),
+            None,
+        );
+    };
+    let detail = format!(
+        "{} MiB free on {}",
+        available / (1024 * 1024),
+        dir.display()
+    );
+    if available < CRITICAL_DISK_BYTES {
+        item(
+            "disk_space",
+            CheckStatus::Fail,
+            detail,
+            Some("Free space or relocate databases with INDEX_MCP_DATA_DIR and INDEX_MCP_DATA_DIR_DATABASES; ingest will fail mid-write."),
+        )
+    } else if available < LOW_DISK_BYTES {
+        item(
+            "disk_space",
+            CheckStatus::Warn,
+            detail,
+            Some("Large ingests may not fit; enable autoEvict or lower maxDatabaseSizeBytes."),
+        )
+    } else {
+        item("disk_space", CheckStatus::Pass, detail, None)
+    }
+}
+
+/// Free bytes on the filesystem holding `dir`, from POSIX `df`.
+fn available_bytes(dir: &Path) -> Option<u64> {
+    let dir = dir.ancestors().find(|candidate| candidate.is_dir())?;
+    let output = Command::new("df").arg("-Pk").arg(dir).output().ok()?;
+    if !output.status.success() {
+        return None;
+    }
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    let kilobytes: u64 = stdout
+        .lines()
+        .nth(1)?
+        .split_whitespace()
+        .nth(3)?
+        .parse()
+        .ok()?;
+    Some(kilobytes * 1024)
+}
+
+fn resolve_root(root: &str) -> Result<PathBuf, SelfCheckError> {
+    resolve_workspace_root(root).map_err(|source| SelfCheckError::InvalidRoot {
+        path: root.to_string(),
+        source,
+    })
+}
@@ -1,42 +1,102 @@
 use anyhow::Result;
 use serde::{Deserialize, Serialize};
 use serde_json::{json, Map, Value};
-use std::collections::HashSet;
+use sha2::{Digest, Sha256};
+use std::collections::{BTreeMap, HashSet};
 use std::io::ErrorKind;
 use std::sync::{Arc, RwLock};
+use std::time::Instant;
 
+use crate::audit::{AuditLog, SessionHistoryParams, SessionHistoryResponse};
 use crate::bundle::{
     context_bundle, ContextBundleError, ContextBundleParams, ContextBundleResponse, LineRange,
     QuickLinkType, SnippetSource, SymbolSelector,
 };
+use crate::chunks::{
+    chunk_neighbors, ChunkNeighborsError, ChunkNeighborsParams, ChunkNeighborsResponse,
+};
+use crate::compression::{compress_structured_content, negotiate_encoding};
+use crate::coverage::{
+    coverage_report, CoverageError, CoverageReportParams, CoverageReportResponse,
+};
+use crate::data_dir::{layout, DataDirLayout};
+use crate::db_lock::DatabaseLockError;
+use crate::diff_search::{search_diffs, SearchDiffsError, SearchDiffsParams, SearchDiffsResponse};
+use crate::embedding_store::{
+    recode_embeddings, RecodeEmbeddingsError, RecodeEmbeddingsParams, RecodeEmbeddingsResponse,
+};
+use crate::explain::{explain_path, ExplainPathError, ExplainPathParams, ExplainPathResponse};
+use crate::filter_presets::{
+    fill, load_filter_presets, merge_globs, resolve_filter_preset, FilterPreset, FilterPresetError,
+};
+use crate::generations::GenerationError;
 use crate::git_timeline::{
-    repository_timeline, repository_timeline_entry_detail, RepositoryTimelineEntryLookupParams,
-    RepositoryTimelineEntryLookupResponse, RepositoryTimelineError, RepositoryTimelineParams,
-    RepositoryTimelineResponse,
+    repository_timeline, repository_timeline_entry_detail, DiffEvictionReason,
+    RepositoryTimelineEntryLookupParams, RepositoryTimelineEntryLookupResponse,
+    RepositoryTimelineError, RepositoryTimelineParams, RepositoryTimelineResponse,
+};
+use crate::graph_neighbors::{
+    graph_neighbors, GraphNeighborsError, GraphNeighborsParams, GraphNeighborsResponse,
+};
+use crate::health::{
+    check_health, record_embedder_state, watcher_status, ComponentState, DatabaseState,
+    HealthError, HealthParams, HealthResponse, WatcherStatusResponse,
 };
 use crate::index_status::{
-    get_index_status, IndexStatusError, IndexStatusParams, IndexStatusResponse,
+    get_index_status, list_databases, HealthStatus, IndexStatusError, IndexStatusParams,
+    IndexStatusResponse, ListDatabasesParams, ListDatabasesResponse, SCHEMA_VERSION,
+};
+use crate::ingest::{
+    estimate_ingest, ingest_codebase, preview_eviction, warm_up_embedder, EvictionPreviewParams,
+    EvictionPreviewResponse, EvictionTier, IngestError, IngestEstimateParams,
+    IngestEstimateResponse, IngestParams, IngestResponse, DEFAULT_EMBEDDING_MODEL,
+};
+use crate::integrity::{verify_index, VerifyIndexError, VerifyIndexParams, VerifyIndexResponse};
+use crate::language_pack::{registered_packs, LanguagePackInfo};
+use crate::language_stats::{
+    language_stats, LanguageStatsError, LanguageStatsParams, LanguageStatsResponse,
 };
-use crate::ingest::{ingest_codebase, warm_up_embedder, IngestError, IngestParams, IngestResponse};
+use crate::limits::describe_adjustments;
+use crate::log_control::{set_log_level, LogControlError, SetLogLevelParams, SetLogLevelResponse};
+use crate::model_registry::{model_aliases, ModelAlias};
+use crate::path_boosts::configured_path_boosts;
+use crate::quota::{QuotaExceeded, QuotaKind, QuotaStatus, UsageMeter};
+use crate::recommendations::recommend;
+use crate::regex_search::{regex_search, RegexSearchError, RegexSearchParams, RegexSearchResponse};
 use crate::remote_proxy::RemoteProxyRegistry;
+use crate::roots::resolve_workspace_root;
+use crate::sandbox::SandboxError;
 use crate::search::{
-    semantic_search, summarize_semantic_search, Classification, SemanticSearchError,
-    SemanticSearchMatch, SemanticSearchParams, SemanticSearchResponse, SuggestedTool, SummaryMode,
+    semantic_search, summarize_semantic_search, Classification, ContextScope, ContextSource,
+    GraphSignal, GroupBy, SemanticSearchError, SemanticSearchMatch, SemanticSearchParams,
+    SemanticSearchResponse, SuggestedTool, SummaryMode,
 };
-use tracing::warn;
+use crate::self_check::{
+    self_check, CheckStatus, SelfCheckError, SelfCheckParams, SelfCheckResponse,
+};
+use crate::skipped::{list_skipped, ListSkippedError, ListSkippedParams, ListSkippedResponse};
+use crate::suggest_excludes::{
+    suggest_excludes, SuggestExcludesError, SuggestExcludesParams, SuggestExcludesResponse,
+};
+use crate::timeline_query::{
+    timeline_query, TimelineQueryError, TimelineQueryParams, TimelineQueryResponse,
+};
+use tracing::{debug, warn};
+use url::Url;
 
 use rmcp::{
     handler::server::{
-        router::prompt::PromptRouter, router::tool::ToolRouter, wrapper::Parameters,
+        router::prompt::PromptRouter, router::tool::ToolRouter, tool::ToolCallContext,
+        wrapper::Parameters,
     },
     model::{
-        CallToolResult, Content, GetPromptRequestParam, GetPromptResult, Implementation,
-        ListPromptsResult, Meta, PaginatedRequestParam, PromptMessage, PromptMessageRole,
-        ProtocolVersion, ServerCapabilities, ServerInfo,
+        CallToolRequestParam, CallToolResult, Content, GetPromptRequestParam, GetPromptResult,
+        Implementation, ListPromptsResult, ListToolsResult, Meta, PaginatedRequestParam,
+        PromptMessage, PromptMessageRole, ProtocolVersion, Root, ServerCapabilities, ServerInfo,
     },
     schemars::JsonSchema,
-    service::RequestContext,
-    tool, tool_handler, tool_router, ErrorData as McpError, RoleServer, ServerHandler,
+    service::{NotificationContext, Peer, RequestContext},
+    tool, tool_router, ErrorData as McpError, RoleServer, ServerHandler,
 };
 
 const DEFAULT_BUNDLE_BUDGET: usize = 2_000;
@@ -44,6 +104,11 @@ const MIN_BUNDLE_BUDGET: usize = 600;
 const DEFAULT_SNIPPET_LIMIT_HINT: u32 = 2;
 const DEFAULT_SEARCH_LIMIT_HINT: u32 = 6;
 const SUGGESTED_RANGE_PADDING: u32 = 2;
+const MAX_LOOKUP_BATCH: usize = 6;
+// remaining-window thresholds below which search output is trimmed
+const LOW_CONTEXT_TOKENS: usize = 12_000;
+const CRITICAL_CONTEXT_TOKENS: usize = 4_000;
+const DEFAULT_PREVIEW_CHARS: usize = 160;
 
 #[derive(Debug, Clone, Default)]
 struct EnvironmentSnapshot {
@@ -51,6 +116,41 @@ struct EnvironmentSnapshot {
     bundle_budget_override: Option<usize>,
     remaining_context_tokens: Option<usize>,
     recent_hits: Vec<RecentHit>,
+    cwd_source: Option<EnvironmentSource>,
+    budget_source: Option<EnvironmentSource>,
+    remaining_source: Option<EnvironmentSource>,
+}
+
+/// Where an environment value came from. Values set through `set_environment`
+/// are sticky: later `_meta` payloads do not override them. A root advertised
+/// through the client's roots capability also outranks `_meta`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, JsonSchema)]
+#[serde(rename_all = "camelCase")]
+enum EnvironmentSource {
+    Meta,
+    ClientRoots,
+    SetEnvironment,
+}
+
+/// Search output limits applied while the client's window is nearly full.
+#[derive(Debug, Clone, Copy, Serialize)]
+#[serde(rename_all = "camelCase")]
+struct SearchAdaptation {
+    remaining_context_tokens: usize,
+    max_context_lines: u32,
+    preview_chars: usize,
+}
+
+impl SearchAdaptation {
+    fn clamp_context(&self, before: &mut Option<u32>, after: &mut Option<u32>) {
+        for value in [before, after] {
+            *value = value.map(|lines| lines.min(self.max_context_lines));
+        }
+    }
+}
+
+fn accepts_meta(source: Option<EnvironmentSource>) -> bool {
+    matches!(source, None | Some(EnvironmentSource::Meta))
 }
 
 impl EnvironmentSnapshot {
@@ -65,6 +165,22 @@ impl EnvironmentSnapshot {
         }
         budget.max(MIN_BUNDLE_BUDGET)
     }
+
+    fn search_adaptation(&self) -> Option<SearchAdaptation> {
+        let remaining = self.remaining_context_tokens?;
+        let (max_context_lines, preview_chars) = if remaining < CRITICAL_CONTEXT_TOKENS {
+            (0, 60)
+        } else if remaining < LOW_CONTEXT_TOKENS {
+            (1, 100)
+        } else {
+            return None;
+        };
+        Some(SearchAdaptation {
+            remaining_context_tokens: remaining,
+            max_context_lines,
+            preview_chars,
+        })
+    }
 }
 
 #[derive(Debug, Clone, Default)]
@@ -80,6 +196,14 @@ struct RecentHit {
 
 const RECENT_HIT_HISTORY: usize = 32;
 
+// multi-root hits can share relative paths, so they are keyed by root too
+fn recent_hit_path(result: &SemanticSearchMatch) -> String {
+    match &result.root {
+        Some(root) => format!("{}::{}", root, result.path),
+        None => result.path.clone(),
+    }
+}
+
 impl EnvironmentState {
     fn new() -> Self {
         Self {
@@ -107,7 +231,10 @@ impl EnvironmentState {
         let source = env_value.unwrap_or(&value);
 
         if let Some(cwd) = source.get("cwd").and_then(|v| v.as_str()) {
-            next.cwd = Some(cwd.trim().to_string());
+            if accepts_meta(next.cwd_source) {
+                next.cwd = Some(cwd.trim().to_string());
+                next.cwd_source = Some(EnvironmentSource::Meta);
+            }
         }
 
         if let Some(budget) = source
@@ -115,20 +242,100 @@ impl EnvironmentState {
             .or_else(|| source.get("budgetTokens"))
             .and_then(|v| v.as_u64())
         {
-            next.bundle_budget_override = Some(budget as usize);
+            if accepts_meta(next.budget_source) {
+                next.bundle_budget_override = Some(budget as usize);
+                next.budget_source = Some(EnvironmentSource::Meta);
+            }
         }
 
-        if let Some(usage) = source.get("tokenUsage") {
-            if let Some(remaining) = usage.get("remainingContextTokens").and_then(|v| v.as_u64()) {
+        let remaining = source
+            .get("remainingContextTokens")
+            .or_else(|| {
+                source
+                    .get("tokenUsage")
+                    .and_then(|usage| usage.get("remainingContextTokens"))
+            })
+            .and_then(|v| v.as_u64());
+        if let Some(remaining) = remaining {
+            if accepts_meta(next.remaining_source) {
                 next.remaining_context_tokens = Some(remaining as usize);
+                next.remaining_source = Some(EnvironmentSource::Meta);
             }
         }
 
-        if let Some(remaining) = source
-            .get("remainingContextTokens")
-            .and_then(|v| v.as_u64())
-        {
+        if let Ok(mut guard) = self.inner.write() {
+            *guard = next;
+        }
+    }
+
+    /// Uses the first `file://` root the client advertised as the default
+    /// root, unless `set_environment` pinned one.
+    fn apply_client_roots(&self, roots: &[Root]) {
+        let mut next = self.snapshot();
+        if next.cwd_source == Some(EnvironmentSource::SetEnvironment) {
+            return;
+        }
+        let first_root = roots.iter().find_map(|root| {
+            Url::parse(&root.uri)
+                .ok()
+                .filter(|url| url.scheme() == "file")?
+                .to_file_path()
+                .ok()
+        });
+        match first_root {
+            Some(path) => {
+                next.cwd = Some(path.to_string_lossy().to_string());
+                next.cwd_source = Some(EnvironmentSource::ClientRoots);
+            }
+            // the client withdrew its roots
+            None if next.cwd_source == Some(EnvironmentSource::ClientRoots) => {
+                next.cwd = None;
+                next.cwd_source = None;
+            }
+            None => return,
+        }
+
+        if let Ok(mut guard) = self.inner.write() {
+            *guard = next;
+        }
+    }
+
+    fn apply_overrides(&self, params: &SetEnvironmentParams) {
+        let mut next = if params.reset.unwrap_or(false) {
+            let current = self.snapshot();
+            // a reset falls back to the client's root rather than the cwd
+            let client_root =
+                (current.cwd_source == Some(EnvironmentSource::ClientRoots)).then_some(current.cwd);
+            EnvironmentSnapshot {
+                recent_hits: current.recent_hits,
+                cwd_source: client_root
+                    .is_some()
+                    .then_some(EnvironmentSource::ClientRoots),
+                cwd: client_root.flatten(),
+                ..EnvironmentSnapshot::default()
+            }
+        } else {
+            self.snapshot()
+        };
+
+        if let Some(cwd) = params.cwd.as_deref().map(str::trim) {
+            if cwd.is_empty() {
+                next.cwd = None;
+                next.cwd_source = None;
+            } else {
+                next.cwd = Some(cwd.to_string());
+                next.cwd_source = Some(EnvironmentSource::SetEnvironment);
+            }
+        }
+
+        if let Some(budget) = params.budget_tokens {
+            next.bundle_budget_override = Some(budget as usize);
+            next.budget_source = Some(EnvironmentSource::SetEnvironment);
+        }
+
+        if let Some(remaining) = params.remaining_context_tokens {
             next.remaining_context_tokens = Some(remaining as usize);
+            next.remaining_source = Some(EnvironmentSource::SetEnvironment);
         }
 
         if let Ok(mut guard) = self.inner.write() {
@@ -144,12 +351,53 @@ impl EnvironmentState {
         }
     }
 
-    fn apply_semantic_defaults(&self, params: &mut SemanticSearchRequest) {
+    fn apply_estimate_defaults(&self, params: &mut IngestEstimateParams) {
+        if params.root.is_none() {
+            if let Some(cwd) = self.snapshot().cwd {
+                params.root = Some(cwd);
+            }
+        }
+    }
+
+    fn apply_eviction_preview_defaults(&self, params: &mut EvictionPreviewParams) {
+        if params.root.is_none() {
+            if let Some(cwd) = self.snapshot().cwd {
+                params.root = Some(cwd);
+            }
+        }
+    }
+
+    fn apply_recode_embeddings_defaults(&self, params: &mut RecodeEmbeddingsParams) {
+        if params.root.is_none() {
+            if let Some(cwd) = self.snapshot().cwd {
+                params.root = Some(cwd);
+            }
+        }
+    }
+
+    fn apply_graph_neighbors_defaults(&self, params: &mut GraphNeighborsParams) {
+        if params.root.is_none() {
+            if let Some(cwd) = self.snapshot().cwd {
+                params.root = Some(cwd);
+            }
+        }
+    }
+
+    fn apply_regex_search_defaults(&self, params: &mut RegexSearchParams) {
         if params.root.is_none() {
             if let Some(cwd) = self.snapshot().cwd {
                 params.root = Some(cwd);
             }
         }
+    }
+
+    fn apply_semantic_defaults(&self, params: &mut SemanticSearchRequest) {
+        let snapshot = self.snapshot();
+        if params.root.is_none() {
+            if let Some(cwd) = snapshot.cwd.clone() {
+                params.root = Some(cwd);
+            }
+        }
         if params.limit.is_none() {
             params.limit = Some(DEFAULT_SEARCH_LIMIT_HINT);
         }
@@ -162,6 +410,12 @@ impl EnvironmentState {
         if params.max_context_after.is_none() {
             params.max_context_after = Some(1);
         }
+        if let Some(adaptation) = snapshot.search_adaptation() {
+            adaptation.clamp_context(
+                &mut params.max_context_before,
+                &mut params.max_context_after,
+            );
+        }
     }
 
     fn apply_bundle_defaults(&self, params: &mut ContextBundleParams) {
@@ -183,8 +437,9 @@ impl EnvironmentState {
     }
 
     fn apply_code_lookup_defaults(&self, params: &mut CodeLookupParams) {
+        let snapshot = self.snapshot();
         if params.root.is_none() {
-            if let Some(cwd) = self.snapshot().cwd {
+            if let Some(cwd) = snapshot.cwd.clone() {
                 params.root = Some(cwd);
             }
         }
@@ -197,6 +452,12 @@ impl EnvironmentState {
         if params.max_context_after.is_none() {
             params.max_context_after = Some(1);
         }
+        if let Some(adaptation) = snapshot.search_adaptation() {
+            adaptation.clamp_context(
+                &mut params.max_context_before,
+                &mut params.max_context_after,
+            );
+        }
     }
 
     fn deduplicate_search_results(
@@ -214,7 +475,7 @@ impl EnvironmentState {
             let mut duplicates = Vec::new();
 
             for result in results {
-                let key = (result.path.clone(), result.chunk_index);
+                let key = (recent_hit_path(&result), result.chunk_index);
                 if seen.insert(key.clone()) {
                     guard.recent_hits.push(RecentHit {
                         path: key.0,
@@ -233,7 +494,7 @@ impl EnvironmentState {
 
             if retained.is_empty() && !duplicates.is_empty() {
                 if let Some(result) = duplicates.pop() {
-                    let key = (result.path.clone(), result.chunk_index);
+                    let key = (recent_hit_path(&result), result.chunk_index);
                     guard.recent_hits.push(RecentHit {
                         path: key.0,
                         chunk_index: key.1,
@@ -285,10 +546,24 @@ impl EnvironmentState {
         if let Some(filters) = filters {
             info["filters"] = filters;
         }
+        if let Some(freshness) = &response.freshness {
+            info["staleness"] = json!({
+                "staleFiles": freshness.stale_paths.len(),
+                "checkedFiles": freshness.checked_files,
+                "sessionStaleFiles": freshness.session_stale_files,
+                "sessionCheckedFiles": freshness.session_checked_files,
+            });
+        }
         meta.insert("semanticSearch".to_string(), info);
         if let Some(remaining) = snapshot.remaining_context_tokens {
             meta.insert("remainingContextTokens".to_string(), json!(remaining));
         }
+        if let Some(adaptation) = snapshot.search_adaptation() {
+            meta.insert(
+                "contextAdaptation".to_string(),
+                serde_json::to_value(adaptation).unwrap_or_else(|_| json!({})),
+            );
+        }
         meta
     }
 
@@ -320,6 +595,36 @@ impl EnvironmentState {
     }
 }
 
+#[derive(Debug, Deserialize, JsonSchema)]
+#[serde(rename_all = "camelCase")]
+struct SetEnvironmentParams {
+    #[serde(default)]
+    cwd: Option<String>,
+    #[serde(default)]
+    budget_tokens: Option<u32>,
+    #[serde(default)]
+    remaining_context_tokens: Option<u32>,
+    #[serde(default)]
+    reset: Option<bool>,
+}
+
+#[derive(Debug, Serialize, JsonSchema)]
+#[serde(rename_all = "camelCase")]
+struct EnvironmentResponse {
+    cwd: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    cwd_source: Option<EnvironmentSource>,
+    resolved_root: String,
+    bundle_budget_override: Option<usize>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    budget_source: Option<EnvironmentSource>,
+    remaining_context_tokens: Option<usize>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    remaining_source: Option<EnvironmentSource>,
+    effective_bundle_budget: usize,
+    recent_hit_count: usize,
+}
+
 #[derive(Debug, Deserialize, JsonSchema)]
 #[serde(rename_all = "camelCase")]
 struct CodeLookupParams {
@@ -358,11 +663,23 @@ struct CodeLookupParams {
     #[serde(default)]
     classification: Option<Classification>,
     #[serde(default)]
+    package: Option<String>,
+    #[serde(default)]
+    include: Option<Vec<String>>,
+    #[serde(default)]
+    exclude: Option<Vec<String>>,
+    /// Named filter preset from INDEX_MCP_FILTER_PRESETS; explicit filters
+    /// win and `exclude` globs are merged.
+    #[serde(default)]
+    preset: Option<String>,
+    #[serde(default)]
     summary_mode: Option<SummaryMode>,
     #[serde(default)]
     max_context_before: Option<u32>,
     #[serde(default)]
     max_context_after: Option<u32>,
+    #[serde(default)]
+    batch: Option<Vec<CodeLookupParams>>,
 }
 
 #[derive(Debug, Serialize, JsonSchema)]
@@ -376,11 +693,82 @@ struct CodeLookupResponse {
     bundle_result: Option<Value>,
 }
 
+#[derive(Debug, Serialize, JsonSchema)]
+#[serde(rename_all = "camelCase")]
+struct ServerInfoResponse {
+    name: String,
+    version: String,
+    audit_log_enabled: bool,
+    /// Empty when no quotas are configured.
+    quotas: Vec<QuotaStatus>,
+    /// Set when INDEX_MCP_DATA_DIR relocates caches, logs, and databases.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    data_dir: Option<DataDirLayout>,
+    /// Compiled-in extractors and the hooks each one provides.
+    language_packs: Vec<LanguagePackInfo>,
+    /// Short names accepted wherever an embedding model is requested.
+    model_aliases: Vec<ModelAlias>,
+    /// Presets from INDEX_MCP_FILTER_PRESETS, usable as `preset`.
+    #[serde(skip_serializing_if = "BTreeMap::is_empty")]
+    filter_presets: BTreeMap<String, FilterPreset>,
+    /// Score multipliers from INDEX_MCP_PATH_BOOSTS, applied by semantic_search.
+    #[serde(skip_serializing_if = "BTreeMap::is_empty")]
+    path_boosts: BTreeMap<String, f64>,
+    diagnostics: StartupDiagnostics,
+}
+
+/// Basic facts about this server, also sent in the initialize response under
+/// `capabilities.experimental["index-mcp/diagnostics"]` so clients can log
+/// environment mismatches before calling any tool.
+#[derive(Debug, Serialize, JsonSchema)]
+#[serde(rename_all = "camelCase")]
+struct StartupDiagnostics {
+    server_version: String,
+    schema_version: i64,
+    default_model: String,
+    watcher: ComponentState,
+    /// Changes whenever the instructions text does, including the root
+    /// rendered into it.
+    instructions_hash: String,
+}
+
+const DIAGNOSTICS_CAPABILITY: &str = "index-mcp/diagnostics";
+
+#[derive(Debug, Serialize, JsonSchema)]
+#[serde(rename_all = "camelCase")]
+struct CodeLookupBatchResponse {
+    mode: String,
+    summary: String,
+    budget_tokens: usize,
+    used_tokens: usize,
+    results: Vec<CodeLookupBatchEntry>,
+}
+
+#[derive(Debug, Serialize, JsonSchema)]
+#[serde(rename_all = "camelCase")]
+struct CodeLookupBatchEntry {
+    index: usize,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    result: Option<CodeLookupResponse>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    error: Option<String>,
+}
+
 #[derive(Debug, Deserialize, JsonSchema)]
 #[serde(rename_all = "camelCase")]
 struct SemanticSearchRequest {
     #[serde(default)]
     root: Option<String>,
+    /// Search several roots concurrently; each hit reports its root.
+    #[serde(default)]
+    roots: Option<Vec<String>>,
+    /// Search every root in INDEX_MCP_ALLOWED_ROOTS.
+    #[serde(default)]
+    all_roots: Option<bool>,
+    /// Search every git worktree of root's repository; the same chunk in
+    /// several worktrees is reported once, with the others in alsoInRoots.
+    #[serde(default)]
+    worktrees: Option<bool>,
     query: String,
     #[serde(default)]
     database_name: Option<String>,
@@ -388,6 +776,9 @@ struct SemanticSearchRequest {
     limit: Option<u32>,
     #[serde(default)]
     model: Option<String>,
+    /// Search each model and fuse the rankings (databases mid-migration).
+    #[serde(default)]
+    models: Option<Vec<String>>,
     #[serde(default)]
     language: Option<String>,
     #[serde(default)]
@@ -395,6 +786,14 @@ struct SemanticSearchRequest {
     #[serde(default)]
     path_contains: Option<String>,
     #[serde(default)]
+    include: Option<Vec<String>>,
+    #[serde(default)]
+    exclude: Option<Vec<String>>,
+    /// Named filter preset from INDEX_MCP_FILTER_PRESETS; explicit filters
+    /// win and `exclude` globs are merged.
+    #[serde(default)]
+    preset: Option<String>,
+    #[serde(default)]
     classification: Option<Classification>,
     #[serde(default)]
     summary_mode: Option<SummaryMode>,
@@ -402,6 +801,36 @@ struct SemanticSearchRequest {
     max_context_before: Option<u32>,
     #[serde(default)]
     max_context_after: Option<u32>,
+    #[serde(default)]
+    only_paths: Option<Vec<String>>,
+    #[serde(default)]
+    recency_weight: Option<f32>,
+    #[serde(default)]
+    package: Option<String>,
+    #[serde(default)]
+    include_symbols: Option<bool>,
+    #[serde(default)]
+    context_source: Option<ContextSource>,
+    #[serde(default)]
+    context_scope: Option<ContextScope>,
+    /// Shortlist this many files by ingest-time keywords before scoring
+    /// chunks; 0 disables. Defaults to 200 on indexes over 20,000 chunks.
+    #[serde(default)]
+    router_files: Option<u32>,
+    /// `symbol` folds hits inside one definition into its best chunk and
+    /// reports the rest as `mergedHits`.
+    #[serde(default)]
+    group_by: Option<GroupBy>,
+    /// Search the retained snapshot of this ingest generation (needs
+    /// INDEX_MCP_KEEP_GENERATIONS).
+    #[serde(default)]
+    generation: Option<i64>,
+    /// Search the newest retained snapshot indexed at this commit.
+    #[serde(default)]
+    at_commit: Option<String>,
+    /// Report the INDEX_MCP_PATH_BOOSTS multiplier applied to each hit.
+    #[serde(default)]
+    explain: Option<bool>,
 }
 
 /// Textual instructions shared with MCP clients.
@@ -414,7 +843,7 @@ const SERVER_INSTRUCTIONS_TEMPLATE: &str = r#"Rust rewrite is production-ready.
 6. When you need additional detail, follow up with semantic_search or focused context_bundle calls instead of broad re-ingests.
 7. After modifying files, re-run ingest_codebase or rely on watch mode, then confirm freshness with index_status/info so the next task sees the updated payload.
 
-Available tools: ingest_codebase, index_status, code_lookup (search/bundle), semantic_search, context_bundle, repository_timeline, repository_timeline_entry, indexing_guidance, indexing_guidance_tool, info."#;
+Available tools: ingest_codebase, estimate_ingest, preview_eviction, recode_embeddings, index_status, list_databases, coverage_report, explain_path, list_skipped, suggest_excludes, language_stats, verify_index, health, self_check, watcher_status, code_lookup (search/bundle), semantic_search, regex_search, context_bundle, graph_neighbors, chunk_neighbors, set_environment, repository_timeline, repository_timeline_entry, search_diffs, timeline_query, session_history, set_log_level, indexing_guidance, indexing_guidance_tool, info."#;
 const INDEXING_GUIDANCE_PROMPT_TEMPLATE: &str = r#"Workflow reminder:
 1. Prime the index after a checkout, pull, or edit by running ingest_codebase {"root": "{ABSOLUTE_ROOT}"} (or enabling watch mode); respect .gitignore, skip files >8 MiB, and configure autoEvict/maxDatabaseSizeBytes when needed. Always provide the absolute workspace root to avoid indexing the wrong project.
 2. Call index_status before reasoning. If it reports staleness or a HEAD mismatch, ingest before continuing.
@@ -440,6 +869,17 @@ fn server_instructions() -> String {
     render_instruction(SERVER_INSTRUCTIONS_TEMPLATE)
 }
 
+fn startup_diagnostics(instructions: &str) -> StartupDiagnostics {
+    let digest = hex::encode(Sha256::digest(instructions.as_bytes()));
+    StartupDiagnostics {
+        server_version: Implementation::from_build_env().version,
+        schema_version: SCHEMA_VERSION,
+        default_model: DEFAULT_EMBEDDING_MODEL.to_string(),
+        watcher: watcher_status().watcher.state,
+        instructions_hash: digest[..16].to_string(),
+    }
+}
+
 fn indexing_guidance_prompt_text() -> String {
     render_instruction(INDEXING_GUIDANCE_PROMPT_TEMPLATE)
 }
@@ -450,6 +890,8 @@ pub struct IndexMcpService {
     tool_router: ToolRouter<Self>,
     prompt_router: PromptRouter<Self>,
     environment: EnvironmentState,
+    audit: AuditLog,
+    quotas: UsageMeter,
 }
 
 impl IndexMcpService {
@@ -475,9 +917,15 @@ impl IndexMcpService {
 
         tokio::spawn(async {
             match tokio::task::spawn_blocking(|| warm_up_embedder(None)).await {
-                Ok(Ok(())) => {}
-                Ok(Err(error)) => warn!(?error, "Embedder warm-up failed"),
-                Err(join_error) => warn!(?join_error, "Embedder warm-up task cancelled"),
+                Ok(Ok(())) => record_embedder_state(ComponentState::Ready, None),
+                Ok(Err(error)) => {
+                    warn!(?error, "Embedder warm-up failed");
+                    record_embedder_state(ComponentState::Failed, Some(error.to_string()));
+                }
+                Err(join_error) => {
+                    warn!(?join_error, "Embedder warm-up task cancelled");
+                    record_embedder_state(ComponentState::Failed, Some(join_error.to_string()));
+                }
             }
         });
 
@@ -485,8 +933,52 @@ impl IndexMcpService {
             tool_router,
             prompt_router,
             environment: EnvironmentState::new(),
+            audit: AuditLog::from_env(),
+            quotas: UsageMeter::from_env(),
         })
     }
+
+    /// Fetches the client's roots in the background when it advertises the
+    /// roots capability; the request cannot be awaited from inside the
+    /// notification handler that triggers it.
+    fn refresh_client_roots(&self, peer: Peer<RoleServer>) {
+        let supports_roots = peer
+            .peer_info()
+            .is_some_and(|info| info.capabilities.roots.is_some());
+        if !supports_roots {
+            return;
+        }
+        let environment = self.environment.clone();
+        tokio::spawn(async move {
+            match peer.list_roots().await {
+                Ok(result) => environment.apply_client_roots(&result.roots),
+                Err(error) => debug!(?error, "Listing client roots failed"),
+            }
+        });
+    }
+
+    /// The root a call resolved to, for tools that take one: the `root`
+    /// argument, else the environment default, else the working directory.
+    fn resolved_root(&self, tool: &str, root_argument: Option<&str>) -> Option<String> {
+        let accepts_root = self.tool_router.list_all().iter().any(|candidate| {
+            candidate.name == tool
+                && candidate
+                    .input_schema
+                    .get("properties")
+                    .and_then(|properties| properties.get("root"))
+                    .is_some()
+        });
+        if !accepts_root {
+            return None;
+        }
+        let root = root_argument
+            .map(str::to_string)
+            .or_else(|| self.environment.snapshot().cwd)
+            .unwrap_or_else(|| "./".to_string());
+        resolve_workspace_root(&root)
+            .ok()
+            .map(|path| path.to_string_lossy().to_string())
+    }
 }
 
 #[rmcp::prompt_router]
@@ -521,6 +1013,9 @@ impl IndexMcpService {
     ) -> Result<CallToolResult, McpError> {
         self.environment.update_from_meta(&ctx.meta);
         self.environment.apply_ingest_defaults(&mut params);
+        self.quotas
+            .check(QuotaKind::Ingests)
+            .map_err(convert_quota_error)?;
 
         let response = ingest_codebase(params)
             .await
@@ -529,6 +1024,65 @@ impl IndexMcpService {
         build_ingest_result(response)
     }
 
+    #[tool(
+        name = "estimate_ingest",
+        description = "Forecast database size, embedding time, and memory for an ingest without writing the index."
+    )]
+    async fn estimate_ingest_tool(
+        &self,
+        Parameters(mut params): Parameters<IngestEstimateParams>,
+        ctx: RequestContext<RoleServer>,
+    ) -> Result<CallToolResult, McpError> {
+        self.environment.update_from_meta(&ctx.meta);
+        self.environment.apply_estimate_defaults(&mut params);
+
+        let response = estimate_ingest(params)
+            .await
+            .map_err(convert_ingest_error)?;
+
+        build_ingest_estimate_result(response)
+    }
+
+    #[tool(
+        name = "preview_eviction",
+        description = "Show what autoEvict would delete from the current index, by category, without deleting anything."
+    )]
+    async fn preview_eviction_tool(
+        &self,
+        Parameters(mut params): Parameters<EvictionPreviewParams>,
+        ctx: RequestContext<RoleServer>,
+    ) -> Result<CallToolResult, McpError> {
+        self.environment.update_from_meta(&ctx.meta);
+        self.environment
+            .apply_eviction_preview_defaults(&mut params);
+
+        let response = preview_eviction(params)
+            .await
+            .map_err(convert_ingest_error)?;
+
+        build_eviction_preview_result(response)
+    }
+
+    #[tool(
+        name = "recode_embeddings",
+        description = "Rewrite every stored chunk embedding as f32, f16, or zstd without re-embedding, then vacuum the database."
+    )]
+    async fn recode_embeddings_tool(
+        &self,
+        Parameters(mut params): Parameters<RecodeEmbeddingsParams>,
+        ctx: RequestContext<RoleServer>,
+    ) -> Result<CallToolResult, McpError> {
+        self.environment.update_from_meta(&ctx.meta);
+        self.environment
+            .apply_recode_embeddings_defaults(&mut params);
+
+        let response = recode_embeddings(params)
+            .await
+            .map_err(convert_recode_embeddings_error)?;
+
+        build_recode_embeddings_result(response)
+    }
+
     #[tool(
         name = "semantic_search",
         description = "Search indexed chunks using embeddings."
@@ -540,20 +1094,41 @@ impl IndexMcpService {
     ) -> Result<CallToolResult, McpError> {
         self.environment.update_from_meta(&ctx.meta);
         self.environment.apply_semantic_defaults(&mut params);
+        apply_search_preset(&mut params)?;
+        self.quotas
+            .check(QuotaKind::EmbeddedQueries)
+            .map_err(convert_quota_error)?;
         let filter_summary = build_search_filter_summary(&params);
         let search_params = SemanticSearchParams {
             root: params.root.clone(),
+            roots: params.roots.clone(),
+            all_roots: params.all_roots,
+            worktrees: params.worktrees,
             query: params.query.clone(),
             database_name: params.database_name.clone(),
             limit: params.limit,
             model: params.model.clone(),
+            models: params.models.clone(),
             language: params.language.clone(),
             path_prefix: params.path_prefix.clone(),
             path_contains: params.path_contains.clone(),
+            include: params.include.clone(),
+            exclude: params.exclude.clone(),
             classification: params.classification.clone(),
             summary_mode: params.summary_mode,
             max_context_before: params.max_context_before,
             max_context_after: params.max_context_after,
+            only_paths: params.only_paths.clone(),
+            recency_weight: params.recency_weight,
+            package: params.package.clone(),
+            include_symbols: params.include_symbols,
+            context_source: params.context_source,
+            context_scope: params.context_scope,
+            router_files: params.router_files,
+            group_by: params.group_by,
+            generation: params.generation,
+            at_commit: params.at_commit.clone(),
+            explain: params.explain,
         };
 
         let mut response = semantic_search(search_params)
@@ -598,7 +1173,7 @@ impl IndexMcpService {
 
     #[tool(
         name = "code_lookup",
-        description = "Route lookups to semantic search (search mode only)."
+        description = "Route lookups to semantic search or context bundles; pass `batch` to run several lookups concurrently under one token budget."
     )]
     async fn code_lookup(
         &self,
@@ -606,59 +1181,475 @@ impl IndexMcpService {
         ctx: RequestContext<RoleServer>,
     ) -> Result<CallToolResult, McpError> {
         self.environment.update_from_meta(&ctx.meta);
+        if let Some(batch) = params.batch.take() {
+            return self.code_lookup_batch(params, batch).await;
+        }
+
         self.environment.apply_code_lookup_defaults(&mut params);
-        let CodeLookupParams {
-            root,
-            database_name,
-            mode,
-            query,
-            file,
-            symbol,
-            ranges,
-            focus_line,
-            max_snippets,
-            max_neighbors,
-            budget_tokens,
-            limit,
-            model,
-            language,
-            path_prefix,
-            path_contains,
-            classification,
-            summary_mode,
-            max_context_before,
-            max_context_after,
-        } = params;
+        let (payload, meta) = self.execute_code_lookup(params).await?;
+        build_code_lookup_response(&payload, Some(meta))
+    }
 
-        let resolved_mode = mode.unwrap_or_else(|| {
-            if query.as_ref().is_some_and(|value| !value.trim().is_empty()) {
-                "search".to_string()
-            } else if file.as_ref().is_some_and(|value| !value.trim().is_empty()) {
-                "bundle".to_string()
-            } else {
-                "search".to_string()
-            }
-        });
+    #[tool(
+        name = "set_environment",
+        description = "Set the workspace cwd, bundle token budget, and remaining context explicitly; returns the effective environment."
+    )]
+    async fn set_environment(
+        &self,
+        Parameters(params): Parameters<SetEnvironmentParams>,
+        ctx: RequestContext<RoleServer>,
+    ) -> Result<CallToolResult, McpError> {
+        self.environment.update_from_meta(&ctx.meta);
+        if let Some(cwd) = params
+            .cwd
+            .as_deref()
+            .map(str::trim)
+            .filter(|cwd| !cwd.is_empty())
+        {
+            if !std::path::Path::new(cwd).is_dir() {
+                return Err(McpError::invalid_params(
+                    format!("set_environment cwd '{cwd}' is not a directory."),
+                    Some(json!({ "cwd": cwd })),
+                ));
+            }
+        }
+
+        self.environment.apply_overrides(&params);
+        build_environment_result(&self.environment.snapshot())
+    }
+
+    #[tool(
+        name = "index_status",
+        description = "Summarize SQLite index freshness and coverage."
+    )]
+    async fn index_status(
+        &self,
+        Parameters(params): Parameters<IndexStatusParams>,
+        _ctx: RequestContext<RoleServer>,
+    ) -> Result<CallToolResult, McpError> {
+        let response = get_index_status(params)
+            .await
+            .map_err(convert_index_status_error)?;
+
+        build_index_status_result(response)
+    }
+
+    #[tool(
+        name = "list_databases",
+        description = "Discover index databases under a root with their schema version, size, models, and last ingest."
+    )]
+    async fn list_databases_tool(
+        &self,
+        Parameters(params): Parameters<ListDatabasesParams>,
+        _ctx: RequestContext<RoleServer>,
+    ) -> Result<CallToolResult, McpError> {
+        let response = list_databases(params)
+            .await
+            .map_err(convert_index_status_error)?;
+
+        build_list_databases_result(response)
+    }
+
+    #[tool(
+        name = "chunk_neighbors",
+        description = "Return the chunks before and after a chunk index so a file can be read incrementally."
+    )]
+    async fn chunk_neighbors_tool(
+        &self,
+        Parameters(params): Parameters<ChunkNeighborsParams>,
+        _ctx: RequestContext<RoleServer>,
+    ) -> Result<CallToolResult, McpError> {
+        let response = chunk_neighbors(params)
+            .await
+            .map_err(convert_chunk_neighbors_error)?;
+
+        build_chunk_neighbors_result(response)
+    }
+
+    #[tool(
+        name = "graph_neighbors",
+        description = "Walk the call graph from a file, symbol, or node id: callers and callees one hop out, or two with depth 2 (callers of callers, callees of callees)."
+    )]
+    async fn graph_neighbors_tool(
+        &self,
+        Parameters(mut params): Parameters<GraphNeighborsParams>,
+        ctx: RequestContext<RoleServer>,
+    ) -> Result<CallToolResult, McpError> {
+        self.environment.update_from_meta(&ctx.meta);
+        self.environment.apply_graph_neighbors_defaults(&mut params);
+
+        let response = graph_neighbors(params)
+            .await
+            .map_err(convert_graph_neighbors_error)?;
+
+        build_graph_neighbors_result(response)
+    }
+
+    #[tool(
+        name = "regex_search",
+        description = "Run a regex over stored file content, line by line, with glob and path-prefix filters; returns line-anchored matches for exact-pattern lookups embeddings cannot answer."
+    )]
+    async fn regex_search_tool(
+        &self,
+        Parameters(mut params): Parameters<RegexSearchParams>,
+        ctx: RequestContext<RoleServer>,
+    ) -> Result<CallToolResult, McpError> {
+        self.environment.update_from_meta(&ctx.meta);
+        self.environment.apply_regex_search_defaults(&mut params);
+        if let Some(preset) =
+            resolve_filter_preset(params.preset.as_deref()).map_err(convert_filter_preset_error)?
+        {
+            fill(&mut params.path_prefix, preset.path_prefix);
+            fill(&mut params.include, preset.include);
+            merge_globs(&mut params.exclude, preset.exclude);
+        }
+
+        let response = regex_search(params)
+            .await
+            .map_err(convert_regex_search_error)?;
+
+        build_regex_search_result(response)
+    }
+
+    #[tool(
+        name = "list_skipped",
+        description = "Page through the files the last ingest skipped, optionally filtered by reason."
+    )]
+    async fn list_skipped_tool(
+        &self,
+        Parameters(params): Parameters<ListSkippedParams>,
+        _ctx: RequestContext<RoleServer>,
+    ) -> Result<CallToolResult, McpError> {
+        let response = list_skipped(params)
+            .await
+            .map_err(convert_list_skipped_error)?;
+
+        build_list_skipped_result(response)
+    }
+
+    #[tool(
+        name = "suggest_excludes",
+        description = "Analyze the last scan for build output, generated code, and binary-heavy directories, and propose exclude/include globs with estimated index size savings to feed back into ingest_codebase."
+    )]
+    async fn suggest_excludes_tool(
+        &self,
+        Parameters(params): Parameters<SuggestExcludesParams>,
+        _ctx: RequestContext<RoleServer>,
+    ) -> Result<CallToolResult, McpError> {
+        let response = suggest_excludes(params)
+            .await
+            .map_err(convert_suggest_excludes_error)?;
+
+        build_suggest_excludes_result(response)
+    }
+
+    #[tool(
+        name = "language_stats",
+        description = "Summarize indexed files, lines, chunks, and bytes per language and per directory from stored metadata, to calibrate search filters and sanity-check index coverage."
+    )]
+    async fn language_stats_tool(
+        &self,
+        Parameters(params): Parameters<LanguageStatsParams>,
+        _ctx: RequestContext<RoleServer>,
+    ) -> Result<CallToolResult, McpError> {
+        let response = language_stats(params)
+            .await
+            .map_err(convert_language_stats_error)?;
+
+        build_language_stats_result(response)
+    }
+
+    #[tool(
+        name = "verify_index",
+        description = "Check every stored chunk against its checksum and report truncated or corrupt embeddings and content."
+    )]
+    async fn verify_index_tool(
+        &self,
+        Parameters(params): Parameters<VerifyIndexParams>,
+        _ctx: RequestContext<RoleServer>,
+    ) -> Result<CallToolResult, McpError> {
+        let response = verify_index(params)
+            .await
+            .map_err(convert_verify_index_error)?;
+
+        build_verify_index_result(response)
+    }
+
+    #[tool(
+        name = "coverage_report",
+        description = "Diff indexed paths against git ls-files to find tracked files missing from the index and indexed files git no longer tracks."
+    )]
+    async fn coverage_report_tool(
+        &self,
+        Parameters(mut params): Parameters<CoverageReportParams>,
+        _ctx: RequestContext<RoleServer>,
+    ) -> Result<CallToolResult, McpError> {
+        if let Some(preset) =
+            resolve_filter_preset(params.preset.as_deref()).map_err(convert_filter_preset_error)?
+        {
+            fill(&mut params.include, preset.include_globs());
+            merge_globs(&mut params.exclude, preset.exclude);
+        }
+        let response = coverage_report(params)
+            .await
+            .map_err(convert_coverage_error)?;
+
+        build_coverage_report_result(response)
+    }
+
+    #[tool(
+        name = "explain_path",
+        description = "Explain whether ingest would index a path and which gitignore rule or include/exclude glob filters it (like git check-ignore -v)."
+    )]
+    async fn explain_path_tool(
+        &self,
+        Parameters(params): Parameters<ExplainPathParams>,
+        _ctx: RequestContext<RoleServer>,
+    ) -> Result<CallToolResult, McpError> {
+        let response = explain_path(params)
+            .await
+            .map_err(convert_explain_path_error)?;
+
+        build_explain_path_result(response)
+    }
+
+    #[tool(
+        name = "info",
+        description = "Report server version, audit logging, per-session quota usage, and registered language packs."
+    )]
+    async fn info_tool(
+        &self,
+        _ctx: RequestContext<RoleServer>,
+    ) -> Result<CallToolResult, McpError> {
+        let implementation = Implementation::from_build_env();
+        build_info_result(ServerInfoResponse {
+            name: implementation.name,
+            version: implementation.version,
+            audit_log_enabled: self.audit.is_enabled(),
+            quotas: self.quotas.status(),
+            data_dir: layout().cloned(),
+            language_packs: registered_packs(),
+            model_aliases: model_aliases(),
+            filter_presets: load_filter_presets().unwrap_or_default(),
+            path_boosts: configured_path_boosts().unwrap_or_default(),
+            diagnostics: startup_diagnostics(&server_instructions()),
+        })
+    }
+
+    #[tool(
+        name = "health",
+        description = "Report readiness: database accessibility, embedder warm-up, and watcher state."
+    )]
+    async fn health_tool(
+        &self,
+        Parameters(params): Parameters<HealthParams>,
+        _ctx: RequestContext<RoleServer>,
+    ) -> Result<CallToolResult, McpError> {
+        let response = check_health(params).await.map_err(convert_health_error)?;
+
+        build_health_result(response)
+    }
+
+    #[tool(
+        name = "self_check",
+        description = "Check the environment: model cache, git, database and schema version, native extensions, and free disk space, with a remediation hint for each problem."
+    )]
+    async fn self_check_tool(
+        &self,
+        Parameters(params): Parameters<SelfCheckParams>,
+        _ctx: RequestContext<RoleServer>,
+    ) -> Result<CallToolResult, McpError> {
+        let response = self_check(params).await.map_err(convert_self_check_error)?;
+
+        build_self_check_result(response)
+    }
+
+    #[tool(
+        name = "watcher_status",
+        description = "Report watcher state and the ingest queue: running and waiting ingests, their priority, and how often a full pass yielded to targeted ingests."
+    )]
+    async fn watcher_status_tool(
+        &self,
+        _ctx: RequestContext<RoleServer>,
+    ) -> Result<CallToolResult, McpError> {
+        build_watcher_status_result(watcher_status())
+    }
+
+    #[tool(
+        name = "session_history",
+        description = "List recent tool calls recorded by the audit log (enable with INDEX_MCP_AUDIT_LOG)."
+    )]
+    async fn session_history_tool(
+        &self,
+        Parameters(params): Parameters<SessionHistoryParams>,
+        _ctx: RequestContext<RoleServer>,
+    ) -> Result<CallToolResult, McpError> {
+        build_session_history_result(self.audit.history(params))
+    }
+
+    #[tool(
+        name = "set_log_level",
+        description = "Adjust tracing filter directives at runtime (e.g. rmcp::service=debug) without restarting the server."
+    )]
+    async fn set_log_level_tool(
+        &self,
+        Parameters(params): Parameters<SetLogLevelParams>,
+        _ctx: RequestContext<RoleServer>,
+    ) -> Result<CallToolResult, McpError> {
+        let response = set_log_level(params).map_err(convert_log_control_error)?;
+
+        build_set_log_level_result(response)
+    }
+
+    #[tool(
+        name = "repository_timeline",
+        description = "Summarize recent git commits, merges, and file churn."
+    )]
+    async fn repository_timeline_tool(
+        &self,
+        Parameters(params): Parameters<RepositoryTimelineParams>,
+        _ctx: RequestContext<RoleServer>,
+    ) -> Result<CallToolResult, McpError> {
+        let response = repository_timeline(params)
+            .await
+            .map_err(convert_repository_timeline_error)?;
+
+        build_repository_timeline_result(response)
+    }
+
+    #[tool(
+        name = "repository_timeline_entry",
+        description = "Fetch a stored repository timeline entry, including full diff text if available."
+    )]
+    async fn repository_timeline_entry_tool(
+        &self,
+        Parameters(params): Parameters<RepositoryTimelineEntryLookupParams>,
+        _ctx: RequestContext<RoleServer>,
+    ) -> Result<CallToolResult, McpError> {
+        let response = repository_timeline_entry_detail(params)
+            .await
+            .map_err(convert_repository_timeline_error)?;
+
+        build_repository_timeline_entry_result(response)
+    }
+
+    #[tool(
+        name = "search_diffs",
+        description = "Semantic search over cached commit diffs, hunk by hunk, to find which recent change touched some behavior. Embeds newly cached diffs on first use; cache them with repository_timeline (includeDiffs)."
+    )]
+    async fn search_diffs_tool(
+        &self,
+        Parameters(params): Parameters<SearchDiffsParams>,
+        _ctx: RequestContext<RoleServer>,
+    ) -> Result<CallToolResult, McpError> {
+        let response = search_diffs(params)
+            .await
+            .map_err(convert_search_diffs_error)?;
+
+        build_search_diffs_result(response)
+    }
+
+    #[tool(
+        name = "timeline_query",
+        description = "Find cached timeline commits touching a path glob or by an author within a date range, without running git."
+    )]
+    async fn timeline_query_tool(
+        &self,
+        Parameters(params): Parameters<TimelineQueryParams>,
+        _ctx: RequestContext<RoleServer>,
+    ) -> Result<CallToolResult, McpError> {
+        let response = timeline_query(params)
+            .await
+            .map_err(convert_timeline_query_error)?;
+
+        build_timeline_query_result(response)
+    }
+}
+
+impl IndexMcpService {
+    async fn execute_code_lookup(
+        &self,
+        mut params: CodeLookupParams,
+    ) -> Result<(CodeLookupResponse, Meta), McpError> {
+        let resolved_mode = resolve_code_lookup_mode(&params);
+        if let Some(preset) =
+            resolve_filter_preset(params.preset.as_deref()).map_err(convert_filter_preset_error)?
+        {
+            fill(&mut params.path_prefix, preset.path_prefix);
+            fill(&mut params.path_contains, preset.path_contains);
+            fill(&mut params.language, preset.language);
+            fill(&mut params.classification, preset.classification);
+            fill(&mut params.package, preset.package);
+            fill(&mut params.include, preset.include);
+            merge_globs(&mut params.exclude, preset.exclude);
+        }
+        let CodeLookupParams {
+            root,
+            database_name,
+            mode: _,
+            query,
+            file,
+            symbol,
+            ranges,
+            focus_line,
+            max_snippets,
+            max_neighbors,
+            budget_tokens,
+            limit,
+            model,
+            language,
+            path_prefix,
+            path_contains,
+            classification,
+            package,
+            include,
+            exclude,
+            preset,
+            summary_mode,
+            max_context_before,
+            max_context_after,
+            batch: _,
+        } = params;
 
         match resolved_mode.as_str() {
             "search" => {
                 let query = query.ok_or_else(|| {
                     McpError::invalid_params("code_lookup search mode requires a query.", None)
                 })?;
+                self.quotas
+                    .check(QuotaKind::EmbeddedQueries)
+                    .map_err(convert_quota_error)?;
 
                 let search_params = SemanticSearchParams {
                     root,
+                    roots: None,
+                    all_roots: None,
+                    worktrees: None,
                     query,
                     database_name,
                     limit,
                     model,
+                    models: None,
                     language: language.clone(),
                     path_prefix: path_prefix.clone(),
                     path_contains: path_contains.clone(),
+                    include,
+                    exclude,
                     classification: classification.clone(),
                     summary_mode,
                     max_context_before,
                     max_context_after,
+                    only_paths: None,
+                    recency_weight: None,
+                    package,
+                    include_symbols: None,
+                    context_source: None,
+                    context_scope: None,
+                    router_files: None,
+                    group_by: None,
+                    generation: None,
+                    at_commit: None,
+                    explain: None,
                 };
 
                 let mut response = semantic_search(search_params)
@@ -675,13 +1666,14 @@ impl IndexMcpService {
                     &path_prefix,
                     &path_contains,
                     &classification,
+                    &preset,
                 );
                 let meta = self.environment.build_search_meta(
                     &response,
                     duplicates_filtered,
                     filter_summary,
                 );
-                build_code_lookup_result(resolved_mode, response, Some(meta))
+                Ok((code_lookup_search_payload(resolved_mode, response), meta))
             }
             "bundle" => {
                 let file = file.or(query).ok_or_else(|| {
@@ -696,8 +1688,13 @@ impl IndexMcpService {
                     max_snippets: max_snippets.or(limit),
                     max_neighbors,
                     budget_tokens,
+                    max_quick_links: None,
                     ranges,
                     focus_line,
+                    content_override: None,
+                    compare_to_commit: None,
+                    generation: None,
+                    at_commit: None,
                 };
                 self.environment.apply_bundle_defaults(&mut bundle_params);
 
@@ -708,7 +1705,7 @@ impl IndexMcpService {
                     .environment
                     .build_bundle_meta(&response.usage, response.usage.cache_hit);
 
-                build_code_lookup_bundle_response(resolved_mode, response, Some(meta))
+                Ok((code_lookup_bundle_payload(resolved_mode, response)?, meta))
             }
             _ => Err(McpError::invalid_params(
                 "Unsupported code_lookup mode. Supported modes: search, bundle.",
@@ -717,67 +1714,228 @@ impl IndexMcpService {
         }
     }
 
-    #[tool(
-        name = "index_status",
-        description = "Summarize SQLite index freshness and coverage."
-    )]
-    async fn index_status(
+    async fn code_lookup_batch(
         &self,
-        Parameters(params): Parameters<IndexStatusParams>,
-        _ctx: RequestContext<RoleServer>,
+        shared: CodeLookupParams,
+        batch: Vec<CodeLookupParams>,
     ) -> Result<CallToolResult, McpError> {
-        let response = get_index_status(params)
-            .await
-            .map_err(convert_index_status_error)?;
+        if batch.is_empty() {
+            return Err(McpError::invalid_params(
+                "code_lookup batch requires at least one request.",
+                None,
+            ));
+        }
+        if batch.len() > MAX_LOOKUP_BATCH {
+            return Err(McpError::invalid_params(
+                format!(
+                    "code_lookup batch accepts at most {} requests.",
+                    MAX_LOOKUP_BATCH
+                ),
+                Some(json!({
+                    "maxBatchSize": MAX_LOOKUP_BATCH,
+                    "requested": batch.len(),
+                })),
+            ));
+        }
 
-        build_index_status_result(response)
+        let budget_tokens = shared
+            .budget_tokens
+            .map(|value| value as usize)
+            .unwrap_or_else(|| self.environment.snapshot().bundle_budget());
+        // every lookup gets an even share: bundles as their budget, searches
+        // by trimming their hits afterwards
+        let lookup_count = batch.iter().filter(|item| item.batch.is_none()).count();
+        let share = budget_tokens / lookup_count.max(1);
+
+        let request_count = batch.len();
+        let mut entries: Vec<CodeLookupBatchEntry> = Vec::with_capacity(request_count);
+        let mut tasks = tokio::task::JoinSet::new();
+        for (index, mut item) in batch.into_iter().enumerate() {
+            if item.batch.is_some() {
+                entries.push(CodeLookupBatchEntry {
+                    index,
+                    result: None,
+                    error: Some("Nested code_lookup batches are not supported.".to_string()),
+                });
+                continue;
+            }
+
+            if item.root.is_none() {
+                item.root = shared.root.clone();
+            }
+            if item.database_name.is_none() {
+                item.database_name = shared.database_name.clone();
+            }
+            if item.preset.is_none() {
+                item.preset = shared.preset.clone();
+            }
+            if resolve_code_lookup_mode(&item) == "bundle" {
+                let share = share as u32;
+                item.budget_tokens =
+                    Some(item.budget_tokens.map_or(share, |value| value.min(share)));
+            }
+            self.environment.apply_code_lookup_defaults(&mut item);
+
+            let service = self.clone();
+            tasks.spawn(async move { (index, service.execute_code_lookup(item).await) });
+        }
+
+        while let Some(joined) = tasks.join_next().await {
+            let (index, outcome) = joined.map_err(|error| {
+                McpError::internal_error(format!("code_lookup batch task failed: {error}"), None)
+            })?;
+            entries.push(match outcome {
+                Ok((mut payload, _)) => {
+                    fit_search_lookup(&mut payload, share);
+                    CodeLookupBatchEntry {
+                        index,
+                        result: Some(payload),
+                        error: None,
+                    }
+                }
+                Err(error) => CodeLookupBatchEntry {
+                    index,
+                    result: None,
+                    error: Some(error.message.to_string()),
+                },
+            });
+        }
+        entries.sort_by_key(|entry| entry.index);
+
+        let used_tokens: usize = entries
+            .iter()
+            .filter_map(|entry| entry.result.as_ref())
+            .map(code_lookup_token_usage)
+            .sum();
+        let payload = CodeLookupBatchResponse {
+            mode: "batch".to_string(),
+            summary: summarize_code_lookup_batch(&entries, used_tokens, budget_tokens),
+            budget_tokens,
+            used_tokens,
+            results: entries,
+        };
+
+        let mut meta = Meta::new();
+        meta.insert(
+            "codeLookupBatch".to_string(),
+            json!({
+                "requestCount": request_count,
+                "failedCount": payload.results.iter().filter(|entry| entry.error.is_some()).count(),
+                "budgetTokens": budget_tokens,
+                "usedTokens": used_tokens,
+            }),
+        );
+        if let Some(remaining) = self.environment.snapshot().remaining_context_tokens {
+            meta.insert("remainingContextTokens".to_string(), json!(remaining));
+        }
+
+        let value: Value = serde_json::to_value(&payload).map_err(|error| {
+            McpError::internal_error(
+                format!("Failed to serialize code_lookup batch result: {error}"),
+                None,
+            )
+        })?;
+
+        Ok(CallToolResult {
+            content: vec![Content::text(payload.summary.clone())],
+            structured_content: Some(value),
+            is_error: Some(false),
+            meta: Some(meta),
+        })
     }
+}
 
-    #[tool(
-        name = "repository_timeline",
-        description = "Summarize recent git commits, merges, and file churn."
-    )]
-    async fn repository_timeline_tool(
+#[rmcp::prompt_handler]
+impl ServerHandler for IndexMcpService {
+    async fn call_tool(
         &self,
-        Parameters(params): Parameters<RepositoryTimelineParams>,
-        _ctx: RequestContext<RoleServer>,
+        request: CallToolRequestParam,
+        context: RequestContext<RoleServer>,
     ) -> Result<CallToolResult, McpError> {
-        let response = repository_timeline(params)
-            .await
-            .map_err(convert_repository_timeline_error)?;
+        let encoding = negotiate_encoding(&context.meta);
+        let tool = request.name.to_string();
+        let arguments = request.arguments.clone();
+        let root_argument = arguments
+            .as_ref()
+            .and_then(|arguments| arguments.get("root"))
+            .and_then(Value::as_str)
+            .map(str::to_string);
+        let mut result = if self.audit.is_enabled() {
+            let started = Instant::now();
+            let result = self
+                .tool_router
+                .call(ToolCallContext::new(self, request, context))
+                .await;
+            self.audit
+                .record(&tool, arguments.as_ref(), started.elapsed(), &result);
+            result
+        } else {
+            self.tool_router
+                .call(ToolCallContext::new(self, request, context))
+                .await
+        };
+        if let Ok(result) = result.as_mut() {
+            // resolved after the call so a cwd supplied in its meta counts
+            let resolved_root = self.resolved_root(&tool, root_argument.as_deref());
+            if let Some(Value::Object(structured)) = result.structured_content.as_mut() {
+                let recommendations = recommend(
+                    &tool,
+                    arguments.as_ref(),
+                    resolved_root.as_deref(),
+                    &Value::Object(structured.clone()),
+                );
+                if !recommendations.is_empty() {
+                    structured.insert("recommendations".to_string(), json!(recommendations));
+                }
+            }
+            if let Some(root) = resolved_root {
+                result
+                    .meta
+                    .get_or_insert_with(Meta::new)
+                    .insert("resolvedRoot".to_string(), json!(root));
+            }
+            if let Some(encoding) = encoding {
+                compress_structured_content(result, encoding);
+            }
+        }
+        result
+    }
 
-        build_repository_timeline_result(response)
+    async fn on_initialized(&self, context: NotificationContext<RoleServer>) {
+        self.refresh_client_roots(context.peer);
     }
 
-    #[tool(
-        name = "repository_timeline_entry",
-        description = "Fetch a stored repository timeline entry, including full diff text if available."
-    )]
-    async fn repository_timeline_entry_tool(
-        &self,
-        Parameters(params): Parameters<RepositoryTimelineEntryLookupParams>,
-        _ctx: RequestContext<RoleServer>,
-    ) -> Result<CallToolResult, McpError> {
-        let response = repository_timeline_entry_detail(params)
-            .await
-            .map_err(convert_repository_timeline_error)?;
+    async fn on_roots_list_changed(&self, context: NotificationContext<RoleServer>) {
+        self.refresh_client_roots(context.peer);
+    }
 
-        build_repository_timeline_entry_result(response)
+    async fn list_tools(
+        &self,
+        _request: Option<PaginatedRequestParam>,
+        _context: RequestContext<RoleServer>,
+    ) -> Result<ListToolsResult, McpError> {
+        Ok(ListToolsResult::with_all_items(self.tool_router.list_all()))
     }
-}
 
-#[tool_handler]
-#[rmcp::prompt_handler]
-impl ServerHandler for IndexMcpService {
     fn get_info(&self) -> ServerInfo {
+        let instructions = server_instructions();
+        let mut capabilities = ServerCapabilities::builder()
+            .enable_tools()
+            .enable_prompts()
+            .build();
+        if let Ok(Value::Object(diagnostics)) =
+            serde_json::to_value(startup_diagnostics(&instructions))
+        {
+            capabilities.experimental = Some(BTreeMap::from([(
+                DIAGNOSTICS_CAPABILITY.to_string(),
+                diagnostics,
+            )]));
+        }
         ServerInfo {
             protocol_version: ProtocolVersion::V_2024_11_05,
-            capabilities: ServerCapabilities::builder()
-                .enable_tools()
-                .enable_prompts()
-                .build(),
+            capabilities,
             server_info: Implementation::from_build_env(),
-            instructions: Some(server_instructions()),
+            instructions: Some(instructions),
         }
     }
 }
@@ -799,6 +1957,236 @@ fn convert_index_status_error(error: IndexStatusError) -> McpError {
         IndexStatusError::Join(source) => {
             McpError::internal_error(format!("Background task failed: {source}"), None)
         }
+        IndexStatusError::Sandbox(error) => convert_sandbox_error(error),
+    }
+}
+
+fn convert_chunk_neighbors_error(error: ChunkNeighborsError) -> McpError {
+    match error {
+        ChunkNeighborsError::InvalidRoot { path, source } => {
+            McpError::invalid_params(format!("Unable to resolve root '{path}': {source}"), None)
+        }
+        ChunkNeighborsError::Sqlite(source) => {
+            McpError::internal_error(format!("SQLite error: {source}"), None)
+        }
+        ChunkNeighborsError::ChunkNotFound {
+            path,
+            chunk_index,
+            total_chunks,
+        } => McpError::invalid_params(
+            format!(
+                "Chunk {chunk_index} of '{path}' is not indexed ({total_chunks} chunk(s) stored)."
+            ),
+            Some(json!({
+                "path": path,
+                "chunkIndex": chunk_index,
+                "totalChunks": total_chunks,
+            })),
+        ),
+        ChunkNeighborsError::Sandbox(error) => convert_sandbox_error(error),
+        ChunkNeighborsError::Join(source) => {
+            McpError::internal_error(format!("Background task failed: {source}"), None)
+        }
+    }
+}
+
+fn convert_list_skipped_error(error: ListSkippedError) -> McpError {
+    match error {
+        ListSkippedError::InvalidRoot { path, source } => {
+            McpError::invalid_params(format!("Unable to resolve root '{path}': {source}"), None)
+        }
+        ListSkippedError::Sqlite(source) => {
+            McpError::internal_error(format!("SQLite error: {source}"), None)
+        }
+        ListSkippedError::Sandbox(error) => convert_sandbox_error(error),
+        ListSkippedError::Join(source) => {
+            McpError::internal_error(format!("Background task failed: {source}"), None)
+        }
+    }
+}
+
+fn convert_recode_embeddings_error(error: RecodeEmbeddingsError) -> McpError {
+    match error {
+        RecodeEmbeddingsError::InvalidRoot { path, source } => {
+            McpError::invalid_params(format!("Unable to resolve root '{path}': {source}"), None)
+        }
+        RecodeEmbeddingsError::MissingDatabase { path } => McpError::invalid_request(
+            format!("No index at '{path}'; run ingest_codebase first."),
+            None,
+        ),
+        RecodeEmbeddingsError::ReadOnlyDatabase { path } => McpError::invalid_request(
+            format!("Database '{path}' is on read-only media, so its embeddings cannot be rewritten here."),
+            None,
+        ),
+        RecodeEmbeddingsError::DatabaseLocked(error) => convert_database_lock_error(error),
+        RecodeEmbeddingsError::Sqlite(source) => {
+            McpError::internal_error(format!("SQLite error: {source}"), None)
+        }
+        RecodeEmbeddingsError::Sandbox(error) => convert_sandbox_error(error),
+        RecodeEmbeddingsError::Join(source) => {
+            McpError::internal_error(format!("Background task failed: {source}"), None)
+        }
+    }
+}
+
+fn convert_graph_neighbors_error(error: GraphNeighborsError) -> McpError {
+    match error {
+        GraphNeighborsError::InvalidRoot { path, source } => {
+            McpError::invalid_params(format!("Unable to resolve root '{path}': {source}"), None)
+        }
+        GraphNeighborsError::MissingTarget => McpError::invalid_params(
+            "graph_neighbors needs a path, symbol, or nodeId to start from.".to_string(),
+            None,
+        ),
+        GraphNeighborsError::Sqlite(source) => {
+            McpError::internal_error(format!("SQLite error: {source}"), None)
+        }
+        GraphNeighborsError::Sandbox(error) => convert_sandbox_error(error),
+        GraphNeighborsError::Join(source) => {
+            McpError::internal_error(format!("Background task failed: {source}"), None)
+        }
+    }
+}
+
+fn convert_regex_search_error(error: RegexSearchError) -> McpError {
+    match error {
+        RegexSearchError::InvalidRoot { path, source } => {
+            McpError::invalid_params(format!("Unable to resolve root '{path}': {source}"), None)
+        }
+        RegexSearchError::InvalidPattern { pattern, source } => {
+            McpError::invalid_params(format!("Invalid regex '{pattern}': {source}"), None)
+        }
+        RegexSearchError::Glob(error) => convert_ingest_error(error),
+        RegexSearchError::Sqlite(source) => {
+            McpError::internal_error(format!("SQLite error: {source}"), None)
+        }
+        RegexSearchError::Sandbox(error) => convert_sandbox_error(error),
+        RegexSearchError::Join(source) => {
+            McpError::internal_error(format!("Background task failed: {source}"), None)
+        }
+    }
+}
+
+fn convert_suggest_excludes_error(error: SuggestExcludesError) -> McpError {
+    match error {
+        SuggestExcludesError::InvalidRoot { path, source } => {
+            McpError::invalid_params(format!("Unable to resolve root '{path}': {source}"), None)
+        }
+        SuggestExcludesError::Sqlite(source) => {
+            McpError::internal_error(format!("SQLite error: {source}"), None)
+        }
+        SuggestExcludesError::Sandbox(error) => convert_sandbox_error(error),
+        SuggestExcludesError::Join(source) => {
+            McpError::internal_error(format!("Background task failed: {source}"), None)
+        }
+    }
+}
+
+fn convert_language_stats_error(error: LanguageStatsError) -> McpError {
+    match error {
+        LanguageStatsError::InvalidRoot { path, source } => {
+            McpError::invalid_params(format!("Unable to resolve root '{path}': {source}"), None)
+        }
+        LanguageStatsError::Sqlite(source) => {
+            McpError::internal_error(format!("SQLite error: {source}"), None)
+        }
+        LanguageStatsError::Sandbox(error) => convert_sandbox_error(error),
+        LanguageStatsError::Join(source) => {
+            McpError::internal_error(format!("Background task failed: {source}"), None)
+        }
+    }
+}
+
+fn convert_verify_index_error(error: VerifyIndexError) -> McpError {
+    match error {
+        VerifyIndexError::InvalidRoot { path, source } => {
+            McpError::invalid_params(format!("Unable to resolve root '{path}': {source}"), None)
+        }
+        VerifyIndexError::Sqlite(source) => {
+            McpError::internal_error(format!("SQLite error: {source}"), None)
+        }
+        VerifyIndexError::Sandbox(error) => convert_sandbox_error(error),
+        VerifyIndexError::Join(source) => {
+            McpError::internal_error(format!("Background task failed: {source}"), None)
+        }
+    }
+}
+
+fn convert_search_diffs_error(error: SearchDiffsError) -> McpError {
+    match error {
+        SearchDiffsError::InvalidRoot { path, source } => {
+            McpError::invalid_params(format!("Unable to resolve root '{path}': {source}"), None)
+        }
+        SearchDiffsError::EmptyQuery => {
+            McpError::invalid_params("Query must not be empty.".to_string(), None)
+        }
+        SearchDiffsError::Sqlite(source) => {
+            McpError::internal_error(format!("SQLite error: {source}"), None)
+        }
+        SearchDiffsError::Embedding(message) => {
+            McpError::internal_error(format!("Embedding failed: {message}"), None)
+        }
+        SearchDiffsError::Sandbox(error) => convert_sandbox_error(error),
+        SearchDiffsError::Join(source) => {
+            McpError::internal_error(format!("Background task failed: {source}"), None)
+        }
+    }
+}
+
+fn convert_self_check_error(error: SelfCheckError) -> McpError {
+    match error {
+        SelfCheckError::InvalidRoot { path, source } => {
+            McpError::invalid_params(format!("Unable to resolve root '{path}': {source}"), None)
+        }
+        SelfCheckError::Sandbox(error) => convert_sandbox_error(error),
+        SelfCheckError::Join(source) => {
+            McpError::internal_error(format!("Background task failed: {source}"), None)
+        }
+    }
+}
+
+fn convert_health_error(error: HealthError) -> McpError {
+    match error {
+        HealthError::InvalidRoot { path, source } => {
+            McpError::invalid_params(format!("Unable to resolve root '{path}': {source}"), None)
+        }
+        HealthError::Sandbox(error) => convert_sandbox_error(error),
+        HealthError::Join(source) => {
+            McpError::internal_error(format!("Background task failed: {source}"), None)
+        }
+    }
+}
+
+fn convert_coverage_error(error: CoverageError) -> McpError {
+    match error {
+        CoverageError::InvalidRoot { path, source } => {
+            McpError::invalid_params(format!("Unable to resolve root '{path}': {source}"), None)
+        }
+        CoverageError::Git(source) => McpError::invalid_params(
+            format!("git ls-files failed; coverage_report needs a git work tree: {source}"),
+            None,
+        ),
+        CoverageError::Sqlite(source) => {
+            McpError::internal_error(format!("SQLite error: {source}"), None)
+        }
+        CoverageError::Glob(error) => convert_ingest_error(error),
+        CoverageError::Join(source) => {
+            McpError::internal_error(format!("Background task failed: {source}"), None)
+        }
+        CoverageError::Sandbox(error) => convert_sandbox_error(error),
+    }
+}
+
+fn convert_explain_path_error(error: ExplainPathError) -> McpError {
+    match error {
+        ExplainPathError::InvalidRoot { path, source } => {
+            McpError::invalid_params(format!("Unable to resolve root '{path}': {source}"), None)
+        }
+        ExplainPathError::Glob(error) => convert_ingest_error(error),
+        ExplainPathError::Sandbox(error) => convert_sandbox_error(error),
+        ExplainPathError::Join(source) => {
+            McpError::internal_error(format!("Background task failed: {source}"), None)
+        }
     }
 }
 
@@ -813,18 +2201,131 @@ fn convert_ingest_error(error: IngestError) -> McpError {
         IngestError::GlobSet(source) => {
             McpError::invalid_params(format!("Failed to compile glob patterns: {source}"), None)
         }
+        IngestError::DatabaseDirectory { path, source } => McpError::internal_error(
+            format!("Unable to create database directory '{path}': {source}"),
+            None,
+        ),
         IngestError::Sqlite(source) => {
             McpError::internal_error(format!("SQLite error: {source}"), None)
         }
         IngestError::Embedding(message) => {
             McpError::internal_error(format!("Embedding failed: {message}"), None)
         }
+        IngestError::EmbeddingModelMismatch {
+            pinned,
+            pinned_dimensions,
+            requested,
+        } => McpError::invalid_params(
+            format!(
+                "Database is pinned to embedding model '{pinned}' but ingest requested '{requested}'. Re-run with embedding.model='{pinned}', choose a different databaseName, or pass allowModelMix: true to mix models deliberately."
+            ),
+            Some(json!({
+                "pinnedModel": pinned,
+                "pinnedDimensions": pinned_dimensions,
+                "requestedModel": requested,
+                "remediation": {
+                    "embedding": { "model": pinned },
+                    "allowModelMix": true,
+                },
+            })),
+        ),
+        IngestError::NormalizationChanged { stored, requested } => McpError::invalid_params(
+            "Database embeds chunks with a different normalization than this ingest requested. Changing it re-embeds every file, so run a full ingest without paths, or omit embedding.normalize to keep the stored one.",
+            Some(json!({
+                "storedNormalization": stored,
+                "requestedNormalization": requested,
+            })),
+        ),
+        IngestError::ChunkingChanged { stored, requested } => McpError::invalid_params(
+            "Database was chunked with different settings than this ingest requested. Changing them re-chunks every file, so run a full ingest without paths, or omit the chunking settings to keep the stored ones.",
+            Some(json!({
+                "storedChunking": stored,
+                "requestedChunking": requested,
+            })),
+        ),
+        IngestError::DatabaseLocked(error) => convert_database_lock_error(error),
+        IngestError::ReadOnlyDatabase { path } => McpError::invalid_request(
+            format!(
+                "Database '{path}' is on read-only media, so it cannot be re-indexed here. Search and status still work; ingest on a writable copy or remount read-write."
+            ),
+            None,
+        ),
+        IngestError::Preempted => McpError::internal_error(
+            "Ingest was preempted by a targeted ingest; retry it.",
+            None,
+        ),
+        IngestError::Sandbox(error) => convert_sandbox_error(error),
         IngestError::Join(source) => {
             McpError::internal_error(format!("Background task failed: {source}"), None)
         }
     }
 }
 
+fn convert_database_lock_error(error: DatabaseLockError) -> McpError {
+    match error {
+        DatabaseLockError::Held { database, holder } => McpError::invalid_request(
+            format!(
+                "Database '{database}' is busy: pid {} is running {}. Retry once it finishes; the lock is taken over automatically if its heartbeat stops for 30s.",
+                holder.pid, holder.operation
+            ),
+            Some(json!({
+                "reason": "databaseLocked",
+                "holder": holder,
+            })),
+        ),
+        DatabaseLockError::Io { path, source } => McpError::internal_error(
+            format!("Unable to write database lock '{path}': {source}"),
+            None,
+        ),
+    }
+}
+
+fn convert_quota_error(error: QuotaExceeded) -> McpError {
+    McpError::invalid_request(
+        format!("Throttled: {error}."),
+        Some(json!({
+            "reason": "quotaExceeded",
+            "quota": error.kind,
+            "limit": error.limit,
+            "windowSeconds": error.window_seconds,
+            "retryAfterMs": error.retry_after_ms,
+            "resetAt": error.reset_at_ms,
+        })),
+    )
+}
+
+fn convert_log_control_error(error: LogControlError) -> McpError {
+    let message = error.to_string();
+    match error {
+        LogControlError::InvalidDirective { .. } => McpError::invalid_params(message, None),
+        LogControlError::Unavailable | LogControlError::Reload(_) => {
+            McpError::internal_error(message, None)
+        }
+    }
+}
+
+fn convert_sandbox_error(error: SandboxError) -> McpError {
+    let message = error.to_string();
+    match error {
+        SandboxError::RootNotAllowed { root, allowed } => McpError::invalid_params(
+            format!("{message}. Add it to INDEX_MCP_ALLOWED_ROOTS to permit it."),
+            Some(json!({
+                "reason": "rootNotAllowed",
+                "root": root,
+                "allowedRoots": allowed,
+            })),
+        ),
+        SandboxError::PathEscape { path, root } => McpError::invalid_params(
+            message,
+            Some(json!({
+                "reason": "pathEscape",
+                "path": path,
+                "root": root,
+            })),
+        ),
+    }
+}
+
 fn build_ingest_result(response: IngestResponse) -> Result<CallToolResult, McpError> {
     let summary = summarize_ingest(&response);
     let value: Value = serde_json::to_value(&response).map_err(|error| {
@@ -857,6 +2358,12 @@ fn summarize_ingest(payload: &IngestResponse) -> String {
         summary.push_str(&format!(" Embedding model {}.", model));
     }
 
+    if payload.mock_embeddings {
+        summary.push_str(
+            " Embeddings come from the mock provider; search over them matches words, not meaning.",
+        );
+    }
+
     if let Some(reused) = payload.reused_file_count {
         summary.push_str(&format!(
             " Reused cached embeddings for {} unchanged file(s).",
@@ -864,8 +2371,42 @@ fn summarize_ingest(payload: &IngestResponse) -> String {
         ));
     }
 
-    if !payload.skipped.is_empty() {
-        summary.push_str(&format!(" Skipped {} file(s).", payload.skipped.len()));
+    if payload.embedding_cache_hits > 0 {
+        summary.push_str(&format!(
+            " {} changed chunk(s) matched previously embedded text and skipped the model.",
+            payload.embedding_cache_hits
+        ));
+    }
+
+    if !payload.gitignore_overrides.is_empty() {
+        summary.push_str(&format!(
+            " Included {} .gitignore'd file(s) from explicit paths.",
+            payload.gitignore_overrides.len()
+        ));
+    }
+
+    if !payload.truncated_files.is_empty() {
+        summary.push_str(&format!(
+            " Capped chunks for {} oversized file(s) (see truncatedFiles).",
+            payload.truncated_files.len()
+        ));
+    }
+
+    if let Some(generation) = payload.generation {
+        summary.push_str(&format!(" Snapshotted as generation {generation}."));
+    }
+
+    if payload.skipped_count > 0 {
+        let reasons: Vec<String> = payload
+            .skipped_by_reason
+            .iter()
+            .map(|(reason, count)| format!("{reason} {count}"))
+            .collect();
+        summary.push_str(&format!(
+            " Skipped {} file(s) ({}); list_skipped has the details.",
+            payload.skipped_count,
+            reasons.join(", ")
+        ));
     }
 
     if !payload.deleted_paths.is_empty() {
@@ -882,14 +2423,138 @@ fn summarize_ingest(payload: &IngestResponse) -> String {
 
     if let Some(evicted) = &payload.evicted {
         summary.push_str(&format!(
-            " Evicted {} chunk(s) and {} node(s) to control database size.",
-            evicted.evicted_chunks, evicted.evicted_nodes
+            " Evicted {} chunk(s), {} node(s), and {} cached embedding(s) to control database size.",
+            evicted.evicted_chunks, evicted.evicted_nodes, evicted.evicted_cache_entries
+        ));
+    }
+
+    summary
+}
+
+fn build_ingest_estimate_result(
+    response: IngestEstimateResponse,
+) -> Result<CallToolResult, McpError> {
+    let summary = summarize_ingest_estimate(&response);
+    let value: Value = serde_json::to_value(&response).map_err(|error| {
+        McpError::internal_error(
+            format!("Failed to serialize ingest estimate: {error}"),
+            None,
+        )
+    })?;
+
+    Ok(CallToolResult {
+        content: vec![Content::text(summary)],
+        structured_content: Some(value),
+        is_error: Some(false),
+        meta: None,
+    })
+}
+
+fn summarize_ingest_estimate(payload: &IngestEstimateResponse) -> String {
+    let mut summary = format!(
+        "Scanned {} file(s) ({}) at {} in {:.2}s.",
+        payload.file_count,
+        format_bytes(payload.total_bytes),
+        payload.root,
+        payload.scan_duration_ms as f64 / 1000.0
+    );
+
+    if payload.embedding_enabled {
+        summary.push_str(&format!(
+            " Expect ~{} chunk(s) with {} ({}/{} tokens); embedding would take ~{:.1}s at {:.1} chunk(s)/s{}.",
+            payload.estimated_chunk_count,
+            payload.embedding_model,
+            payload.chunk_size_tokens,
+            payload.chunk_overlap_tokens,
+            payload.estimated_embedding_ms as f64 / 1000.0,
+            payload.chunks_per_second,
+            if payload.throughput_measured {
+                " (measured)"
+            } else {
+                " (fallback rate)"
+            }
         ));
+    } else {
+        summary.push_str(" Embeddings disabled; no chunks would be created.");
+    }
+
+    summary.push_str(&format!(
+        " Predicted database size {}, peak memory {}.",
+        format_bytes(payload.estimated_database_size_bytes),
+        format_bytes(payload.estimated_peak_memory_bytes)
+    ));
+
+    if payload.skipped_count > 0 {
+        summary.push_str(&format!(" Would skip {} file(s).", payload.skipped_count));
     }
 
     summary
 }
 
+fn build_eviction_preview_result(
+    response: EvictionPreviewResponse,
+) -> Result<CallToolResult, McpError> {
+    let summary = summarize_eviction_preview(&response);
+    let value: Value = serde_json::to_value(&response).map_err(|error| {
+        McpError::internal_error(
+            format!("Failed to serialize eviction preview: {error}"),
+            None,
+        )
+    })?;
+
+    Ok(CallToolResult {
+        content: vec![Content::text(summary)],
+        structured_content: Some(value),
+        is_error: Some(false),
+        meta: None,
+    })
+}
+
+fn summarize_eviction_preview(payload: &EvictionPreviewResponse) -> String {
+    if !payload.would_evict {
+        return format!(
+            "Database at {} is {} against a {} cap; nothing would be evicted.{}",
+            payload.database_path,
+            format_bytes(payload.database_size_bytes),
+            format_bytes(payload.max_database_size_bytes),
+            payload
+                .note
+                .as_deref()
+                .map(|note| format!(" {note}"))
+                .unwrap_or_default()
+        );
+    }
+
+    let mut summary = format!(
+        "autoEvict would shrink {} from {} toward {} by deleting {} chunk(s) and up to {} graph node(s).",
+        payload.database_path,
+        format_bytes(payload.database_size_bytes),
+        format_bytes(payload.target_size_bytes),
+        payload.chunks_to_evict,
+        payload.max_nodes_to_evict
+    );
+    for category in &payload.categories {
+        let label = match category.tier {
+            EvictionTier::MissingFile => "from deleted files",
+            EvictionTier::Comment => "comments/docs",
+            EvictionTier::Generated => "generated code",
+            EvictionTier::Code => "code",
+        };
+        summary.push_str(&format!(
+            " {} {label} (e.g. {}).",
+            category.chunks,
+            category.example_paths.join(", ")
+        ));
+    }
+    if payload.protected_chunks > 0 {
+        summary.push_str(&format!(
+            " {} protected chunk(s) would be kept.",
+            payload.protected_chunks
+        ));
+    }
+    summary
+}
+
 fn build_index_status_result(response: IndexStatusResponse) -> Result<CallToolResult, McpError> {
     let summary = summarize_index_status(&response);
     let value: Value = serde_json::to_value(&response).map_err(|error| {
@@ -931,6 +2596,12 @@ fn summarize_index_status(payload: &IndexStatusResponse) -> String {
         summary.push_str(" No ingestion history recorded yet.");
     }
 
+    if payload.read_only {
+        summary.push_str(
+            " Database is on read-only media: search works, ingest and hit counting are off.",
+        );
+    }
+
     if payload.is_stale {
         let indexed = payload
             .commit_sha
@@ -960,9 +2631,838 @@ fn summarize_index_status(payload: &IndexStatusResponse) -> String {
         ));
     }
 
+    summary.push_str(&format!(
+        " Health {}/100 ({}).",
+        payload.health.score,
+        health_status_label(payload.health.status)
+    ));
+    if let Some(weakest) = payload
+        .health
+        .dimensions
+        .iter()
+        .filter(|dimension| dimension.status != HealthStatus::Green)
+        .min_by_key(|dimension| dimension.score)
+    {
+        summary.push_str(&format!(" Weakest: {} – {}", weakest.name, weakest.detail));
+    }
+
+    if !payload.packages.is_empty() {
+        let top = payload
+            .packages
+            .iter()
+            .take(3)
+            .map(|package| format!("{} ({} file(s))", package.name, package.file_count))
+            .collect::<Vec<_>>()
+            .join(", ");
+        summary.push_str(&format!(
+            " Packages: {} tracked; largest {}.",
+            payload.packages.len(),
+            top
+        ));
+    }
+
+    summary
+}
+
+fn build_list_databases_result(
+    response: ListDatabasesResponse,
+) -> Result<CallToolResult, McpError> {
+    let summary = summarize_list_databases(&response);
+    let value: Value = serde_json::to_value(&response).map_err(|error| {
+        McpError::internal_error(format!("Failed to serialize databases: {error}"), None)
+    })?;
+
+    Ok(CallToolResult {
+        content: vec![Content::text(summary)],
+        structured_content: Some(value),
+        is_error: Some(false),
+        meta: None,
+    })
+}
+
+fn build_chunk_neighbors_result(
+    response: ChunkNeighborsResponse,
+) -> Result<CallToolResult, McpError> {
+    let summary = summarize_chunk_neighbors(&response);
+    let value: Value = serde_json::to_value(&response).map_err(|error| {
+        McpError::internal_error(
+            format!("Failed to serialize chunk neighbors: {error}"),
+            None,
+        )
+    })?;
+
+    Ok(CallToolResult {
+        content: vec![Content::text(summary)],
+        structured_content: Some(value),
+        is_error: Some(false),
+        meta: None,
+    })
+}
+
+fn build_list_skipped_result(response: ListSkippedResponse) -> Result<CallToolResult, McpError> {
+    let mut summary = if response.total == 0 {
+        "No skipped files recorded for the last ingest.".to_string()
+    } else {
+        format!(
+            "Skipped files {}-{} of {}.",
+            response.offset + 1,
+            response.offset + response.entries.len(),
+            response.total
+        )
+    };
+    if let Some(next) = response.next_offset {
+        summary.push_str(&format!(" Next page: offset {next}."));
+    }
+    let value: Value = serde_json::to_value(&response).map_err(|error| {
+        McpError::internal_error(format!("Failed to serialize skipped files: {error}"), None)
+    })?;
+
+    Ok(CallToolResult {
+        content: vec![Content::text(summary)],
+        structured_content: Some(value),
+        is_error: Some(false),
+        meta: None,
+    })
+}
+
+fn build_recode_embeddings_result(
+    response: RecodeEmbeddingsResponse,
+) -> Result<CallToolResult, McpError> {
+    let mut summary = format!(
+        "Recoded {} chunk embedding(s): {} -> {} bytes; database is now {} bytes.",
+        response.recoded_chunks,
+        response.bytes_before,
+        response.bytes_after,
+        response.database_size_bytes
+    );
+    if response.unchanged_chunks > 0 {
+        summary.push_str(&format!(
+            " {} were already in the target encoding.",
+            response.unchanged_chunks
+        ));
+    }
+    if response.skipped_chunks > 0 {
+        summary.push_str(&format!(
+            " Skipped {} corrupt chunk(s); run verify_index to list them.",
+            response.skipped_chunks
+        ));
+    }
+    let value: Value = serde_json::to_value(&response).map_err(|error| {
+        McpError::internal_error(format!("Failed to serialize recode report: {error}"), None)
+    })?;
+
+    Ok(CallToolResult {
+        content: vec![Content::text(summary)],
+        structured_content: Some(value),
+        is_error: Some(false),
+        meta: None,
+    })
+}
+
+fn build_graph_neighbors_result(
+    response: GraphNeighborsResponse,
+) -> Result<CallToolResult, McpError> {
+    let second_hop = response
+        .neighbors
+        .iter()
+        .filter(|neighbor| neighbor.hop == 2)
+        .count();
+    let mut summary = format!(
+        "{} neighbor(s) of {} start node(s)",
+        response.neighbors.len(),
+        response.start.len()
+    );
+    if response.depth > 1 {
+        summary.push_str(&format!(", {second_hop} of them two hops out"));
+    }
+    summary.push('.');
+    if response.truncated {
+        summary.push_str(" Stopped at the limit; narrow direction or edgeType, or raise limit.");
+    }
+    if let Some(note) = &response.note {
+        summary.push(' ');
+        summary.push_str(note);
+    }
+    let value: Value = serde_json::to_value(&response).map_err(|error| {
+        McpError::internal_error(
+            format!("Failed to serialize graph neighbors: {error}"),
+            None,
+        )
+    })?;
+
+    Ok(CallToolResult {
+        content: vec![Content::text(summary)],
+        structured_content: Some(value),
+        is_error: Some(false),
+        meta: None,
+    })
+}
+
+fn build_regex_search_result(response: RegexSearchResponse) -> Result<CallToolResult, McpError> {
+    let mut summary = format!(
+        "{} match(es) for /{}/ in {} of {} searched file(s).",
+        response.matches.len(),
+        response.pattern,
+        response.files_matched,
+        response.files_searched
+    );
+    if let Some(first) = response.matches.first() {
+        summary.push_str(&format!(" First: {}:{}.", first.path, first.line));
+    }
+    if response.truncated {
+        summary.push_str(" Stopped at the match limit; narrow the filters or raise limit.");
+    }
+    if let Some(note) = &response.note {
+        summary.push(' ');
+        summary.push_str(note);
+    }
+    let value: Value = serde_json::to_value(&response).map_err(|error| {
+        McpError::internal_error(format!("Failed to serialize regex matches: {error}"), None)
+    })?;
+
+    Ok(CallToolResult {
+        content: vec![Content::text(summary)],
+        structured_content: Some(value),
+        is_error: Some(false),
+        meta: None,
+    })
+}
+
+fn build_verify_index_result(response: VerifyIndexResponse) -> Result<CallToolResult, McpError> {
+    let mut summary = format!(
+        "Verified {} chunk(s): {} corrupt.",
+        response.checked_chunks, response.corrupt_chunks
+    );
+    if response.unverified_chunks > 0 {
+        summary.push_str(&format!(
+            " {} chunk(s) predate checksums; only their embeddings were checked.",
+            response.unverified_chunks
+        ));
+    }
+    if let Some(recommendation) = &response.recommendation {
+        summary.push(' ');
+        summary.push_str(recommendation);
+    }
+    let value: Value = serde_json::to_value(&response).map_err(|error| {
+        McpError::internal_error(
+            format!("Failed to serialize verification report: {error}"),
+            None,
+        )
+    })?;
+
+    Ok(CallToolResult {
+        content: vec![Content::text(summary)],
+        structured_content: Some(value),
+        is_error: Some(false),
+        meta: None,
+    })
+}
+
+fn summarize_chunk_neighbors(payload: &ChunkNeighborsResponse) -> String {
+    let lines = payload
+        .chunks
+        .first()
+        .and_then(|first| first.line_start)
+        .zip(payload.chunks.last().and_then(|last| last.line_end))
+        .map(|(start, end)| format!(" (lines {start}-{end})"))
+        .unwrap_or_default();
+    let mut summary = format!(
+        "{} chunk(s) of {} around chunk {} of {}{lines}.",
+        payload.chunks.len(),
+        payload.path,
+        payload.anchor_index,
+        payload.total_chunks
+    );
+    match (payload.previous_index, payload.next_index) {
+        (Some(previous), Some(next)) => summary.push_str(&format!(
+            " Scroll with chunkIndex {previous} (up) or {next} (down)."
+        )),
+        (Some(previous), None) => summary.push_str(&format!(
+            " End of file; scroll up with chunkIndex {previous}."
+        )),
+        (None, Some(next)) => summary.push_str(&format!(
+            " Start of file; scroll down with chunkIndex {next}."
+        )),
+        (None, None) => summary.push_str(" Whole file shown."),
+    }
+    summary
+}
+
+fn build_coverage_report_result(
+    response: CoverageReportResponse,
+) -> Result<CallToolResult, McpError> {
+    let summary = summarize_coverage_report(&response);
+    let value: Value = serde_json::to_value(&response).map_err(|error| {
+        McpError::internal_error(
+            format!("Failed to serialize coverage report: {error}"),
+            None,
+        )
+    })?;
+
+    Ok(CallToolResult {
+        content: vec![Content::text(summary)],
+        structured_content: Some(value),
+        is_error: Some(false),
+        meta: None,
+    })
+}
+
+fn summarize_coverage_report(payload: &CoverageReportResponse) -> String {
+    if !payload.database_exists {
+        return format!(
+            "SQLite index not found at {}; all {} tracked file(s) are missing. Run ingest_codebase to create it.",
+            payload.database_path, payload.tracked_file_count
+        );
+    }
+
+    let mut summary = format!(
+        "{} of {} git-tracked file(s) are indexed.",
+        payload.covered_file_count, payload.tracked_file_count
+    );
+    if payload.missing_count > 0 {
+        summary.push_str(&format!(
+            " {} tracked file(s) missing from the index",
+            payload.missing_count
+        ));
+        let hotspots = payload
+            .directories
+            .iter()
+            .filter(|directory| directory.missing > 0)
+            .take(3)
+            .map(|directory| format!("{} ({})", directory.directory, directory.missing))
+            .collect::<Vec<_>>();
+        if hotspots.is_empty() {
+            summary.push('.');
+        } else {
+            summary.push_str(&format!(", mostly in {}.", hotspots.join(", ")));
+        }
+    }
+    if !payload.missing_reasons.is_empty() {
+        let mut reasons: BTreeMap<&str, usize> = BTreeMap::new();
+        for reason in payload.missing_reasons.values() {
+            *reasons.entry(reason.as_str()).or_insert(0) += 1;
+        }
+        let reasons: Vec<String> = reasons
+            .iter()
+            .map(|(reason, count)| format!("{reason} {count}"))
+            .collect();
+        summary.push_str(&format!(" Recorded skip reasons: {}.", reasons.join(", ")));
+    }
+    if payload.untracked_count > 0 {
+        summary.push_str(&format!(
+            " {} indexed file(s) are not tracked by git (deleted, renamed, or never added).",
+            payload.untracked_count
+        ));
+    }
+    if payload.missing_count == 0 && payload.untracked_count == 0 {
+        summary.push_str(" No coverage gaps.");
+    }
+    summary
+}
+
+fn build_explain_path_result(response: ExplainPathResponse) -> Result<CallToolResult, McpError> {
+    let summary = summarize_explain_path(&response);
+    let value: Value = serde_json::to_value(&response).map_err(|error| {
+        McpError::internal_error(
+            format!("Failed to serialize path explanation: {error}"),
+            None,
+        )
+    })?;
+
+    Ok(CallToolResult {
+        content: vec![Content::text(summary)],
+        structured_content: Some(value),
+        is_error: Some(false),
+        meta: None,
+    })
+}
+
+fn summarize_explain_path(payload: &ExplainPathResponse) -> String {
+    let mut summary = describe_explain_reason(payload);
+    if let Some(recorded) = &payload.recorded_skip {
+        summary.push_str(&format!(
+            " The last ingest that saw it skipped it as {}.",
+            recorded.reason
+        ));
+    }
     summary
 }
 
+fn describe_explain_reason(payload: &ExplainPathResponse) -> String {
+    let path = &payload.path;
+    let rule = payload.gitignore.as_ref().map(|rule| {
+        format!(
+            "{}:{}",
+            rule.source.as_deref().unwrap_or("global gitignore"),
+            rule.pattern
+        )
+    });
+    match payload.reason.as_deref() {
+        Some("not_found") => format!("{path} does not exist under {}.", payload.root),
+        Some("gitignore") => format!(
+            "{path} is ignored by {}.",
+            rule.unwrap_or_else(|| "a gitignore rule".to_string())
+        ),
+        Some("directory") => {
+            format!("{path} is a directory; ingest walks into it and filters each file separately.")
+        }
+        Some("include_glob") => format!("{path} matches none of the include globs."),
+        Some("exclude_glob") => format!(
+            "{path} is excluded by glob '{}'.",
+            payload.exclude_pattern.as_deref().unwrap_or_default()
+        ),
+        Some("max_file_size") => format!(
+            "{path} is {} bytes, above maxFileSizeBytes.",
+            payload.size.unwrap_or_default()
+        ),
+        Some("binary") => {
+            format!("{path} would be indexed as a binary file without chunks or embeddings.")
+        }
+        _ => {
+            let mut summary = format!("{path} would be indexed");
+            if let Some(pattern) = &payload.include_pattern {
+                summary.push_str(&format!(" (include '{pattern}')"));
+            }
+            if let Some(rule) = rule {
+                summary.push_str(&format!("; re-included by {rule}"));
+            }
+            summary.push('.');
+            summary
+        }
+    }
+}
+
+fn build_set_log_level_result(response: SetLogLevelResponse) -> Result<CallToolResult, McpError> {
+    let summary = if response.changed {
+        format!(
+            "Log filter changed from '{}' to '{}'.",
+            response.previous_filter, response.active_filter
+        )
+    } else {
+        format!("Log filter unchanged: '{}'.", response.active_filter)
+    };
+    let value: Value = serde_json::to_value(&response).map_err(|error| {
+        McpError::internal_error(format!("Failed to serialize log filter: {error}"), None)
+    })?;
+
+    Ok(CallToolResult {
+        content: vec![Content::text(summary)],
+        structured_content: Some(value),
+        is_error: Some(false),
+        meta: None,
+    })
+}
+
+fn build_info_result(response: ServerInfoResponse) -> Result<CallToolResult, McpError> {
+    let mut summary = format!("{} {}.", response.name, response.version);
+    if response.quotas.is_empty() {
+        summary.push_str(" No usage quotas configured.");
+    } else {
+        let quotas = response
+            .quotas
+            .iter()
+            .map(|quota| {
+                format!(
+                    "{} {}/{} per {}s",
+                    quota.kind.label(),
+                    quota.used,
+                    quota.limit,
+                    quota.window_seconds
+                )
+            })
+            .collect::<Vec<_>>()
+            .join(", ");
+        summary.push_str(&format!(" Quotas: {quotas}."));
+    }
+    if response.audit_log_enabled {
+        summary.push_str(" Audit log enabled.");
+    }
+    summary.push_str(&format!(
+        " Schema v{}, default model {}, instructions {}.",
+        response.diagnostics.schema_version,
+        response.diagnostics.default_model,
+        response.diagnostics.instructions_hash
+    ));
+    if let Some(layout) = response.data_dir.as_ref() {
+        summary.push_str(&format!(" Data directory: {}.", layout.root.display()));
+    }
+    if !response.language_packs.is_empty() {
+        let packs = response
+            .language_packs
+            .iter()
+            .map(|pack| pack.name.as_str())
+            .collect::<Vec<_>>()
+            .join(", ");
+        summary.push_str(&format!(" Language packs: {packs}."));
+    }
+    if !response.model_aliases.is_empty() {
+        let aliases = response
+            .model_aliases
+            .iter()
+            .map(|alias| format!("{} = {}", alias.alias, alias.model))
+            .collect::<Vec<_>>()
+            .join(", ");
+        summary.push_str(&format!(" Model aliases: {aliases}."));
+    }
+    let value: Value = serde_json::to_value(&response).map_err(|error| {
+        McpError::internal_error(format!("Failed to serialize server info: {error}"), None)
+    })?;
+
+    Ok(CallToolResult {
+        content: vec![Content::text(summary)],
+        structured_content: Some(value),
+        is_error: Some(false),
+        meta: None,
+    })
+}
+
+fn build_search_diffs_result(response: SearchDiffsResponse) -> Result<CallToolResult, McpError> {
+    let mut summary = format!(
+        "Searched {} diff hunk(s) with {} and returned {} match(es).",
+        response.searched_hunks,
+        response.embedding_model,
+        response.hits.len()
+    );
+    if response.newly_indexed_commits > 0 {
+        summary.push_str(&format!(
+            " Embedded {} newly cached commit(s).",
+            response.newly_indexed_commits
+        ));
+    }
+    for hit in &response.hits {
+        summary.push_str(&format!(
+            "\n- {} {} ({}) {} {} score {:.3}",
+            short_sha(&hit.commit_sha),
+            hit.subject,
+            hit.author_date,
+            hit.path,
+            hit.hunk_header,
+            hit.score
+        ));
+    }
+    if let Some(recommendation) = &response.recommendation {
+        summary.push_str(&format!("\n{recommendation}"));
+    }
+    let value: Value = serde_json::to_value(&response).map_err(|error| {
+        McpError::internal_error(
+            format!("Failed to serialize diff search results: {error}"),
+            None,
+        )
+    })?;
+
+    Ok(CallToolResult {
+        content: vec![Content::text(summary)],
+        structured_content: Some(value),
+        is_error: Some(false),
+        meta: None,
+    })
+}
+
+fn build_suggest_excludes_result(
+    response: SuggestExcludesResponse,
+) -> Result<CallToolResult, McpError> {
+    let mut summary = format!(
+        "Analyzed {} indexed file(s) ({}).",
+        response.indexed_files,
+        format_bytes(response.index_bytes)
+    );
+    if !response.suggestions.is_empty() {
+        summary.push_str(&format!(
+            " {} suggestion(s) would save about {}:",
+            response.suggestions.len(),
+            format_bytes(response.estimated_savings_bytes)
+        ));
+        for suggestion in &response.suggestions {
+            summary.push_str(&format!(
+                "\n- {} ({}, {} file(s), {})",
+                suggestion.glob,
+                suggestion.reason,
+                suggestion.matched_files,
+                format_bytes(suggestion.estimated_savings_bytes)
+            ));
+        }
+    }
+    if let Some(include) = &response.suggested_include {
+        summary.push_str(&format!("\nSuggested include: {}", include.join(", ")));
+    }
+    if let Some(recommendation) = &response.recommendation {
+        summary.push_str(&format!("\n{recommendation}"));
+    }
+    let value: Value = serde_json::to_value(&response).map_err(|error| {
+        McpError::internal_error(
+            format!("Failed to serialize exclude suggestions: {error}"),
+            None,
+        )
+    })?;
+
+    Ok(CallToolResult {
+        content: vec![Content::text(summary)],
+        structured_content: Some(value),
+        is_error: Some(false),
+        meta: None,
+    })
+}
+
+fn build_language_stats_result(
+    response: LanguageStatsResponse,
+) -> Result<CallToolResult, McpError> {
+    let mut summary = match &response.note {
+        Some(note) => note.clone(),
+        None => format!(
+            "{} file(s), {} line(s), {} chunk(s), {} indexed.",
+            response.total_files,
+            response.total_lines,
+            response.total_chunks,
+            format_bytes(response.total_bytes)
+        ),
+    };
+    for language in response.languages.iter().take(8) {
+        summary.push_str(&format!(
+            "\n- {} {:.1}% ({} file(s), {} line(s))",
+            language.language, language.percentage, language.files, language.lines
+        ));
+    }
+    if let Some(largest) = response.directories.first() {
+        summary.push_str(&format!(
+            "\nLargest directory: {} ({} file(s), {}).",
+            largest.directory,
+            largest.files,
+            format_bytes(largest.bytes)
+        ));
+    }
+    let value: Value = serde_json::to_value(&response).map_err(|error| {
+        McpError::internal_error(format!("Failed to serialize language stats: {error}"), None)
+    })?;
+
+    Ok(CallToolResult {
+        content: vec![Content::text(summary)],
+        structured_content: Some(value),
+        is_error: Some(false),
+        meta: None,
+    })
+}
+
+fn build_self_check_result(response: SelfCheckResponse) -> Result<CallToolResult, McpError> {
+    let label = |status: CheckStatus| match status {
+        CheckStatus::Pass => "pass",
+        CheckStatus::Skipped => "skipped",
+        CheckStatus::Warn => "warn",
+        CheckStatus::Fail => "fail",
+    };
+    let mut summary = format!("Self-check: {}.", label(response.status));
+    for check in &response.checks {
+        summary.push_str(&format!(
+            "\n- {} [{}] {}",
+            check.name,
+            label(check.status),
+            check.detail
+        ));
+        if let Some(remediation) = &check.remediation {
+            summary.push_str(&format!(" Fix: {remediation}"));
+        }
+    }
+    let value: Value = serde_json::to_value(&response).map_err(|error| {
+        McpError::internal_error(
+            format!("Failed to serialize self-check report: {error}"),
+            None,
+        )
+    })?;
+
+    Ok(CallToolResult {
+        content: vec![Content::text(summary)],
+        structured_content: Some(value),
+        is_error: Some(false),
+        meta: None,
+    })
+}
+
+fn build_watcher_status_result(
+    response: WatcherStatusResponse,
+) -> Result<CallToolResult, McpError> {
+    let running = response
+        .ingest_queue
+        .iter()
+        .filter(|job| job.running)
+        .count();
+    let waiting = response.ingest_queue.len() - running;
+    let mut summary = format!(
+        "Watcher {}. {running} ingest(s) running, {waiting} waiting.",
+        component_state_label(response.watcher.state)
+    );
+    for job in &response.ingest_queue {
+        let scope = if job.target_paths > 0 {
+            format!("{} path(s)", job.target_paths)
+        } else {
+            "full pass".to_string()
+        };
+        summary.push_str(&format!(
+            "\n- #{} {} on {} ({scope}{})",
+            job.id,
+            if job.running { "running" } else { "waiting" },
+            job.database_path,
+            if job.preemptions > 0 {
+                format!(", preempted {}x", job.preemptions)
+            } else {
+                String::new()
+            }
+        ));
+    }
+    let value: Value = serde_json::to_value(&response).map_err(|error| {
+        McpError::internal_error(format!("Failed to serialize watcher status: {error}"), None)
+    })?;
+
+    Ok(CallToolResult {
+        content: vec![Content::text(summary)],
+        structured_content: Some(value),
+        is_error: Some(false),
+        meta: None,
+    })
+}
+
+fn component_state_label(state: ComponentState) -> &'static str {
+    match state {
+        ComponentState::Pending => "pending",
+        ComponentState::Ready => "ready",
+        ComponentState::Running => "running",
+        ComponentState::Stopped => "stopped",
+        ComponentState::Disabled => "disabled",
+        ComponentState::Failed => "failed",
+    }
+}
+
+fn build_health_result(response: HealthResponse) -> Result<CallToolResult, McpError> {
+    let describe = component_state_label;
+    let database = match response.database.state {
+        DatabaseState::Accessible => "accessible",
+        DatabaseState::Missing => "not created yet",
+        DatabaseState::Unreadable => "unreadable",
+    };
+    let mut summary = format!(
+        "{}. Database {database}, embedder {}, watcher {}.",
+        if response.ready { "Ready" } else { "Not ready" },
+        describe(response.embedder.state),
+        describe(response.watcher.state)
+    );
+    for detail in [
+        response.database.error.as_deref(),
+        response.embedder.detail.as_deref(),
+        response.watcher.detail.as_deref(),
+    ]
+    .into_iter()
+    .flatten()
+    {
+        summary.push_str(&format!(" {detail}."));
+    }
+    let value: Value = serde_json::to_value(&response).map_err(|error| {
+        McpError::internal_error(format!("Failed to serialize health report: {error}"), None)
+    })?;
+
+    Ok(CallToolResult {
+        content: vec![Content::text(summary)],
+        structured_content: Some(value),
+        is_error: Some(false),
+        meta: None,
+    })
+}
+
+fn build_session_history_result(
+    response: SessionHistoryResponse,
+) -> Result<CallToolResult, McpError> {
+    let summary = summarize_session_history(&response);
+    let value: Value = serde_json::to_value(&response).map_err(|error| {
+        McpError::internal_error(
+            format!("Failed to serialize session history: {error}"),
+            None,
+        )
+    })?;
+
+    Ok(CallToolResult {
+        content: vec![Content::text(summary)],
+        structured_content: Some(value),
+        is_error: Some(false),
+        meta: None,
+    })
+}
+
+fn summarize_session_history(payload: &SessionHistoryResponse) -> String {
+    if !payload.enabled {
+        return "Audit log is disabled. Set INDEX_MCP_AUDIT_LOG to a file path to record tool calls."
+            .to_string();
+    }
+    if payload.entries.is_empty() {
+        return "No tool calls recorded yet this session.".to_string();
+    }
+
+    let entries = payload
+        .entries
+        .iter()
+        .map(|entry| {
+            let status = if entry.is_error { ", failed" } else { "" };
+            format!(
+                "{} ({} ms, {}{status})",
+                entry.tool,
+                entry.duration_ms,
+                format_bytes(entry.result_bytes as u64)
+            )
+        })
+        .collect::<Vec<_>>()
+        .join("; ");
+    format!(
+        "Showing {} of {} recorded call(s), newest first: {entries}.",
+        payload.entries.len(),
+        payload.recorded_calls
+    )
+}
+
+fn health_status_label(status: HealthStatus) -> &'static str {
+    match status {
+        HealthStatus::Green => "green",
+        HealthStatus::Yellow => "yellow",
+        HealthStatus::Red => "red",
+    }
+}
+
+fn summarize_list_databases(payload: &ListDatabasesResponse) -> String {
+    if payload.databases.is_empty() {
+        return format!(
+            "No index databases found under {}. Run ingest_codebase to create one.",
+            payload.root
+        );
+    }
+
+    let entries = payload
+        .databases
+        .iter()
+        .map(|database| {
+            let mut entry = format!(
+                "{} ({}, schema v{}",
+                database.name,
+                format_bytes(database.size_bytes),
+                database.schema_version
+            );
+            if !database.embedding_models.is_empty() {
+                entry.push_str(&format!(", {}", database.embedding_models.join("/")));
+            }
+            match &database.latest_ingestion {
+                Some(latest) => {
+                    entry.push_str(&format!(", last ingest {} file(s)", latest.file_count))
+                }
+                None => entry.push_str(", never ingested"),
+            }
+            entry.push(')');
+            entry
+        })
+        .collect::<Vec<_>>()
+        .join("; ");
+
+    format!(
+        "Found {} index database(s) under {}: {}. Pass a name as databaseName to target it.",
+        payload.databases.len(),
+        payload.root,
+        entries
+    )
+}
+
 fn convert_semantic_search_error(error: SemanticSearchError) -> McpError {
     match error {
         SemanticSearchError::InvalidRoot { path, source } => {
@@ -974,11 +3474,12 @@ fn convert_semantic_search_error(error: SemanticSearchError) -> McpError {
         SemanticSearchError::Embedding(message) => {
             McpError::internal_error(format!("Embedding failed: {message}"), None)
         }
+        SemanticSearchError::Sandbox(error) => convert_sandbox_error(error),
         SemanticSearchError::Join(source) => {
             McpError::internal_error(format!("Background task failed: {source}"), None)
         }
         SemanticSearchError::MultipleModels { available } => McpError::invalid_params(
-            format!("Multiple embedding models found ({available}). Specify the desired model."),
+            format!("Multiple embedding models found ({available}). Specify the desired model, or pass `models` to search them all and fuse the rankings."),
             None,
         ),
         SemanticSearchError::ModelNotFound { requested, available } => McpError::invalid_params(
@@ -987,6 +3488,30 @@ fn convert_semantic_search_error(error: SemanticSearchError) -> McpError {
             ),
             None,
         ),
+        SemanticSearchError::NoRegisteredRoots => McpError::invalid_params(
+            "allRoots needs INDEX_MCP_ALLOWED_ROOTS to list the workspace roots; pass roots instead."
+                .to_string(),
+            None,
+        ),
+        SemanticSearchError::NoWorktrees { root } => McpError::invalid_params(
+            format!("worktrees needs '{root}' to be inside a git repository; pass roots instead."),
+            None,
+        ),
+        SemanticSearchError::Generation(error) => convert_generation_error(error),
+        SemanticSearchError::Glob(error) => convert_ingest_error(error),
+        SemanticSearchError::PathBoosts(error) => {
+            McpError::invalid_params(error.to_string(), None)
+        }
+    }
+}
+
+fn convert_generation_error(error: GenerationError) -> McpError {
+    match error {
+        GenerationError::Sqlite(source) => {
+            McpError::internal_error(format!("SQLite error: {source}"), None)
+        }
+        GenerationError::Snapshot { .. } => McpError::internal_error(error.to_string(), None),
+        other => McpError::invalid_params(other.to_string(), None),
     }
 }
 
@@ -1015,21 +3540,42 @@ fn build_search_suggestions(
     response: &SemanticSearchResponse,
 ) -> Vec<SuggestedTool> {
     const MAX_SUGGESTIONS: usize = 3;
+    const SUGGESTION_CANDIDATES: usize = 8;
     if response.results.is_empty() {
         return Vec::new();
     }
 
     let budget_hint = snapshot.bundle_budget().min(u32::MAX as usize) as u32;
+    let preview_chars = snapshot
+        .search_adaptation()
+        .map_or(DEFAULT_PREVIEW_CHARS, |adaptation| adaptation.preview_chars);
 
-    response
+    // prefer hits that look like definition sites so the follow-up bundle
+    // lands on the symbol rather than one of its callers
+    let mut candidates: Vec<(f32, &SemanticSearchMatch)> = response
         .results
         .iter()
+        .take(SUGGESTION_CANDIDATES)
+        .map(|result| {
+            let boost = result
+                .graph_signal
+                .as_ref()
+                .map(definition_site_boost)
+                .unwrap_or(0.0);
+            (result.rank_score + boost, result)
+        })
+        .collect();
+    candidates.sort_by(|left, right| right.0.total_cmp(&left.0));
+
+    candidates
+        .into_iter()
+        .map(|(_, result)| result)
         .take(MAX_SUGGESTIONS)
         .enumerate()
         .map(|(index, result)| {
             let mut params = Map::new();
-            if let Some(cwd) = snapshot.cwd.clone() {
-                params.insert("root".to_string(), json!(cwd));
+            if let Some(root) = result.root.clone().or_else(|| snapshot.cwd.clone()) {
+                params.insert("root".to_string(), json!(root));
             }
             params.insert("file".to_string(), json!(result.path));
             if let Some(database_name) = response.database_name.as_ref() {
@@ -1072,12 +3618,16 @@ fn build_search_suggestions(
                 }
             }
 
-            let preview = snippet_preview(&result.content, result.context_before.as_deref());
+            let preview = snippet_preview(
+                &result.content,
+                result.context_before.as_deref(),
+                preview_chars,
+            );
 
             SuggestedTool {
                 tool: "context_bundle".to_string(),
                 rank: (index as u32) + 1,
-                score: result.normalized_score,
+                score: result.rank_score,
                 description: Some(description),
                 preview,
                 parameters: Value::Object(params),
@@ -1086,7 +3636,21 @@ fn build_search_suggestions(
         .collect()
 }
 
-fn snippet_preview(content: &str, context_before: Option<&str>) -> Option<String> {
+fn definition_site_boost(signal: &GraphSignal) -> f32 {
+    let mut boost = 0.0;
+    if signal.defines_symbol {
+        boost += 0.15;
+    }
+    boost += (0.03 * (signal.incoming_edges as f32).ln_1p()).min(0.1);
+    boost += 0.005 * signal.definition_count.min(10) as f32;
+    boost
+}
+
+fn snippet_preview(
+    content: &str,
+    context_before: Option<&str>,
+    max_chars: usize,
+) -> Option<String> {
     let mut fragments = Vec::new();
     if let Some(before) = context_before {
         let trimmed = before.trim();
@@ -1100,15 +3664,14 @@ fn snippet_preview(content: &str, context_before: Option<&str>) -> Option<String
         if fragments.is_empty() {
             return None;
         }
-        return Some(compose_preview(&fragments));
+        return Some(compose_preview(&fragments, max_chars));
     }
     fragments.push(trimmed);
 
-    Some(compose_preview(&fragments))
+    Some(compose_preview(&fragments, max_chars))
 }
 
-fn compose_preview(segments: &[&str]) -> String {
-    const MAX_PREVIEW_LEN: usize = 160;
+fn compose_preview(segments: &[&str], max_chars: usize) -> String {
     let joined = segments
         .iter()
         .flat_map(|segment| segment.lines())
@@ -1118,29 +3681,35 @@ fn compose_preview(segments: &[&str]) -> String {
 
     let trimmed = joined.trim();
     let mut chars = trimmed.chars();
-    let mut preview: String = chars.by_ref().take(MAX_PREVIEW_LEN).collect();
+    let mut preview: String = chars.by_ref().take(max_chars).collect();
     if chars.next().is_some() {
         preview.push_str("...");
     }
     preview
 }
 
-fn build_code_lookup_result(
-    mode: String,
-    search_result: SemanticSearchResponse,
-    meta: Option<Meta>,
-) -> Result<CallToolResult, McpError> {
-    let summary = summarize_semantic_search(&search_result);
-    let payload = CodeLookupResponse {
-        mode,
-        summary: summary.clone(),
-        search_result: Some(search_result),
-        bundle_result: None,
+fn build_environment_result(snapshot: &EnvironmentSnapshot) -> Result<CallToolResult, McpError> {
+    let resolved_root = snapshot.cwd.clone().unwrap_or_else(|| {
+        std::env::current_dir()
+            .map(|path| path.display().to_string())
+            .unwrap_or_else(|_| "./".to_string())
+    });
+    let payload = EnvironmentResponse {
+        cwd: snapshot.cwd.clone(),
+        cwd_source: snapshot.cwd_source,
+        resolved_root,
+        bundle_budget_override: snapshot.bundle_budget_override,
+        budget_source: snapshot.budget_source,
+        remaining_context_tokens: snapshot.remaining_context_tokens,
+        remaining_source: snapshot.remaining_source,
+        effective_bundle_budget: snapshot.bundle_budget(),
+        recent_hit_count: snapshot.recent_hits.len(),
     };
+    let summary = summarize_environment(&payload);
 
     let value: Value = serde_json::to_value(&payload).map_err(|error| {
         McpError::internal_error(
-            format!("Failed to serialize code_lookup result: {error}"),
+            format!("Failed to serialize environment snapshot: {error}"),
             None,
         )
     })?;
@@ -1149,20 +3718,70 @@ fn build_code_lookup_result(
         content: vec![Content::text(summary)],
         structured_content: Some(value),
         is_error: Some(false),
-        meta,
+        meta: None,
     })
 }
 
-fn build_code_lookup_bundle_response(
+fn summarize_environment(payload: &EnvironmentResponse) -> String {
+    let root_origin = match payload.cwd_source {
+        Some(EnvironmentSource::SetEnvironment) => "set via set_environment",
+        Some(EnvironmentSource::Meta) => "from request _meta",
+        Some(EnvironmentSource::ClientRoots) => "first root advertised by the client",
+        None => "process working directory",
+    };
+    let mut summary = format!(
+        "Root {} ({}). Effective bundle budget {} tokens",
+        payload.resolved_root, root_origin, payload.effective_bundle_budget
+    );
+    if let Some(budget) = payload.bundle_budget_override {
+        summary.push_str(&format!(" (override {budget})"));
+    }
+    summary.push('.');
+    if let Some(remaining) = payload.remaining_context_tokens {
+        summary.push_str(&format!(" Remaining context {remaining} tokens."));
+    }
+    summary
+}
+
+fn resolve_code_lookup_mode(params: &CodeLookupParams) -> String {
+    params.mode.clone().unwrap_or_else(|| {
+        if params
+            .query
+            .as_ref()
+            .is_some_and(|value| !value.trim().is_empty())
+        {
+            "search".to_string()
+        } else if params
+            .file
+            .as_ref()
+            .is_some_and(|value| !value.trim().is_empty())
+        {
+            "bundle".to_string()
+        } else {
+            "search".to_string()
+        }
+    })
+}
+
+fn code_lookup_search_payload(
     mode: String,
-    bundle: ContextBundleResponse,
-    meta: Option<Meta>,
-) -> Result<CallToolResult, McpError> {
-    let summary = summarize_bundle(&bundle);
+    search_result: SemanticSearchResponse,
+) -> CodeLookupResponse {
+    CodeLookupResponse {
+        mode,
+        summary: summarize_semantic_search(&search_result),
+        search_result: Some(search_result),
+        bundle_result: None,
+    }
+}
 
-    let payload = CodeLookupResponse {
+fn code_lookup_bundle_payload(
+    mode: String,
+    bundle: ContextBundleResponse,
+) -> Result<CodeLookupResponse, McpError> {
+    Ok(CodeLookupResponse {
         mode,
-        summary: summary.clone(),
+        summary: summarize_bundle(&bundle),
         search_result: None,
         bundle_result: Some(serde_json::to_value(&bundle).map_err(|error| {
             McpError::internal_error(
@@ -1170,9 +3789,14 @@ fn build_code_lookup_bundle_response(
                 None,
             )
         })?),
-    };
+    })
+}
 
-    let value: Value = serde_json::to_value(&payload).map_err(|error| {
+fn build_code_lookup_response(
+    payload: &CodeLookupResponse,
+    meta: Option<Meta>,
+) -> Result<CallToolResult, McpError> {
+    let value: Value = serde_json::to_value(payload).map_err(|error| {
         McpError::internal_error(
             format!("Failed to serialize code_lookup result: {error}"),
             None,
@@ -1180,13 +3804,79 @@ fn build_code_lookup_bundle_response(
     })?;
 
     Ok(CallToolResult {
-        content: vec![Content::text(summary)],
+        content: vec![Content::text(payload.summary.clone())],
         structured_content: Some(value),
         is_error: Some(false),
         meta,
     })
 }
 
+fn code_lookup_token_usage(payload: &CodeLookupResponse) -> usize {
+    if let Some(search) = &payload.search_result {
+        return estimate_token_cost(&search.results);
+    }
+    payload
+        .bundle_result
+        .as_ref()
+        .and_then(|bundle| bundle.pointer("/usage/usedTokens"))
+        .and_then(|value| value.as_u64())
+        .unwrap_or(0) as usize
+}
+
+/// Drops a search lookup's lowest-ranked hits, then the remaining hits'
+/// context, until its estimated cost fits `share` tokens.
+fn fit_search_lookup(payload: &mut CodeLookupResponse, share: usize) {
+    let Some(search) = payload.search_result.as_mut() else {
+        return;
+    };
+    let served = search.results.len();
+    while search.results.len() > 1 && estimate_token_cost(&search.results) > share {
+        search.results.pop();
+    }
+    if estimate_token_cost(&search.results) > share {
+        for result in &mut search.results {
+            result.context_before = None;
+            result.context_after = None;
+        }
+    }
+    if estimate_token_cost(&search.results) > share {
+        search.results.clear();
+    }
+    if search.results.len() < served {
+        payload.summary.push_str(&format!(
+            " Kept {} of {} hit(s) to fit the batch's ~{} token share.",
+            search.results.len(),
+            served,
+            share
+        ));
+    }
+}
+
+fn summarize_code_lookup_batch(
+    entries: &[CodeLookupBatchEntry],
+    used_tokens: usize,
+    budget_tokens: usize,
+) -> String {
+    let failed = entries.iter().filter(|entry| entry.error.is_some()).count();
+    let mut lines = vec![format!(
+        "Batch of {} lookup(s): {} succeeded, {} failed. ~{} of {} shared tokens used.",
+        entries.len(),
+        entries.len() - failed,
+        failed,
+        used_tokens,
+        budget_tokens
+    )];
+    for entry in entries {
+        let detail = match (&entry.result, &entry.error) {
+            (Some(result), _) => result.summary.clone(),
+            (None, Some(error)) => format!("Error: {error}"),
+            (None, None) => String::new(),
+        };
+        lines.push(format!("[{}] {}", entry.index, detail));
+    }
+    lines.join("\n")
+}
+
 fn summarize_bundle(bundle: &ContextBundleResponse) -> String {
     let mut parts = Vec::new();
     parts.push(format!(
@@ -1195,6 +3885,20 @@ fn summarize_bundle(bundle: &ContextBundleResponse) -> String {
         bundle.definitions.len(),
         bundle.snippets.len()
     ));
+    if bundle.ephemeral {
+        parts.push("Built from the unsaved buffer (contentOverride); not cached.".to_string());
+    }
+    if bundle.content_drift {
+        parts.push(format!(
+            "Content drift: working tree differs from the index; {} snippet(s) remapped.",
+            bundle.remapped_snippets
+        ));
+    }
+    if let Some(generation) = bundle.generation {
+        parts.push(format!(
+            "Built from the generation {generation} snapshot, not the live index."
+        ));
+    }
 
     if let Some(focus) = &bundle.focus_definition {
         parts.push(format!("Focus on {} {}.", focus.kind, focus.name));
@@ -1205,6 +3909,14 @@ fn summarize_bundle(bundle: &ContextBundleResponse) -> String {
         ));
     }
 
+    if let Some(interface) = &bundle.interface {
+        parts.push(format!(
+            "Interface: {} import(s), {} export(s).",
+            interface.imports.len(),
+            interface.exports.len()
+        ));
+    }
+
     match summarize_snippets(bundle) {
         Some(detail) => parts.push(detail),
         None => {
@@ -1216,10 +3928,21 @@ fn summarize_bundle(bundle: &ContextBundleResponse) -> String {
         let label = match link.r#type {
             QuickLinkType::File => format!("file {}", link.label),
             QuickLinkType::RelatedSymbol => format!("symbol {}", link.label),
+            QuickLinkType::CoChange => link.label.clone(),
         };
         parts.push(format!("First quick link: {}.", label));
     }
 
+    let co_changes: Vec<&str> = bundle
+        .quick_links
+        .iter()
+        .filter(|link| matches!(link.r#type, QuickLinkType::CoChange))
+        .filter_map(|link| link.path.as_deref())
+        .collect();
+    if !co_changes.is_empty() {
+        parts.push(format!("Usually changes with {}.", co_changes.join(", ")));
+    }
+
     parts.push(format!(
         "Token usage {} of {} ({} unused).",
         bundle.usage.used_tokens, bundle.usage.budget_tokens, bundle.usage.remaining_tokens
@@ -1338,12 +4061,37 @@ fn estimate_token_cost(results: &[SemanticSearchMatch]) -> usize {
 }
 
 fn build_search_filter_summary(request: &SemanticSearchRequest) -> Option<Value> {
-    filters_to_value(
+    let mut summary = filters_to_value(
         &request.language,
         &request.path_prefix,
         &request.path_contains,
         &request.classification,
-    )
+    );
+    if let Some(paths) = request
+        .only_paths
+        .as_ref()
+        .filter(|paths| !paths.is_empty())
+    {
+        let value = summary.get_or_insert_with(|| Value::Object(Map::new()));
+        value["onlyPaths"] = json!(paths.len());
+    }
+    if let Some(package) = request
+        .package
+        .as_ref()
+        .filter(|package| !package.trim().is_empty())
+    {
+        let value = summary.get_or_insert_with(|| Value::Object(Map::new()));
+        value["package"] = json!(package);
+    }
+    if let Some(weight) = request.recency_weight.filter(|weight| *weight > 0.0) {
+        let value = summary.get_or_insert_with(|| Value::Object(Map::new()));
+        value["recencyWeight"] = json!(weight);
+    }
+    if let Some(exclude) = request.exclude.as_ref().filter(|globs| !globs.is_empty()) {
+        let value = summary.get_or_insert_with(|| Value::Object(Map::new()));
+        value["exclude"] = json!(exclude);
+    }
+    with_preset(summary, &request.preset)
 }
 
 fn build_lookup_filter_summary(
@@ -1351,8 +4099,58 @@ fn build_lookup_filter_summary(
     path_prefix: &Option<String>,
     path_contains: &Option<String>,
     classification: &Option<Classification>,
+    preset: &Option<String>,
 ) -> Option<Value> {
-    filters_to_value(language, path_prefix, path_contains, classification)
+    with_preset(
+        filters_to_value(language, path_prefix, path_contains, classification),
+        preset,
+    )
+}
+
+/// Names the preset so the summary explains filters the caller never typed.
+fn with_preset(summary: Option<Value>, preset: &Option<String>) -> Option<Value> {
+    let Some(preset) = preset.as_ref().filter(|preset| !preset.trim().is_empty()) else {
+        return summary;
+    };
+    let mut summary = summary.unwrap_or_else(|| Value::Object(Map::new()));
+    summary["preset"] = json!(preset);
+    Some(summary)
+}
+
+fn apply_search_preset(params: &mut SemanticSearchRequest) -> Result<(), McpError> {
+    let Some(preset) =
+        resolve_filter_preset(params.preset.as_deref()).map_err(convert_filter_preset_error)?
+    else {
+        return Ok(());
+    };
+    fill(&mut params.path_prefix, preset.path_prefix);
+    fill(&mut params.path_contains, preset.path_contains);
+    fill(&mut params.language, preset.language);
+    fill(&mut params.classification, preset.classification);
+    fill(&mut params.package, preset.package);
+    fill(&mut params.include, preset.include);
+    merge_globs(&mut params.exclude, preset.exclude);
+    Ok(())
+}
+
+fn convert_filter_preset_error(error: FilterPresetError) -> McpError {
+    match error {
+        FilterPresetError::Invalid(message) => McpError::invalid_request(
+            format!("INDEX_MCP_FILTER_PRESETS is not a JSON object of presets: {message}"),
+            None,
+        ),
+        FilterPresetError::Unknown { name, available } => McpError::invalid_params(
+            if available.is_empty() {
+                format!("Unknown filter preset '{name}'; INDEX_MCP_FILTER_PRESETS defines none.")
+            } else {
+                format!(
+                    "Unknown filter preset '{name}'. Configured presets: {}.",
+                    available.join(", ")
+                )
+            },
+            Some(json!({ "preset": name, "available": available })),
+        ),
+    }
 }
 
 fn filters_to_value(
@@ -1408,6 +4206,8 @@ fn convert_context_bundle_error(error: ContextBundleError) -> McpError {
                 McpError::internal_error(format!("Failed to access '{path}': {source}"), None)
             }
         }
+        ContextBundleError::Sandbox(error) => convert_sandbox_error(error),
+        ContextBundleError::Generation(error) => convert_generation_error(error),
         ContextBundleError::Join(source) => {
             McpError::internal_error(format!("Background task failed: {source}"), None)
         }
@@ -1442,6 +4242,28 @@ fn convert_repository_timeline_error(error: RepositoryTimelineError) -> McpError
     }
 }
 
+fn convert_timeline_query_error(error: TimelineQueryError) -> McpError {
+    match error {
+        TimelineQueryError::InvalidRoot { path, source } => {
+            McpError::invalid_params(format!("Unable to resolve root '{path}': {source}"), None)
+        }
+        TimelineQueryError::InvalidPattern { pattern, source } => {
+            McpError::invalid_params(format!("Invalid path pattern '{pattern}': {source}"), None)
+        }
+        TimelineQueryError::InvalidDate { value } => McpError::invalid_params(
+            format!("Invalid date '{value}'; use YYYY-MM-DD or a span such as 30d, 6w, 3m, 1y."),
+            None,
+        ),
+        TimelineQueryError::Sqlite(source) => {
+            McpError::internal_error(format!("SQLite error: {source}"), None)
+        }
+        TimelineQueryError::Sandbox(error) => convert_sandbox_error(error),
+        TimelineQueryError::Join(source) => {
+            McpError::internal_error(format!("Background task failed: {source}"), None)
+        }
+    }
+}
+
 fn build_context_bundle_result(
     response: ContextBundleResponse,
     meta: Option<Meta>,
@@ -1512,6 +4334,18 @@ fn build_repository_timeline_result(
         summary
             .push_str(" Diffs cached in SQLite; call repository_timeline_entry for full output.");
     }
+    if let Some(retention) = &response.diff_retention {
+        summary.push_str(&format!(
+            " Retention evicted {} cached diff(s) by age and {} by size, freeing {}.",
+            retention.evicted_by_age,
+            retention.evicted_by_size,
+            format_bytes(retention.freed_bytes)
+        ));
+    }
+    if let Some(adjusted) = describe_adjustments(&response.adjustments) {
+        summary.push(' ');
+        summary.push_str(&adjusted);
+    }
 
     let value: Value = serde_json::to_value(&response).map_err(|error| {
         McpError::internal_error(
@@ -1537,12 +4371,29 @@ fn build_repository_timeline_entry_result(
             "repository_timeline_entry: retrieved diff for commit {} ({} bytes cached).",
             response.entry.sha, diff_len
         )
+    } else if let Some(tombstone) = &response.diff_evicted {
+        let reason = match tombstone.reason {
+            DiffEvictionReason::MaxAge => "it exceeded the retention age",
+            DiffEvictionReason::MaxTotalBytes => "the diff cache exceeded its byte budget",
+        };
+        format!(
+            "repository_timeline_entry: diff for commit {} ({} bytes) was evicted because {reason}; rerun repository_timeline with includeDiffs to recapture it.",
+            response.entry.sha, tombstone.original_bytes
+        )
     } else {
         format!(
             "repository_timeline_entry: no diff stored for commit {}.",
             response.entry.sha
         )
     };
+    let summary = match &response.filters {
+        Some(filters) if !filters.paths.is_empty() => format!(
+            "{summary} Stats were captured with path filters: {}.",
+            filters.paths.join(", ")
+        ),
+        Some(_) => format!("{summary} Stats were captured without file stats."),
+        None => summary,
+    };
 
     let value: Value = serde_json::to_value(&response).map_err(|error| {
         McpError::internal_error(
@@ -1559,14 +4410,58 @@ fn build_repository_timeline_entry_result(
     })
 }
 
+fn build_timeline_query_result(
+    response: TimelineQueryResponse,
+) -> Result<CallToolResult, McpError> {
+    let mut summary = if response.cached_commits == 0 {
+        "timeline_query: no cached commits; run repository_timeline first to populate the cache."
+            .to_string()
+    } else {
+        let mut summary = format!(
+            "timeline_query: {} of {} cached commit(s) matched.",
+            response.total_matches, response.cached_commits
+        );
+        if let Some(latest) = response.entries.first() {
+            summary.push_str(&format!(
+                " Latest: {} {} ({}).",
+                latest.sha.get(..7).unwrap_or(&latest.sha),
+                latest.subject,
+                latest.author.name
+            ));
+        }
+        summary
+    };
+    if let Some(adjusted) = describe_adjustments(&response.adjustments) {
+        summary.push(' ');
+        summary.push_str(&adjusted);
+    }
+
+    let value: Value = serde_json::to_value(&response).map_err(|error| {
+        McpError::internal_error(
+            format!("Failed to serialize timeline query result: {error}"),
+            None,
+        )
+    })?;
+
+    Ok(CallToolResult {
+        content: vec![Content::text(summary)],
+        structured_content: Some(value),
+        is_error: Some(false),
+        meta: None,
+    })
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
     use crate::bundle::{
         BundleDefinition, BundleFileMetadata, BundleSnippet, ContextBundleQuickLink,
-        ContextBundleResponse, QuickLinkType, SnippetSource,
+        ContextBundleResponse, QuickLinkReason, QuickLinkType, SnippetSource,
+    };
+    use crate::index_status::{
+        DatabaseSummary, HealthDimension, IndexHealth, IndexStatusIngestion, IndexStatusResponse,
+        ListDatabasesResponse,
     };
-    use crate::index_status::{IndexStatusIngestion, IndexStatusResponse};
     use crate::ingest::IngestResponse;
     use crate::search::{Classification, SemanticSearchMatch, SemanticSearchResponse};
     use serde_json::json;
@@ -1578,15 +4473,23 @@ mod tests {
             database_path: "/workspace/.mcp-index.sqlite".into(),
             database_size_bytes: 1_024,
             ingested_file_count: 3,
+            skipped_count: 0,
+            skipped_by_reason: Default::default(),
             skipped: Vec::new(),
             deleted_paths: Vec::new(),
             duration_ms: 1_500,
             embedded_chunk_count: 42,
+            embedding_cache_hits: 0,
             embedding_model: Some("Xenova/all-MiniLM-L6-v2".into()),
+            chunk_normalization: None,
+            mock_embeddings: false,
             graph_node_count: 0,
             graph_edge_count: 0,
             evicted: None,
             reused_file_count: Some(1),
+            gitignore_overrides: Vec::new(),
+            truncated_files: Vec::new(),
+            generation: None,
         };
 
         let summary = summarize_ingest(&payload);
@@ -1624,6 +4527,19 @@ mod tests {
             indexed_at: Some(0),
             current_commit_sha: Some("bbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbb".into()),
             is_stale: true,
+            changed_paths: None,
+            read_only: false,
+            packages: Vec::new(),
+            health: IndexHealth {
+                score: 62,
+                status: HealthStatus::Red,
+                dimensions: vec![HealthDimension {
+                    name: "freshness".into(),
+                    score: 30,
+                    status: HealthStatus::Red,
+                    detail: "Indexed commit differs from HEAD; last indexed 3 day(s) ago.".into(),
+                }],
+            },
         };
 
         let summary = summarize_index_status(&payload);
@@ -1631,6 +4547,70 @@ mod tests {
         assert!(summary.contains("Size 10.0 MiB."));
         assert!(summary.contains("Index is stale (stored aaaaaaa vs. workspace bbbbbbb)."));
         assert!(summary.contains("Embedding models: model-A, model-B."));
+        assert!(summary.contains("Health 62/100 (red). Weakest: freshness"));
+    }
+
+    #[test]
+    fn summarize_list_databases_names_each_database() {
+        let payload = ListDatabasesResponse {
+            root: "/workspace".into(),
+            databases: vec![
+                DatabaseSummary {
+                    name: ".mcp-index.sqlite".into(),
+                    database_path: "/workspace/.mcp-index.sqlite".into(),
+                    size_bytes: 2_048,
+                    schema_version: 1,
+                    embedding_models: vec!["model-A".into()],
+                    total_files: 4,
+                    total_chunks: 9,
+                    latest_ingestion: None,
+                    is_default: true,
+                },
+                DatabaseSummary {
+                    name: "tools/legacy.sqlite".into(),
+                    database_path: "/workspace/tools/legacy.sqlite".into(),
+                    size_bytes: 1_024,
+                    schema_version: 0,
+                    embedding_models: Vec::new(),
+                    total_files: 0,
+                    total_chunks: 0,
+                    latest_ingestion: None,
+                    is_default: false,
+                },
+            ],
+        };
+
+        let summary = summarize_list_databases(&payload);
+
+        assert!(summary.starts_with("Found 2 index database(s) under /workspace"));
+        assert!(summary.contains(".mcp-index.sqlite (2.0 KiB, schema v1, model-A"));
+        assert!(summary.contains("tools/legacy.sqlite (1.0 KiB, schema v0, never ingested)"));
+    }
+
+    #[test]
+    fn merge_log_directives_replaces_matching_targets() {
+        let merged = crate::log_control::merge_directives(
+            "info,rmcp::service=info,hf_hub=warn",
+            "rmcp::service=debug, trace",
+        );
+
+        assert_eq!(merged, "hf_hub=warn,rmcp::service=debug,trace");
+    }
+
+    #[test]
+    fn audit_redaction_masks_credentials_and_keeps_counts() {
+        let redacted = crate::audit::redact_arguments(&json!({
+            "root": "/workspace",
+            "budgetTokens": 800,
+            "auth": { "accessToken": "abc", "apiKey": "def" },
+            "query": "x".repeat(500),
+        }));
+
+        assert_eq!(redacted["root"], "/workspace");
+        assert_eq!(redacted["budgetTokens"], 800);
+        assert_eq!(redacted["auth"]["accessToken"], "[redacted]");
+        assert_eq!(redacted["auth"]["apiKey"], "[redacted]");
+        assert!(redacted["query"].as_str().unwrap().chars().count() < 500);
     }
 
     #[test]
@@ -1644,6 +4624,8 @@ mod tests {
                 hash: "abc123".into(),
                 last_indexed_at: 1_710_000_123,
                 brief: None,
+                text_encoding: None,
+                line_ending: None,
                 content: None,
             },
             definitions: vec![BundleDefinition {
@@ -1657,9 +4639,11 @@ mod tests {
                 visibility: Some("pub".into()),
                 docstring: None,
                 todo_count: None,
+                metrics: None,
             }],
             focus_definition: None,
             related: Vec::new(),
+            interface: None,
             snippets: vec![BundleSnippet {
                 source: SnippetSource::Chunk,
                 chunk_index: Some(0),
@@ -1669,19 +4653,25 @@ mod tests {
                 line_start: Some(1),
                 line_end: Some(1),
                 served_count: None,
+                provenance: None,
+                changes: None,
             }],
             latest_ingestion: None,
             warnings: vec!["No graph metadata".into()],
+            range_corrections: Vec::new(),
             quick_links: vec![ContextBundleQuickLink {
                 r#type: QuickLinkType::File,
+                reason: QuickLinkReason::File,
                 label: "src/lib.rs".into(),
                 path: Some("src/lib.rs".into()),
                 direction: None,
                 symbol_id: None,
                 symbol_kind: None,
+                co_change_commits: None,
             }],
             usage: crate::bundle::BundleUsageStats {
                 definitions_tokens: 10,
+                interface_tokens: 0,
                 snippet_tokens: 12,
                 used_tokens: 22,
                 budget_tokens: 3_000,
@@ -1691,6 +4681,13 @@ mod tests {
                 summary_snippets: 0,
                 cache_hit: false,
             },
+            ephemeral: false,
+            adjustments: Vec::new(),
+            compared_to_commit: None,
+            content_drift: false,
+            disk_hash: None,
+            remapped_snippets: 0,
+            generation: None,
         };
 
         let summary = summarize_bundle(&bundle);
@@ -1712,11 +4709,14 @@ mod tests {
             embedding_model: Some("custom-model".into()),
             total_chunks: 1_000,
             evaluated_chunks: 250,
+            routed_files: None,
             results: vec![SemanticSearchMatch {
+                root: None,
                 path: "src/main.rs".into(),
                 chunk_index: 0,
                 score: 0.92,
                 normalized_score: 0.87,
+                rank_score: 0.87,
                 language: Some("Rust".into()),
                 classification: Classification::Function,
                 content: "fn main() {}".into(),
@@ -1727,9 +4727,30 @@ mod tests {
                 line_end: Some(45),
                 context_before: None,
                 context_after: None,
+                context_scope: None,
+                permalink: None,
+                recency_score: None,
+                symbol: None,
+                documents: None,
+                graph_signal: None,
+                merged_hits: None,
+                matched_models: Vec::new(),
+                also_in_roots: Vec::new(),
+                path_boost: None,
+                stale: false,
             }],
             summary_mode: SummaryMode::Brief,
             suggested_tools: Vec::new(),
+            fallback: None,
+            mock_embeddings: false,
+            freshness: None,
+            dimension_mismatch: None,
+            corrupt_chunks: None,
+            adjustments: Vec::new(),
+            roots: Vec::new(),
+            models: Vec::new(),
+            generation: None,
+            search_strategy: None,
         };
 
         let summary = crate::search::summarize_semantic_search(&response);
@@ -1755,11 +4776,14 @@ mod tests {
             embedding_model: Some("model".into()),
             total_chunks: 100,
             evaluated_chunks: 50,
+            routed_files: None,
             results: vec![SemanticSearchMatch {
+                root: None,
                 path: "src/lib.rs".into(),
                 chunk_index: 7,
                 score: 0.91,
                 normalized_score: 0.82,
+                rank_score: 0.82,
                 language: Some("Rust".into()),
                 classification: Classification::Function,
                 content: "fn sample() { /* ... */ }".into(),
@@ -1770,9 +4794,30 @@ mod tests {
                 line_end: Some(44),
                 context_before: None,
                 context_after: None,
+                context_scope: None,
+                permalink: None,
+                recency_score: None,
+                symbol: None,
+                documents: None,
+                graph_signal: None,
+                merged_hits: None,
+                matched_models: Vec::new(),
+                also_in_roots: Vec::new(),
+                path_boost: None,
+                stale: false,
             }],
             summary_mode: SummaryMode::Brief,
             suggested_tools: Vec::new(),
+            fallback: None,
+            mock_embeddings: false,
+            freshness: None,
+            dimension_mismatch: None,
+            corrupt_chunks: None,
+            adjustments: Vec::new(),
+            roots: Vec::new(),
+            models: Vec::new(),
+            generation: None,
+            search_strategy: None,
         };
 
         let suggestions = build_search_suggestions(&snapshot, &response);
@@ -1795,6 +4840,86 @@ mod tests {
         assert_eq!(range.get("endLine"), Some(&json!(46)));
     }
 
+    #[test]
+    fn build_search_suggestions_prefer_definition_sites() {
+        let snapshot = EnvironmentSnapshot {
+            cwd: None,
+            bundle_budget_override: None,
+            remaining_context_tokens: None,
+            recent_hits: Vec::new(),
+        };
+        let hit = |path: &str, normalized_score: f32, graph_signal: Option<GraphSignal>| {
+            SemanticSearchMatch {
+                root: None,
+                path: path.into(),
+                chunk_index: 0,
+                score: normalized_score,
+                normalized_score,
+                rank_score: normalized_score,
+                language: Some("TypeScript".into()),
+                classification: Classification::Code,
+                content: "parseConfig(input)".into(),
+                embedding_model: "model".into(),
+                byte_start: None,
+                byte_end: None,
+                line_start: None,
+                line_end: None,
+                context_before: None,
+                context_after: None,
+                context_scope: None,
+                permalink: None,
+                recency_score: None,
+                symbol: None,
+                documents: None,
+                graph_signal,
+                merged_hits: None,
+                matched_models: Vec::new(),
+                also_in_roots: Vec::new(),
+                path_boost: None,
+                stale: false,
+            }
+        };
+
+        let response = SemanticSearchResponse {
+            database_path: "db.sqlite".into(),
+            database_name: None,
+            embedding_model: Some("model".into()),
+            total_chunks: 10,
+            evaluated_chunks: 10,
+            routed_files: None,
+            results: vec![
+                hit("src/cli.ts", 0.80, Some(GraphSignal::default())),
+                hit(
+                    "src/config.ts",
+                    0.74,
+                    Some(GraphSignal {
+                        defines_symbol: true,
+                        definition_count: 4,
+                        incoming_edges: 6,
+                    }),
+                ),
+            ],
+            summary_mode: SummaryMode::Brief,
+            suggested_tools: Vec::new(),
+            fallback: None,
+            mock_embeddings: false,
+            freshness: None,
+            dimension_mismatch: None,
+            corrupt_chunks: None,
+            adjustments: Vec::new(),
+            roots: Vec::new(),
+            models: Vec::new(),
+            generation: None,
+            search_strategy: None,
+        };
+
+        let suggestions = build_search_suggestions(&snapshot, &response);
+        assert_eq!(suggestions.len(), 2);
+        assert_eq!(suggestions[0].rank, 1);
+        assert_eq!(suggestions[0].parameters["file"], json!("src/config.ts"));
+        assert_eq!(suggestions[1].parameters["file"], json!("src/cli.ts"));
+    }
+
     #[test]
     fn code_lookup_infers_bundle_mode_from_file_path_when_mode_missing() {
         let env = EnvironmentState::new();
@@ -1816,31 +4941,222 @@ mod tests {
             path_prefix: None,
             path_contains: None,
             classification: None,
+            package: None,
+            include: None,
+            exclude: None,
+            preset: None,
             summary_mode: None,
             max_context_before: None,
             max_context_after: None,
+            batch: None,
         };
 
         env.apply_code_lookup_defaults(&mut params);
 
-        let resolved_mode = params.mode.clone().unwrap_or_else(|| {
-            if params
-                .query
-                .as_ref()
-                .is_some_and(|value| !value.trim().is_empty())
-            {
-                "search".to_string()
-            } else if params
-                .file
-                .as_ref()
-                .is_some_and(|value| !value.trim().is_empty())
-            {
-                "bundle".to_string()
-            } else {
-                "search".to_string()
-            }
-        });
+        let resolved_mode = resolve_code_lookup_mode(&params);
 
         assert_eq!(resolved_mode, "bundle");
     }
+
+    #[test]
+    fn low_remaining_context_trims_search_context_lines() {
+        let env = EnvironmentState::new();
+        env.apply_overrides(&SetEnvironmentParams {
+            cwd: None,
+            budget_tokens: None,
+            remaining_context_tokens: Some(2_500),
+            reset: None,
+        });
+        let mut params: CodeLookupParams = serde_json::from_value(json!({
+            "query": "parse config",
+            "maxContextBefore": 5,
+        }))
+        .expect("valid params");
+
+        env.apply_code_lookup_defaults(&mut params);
+
+        assert_eq!(params.max_context_before, Some(0));
+        assert_eq!(params.max_context_after, Some(0));
+    }
+
+    #[test]
+    fn set_environment_overrides_are_not_replaced_by_meta() {
+        let env = EnvironmentState::new();
+        env.apply_overrides(&SetEnvironmentParams {
+            cwd: Some("/workspace/project".into()),
+            budget_tokens: Some(1_200),
+            remaining_context_tokens: None,
+            reset: None,
+        });
+
+        let mut meta = Meta::new();
+        meta.insert("cwd".to_string(), json!("/elsewhere"));
+        meta.insert("remainingContextTokens".to_string(), json!(50_000));
+        env.update_from_meta(&meta);
+
+        let snapshot = env.snapshot();
+        assert_eq!(snapshot.cwd.as_deref(), Some("/workspace/project"));
+        assert_eq!(snapshot.cwd_source, Some(EnvironmentSource::SetEnvironment));
+        assert_eq!(snapshot.bundle_budget_override, Some(1_200));
+        assert_eq!(snapshot.remaining_context_tokens, Some(50_000));
+        assert_eq!(snapshot.remaining_source, Some(EnvironmentSource::Meta));
+
+        env.apply_overrides(&SetEnvironmentParams {
+            cwd: None,
+            budget_tokens: None,
+            remaining_context_tokens: None,
+            reset: Some(true),
+        });
+        let snapshot = env.snapshot();
+        assert!(snapshot.cwd.is_none());
+        assert!(snapshot.bundle_budget_override.is_none());
+    }
+
+    #[test]
+    fn client_roots_outrank_meta_but_not_set_environment() {
+        let env = EnvironmentState::new();
+        env.apply_client_roots(&[Root {
+            uri: "file:///workspace/client".into(),
+            name: None,
+        }]);
+        let mut meta = Meta::new();
+        meta.insert("cwd".to_string(), json!("/elsewhere"));
+        env.update_from_meta(&meta);
+        let snapshot = env.snapshot();
+        assert_eq!(snapshot.cwd.as_deref(), Some("/workspace/client"));
+        assert_eq!(snapshot.cwd_source, Some(EnvironmentSource::ClientRoots));
+
+        env.apply_overrides(&SetEnvironmentParams {
+            cwd: Some("/workspace/pinned".into()),
+            budget_tokens: None,
+            remaining_context_tokens: None,
+            reset: None,
+        });
+        env.apply_client_roots(&[Root {
+            uri: "file:///workspace/other".into(),
+            name: None,
+        }]);
+        assert_eq!(env.snapshot().cwd.as_deref(), Some("/workspace/pinned"));
+    }
+
+    #[test]
+    fn code_lookup_batch_searches_fit_their_share() {
+        let hit = |chunk_index: i32| SemanticSearchMatch {
+            root: None,
+            path: "src/lib.rs".into(),
+            chunk_index,
+            score: 0.9,
+            normalized_score: 0.9,
+            rank_score: 0.9,
+            language: Some("Rust".into()),
+            classification: Classification::Code,
+            content: "x".repeat(400),
+            embedding_model: "model".into(),
+            byte_start: None,
+            byte_end: None,
+            line_start: None,
+            line_end: None,
+            context_before: Some("y".repeat(200)),
+            context_after: None,
+            context_scope: None,
+            permalink: None,
+            recency_score: None,
+            symbol: None,
+            documents: None,
+            graph_signal: None,
+            merged_hits: None,
+            matched_models: Vec::new(),
+            also_in_roots: Vec::new(),
+            path_boost: None,
+            stale: false,
+        };
+        let search = |hits: usize| CodeLookupResponse {
+            mode: "search".into(),
+            summary: "Semantic search returned hits.".into(),
+            search_result: Some(SemanticSearchResponse {
+                database_path: "db.sqlite".into(),
+                database_name: None,
+                embedding_model: Some("model".into()),
+                total_chunks: 100,
+                evaluated_chunks: 100,
+                routed_files: None,
+                results: (0..hits as i32).map(hit).collect(),
+                summary_mode: SummaryMode::Full,
+                suggested_tools: Vec::new(),
+                fallback: None,
+                mock_embeddings: false,
+                freshness: None,
+                dimension_mismatch: None,
+                corrupt_chunks: None,
+                adjustments: Vec::new(),
+                roots: Vec::new(),
+                models: Vec::new(),
+                generation: None,
+                search_strategy: None,
+            }),
+            bundle_result: None,
+        };
+        let budget_tokens = 1_000;
+        // one bundle at its full share and three searches of ~150 tokens a hit
+        let share = budget_tokens / 4;
+        let mut payloads = vec![
+            CodeLookupResponse {
+                mode: "bundle".into(),
+                summary: "Context bundle prepared for src/lib.rs.".into(),
+                search_result: None,
+                bundle_result: Some(json!({ "usage": { "usedTokens": share } })),
+            },
+            search(10),
+            search(1),
+            search(0),
+        ];
+        for payload in &mut payloads {
+            fit_search_lookup(payload, share);
+        }
+
+        let used_tokens: usize = payloads.iter().map(code_lookup_token_usage).sum();
+        assert!(
+            used_tokens <= budget_tokens,
+            "{used_tokens} > {budget_tokens}"
+        );
+        let kept = payloads[1]
+            .search_result
+            .as_ref()
+            .map(|search| search.results.len());
+        assert_eq!(kept, Some(1));
+        assert!(payloads[1].summary.contains("Kept 1 of 10 hit(s)"));
+    }
+
+    #[test]
+    fn summarize_code_lookup_batch_reports_failures_in_request_order() {
+        let entries = vec![
+            CodeLookupBatchEntry {
+                index: 0,
+                result: Some(CodeLookupResponse {
+                    mode: "bundle".into(),
+                    summary: "Context bundle prepared for src/lib.rs.".into(),
+                    search_result: None,
+                    bundle_result: Some(json!({ "usage": { "usedTokens": 420 } })),
+                }),
+                error: None,
+            },
+            CodeLookupBatchEntry {
+                index: 1,
+                result: None,
+                error: Some("code_lookup search mode requires a query.".into()),
+            },
+        ];
+
+        let used = entries
+            .iter()
+            .filter_map(|entry| entry.result.as_ref())
+            .map(code_lookup_token_usage)
+            .sum();
+        let summary = summarize_code_lookup_batch(&entries, used, 2_000);
+
+        assert_eq!(used, 420);
+        assert!(summary.starts_with("Batch of 2 lookup(s): 1 succeeded, 1 failed."));
+        assert!(summary.contains("[0] Context bundle prepared for src/lib.rs."));
+        assert!(summary.contains("[1] Error: code_lookup search mode requires a query."));
+    }
 }
@@ -0,0 +1,211 @@
+use std::path::PathBuf;
+
+use rmcp::schemars::{self, JsonSchema};
+use rusqlite::{params, Connection, OptionalExtension};
+use serde::{Deserialize, Serialize};
+use thiserror::Error;
+
+use crate::data_dir::{database_path, open_read_only};
+use crate::index_status::DEFAULT_DB_FILENAME;
+use crate::ingest::SkippedFile;
+use crate::roots::resolve_workspace_root;
+use crate::sandbox::{ensure_root_allowed, SandboxError};
+
+const DEFAULT_PAGE_SIZE: u32 = 100;
+const MAX_PAGE_SIZE: u32 = 500;
+
+#[derive(Debug, Deserialize, JsonSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct ListSkippedParams {
+    #[serde(default)]
+    pub root: Option<String>,
+    #[serde(default)]
+    pub database_name: Option<String>,
+    /// One of the recently retained ingestions; defaults to the latest.
+    #[serde(default)]
+    pub ingestion_id: Option<String>,
+    /// Only return files skipped for this reason, e.g. `max_file_size`.
+    #[serde(default)]
+    pub reason: Option<String>,
+    #[serde(default)]
+    pub offset: Option<u32>,
+    /// Page size (default 100, max 500).
+    #[serde(default)]
+    pub limit: Option<u32>,
+}
+
+#[derive(Debug, Clone, Serialize, JsonSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct ListSkippedResponse {
+    pub database_path: String,
+    /// Ingestion the records belong to; `None` before the first ingest.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub ingestion_id: Option<String>,
+    pub total: usize,
+    pub offset: usize,
+    pub entries: Vec<SkippedFile>,
+    /// Pass as `offset` for the next page; `None` on the last page.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub next_offset: Option<usize>,
+}
+
+/// Why the most recent ingestion that skipped a path left it out.
+#[derive(Debug, Clone, Serialize, JsonSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct RecordedSkip {
+    pub reason: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub message: Option<String>,
+    pub ingestion_id: String,
+    /// Unix milliseconds when that ingestion finished.
+    pub recorded_at: i64,
+}
+
+#[derive(Debug, Error)]
+pub enum ListSkippedError {
+    #[error("failed to resolve workspace root '{path}': {source}")]
+    InvalidRoot {
+        path: String,
+        #[source]
+        source: std::io::Error,
+    },
+    #[error("SQLite error: {0}")]
+    Sqlite(#[from] rusqlite::Error),
+    #[error(transparent)]
+    Sandbox(#[from] SandboxError),
+    #[error("blocking task panicked: {0}")]
+    Join(#[from] tokio::task::JoinError),
+}
+
+pub async fn list_skipped(
+    params: ListSkippedParams,
+) -> Result<ListSkippedResponse, ListSkippedError> {
+    tokio::task::spawn_blocking(move || load_skipped(params)).await?
+}
+
+fn load_skipped(params: ListSkippedParams) -> Result<ListSkippedResponse, ListSkippedError> {
+    let root = params.root.unwrap_or_else(|| "./".to_string());
+    let root_path = resolve_root(&root)?;
+    ensure_root_allowed(&root_path)?;
+    let database_path = database_path(
+        &root_path,
+        &params
+            .database_name
+            .unwrap_or_else(|| DEFAULT_DB_FILENAME.to_string()),
+    );
+    let database_path_string = database_path.to_string_lossy().to_string();
+    let offset = params.offset.unwrap_or(0) as usize;
+    let limit = params
+        .limit
+        .unwrap_or(DEFAULT_PAGE_SIZE)
+        .clamp(1, MAX_PAGE_SIZE) as usize;
+    let reason = params
+        .reason
+        .as_deref()
+        .map(str::trim)
+        .filter(|value| !value.is_empty());
+
+    let empty = || ListSkippedResponse {
+        database_path: database_path_string.clone(),
+        ingestion_id: None,
+        total: 0,
+        offset,
+        entries: Vec::new(),
+        next_offset: None,
+    };
+
+    if !database_path.is_file() {
+        return Ok(empty());
+    }
+    let conn = open_read_only(&database_path)?;
+    // databases from before skip records were persisted have no table
+    let table_exists: bool = conn.query_row(
+        "SELECT EXISTS(SELECT 1 FROM sqlite_master WHERE type = 'table' AND name = 'ingestion_skips')",
+        [],
+        |row| row.get(0),
+    )?;
+    if !table_exists {
+        return Ok(empty());
+    }
+
+    let ingestion_id: Option<String> = match params.ingestion_id {
+        Some(id) => Some(id),
+        None => conn
+            .query_row(
+                "SELECT id FROM ingestions ORDER BY finished_at DESC LIMIT 1",
+                [],
+                |row| row.get(0),
+            )
+            .optional()?,
+    };
+    let Some(ingestion_id) = ingestion_id else {
+        return Ok(empty());
+    };
+
+    let total: i64 = conn.query_row(
+        "SELECT COUNT(*) FROM ingestion_skips
+         WHERE ingestion_id = ?1 AND (?2 IS NULL OR reason = ?2)",
+        params![ingestion_id, reason],
+        |row| row.get(0),
+    )?;
+    let total = total as usize;
+    let mut stmt = conn.prepare(
+        "SELECT path, reason, size, message FROM ingestion_skips
+         WHERE ingestion_id = ?1 AND (?2 IS NULL OR reason = ?2)
+         ORDER BY rowid
+         LIMIT ?3 OFFSET ?4",
+    )?;
+    let entries = stmt
+        .query_map(
+            params![ingestion_id, reason, limit as i64, offset as i64],
+            |row| {
+                Ok(SkippedFile {
+                    path: row.get(0)?,
+                    reason: row.get(1)?,
+                    size: row.get(2)?,
+                    message: row.get(3)?,
+                })
+            },
+        )?
+        .collect::<Result<Vec<_>, _>>()?;
+
+    let next_offset = offset + entries.len();
+    Ok(ListSkippedResponse {
+        database_path: database_path_string,
+        ingestion_id: Some(ingestion_id),
+        total,
+        offset,
+        entries,
+        next_offset: (next_offset < total).then_some(next_offset),
+    })
+}
+
+/// Latest recorded skip for `path` across the retained ingestions, unless
+/// the path has been indexed since. Errors, including databases without skip
+/// records, read as `None`.
+pub(crate) fn recorded_skip(conn: &Connection, path: &str) -> Option<RecordedSkip> {
+    conn.query_row(
+        "SELECT s.reason, s.message, s.ingestion_id, i.finished_at
+         FROM ingestion_skips s JOIN ingestions i ON i.id = s.ingestion_id
+         WHERE s.path = ?1 AND NOT EXISTS (SELECT 1 FROM files WHERE path = ?1)
+         ORDER BY i.finished_at DESC
+         LIMIT 1",
+        params![path],
+        |row| {
+            Ok(RecordedSkip {
+                reason: row.get(0)?,
+                message: row.get(1)?,
+                ingestion_id: row.get(2)?,
+                recorded_at: row.get(3)?,
+            })
+        },
+    )
+    .ok()
+}
+
+fn resolve_root(root: &str) -> Result<PathBuf, ListSkippedError> {
+    resolve_workspace_root(root).map_err(|source| ListSkippedError::InvalidRoot {
+        path: root.to_string(),
+        source,
+    })
+}
@@ -0,0 +1,419 @@
+use std::collections::{BTreeMap, HashMap};
+use std::path::PathBuf;
+
+use rmcp::schemars::{self, JsonSchema};
+use rusqlite::types::Value as SqlValue;
+use rusqlite::Connection;
+use serde::{Deserialize, Serialize};
+use thiserror::Error;
+
+use crate::content_store::{content_encoding_column, decode_file_content};
+use crate::data_dir::{database_path, open_read_only};
+use crate::index_status::DEFAULT_DB_FILENAME;
+use crate::ingest::is_generated_file;
+use crate::roots::resolve_workspace_root;
+use crate::sandbox::{ensure_root_allowed, SandboxError};
+
+const DEFAULT_SUGGESTION_LIMIT: usize = 15;
+const MAX_SUGGESTION_LIMIT: usize = 100;
+const MAX_DIRECTORY_ROWS: usize = 10;
+// directory depth suggestions are made at; deeper trees roll up
+const MAX_DIRECTORY_DEPTH: usize = 2;
+const MIN_GENERATED_RATIO: f64 = 0.5;
+const MIN_BINARY_RATIO: f64 = 0.5;
+const MIN_BINARY_FILES: u64 = 5;
+// share of the remaining index under a few top-level directories that
+// makes an include list worth proposing
+const INCLUDE_COVERAGE: f64 = 0.9;
+const MAX_INCLUDE_DIRECTORIES: usize = 3;
+const GENERATED_SCAN_CHARS: usize = 600;
+
+const BUILD_DIRECTORY_NAMES: &[&str] = &[
+    "dist",
+    "build",
+    "out",
+    "target",
+    "vendor",
+    "third_party",
+    "node_modules",
+    "coverage",
+    "generated",
+    "__generated__",
+    "gen",
+    "__snapshots__",
+];
+#[derive(Debug, Deserialize, JsonSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct SuggestExcludesParams {
+    #[serde(default)]
+    pub root: Option<String>,
+    #[serde(default)]
+    pub database_name: Option<String>,
+    /// Maximum suggestions (default 15, max 100).
+    #[serde(default)]
+    pub limit: Option<u32>,
+}
+
+#[derive(Debug, Clone, Serialize, JsonSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct ExcludeSuggestion {
+    pub glob: String,
+    /// `build_output`, `generated_code`, `generated_files`, or `binary_files`.
+    pub reason: String,
+    pub matched_files: u64,
+    /// Bytes of chunks, embeddings, and stored content the glob would drop.
+    pub estimated_savings_bytes: u64,
+}
+
+#[derive(Debug, Clone, Serialize, JsonSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct DirectoryFootprint {
+    pub directory: String,
+    pub files: u64,
+    pub source_bytes: u64,
+    pub index_bytes: u64,
+    pub binary_files: u64,
+    pub generated_files: u64,
+}
+
+#[derive(Debug, Clone, Serialize, JsonSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct SuggestExcludesResponse {
+    pub database_path: String,
+    pub indexed_files: u64,
+    pub index_bytes: u64,
+    /// Directories holding the most index bytes.
+    pub largest_directories: Vec<DirectoryFootprint>,
+    pub suggestions: Vec<ExcludeSuggestion>,
+    /// Ready to pass as `exclude` to ingest_codebase (added to your own).
+    pub suggested_exclude: Vec<String>,
+    /// Set when nearly everything left after the excludes lives under a few
+    /// top-level directories.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub suggested_include: Option<Vec<String>>,
+    pub estimated_savings_bytes: u64,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub recommendation: Option<String>,
+}
+
+#[derive(Debug, Error)]
+pub enum SuggestExcludesError {
+    #[error("failed to resolve workspace root '{path}': {source}")]
+    InvalidRoot {
+        path: String,
+        #[source]
+        source: std::io::Error,
+    },
+    #[error("SQLite error: {0}")]
+    Sqlite(#[from] rusqlite::Error),
+    #[error(transparent)]
+    Sandbox(#[from] SandboxError),
+    #[error("blocking task panicked: {0}")]
+    Join(#[from] tokio::task::JoinError),
+}
+
+struct IndexedFile {
+    path: String,
+    size: u64,
+    index_bytes: u64,
+    binary: bool,
+    generated: bool,
+}
+
+pub async fn suggest_excludes(
+    params: SuggestExcludesParams,
+) -> Result<SuggestExcludesResponse, SuggestExcludesError> {
+    tokio::task::spawn_blocking(move || analyze(params)).await?
+}
+
+fn analyze(params: SuggestExcludesParams) -> Result<SuggestExcludesResponse, SuggestExcludesError> {
+    let root = params.root.unwrap_or_else(|| "./".to_string());
+    let root_path = resolve_root(&root)?;
+    ensure_root_allowed(&root_path)?;
+    let database_path = database_path(
+        &root_path,
+        &params
+            .database_name
+            .unwrap_or_else(|| DEFAULT_DB_FILENAME.to_string()),
+    );
+    let limit = params
+        .limit
+        .map_or(DEFAULT_SUGGESTION_LIMIT, |value| value as usize)
+        .clamp(1, MAX_SUGGESTION_LIMIT);
+
+    let mut response = SuggestExcludesResponse {
+        database_path: database_path.to_string_lossy().to_string(),
+        indexed_files: 0,
+        index_bytes: 0,
+        largest_directories: Vec::new(),
+        suggestions: Vec::new(),
+        suggested_exclude: Vec::new(),
+        suggested_include: None,
+        estimated_savings_bytes: 0,
+        recommendation: None,
+    };
+    if !database_path.is_file() {
+        response.recommendation = Some(
+            "No index yet; run ingest_codebase first so there is a scan to analyze.".to_string(),
+        );
+        return Ok(response);
+    }
+
+    let conn = open_read_only(&database_path)?;
+    let files = load_files(&conn)?;
+    response.indexed_files = files.len() as u64;
+    response.index_bytes = files.iter().map(|file| file.index_bytes).sum();
+
+    let directories = directory_footprints(&files);
+    let mut largest: Vec<&DirectoryFootprint> = directories
+        .values()
+        .filter(|directory| !directory.directory.contains('/'))
+        .collect();
+    largest.sort_by(|left, right| right.index_bytes.cmp(&left.index_bytes));
+    response.largest_directories = largest
+        .into_iter()
+        .take(MAX_DIRECTORY_ROWS)
+        .cloned()
+        .collect();
+
+    let mut suggestions = directory_suggestions(&directories);
+    suggestions.extend(suffix_suggestions(&files, &suggestions));
+    suggestions.sort_by(|left, right| {
+        right
+            .estimated_savings_bytes
+            .cmp(&left.estimated_savings_bytes)
+            .then_with(|| right.matched_files.cmp(&left.matched_files))
+    });
+    suggestions.truncate(limit);
+
+    let excluded = |path: &str| {
+        suggestions
+            .iter()
+            .any(|suggestion| glob_covers(&suggestion.glob, path))
+    };
+    response.estimated_savings_bytes = files
+        .iter()
+        .filter(|file| excluded(&file.path))
+        .map(|file| file.index_bytes)
+        .sum();
+    response.suggested_include = include_suggestion(&files, &excluded);
+    response.suggested_exclude = suggestions
+        .iter()
+        .map(|suggestion| suggestion.glob.clone())
+        .collect();
+    response.suggestions = suggestions;
+    if response.suggestions.is_empty() {
+        response.recommendation =
+            Some("Nothing in the last scan looks like build output or generated code.".to_string());
+    }
+    Ok(response)
+}
+
+fn load_files(conn: &Connection) -> Result<Vec<IndexedFile>, SuggestExcludesError> {
+    let mut chunk_bytes: HashMap<String, (u64, u64)> = HashMap::new();
+    {
+        let mut stmt = conn.prepare(
+            "SELECT path, SUM(length(content) + length(embedding)), COUNT(*) FROM file_chunks GROUP BY path",
+        )?;
+        let rows = stmt.query_map([], |row| {
+            Ok((
+                row.get::<_, String>(0)?,
+                row.get::<_, i64>(1)?,
+                row.get::<_, i64>(2)?,
+            ))
+        })?;
+        for row in rows {
+            let (path, bytes, count) = row?;
+            chunk_bytes.insert(path, (bytes.max(0) as u64, count.max(0) as u64));
+        }
+    }
+
+    // compressed content has to be decoded in full; plain text is cut to the
+    // scanned prefix in SQL
+    let encoding = content_encoding_column(conn, "files");
+    let mut stmt = conn.prepare(&format!(
+        "SELECT f.path, f.size, length(f.content),
+                CASE WHEN {encoding} IS NULL THEN substr(f.content, 1, ?1) ELSE f.content END,
+                {encoding}, substr(c.content, 1, ?1)
+         FROM files f
+         LEFT JOIN file_chunks c ON c.path = f.path AND c.chunk_index = 0"
+    ))?;
+    let rows = stmt.query_map([GENERATED_SCAN_CHARS as i64], |row| {
+        Ok((
+            row.get::<_, String>(0)?,
+            row.get::<_, i64>(1)?,
+            row.get::<_, Option<i64>>(2)?,
+            row.get::<_, SqlValue>(3)?,
+            row.get::<_, Option<String>>(4)?,
+            row.get::<_, Option<String>>(5)?,
+        ))
+    })?;
+    let mut files = Vec::new();
+    for row in rows {
+        let (path, size, stored_bytes, content, encoding, chunk_head) = row?;
+        let head: String = decode_file_content(Some(content), encoding.as_deref())
+            .or(chunk_head)
+            .unwrap_or_default()
+            .chars()
+            .take(GENERATED_SCAN_CHARS)
+            .collect();
+        let (chunks_bytes, chunk_count) = chunk_bytes.get(&path).copied().unwrap_or((0, 0));
+        let size = size.max(0) as u64;
+        files.push(IndexedFile {
+            binary: chunk_count == 0 && size > 0 && stored_bytes.is_none(),
+            generated: is_generated_file(&path, &head),
+            index_bytes: chunks_bytes + stored_bytes.unwrap_or(0).max(0) as u64,
+            path,
+            size,
+        });
+    }
+    Ok(files)
+}
+
+/// Footprints for every directory prefix up to `MAX_DIRECTORY_DEPTH`.
+fn directory_footprints(files: &[IndexedFile]) -> BTreeMap<String, DirectoryFootprint> {
+    let mut directories: BTreeMap<String, DirectoryFootprint> = BTreeMap::new();
+    for file in files {
+        let segments: Vec<&str> = file.path.split('/').collect();
+        for depth in 1..segments.len().min(MAX_DIRECTORY_DEPTH + 1) {
+            let directory = segments[..depth].join("/");
+            let entry =
+                directories
+                    .entry(directory.clone())
+                    .or_insert_with(|| DirectoryFootprint {
+                        directory,
+                        files: 0,
+                        source_bytes: 0,
+                        index_bytes: 0,
+                        binary_files: 0,
+                        generated_files: 0,
+                    });
+            entry.files += 1;
+            entry.source_bytes += file.size;
+            entry.index_bytes += file.index_bytes;
+            entry.binary_files += u64::from(file.binary);
+            entry.generated_files += u64::from(file.generated);
+        }
+    }
+    directories
+}
+
+fn directory_suggestions(
+    directories: &BTreeMap<String, DirectoryFootprint>,
+) -> Vec<ExcludeSuggestion> {
+    let mut suggestions: Vec<ExcludeSuggestion> = Vec::new();
+    for directory in directories.values() {
+        // a parent already suggested covers this directory
+        if suggestions
+            .iter()
+            .any(|suggestion| glob_covers(&suggestion.glob, &directory.directory))
+        {
+            continue;
+        }
+        let name = directory
+            .directory
+            .rsplit('/')
+            .next()
+            .unwrap_or(&directory.directory);
+        let files = directory.files as f64;
+        let reason = if BUILD_DIRECTORY_NAMES.contains(&name) {
+            "build_output"
+        } else if directory.generated_files as f64 / files >= MIN_GENERATED_RATIO {
+            "generated_code"
+        } else if directory.binary_files >= MIN_BINARY_FILES
+            && directory.binary_files as f64 / files >= MIN_BINARY_RATIO
+        {
+            "binary_files"
+        } else {
+            continue;
+        };
+        suggestions.push(ExcludeSuggestion {
+            glob: format!("{}/**", directory.directory),
+            reason: reason.to_string(),
+            matched_files: directory.files,
+            estimated_savings_bytes: directory.index_bytes,
+        });
+    }
+    suggestions
+}
+
+/// `**/*<suffix>` globs for generated file types that are not already
+/// covered by a directory suggestion.
+fn suffix_suggestions(
+    files: &[IndexedFile],
+    directory_suggestions: &[ExcludeSuggestion],
+) -> Vec<ExcludeSuggestion> {
+    let mut by_suffix: BTreeMap<&str, (u64, u64)> = BTreeMap::new();
+    for file in files {
+        if directory_suggestions
+            .iter()
+            .any(|suggestion| glob_covers(&suggestion.glob, &file.path))
+        {
+            continue;
+        }
+        if let Some(suffix) = GENERATED_FILE_SUFFIXES
+            .iter()
+            .find(|suffix| file.path.ends_with(*suffix))
+        {
+            let entry = by_suffix.entry(*suffix).or_default();
+            entry.0 += 1;
+            entry.1 += file.index_bytes;
+        }
+    }
+    by_suffix
+        .into_iter()
+        .map(|(suffix, (matched_files, bytes))| ExcludeSuggestion {
+            glob: format!("**/*{suffix}"),
+            reason: "generated_files".to_string(),
+            matched_files,
+            estimated_savings_bytes: bytes,
+        })
+        .collect()
+}
+
+fn include_suggestion(
+    files: &[IndexedFile],
+    excluded: &dyn Fn(&str) -> bool,
+) -> Option<Vec<String>> {
+    let mut by_top: BTreeMap<&str, u64> = BTreeMap::new();
+    let mut total = 0u64;
+    for file in files.iter().filter(|file| !excluded(&file.path)) {
+        // root-level files keep the whole tree in scope
+        let (top, _) = file.path.split_once('/')?;
+        *by_top.entry(top).or_default() += file.index_bytes;
+        total += file.index_bytes;
+    }
+    if total == 0 || by_top.len() <= 1 {
+        return None;
+    }
+    let mut ranked: Vec<(&str, u64)> = by_top.into_iter().collect();
+    ranked.sort_by(|left, right| right.1.cmp(&left.1));
+    let mut covered = 0u64;
+    let mut include = Vec::new();
+    for (top, bytes) in ranked.into_iter().take(MAX_INCLUDE_DIRECTORIES) {
+        covered += bytes;
+        include.push(format!("{top}/**"));
+        if covered as f64 / total as f64 >= INCLUDE_COVERAGE {
+            return Some(include);
+        }
+    }
+    None
+}
+
+/// Whether a suggested glob (`dir/**` or `**/*suffix`) matches `path`.
+fn glob_covers(glob: &str, path: &str) -> bool {
+    if let Some(directory) = glob.strip_suffix("/**") {
+        path == directory || path.starts_with(&format!("{directory}/"))
+    } else if let Some(suffix) = glob.strip_prefix("**/*") {
+        path.ends_with(suffix)
+    } else {
+        false
+    }
+}
+
+fn resolve_root(root: &str) -> Result<PathBuf, SuggestExcludesError> {
+    resolve_workspace_root(root).map_err(|source| SuggestExcludesError::InvalidRoot {
+        path: root.to_string(),
+        source,
+    })
+}
@@ -0,0 +1,172 @@
+use tree_sitter::{Language, Node, Parser};
+
+// containers are descended into this far, so members of impls, classes, and
+// modules get boundaries too but nested closures and blocks do not
+const MAX_DEPTH: usize = 4;
+
+/// Node kinds for one grammar: `definitions` start a chunk boundary,
+/// `opaque` definitions are not searched for nested ones, and `preludes`
+/// directly above a definition (attributes, decorators, doc comments) move
+/// the boundary up with them.
+struct Grammar {
+    definitions: &'static [&'static str],
+    opaque: &'static [&'static str],
+    preludes: &'static [&'static str],
+}
+
+const RUST: Grammar = Grammar {
+    definitions: &[
+        "function_item",
+        "impl_item",
+        "struct_item",
+        "enum_item",
+        "union_item",
+        "trait_item",
+        "mod_item",
+        "macro_definition",
+        "type_item",
+    ],
+    opaque: &["function_item", "macro_definition"],
+    preludes: &["attribute_item", "line_comment", "block_comment"],
+};
+
+const TYPESCRIPT: Grammar = Grammar {
+    definitions: &[
+        "function_declaration",
+        "generator_function_declaration",
+        "class_declaration",
+        "abstract_class_declaration",
+        "interface_declaration",
+        "type_alias_declaration",
+        "enum_declaration",
+        "method_definition",
+        "export_statement",
+    ],
+    opaque: &[
+        "function_declaration",
+        "generator_function_declaration",
+        "method_definition",
+        "interface_declaration",
+    ],
+    preludes: &["comment", "decorator"],
+};
+
+const PYTHON: Grammar = Grammar {
+    definitions: &[
+        "function_definition",
+        "class_definition",
+        "decorated_definition",
+    ],
+    opaque: &["function_definition"],
+    preludes: &["comment"],
+};
+
+const GO: Grammar = Grammar {
+    definitions: &[
+        "function_declaration",
+        "method_declaration",
+        "type_declaration",
+    ],
+    opaque: &["function_declaration", "method_declaration"],
+    preludes: &["comment"],
+};
+
+/// Zero-based lines where a Rust definition starts, leading attributes and
+/// doc comments included.
+pub(crate) fn rust_definition_lines(_path: &str, source: &str) -> Option<Vec<usize>> {
+    definition_lines(tree_sitter_rust::LANGUAGE.into(), &RUST, source)
+}
+
+/// `.ts` files use the TypeScript grammar, which allows `<T>value`
+/// assertions; everything else the script pack claims parses as TSX, which
+/// also covers plain JavaScript and JSX.
+pub(crate) fn script_definition_lines(path: &str, source: &str) -> Option<Vec<usize>> {
+    let language = if path.to_ascii_lowercase().ends_with(".ts") {
+        tree_sitter_typescript::LANGUAGE_TYPESCRIPT
+    } else {
+        tree_sitter_typescript::LANGUAGE_TSX
+    };
+    definition_lines(language.into(), &TYPESCRIPT, source)
+}
+
+pub(crate) fn python_definition_lines(_path: &str, source: &str) -> Option<Vec<usize>> {
+    definition_lines(tree_sitter_python::LANGUAGE.into(), &PYTHON, source)
+}
+
+pub(crate) fn go_definition_lines(_path: &str, source: &str) -> Option<Vec<usize>> {
+    definition_lines(tree_sitter_go::LANGUAGE.into(), &GO, source)
+}
+
+/// `None` when the source does not parse into any definition, so the caller
+/// can fall back to line patterns.
+fn definition_lines(language: Language, grammar: &Grammar, source: &str) -> Option<Vec<usize>> {
+    let mut parser = Parser::new();
+    parser.set_language(&language).ok()?;
+    let tree = parser.parse(source, None)?;
+    let mut lines = Vec::new();
+    collect(tree.root_node(), grammar, 0, false, &mut lines);
+    if lines.is_empty() {
+        return None;
+    }
+    lines.sort_unstable();
+    lines.dedup();
+    Some(lines)
+}
+
+/// A definition directly inside another (`export class`, a decorated
+/// `def`) shares its wrapper's boundary rather than splitting from it.
+fn collect(
+    node: Node,
+    grammar: &Grammar,
+    depth: usize,
+    inside_definition: bool,
+    lines: &mut Vec<usize>,
+) {
+    if depth > MAX_DEPTH {
+        return;
+    }
+    let mut cursor = node.walk();
+    for child in node.named_children(&mut cursor) {
+        let kind = child.kind();
+        let is_definition = grammar.definitions.contains(&kind);
+        if is_definition && !inside_definition {
+            lines.push(prelude_start(child, grammar));
+        }
+        if !grammar.opaque.contains(&kind) {
+            collect(child, grammar, depth + 1, is_definition, lines);
+        }
+    }
+}
+
+/// The first line of the definition's unbroken run of preludes.
+fn prelude_start(node: Node, grammar: &Grammar) -> usize {
+    let mut start = node.start_position().row;
+    let mut current = node;
+    while let Some(previous) = current.prev_named_sibling() {
+        if !grammar.preludes.contains(&previous.kind()) || last_row(previous) + 1 < start {
+            break;
+        }
+        // a comment trailing the previous item's last line belongs to it
+        if shares_line_with_previous(previous) {
+            break;
+        }
+        start = previous.start_position().row;
+        current = previous;
+    }
+    start
+}
+
+fn shares_line_with_previous(node: Node) -> bool {
+    node.prev_sibling()
+        .is_some_and(|before| last_row(before) == node.start_position().row)
+}
+
+// line comments end at column 0 of the next row, after their newline
+fn last_row(node: Node) -> usize {
+    let end = node.end_position();
+    if end.column == 0 && end.row > node.start_position().row {
+        end.row - 1
+    } else {
+        end.row
+    }
+}
@@ -0,0 +1,191 @@
+use rmcp::schemars::{self, JsonSchema};
+use rusqlite::Connection;
+use serde::{Deserialize, Serialize};
+
+/// How a file's bytes were turned into the UTF-8 text that ingest stores,
+/// chunks, and measures byte ranges against.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, JsonSchema)]
+pub enum TextEncoding {
+    #[serde(rename = "utf-8")]
+    Utf8,
+    /// UTF-8 with a byte order mark, which is dropped from the stored text.
+    #[serde(rename = "utf-8-bom")]
+    Utf8Bom,
+    #[serde(rename = "utf-16le")]
+    Utf16Le,
+    #[serde(rename = "utf-16be")]
+    Utf16Be,
+    /// Not valid UTF-8; each byte was read as its Latin-1 character.
+    #[serde(rename = "latin-1")]
+    Latin1,
+}
+
+impl TextEncoding {
+    pub(crate) fn as_str(self) -> &'static str {
+        match self {
+            Self::Utf8 => "utf-8",
+            Self::Utf8Bom => "utf-8-bom",
+            Self::Utf16Le => "utf-16le",
+            Self::Utf16Be => "utf-16be",
+            Self::Latin1 => "latin-1",
+        }
+    }
+
+    pub(crate) fn parse(value: &str) -> Option<Self> {
+        [
+            Self::Utf8,
+            Self::Utf8Bom,
+            Self::Utf16Le,
+            Self::Utf16Be,
+            Self::Latin1,
+        ]
+        .into_iter()
+        .find(|encoding| encoding.as_str() == value)
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, JsonSchema)]
+#[serde(rename_all = "camelCase")]
+pub enum LineEnding {
+    Lf,
+    Crlf,
+    Cr,
+    Mixed,
+}
+
+impl LineEnding {
+    pub(crate) fn as_str(self) -> &'static str {
+        match self {
+            Self::Lf => "lf",
+            Self::Crlf => "crlf",
+            Self::Cr => "cr",
+            Self::Mixed => "mixed",
+        }
+    }
+
+    pub(crate) fn parse(value: &str) -> Option<Self> {
+        [Self::Lf, Self::Crlf, Self::Cr, Self::Mixed]
+            .into_iter()
+            .find(|ending| ending.as_str() == value)
+    }
+}
+
+#[derive(Debug)]
+pub(crate) struct DecodedText {
+    pub text: String,
+    pub encoding: TextEncoding,
+}
+
+/// Files without a UTF-16 byte order mark that contain NUL bytes.
+pub(crate) fn is_binary(bytes: &[u8]) -> bool {
+    utf16_order(bytes).is_none() && bytes.contains(&0)
+}
+
+/// The file's text, or none for binary content.
+pub(crate) fn decode_text(bytes: &[u8]) -> Option<DecodedText> {
+    if let Some(little_endian) = utf16_order(bytes) {
+        let units: Vec<u16> = bytes[2..]
+            .chunks_exact(2)
+            .map(|pair| {
+                if little_endian {
+                    u16::from_le_bytes([pair[0], pair[1]])
+                } else {
+                    u16::from_be_bytes([pair[0], pair[1]])
+                }
+            })
+            .collect();
+        let text = String::from_utf16(&units).ok()?;
+        let encoding = if little_endian {
+            TextEncoding::Utf16Le
+        } else {
+            TextEncoding::Utf16Be
+        };
+        return Some(DecodedText { text, encoding });
+    }
+    if is_binary(bytes) {
+        return None;
+    }
+    if let Some(rest) = bytes.strip_prefix(b"\xEF\xBB\xBF") {
+        if let Ok(text) = std::str::from_utf8(rest) {
+            return Some(DecodedText {
+                text: text.to_string(),
+                encoding: TextEncoding::Utf8Bom,
+            });
+        }
+    }
+    match std::str::from_utf8(bytes) {
+        Ok(text) => Some(DecodedText {
+            text: text.to_string(),
+            encoding: TextEncoding::Utf8,
+        }),
+        Err(_) => Some(DecodedText {
+            text: bytes.iter().map(|byte| char::from(*byte)).collect(),
+            encoding: TextEncoding::Latin1,
+        }),
+    }
+}
+
+/// Line break style of `text`; none for text without line breaks.
+pub(crate) fn detect_line_ending(text: &str) -> Option<LineEnding> {
+    let bytes = text.as_bytes();
+    let mut found = None;
+    let mut index = 0;
+    while index < bytes.len() {
+        let ending = match bytes[index] {
+            b'\r' if bytes.get(index + 1) == Some(&b'\n') => {
+                index += 1;
+                LineEnding::Crlf
+            }
+            b'\r' => LineEnding::Cr,
+            b'\n' => LineEnding::Lf,
+            _ => {
+                index += 1;
+                continue;
+            }
+        };
+        match found {
+            Some(previous) if previous != ending => return Some(LineEnding::Mixed),
+            _ => found = Some(ending),
+        }
+        index += 1;
+    }
+    found
+}
+
+fn utf16_order(bytes: &[u8]) -> Option<bool> {
+    match bytes {
+        [0xFF, 0xFE, ..] => Some(true),
+        [0xFE, 0xFF, ..] => Some(false),
+        _ => None,
+    }
+}
+
+pub(crate) fn ensure_text_format_schema(conn: &Connection) -> Result<(), rusqlite::Error> {
+    if !has_text_format(conn)? {
+        conn.execute_batch(
+            "ALTER TABLE files ADD COLUMN text_encoding TEXT;
+             ALTER TABLE files ADD COLUMN line_ending TEXT;",
+        )?;
+    }
+    Ok(())
+}
+
+/// Column expressions for `text_encoding, line_ending`, for readers that may
+/// open databases written before they were recorded.
+pub(crate) fn text_format_columns(conn: &Connection) -> &'static str {
+    match has_text_format(conn) {
+        Ok(true) => "text_encoding, line_ending",
+        _ => "NULL, NULL",
+    }
+}
+
+fn has_text_format(conn: &Connection) -> Result<bool, rusqlite::Error> {
+    let mut stmt = conn.prepare("PRAGMA table_info(files)")?;
+    let names = stmt.query_map([], |row| row.get::<_, String>(1))?;
+    for name in names {
+        if name? == "text_encoding" {
+            return Ok(true);
+        }
+    }
+    Ok(false)
+}
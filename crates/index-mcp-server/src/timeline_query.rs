@@ -0,0 +1,337 @@
+use std::collections::HashSet;
+use std::path::{Path, PathBuf};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use globset::{Glob, GlobMatcher};
+use once_cell::sync::Lazy;
+use regex::Regex;
+use rmcp::schemars::{self, JsonSchema};
+use serde::{Deserialize, Serialize};
+use thiserror::Error;
+
+use crate::data_dir::{database_path, open_read_only};
+use crate::git_timeline::{has_timeline_filter_columns, RepositoryTimelineEntry, TimelineIdentity};
+use crate::index_status::DEFAULT_DB_FILENAME;
+use crate::limits::{clamp_parameter, ParameterAdjustment};
+use crate::roots::resolve_workspace_root;
+use crate::sandbox::{ensure_root_allowed, SandboxError};
+
+const DEFAULT_QUERY_LIMIT: u32 = 20;
+const MAX_QUERY_LIMIT: u32 = 200;
+
+static ABSOLUTE_DATE_PATTERN: Lazy<Regex> =
+    Lazy::new(|| Regex::new(r"^\d{4}-\d{2}-\d{2}").expect("valid regex"));
+static RELATIVE_DATE_PATTERN: Lazy<Regex> =
+    Lazy::new(|| Regex::new(r"^(\d+)\s*(d|w|m|y)$").expect("valid regex"));
+
+#[derive(Debug, Deserialize, JsonSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct TimelineQueryParams {
+    #[serde(default)]
+    pub root: Option<String>,
+    #[serde(default)]
+    pub database_name: Option<String>,
+    /// Glob or directory prefix matched against each commit's changed files.
+    /// Commits cached without file stats never match.
+    #[serde(default)]
+    pub path: Option<String>,
+    /// Case-insensitive substring of the author name or email.
+    #[serde(default)]
+    pub author: Option<String>,
+    /// Inclusive lower bound on the author date: `YYYY-MM-DD` or a relative
+    /// span such as `30d`, `6w`, `3m`, `1y`.
+    #[serde(default)]
+    pub since: Option<String>,
+    /// Inclusive upper bound on the author date, same formats as `since`.
+    #[serde(default)]
+    pub until: Option<String>,
+    #[serde(default)]
+    pub limit: Option<u32>,
+}
+
+#[derive(Debug, Clone, Serialize, JsonSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct TimelineQueryMatch {
+    pub sha: String,
+    pub subject: String,
+    pub author: TimelineIdentity,
+    pub author_date: String,
+    pub insertions: i64,
+    pub deletions: i64,
+    /// Changed files that matched `path`; empty when no path was given.
+    pub matched_paths: Vec<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub pull_request_url: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize, JsonSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct TimelineQueryResponse {
+    pub database_path: String,
+    /// Distinct commits in the timeline cache.
+    pub cached_commits: usize,
+    pub total_matches: usize,
+    pub entries: Vec<TimelineQueryMatch>,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub adjustments: Vec<ParameterAdjustment>,
+}
+
+#[derive(Debug, Error)]
+pub enum TimelineQueryError {
+    #[error("failed to resolve workspace root '{path}': {source}")]
+    InvalidRoot {
+        path: String,
+        #[source]
+        source: std::io::Error,
+    },
+    #[error("invalid path pattern '{pattern}': {source}")]
+    InvalidPattern {
+        pattern: String,
+        #[source]
+        source: globset::Error,
+    },
+    #[error("invalid date '{value}'; expected YYYY-MM-DD or a span such as 30d")]
+    InvalidDate { value: String },
+    #[error("SQLite error: {0}")]
+    Sqlite(#[from] rusqlite::Error),
+    #[error(transparent)]
+    Sandbox(#[from] SandboxError),
+    #[error("blocking task panicked: {0}")]
+    Join(#[from] tokio::task::JoinError),
+}
+
+pub async fn timeline_query(
+    params: TimelineQueryParams,
+) -> Result<TimelineQueryResponse, TimelineQueryError> {
+    tokio::task::spawn_blocking(move || run_timeline_query(params)).await?
+}
+
+fn run_timeline_query(
+    params: TimelineQueryParams,
+) -> Result<TimelineQueryResponse, TimelineQueryError> {
+    let root = params.root.unwrap_or_else(|| "./".to_string());
+    let root_path = resolve_root(&root)?;
+    ensure_root_allowed(&root_path)?;
+    let database_path = database_path(
+        &root_path,
+        &params
+            .database_name
+            .unwrap_or_else(|| DEFAULT_DB_FILENAME.to_string()),
+    );
+    let mut adjustments = Vec::new();
+    let limit = clamp_parameter(
+        "limit",
+        params.limit,
+        DEFAULT_QUERY_LIMIT,
+        1,
+        MAX_QUERY_LIMIT,
+        &mut adjustments,
+    ) as usize;
+
+    let path_filter = params
+        .path
+        .as_deref()
+        .map(str::trim)
+        .filter(|value| !value.is_empty())
+        .map(PathFilter::new)
+        .transpose()?;
+    let author = params
+        .author
+        .as_deref()
+        .map(str::trim)
+        .filter(|value| !value.is_empty())
+        .map(str::to_lowercase);
+    let since = params
+        .since
+        .as_deref()
+        .map(resolve_date_bound)
+        .transpose()?;
+    let until = params
+        .until
+        .as_deref()
+        .map(resolve_date_bound)
+        .transpose()?;
+
+    let entries = load_cached_entries(&database_path)?;
+    let cached_commits = entries.len();
+
+    let mut matches: Vec<TimelineQueryMatch> = entries
+        .into_iter()
+        .filter(|entry| {
+            author.as_deref().is_none_or(|needle| {
+                entry.author.name.to_lowercase().contains(needle)
+                    || entry.author.email.to_lowercase().contains(needle)
+            })
+        })
+        .filter(|entry| {
+            let date = entry.author_date.get(..10).unwrap_or(&entry.author_date);
+            since.as_deref().is_none_or(|bound| date >= bound)
+                && until.as_deref().is_none_or(|bound| date <= bound)
+        })
+        .filter_map(|entry| {
+            let matched_paths = match &path_filter {
+                Some(filter) => {
+                    let matched: Vec<String> = entry
+                        .file_changes
+                        .iter()
+                        .filter(|change| filter.matches(&change.path))
+                        .map(|change| change.path.clone())
+                        .collect();
+                    if matched.is_empty() {
+                        return None;
+                    }
+                    matched
+                }
+                None => Vec::new(),
+            };
+            Some(TimelineQueryMatch {
+                sha: entry.sha,
+                subject: entry.subject,
+                author: entry.author,
+                author_date: entry.author_date,
+                insertions: entry.insertions,
+                deletions: entry.deletions,
+                matched_paths,
+                pull_request_url: entry.pull_request_url,
+            })
+        })
+        .collect();
+
+    matches.sort_by(|left, right| right.author_date.cmp(&left.author_date));
+    let total_matches = matches.len();
+    matches.truncate(limit);
+
+    Ok(TimelineQueryResponse {
+        database_path: database_path.to_string_lossy().to_string(),
+        cached_commits,
+        total_matches,
+        entries: matches,
+        adjustments,
+    })
+}
+
+/// One entry per commit, preferring the unfiltered capture when a commit was
+/// cached under several path filters.
+fn load_cached_entries(
+    database_path: &Path,
+) -> Result<Vec<RepositoryTimelineEntry>, TimelineQueryError> {
+    if !database_path.is_file() {
+        return Ok(Vec::new());
+    }
+    let conn = open_read_only(database_path)?;
+    let table_exists: bool = conn.query_row(
+        "SELECT EXISTS(SELECT 1 FROM sqlite_master WHERE type = 'table' AND name = 'repository_timeline_entries')",
+        [],
+        |row| row.get(0),
+    )?;
+    if !table_exists {
+        return Ok(Vec::new());
+    }
+
+    let query = if has_timeline_filter_columns(&conn) {
+        "SELECT commit_sha, payload FROM repository_timeline_entries
+         ORDER BY filter_fingerprint = '' DESC, captured_at DESC"
+    } else {
+        "SELECT commit_sha, payload FROM repository_timeline_entries"
+    };
+    let mut stmt = conn.prepare(query)?;
+    let rows = stmt.query_map([], |row| {
+        Ok((row.get::<_, String>(0)?, row.get::<_, String>(1)?))
+    })?;
+
+    let mut seen = HashSet::new();
+    let mut entries = Vec::new();
+    for row in rows {
+        let (sha, payload) = row?;
+        if !seen.insert(sha) {
+            continue;
+        }
+        match serde_json::from_str::<RepositoryTimelineEntry>(&payload) {
+            Ok(entry) => entries.push(entry),
+            Err(error) => {
+                tracing::debug!(?error, "Skipping unreadable timeline entry");
+            }
+        }
+    }
+    Ok(entries)
+}
+
+struct PathFilter {
+    matcher: GlobMatcher,
+    prefix: String,
+}
+
+impl PathFilter {
+    fn new(pattern: &str) -> Result<Self, TimelineQueryError> {
+        let normalized = pattern.replace('\\', "/");
+        let matcher = Glob::new(&normalized)
+            .map_err(|source| TimelineQueryError::InvalidPattern {
+                pattern: pattern.to_string(),
+                source,
+            })?
+            .compile_matcher();
+        Ok(Self {
+            matcher,
+            prefix: format!("{}/", normalized.trim_end_matches('/')),
+        })
+    }
+
+    fn matches(&self, path: &str) -> bool {
+        self.matcher.is_match(path) || path.starts_with(&self.prefix)
+    }
+}
+
+/// Normalizes a bound to `YYYY-MM-DD` so it compares directly against the
+/// date prefix of ISO-8601 author dates.
+fn resolve_date_bound(value: &str) -> Result<String, TimelineQueryError> {
+    let trimmed = value.trim();
+    if let Some(matched) = ABSOLUTE_DATE_PATTERN.find(trimmed) {
+        return Ok(matched.as_str().to_string());
+    }
+    let invalid = || TimelineQueryError::InvalidDate {
+        value: value.to_string(),
+    };
+    let captures = RELATIVE_DATE_PATTERN
+        .captures(trimmed)
+        .ok_or_else(invalid)?;
+    let amount: i64 = captures[1].parse().map_err(|_| invalid())?;
+    let days_per_unit = match &captures[2] {
+        "d" => 1,
+        "w" => 7,
+        "m" => 30,
+        _ => 365,
+    };
+    let today = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|duration| duration.as_secs() as i64 / 86_400)
+        .unwrap_or(0);
+    Ok(format_civil_date(
+        today - amount.saturating_mul(days_per_unit),
+    ))
+}
+
+// days since 1970-01-01 to a proleptic Gregorian date
+fn format_civil_date(days: i64) -> String {
+    let z = days + 719_468;
+    let era = z.div_euclid(146_097);
+    let day_of_era = z.rem_euclid(146_097);
+    let year_of_era =
+        (day_of_era - day_of_era / 1_460 + day_of_era / 36_524 - day_of_era / 146_096) / 365;
+    let day_of_year = day_of_era - (365 * year_of_era + year_of_era / 4 - year_of_era / 100);
+    let month_index = (5 * day_of_year + 2) / 153;
+    let day = day_of_year - (153 * month_index + 2) / 5 + 1;
+    let month = if month_index < 10 {
+        month_index + 3
+    } else {
+        month_index - 9
+    };
+    let year = year_of_era + era * 400 + i64::from(month <= 2);
+    format!("{year:04}-{month:02}-{day:02}")
+}
+
+fn resolve_root(root: &str) -> Result<PathBuf, TimelineQueryError> {
+    resolve_workspace_root(root).map_err(|source| TimelineQueryError::InvalidRoot {
+        path: root.to_string(),
+        source,
+    })
+}
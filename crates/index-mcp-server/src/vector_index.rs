@@ -0,0 +1,239 @@
+use std::collections::HashMap;
+
+use rusqlite::{params, Connection};
+
+use crate::embedding_store::{decode_embedding, embedding_encoding_column};
+use crate::ingest::embedding_to_bytes;
+use crate::search::{blob_to_vec, dot_product};
+
+/// Models with fewer chunks are scanned exhaustively; the index is dropped.
+pub(crate) const VECTOR_INDEX_MIN_CHUNKS: u64 = 20_000;
+const VECTOR_INDEX_MIN_CHUNKS_ENV: &str = "INDEX_MCP_VECTOR_INDEX_MIN_CHUNKS";
+const MIN_LISTS: usize = 16;
+const MAX_LISTS: usize = 1_024;
+// training vectors per list; k-means over every chunk would rival embedding
+const TRAINING_SAMPLES_PER_LIST: usize = 32;
+const TRAINING_ITERATIONS: usize = 6;
+const MIN_PROBES: usize = 8;
+const MAX_PROBES: usize = 64;
+
+/// The lists a query should scan, nearest centroid first.
+#[derive(Debug, Clone)]
+pub(crate) struct ListProbe {
+    pub lists: Vec<i64>,
+    pub total_lists: usize,
+}
+
+pub(crate) fn ensure_vector_index_schema(conn: &Connection) -> Result<(), rusqlite::Error> {
+    conn.execute_batch(
+        "CREATE TABLE IF NOT EXISTS chunk_centroids (
+            embedding_model TEXT NOT NULL,
+            list INTEGER NOT NULL,
+            vector BLOB NOT NULL,
+            PRIMARY KEY (embedding_model, list)
+        );
+        CREATE TABLE IF NOT EXISTS chunk_lists (
+            chunk_id TEXT PRIMARY KEY,
+            embedding_model TEXT NOT NULL,
+            list INTEGER NOT NULL,
+            FOREIGN KEY (chunk_id) REFERENCES file_chunks(id) ON DELETE CASCADE
+        );
+        CREATE INDEX IF NOT EXISTS chunk_lists_model_list_idx ON chunk_lists(embedding_model, list);",
+    )
+}
+
+/// Chunk count at which a model gets an index, from
+/// `INDEX_MCP_VECTOR_INDEX_MIN_CHUNKS` (default `VECTOR_INDEX_MIN_CHUNKS`).
+pub(crate) fn vector_index_min_chunks() -> u64 {
+    std::env::var(VECTOR_INDEX_MIN_CHUNKS_ENV)
+        .ok()
+        .and_then(|value| value.trim().parse().ok())
+        .unwrap_or(VECTOR_INDEX_MIN_CHUNKS)
+}
+
+/// Keeps the inverted-file index for `model` in step with its chunks:
+/// retrains the centroids when the chunk count has outgrown them (or there
+/// are none) and otherwise only files chunks that have no list yet.
+pub(crate) fn refresh_vector_index(conn: &Connection, model: &str) -> Result<(), rusqlite::Error> {
+    // eviction deletes without foreign keys, leaving assignments behind
+    conn.execute(
+        "DELETE FROM chunk_lists WHERE chunk_id NOT IN (SELECT id FROM file_chunks)",
+        [],
+    )?;
+    let chunk_count: u64 = conn.query_row(
+        "SELECT COUNT(*) FROM file_chunks WHERE embedding_model = ?1",
+        params![model],
+        |row| row.get(0),
+    )?;
+    if chunk_count < vector_index_min_chunks() {
+        drop_vector_index(conn, model)?;
+        return Ok(());
+    }
+
+    let wanted = list_count(chunk_count);
+    let existing = load_centroids(conn, model)?;
+    let centroids =
+        if existing.is_empty() || existing.len() * 2 < wanted || existing.len() > wanted * 2 {
+            let trained = train_centroids(conn, model, wanted)?;
+            if trained.is_empty() {
+                return Ok(());
+            }
+            drop_vector_index(conn, model)?;
+            let mut insert = conn.prepare(
+                "INSERT INTO chunk_centroids (embedding_model, list, vector) VALUES (?1, ?2, ?3)",
+            )?;
+            for (list, centroid) in trained.iter().enumerate() {
+                insert.execute(params![model, list as i64, embedding_to_bytes(centroid)])?;
+            }
+            trained
+        } else {
+            existing
+        };
+
+    // assignments are collected first; the scan reads the table being filled
+    let assignments: Vec<(String, usize)> = {
+        let mut stmt = conn.prepare(&format!(
+            "SELECT c.id, c.embedding, {} FROM file_chunks c
+             LEFT JOIN chunk_lists l ON l.chunk_id = c.id
+             WHERE c.embedding_model = ?1 AND l.chunk_id IS NULL",
+            embedding_encoding_column(conn)
+        ))?;
+        let mut rows = stmt.query(params![model])?;
+        let mut assignments = Vec::new();
+        while let Some(row) = rows.next()? {
+            let encoding: Option<String> = row.get(2)?;
+            let vector = decode_embedding(&row.get::<_, Vec<u8>>(1)?, encoding.as_deref());
+            if let Some(list) = nearest(&centroids, &vector) {
+                assignments.push((row.get(0)?, list));
+            }
+        }
+        assignments
+    };
+    let mut assign = conn.prepare(
+        "INSERT OR REPLACE INTO chunk_lists (chunk_id, embedding_model, list) VALUES (?1, ?2, ?3)",
+    )?;
+    for (id, list) in assignments {
+        assign.execute(params![id, model, list as i64])?;
+    }
+    Ok(())
+}
+
+/// Picks the lists to scan for `query`, or `None` when `model` has no
+/// index or the query's dimensions do not match it.
+pub(crate) fn probe_lists(conn: &Connection, model: &str, query: &[f32]) -> Option<ListProbe> {
+    let centroids = load_centroids(conn, model).ok()?;
+    if centroids.is_empty() || centroids[0].len() != query.len() {
+        return None;
+    }
+    let mut scored: Vec<(usize, f32)> = centroids
+        .iter()
+        .enumerate()
+        .map(|(list, centroid)| (list, dot_product(query, centroid)))
+        .collect();
+    scored.sort_by(|left, right| right.1.total_cmp(&left.1));
+    let probes = (centroids.len() / 10).clamp(MIN_PROBES, MAX_PROBES);
+    Some(ListProbe {
+        lists: scored
+            .into_iter()
+            .take(probes)
+            .map(|(list, _)| list as i64)
+            .collect(),
+        total_lists: centroids.len(),
+    })
+}
+
+fn drop_vector_index(conn: &Connection, model: &str) -> Result<(), rusqlite::Error> {
+    conn.execute(
+        "DELETE FROM chunk_lists WHERE embedding_model = ?1",
+        params![model],
+    )?;
+    conn.execute(
+        "DELETE FROM chunk_centroids WHERE embedding_model = ?1",
+        params![model],
+    )?;
+    Ok(())
+}
+
+fn list_count(chunks: u64) -> usize {
+    ((chunks as f64).sqrt() as usize).clamp(MIN_LISTS, MAX_LISTS)
+}
+
+fn load_centroids(conn: &Connection, model: &str) -> Result<Vec<Vec<f32>>, rusqlite::Error> {
+    let mut stmt = conn
+        .prepare("SELECT vector FROM chunk_centroids WHERE embedding_model = ?1 ORDER BY list")?;
+    let rows = stmt.query_map(params![model], |row| row.get::<_, Vec<u8>>(0))?;
+    let mut centroids = Vec::new();
+    for row in rows {
+        centroids.push(blob_to_vec(&row?));
+    }
+    Ok(centroids)
+}
+
+/// Spherical k-means over a random sample, seeded with the first `lists`
+/// sampled vectors. Embeddings are unit length, so the dot product ranks
+/// like cosine similarity and centroids are renormalized each round.
+fn train_centroids(
+    conn: &Connection,
+    model: &str,
+    lists: usize,
+) -> Result<Vec<Vec<f32>>, rusqlite::Error> {
+    let sample: Vec<Vec<f32>> = {
+        let mut stmt = conn.prepare(&format!(
+            "SELECT embedding, {} FROM file_chunks WHERE embedding_model = ?1
+             ORDER BY RANDOM() LIMIT ?2",
+            embedding_encoding_column(conn)
+        ))?;
+        let rows = stmt.query_map(
+            params![model, (lists * TRAINING_SAMPLES_PER_LIST) as i64],
+            |row| Ok((row.get::<_, Vec<u8>>(0)?, row.get::<_, Option<String>>(1)?)),
+        )?;
+        let mut sample = Vec::new();
+        for row in rows {
+            let (blob, encoding) = row?;
+            let vector = decode_embedding(&blob, encoding.as_deref());
+            if !vector.is_empty() {
+                sample.push(vector);
+            }
+        }
+        sample
+    };
+    let Some(dimensions) = sample.first().map(Vec::len) else {
+        return Ok(Vec::new());
+    };
+    let sample: Vec<Vec<f32>> = sample
+        .into_iter()
+        .filter(|vector| vector.len() == dimensions)
+        .collect();
+
+    let mut centroids: Vec<Vec<f32>> = sample.iter().take(lists).cloned().collect();
+    for _ in 0..TRAINING_ITERATIONS {
+        let mut sums: HashMap<usize, Vec<f32>> = HashMap::new();
+        for vector in &sample {
+            let Some(list) = nearest(&centroids, vector) else {
+                continue;
+            };
+            let sum = sums.entry(list).or_insert_with(|| vec![0.0; dimensions]);
+            for (total, value) in sum.iter_mut().zip(vector) {
+                *total += value;
+            }
+        }
+        // lists that attracted nothing keep their previous centroid
+        for (list, sum) in sums {
+            let norm = sum.iter().map(|value| value * value).sum::<f32>().sqrt();
+            if norm > 0.0 {
+                centroids[list] = sum.into_iter().map(|value| value / norm).collect();
+            }
+        }
+    }
+    Ok(centroids)
+}
+
+fn nearest(centroids: &[Vec<f32>], vector: &[f32]) -> Option<usize> {
+    centroids
+        .iter()
+        .enumerate()
+        .filter(|(_, centroid)| centroid.len() == vector.len())
+        .map(|(list, centroid)| (list, dot_product(vector, centroid)))
+        .max_by(|left, right| left.1.total_cmp(&right.1))
+        .map(|(list, _)| list)
+}
@@ -4,11 +4,22 @@ use std::sync::Arc;
 use std::time::Duration;
 
 use globset::{Glob, GlobSet, GlobSetBuilder};
+use notify::event::{ModifyKind, RenameMode};
 use notify::{Config, Event, EventKind, RecommendedWatcher, RecursiveMode, Watcher};
 use tokio::sync::{mpsc, oneshot, Mutex};
 
 use crate::ingest::IngestError;
 use crate::ingest::{ingest_codebase, IngestParams, DEFAULT_EXCLUDE_GLOBS, DEFAULT_INCLUDE_GLOBS};
+use crate::roots::{relative_to_root, resolve_workspace_root};
+
+// vim swap files and its `4913` write probe, emacs backups and lock files,
+// and the scratch files editors write before renaming over the original
+const EDITOR_TEMP_SUFFIXES: &[&str] = &["~", ".swp", ".swo", ".swx", ".tmp", ".bak"];
+const EDITOR_TEMP_PREFIXES: &[&str] = &[".#", ".~lock."];
+const EDITOR_TEMP_NAMES: &[&str] = &["4913"];
+// past this many paths in one window (a branch switch, a mass rename) one
+// full pass is cheaper than resolving each target
+const MAX_TARGETED_PATHS: usize = 2_000;
 
 #[derive(Debug, thiserror::Error)]
 pub enum WatcherError {
@@ -63,6 +74,10 @@ struct WatchContext {
 struct WatchState {
     changed_paths: HashSet<String>,
     removed_paths: HashSet<String>,
+    /// Paths first seen via a create event in the pending window; removing
+    /// one again before the ingest runs cancels both events.
+    created_paths: HashSet<String>,
+    full_ingest_requested: bool,
     ingest_in_progress: bool,
     rerun_requested: bool,
     timer_handle: Option<tokio::task::JoinHandle<()>>,
@@ -90,6 +105,8 @@ pub async fn start_ingest_watcher(options: WatcherOptions) -> Result<WatcherHand
     exclude_patterns.push(format!("**/{}", database_name));
     exclude_patterns.push(format!("**/{}-wal", database_name));
     exclude_patterns.push(format!("**/{}-shm", database_name));
+    // rewritten by the ingest lock heartbeat
+    exclude_patterns.push(format!("**/{}.lock", database_name));
 
     let include_matcher = compile_globs(&include_patterns)?;
     let exclude_matcher = compile_globs(&exclude_patterns)?;
@@ -122,6 +139,8 @@ pub async fn start_ingest_watcher(options: WatcherOptions) -> Result<WatcherHand
     let state = Arc::new(Mutex::new(WatchState {
         changed_paths: HashSet::new(),
         removed_paths: HashSet::new(),
+        created_paths: HashSet::new(),
+        full_ingest_requested: false,
         ingest_in_progress: false,
         rerun_requested: false,
         timer_handle: None,
@@ -129,6 +148,7 @@ pub async fn start_ingest_watcher(options: WatcherOptions) -> Result<WatcherHand
 
     if run_initial {
         let mut guard = state.lock().await;
+        guard.full_ingest_requested = true;
         schedule_ingest_locked(
             &mut guard,
             state.clone(),
@@ -173,24 +193,42 @@ pub async fn start_ingest_watcher(options: WatcherOptions) -> Result<WatcherHand
 }
 
 async fn process_event(context: &Arc<WatchContext>, state: &Arc<Mutex<WatchState>>, event: Event) {
-    let mut guard = state.lock().await;
+    let tracked: Vec<Option<String>> = event
+        .paths
+        .iter()
+        .map(|path| {
+            normalize_relative_path(&context.absolute_root, path)
+                .filter(|relative| should_track(context, Path::new(relative)))
+        })
+        .collect();
 
-    for path in event.paths {
-        if let Some(relative) = normalize_relative_path(&context.absolute_root, &path) {
-            let relative_path = Path::new(&relative);
-            if !should_track(context, relative_path) {
-                continue;
+    let mut guard = state.lock().await;
+    // the OS dropped events, so the changed paths are unknown
+    if event.need_rescan() {
+        guard.full_ingest_requested = true;
+        schedule_ingest_locked(&mut guard, state.clone(), context.clone(), context.debounce);
+        return;
+    }
+    match event.kind {
+        // atomic saves rename a scratch file over the original: the source
+        // disappears and only the destination needs ingesting
+        EventKind::Modify(ModifyKind::Name(RenameMode::Both)) if tracked.len() == 2 => {
+            if let Some(from) = &tracked[0] {
+                record_removal(&mut guard, from.clone());
             }
-
-            match event.kind {
-                EventKind::Remove(_) => {
-                    guard.changed_paths.remove(&relative);
-                    guard.removed_paths.insert(relative);
-                }
-                _ => {
-                    guard.removed_paths.remove(&relative);
-                    guard.changed_paths.insert(relative);
-                }
+            if let Some(to) = &tracked[1] {
+                record_change(&mut guard, to.clone(), false);
+            }
+        }
+        EventKind::Remove(_) | EventKind::Modify(ModifyKind::Name(RenameMode::From)) => {
+            for relative in tracked.into_iter().flatten() {
+                record_removal(&mut guard, relative);
+            }
+        }
+        kind => {
+            let created = matches!(kind, EventKind::Create(_));
+            for relative in tracked.into_iter().flatten() {
+                record_change(&mut guard, relative, created);
             }
         }
     }
@@ -200,6 +238,25 @@ async fn process_event(context: &Arc<WatchContext>, state: &Arc<Mutex<WatchState
     }
 }
 
+fn record_change(guard: &mut WatchState, relative: String, created: bool) {
+    if created
+        && !guard.changed_paths.contains(&relative)
+        && !guard.removed_paths.contains(&relative)
+    {
+        guard.created_paths.insert(relative.clone());
+    }
+    guard.removed_paths.remove(&relative);
+    guard.changed_paths.insert(relative);
+}
+
+fn record_removal(guard: &mut WatchState, relative: String) {
+    guard.changed_paths.remove(&relative);
+    // created and deleted inside one window: the index never saw it
+    if !guard.created_paths.remove(&relative) {
+        guard.removed_paths.insert(relative);
+    }
+}
+
 fn schedule_ingest_locked(
     guard: &mut WatchState,
     state: Arc<Mutex<WatchState>>,
@@ -219,22 +276,35 @@ fn schedule_ingest_locked(
 }
 
 async fn execute_ingest(state: Arc<Mutex<WatchState>>, context: Arc<WatchContext>) {
-    let (paths, removed) = {
+    let (full, paths, removed) = {
         let mut guard = state.lock().await;
         if guard.ingest_in_progress {
             guard.rerun_requested = true;
             return;
         }
-        guard.ingest_in_progress = true;
-        guard.rerun_requested = false;
+        let full = std::mem::take(&mut guard.full_ingest_requested);
         let paths = guard.changed_paths.drain().collect::<Vec<_>>();
         let removed = guard.removed_paths.drain().collect::<Vec<_>>();
-        (paths, removed)
+        guard.created_paths.clear();
+        // every event in the window cancelled out
+        if !full && paths.is_empty() && removed.is_empty() {
+            return;
+        }
+        guard.ingest_in_progress = true;
+        guard.rerun_requested = false;
+        (full, paths, removed)
     };
 
-    let mut target_paths: HashSet<String> = paths.into_iter().collect();
-    target_paths.extend(removed.into_iter());
-    let target_list: Vec<String> = target_paths.into_iter().collect();
+    // only the paths touched in the window are rescanned; removed paths
+    // (files or whole directories) are dropped from the index. An empty
+    // target list asks ingest for a full pass.
+    let target_list: Vec<String> = if full || paths.len() + removed.len() > MAX_TARGETED_PATHS {
+        Vec::new()
+    } else {
+        let mut target_paths: HashSet<String> = paths.into_iter().collect();
+        target_paths.extend(removed);
+        target_paths.into_iter().collect()
+    };
 
     if let Err(error) = run_ingest(&context, &target_list).await {
         tracing::error!(?error, "Watcher ingest failed");
@@ -256,6 +326,8 @@ async fn run_ingest(context: &WatchContext, paths: &[String]) -> Result<(), Inge
         database_name: Some(context.database_name.clone()),
         max_file_size_bytes: None,
         store_file_content: None,
+        store_content_globs: None,
+        skip_content_globs: None,
         paths: if paths.is_empty() {
             None
         } else {
@@ -263,12 +335,21 @@ async fn run_ingest(context: &WatchContext, paths: &[String]) -> Result<(), Inge
         },
         auto_evict: None,
         max_database_size_bytes: None,
+        protected_classifications: None,
         embedding: None,
+        allow_model_mix: None,
+        compress_content: None,
+        chunk_content_by_reference: None,
+        embedding_encoding: None,
+        include_ignored_paths: None,
+        include_hidden: None,
+        include_skipped: None,
     };
 
     if !context.quiet {
         tracing::info!(
             changed_paths = paths.len(),
+            full = paths.is_empty(),
             database = %context.database_name,
             "Watcher ingest scheduled"
         );
@@ -279,7 +360,7 @@ async fn run_ingest(context: &WatchContext, paths: &[String]) -> Result<(), Inge
             tracing::info!(
                 ingested = result.ingested_file_count,
                 deleted = result.deleted_paths.len(),
-                skipped = result.skipped.len(),
+                skipped = result.skipped_count,
                 duration_ms = result.duration_ms,
                 "Watcher ingest completed"
             );
@@ -288,6 +369,10 @@ async fn run_ingest(context: &WatchContext, paths: &[String]) -> Result<(), Inge
 }
 
 fn should_track(context: &WatchContext, relative: &Path) -> bool {
+    if is_editor_temp_file(relative) {
+        return false;
+    }
+
     if let Some(include) = &context.include_matcher {
         if !include.is_match(relative) {
             return false;
@@ -303,6 +388,19 @@ fn should_track(context: &WatchContext, relative: &Path) -> bool {
     true
 }
 
+fn is_editor_temp_file(relative: &Path) -> bool {
+    let Some(name) = relative.file_name().and_then(|name| name.to_str()) else {
+        return false;
+    };
+    EDITOR_TEMP_NAMES.contains(&name)
+        || EDITOR_TEMP_PREFIXES
+            .iter()
+            .any(|prefix| name.starts_with(prefix))
+        || EDITOR_TEMP_SUFFIXES
+            .iter()
+            .any(|suffix| name.ends_with(suffix))
+}
+
 fn normalize_relative_path(root: &Path, candidate: &Path) -> Option<String> {
     let absolute = if candidate.is_absolute() {
         candidate.to_path_buf()
@@ -310,7 +408,7 @@ fn normalize_relative_path(root: &Path, candidate: &Path) -> Option<String> {
         root.join(candidate)
     };
 
-    let relative = absolute.strip_prefix(root).ok()?;
+    let relative = relative_to_root(root, &absolute)?;
     if relative.as_os_str().is_empty() {
         return None;
     }
@@ -335,28 +433,9 @@ fn compile_globs(patterns: &[String]) -> Result<Option<GlobSet>, WatcherError> {
 }
 
 fn resolve_root(root: &Path) -> Result<PathBuf, WatcherError> {
-    let candidate = if root.is_absolute() {
-        root.to_path_buf()
-    } else {
-        std::env::current_dir()
-            .map_err(|source| WatcherError::InvalidRoot {
-                path: root.to_string_lossy().to_string(),
-                source,
-            })?
-            .join(root)
-    };
-
-    let metadata = std::fs::metadata(&candidate).map_err(|source| WatcherError::InvalidRoot {
-        path: candidate.to_string_lossy().to_string(),
+    let root = root.to_string_lossy();
+    resolve_workspace_root(&root).map_err(|source| WatcherError::InvalidRoot {
+        path: root.to_string(),
         source,
-    })?;
-
-    if !metadata.is_dir() {
-        return Err(WatcherError::InvalidRoot {
-            path: candidate.to_string_lossy().to_string(),
-            source: std::io::Error::other("path is not a directory"),
-        });
-    }
-
-    Ok(candidate)
+    })
 }
@@ -0,0 +1,184 @@
+use std::collections::HashMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use rusqlite::{params, Connection};
+use serde_json::Value;
+
+pub(crate) const CARGO_ECOSYSTEM: &str = "cargo";
+pub(crate) const NPM_ECOSYSTEM: &str = "npm";
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(crate) struct PackageAssignment {
+    pub ecosystem: &'static str,
+    pub name: String,
+    pub root: String,
+}
+
+/// Maps workspace-relative file paths to the package that owns them by walking
+/// up to the nearest manifest. Manifest lookups are cached per directory.
+pub(crate) struct PackageResolver {
+    root: PathBuf,
+    cargo_manifests: HashMap<String, Option<String>>,
+    npm_manifests: HashMap<String, Option<String>>,
+}
+
+impl PackageResolver {
+    pub fn new(root: &Path) -> Self {
+        Self {
+            root: root.to_path_buf(),
+            cargo_manifests: HashMap::new(),
+            npm_manifests: HashMap::new(),
+        }
+    }
+
+    pub fn resolve(&mut self, relative_path: &str) -> Vec<PackageAssignment> {
+        let mut assignments = Vec::new();
+        let directories = ancestor_directories(relative_path);
+        for directory in &directories {
+            if let Some(name) = self.cargo_package_name(directory) {
+                assignments.push(PackageAssignment {
+                    ecosystem: CARGO_ECOSYSTEM,
+                    name,
+                    root: directory.clone(),
+                });
+                break;
+            }
+        }
+        for directory in &directories {
+            if let Some(name) = self.npm_package_name(directory) {
+                assignments.push(PackageAssignment {
+                    ecosystem: NPM_ECOSYSTEM,
+                    name,
+                    root: directory.clone(),
+                });
+                break;
+            }
+        }
+        assignments
+    }
+
+    fn cargo_package_name(&mut self, directory: &str) -> Option<String> {
+        if let Some(cached) = self.cargo_manifests.get(directory) {
+            return cached.clone();
+        }
+
+        let manifest = self.root.join(directory).join("Cargo.toml");
+        let name = fs::read_to_string(&manifest)
+            .ok()
+            .and_then(|content| parse_cargo_package_name(&content));
+        self.cargo_manifests
+            .insert(directory.to_string(), name.clone());
+        name
+    }
+
+    // package.json covers npm, yarn, and pnpm workspaces alike: every workspace
+    // member carries its own manifest with a `name`.
+    fn npm_package_name(&mut self, directory: &str) -> Option<String> {
+        if let Some(cached) = self.npm_manifests.get(directory) {
+            return cached.clone();
+        }
+
+        let manifest = self.root.join(directory).join("package.json");
+        let name = fs::read_to_string(&manifest)
+            .ok()
+            .and_then(|content| serde_json::from_str::<Value>(&content).ok())
+            .and_then(|value| {
+                value
+                    .get("name")
+                    .and_then(|name| name.as_str())
+                    .map(|name| name.trim().to_string())
+            })
+            .filter(|name| !name.is_empty());
+        self.npm_manifests
+            .insert(directory.to_string(), name.clone());
+        name
+    }
+}
+
+fn ancestor_directories(relative_path: &str) -> Vec<String> {
+    let mut directories = Vec::new();
+    let mut current = relative_path;
+    while let Some(index) = current.rfind('/') {
+        current = &current[..index];
+        directories.push(current.to_string());
+    }
+    directories.push(String::new());
+    directories
+}
+
+fn parse_cargo_package_name(manifest: &str) -> Option<String> {
+    let mut in_package = false;
+    for line in manifest.lines() {
+        let trimmed = line.trim();
+        if trimmed.starts_with('[') {
+            in_package = trimmed == "[package]";
+            continue;
+        }
+        if !in_package {
+            continue;
+        }
+
+        let Some((key, value)) = trimmed.split_once('=') else {
+            continue;
+        };
+        if key.trim() != "name" {
+            continue;
+        }
+
+        let value = value.trim().trim_matches('"').trim_matches('\'').trim();
+        if !value.is_empty() {
+            return Some(value.to_string());
+        }
+    }
+    None
+}
+
+pub(crate) fn ensure_package_schema(conn: &Connection) -> Result<(), rusqlite::Error> {
+    conn.execute_batch(
+        r#"
+        CREATE TABLE IF NOT EXISTS file_packages (
+            path TEXT NOT NULL,
+            ecosystem TEXT NOT NULL,
+            name TEXT NOT NULL,
+            root TEXT NOT NULL,
+            PRIMARY KEY (path, ecosystem),
+            FOREIGN KEY (path) REFERENCES files(path) ON DELETE CASCADE
+        );
+        CREATE INDEX IF NOT EXISTS file_packages_name_idx ON file_packages(ecosystem, name);
+        "#,
+    )
+}
+
+pub(crate) fn replace_file_packages(
+    conn: &Connection,
+    path: &str,
+    assignments: &[PackageAssignment],
+) -> Result<(), rusqlite::Error> {
+    conn.execute("DELETE FROM file_packages WHERE path = ?1", params![path])?;
+    for assignment in assignments {
+        conn.execute(
+            "INSERT INTO file_packages (path, ecosystem, name, root) VALUES (?1, ?2, ?3, ?4)",
+            params![path, assignment.ecosystem, assignment.name, assignment.root],
+        )?;
+    }
+    Ok(())
+}
+
+/// Returns the indexed paths owned by the named package, or `None` when the
+/// database predates package tracking.
+pub(crate) fn load_package_paths(
+    conn: &Connection,
+    ecosystem: Option<&str>,
+    name: &str,
+) -> Option<Vec<String>> {
+    let mut stmt = conn
+        .prepare(
+            "SELECT path FROM file_packages WHERE name = ?1 AND (?2 IS NULL OR ecosystem = ?2)",
+        )
+        .ok()?;
+    let rows = stmt
+        .query_map(params![name, ecosystem], |row| row.get::<_, String>(0))
+        .ok()?;
+    Some(rows.flatten().collect())
+}